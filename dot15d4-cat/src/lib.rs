@@ -1,191 +1,529 @@
-use colored::*;
 use dot15d4_frame::*;
 
-struct Writer<'b> {
-    buffer: &'b mut String,
-    indent: usize,
+pub mod output;
+pub use output::{Document, Line, Node, Span, Style};
+
+/// The type of the frame, including whether a beacon or an acknowledgment is
+/// enhanced.
+fn frame_type(fc: &FrameControl<&[u8]>) -> String {
+    let enhanced = fc.frame_version() == FrameVersion::Ieee802154_2020
+        && matches!(fc.frame_type(), FrameType::Beacon | FrameType::Ack);
+    format!(
+        "{}{:?}",
+        if enhanced { "Enhanced " } else { "" },
+        fc.frame_type()
+    )
+}
+
+/// Whether the input ends with a 2-byte Frame Check Sequence (FCS).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FcsMode {
+    /// Use the FCS when it is valid, otherwise parse the frame without FCS.
+    #[default]
+    Auto,
+    /// The input ends with an FCS, which is verified.
+    Present,
+    /// The input has no FCS.
+    Absent,
 }
 
-impl<'b> Writer<'b> {
-    fn new(buffer: &'b mut String) -> Self {
-        Self { buffer, indent: 0 }
+/// A field of a frame: its section, name and value.
+type Field = (&'static str, String, String);
+
+/// The sections of [`Field`]s, in the order of the frame.
+const SECTIONS: [&str; 6] = [
+    "Frame Control",
+    "Sequence Number",
+    "Addressing",
+    "Auxiliary Security Header",
+    "Information Elements",
+    "Payload",
+];
+
+pub struct FrameParser {}
+
+impl FrameParser {
+    pub fn parse_hex(input: &str) -> Result<Document> {
+        let data = hex::decode_to_vec(input)?;
+        Self::parse(&data)
+    }
+
+    pub fn parse_hex_with_fcs(input: &str, fcs: FcsMode) -> Result<Document> {
+        let data = hex::decode_to_vec(input)?;
+        Self::parse_with_fcs(&data, fcs)
     }
 
-    fn increase_indent(&mut self) {
-        self.indent += 2;
+    /// Parse a frame, of which the FCS is handled according to `fcs`. When
+    /// an FCS is present, it is reported as OK or BAD after the frame.
+    pub fn parse_with_fcs(input: &[u8], fcs: FcsMode) -> Result<Document> {
+        let with_fcs = FrameWithFcs::new_unchecked(input);
+        match fcs {
+            FcsMode::Absent => Self::parse(input),
+            FcsMode::Present if !with_fcs.check_len() => Err(Error),
+            FcsMode::Present => Self::parse_fcs(&with_fcs),
+            FcsMode::Auto => {
+                if with_fcs.check_len() && with_fcs.check_fcs() {
+                    if let Ok(parsed) = Self::parse_fcs(&with_fcs) {
+                        return Ok(parsed);
+                    }
+                }
+                Self::parse(input)
+            }
+        }
     }
 
-    fn decrease_indent(&mut self) {
-        self.indent -= 2;
+    fn parse_fcs(frame: &FrameWithFcs<&[u8]>) -> Result<Document> {
+        let mut document = Self::parse(frame.content())?;
+
+        let fcs = frame.fcs();
+        let expected = frame.calculate_fcs();
+        let value = Line::from(format!("{fcs:04x} ("));
+        let value = if fcs == expected {
+            value.with("OK", Style::Good).with(")", Style::Plain)
+        } else {
+            value
+                .with("BAD", Style::Bad)
+                .with(format!(", expected {expected:04x})"), Style::Plain)
+        };
+        document.0.push(Node::section(
+            "Frame Check Sequence",
+            Style::Section,
+            vec![Node::field("fcs", value)],
+        ));
+
+        Ok(document)
     }
 
-    fn write(&mut self, s: String) {
-        self.buffer.push_str(&" ".repeat(self.indent));
-        self.buffer.push_str(&s);
+    /// Write an annotated hexdump of a frame, of which the FCS is handled
+    /// according to `fcs`. The FCS itself is not annotated.
+    pub fn spans_with_fcs(input: &[u8], fcs: FcsMode) -> Document {
+        Self::spans(Self::without_fcs(input, fcs))
     }
 
-    fn writeln(&mut self, s: String) {
-        self.write(s);
-        self.buffer.push('\n');
+    /// Return the frame without the FCS, if it has one according to `fcs`.
+    fn without_fcs(input: &[u8], fcs: FcsMode) -> &[u8] {
+        let with_fcs = FrameWithFcs::new_unchecked(input);
+        let has_fcs = match fcs {
+            FcsMode::Auto => with_fcs.check_len() && with_fcs.check_fcs(),
+            FcsMode::Present => with_fcs.check_len(),
+            FcsMode::Absent => false,
+        };
+        if has_fcs {
+            &input[..input.len() - 2]
+        } else {
+            input
+        }
     }
-}
 
-pub struct FrameParser {}
+    /// Write the raw bytes of a frame, of which the FCS is handled according
+    /// to `fcs`, with every field on its own row. The bytes of a field are
+    /// aligned to their column, 16 bytes per line, followed by the name of the
+    /// field.
+    pub fn hexdump_with_fcs(input: &[u8], fcs: FcsMode) -> Document {
+        let content = Self::without_fcs(input, fcs);
 
-impl FrameParser {
-    pub fn parse_hex(input: &str) -> Result<String> {
-        let data = hex::decode(input).unwrap();
-        Self::parse(&data)
+        let mut rows: Vec<(Span, core::ops::Range<usize>)> = Vec::new();
+        let result = field_spans(content, |field, range| {
+            rows.push((Span::styled(field.to_string(), Style::Name), range))
+        });
+        if let Err(err) = result {
+            rows.push((
+                Span::styled(err.to_string(), Style::Bad),
+                err.offset.min(content.len())..content.len(),
+            ));
+        }
+        if content.len() < input.len() {
+            rows.push((
+                Span::styled("frame check sequence", Style::Name),
+                content.len()..input.len(),
+            ));
+        }
+
+        let mut document = Document::default();
+        document.0.push(Node::text(
+            Line::default()
+                .with("    ", Style::Column)
+                .with("  ", Style::Plain)
+                .with(
+                    (0..16)
+                        .map(|column| format!("{column:02x}"))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    Style::Column,
+                ),
+        ));
+        for (label, range) in rows {
+            // A field spanning multiple lines gets a row on every line
+            let first_line = range.start / 16;
+            let last_line = range.end.saturating_sub(1).max(range.start) / 16;
+            for line in first_line..=last_line {
+                let bytes = (line * 16..line * 16 + 16)
+                    .map(|i| match input.get(i) {
+                        Some(byte) if range.contains(&i) => format!("{byte:02x}"),
+                        _ => "  ".to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let mut row = Line::from(format!("{:04x}  {}  ", line * 16, bytes));
+                row.0.push(label.clone());
+                document.0.push(Node::text(row));
+            }
+        }
+
+        document
+    }
+
+    /// Write an annotated hexdump of a frame without FCS, with the byte range
+    /// of every field.
+    pub fn spans(input: &[u8]) -> Document {
+        let mut spans = Vec::new();
+        let result = field_spans(input, |field, range| {
+            spans.push(Node::text(
+                Line::default().with(field.to_string(), Style::Name).with(
+                    format!(
+                        " [{}..{}]: {}",
+                        range.start,
+                        range.end,
+                        hex::Hex(&input[range.clone()])
+                    ),
+                    Style::Plain,
+                ),
+            ));
+        });
+        if let Err(err) = result {
+            let offset = err.offset.min(input.len());
+            spans.push(Node::text(
+                Line::default().with(err.to_string(), Style::Bad).with(
+                    format!(
+                        " [{}..{}]: {}",
+                        offset,
+                        input.len(),
+                        hex::Hex(&input[offset..])
+                    ),
+                    Style::Plain,
+                ),
+            ));
+        }
+
+        Document(vec![Node::section("Field Spans", Style::Section, spans)])
+    }
+
+    /// Summarize a frame, of which the FCS is handled according to `fcs`, see
+    /// [`FrameParser::summary`]. A wrong FCS is reported at the end.
+    pub fn summary_with_fcs(input: &[u8], fcs: FcsMode) -> Result<Line> {
+        let content = Self::without_fcs(input, fcs);
+        let mut summary = Self::summary(content)?;
+        let with_fcs = FrameWithFcs::new_unchecked(input);
+        if content.len() < input.len() && !with_fcs.check_fcs() {
+            summary.push(" (", Style::Plain);
+            summary.push("bad fcs", Style::Bad);
+            summary.push(")", Style::Plain);
+        }
+        Ok(summary)
+    }
+
+    /// Summarize a frame without FCS on a single line: its type, the source
+    /// and destination address, the sequence number, and the Information
+    /// Elements it carries, e.g.
+    ///
+    /// ```txt
+    /// Data c7:d9:b5:14:00:4b:12:00 -> ff:ff seq 1
+    /// ```
+    ///
+    /// Absent fields are written as `-`. A malformed frame is summarized by
+    /// its first malformed field.
+    pub fn summary(input: &[u8]) -> Result<Line> {
+        if let Err(err) = validate(input) {
+            return Ok(Line::default()
+                .with("invalid", Style::Bad)
+                .with(format!(": {err}"), Style::Plain));
+        }
+
+        let frame = Frame::new(input)?;
+        let repr = FrameRepr::parse(&DataFrame::new_unchecked(input))?;
+        let address = |address: Option<Address>| match address {
+            Some(Address::Absent) | None => "-".to_string(),
+            Some(address) => address.to_string(),
+        };
+        let addressing = repr.addressing_fields.as_ref();
+
+        let mut summary = Line::default()
+            .with(frame_type(&frame.frame_control()), Style::FrameType)
+            .with(
+                format!(
+                    " {} -> {} seq {}",
+                    address(addressing.and_then(|addr| addr.src_address)),
+                    address(addressing.and_then(|addr| addr.dst_address)),
+                    repr.sequence_number
+                        .map_or_else(|| "-".to_string(), |seq| seq.to_string()),
+                ),
+                Style::Plain,
+            );
+
+        let mut elements = Vec::new();
+        if let Some(ie) = &repr.information_elements {
+            for header in &ie.header_information_elements {
+                match HeaderElementId::from(header) {
+                    HeaderElementId::HeaderTermination1 | HeaderElementId::HeaderTermination2 => {}
+                    id => elements.push(format!("{id:?}")),
+                }
+            }
+            for payload in &ie.payload_information_elements {
+                if let PayloadInformationElementRepr::Mlme(nested) = payload {
+                    elements.extend(nested.iter().map(|nested| match NestedSubId::from(nested) {
+                        NestedSubId::Short(id) => format!("{id:?}"),
+                        NestedSubId::Long(id) => format!("{id:?}"),
+                    }));
+                }
+            }
+        }
+        if !elements.is_empty() {
+            summary.push(format!(" [{}]", elements.join(", ")), Style::Plain);
+        }
+
+        Ok(summary)
+    }
+
+    /// Compare two frames, of which the FCS is handled according to `fcs`,
+    /// see [`FrameParser::diff`].
+    pub fn diff_with_fcs(a: &[u8], b: &[u8], fcs: FcsMode) -> Result<Document> {
+        Self::diff(Self::without_fcs(a, fcs), Self::without_fcs(b, fcs))
     }
 
-    pub fn parse(input: &[u8]) -> Result<String> {
-        let frame = Frame::new(input).unwrap();
-        let mut buffer = String::new();
+    /// Compare two frames without FCS field by field, and write the fields
+    /// that differ, grouped per part of the frame: the frame control flags,
+    /// the sequence number, the addressing fields, the auxiliary security
+    /// header, the Information Elements and the payload bytes.
+    pub fn diff(a: &[u8], b: &[u8]) -> Result<Document> {
+        let a = Self::fields(a)?;
+        let b = Self::fields(b)?;
+
+        let mut keys: Vec<(&str, &str)> = Vec::new();
+        for (section, name, _) in a.iter().chain(b.iter()) {
+            if !keys.contains(&(section, name)) {
+                keys.push((section, name));
+            }
+        }
+        // Fields that only the second frame has are moved to their section
+        keys.sort_by_key(|(section, _)| SECTIONS.iter().position(|s| s == section));
+        let value = |fields: &[Field], key: (&str, &str)| {
+            fields
+                .iter()
+                .find(|(section, name, _)| (*section, name.as_str()) == key)
+                .map(|(_, _, value)| value.clone())
+        };
+
+        let mut document = Document::default();
+        let mut current_section = None;
+        for key in keys {
+            let (section, name) = key;
+            let (a, b) = (value(&a, key), value(&b, key));
+            if a == b {
+                continue;
+            }
+
+            if current_section != Some(section) {
+                document
+                    .0
+                    .push(Node::section(section, Style::Section, Vec::new()));
+                current_section = Some(section);
+            }
+
+            let absent = || Span::styled("absent", Style::Absent);
+            let field = Node::field(
+                name,
+                Line(vec![
+                    a.map_or_else(absent, |a| Span::styled(a, Style::Bad)),
+                    Span::styled(" -> ", Style::Plain),
+                    b.map_or_else(absent, |b| Span::styled(b, Style::Good)),
+                ]),
+            );
+            if let Some(Node::Section { children, .. }) = document.0.last_mut() {
+                children.push(field);
+            }
+        }
+
+        if current_section.is_none() {
+            document.0.push(Node::text("The frames are identical."));
+        }
+
+        Ok(document)
+    }
 
-        let mut w = Writer::new(&mut buffer);
+    /// Return the fields of a frame without FCS as their section, name and
+    /// value, in the order of the frame.
+    fn fields(input: &[u8]) -> Result<Vec<Field>> {
+        let frame = Frame::new(input)?;
+        let repr = FrameRepr::parse(&DataFrame::new_unchecked(input))?;
+        let mut fields: Vec<Field> = Vec::new();
+        let mut push = |section, name: &str, value: String| {
+            // Repeated fields, such as Information Elements, are numbered
+            let count = fields
+                .iter()
+                .filter(|(s, n, _)| *s == section && n.split(" #").next() == Some(name))
+                .count();
+            let name = match count {
+                0 => name.to_string(),
+                count => format!("{name} #{}", count + 1),
+            };
+            fields.push((section, name, value));
+        };
 
         let fc = frame.frame_control();
+        let section = "Frame Control";
+        push(section, "frame type", format!("{:?}", fc.frame_type()));
+        for (name, flag) in [
+            ("security", fc.security_enabled()),
+            ("frame pending", fc.frame_pending()),
+            ("ack request", fc.ack_request()),
+            ("pan id compression", fc.pan_id_compression()),
+            (
+                "sequence number suppression",
+                fc.sequence_number_suppression(),
+            ),
+            (
+                "information elements present",
+                fc.information_elements_present(),
+            ),
+        ] {
+            push(section, name, (flag as usize).to_string());
+        }
+        push(
+            section,
+            "dst addressing mode",
+            format!("{:?}", fc.dst_addressing_mode()),
+        );
+        push(
+            section,
+            "src addressing mode",
+            format!("{:?}", fc.src_addressing_mode()),
+        );
+        push(
+            section,
+            "frame version",
+            format!("{} ({:?})", fc.frame_version() as usize, fc.frame_version()),
+        );
+
+        if let Some(seq) = repr.sequence_number {
+            push("Sequence Number", "sequence number", seq.to_string());
+        }
+
+        if let Some(addr) = &repr.addressing_fields {
+            let section = "Addressing";
+            if let Some(dst_pan_id) = addr.dst_pan_id {
+                push(section, "dst pan id", format!("{dst_pan_id:x}"));
+            }
+            if let Some(dst_addr) = addr.dst_address {
+                push(section, "dst addr", dst_addr.to_string());
+            }
+            if let Some(src_pan_id) = addr.src_pan_id {
+                push(section, "src pan id", format!("{src_pan_id:x}"));
+            }
+            if let Some(src_addr) = addr.src_address {
+                push(section, "src addr", src_addr.to_string());
+            }
+        }
+
+        if let Some(aux) = &repr.auxiliary_security_header {
+            push(
+                "Auxiliary Security Header",
+                "auxiliary security header",
+                Self::single_line(&aux.to_string()),
+            );
+        }
+
+        if let Some(ie) = &repr.information_elements {
+            let section = "Information Elements";
+            for header in &ie.header_information_elements {
+                push(
+                    section,
+                    &format!("{:?}", HeaderElementId::from(header)),
+                    Self::ie_content(&header.to_string()),
+                );
+            }
+            for payload in &ie.payload_information_elements {
+                match payload {
+                    PayloadInformationElementRepr::Mlme(nested) => {
+                        for nested in nested {
+                            let name = match NestedSubId::from(nested) {
+                                NestedSubId::Short(id) => format!("MLME {id:?}"),
+                                NestedSubId::Long(id) => format!("MLME {id:?}"),
+                            };
+                            push(section, &name, Self::ie_content(&nested.to_string()));
+                        }
+                    }
+                    PayloadInformationElementRepr::PayloadTermination => {
+                        push(section, "PayloadTermination", "present".to_string());
+                    }
+                }
+            }
+        }
+
+        if let Some(payload) = repr.payload {
+            push("Payload", "payload", hex::Hex(payload).to_string());
+        }
+
+        Ok(fields)
+    }
+
+    /// Return the content of a displayed Information Element, without the
+    /// line with its identifier, on a single line.
+    fn ie_content(ie: &str) -> String {
+        match ie.split_once('\n') {
+            Some((_, content)) if !content.trim().is_empty() => Self::single_line(content),
+            _ => "present".to_string(),
+        }
+    }
+
+    fn single_line(s: &str) -> String {
+        s.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Parse a frame without FCS. A malformed frame is parsed up to the
+    /// first malformed field, which is annotated with the error.
+    pub fn parse(input: &[u8]) -> Result<Document> {
+        if let Err(err) = validate(input) {
+            return Ok(Self::parse_partial(input, err));
+        }
+
+        let frame = Frame::new(input)?;
+        let mut document = Document::default();
 
         // -----------------------------------------------------------------
         // Frame Control
         // -----------------------------------------------------------------
-        w.writeln("Frame Control".underline().bold().to_string());
-        w.increase_indent();
-        w.writeln(format!(
-            "{}: {}",
-            "frame type".bold(),
-            format!(
-                "{}{:?}",
-                if fc.frame_version() == FrameVersion::Ieee802154_2020
-                    && (fc.frame_type() == FrameType::Beacon || fc.frame_type() == FrameType::Ack)
-                {
-                    "Enhanced "
-                } else {
-                    ""
-                },
-                fc.frame_type()
-            )
-            .bright_blue(),
-        ));
-        w.writeln(format!(
-            "{}: {}",
-            "security".bold(),
-            fc.security_enabled() as usize
-        ));
-        w.writeln(format!(
-            "{}: {}",
-            "frame pending".bold(),
-            fc.frame_pending() as usize
-        ));
-        w.writeln(format!(
-            "{}: {}",
-            "ack request".bold(),
-            fc.ack_request() as usize
-        ));
-        w.writeln(format!(
-            "{}: {}",
-            "pan id compression".bold(),
-            fc.pan_id_compression() as usize
-        ));
-        w.writeln(format!(
-            "{}: {}",
-            "sequence number suppression".bold(),
-            fc.sequence_number_suppression() as usize
-        ));
-        w.writeln(format!(
-            "{}: {}",
-            "information elements present".bold(),
-            fc.information_elements_present() as usize
-        ));
-        w.writeln(format!(
-            "{}: {:?}",
-            "dst addressing mode".bold(),
-            fc.dst_addressing_mode()
-        ));
-        w.writeln(format!(
-            "{}: {:?}",
-            "src addressing mode".bold(),
-            fc.src_addressing_mode()
-        ));
-        w.writeln(format!(
-            "{}: {} ({:?})",
-            "frame version".bold(),
-            fc.frame_version() as usize,
-            fc.frame_version()
-        ));
-        w.decrease_indent();
+        document.0.push(Self::frame_control(&frame.frame_control()));
 
         // -----------------------------------------------------------------
         // Sequence Number
         // -----------------------------------------------------------------
         if let Some(seq) = frame.sequence_number() {
-            w.writeln(format!("{}", "Sequence Number".underline().bold()));
-            w.increase_indent();
-            w.writeln(format!("{}: {}", "sequence number".bold(), seq));
-            w.decrease_indent();
+            document.0.push(Self::sequence_number(seq));
         }
 
         // -----------------------------------------------------------------
         // Addressing
         // -----------------------------------------------------------------
         if let Some(addr) = frame.addressing() {
-            w.writeln(format!("{}", "Addressing".underline().bold()));
-            w.increase_indent();
-
-            if let Some(dst_pan_id) = addr.dst_pan_id() {
-                w.writeln(format!("{}: {:x}", "dst pan id".bold(), dst_pan_id));
-            }
-
-            if let Some(dst_addr) = addr.dst_address() {
-                w.writeln(format!(
-                    "{}: {}{}",
-                    "dst addr".bold(),
-                    dst_addr,
-                    if dst_addr.is_broadcast() {
-                        " (broadcast)"
-                    } else {
-                        ""
-                    }
-                ));
-            }
-
-            if let Some(src_pan_id) = addr.src_pan_id() {
-                w.writeln(format!("{}: {:x}", "src pan id".bold(), src_pan_id));
-            }
-
-            if let Some(src_addr) = addr.src_address() {
-                w.writeln(format!(
-                    "{}: {}{}",
-                    "src addr".bold(),
-                    src_addr,
-                    if src_addr.is_broadcast() {
-                        " (broadcast)"
-                    } else {
-                        ""
-                    }
-                ));
-            }
-            w.decrease_indent();
+            document.0.push(Self::addressing(&addr));
         }
 
         // -----------------------------------------------------------------
         // Auxiliary Security Header
         // -----------------------------------------------------------------
         if frame.auxiliary_security_header().is_some() {
-            w.writeln(format!(
-                "{}",
-                "Auxiliary Security Header".underline().bold()
-            ));
-            w.increase_indent();
-            w.writeln("unimplementec".to_string());
-            w.decrease_indent();
+            document.0.push(Self::auxiliary_security_header());
         }
 
         // -----------------------------------------------------------------
         // Information Elements
         // -----------------------------------------------------------------
         if let Some(ie) = frame.information_elements() {
-            w.writeln(format!("{}", "Information Elements".underline().bold()));
+            let mut elements = Vec::new();
 
             // -------------------------------------------------------------
             // Header Information Elements
@@ -193,36 +531,15 @@ impl FrameParser {
             let headers: Vec<HeaderInformationElement<&[u8]>> =
                 ie.header_information_elements().collect();
             if !headers.is_empty() {
-                w.increase_indent();
-                w.writeln(format!("{}", "Header Information Elements".italic()));
-
-                for header in headers {
-                    w.increase_indent();
-                    let id = header.element_id();
-                    if matches!(
-                        id,
-                        HeaderElementId::HeaderTermination1 | HeaderElementId::HeaderTermination2
-                    ) {
-                        w.writeln(format!("{}", format!("{:?}", header.element_id()).bold()));
-                    } else {
-                        w.writeln(format!("{}", format!("{:?}", header.element_id()).bold()));
-
-                        w.increase_indent();
-                        match id {
-                            HeaderElementId::TimeCorrection => {
-                                if let Ok(tc) = TimeCorrection::new(header.content()) {
-                                    w.writeln(format!("{tc}"));
-                                } else {
-                                    w.writeln("invalid".to_string());
-                                }
-                            }
-                            _ => w.writeln("unimplemented".to_string()),
-                        }
-                        w.decrease_indent();
-                    }
-                    w.decrease_indent();
-                }
-                w.decrease_indent();
+                let headers = headers
+                    .iter()
+                    .map(Self::header_information_element)
+                    .collect();
+                elements.push(Node::section(
+                    "Header Information Elements",
+                    Style::Subsection,
+                    headers,
+                ));
             }
 
             // -------------------------------------------------------------
@@ -231,107 +548,299 @@ impl FrameParser {
             let payloads: Vec<PayloadInformationElement<&[u8]>> =
                 ie.payload_information_elements().collect();
             if !payloads.is_empty() {
-                w.increase_indent();
-                w.writeln(format!("{}", "Payload Information Elements".italic()));
-
-                for payload in payloads {
-                    w.increase_indent();
-                    match payload.group_id() {
-                        PayloadGroupId::Mlme => {
-                            w.writeln("MLME".to_string());
-
-                            for nested in payload.nested_information_elements() {
-                                w.increase_indent();
-                                w.writeln(format!(
-                                    "{}",
-                                    match nested.sub_id() {
-                                        NestedSubId::Short(id) => format!("{id:?}").bold(),
-                                        NestedSubId::Long(id) => format!("{id:?}").bold(),
-                                    }
-                                ));
-
-                                w.increase_indent();
-                                match nested.sub_id() {
-                                    NestedSubId::Short(NestedSubIdShort::TschSynchronization) => {
-                                        if let Ok(sync) = TschSynchronization::new(nested.content())
-                                        {
-                                            w.writeln(format!("{sync}"));
-                                        } else {
-                                            w.writeln("invalid".to_string());
-                                        }
-                                    }
-                                    NestedSubId::Short(NestedSubIdShort::TschTimeslot) => {
-                                        if let Ok(timeslot) = TschTimeslot::new(nested.content()) {
-                                            w.writeln(format!("{timeslot}"));
-                                            if timeslot.has_timeslot_timings() {
-                                                w.write(format!(
-                                                    "{:indent$}",
-                                                    timeslot.timeslot_timings(),
-                                                    indent = w.indent
-                                                ));
-                                            }
-                                        } else {
-                                            w.writeln("invalid".to_string());
-                                        }
-                                    }
-                                    NestedSubId::Short(NestedSubIdShort::TschSlotframeAndLink) => {
-                                        if let Ok(slotframe_and_link) =
-                                            TschSlotframeAndLink::new(nested.content())
-                                        {
-                                            w.writeln(format!("{slotframe_and_link}"));
-                                            for slotframe_descriptor in
-                                                slotframe_and_link.slotframe_descriptors()
-                                            {
-                                                w.writeln(format!(
-                                                    "{}",
-                                                    format!("{slotframe_descriptor}").italic()
-                                                ));
-                                                w.increase_indent();
-                                                for link_information in
-                                                    slotframe_descriptor.link_informations()
-                                                {
-                                                    w.writeln(format!("{link_information}"));
-                                                }
-                                                w.decrease_indent();
-                                            }
-                                        } else {
-                                            w.writeln("invalid".to_string());
-                                        }
-                                    }
-                                    NestedSubId::Long(NestedSubIdLong::ChannelHopping) => {
-                                        if let Ok(channel_hopping) =
-                                            ChannelHopping::new(nested.content())
-                                        {
-                                            w.writeln(format!("{channel_hopping}"));
-                                        } else {
-                                            w.writeln("invalid".to_string());
-                                        }
-                                    }
-                                    _ => w.writeln("unimplemented".to_string()),
-                                }
-                                w.decrease_indent();
-                                w.decrease_indent();
-                            }
-                        }
-                        id => w.writeln(format!("{}: unimplemented", format!("{:?}", id).bold())),
-                    }
-
-                    w.decrease_indent();
-                }
-
-                w.decrease_indent();
+                let payloads = payloads
+                    .iter()
+                    .map(Self::payload_information_element)
+                    .collect();
+                elements.push(Node::section(
+                    "Payload Information Elements",
+                    Style::Subsection,
+                    payloads,
+                ));
             }
+
+            document.0.push(Node::section(
+                "Information Elements",
+                Style::Section,
+                elements,
+            ));
         }
 
         // -----------------------------------------------------------------
         // Payload
         // -----------------------------------------------------------------
         if let Some(payload) = frame.payload() {
-            w.writeln(format!("{}", "Payload".underline().bold()));
-            w.increase_indent();
-            w.writeln(format!("{:x?}", payload));
+            document.0.push(Node::section(
+                "Payload",
+                Style::Section,
+                vec![Node::text(format!("{:x?}", payload))],
+            ));
         }
 
-        Ok(buffer)
+        Ok(document)
+    }
+
+    fn header_information_element(header: &HeaderInformationElement<&[u8]>) -> Node {
+        let id = header.element_id();
+        let content = match id {
+            HeaderElementId::HeaderTermination1 | HeaderElementId::HeaderTermination2 => {
+                return Node::text(Span::styled(format!("{id:?}"), Style::Name));
+            }
+            HeaderElementId::TimeCorrection => {
+                if let Ok(tc) = TimeCorrection::new(header.content()) {
+                    vec![Node::text(format!("{tc}"))]
+                } else {
+                    vec![Node::text("invalid")]
+                }
+            }
+            HeaderElementId::SimplifiedSuperframeSpecification => {
+                if let Ok(sss) = SimplifiedSuperframeSpecification::new(header.content())
+                    .and_then(|sss| SimplifiedSuperframeSpecificationRepr::parse(&sss))
+                {
+                    Self::indented(&format!("{sss}"))
+                } else {
+                    vec![Node::text("invalid")]
+                }
+            }
+            _ => vec![Node::text("unimplemented")],
+        };
+
+        Node::section(format!("{id:?}"), Style::Name, content)
+    }
+
+    fn payload_information_element(payload: &PayloadInformationElement<&[u8]>) -> Node {
+        match payload.group_id() {
+            PayloadGroupId::Mlme => Node::section(
+                "MLME",
+                Style::Plain,
+                payload
+                    .nested_information_elements()
+                    .map(|nested| Self::nested_information_element(&nested))
+                    .collect(),
+            ),
+            id => Node::field(format!("{:?}", id), "unimplemented"),
+        }
+    }
+
+    fn nested_information_element(nested: &NestedInformationElement<&[u8]>) -> Node {
+        let mut content = Vec::new();
+        match nested.sub_id() {
+            NestedSubId::Short(NestedSubIdShort::TschSynchronization) => {
+                if let Ok(sync) = TschSynchronization::new(nested.content()) {
+                    content.push(Node::text(format!("{sync}")));
+                } else {
+                    content.push(Node::text("invalid"));
+                }
+            }
+            NestedSubId::Short(NestedSubIdShort::TschTimeslot) => {
+                if let Ok(timeslot) = TschTimeslot::new(nested.content()) {
+                    content.push(Node::text(format!("{timeslot}")));
+                    if timeslot.has_timeslot_timings() {
+                        content.extend(Node::lines(&format!("{}", timeslot.timeslot_timings())));
+                    }
+                } else {
+                    content.push(Node::text("invalid"));
+                }
+            }
+            NestedSubId::Short(NestedSubIdShort::TschSlotframeAndLink) => {
+                if let Ok(slotframe_and_link) = TschSlotframeAndLink::new(nested.content()) {
+                    content.push(Node::text(format!("{slotframe_and_link}")));
+                    for slotframe_descriptor in slotframe_and_link.slotframe_descriptors() {
+                        content.push(Node::section(
+                            format!("{slotframe_descriptor}"),
+                            Style::Subsection,
+                            slotframe_descriptor
+                                .link_informations()
+                                .map(|link_information| Node::text(format!("{link_information}")))
+                                .collect(),
+                        ));
+                    }
+                } else {
+                    content.push(Node::text("invalid"));
+                }
+            }
+            NestedSubId::Short(NestedSubIdShort::LinkMargin) => {
+                if let Ok(link_margin) = LinkMargin::new(nested.content()) {
+                    content.push(Node::text(format!("{link_margin}")));
+                } else {
+                    content.push(Node::text("invalid"));
+                }
+            }
+            NestedSubId::Short(NestedSubIdShort::EnhancedBeaconFilter) => {
+                if let Ok(filter) = EnhancedBeaconFilter::new(nested.content()) {
+                    content.push(Node::text(format!("{filter}")));
+                } else {
+                    content.push(Node::text("invalid"));
+                }
+            }
+            NestedSubId::Long(NestedSubIdLong::ChannelHopping) => {
+                if let Ok(channel_hopping) = ChannelHopping::new(nested.content()) {
+                    content.push(Node::text(format!("{channel_hopping}")));
+                } else {
+                    content.push(Node::text("invalid"));
+                }
+            }
+            _ => content.push(Node::text("unimplemented")),
+        }
+
+        let name = match nested.sub_id() {
+            NestedSubId::Short(id) => format!("{id:?}"),
+            NestedSubId::Long(id) => format!("{id:?}"),
+        };
+        Node::section(name, Style::Name, content)
+    }
+
+    /// Return the lines of an indented multi-line text, e.g. the `Display`
+    /// output of a representation, as nested sections.
+    fn indented(text: &str) -> Vec<Node> {
+        fn indent(line: &str) -> usize {
+            line.len() - line.trim_start().len()
+        }
+
+        let lines: Vec<&str> = text.lines().collect();
+        let mut nodes = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+            let end = lines[i + 1..]
+                .iter()
+                .position(|next| indent(next) <= indent(line))
+                .map_or(lines.len(), |end| i + 1 + end);
+            if end > i + 1 {
+                let children = lines[i + 1..end].join("\n");
+                nodes.push(Node::Section {
+                    title: line.trim().into(),
+                    children: Self::indented(&children),
+                });
+            } else {
+                nodes.push(Node::text(line.trim()));
+            }
+            i = end;
+        }
+        nodes
+    }
+
+    /// Write the fields in front of the first malformed field, followed by
+    /// the error and the bytes that could not be parsed.
+    fn parse_partial(input: &[u8], err: ParseError) -> Document {
+        let mut document = Document::default();
+
+        if err.field > FrameField::FrameControl {
+            let fc = FrameControl::new_unchecked(&input[..2]);
+            document.0.push(Self::frame_control(&fc));
+
+            let mut offset = 2;
+            if !fc.sequence_number_suppression() && err.field > FrameField::SequenceNumber {
+                document.0.push(Self::sequence_number(input[2]));
+                offset += 1;
+            }
+
+            if err.field > FrameField::Addressing {
+                if let Ok(addr) = AddressingFields::new(&input[offset..], fc) {
+                    document.0.push(Self::addressing(&addr));
+                }
+            }
+
+            if fc.security_enabled() && err.field > FrameField::AuxiliarySecurityHeader {
+                document.0.push(Self::auxiliary_security_header());
+            }
+        }
+
+        document.0.push(Node::section(
+            "Error",
+            Style::Error,
+            vec![
+                Node::text(Span::styled(err.to_string(), Style::Bad)),
+                Node::field(
+                    "unparsed",
+                    format!("{:x?}", &input[err.offset.min(input.len())..]),
+                ),
+            ],
+        ));
+
+        document
+    }
+
+    fn frame_control(fc: &FrameControl<&[u8]>) -> Node {
+        Node::section(
+            "Frame Control",
+            Style::Section,
+            vec![
+                Node::field("frame type", Span::styled(frame_type(fc), Style::FrameType)),
+                Node::field("security", (fc.security_enabled() as usize).to_string()),
+                Node::field("frame pending", (fc.frame_pending() as usize).to_string()),
+                Node::field("ack request", (fc.ack_request() as usize).to_string()),
+                Node::field(
+                    "pan id compression",
+                    (fc.pan_id_compression() as usize).to_string(),
+                ),
+                Node::field(
+                    "sequence number suppression",
+                    (fc.sequence_number_suppression() as usize).to_string(),
+                ),
+                Node::field(
+                    "information elements present",
+                    (fc.information_elements_present() as usize).to_string(),
+                ),
+                Node::field(
+                    "dst addressing mode",
+                    format!("{:?}", fc.dst_addressing_mode()),
+                ),
+                Node::field(
+                    "src addressing mode",
+                    format!("{:?}", fc.src_addressing_mode()),
+                ),
+                Node::field(
+                    "frame version",
+                    format!("{} ({:?})", fc.frame_version() as usize, fc.frame_version()),
+                ),
+            ],
+        )
+    }
+
+    fn sequence_number(seq: u8) -> Node {
+        Node::section(
+            "Sequence Number",
+            Style::Section,
+            vec![Node::field("sequence number", seq.to_string())],
+        )
+    }
+
+    fn addressing(addr: &AddressingFields<&[u8], &[u8]>) -> Node {
+        let address = |address: Address| {
+            format!(
+                "{}{}",
+                address,
+                if address.is_broadcast() {
+                    " (broadcast)"
+                } else {
+                    ""
+                }
+            )
+        };
+
+        let mut fields = Vec::new();
+        if let Some(dst_pan_id) = addr.dst_pan_id() {
+            fields.push(Node::field("dst pan id", format!("{:x}", dst_pan_id)));
+        }
+        if let Some(dst_addr) = addr.dst_address() {
+            fields.push(Node::field("dst addr", address(dst_addr)));
+        }
+        if let Some(src_pan_id) = addr.src_pan_id() {
+            fields.push(Node::field("src pan id", format!("{:x}", src_pan_id)));
+        }
+        if let Some(src_addr) = addr.src_address() {
+            fields.push(Node::field("src addr", address(src_addr)));
+        }
+
+        Node::section("Addressing", Style::Section, fields)
+    }
+
+    fn auxiliary_security_header() -> Node {
+        Node::section(
+            "Auxiliary Security Header",
+            Style::Section,
+            vec![Node::text("unimplementec")],
+        )
     }
 }
@@ -1,5 +1,9 @@
-use clap::Parser;
-use dot15d4_cat::FrameParser;
+use std::io::{BufRead, IsTerminal};
+
+use clap::{Parser, Subcommand, ValueEnum};
+use colored::Colorize;
+use dot15d4_cat::{Document, FcsMode, FrameParser, Line, Style};
+use dot15d4_frame::hex;
 
 // dot15d4 40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b00
 // dot15d4 022e37cdab0200020002000200020fe18f
@@ -8,19 +12,186 @@ use dot15d4_cat::FrameParser;
 
 /// `cat`, but for IEEE 802.15.4 frames.
 #[derive(Parser, Debug)]
-#[command(version, about, long_about)]
+#[command(version, about, long_about, args_conflicts_with_subcommands = true)]
 struct Args {
-    /// The IEEE 802.15.4 frame to parse.
-    #[clap(value_parser(clap::builder::NonEmptyStringValueParser::new()))]
-    input: String,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// The IEEE 802.15.4 frames to parse. With `-`, frames are read from
+    /// standard input, one per line.
+    #[clap(
+        required = true,
+        value_parser(clap::builder::NonEmptyStringValueParser::new())
+    )]
+    input: Vec<String>,
+
+    /// Whether the frame ends with a 2-byte Frame Check Sequence.
+    #[clap(long, value_enum, default_value_t = FcsMode::Auto)]
+    fcs: FcsMode,
+
+    /// Also print the byte range of every field.
+    #[clap(long)]
+    spans: bool,
+
+    /// Print the raw bytes, annotated with the field they belong to, instead
+    /// of the parsed fields.
+    #[clap(long)]
+    hexdump: bool,
+
+    /// Print a one-line summary per frame: its type, source and destination
+    /// address, sequence number and Information Elements.
+    #[clap(short, long, conflicts_with_all = ["spans", "hexdump"])]
+    quiet: bool,
+
+    /// When to color the output.
+    #[clap(long, value_enum, default_value_t = ColorMode::Auto, global = true)]
+    color: ColorMode,
+
+    /// Print the output as JSON, one line per frame.
+    #[clap(long, global = true)]
+    json: bool,
+}
+
+/// When to color the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ColorMode {
+    /// Color the output when it is written to a terminal, and `NO_COLOR` is
+    /// not set.
+    Auto,
+    /// Always color the output.
+    Always,
+    /// Never color the output.
+    Never,
+}
+
+impl ColorMode {
+    fn apply(self) {
+        match self {
+            ColorMode::Auto if !std::io::stdout().is_terminal() => {
+                colored::control::set_override(false)
+            }
+            // The environment, e.g. `NO_COLOR`, decides
+            ColorMode::Auto => {}
+            ColorMode::Always => colored::control::set_override(true),
+            ColorMode::Never => colored::control::set_override(false),
+        }
+    }
+}
+
+/// Apply the ANSI style of `style` to `text`. Whether colors are written is
+/// decided by [`colored::control`], see [`ColorMode::apply`].
+fn ansi(text: &str, style: Style) -> String {
+    match style {
+        Style::Plain => text.normal(),
+        Style::Section => text.underline().bold(),
+        Style::Subsection => text.italic(),
+        Style::Name => text.bold(),
+        Style::FrameType => text.bright_blue(),
+        Style::Good => text.green(),
+        Style::Bad => text.red(),
+        Style::Absent => text.dimmed(),
+        Style::Error => text.underline().bold().red(),
+        Style::Column => text.underline(),
+    }
+    .to_string()
+}
+
+/// Print a document, as JSON or as colored text.
+fn print(document: &Document, json: bool) {
+    if json {
+        println!("{}", document.to_json());
+    } else {
+        print!("{}", document.render(ansi));
+    }
+}
+
+/// Print a single line, as a JSON string or as colored text.
+fn print_line(line: &Line, json: bool) {
+    if json {
+        println!("{}", serde_json::Value::from(line.text()));
+    } else {
+        println!("{}", line.render(ansi));
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Compare two frames field by field, and print the fields that differ.
+    Diff {
+        /// The first IEEE 802.15.4 frame.
+        #[clap(value_parser(clap::builder::NonEmptyStringValueParser::new()))]
+        a: String,
+
+        /// The second IEEE 802.15.4 frame.
+        #[clap(value_parser(clap::builder::NonEmptyStringValueParser::new()))]
+        b: String,
+
+        /// Whether the frames end with a 2-byte Frame Check Sequence.
+        #[clap(long, value_enum, default_value_t = FcsMode::Auto)]
+        fcs: FcsMode,
+    },
 }
 
 fn main() {
     let args = Args::parse();
-    let data = hex::decode(args.input).unwrap();
+    args.color.apply();
+
+    if let Some(Command::Diff { a, b, fcs }) = args.command {
+        let a = hex::decode_to_vec(&a).unwrap();
+        let b = hex::decode_to_vec(&b).unwrap();
+        match FrameParser::diff_with_fcs(&a, &b, fcs) {
+            Ok(diff) => print(&diff, args.json),
+            Err(_) => eprintln!("Failed to parse the frames."),
+        }
+        return;
+    }
+
+    let mut inputs = Vec::new();
+    for input in args.input {
+        if input == "-" {
+            let stdin = std::io::stdin().lock();
+            inputs.extend(stdin.lines().map(|line| line.unwrap()));
+        } else {
+            inputs.push(input);
+        }
+    }
+
+    for (i, input) in inputs.iter().map(|input| input.trim()).enumerate() {
+        if input.is_empty() {
+            continue;
+        }
+        let Ok(data) = hex::decode_to_vec(input) else {
+            eprintln!("Invalid hexadecimal frame: {input}");
+            continue;
+        };
+
+        if args.quiet {
+            match FrameParser::summary_with_fcs(&data, args.fcs) {
+                Ok(summary) => print_line(&summary, args.json),
+                Err(_) => eprintln!("Failed to parse the frame."),
+            }
+            continue;
+        }
+
+        if i > 0 && !args.json {
+            println!();
+        }
+
+        if args.hexdump {
+            print(&FrameParser::hexdump_with_fcs(&data, args.fcs), args.json);
+            continue;
+        }
+
+        match FrameParser::parse_with_fcs(&data, args.fcs) {
+            Ok(parsed) => print(&parsed, args.json),
+            Err(_) => eprintln!("Failed to parse the frame."),
+        }
 
-    match FrameParser::parse(&data) {
-        Ok(parsed) => println!("{}", parsed),
-        Err(_) => eprintln!("Failed to parse the frame."),
+        if args.spans {
+            if !args.json {
+                println!();
+            }
+            print(&FrameParser::spans_with_fcs(&data, args.fcs), args.json);
+        }
     }
 }
@@ -0,0 +1,222 @@
+//! The output of [`FrameParser`](crate::FrameParser), as a tree of sections,
+//! fields and lines of text.
+//!
+//! The tree is independent of how it is shown: every piece of text is tagged
+//! with a [`Style`] that describes what it is, e.g. the name of a field, or a
+//! wrong value. A [`Document`] is rendered as plain text with its `Display`
+//! implementation, or as JSON with [`Document::to_json`]. Other renderers, e.g.
+//! one that colors the text for a terminal, use [`Document::render`], or walk
+//! the tree themselves.
+
+use core::fmt;
+
+/// What a piece of text is, which decides how it is styled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    /// Text without a particular meaning.
+    #[default]
+    Plain,
+    /// The title of a part of the frame.
+    Section,
+    /// The title of a group within a part of the frame.
+    Subsection,
+    /// The name of a field.
+    Name,
+    /// The type of the frame.
+    FrameType,
+    /// A value that is correct, or that was added.
+    Good,
+    /// A value that is wrong, or that was removed.
+    Bad,
+    /// A placeholder for a value that is absent.
+    Absent,
+    /// The title of an error.
+    Error,
+    /// The header of a column.
+    Column,
+}
+
+/// A piece of text with a single style.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+impl Span {
+    /// Create a span of `text` with the given `style`.
+    pub fn styled(text: impl Into<String>, style: Style) -> Self {
+        Self {
+            text: text.into(),
+            style,
+        }
+    }
+}
+
+/// A line of text, made of spans with their own style.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Line(pub Vec<Span>);
+
+impl Line {
+    /// Append `text` with the given `style`.
+    pub fn push(&mut self, text: impl Into<String>, style: Style) {
+        self.0.push(Span::styled(text, style));
+    }
+
+    /// Append `text` with the given `style`, and return the line.
+    pub fn with(mut self, text: impl Into<String>, style: Style) -> Self {
+        self.push(text, style);
+        self
+    }
+
+    /// Return the text of the line, without styles.
+    pub fn text(&self) -> String {
+        self.0.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    /// Render the line, of which every piece of text is written by `style`,
+    /// see [`Document::render`].
+    pub fn render(&self, style: impl Fn(&str, Style) -> String) -> String {
+        self.0
+            .iter()
+            .map(|span| style(&span.text, span.style))
+            .collect()
+    }
+}
+
+/// Renders the line as plain text, without styles.
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+impl From<Span> for Line {
+    fn from(span: Span) -> Self {
+        Self(vec![span])
+    }
+}
+
+impl From<String> for Line {
+    fn from(text: String) -> Self {
+        Span::styled(text, Style::Plain).into()
+    }
+}
+
+impl From<&str> for Line {
+    fn from(text: &str) -> Self {
+        text.to_string().into()
+    }
+}
+
+/// A node of a [`Document`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    /// A titled group of nodes, e.g. a part of the frame. The children are
+    /// indented below the title.
+    Section { title: Line, children: Vec<Node> },
+    /// A named value, written as `name: value`.
+    Field { name: String, value: Line },
+    /// A line of text.
+    Text(Line),
+}
+
+impl Node {
+    /// Create a section titled `title`, styled as `style`.
+    pub fn section(title: impl Into<String>, style: Style, children: Vec<Node>) -> Self {
+        Self::Section {
+            title: Span::styled(title, style).into(),
+            children,
+        }
+    }
+
+    /// Create a field named `name`.
+    pub fn field(name: impl Into<String>, value: impl Into<Line>) -> Self {
+        Self::Field {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Create a line of text.
+    pub fn text(line: impl Into<Line>) -> Self {
+        Self::Text(line.into())
+    }
+
+    /// Create a line of text for every line of `text`, e.g. the multi-line
+    /// `Display` output of a reader.
+    pub fn lines(text: &str) -> impl Iterator<Item = Node> + '_ {
+        text.lines().map(Node::text)
+    }
+}
+
+/// A tree of sections, fields and lines of text.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Document(pub Vec<Node>);
+
+impl Document {
+    /// Render the document as indented lines of text, of which every piece of
+    /// text is written by `style`, e.g. to color it.
+    pub fn render(&self, style: impl Fn(&str, Style) -> String) -> String {
+        fn write(
+            buffer: &mut String,
+            nodes: &[Node],
+            indent: usize,
+            style: &impl Fn(&str, Style) -> String,
+        ) {
+            for node in nodes {
+                buffer.push_str(&" ".repeat(indent));
+                match node {
+                    Node::Section { title, children } => {
+                        buffer.push_str(&title.render(style));
+                        buffer.push('\n');
+                        write(buffer, children, indent + 2, style);
+                        continue;
+                    }
+                    Node::Field { name, value } => {
+                        buffer.push_str(&style(name, Style::Name));
+                        buffer.push_str(": ");
+                        buffer.push_str(&value.render(style));
+                    }
+                    Node::Text(text) => buffer.push_str(&text.render(style)),
+                }
+                buffer.push('\n');
+            }
+        }
+
+        let mut buffer = String::new();
+        write(&mut buffer, &self.0, 0, &style);
+        buffer
+    }
+
+    /// Render the document as a JSON array of its nodes, without styles. A
+    /// section is written as `{"title": ..., "children": [...]}`, a field as
+    /// `{"name": ..., "value": ...}`, and a line of text as `{"text": ...}`.
+    pub fn to_json(&self) -> String {
+        fn json(nodes: &[Node]) -> serde_json::Value {
+            nodes
+                .iter()
+                .map(|node| match node {
+                    Node::Section { title, children } => serde_json::json!({
+                        "title": title.text(),
+                        "children": json(children),
+                    }),
+                    Node::Field { name, value } => serde_json::json!({
+                        "name": name,
+                        "value": value.text(),
+                    }),
+                    Node::Text(text) => serde_json::json!({ "text": text.text() }),
+                })
+                .collect()
+        }
+
+        json(&self.0).to_string()
+    }
+}
+
+/// Renders the document as plain text, without styles.
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(|text, _| text.to_string()))
+    }
+}
@@ -1,11 +1,10 @@
-use dot15d4_cat::FrameParser;
-
-use strip_ansi_escapes::strip;
+use dot15d4_cat::{FcsMode, FrameParser, Line, Node, Style};
+use dot15d4_frame::hex;
 
 #[test]
 fn enhanced_beacon() {
     let input = "40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b00";
-    let output = String::from_utf8(strip(FrameParser::parse_hex(input).unwrap())).unwrap();
+    let output = FrameParser::parse_hex(input).unwrap().to_string();
     assert_eq!(
         output,
         "Frame Control
@@ -43,7 +42,7 @@ Information Elements
 #[test]
 fn enhanced_beacon_with_slotframes() {
     let input = "40ebcdabffff0100010001000100003f3788061a110000000000191c01080780004808fc032003e80398089001c0006009a010102701c8000f1b010011000200000100060100020007";
-    let output = String::from_utf8(strip(FrameParser::parse_hex(input).unwrap())).unwrap();
+    let output = FrameParser::parse_hex(input).unwrap().to_string();
     assert_eq!(
         output,
         "Frame Control
@@ -96,7 +95,7 @@ Information Elements
 #[test]
 fn enhanced_ack() {
     let input = "022e37cdab0200020002000200020fe18f";
-    let output = String::from_utf8(strip(FrameParser::parse_hex(input).unwrap())).unwrap();
+    let output = FrameParser::parse_hex(input).unwrap().to_string();
     assert_eq!(
         output,
         "Frame Control
@@ -129,7 +128,7 @@ Payload
 #[test]
 fn data_frame() {
     let input = "41d801cdabffffc7d9b514004b12002b000000";
-    let output = String::from_utf8(strip(FrameParser::parse_hex(input).unwrap())).unwrap();
+    let output = FrameParser::parse_hex(input).unwrap().to_string();
     assert_eq!(
         output,
         "Frame Control
@@ -154,3 +153,404 @@ Payload
 "
     );
 }
+
+#[test]
+fn link_margin() {
+    let input = "41ea01cdab0000c7d9b514004b1200003f03880137fb00f82b";
+    let output = FrameParser::parse_hex(input).unwrap().to_string();
+    assert_eq!(
+        output,
+        "Frame Control
+  frame type: Data
+  security: 0
+  frame pending: 0
+  ack request: 0
+  pan id compression: 1
+  sequence number suppression: 0
+  information elements present: 1
+  dst addressing mode: Short
+  src addressing mode: Extended
+  frame version: 2 (Ieee802154_2020)
+Sequence Number
+  sequence number: 1
+Addressing
+  dst pan id: abcd
+  dst addr: 00:00
+  src addr: 00:12:4b:00:14:b5:d9:c7
+Information Elements
+  Header Information Elements
+    HeaderTermination1
+  Payload Information Elements
+    MLME
+      LinkMargin
+        link margin: -5 dB
+    PayloadTermination: unimplemented
+Payload
+  [2b]
+"
+    );
+}
+
+#[test]
+fn enhanced_beacon_request() {
+    let input = "032a03ffffffff003f0488021e053200f807";
+    let output = FrameParser::parse_hex(input).unwrap().to_string();
+    assert_eq!(
+        output,
+        "Frame Control
+  frame type: MacCommand
+  security: 0
+  frame pending: 0
+  ack request: 0
+  pan id compression: 0
+  sequence number suppression: 0
+  information elements present: 1
+  dst addressing mode: Short
+  src addressing mode: Absent
+  frame version: 2 (Ieee802154_2020)
+Sequence Number
+  sequence number: 3
+Addressing
+  dst pan id: ffff
+  dst addr: ff:ff (broadcast)
+  src addr: absent
+Information Elements
+  Header Information Elements
+    HeaderTermination1
+  Payload Information Elements
+    MLME
+      EnhancedBeaconFilter
+        permit joining on: 1, percent filter: 50%
+    PayloadTermination: unimplemented
+Payload
+  [7]
+"
+    );
+}
+
+#[test]
+fn simplified_superframe_specification() {
+    let input = "40ebcdabffff010001000100010086113412ffcf491f";
+    let output = FrameParser::parse_hex(input).unwrap().to_string();
+    assert_eq!(
+        output,
+        "Frame Control
+  frame type: Enhanced Beacon
+  security: 0
+  frame pending: 0
+  ack request: 0
+  pan id compression: 1
+  sequence number suppression: 1
+  information elements present: 1
+  dst addressing mode: Short
+  src addressing mode: Extended
+  frame version: 2 (Ieee802154_2020)
+Addressing
+  dst pan id: abcd
+  dst addr: ff:ff (broadcast)
+  src addr: 00:01:00:01:00:01:00:01
+Information Elements
+  Header Information Elements
+    SimplifiedSuperframeSpecification
+      timestamp: 4660
+      superframe specification
+        beacon order: OnDemand
+        superframe order: Inactive
+        final cap slot: 15
+        battery life extension: 0
+        pan coordinator: 1
+        association permit: 1
+      cfp specification
+        gts count: 1
+        first cfp slot: 9
+        last cfp slot: 15
+        gts permit: 1
+"
+    );
+}
+
+#[test]
+fn fcs() {
+    let input = "40ebcdabffff0100010001000100003f3288061a110000000000191c01080780004808fc032003e80398089001c0006009a010102701c8000a1b010011000100000000071216";
+    let output = FrameParser::parse_hex_with_fcs(input, FcsMode::Present)
+        .unwrap()
+        .to_string();
+    assert!(output.ends_with(
+        "Frame Check Sequence
+  fcs: 1612 (OK)
+"
+    ));
+    assert_eq!(
+        FrameParser::parse_hex_with_fcs(input, FcsMode::Auto).unwrap(),
+        FrameParser::parse_hex_with_fcs(input, FcsMode::Present).unwrap()
+    );
+
+    // A corrupted FCS is reported, or not used in auto mode
+    let input = &format!("{}17", &input[..input.len() - 2]);
+    let output = FrameParser::parse_hex_with_fcs(input, FcsMode::Present)
+        .unwrap()
+        .to_string();
+    assert!(output.ends_with(
+        "Frame Check Sequence
+  fcs: 1712 (BAD, expected 1612)
+"
+    ));
+    assert!(!FrameParser::parse_hex_with_fcs(input, FcsMode::Auto)
+        .unwrap()
+        .to_string()
+        .contains("Frame Check Sequence"));
+
+    // Frames without FCS are parsed as before
+    let input = "40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b00";
+    assert_eq!(
+        FrameParser::parse_hex_with_fcs(input, FcsMode::Auto).unwrap(),
+        FrameParser::parse_hex(input).unwrap()
+    );
+    assert_eq!(
+        FrameParser::parse_hex_with_fcs(input, FcsMode::Absent).unwrap(),
+        FrameParser::parse_hex(input).unwrap()
+    );
+}
+
+#[test]
+fn truncated_addressing() {
+    let input = "41d801cdabffffc7d9b5";
+    let output = FrameParser::parse_hex(input).unwrap().to_string();
+    assert_eq!(
+        output,
+        "Frame Control
+  frame type: Data
+  security: 0
+  frame pending: 0
+  ack request: 0
+  pan id compression: 1
+  sequence number suppression: 0
+  information elements present: 0
+  dst addressing mode: Short
+  src addressing mode: Extended
+  frame version: 1 (Ieee802154_2006)
+Sequence Number
+  sequence number: 1
+Error
+  truncated addressing fields at byte 3
+  unparsed: [cd, ab, ff, ff, c7, d9, b5]
+"
+    );
+}
+
+#[test]
+fn truncated_information_element() {
+    let input = "40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b";
+    let output = FrameParser::parse_hex(input).unwrap().to_string();
+    assert!(output.contains("Addressing\n"));
+    assert!(output.ends_with(
+        "Error
+  truncated payload information element at byte 16
+  unparsed: [11, 88, 6, 1a, e, 0, 0, 0, 0, 0, 1, 1c, 0, 1, c8, 0, 1, 1b]
+"
+    ));
+
+    let output = FrameParser::parse_hex("40").unwrap().to_string();
+    assert_eq!(
+        output,
+        "Error
+  truncated frame control at byte 0
+  unparsed: [40]
+"
+    );
+}
+
+#[test]
+fn spans() {
+    let input = hex::decode_to_vec("41d801cdabffffc7d9b514004b12002b000000").unwrap();
+    let output = FrameParser::spans(&input).to_string();
+    assert_eq!(
+        output,
+        "Field Spans
+  frame control [0..2]: 41d8
+  sequence number [2..3]: 01
+  addressing fields [3..15]: cdabffffc7d9b514004b1200
+  payload [15..19]: 2b000000
+"
+    );
+
+    let output = FrameParser::spans(&input[..10]).to_string();
+    assert_eq!(
+        output,
+        "Field Spans
+  frame control [0..2]: 41d8
+  sequence number [2..3]: 01
+  truncated addressing fields at byte 3 [3..10]: cdabffffc7d9b5
+"
+    );
+}
+
+#[test]
+fn hexdump() {
+    let input = hex::decode_to_vec("41d801cdabffffc7d9b514004b12002b000000").unwrap();
+    let output = FrameParser::hexdump_with_fcs(&input, FcsMode::Absent).to_string();
+    assert_eq!(
+        output,
+        "      00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f
+0000  41 d8                                            frame control
+0000        01                                         sequence number
+0000           cd ab ff ff c7 d9 b5 14 00 4b 12 00     addressing fields
+0000                                               2b  payload
+0010  00 00 00                                         payload
+"
+    );
+
+    // The FCS and a malformed field get their own row
+    let input = hex::decode_to_vec("41d801cdabffffc7d9b5").unwrap();
+    let output = FrameParser::hexdump_with_fcs(&input, FcsMode::Present).to_string();
+    assert_eq!(
+        output,
+        "      00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f
+0000  41 d8                                            frame control
+0000        01                                         sequence number
+0000           cd ab ff ff c7                          truncated addressing fields at byte 3
+0000                          d9 b5                    frame check sequence
+"
+    );
+}
+
+#[test]
+fn diff() {
+    let diff = |a: &str, b: &str| {
+        FrameParser::diff(
+            &hex::decode_to_vec(a).unwrap(),
+            &hex::decode_to_vec(b).unwrap(),
+        )
+        .unwrap()
+        .to_string()
+    };
+
+    let data = "41d801cdabffffc7d9b514004b12002b000000";
+    assert_eq!(diff(data, data), "The frames are identical.\n");
+    assert_eq!(
+        diff(data, "41d802cdabffffc7d9b514004b12002b000001"),
+        "Sequence Number
+  sequence number: 1 -> 2
+Payload
+  payload: 2b000000 -> 2b000001
+"
+    );
+
+    // Fields of only one of the frames are reported as absent
+    assert_eq!(
+        diff("022e37cdab0200020002000200020fe18f", data),
+        "Frame Control
+  frame type: Ack -> Data
+  pan id compression: 0 -> 1
+  information elements present: 1 -> 0
+  dst addressing mode: Extended -> Short
+  src addressing mode: Absent -> Extended
+  frame version: 2 (Ieee802154_2020) -> 1 (Ieee802154_2006)
+Sequence Number
+  sequence number: 55 -> 1
+Addressing
+  dst addr: 00:02:00:02:00:02:00:02 -> ff:ff
+  src addr: absent -> 00:12:4b:00:14:b5:d9:c7
+Information Elements
+  TimeCorrection: -0.03ms, nack: 1 -> absent
+Payload
+  payload: absent -> 2b000000
+"
+    );
+
+    // Information Elements are compared one by one
+    assert_eq!(
+        diff(
+            "40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b00",
+            "40ebcdabffff0100010001000100003f1188061a0f0000000000011c0001c800011b00"
+        ),
+        "Information Elements
+  MLME TschSynchronization: ASN: 14, join metric: 0 -> ASN: 15, join metric: 0
+"
+    );
+
+    // With an FCS, the FCS itself is not compared
+    let with_fcs = hex::decode_to_vec("40ebcdabffff0100010001000100003f3288061a110000000000191c01080780004808fc032003e80398089001c0006009a010102701c8000a1b010011000100000000071216").unwrap();
+    let without_fcs = &with_fcs[..with_fcs.len() - 2];
+    assert_eq!(
+        FrameParser::diff_with_fcs(&with_fcs, without_fcs, FcsMode::Auto)
+            .unwrap()
+            .to_string(),
+        "The frames are identical.\n"
+    );
+}
+
+#[test]
+fn summary() {
+    let summary = |input: &str| {
+        let data = hex::decode_to_vec(input).unwrap();
+        FrameParser::summary_with_fcs(&data, FcsMode::Auto)
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(
+        summary("40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b00"),
+        "Enhanced Beacon 00:01:00:01:00:01:00:01 -> ff:ff seq - \
+         [TschSynchronization, TschTimeslot, ChannelHopping, TschSlotframeAndLink]"
+    );
+    assert_eq!(
+        summary("41d801cdabffffc7d9b514004b12002b000000"),
+        "Data 00:12:4b:00:14:b5:d9:c7 -> ff:ff seq 1"
+    );
+    assert_eq!(
+        summary("022e37cdab0200020002000200020fe18f"),
+        "Enhanced Ack - -> 00:02:00:02:00:02:00:02 seq 55 [TimeCorrection]"
+    );
+    assert_eq!(
+        summary("4188"),
+        "invalid: truncated sequence number at byte 2"
+    );
+
+    // A wrong FCS is only reported when the FCS is known to be present
+    let data = hex::decode_to_vec("41d801cdabffffc7d9b514004b12002b000000").unwrap();
+    assert_eq!(
+        FrameParser::summary_with_fcs(&data, FcsMode::Present)
+            .unwrap()
+            .to_string(),
+        "Data 00:12:4b:00:14:b5:d9:c7 -> ff:ff seq 1 (bad fcs)"
+    );
+}
+
+#[test]
+fn document() {
+    let document = FrameParser::parse_hex("022e37cdab0200020002000200020fe18f").unwrap();
+
+    // The output is a tree of which the text is tagged with its style, but
+    // not colored
+    let Node::Section { title, children } = &document.0[0] else {
+        panic!("expected the frame control section");
+    };
+    assert_eq!(
+        title,
+        &Line::default().with("Frame Control", Style::Section)
+    );
+    assert_eq!(
+        children[0],
+        Node::field(
+            "frame type",
+            Line::default().with("Enhanced Ack", Style::FrameType)
+        )
+    );
+    assert!(!document.to_string().contains('\x1b'));
+
+    // Renderers decide how a style is shown
+    let rendered = document.render(|text, style| match style {
+        Style::FrameType => format!("<{text}>"),
+        _ => text.to_string(),
+    });
+    assert!(rendered.contains("  frame type: <Enhanced Ack>\n"));
+
+    let json = FrameParser::parse_hex("41d801cdabffffc7d9b514004b12002b000000")
+        .unwrap()
+        .to_json();
+    assert!(json.starts_with(
+        r#"[{"children":[{"name":"frame type","value":"Data"},{"name":"security","value":"0"},"#
+    ));
+    assert!(json.ends_with(r#"{"children":[{"text":"[2b, 0, 0, 0]"}],"title":"Payload"}]"#));
+}
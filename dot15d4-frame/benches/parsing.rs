@@ -0,0 +1,59 @@
+use core::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use dot15d4_frame::*;
+
+const ENHANCED_BEACON: [u8; 35] = [
+    0x40, 0xeb, 0xcd, 0xab, 0xff, 0xff, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x3f,
+    0x11, 0x88, 0x06, 0x1a, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x1c, 0x00, 0x01, 0xc8, 0x00,
+    0x01, 0x1b, 0x00,
+];
+
+const DATA_FRAME: [u8; 19] = [
+    0x41, 0xd8, 0x01, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12, 0x00, 0x2b,
+    0x00, 0x00, 0x00,
+];
+
+fn enhanced_beacon(c: &mut Criterion) {
+    c.bench_function("enhanced beacon repr", |b| {
+        b.iter(|| {
+            let frame = DataFrame::new(black_box(&ENHANCED_BEACON[..])).unwrap();
+            FrameRepr::parse(&frame).unwrap()
+        })
+    });
+}
+
+fn data_frame(c: &mut Criterion) {
+    c.bench_function("data frame fields", |b| {
+        b.iter(|| {
+            let frame = DataFrame::new(black_box(&DATA_FRAME[..])).unwrap();
+            let addressing = frame.addressing().unwrap();
+            black_box((
+                addressing.dst_pan_id(),
+                addressing.dst_address(),
+                addressing.src_pan_id(),
+                addressing.src_address(),
+                frame.payload(),
+            ))
+        })
+    });
+}
+
+fn ie_iteration(c: &mut Criterion) {
+    c.bench_function("enhanced beacon nested information elements", |b| {
+        b.iter(|| {
+            let frame = EnhancedBeacon::new(black_box(&ENHANCED_BEACON[..])).unwrap();
+            let ie = frame.information_elements().unwrap();
+
+            let mut count = ie.header_information_elements().count();
+            for payload in ie.payload_information_elements() {
+                count += payload.nested_information_elements().count();
+            }
+
+            count
+        })
+    });
+}
+
+criterion_group!(benches, enhanced_beacon, data_frame, ie_iteration);
+criterion_main!(benches);
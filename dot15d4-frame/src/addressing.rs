@@ -7,6 +7,7 @@ use super::{Error, Result};
 /// An IEEE 802.15.4 address.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Address {
     /// The address is absent.
     Absent,
@@ -30,6 +31,13 @@ impl Address {
         *self == Self::BROADCAST
     }
 
+    /// Query whether this address is one of the given group addresses. A
+    /// groupcast frame is addressed to a short address that is shared by all
+    /// members of the group.
+    pub fn is_group(&self, groups: &[[u8; 2]]) -> bool {
+        matches!(self, Address::Short(addr) if !self.is_broadcast() && groups.contains(addr))
+    }
+
     /// Create an [`Address`] from a slice of bytes.
     pub fn from(a: &[u8]) -> Self {
         if a.is_empty() {
@@ -156,9 +164,39 @@ impl core::fmt::Display for Address {
     }
 }
 
+/// Destination address filter of received frames, following the third level
+/// of filtering of IEEE 802.15.4-2020, section 6.7.2, with the group addresses
+/// of groupcast.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct AddressFilter<'a> {
+    /// The short address of the device. `ff:fe` and `ff:ff` mean that the
+    /// device has no short address.
+    pub short_address: [u8; 2],
+    /// The extended address of the device.
+    pub extended_address: [u8; 8],
+    /// The short addresses of the groups of which the device is a member.
+    pub groups: &'a [[u8; 2]],
+}
+
+impl AddressFilter<'_> {
+    /// Query whether a frame addressed to `address` is accepted: the broadcast
+    /// address, one of the groups and the addresses of the device are. An
+    /// absent address is not accepted, as that depends on the frame type and
+    /// the PAN identifiers.
+    pub fn accepts(&self, address: &Address) -> bool {
+        match address {
+            Address::Absent => false,
+            _ if address.is_broadcast() || address.is_group(self.groups) => true,
+            Address::Short(addr) => *addr != [0xff, 0xfe] && *addr == self.short_address,
+            Address::Extended(addr) => *addr == self.extended_address,
+        }
+    }
+}
+
 /// IEEE 802.15.4 addressing mode.
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum AddressingMode {
     /// The address is absent.
     Absent = 0b00,
@@ -193,11 +231,67 @@ impl From<u8> for AddressingMode {
     }
 }
 
+/// The layout of the Addressing Fields, derived from the Frame Control field.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+struct AddressingLayout {
+    dst_pan_id: bool,
+    dst_addr_mode: AddressingMode,
+    src_pan_id: bool,
+    src_addr_mode: AddressingMode,
+}
+
+impl AddressingLayout {
+    /// Offset of the destination address.
+    fn dst_address_offset(&self) -> usize {
+        if self.dst_pan_id {
+            2
+        } else {
+            0
+        }
+    }
+
+    /// Offset of the source PAN ID.
+    fn src_pan_id_offset(&self) -> usize {
+        self.dst_address_offset() + self.dst_addr_mode.size()
+    }
+
+    /// Offset of the source address.
+    fn src_address_offset(&self) -> usize {
+        self.src_pan_id_offset() + if self.src_pan_id { 2 } else { 0 }
+    }
+
+    /// Length of the Addressing Fields.
+    fn len(&self) -> usize {
+        self.src_address_offset() + self.src_addr_mode.size()
+    }
+}
+
+/// The offsets of the Addressing Fields, relative to the start of the
+/// Addressing Fields. Fields that are not present have no offset.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct AddressingOffsets {
+    /// Offset of the destination PAN ID.
+    pub dst_pan_id: Option<usize>,
+    /// Offset of the destination address.
+    pub dst_address: Option<usize>,
+    /// Offset of the source PAN ID.
+    pub src_pan_id: Option<usize>,
+    /// Offset of the source address.
+    pub src_address: Option<usize>,
+    /// Length of the Addressing Fields, which is the offset of the first byte
+    /// after them.
+    pub len: usize,
+}
+
 /// A reader/writer for the IEEE 802.15.4 Addressing Fields.
+///
+/// The layout of the fields is computed once from the Frame Control field
+/// when the reader is created.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct AddressingFields<T: AsRef<[u8]>, FC: AsRef<[u8]>> {
     buffer: T,
     fc: FrameControl<FC>,
+    layout: Option<AddressingLayout>,
 }
 
 impl<T: AsRef<[u8]>, FC: AsRef<[u8]>> AddressingFields<T, FC> {
@@ -220,51 +314,63 @@ impl<T: AsRef<[u8]>, FC: AsRef<[u8]>> AddressingFields<T, FC> {
 
     /// Check if the buffer is large enough to contain the addressing fields.
     fn check_len(&self) -> bool {
-        let Some((dst_pan_id_present, dst_addr_mode, src_pan_id_present, src_addr_mode)) =
-            Self::address_present_flags(
-                self.fc.frame_version(),
-                self.fc.dst_addressing_mode(),
-                self.fc.src_addressing_mode(),
-                self.fc.pan_id_compression(),
-            )
-        else {
+        let Some(layout) = self.layout else {
             return false;
         };
 
-        let expected_len = (if dst_pan_id_present { 2 } else { 0 })
-            + dst_addr_mode.size()
-            + (if src_pan_id_present { 2 } else { 0 })
-            + src_addr_mode.size();
-
-        self.buffer.as_ref().len() >= expected_len
+        self.buffer.as_ref().len() >= layout.len()
     }
 
     /// Create a new [`AddressingFields`] reader/writer from a given buffer
     /// without checking the length.
     pub fn new_unchecked(buffer: T, fc: FrameControl<FC>) -> Self {
-        Self { buffer, fc }
+        let layout = Self::address_present_flags(
+            fc.frame_version(),
+            fc.dst_addressing_mode(),
+            fc.src_addressing_mode(),
+            fc.pan_id_compression(),
+        )
+        .map(
+            |(dst_pan_id, dst_addr_mode, src_pan_id, src_addr_mode)| AddressingLayout {
+                dst_pan_id,
+                dst_addr_mode,
+                src_pan_id,
+                src_addr_mode,
+            },
+        );
+
+        Self { buffer, fc, layout }
     }
 
     /// Return the length of the Addressing Fields in octets.
+    ///
+    /// Returns 0 when the addressing modes in the Frame Control field are
+    /// invalid.
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
-        (match self.dst_pan_id() {
-            Some(_) => 2,
-            None => 0,
-        }) + match self.fc.dst_addressing_mode() {
-            AddressingMode::Absent => 0,
-            AddressingMode::Short => 2,
-            AddressingMode::Extended => 8,
-            _ => unreachable!(),
-        } + match self.src_pan_id() {
-            Some(_) => 2,
-            None => 0,
-        } + match self.fc.src_addressing_mode() {
-            AddressingMode::Absent => 0,
-            AddressingMode::Short => 2,
-            AddressingMode::Extended => 8,
-            _ => unreachable!(),
-        }
+        self.layout.map(|layout| layout.len()).unwrap_or(0)
+    }
+
+    /// Return the offsets of the fields that are present.
+    ///
+    /// Returns `None` when the addressing modes in the Frame Control field are
+    /// invalid.
+    pub fn offsets(&self) -> Option<AddressingOffsets> {
+        let layout = self.layout?;
+        let present = |present: bool, offset: usize| present.then_some(offset);
+        Some(AddressingOffsets {
+            dst_pan_id: present(layout.dst_pan_id, 0),
+            dst_address: present(
+                layout.dst_addr_mode != AddressingMode::Absent,
+                layout.dst_address_offset(),
+            ),
+            src_pan_id: present(layout.src_pan_id, layout.src_pan_id_offset()),
+            src_address: present(
+                layout.src_addr_mode != AddressingMode::Absent,
+                layout.src_address_offset(),
+            ),
+            len: layout.len(),
+        })
     }
 
     fn address_present_flags(
@@ -275,6 +381,7 @@ impl<T: AsRef<[u8]>, FC: AsRef<[u8]>> AddressingFields<T, FC> {
     ) -> Option<(bool, AddressingMode, bool, AddressingMode)> {
         use AddressingMode::*;
         match frame_version {
+            #[cfg(feature = "legacy-frames")]
             FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => {
                 match (dst_addr_mode, src_addr_mode, pan_id_compression) {
                     // If both destination and source address information is present, and the
@@ -324,100 +431,57 @@ impl<T: AsRef<[u8]>, FC: AsRef<[u8]>> AddressingFields<T, FC> {
         }
     }
 
-    /// Return the IEEE 802.15.4 destination [`Address`] if not absent.
-    pub fn dst_address(&self) -> Option<Address> {
-        if let Some((dst_pan_id, dst_addr, _, _)) = Self::address_present_flags(
-            self.fc.frame_version(),
-            self.fc.dst_addressing_mode(),
-            self.fc.src_addressing_mode(),
-            self.fc.pan_id_compression(),
-        ) {
-            let offset = if dst_pan_id { 2 } else { 0 };
-
-            match dst_addr {
-                AddressingMode::Absent => Some(Address::Absent),
-                AddressingMode::Short => {
-                    let mut raw = [0u8; 2];
-                    raw.clone_from_slice(&self.buffer.as_ref()[offset..offset + 2]);
-                    raw.reverse();
-                    Some(Address::short_from_bytes(raw))
-                }
-                AddressingMode::Extended => {
-                    let mut raw = [0u8; 8];
-                    raw.clone_from_slice(&self.buffer.as_ref()[offset..offset + 8]);
-                    raw.reverse();
-                    Some(Address::extended_from_bytes(raw))
-                }
-                AddressingMode::Unknown => None,
+    /// Read the address with the given mode at the given offset.
+    fn address(&self, mode: AddressingMode, offset: usize) -> Option<Address> {
+        match mode {
+            AddressingMode::Absent => Some(Address::Absent),
+            AddressingMode::Short => {
+                let mut raw = [0u8; 2];
+                raw.clone_from_slice(&self.buffer.as_ref()[offset..offset + 2]);
+                raw.reverse();
+                Some(Address::short_from_bytes(raw))
             }
-        } else {
-            None
+            AddressingMode::Extended => {
+                let mut raw = [0u8; 8];
+                raw.clone_from_slice(&self.buffer.as_ref()[offset..offset + 8]);
+                raw.reverse();
+                Some(Address::extended_from_bytes(raw))
+            }
+            AddressingMode::Unknown => None,
         }
     }
 
+    /// Return the IEEE 802.15.4 destination [`Address`] if not absent.
+    pub fn dst_address(&self) -> Option<Address> {
+        let layout = self.layout?;
+        self.address(layout.dst_addr_mode, layout.dst_address_offset())
+    }
+
     /// Return the IEEE 802.15.4 source [`Address`] if not absent.
     pub fn src_address(&self) -> Option<Address> {
-        if let Some((dst_pan_id, dst_addr, src_pan_id, src_addr)) = Self::address_present_flags(
-            self.fc.frame_version(),
-            self.fc.dst_addressing_mode(),
-            self.fc.src_addressing_mode(),
-            self.fc.pan_id_compression(),
-        ) {
-            let mut offset = if dst_pan_id { 2 } else { 0 };
-            offset += dst_addr.size();
-            offset += if src_pan_id { 2 } else { 0 };
-
-            match src_addr {
-                AddressingMode::Absent => Some(Address::Absent),
-                AddressingMode::Short => {
-                    let mut raw = [0u8; 2];
-                    raw.clone_from_slice(&self.buffer.as_ref()[offset..offset + 2]);
-                    raw.reverse();
-                    Some(Address::short_from_bytes(raw))
-                }
-                AddressingMode::Extended => {
-                    let mut raw = [0u8; 8];
-                    raw.clone_from_slice(&self.buffer.as_ref()[offset..offset + 8]);
-                    raw.reverse();
-                    Some(Address::extended_from_bytes(raw))
-                }
-                AddressingMode::Unknown => None,
-            }
-        } else {
-            None
-        }
+        let layout = self.layout?;
+        self.address(layout.src_addr_mode, layout.src_address_offset())
     }
 
     /// Return the IEEE 802.15.4 destination PAN ID if not elided.
     pub fn dst_pan_id(&self) -> Option<u16> {
-        if let Some((true, _, _, _)) = Self::address_present_flags(
-            self.fc.frame_version(),
-            self.fc.dst_addressing_mode(),
-            self.fc.src_addressing_mode(),
-            self.fc.pan_id_compression(),
-        ) {
-            let b = &self.buffer.as_ref()[..2];
-            Some(u16::from_le_bytes([b[0], b[1]]))
-        } else {
-            None
+        match self.layout {
+            Some(layout) if layout.dst_pan_id => {
+                let b = &self.buffer.as_ref()[..2];
+                Some(u16::from_le_bytes([b[0], b[1]]))
+            }
+            _ => None,
         }
     }
 
     /// Return the IEEE 802.15.4 source PAN ID if not elided.
     pub fn src_pan_id(&self) -> Option<u16> {
-        if let Some((dst_pan_id, dst_addr, true, _)) = Self::address_present_flags(
-            self.fc.frame_version(),
-            self.fc.dst_addressing_mode(),
-            self.fc.src_addressing_mode(),
-            self.fc.pan_id_compression(),
-        ) {
-            let mut offset = if dst_pan_id { 2 } else { 0 };
-            offset += dst_addr.size();
-
-            let b = &self.buffer.as_ref()[offset..][..2];
-            Some(u16::from_le_bytes([b[0], b[1]]))
-        } else {
-            None
+        match self.layout {
+            Some(layout) if layout.src_pan_id => {
+                let b = &self.buffer.as_ref()[layout.src_pan_id_offset()..][..2];
+                Some(u16::from_le_bytes([b[0], b[1]]))
+            }
+            _ => None,
         }
     }
 }
@@ -547,6 +611,51 @@ mod tests {
         assert!(Address::Short([0xff, 0xfe]).is_unicast());
     }
 
+    #[test]
+    fn is_group() {
+        let groups = [[0x80, 0x01], [0x80, 0x02]];
+        assert!(Address::Short([0x80, 0x01]).is_group(&groups));
+        assert!(Address::Short([0x80, 0x02]).is_group(&groups));
+        assert!(!Address::Short([0x80, 0x03]).is_group(&groups));
+        assert!(!Address::Short([0x80, 0x01]).is_group(&[]));
+        assert!(!Address::BROADCAST.is_group(&[[0xff, 0xff]]));
+        assert!(!Address::Extended([0x80; 8]).is_group(&[[0x80, 0x80]]));
+        assert!(!Address::Absent.is_group(&groups));
+    }
+
+    #[test]
+    fn address_filter() {
+        let groups = [[0x80, 0x01]];
+        let filter = AddressFilter {
+            short_address: [0x00, 0x01],
+            extended_address: [1, 2, 3, 4, 5, 6, 7, 8],
+            groups: &groups,
+        };
+        assert!(filter.accepts(&Address::BROADCAST));
+        assert!(filter.accepts(&Address::Short([0x80, 0x01])));
+        assert!(filter.accepts(&Address::Short([0x00, 0x01])));
+        assert!(filter.accepts(&Address::Extended([1, 2, 3, 4, 5, 6, 7, 8])));
+        assert!(!filter.accepts(&Address::Short([0x80, 0x02])));
+        assert!(!filter.accepts(&Address::Short([0x00, 0x02])));
+        assert!(!filter.accepts(&Address::Extended([8, 7, 6, 5, 4, 3, 2, 1])));
+        assert!(!filter.accepts(&Address::Absent));
+
+        // Without groups, groupcast frames are not accepted
+        let filter = AddressFilter {
+            groups: &[],
+            ..filter
+        };
+        assert!(!filter.accepts(&Address::Short([0x80, 0x01])));
+        assert!(filter.accepts(&Address::BROADCAST));
+
+        // ff:fe means that the device has no short address
+        let filter = AddressFilter {
+            short_address: [0xff, 0xfe],
+            ..filter
+        };
+        assert!(!filter.accepts(&Address::Short([0xff, 0xfe])));
+    }
+
     #[test]
     fn as_bytes() {
         assert_eq!(Address::BROADCAST.as_bytes(), &[0xff, 0xff]);
@@ -554,7 +663,7 @@ mod tests {
         assert_eq!(Address::Short([0xff, 0xfe]).as_bytes(), &[0xff, 0xfe]);
         assert_eq!(Address::Extended([0xff; 8]).as_bytes(), &[0xff; 8]);
         assert_eq!(Address::Extended([0x01; 8]).as_bytes(), &[0x01; 8]);
-        assert_eq!(Address::Absent.as_bytes(), &[]);
+        assert_eq!(Address::Absent.as_bytes(), &[0u8; 0]);
     }
 
     #[test]
@@ -591,43 +700,52 @@ mod tests {
             };
         }
 
-        check!((Ieee802154_2003, Short, Short, false) -> Some((true, Short, true, Short)));
-        check!((Ieee802154_2003, Short, Short, true) -> Some((true, Short, false, Short)));
-        check!((Ieee802154_2003, Extended, Extended, false) -> Some((true, Extended, true, Extended)));
-        check!((Ieee802154_2003, Extended, Extended, true) -> Some((true, Extended, false, Extended)));
-        check!((Ieee802154_2003, Short, Extended, false) -> Some((true, Short, true, Extended)));
-        check!((Ieee802154_2003, Short, Extended, true) -> Some((true, Short, false, Extended)));
-        check!((Ieee802154_2003, Extended, Short, false) -> Some((true, Extended, true, Short)));
-        check!((Ieee802154_2003, Extended, Short, true) -> Some((true, Extended, false, Short)));
-        check!((Ieee802154_2003, Absent, Short, false) -> Some((false, Absent, true, Short)));
-        check!((Ieee802154_2003, Absent, Extended, false) -> Some((false, Absent, true, Extended)));
-        check!((Ieee802154_2003, Short, Absent, false) -> Some((true, Short, false, Absent)));
-        check!((Ieee802154_2003, Extended, Absent, false) -> Some((true, Extended, false, Absent)));
-        check!((Ieee802154_2003, Absent, Short, true) -> None);
-        check!((Ieee802154_2003, Absent, Extended, true) -> None);
-        check!((Ieee802154_2003, Short, Absent, true) -> None);
-        check!((Ieee802154_2003, Extended, Absent, true) -> None);
-        check!((Ieee802154_2003, Absent, Absent, false) -> None);
-        check!((Ieee802154_2003, Absent, Absent, true) -> None);
-
-        check!((Ieee802154_2006, Short, Short, false) -> Some((true, Short, true, Short)));
-        check!((Ieee802154_2006, Short, Short, true) -> Some((true, Short, false, Short)));
-        check!((Ieee802154_2006, Extended, Extended, false) -> Some((true, Extended, true, Extended)));
-        check!((Ieee802154_2006, Extended, Extended, true) -> Some((true, Extended, false, Extended)));
-        check!((Ieee802154_2006, Short, Extended, false) -> Some((true, Short, true, Extended)));
-        check!((Ieee802154_2006, Short, Extended, true) -> Some((true, Short, false, Extended)));
-        check!((Ieee802154_2006, Extended, Short, false) -> Some((true, Extended, true, Short)));
-        check!((Ieee802154_2006, Extended, Short, true) -> Some((true, Extended, false, Short)));
-        check!((Ieee802154_2006, Absent, Short, false) -> Some((false, Absent, true, Short)));
-        check!((Ieee802154_2006, Absent, Extended, false) -> Some((false, Absent, true, Extended)));
-        check!((Ieee802154_2006, Short, Absent, false) -> Some((true, Short, false, Absent)));
-        check!((Ieee802154_2006, Extended, Absent, false) -> Some((true, Extended, false, Absent)));
-        check!((Ieee802154_2006, Absent, Short, true) -> None);
-        check!((Ieee802154_2006, Absent, Extended, true) -> None);
-        check!((Ieee802154_2006, Short, Absent, true) -> None);
-        check!((Ieee802154_2006, Extended, Absent, true) -> None);
-        check!((Ieee802154_2006, Absent, Absent, false) -> None);
-        check!((Ieee802154_2006, Absent, Absent, true) -> None);
+        #[cfg(feature = "legacy-frames")]
+        {
+            check!((Ieee802154_2003, Short, Short, false) -> Some((true, Short, true, Short)));
+            check!((Ieee802154_2003, Short, Short, true) -> Some((true, Short, false, Short)));
+            check!((Ieee802154_2003, Extended, Extended, false) -> Some((true, Extended, true, Extended)));
+            check!((Ieee802154_2003, Extended, Extended, true) -> Some((true, Extended, false, Extended)));
+            check!((Ieee802154_2003, Short, Extended, false) -> Some((true, Short, true, Extended)));
+            check!((Ieee802154_2003, Short, Extended, true) -> Some((true, Short, false, Extended)));
+            check!((Ieee802154_2003, Extended, Short, false) -> Some((true, Extended, true, Short)));
+            check!((Ieee802154_2003, Extended, Short, true) -> Some((true, Extended, false, Short)));
+            check!((Ieee802154_2003, Absent, Short, false) -> Some((false, Absent, true, Short)));
+            check!((Ieee802154_2003, Absent, Extended, false) -> Some((false, Absent, true, Extended)));
+            check!((Ieee802154_2003, Short, Absent, false) -> Some((true, Short, false, Absent)));
+            check!((Ieee802154_2003, Extended, Absent, false) -> Some((true, Extended, false, Absent)));
+            check!((Ieee802154_2003, Absent, Short, true) -> None);
+            check!((Ieee802154_2003, Absent, Extended, true) -> None);
+            check!((Ieee802154_2003, Short, Absent, true) -> None);
+            check!((Ieee802154_2003, Extended, Absent, true) -> None);
+            check!((Ieee802154_2003, Absent, Absent, false) -> None);
+            check!((Ieee802154_2003, Absent, Absent, true) -> None);
+
+            check!((Ieee802154_2006, Short, Short, false) -> Some((true, Short, true, Short)));
+            check!((Ieee802154_2006, Short, Short, true) -> Some((true, Short, false, Short)));
+            check!((Ieee802154_2006, Extended, Extended, false) -> Some((true, Extended, true, Extended)));
+            check!((Ieee802154_2006, Extended, Extended, true) -> Some((true, Extended, false, Extended)));
+            check!((Ieee802154_2006, Short, Extended, false) -> Some((true, Short, true, Extended)));
+            check!((Ieee802154_2006, Short, Extended, true) -> Some((true, Short, false, Extended)));
+            check!((Ieee802154_2006, Extended, Short, false) -> Some((true, Extended, true, Short)));
+            check!((Ieee802154_2006, Extended, Short, true) -> Some((true, Extended, false, Short)));
+            check!((Ieee802154_2006, Absent, Short, false) -> Some((false, Absent, true, Short)));
+            check!((Ieee802154_2006, Absent, Extended, false) -> Some((false, Absent, true, Extended)));
+            check!((Ieee802154_2006, Short, Absent, false) -> Some((true, Short, false, Absent)));
+            check!((Ieee802154_2006, Extended, Absent, false) -> Some((true, Extended, false, Absent)));
+            check!((Ieee802154_2006, Absent, Short, true) -> None);
+            check!((Ieee802154_2006, Absent, Extended, true) -> None);
+            check!((Ieee802154_2006, Short, Absent, true) -> None);
+            check!((Ieee802154_2006, Extended, Absent, true) -> None);
+            check!((Ieee802154_2006, Absent, Absent, false) -> None);
+            check!((Ieee802154_2006, Absent, Absent, true) -> None);
+        }
+
+        #[cfg(not(feature = "legacy-frames"))]
+        {
+            check!((Ieee802154_2003, Short, Short, false) -> None);
+            check!((Ieee802154_2006, Short, Short, true) -> None);
+        }
 
         check!((Ieee802154_2020, Short, Short, false) -> Some((true, Short, true, Short)));
         check!((Ieee802154_2020, Short, Short, true) -> Some((true, Short, false, Short)));
@@ -649,6 +767,47 @@ mod tests {
         check!((Ieee802154_2020, Absent, Absent, true) -> Some((true, Absent, false, Absent)));
     }
 
+    #[test]
+    #[cfg(feature = "legacy-frames")]
+    fn offsets() {
+        // Short destination, extended source, PAN ID compression
+        let fc = [0b0100_0001, 0b1100_1000];
+        let buffer = [0u8; 12];
+        let fields =
+            AddressingFields::new(&buffer[..], FrameControl::new_unchecked(&fc[..])).unwrap();
+        assert_eq!(
+            fields.offsets(),
+            Some(AddressingOffsets {
+                dst_pan_id: Some(0),
+                dst_address: Some(2),
+                src_pan_id: None,
+                src_address: Some(4),
+                len: 12,
+            })
+        );
+
+        // Only a short source address in a 2006 frame
+        let fc = [0b0000_0001, 0b1001_0000];
+        let fields =
+            AddressingFields::new(&buffer[..], FrameControl::new_unchecked(&fc[..])).unwrap();
+        assert_eq!(
+            fields.offsets(),
+            Some(AddressingOffsets {
+                dst_pan_id: None,
+                dst_address: None,
+                src_pan_id: Some(0),
+                src_address: Some(2),
+                len: 4,
+            })
+        );
+
+        // Invalid addressing modes
+        let fc = [0b0100_0001, 0b0001_0000];
+        let fields =
+            AddressingFields::new_unchecked(&buffer[..], FrameControl::new_unchecked(&fc[..]));
+        assert_eq!(fields.offsets(), None);
+    }
+
     #[test]
     fn parse() {
         let mut addresses = vec![
@@ -0,0 +1,143 @@
+//! The Absolute Slot Number (ASN) of TSCH.
+
+/// The Absolute Slot Number (ASN): the number of timeslots that elapsed since
+/// the start of a TSCH network.
+///
+/// The ASN is carried as a 5-byte field in the TSCH Synchronization IE, and
+/// thus wraps around at 2^40.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, Eq, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AbsoluteSlotNumber(u64);
+
+impl AbsoluteSlotNumber {
+    /// The first ASN of a network.
+    pub const ZERO: Self = Self(0);
+    /// The largest ASN, after which the ASN wraps around to zero.
+    pub const MAX: Self = Self((1 << 40) - 1);
+
+    /// Create a new [`AbsoluteSlotNumber`].
+    ///
+    /// Returns `None` when `asn` does not fit in 40 bits.
+    pub const fn new(asn: u64) -> Option<Self> {
+        if asn <= Self::MAX.0 {
+            Some(Self(asn))
+        } else {
+            None
+        }
+    }
+
+    /// Create a new [`AbsoluteSlotNumber`], wrapping `asn` around at 2^40.
+    pub const fn new_wrapping(asn: u64) -> Self {
+        Self(asn & Self::MAX.0)
+    }
+
+    /// Return the ASN as an integer.
+    pub const fn value(&self) -> u64 {
+        self.0
+    }
+
+    /// Parse the ASN from the 5-byte field of the TSCH Synchronization IE.
+    pub const fn from_bytes(bytes: [u8; 5]) -> Self {
+        let [b0, b1, b2, b3, b4] = bytes;
+        Self(u64::from_le_bytes([b0, b1, b2, b3, b4, 0, 0, 0]))
+    }
+
+    /// Return the ASN as the 5-byte field of the TSCH Synchronization IE.
+    pub const fn to_bytes(&self) -> [u8; 5] {
+        let [b0, b1, b2, b3, b4, ..] = self.0.to_le_bytes();
+        [b0, b1, b2, b3, b4]
+    }
+
+    /// Add `slots` timeslots, returning `None` when the ASN would wrap around.
+    pub const fn checked_add(&self, slots: u64) -> Option<Self> {
+        match self.0.checked_add(slots) {
+            Some(asn) => Self::new(asn),
+            None => None,
+        }
+    }
+
+    /// Add `slots` timeslots, wrapping around at 2^40.
+    pub const fn wrapping_add(&self, slots: u64) -> Self {
+        Self::new_wrapping(self.0.wrapping_add(slots))
+    }
+
+    /// Return the number of timeslots from `earlier` to this ASN, taking a
+    /// wrap around at 2^40 into account.
+    pub const fn wrapping_sub(&self, earlier: Self) -> u64 {
+        self.0.wrapping_sub(earlier.0) & Self::MAX.0
+    }
+
+    /// Return the offset of the timeslot within a slotframe of `size`
+    /// timeslots (`ASN % size`).
+    ///
+    /// # Panics
+    /// Panics when `size` is zero.
+    pub const fn slotframe_offset(&self, size: u16) -> u16 {
+        (self.0 % size as u64) as u16
+    }
+}
+
+impl core::ops::Add<u64> for AbsoluteSlotNumber {
+    type Output = Self;
+
+    fn add(self, rhs: u64) -> Self::Output {
+        self.wrapping_add(rhs)
+    }
+}
+
+impl core::ops::AddAssign<u64> for AbsoluteSlotNumber {
+    fn add_assign(&mut self, rhs: u64) {
+        *self = self.wrapping_add(rhs);
+    }
+}
+
+impl core::fmt::Display for AbsoluteSlotNumber {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(feature = "fuzz")]
+impl arbitrary::Arbitrary<'_> for AbsoluteSlotNumber {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        Ok(Self::new_wrapping(u.arbitrary()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asn() {
+        assert_eq!(AbsoluteSlotNumber::new(1 << 40), None);
+        assert_eq!(
+            AbsoluteSlotNumber::new_wrapping((1 << 40) + 3),
+            AbsoluteSlotNumber::new(3).unwrap()
+        );
+
+        let asn = AbsoluteSlotNumber::from_bytes([0x0e, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(asn.value(), (1 << 32) + 14);
+        assert_eq!(asn.to_bytes(), [0x0e, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(AbsoluteSlotNumber::MAX.to_bytes(), [0xff; 5]);
+
+        assert_eq!(
+            asn.checked_add(1).map(|asn| asn.value()),
+            Some((1 << 32) + 15)
+        );
+        assert_eq!(AbsoluteSlotNumber::MAX.checked_add(1), None);
+        assert_eq!(
+            AbsoluteSlotNumber::MAX + 2,
+            AbsoluteSlotNumber::new(1).unwrap()
+        );
+        assert_eq!(
+            AbsoluteSlotNumber::new(1)
+                .unwrap()
+                .wrapping_sub(AbsoluteSlotNumber::MAX),
+            2
+        );
+
+        assert_eq!(AbsoluteSlotNumber::new(23).unwrap().slotframe_offset(7), 2);
+        assert_eq!(std::format!("{asn}"), "4294967310");
+    }
+}
@@ -14,14 +14,65 @@ impl<T: AsRef<[u8]>> AuxiliarySecurityHeader<T> {
         Self { buffer }
     }
 
+    /// Returns `false` if the buffer is too short to contain the header.
+    pub fn check_len(&self) -> bool {
+        !self.buffer.as_ref().is_empty() && self.buffer.as_ref().len() >= self.len()
+    }
+
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
-        1 + self.security_control().security_level().mic_length()
+        self.key_identifier_offset() + self.key_identifier_len()
     }
 
     pub fn security_control(&self) -> SecurityControl {
         SecurityControl::from(self.buffer.as_ref()[0])
     }
+
+    /// Return the frame counter, if not suppressed.
+    pub fn frame_counter(&self) -> Option<u32> {
+        if self.security_control().frame_counter_suppression() {
+            return None;
+        }
+
+        let b = &self.buffer.as_ref()[1..5];
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Return the key source, which is empty when not present.
+    pub fn key_source(&self) -> &[u8] {
+        let offset = self.key_identifier_offset();
+        let len = self.key_identifier_len().saturating_sub(1);
+        &self.buffer.as_ref()[offset..offset + len]
+    }
+
+    /// Return the key index, if present.
+    pub fn key_index(&self) -> Option<u8> {
+        match self.key_identifier_len() {
+            0 => None,
+            len => Some(self.buffer.as_ref()[self.key_identifier_offset() + len - 1]),
+        }
+    }
+
+    fn key_identifier_offset(&self) -> usize {
+        1 + if self.security_control().frame_counter_suppression() {
+            0
+        } else {
+            4
+        }
+    }
+
+    fn key_identifier_len(&self) -> usize {
+        match self
+            .security_control()
+            .key_identifier_mode()
+            .key_identifier_mode()
+        {
+            KeyIdentifierMode::Implicit | KeyIdentifierMode::Unknown => 0,
+            KeyIdentifierMode::Explicit => 1,
+            KeyIdentifierMode::ExplicitWithShortSource => 5,
+            KeyIdentifierMode::ExplicitWithLongSource => 9,
+        }
+    }
 }
 
 /// A reader/writer for the IEEE 802.15.4 Security Control field.
@@ -81,7 +132,7 @@ impl SecurityLevel {
 
     /// Return `true` when confidentiality is enabled.
     pub fn data_confidentiality(&self) -> bool {
-        (self.buffer >> 3) & 0b1 == 1
+        (self.buffer >> 2) & 0b1 == 1
     }
 
     /// Return `true` when authenticity is enabled.
@@ -100,15 +151,89 @@ impl SecurityLevel {
     }
 }
 
+/// Build the CCM* nonce of a frame from the extended address of its
+/// originator, in the same byte order as [`Address::Extended`](crate::Address),
+/// its frame counter and its security level.
+pub fn ccm_nonce(
+    extended_address: &[u8; 8],
+    frame_counter: u32,
+    security_level: SecurityAttributes,
+) -> [u8; 13] {
+    let mut nonce = [0; 13];
+    nonce[..8].copy_from_slice(extended_address);
+    nonce[8..12].copy_from_slice(&frame_counter.to_be_bytes());
+    nonce[12] = security_level as u8;
+    nonce
+}
+
+/// The inputs of the CCM* transformation of a secured frame.
+///
+/// The open part of the frame, up to and including the Header IEs and the
+/// Command ID of a MAC command frame, is only authenticated. The private part, the Payload IEs and the payload up to the
+/// MIC, is encrypted when the security level provides confidentiality, and
+/// only authenticated otherwise.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CcmParts {
+    /// The CCM* nonce.
+    pub nonce: [u8; 13],
+    /// Length of the open part of the frame.
+    pub open_len: usize,
+    /// Length of the MIC at the end of the frame.
+    pub mic_len: usize,
+    /// Whether the private part of the frame is encrypted.
+    pub encrypted: bool,
+}
+
+impl CcmParts {
+    /// Return the a data of the frame: the authenticated but not encrypted
+    /// bytes.
+    pub fn a_data<'f>(&self, frame: &'f [u8]) -> &'f [u8] {
+        if self.encrypted {
+            &frame[..self.open_len]
+        } else {
+            &frame[..frame.len() - self.mic_len]
+        }
+    }
+
+    /// Return the m data of the frame: the encrypted bytes.
+    pub fn m_data<'f>(&self, frame: &'f [u8]) -> &'f [u8] {
+        if self.encrypted {
+            &frame[self.open_len..frame.len() - self.mic_len]
+        } else {
+            &[]
+        }
+    }
+
+    /// Return the MIC of the frame.
+    pub fn mic<'f>(&self, frame: &'f [u8]) -> &'f [u8] {
+        &frame[frame.len() - self.mic_len..]
+    }
+
+    /// Split the frame into its a data, m data and MIC, to apply the CCM*
+    /// transformation in place.
+    pub fn split_mut<'f>(&self, frame: &'f mut [u8]) -> (&'f [u8], &'f mut [u8], &'f mut [u8]) {
+        let (private, mic) = frame.split_at_mut(frame.len() - self.mic_len);
+        if self.encrypted {
+            let (a_data, m_data) = private.split_at_mut(self.open_len);
+            (a_data, m_data, mic)
+        } else {
+            (private, &mut [], mic)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum SecurityAttributes {
-    None,
-    Mic32,
-    Mic64,
-    Mic128,
-    EncMic32,
-    EncMic64,
-    EncMic128,
-    Unknown,
+    None = 0,
+    Mic32 = 1,
+    Mic64 = 2,
+    Mic128 = 3,
+    Unknown = 4,
+    EncMic32 = 5,
+    EncMic64 = 6,
+    EncMic128 = 7,
 }
 
 /// A Key Identifier Mode field.
@@ -126,13 +251,22 @@ impl KeyIdentifierField {
         match self.buffer {
             0 => KeyIdentifierMode::Implicit,
             1 => KeyIdentifierMode::Explicit,
+            2 => KeyIdentifierMode::ExplicitWithShortSource,
+            3 => KeyIdentifierMode::ExplicitWithLongSource,
             _ => KeyIdentifierMode::Unknown,
         }
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum KeyIdentifierMode {
-    Implicit,
-    Explicit,
-    Unknown,
+    /// The key is determined implicitly from the originator and recipient.
+    Implicit = 0,
+    /// The key is determined from the key index.
+    Explicit = 1,
+    /// The key is determined from a 4-octet key source and the key index.
+    ExplicitWithShortSource = 2,
+    /// The key is determined from an 8-octet key source and the key index.
+    ExplicitWithLongSource = 3,
+    Unknown = 4,
 }
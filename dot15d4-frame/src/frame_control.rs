@@ -6,6 +6,7 @@ use super::{Error, Result};
 /// IEEE 802.15.4 frame type.
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum FrameType {
     /// Beacon frame.
     Beacon = 0b000,
@@ -43,6 +44,7 @@ impl From<u8> for FrameType {
 /// IEEE 802.15.4 frame version.
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum FrameVersion {
     /// IEEE 802.15.4-2003 frame version.
     Ieee802154_2003 = 0b00,
@@ -76,7 +78,9 @@ impl<T: AsRef<[u8]>> FrameControl<T> {
     ///
     /// # Errors
     ///
-    /// Returns an error if the buffer is too short.
+    /// Returns an error if the buffer is too short. With the `strict` feature,
+    /// an error is also returned if a field holds a reserved value, see
+    /// [`FrameControl::check_reserved`].
     pub fn new(buffer: T) -> Result<Self> {
         let fc = Self::new_unchecked(buffer);
 
@@ -84,6 +88,11 @@ impl<T: AsRef<[u8]>> FrameControl<T> {
             return Err(Error);
         }
 
+        #[cfg(feature = "strict")]
+        if !fc.check_reserved() {
+            return Err(Error);
+        }
+
         Ok(fc)
     }
 
@@ -93,6 +102,15 @@ impl<T: AsRef<[u8]>> FrameControl<T> {
         self.buffer.as_ref().len() >= 2
     }
 
+    /// Returns `false` if the frame type, the frame version or one of the
+    /// addressing modes holds a reserved value.
+    pub fn check_reserved(&self) -> bool {
+        self.frame_type() != FrameType::Unknown
+            && self.frame_version() != FrameVersion::Unknown
+            && self.dst_addressing_mode() != AddressingMode::Unknown
+            && self.src_addressing_mode() != AddressingMode::Unknown
+    }
+
     /// Create a new [`FrameControl`] reader/writer from a given buffer without
     /// length checking.
     pub fn new_unchecked(buffer: T) -> Self {
@@ -181,7 +199,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> FrameControl<T> {
     pub fn set_security_enabled(&mut self, security_enabled: bool) {
         let b = &mut self.buffer.as_mut()[..2];
         let mut raw = u16::from_le_bytes([b[0], b[1]]);
-        raw |= (security_enabled as u16) << 3;
+        raw = (raw & !(0b1 << 3)) | ((security_enabled as u16) << 3);
         b.copy_from_slice(&raw.to_le_bytes());
     }
 
@@ -189,7 +207,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> FrameControl<T> {
     pub fn set_frame_pending(&mut self, frame_pending: bool) {
         let b = &mut self.buffer.as_mut()[..2];
         let mut raw = u16::from_le_bytes([b[0], b[1]]);
-        raw |= (frame_pending as u16) << 4;
+        raw = (raw & !(0b1 << 4)) | ((frame_pending as u16) << 4);
         b.copy_from_slice(&raw.to_le_bytes());
     }
 
@@ -197,7 +215,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> FrameControl<T> {
     pub fn set_ack_request(&mut self, ack_request: bool) {
         let b = &mut self.buffer.as_mut()[..2];
         let mut raw = u16::from_le_bytes([b[0], b[1]]);
-        raw |= (ack_request as u16) << 5;
+        raw = (raw & !(0b1 << 5)) | ((ack_request as u16) << 5);
         b.copy_from_slice(&raw.to_le_bytes());
     }
 
@@ -205,7 +223,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> FrameControl<T> {
     pub fn set_pan_id_compression(&mut self, pan_id_compression: bool) {
         let b = &mut self.buffer.as_mut()[..2];
         let mut raw = u16::from_le_bytes([b[0], b[1]]);
-        raw |= (pan_id_compression as u16) << 6;
+        raw = (raw & !(0b1 << 6)) | ((pan_id_compression as u16) << 6);
         b.copy_from_slice(&raw.to_le_bytes());
     }
 
@@ -213,7 +231,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> FrameControl<T> {
     pub fn set_sequence_number_suppression(&mut self, sequence_number_suppression: bool) {
         let b = &mut self.buffer.as_mut()[..2];
         let mut raw = u16::from_le_bytes([b[0], b[1]]);
-        raw |= (sequence_number_suppression as u16) << 8;
+        raw = (raw & !(0b1 << 8)) | ((sequence_number_suppression as u16) << 8);
         b.copy_from_slice(&raw.to_le_bytes());
     }
 
@@ -221,7 +239,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> FrameControl<T> {
     pub fn set_information_elements_present(&mut self, information_elements_present: bool) {
         let b = &mut self.buffer.as_mut()[..2];
         let mut raw = u16::from_le_bytes([b[0], b[1]]);
-        raw |= (information_elements_present as u16) << 9;
+        raw = (raw & !(0b1 << 9)) | ((information_elements_present as u16) << 9);
         b.copy_from_slice(&raw.to_le_bytes());
     }
 
@@ -283,6 +301,275 @@ impl<T: AsRef<[u8]>> core::fmt::Display for FrameControl<T> {
     }
 }
 
+/// A reader/writer for the Frame Control field of a Multipurpose frame.
+///
+/// The field is either 1 octet (short form) or 2 octets (long form), as
+/// indicated by the Long Frame Control field. In the short form, the fields
+/// of the second octet are absent and read as `false` or as
+/// [`FrameVersion::Ieee802154_2003`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct MpFrameControl<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+impl<T: AsRef<[u8]>> MpFrameControl<T> {
+    /// Create a new [`MpFrameControl`] reader/writer from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too short, or if the frame type is
+    /// not [`FrameType::Multipurpose`].
+    pub fn new(buffer: T) -> Result<Self> {
+        let fc = Self::new_unchecked(buffer);
+
+        if !fc.check_len() || fc.frame_type() != FrameType::Multipurpose {
+            return Err(Error);
+        }
+
+        #[cfg(feature = "strict")]
+        if !fc.check_reserved() {
+            return Err(Error);
+        }
+
+        Ok(fc)
+    }
+
+    /// Returns `false` if the buffer is too short to contain the Frame Control
+    /// field.
+    fn check_len(&self) -> bool {
+        let b = self.buffer.as_ref();
+        !b.is_empty() && b.len() >= self.len()
+    }
+
+    /// Returns `false` if the frame version or one of the addressing modes
+    /// holds a reserved value.
+    pub fn check_reserved(&self) -> bool {
+        self.frame_version() != FrameVersion::Unknown
+            && self.dst_addressing_mode() != AddressingMode::Unknown
+            && self.src_addressing_mode() != AddressingMode::Unknown
+    }
+
+    /// Create a new [`MpFrameControl`] reader/writer from a given buffer
+    /// without length checking.
+    pub fn new_unchecked(buffer: T) -> Self {
+        Self { buffer }
+    }
+
+    /// Return the inner buffer.
+    pub fn into_inner(self) -> T {
+        self.buffer
+    }
+
+    /// Return the length of the Frame Control field, 1 or 2 octets.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        if self.long_frame_control() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Return the raw field, with the second octet zeroed in the short form.
+    fn raw(&self) -> u16 {
+        let b = self.buffer.as_ref();
+        if self.long_frame_control() {
+            u16::from_le_bytes([b[0], b[1]])
+        } else {
+            b[0] as u16
+        }
+    }
+
+    /// Return the [`FrameType`] field.
+    pub fn frame_type(&self) -> FrameType {
+        FrameType::from(self.buffer.as_ref()[0] & 0b111)
+    }
+
+    /// Returns `true` when the long frame control field is set.
+    pub fn long_frame_control(&self) -> bool {
+        (self.buffer.as_ref()[0] >> 3) & 0b1 == 1
+    }
+
+    /// Return the Destination [`AddressingMode`].
+    pub fn dst_addressing_mode(&self) -> AddressingMode {
+        AddressingMode::from((self.buffer.as_ref()[0] >> 4) & 0b11)
+    }
+
+    /// Return the Source [`AddressingMode`].
+    pub fn src_addressing_mode(&self) -> AddressingMode {
+        AddressingMode::from((self.buffer.as_ref()[0] >> 6) & 0b11)
+    }
+
+    /// Returns `true` when the PAN ID present field is set.
+    pub fn pan_id_present(&self) -> bool {
+        (self.raw() >> 8) & 0b1 == 1
+    }
+
+    /// Returns `true` when the security enabled field is set.
+    pub fn security_enabled(&self) -> bool {
+        (self.raw() >> 9) & 0b1 == 1
+    }
+
+    /// Returns `true` when the sequence number suppression field is set.
+    pub fn sequence_number_suppression(&self) -> bool {
+        (self.raw() >> 10) & 0b1 == 1
+    }
+
+    /// Returns `true` when the frame pending field is set.
+    pub fn frame_pending(&self) -> bool {
+        (self.raw() >> 11) & 0b1 == 1
+    }
+
+    /// Return the [`FrameVersion`].
+    pub fn frame_version(&self) -> FrameVersion {
+        FrameVersion::from(((self.raw() >> 12) & 0b11) as u8)
+    }
+
+    /// Returns `true` when the acknowledgement request field is set.
+    pub fn ack_request(&self) -> bool {
+        (self.raw() >> 14) & 0b1 == 1
+    }
+
+    /// Returns `true` when the information element field is set.
+    pub fn information_elements_present(&self) -> bool {
+        (self.raw() >> 15) & 0b1 == 1
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> MpFrameControl<T> {
+    /// Set a bit field in the first octet.
+    fn set_short_bits(&mut self, shift: u8, mask: u8, value: u8) {
+        let b = &mut self.buffer.as_mut()[0];
+        *b = (*b & !(mask << shift)) | ((value & mask) << shift);
+    }
+
+    /// Set a bit field in the second octet.
+    ///
+    /// ## Panics
+    /// This function panics if the long frame control field is not set.
+    fn set_long_bits(&mut self, shift: u8, mask: u8, value: u8) {
+        assert!(
+            self.long_frame_control(),
+            "only the long Frame Control field has this field"
+        );
+        let b = &mut self.buffer.as_mut()[1];
+        *b = (*b & !(mask << shift)) | ((value & mask) << shift);
+    }
+
+    /// Set the frame type field.
+    pub fn set_frame_type(&mut self, frame_type: FrameType) {
+        self.set_short_bits(0, 0b111, frame_type as u8);
+    }
+
+    /// Set the long frame control field. When set, the buffer must hold 2
+    /// octets.
+    pub fn set_long_frame_control(&mut self, long_frame_control: bool) {
+        self.set_short_bits(3, 0b1, long_frame_control as u8);
+    }
+
+    /// Set the destination addressing mode field.
+    pub fn set_dst_addressing_mode(&mut self, addressing_mode: AddressingMode) {
+        self.set_short_bits(4, 0b11, addressing_mode as u8);
+    }
+
+    /// Set the source addressing mode field.
+    pub fn set_src_addressing_mode(&mut self, addressing_mode: AddressingMode) {
+        self.set_short_bits(6, 0b11, addressing_mode as u8);
+    }
+
+    /// Set the PAN ID present field.
+    ///
+    /// ## Panics
+    /// This function panics if the long frame control field is not set.
+    pub fn set_pan_id_present(&mut self, pan_id_present: bool) {
+        self.set_long_bits(0, 0b1, pan_id_present as u8);
+    }
+
+    /// Set the security enabled field.
+    ///
+    /// ## Panics
+    /// This function panics if the long frame control field is not set.
+    pub fn set_security_enabled(&mut self, security_enabled: bool) {
+        self.set_long_bits(1, 0b1, security_enabled as u8);
+    }
+
+    /// Set the sequence number suppression field.
+    ///
+    /// ## Panics
+    /// This function panics if the long frame control field is not set.
+    pub fn set_sequence_number_suppression(&mut self, sequence_number_suppression: bool) {
+        self.set_long_bits(2, 0b1, sequence_number_suppression as u8);
+    }
+
+    /// Set the frame pending field.
+    ///
+    /// ## Panics
+    /// This function panics if the long frame control field is not set.
+    pub fn set_frame_pending(&mut self, frame_pending: bool) {
+        self.set_long_bits(3, 0b1, frame_pending as u8);
+    }
+
+    /// Set the frame version field.
+    ///
+    /// ## Panics
+    /// This function panics if the long frame control field is not set.
+    pub fn set_frame_version(&mut self, frame_version: FrameVersion) {
+        self.set_long_bits(4, 0b11, frame_version as u8);
+    }
+
+    /// Set the acknowledgement request field.
+    ///
+    /// ## Panics
+    /// This function panics if the long frame control field is not set.
+    pub fn set_ack_request(&mut self, ack_request: bool) {
+        self.set_long_bits(6, 0b1, ack_request as u8);
+    }
+
+    /// Set the information element present field.
+    ///
+    /// ## Panics
+    /// This function panics if the long frame control field is not set.
+    pub fn set_information_elements_present(&mut self, information_elements_present: bool) {
+        self.set_long_bits(7, 0b1, information_elements_present as u8);
+    }
+}
+
+impl<T: AsRef<[u8]>> core::fmt::Display for MpFrameControl<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        writeln!(f, "Multipurpose Frame Control")?;
+        writeln!(f, "  type: {:?}", self.frame_type())?;
+        writeln!(
+            f,
+            "  long frame control: {}",
+            self.long_frame_control() as usize
+        )?;
+        writeln!(f, "  dst addressing mode: {:?}", self.dst_addressing_mode())?;
+        writeln!(f, "  src addressing mode: {:?}", self.src_addressing_mode())?;
+        if self.long_frame_control() {
+            writeln!(f, "  pan id present: {}", self.pan_id_present() as usize)?;
+            writeln!(
+                f,
+                "  security enabled: {}",
+                self.security_enabled() as usize
+            )?;
+            writeln!(
+                f,
+                "  sequence number suppression: {}",
+                self.sequence_number_suppression() as usize
+            )?;
+            writeln!(f, "  frame pending: {}", self.frame_pending() as usize)?;
+            writeln!(f, "  frame version: {:?}", self.frame_version())?;
+            writeln!(f, "  ack request: {}", self.ack_request() as usize)?;
+            writeln!(
+                f,
+                "  information elements present: {}",
+                self.information_elements_present() as usize
+            )?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -353,6 +640,39 @@ mod tests {
         assert_eq!(*fc.into_inner(), [0b0010_1001, 0b1010_1010]);
     }
 
+    #[test]
+    fn clear_fields() {
+        let mut fc = [0b0111_1001, 0b1010_1011];
+        let mut fc = FrameControl::new_unchecked(&mut fc);
+        fc.set_security_enabled(false);
+        fc.set_frame_pending(false);
+        fc.set_ack_request(false);
+        fc.set_pan_id_compression(false);
+        fc.set_sequence_number_suppression(false);
+        fc.set_information_elements_present(false);
+        assert_eq!(*fc.into_inner(), [0b0000_0001, 0b1010_1000]);
+    }
+
+    #[test]
+    fn reserved_fields() {
+        let fc = [0b0010_1001, 0b1010_1010];
+        assert!(FrameControl::new_unchecked(&fc).check_reserved());
+
+        // Reserved frame type
+        let fc = [0b0010_1100, 0b1010_1010];
+        assert!(!FrameControl::new_unchecked(&fc).check_reserved());
+        // Reserved destination addressing mode
+        let fc = [0b0010_1001, 0b1010_0110];
+        assert!(!FrameControl::new_unchecked(&fc).check_reserved());
+        // Reserved source addressing mode
+        let fc = [0b0010_1001, 0b0110_1010];
+        assert!(!FrameControl::new_unchecked(&fc).check_reserved());
+        // Reserved frame version
+        let fc = [0b0010_1001, 0b1011_1010];
+        assert!(!FrameControl::new_unchecked(&fc).check_reserved());
+        assert_eq!(FrameControl::new(&fc).is_err(), cfg!(feature = "strict"));
+    }
+
     #[test]
     fn frame_type() {
         assert_eq!(FrameType::from(0b000), FrameType::Beacon);
@@ -373,6 +693,67 @@ mod tests {
         assert_eq!(FrameVersion::from(0b11), FrameVersion::Unknown);
     }
 
+    #[test]
+    fn multipurpose_short() {
+        // Short form, extended destination and short source address
+        let fc = [0b1011_0101];
+        let fc = MpFrameControl::new(&fc).unwrap();
+        assert_eq!(fc.len(), 1);
+        assert_eq!(fc.frame_type(), FrameType::Multipurpose);
+        assert!(!fc.long_frame_control());
+        assert_eq!(fc.dst_addressing_mode(), AddressingMode::Extended);
+        assert_eq!(fc.src_addressing_mode(), AddressingMode::Short);
+        assert!(!fc.pan_id_present());
+        assert!(!fc.security_enabled());
+        assert!(!fc.ack_request());
+        assert_eq!(fc.frame_version(), FrameVersion::Ieee802154_2003);
+
+        // The long form needs a second octet
+        assert!(MpFrameControl::new(&[0b1011_1101]).is_err());
+        // Not a Multipurpose frame
+        assert!(MpFrameControl::new(&[0b1011_0001]).is_err());
+        assert!(MpFrameControl::new(&[]).is_err());
+    }
+
+    #[test]
+    fn multipurpose_long() {
+        let fc = [0b1011_1101, 0b1101_0011];
+        let fc = MpFrameControl::new(&fc).unwrap();
+        assert_eq!(fc.len(), 2);
+        assert!(fc.long_frame_control());
+        assert!(fc.pan_id_present());
+        assert!(fc.security_enabled());
+        assert!(!fc.sequence_number_suppression());
+        assert!(!fc.frame_pending());
+        assert_eq!(fc.frame_version(), FrameVersion::Ieee802154_2006);
+        assert!(fc.ack_request());
+        assert!(fc.information_elements_present());
+
+        let mut buffer = [0; 2];
+        let mut fc = MpFrameControl::new_unchecked(&mut buffer);
+        fc.set_frame_type(FrameType::Multipurpose);
+        fc.set_long_frame_control(true);
+        fc.set_dst_addressing_mode(AddressingMode::Extended);
+        fc.set_src_addressing_mode(AddressingMode::Short);
+        fc.set_pan_id_present(true);
+        fc.set_security_enabled(true);
+        fc.set_sequence_number_suppression(false);
+        fc.set_frame_pending(false);
+        fc.set_frame_version(FrameVersion::Ieee802154_2006);
+        fc.set_ack_request(true);
+        fc.set_information_elements_present(true);
+        assert_eq!(buffer, [0b1011_1101, 0b1101_0011]);
+
+        assert!(MpFrameControl::new(&buffer[..1]).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn multipurpose_short_has_no_ack_request() {
+        let mut buffer = [0b0000_0101];
+        MpFrameControl::new_unchecked(&mut buffer).set_ack_request(true);
+    }
+
     #[test]
     fn formatting() {
         let fc = [0b0010_1001, 0b1010_1010];
@@ -5,11 +5,13 @@ use crate::{
     FrameVersion, InformationElements,
 };
 
+#[cfg(feature = "imm-ack")]
 /// A reader/writer for an IEEE 802.15.4 Acknowledgment frame.
 pub struct Ack<T: AsRef<[u8]>> {
     buffer: T,
 }
 
+#[cfg(feature = "imm-ack")]
 impl<T: AsRef<[u8]>> Ack<T> {
     /// Create a new [`Ack`] reader/writer from a given buffer.
     pub fn new(buffer: T) -> Result<Self> {
@@ -19,6 +21,11 @@ impl<T: AsRef<[u8]>> Ack<T> {
             return Err(Error);
         }
 
+        #[cfg(feature = "strict")]
+        if !ack.frame_control().check_reserved() {
+            return Err(Error);
+        }
+
         Ok(ack)
     }
 
@@ -49,31 +56,75 @@ impl<T: AsRef<[u8]>> Ack<T> {
     }
 }
 
+#[cfg(all(feature = "display", feature = "imm-ack"))]
+impl<T: AsRef<[u8]>> core::fmt::Display for Ack<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        super::fmt_frame(self.buffer.as_ref(), f)
+    }
+}
+
 /// A reader/writer for an IEEE 802.15.4 Enhanced Acknowledgment frame.
 pub struct EnhancedAck<T: AsRef<[u8]>> {
     buffer: T,
+    /// The offset of the payload, computed once when the frame is checked
+    /// with [`EnhancedAck::new`].
+    payload_offset: Option<usize>,
 }
 
 impl<T: AsRef<[u8]>> EnhancedAck<T> {
     /// Create a new [`EnhancedAck`] reader/writer from a given buffer.
     pub fn new(buffer: T) -> Result<Self> {
-        let ack = Self::new_unchecked(buffer);
+        let mut ack = Self::new_unchecked(buffer);
 
-        //if !ack.check_len() {
-        //return Err(Error);
-        //}
+        if !ack.check_len() {
+            return Err(Error);
+        }
+
+        #[cfg(feature = "strict")]
+        if !ack.frame_control().check_reserved() {
+            return Err(Error);
+        }
+
+        ack.payload_offset = ack.find_payload_offset();
 
         Ok(ack)
     }
 
     /// Returns `false` if the buffer is too short to contain an acknowledgment frame.
     pub fn check_len(&self) -> bool {
-        todo!();
+        let buffer = self.buffer.as_ref();
+
+        if buffer.len() < 2 || buffer.len() > 127 {
+            return false;
+        }
+
+        if !self.frame_control().sequence_number_suppression() && buffer.len() < 3 {
+            return false;
+        }
+
+        if self.addressing().is_none() {
+            return false;
+        }
+
+        // A secured Enhanced Acknowledgment must contain the complete
+        // Auxiliary Security Header.
+        if self.frame_control().security_enabled()
+            && !self
+                .auxiliary_security_header()
+                .is_some_and(|aux| aux.check_len())
+        {
+            return false;
+        }
+
+        true
     }
 
     /// Create a new [`EnhancedAck`] reader/writer from a given buffer without length checking.
     pub fn new_unchecked(buffer: T) -> Self {
-        Self { buffer }
+        Self {
+            buffer,
+            payload_offset: None,
+        }
     }
 
     /// Returns a [`FrameControl`] reader.
@@ -113,7 +164,7 @@ impl<T: AsRef<[u8]>> EnhancedAck<T> {
 
         if self.frame_control().security_enabled() {
             Some(AuxiliarySecurityHeader::new(
-                &self.buffer.as_ref()[offset..],
+                self.buffer.as_ref().get(offset..)?,
             ))
         } else {
             None
@@ -122,6 +173,18 @@ impl<T: AsRef<[u8]>> EnhancedAck<T> {
 
     /// Returns an [`InformationElements`] reader.
     pub fn information_elements(&self) -> Option<InformationElements<&'_ [u8]>> {
+        let offset = self.information_elements_offset();
+
+        if self.frame_control().information_elements_present() {
+            InformationElements::new(self.buffer.as_ref().get(offset..)?).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the offset of the Information Elements, which directly follow
+    /// the Auxiliary Security Header.
+    fn information_elements_offset(&self) -> usize {
         let mut offset = 2;
 
         if !self.frame_control().sequence_number_suppression() {
@@ -138,41 +201,59 @@ impl<T: AsRef<[u8]>> EnhancedAck<T> {
             }
         }
 
+        offset
+    }
+
+    /// Returns the offset of the payload, which directly follows the
+    /// Information Elements.
+    fn payload_offset(&self) -> Option<usize> {
+        self.payload_offset.or_else(|| self.find_payload_offset())
+    }
+
+    /// Walks the Information Elements to find the offset of the payload.
+    fn find_payload_offset(&self) -> Option<usize> {
+        let mut offset = self.information_elements_offset();
+
         if self.frame_control().information_elements_present() {
-            Some(InformationElements::new(&self.buffer.as_ref()[offset..]).unwrap())
-        } else {
-            None
+            offset += InformationElements::new(&self.buffer.as_ref()[offset..])
+                .ok()?
+                .len();
         }
+
+        Some(offset)
     }
 }
 
 impl<T: AsRef<[u8]> + ?Sized> EnhancedAck<&T> {
     /// Returns the payload of the frame.
     pub fn payload(&self) -> Option<&'_ [u8]> {
-        let fc = self.frame_control();
-
-        let mut offset = 2;
-
-        if !fc.sequence_number_suppression() {
-            offset += 1;
-        }
+        Some(&self.buffer.as_ref()[self.payload_offset()?..])
+    }
+}
 
-        if let Some(af) = self.addressing() {
-            offset += af.len();
-        }
+impl<T: AsRef<[u8]> + AsMut<[u8]>> EnhancedAck<T> {
+    /// Returns an [`InformationElements`] reader/writer.
+    pub fn information_elements_mut(&mut self) -> Option<InformationElements<&'_ mut [u8]>> {
+        self.payload_offset = None;
+        let offset = self.information_elements_offset();
 
-        if fc.security_enabled() {
-            if let Some(ash) = self.auxiliary_security_header() {
-                offset += ash.len();
-            }
+        if self.frame_control().information_elements_present() {
+            InformationElements::new(self.buffer.as_mut().get_mut(offset..)?).ok()
+        } else {
+            None
         }
+    }
 
-        if fc.information_elements_present() {
-            if let Some(ie) = self.information_elements() {
-                offset += ie.len();
-            }
-        }
+    /// Returns a mutable reference to the payload of the frame.
+    pub fn payload_mut(&mut self) -> Option<&'_ mut [u8]> {
+        let offset = self.payload_offset()?;
+        Some(&mut self.buffer.as_mut()[offset..])
+    }
+}
 
-        Some(&self.buffer.as_ref()[offset..])
+#[cfg(feature = "display")]
+impl<T: AsRef<[u8]>> core::fmt::Display for EnhancedAck<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        super::fmt_frame(self.buffer.as_ref(), f)
     }
 }
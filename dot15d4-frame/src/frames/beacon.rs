@@ -10,20 +10,62 @@ use crate::{
     FrameVersion, InformationElements,
 };
 
+#[cfg(feature = "legacy-beacons")]
 /// A reader/writer for an IEEE 802.15.4 Beacon frame.
 pub struct Beacon<T: AsRef<[u8]>> {
     buffer: T,
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl<T: AsRef<[u8]>> Beacon<T> {
-    #[allow(unused)]
     pub fn new(buffer: T) -> Result<Self> {
-        todo!();
+        let b = Self::new_unchecked(buffer);
+
+        if !b.check_len() {
+            return Err(Error);
+        }
+
+        #[cfg(feature = "strict")]
+        if !b.frame_control().check_reserved() {
+            return Err(Error);
+        }
+
+        Ok(b)
     }
 
-    #[allow(unused)]
     fn check_len(&self) -> bool {
-        todo!();
+        let buffer = self.buffer.as_ref();
+
+        if buffer.len() < 3 {
+            return false;
+        }
+
+        let Ok(addressing) = AddressingFields::new(&buffer[3..], self.frame_control()) else {
+            return false;
+        };
+
+        let mut offset = 3 + addressing.len();
+
+        if self.frame_control().security_enabled() {
+            if buffer.len() <= offset {
+                return false;
+            }
+            offset += AuxiliarySecurityHeader::new(&buffer[offset..]).len();
+        }
+
+        offset += 2; // Superframe specification
+
+        if buffer.len() <= offset {
+            return false;
+        }
+        offset += GtsInfo::new_unchecked(&buffer[offset..]).length();
+
+        if buffer.len() <= offset {
+            return false;
+        }
+        offset += PendingAddress::new_unchecked(&buffer[offset..]).length();
+
+        buffer.len() >= offset
     }
 
     pub fn new_unchecked(buffer: T) -> Self {
@@ -97,11 +139,10 @@ impl<T: AsRef<[u8]>> Beacon<T> {
 
         PendingAddress::new_unchecked(&self.buffer.as_ref()[offset..])
     }
-}
 
-impl<'f, T: AsRef<[u8]> + ?Sized> Beacon<&'f T> {
-    /// Return the payload of the frame.
-    pub fn payload(&self) -> Option<&'f [u8]> {
+    /// Return the offset of the payload, which directly follows the pending
+    /// address fields.
+    fn payload_offset(&self) -> usize {
         let mut offset = 3;
         offset += self.addressing().len();
 
@@ -113,11 +154,35 @@ impl<'f, T: AsRef<[u8]> + ?Sized> Beacon<&'f T> {
         offset += self.gts_info().length();
         offset += self.pending_address().length();
 
-        Some(&self.buffer.as_ref()[offset..])
+        offset
     }
 }
 
-#[frame]
+#[cfg(feature = "legacy-beacons")]
+impl<'f, T: AsRef<[u8]> + ?Sized> Beacon<&'f T> {
+    /// Return the payload of the frame.
+    pub fn payload(&self) -> Option<&'f [u8]> {
+        Some(&self.buffer.as_ref()[self.payload_offset()..])
+    }
+}
+
+#[cfg(feature = "legacy-beacons")]
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Beacon<T> {
+    /// Return a mutable reference to the payload of the frame.
+    pub fn payload_mut(&mut self) -> Option<&'_ mut [u8]> {
+        let offset = self.payload_offset();
+        Some(&mut self.buffer.as_mut()[offset..])
+    }
+}
+
+#[cfg(all(feature = "display", feature = "legacy-beacons"))]
+impl<T: AsRef<[u8]>> core::fmt::Display for Beacon<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        super::fmt_frame(self.buffer.as_ref(), f)
+    }
+}
+
+#[frame(repr)]
 #[derive(Debug)]
 /// A reader/writer for the IEEE 802.15.4 Superframe Specification Header
 /// Information Element.
@@ -146,7 +211,37 @@ pub struct SuperframeSpecification {
     association_permit: bool,
 }
 
+impl core::fmt::Display for SuperframeSpecificationRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+
+        writeln!(f, "{:indent$}beacon order: {:?}", "", self.beacon_order)?;
+        writeln!(
+            f,
+            "{:indent$}superframe order: {:?}",
+            "", self.superframe_order
+        )?;
+        writeln!(f, "{:indent$}final cap slot: {}", "", self.final_cap_slot)?;
+        writeln!(
+            f,
+            "{:indent$}battery life extension: {}",
+            "", self.battery_life_extension as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}pan coordinator: {}",
+            "", self.pan_coordinator as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}association permit: {}",
+            "", self.association_permit as usize
+        )
+    }
+}
+
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(u8)]
 /// Indicates the frequency at which the beacon is transmitted.
 pub enum BeaconOrder {
@@ -175,6 +270,7 @@ impl From<BeaconOrder> for u8 {
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[repr(u8)]
 ///  The length of the active portion of the superframe.
 pub enum SuperframeOrder {
@@ -202,6 +298,7 @@ impl From<SuperframeOrder> for u8 {
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 #[frame]
 /// A reader/writer for the IEEE 802.15.4 GTS Info field.
 pub struct GtsInfo {
@@ -212,6 +309,7 @@ pub struct GtsInfo {
     gts_direction: u8,
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl<T: AsRef<[u8]>> GtsInfo<T> {
     pub fn length(&self) -> usize {
         // TODO: check auto-generated code
@@ -219,6 +317,7 @@ impl<T: AsRef<[u8]>> GtsInfo<T> {
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 /// Guaranteed Timeslot Descriptor
 #[frame(no_constructor)]
 #[derive(Debug, PartialEq, Eq)]
@@ -239,6 +338,7 @@ pub struct GtsSlot {
     direction: GtsDirection,
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl<T: AsRef<[u8]>> GtsSlot<T> {
     /// Create a new [`#name`] reader/writer from a given buffer.
     pub fn new(buffer: T, direction: GtsDirection) -> Result<Self> {
@@ -263,6 +363,7 @@ impl<T: AsRef<[u8]>> GtsSlot<T> {
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl<T: AsRef<[u8]>> GtsSpecification<T> {
     /// Return a [`GtsSlotIterator`].
     pub fn slots(&self) -> GtsSlotIterator {
@@ -283,6 +384,7 @@ impl<T: AsRef<[u8]>> GtsSpecification<T> {
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 /// An [`Iterator`] over GTS slots.
 pub struct GtsSlotIterator<'f> {
     data: &'f [u8],
@@ -290,6 +392,7 @@ pub struct GtsSlotIterator<'f> {
     terminated: bool,
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl<'f> Iterator for GtsSlotIterator<'f> {
     type Item = GtsSlot<&'f [u8]>;
 
@@ -315,6 +418,7 @@ impl<'f> Iterator for GtsSlotIterator<'f> {
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 #[frame]
 #[derive(Debug)]
 /// Guaranteed Time Slot specification.
@@ -329,6 +433,7 @@ pub struct GtsSpecification {
     gts_permit: bool,
 }
 
+#[cfg(feature = "legacy-beacons")]
 /// GTS direciton.
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 #[repr(u8)]
@@ -339,6 +444,7 @@ pub enum GtsDirection {
     Transmit,
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl From<u8> for GtsDirection {
     fn from(value: u8) -> Self {
         match value {
@@ -348,6 +454,7 @@ impl From<u8> for GtsDirection {
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl From<GtsDirection> for u8 {
     fn from(value: GtsDirection) -> Self {
         match value {
@@ -357,12 +464,14 @@ impl From<GtsDirection> for u8 {
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 #[frame]
 pub struct PendingAddress {
     #[bits(8)]
     pending_address_spec: PendingAddressSpecification,
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl<T: AsRef<[u8]>> PendingAddress<T> {
     pub fn length(&self) -> usize {
         let spec = self.pending_address_spec().unwrap();
@@ -379,6 +488,7 @@ impl<T: AsRef<[u8]>> PendingAddress<T> {
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 #[frame]
 pub struct PendingAddressSpecification {
     #[bits(3)]
@@ -391,6 +501,7 @@ pub struct PendingAddressSpecification {
     _reserved: u8,
 }
 
+#[cfg(feature = "legacy-beacons")]
 pub struct PendingAddressIterator<'f> {
     data: &'f [u8],
     short_addresses: u8,
@@ -398,6 +509,7 @@ pub struct PendingAddressIterator<'f> {
     terminated: bool,
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl<'f> PendingAddressIterator<'f> {
     pub fn new(data: &'f [u8], short_addresses: u8, extended_addresses: u8) -> Self {
         Self {
@@ -409,6 +521,7 @@ impl<'f> PendingAddressIterator<'f> {
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl Iterator for PendingAddressIterator<'_> {
     type Item = Address;
 
@@ -450,11 +563,14 @@ impl Iterator for PendingAddressIterator<'_> {
 /// A reader/writer for an IEEE 802.15.4 Enhanced Beacon frame.
 pub struct EnhancedBeacon<T: AsRef<[u8]>> {
     buffer: T,
+    /// The offset of the payload, computed once when the frame is checked
+    /// with [`EnhancedBeacon::new`].
+    payload_offset: Option<usize>,
 }
 
 impl<T: AsRef<[u8]>> EnhancedBeacon<T> {
     pub fn new(buffer: T) -> Result<Self> {
-        let b = Self::new_unchecked(buffer);
+        let mut b = Self::new_unchecked(buffer);
 
         if !b.check_len() {
             return Err(Error);
@@ -466,21 +582,11 @@ impl<T: AsRef<[u8]>> EnhancedBeacon<T> {
             return Err(Error);
         }
 
-        if fc.frame_type() == FrameType::Unknown {
+        if !fc.check_reserved() {
             return Err(Error);
         }
 
-        if fc.frame_version() == FrameVersion::Unknown {
-            return Err(Error);
-        }
-
-        if fc.dst_addressing_mode() == AddressingMode::Unknown {
-            return Err(Error);
-        }
-
-        if fc.src_addressing_mode() == AddressingMode::Unknown {
-            return Err(Error);
-        }
+        b.payload_offset = b.find_payload_offset();
 
         Ok(b)
     }
@@ -502,7 +608,10 @@ impl<T: AsRef<[u8]>> EnhancedBeacon<T> {
     }
 
     pub fn new_unchecked(buffer: T) -> Self {
-        Self { buffer }
+        Self {
+            buffer,
+            payload_offset: None,
+        }
     }
 
     /// Return a [`FrameControl`] reader.
@@ -552,52 +661,98 @@ impl<T: AsRef<[u8]>> EnhancedBeacon<T> {
 
     /// Return an [`InformationElements`] reader.
     pub fn information_elements(&self) -> Option<InformationElements<&'_ [u8]>> {
-        let fc = self.frame_control();
-        if fc.information_elements_present() {
-            let mut offset = 2;
-            offset += !fc.sequence_number_suppression() as usize;
-
-            if let Some(af) = self.addressing() {
-                offset += af.len();
-            }
-
+        if self.frame_control().information_elements_present() {
+            let offset = self.information_elements_offset()?;
             Some(InformationElements::new(&self.buffer.as_ref()[offset..]).ok()?)
         } else {
             None
         }
     }
-}
 
-impl<'f, T: AsRef<[u8]> + ?Sized> EnhancedBeacon<&'f T> {
-    /// Return the payload of the frame.
-    pub fn payload(&self) -> Option<&'f [u8]> {
+    /// Return the offset of the Information Elements, which directly follow
+    /// the Auxiliary Security Header.
+    fn information_elements_offset(&self) -> Option<usize> {
         let fc = self.frame_control();
-
-        let mut offset = 0;
-        offset += 2;
-
-        if !fc.sequence_number_suppression() {
-            offset += 1;
-        }
+        let mut offset = 2;
+        offset += !fc.sequence_number_suppression() as usize;
 
         if let Some(af) = self.addressing() {
             offset += af.len();
         }
 
         if fc.security_enabled() {
-            offset += self.auxiliary_security_header().unwrap().len();
+            offset += self.auxiliary_security_header()?.len();
         }
 
-        if fc.information_elements_present() {
-            if let Some(ie) = self.information_elements() {
-                offset += ie.len();
-            }
+        Some(offset)
+    }
+
+    /// Return the offset of the payload, which directly follows the
+    /// Information Elements.
+    fn payload_offset(&self) -> Option<usize> {
+        self.payload_offset.or_else(|| self.find_payload_offset())
+    }
+
+    /// Walk the Information Elements to find the offset of the payload.
+    fn find_payload_offset(&self) -> Option<usize> {
+        let mut offset = self.information_elements_offset()?;
+
+        if let Some(ie) = self.information_elements() {
+            offset += ie.len();
         }
 
+        Some(offset)
+    }
+}
+
+impl<'f, T: AsRef<[u8]> + ?Sized> EnhancedBeacon<&'f T> {
+    /// Return the payload of the frame.
+    pub fn payload(&self) -> Option<&'f [u8]> {
+        let offset = self.payload_offset()?;
+
         if self.buffer.as_ref().len() <= offset {
             return None;
         }
 
         Some(&self.buffer.as_ref()[offset..])
     }
+
+    /// Parse the frame, including its Information Elements, into a
+    /// [`FrameRepr`], e.g. to change some fields and emit it again.
+    ///
+    /// [`FrameRepr`]: crate::FrameRepr
+    pub fn to_repr(&self) -> Result<crate::FrameRepr<'f>> {
+        crate::FrameRepr::parse(&crate::DataFrame::new_unchecked(self.buffer.as_ref()))
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> EnhancedBeacon<T> {
+    /// Return an [`InformationElements`] reader/writer.
+    pub fn information_elements_mut(&mut self) -> Option<InformationElements<&'_ mut [u8]>> {
+        self.payload_offset = None;
+        if self.frame_control().information_elements_present() {
+            let offset = self.information_elements_offset()?;
+            InformationElements::new(&mut self.buffer.as_mut()[offset..]).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Return a mutable reference to the payload of the frame.
+    pub fn payload_mut(&mut self) -> Option<&'_ mut [u8]> {
+        let offset = self.payload_offset()?;
+
+        if self.buffer.as_ref().len() <= offset {
+            return None;
+        }
+
+        Some(&mut self.buffer.as_mut()[offset..])
+    }
+}
+
+#[cfg(feature = "display")]
+impl<T: AsRef<[u8]>> core::fmt::Display for EnhancedBeacon<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        super::fmt_frame(self.buffer.as_ref(), f)
+    }
 }
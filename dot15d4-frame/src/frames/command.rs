@@ -0,0 +1,44 @@
+//! IEEE 802.15.4 MAC command frames.
+
+/// IEEE 802.15.4 MAC command identifier, the first octet of the payload of a
+/// MAC command frame.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum CommandId {
+    /// Association Request command.
+    AssociationRequest = 0x01,
+    /// Association Response command.
+    AssociationResponse = 0x02,
+    /// Disassociation Notification command.
+    DisassociationNotification = 0x03,
+    /// Data Request command.
+    DataRequest = 0x04,
+    /// PAN ID Conflict Notification command.
+    PanIdConflictNotification = 0x05,
+    /// Orphan Notification command.
+    OrphanNotification = 0x06,
+    /// Beacon Request command.
+    BeaconRequest = 0x07,
+    /// Coordinator Realignment command.
+    CoordinatorRealignment = 0x08,
+    /// GTS Request command.
+    GtsRequest = 0x09,
+    /// Unknown command.
+    Unknown,
+}
+
+impl From<u8> for CommandId {
+    fn from(value: u8) -> Self {
+        match value {
+            0x01 => Self::AssociationRequest,
+            0x02 => Self::AssociationResponse,
+            0x03 => Self::DisassociationNotification,
+            0x04 => Self::DataRequest,
+            0x05 => Self::PanIdConflictNotification,
+            0x06 => Self::OrphanNotification,
+            0x07 => Self::BeaconRequest,
+            0x08 => Self::CoordinatorRealignment,
+            0x09 => Self::GtsRequest,
+            _ => Self::Unknown,
+        }
+    }
+}
@@ -1,15 +1,25 @@
-use crate::{Error, Result};
+use core::ops::Range;
 
+use crate::Result;
+
+use crate::{
+    ccm_nonce, AddressingFields, AddressingMode, AuxiliarySecurityHeader, CcmParts, CommandId,
+    FrameControl, FrameField, FrameType, FrameVersion, HeaderElementId, HeaderInformationElement,
+    HeaderInformationElementsIterator, InformationElements, ParseError, ParseErrorKind,
+    PayloadGroupId, PayloadInformationElement,
+};
 use crate::{
-    AddressingFields, AddressingMode, AuxiliarySecurityHeader, FrameControl, FrameType,
-    FrameVersion, InformationElements,
+    AddressingFieldsRepr, AuxiliarySecurityHeaderRepr, FrameControlRepr, InformationElementsRepr,
 };
-use crate::{AddressingFieldsRepr, FrameControlRepr, InformationElementsRepr};
 
 /// A reader/writer for an IEEE 802.15.4 Data frame.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct DataFrame<T: AsRef<[u8]>> {
     buffer: T,
+    /// The offset of the payload, computed once when the frame is checked
+    /// with [`DataFrame::new`], such that the Information Elements are not
+    /// walked again for every access to the payload.
+    payload_offset: Option<usize>,
 }
 
 impl<T: AsRef<[u8]>> DataFrame<T> {
@@ -17,60 +27,190 @@ impl<T: AsRef<[u8]>> DataFrame<T> {
     ///
     /// # Errors
     ///
-    /// Returns an error if the buffer is too short to contain a valid frame.
-    pub fn new(buffer: T) -> Result<Self> {
-        let b = Self::new_unchecked(buffer);
+    /// Returns the first field that is malformed, see
+    /// [`DataFrame::check_fields`].
+    pub fn new(buffer: T) -> core::result::Result<Self, ParseError> {
+        let mut b = Self::new_unchecked(buffer);
+        let payload_offset = b.check_fields(|_, _| {})?;
+
+        // The Payload IEs of a secured frame are only read once the frame is
+        // unsecured in place
+        b.payload_offset = if b.frame_control().security_enabled() {
+            b.find_payload_offset()
+        } else {
+            Some(payload_offset)
+        };
 
-        if !b.check_len() {
-            return Err(Error);
-        }
+        Ok(b)
+    }
 
-        let fc = b.frame_control();
+    /// Returns `false` if the frame is malformed, see
+    /// [`DataFrame::check_fields`].
+    pub fn check_len(&self) -> bool {
+        self.check_fields(|_, _| {}).is_ok()
+    }
 
-        if fc.security_enabled() {
-            return Err(Error);
+    /// Check all fields of the frame, without FCS, calling `on_field` with
+    /// the byte range of every field that is present, in order. Every
+    /// Information Element is reported separately. The Payload IEs of a
+    /// secured frame are encrypted, and thus part of the payload.
+    ///
+    /// Returns the offset of the payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first field that is malformed. The fields in front of it
+    /// are reported before returning.
+    pub fn check_fields(
+        &self,
+        mut on_field: impl FnMut(FrameField, Range<usize>),
+    ) -> core::result::Result<usize, ParseError> {
+        let buffer = self.buffer.as_ref();
+        let len = buffer.len();
+        let error = |field, kind, offset| {
+            Err(ParseError {
+                field,
+                kind,
+                offset,
+            })
+        };
+
+        // Frame Control
+        if len < 2 {
+            return error(FrameField::FrameControl, ParseErrorKind::Truncated, 0);
         }
-
-        if fc.frame_type() == FrameType::Unknown {
-            return Err(Error);
+        if len > 127 {
+            return error(FrameField::Payload, ParseErrorKind::TooLong, 127);
         }
-
-        if fc.frame_version() == FrameVersion::Unknown {
-            return Err(Error);
+        let fc = self.frame_control();
+        if !fc.check_reserved() {
+            return error(FrameField::FrameControl, ParseErrorKind::Reserved, 0);
+        }
+        if !matches!(
+            fc.frame_type(),
+            FrameType::Beacon | FrameType::Data | FrameType::Ack | FrameType::MacCommand
+        ) {
+            return error(FrameField::FrameControl, ParseErrorKind::Unsupported, 0);
         }
+        #[cfg(not(feature = "legacy-frames"))]
+        if fc.frame_version() != FrameVersion::Ieee802154_2020 {
+            return error(FrameField::FrameControl, ParseErrorKind::Unsupported, 0);
+        }
+        on_field(FrameField::FrameControl, 0..2);
+        let mut offset = 2;
 
-        if fc.dst_addressing_mode() == AddressingMode::Unknown {
-            return Err(Error);
+        // Sequence Number
+        if !fc.sequence_number_suppression() {
+            if len < 3 {
+                return error(
+                    FrameField::SequenceNumber,
+                    ParseErrorKind::Truncated,
+                    offset,
+                );
+            }
+            on_field(FrameField::SequenceNumber, 2..3);
+            offset = 3;
         }
 
-        if fc.src_addressing_mode() == AddressingMode::Unknown {
-            return Err(Error);
+        // Addressing, absent in frames before IEEE 802.15.4-2015 without
+        // addresses, such as the Imm-Ack
+        let without_addresses = fc.frame_version() != FrameVersion::Ieee802154_2020
+            && fc.dst_addressing_mode() == AddressingMode::Absent
+            && fc.src_addressing_mode() == AddressingMode::Absent;
+        if !without_addresses {
+            let addressing = AddressingFields::new_unchecked(&buffer[offset..], fc);
+            if addressing.offsets().is_none() {
+                return error(
+                    FrameField::Addressing,
+                    ParseErrorKind::InvalidAddressing,
+                    offset,
+                );
+            }
+            let end = offset + addressing.len();
+            if end > len {
+                return error(FrameField::Addressing, ParseErrorKind::Truncated, offset);
+            }
+            if end > offset {
+                on_field(FrameField::Addressing, offset..end);
+            }
+            offset = end;
         }
 
-        Ok(b)
-    }
+        // Auxiliary Security Header
+        if fc.security_enabled() {
+            let aux = AuxiliarySecurityHeader::new(&buffer[offset..]);
+            if !aux.check_len() {
+                return error(
+                    FrameField::AuxiliarySecurityHeader,
+                    ParseErrorKind::Truncated,
+                    offset,
+                );
+            }
+            on_field(
+                FrameField::AuxiliarySecurityHeader,
+                offset..offset + aux.len(),
+            );
+            offset += aux.len();
+        }
 
-    /// Returns `false` if the buffer is too short to contain a valid frame.
-    pub fn check_len(&self) -> bool {
-        let buffer = self.buffer.as_ref();
+        // Information Elements
+        if fc.information_elements_present() {
+            let mut payload_ies = false;
+            while offset < len {
+                let Ok(ie) = HeaderInformationElement::new(&buffer[offset..]) else {
+                    return error(
+                        FrameField::HeaderInformationElement,
+                        ParseErrorKind::Truncated,
+                        offset,
+                    );
+                };
+                let end = offset + 2 + ie.len();
+                on_field(FrameField::HeaderInformationElement, offset..end);
+                offset = end;
+
+                match ie.element_id() {
+                    HeaderElementId::HeaderTermination1 => {
+                        payload_ies = !fc.security_enabled();
+                        break;
+                    }
+                    HeaderElementId::HeaderTermination2 => break,
+                    _ => (),
+                }
+            }
 
-        if buffer.len() < 2 || buffer.len() > 127 {
-            return false;
+            while payload_ies && offset < len {
+                let Ok(ie) = PayloadInformationElement::new(&buffer[offset..]) else {
+                    return error(
+                        FrameField::PayloadInformationElement,
+                        ParseErrorKind::Truncated,
+                        offset,
+                    );
+                };
+                let end = offset + 2 + ie.length();
+                on_field(FrameField::PayloadInformationElement, offset..end);
+                offset = end;
+
+                if ie.group_id() == PayloadGroupId::PayloadTermination {
+                    break;
+                }
+            }
         }
 
-        let fc = self.frame_control();
-
-        if !fc.sequence_number_suppression() && buffer.len() < 3 {
-            return false;
+        // Payload
+        if offset < len {
+            on_field(FrameField::Payload, offset..len);
         }
 
-        true
+        Ok(offset)
     }
 
     /// Create a new [`DataFrame`] reader/writer from a given buffer without length
     /// checking.
     pub fn new_unchecked(buffer: T) -> Self {
-        Self { buffer }
+        Self {
+            buffer,
+            payload_offset: None,
+        }
     }
 
     /// Return a [`FrameControl`] reader.
@@ -112,7 +252,7 @@ impl<T: AsRef<[u8]>> DataFrame<T> {
             }
 
             Some(AuxiliarySecurityHeader::new(
-                &self.buffer.as_ref()[offset..],
+                self.buffer.as_ref().get(offset..)?,
             ))
         } else {
             None
@@ -121,60 +261,153 @@ impl<T: AsRef<[u8]>> DataFrame<T> {
 
     /// Return an [`InformationElements`] reader.
     pub fn information_elements(&self) -> Option<InformationElements<&'_ [u8]>> {
-        let fc = self.frame_control();
-        if fc.information_elements_present() {
-            let mut offset = 2;
-            offset += !fc.sequence_number_suppression() as usize;
-
-            if let Some(af) = self.addressing() {
-                offset += af.len();
-            }
-
+        if self.frame_control().information_elements_present() {
+            let offset = self.information_elements_offset()?;
             Some(InformationElements::new(&self.buffer.as_ref()[offset..]).ok()?)
         } else {
             None
         }
     }
-}
 
-impl<'f, T: AsRef<[u8]> + ?Sized> DataFrame<&'f T> {
-    /// Return the payload of the frame.
-    pub fn payload(&self) -> Option<&'f [u8]> {
+    /// Return the offset of the Information Elements, which directly follow
+    /// the Auxiliary Security Header.
+    fn information_elements_offset(&self) -> Option<usize> {
         let fc = self.frame_control();
-
-        let mut offset = 0;
-        offset += 2;
-
-        if !fc.sequence_number_suppression() {
-            offset += 1;
-        }
+        let mut offset = 2;
+        offset += !fc.sequence_number_suppression() as usize;
 
         if let Some(af) = self.addressing() {
             offset += af.len();
         }
 
         if fc.security_enabled() {
-            offset += self.auxiliary_security_header().unwrap().len();
+            offset += self.auxiliary_security_header()?.len();
+        }
+
+        Some(offset)
+    }
+
+    /// Return the offset of the payload, which directly follows the
+    /// Information Elements.
+    fn payload_offset(&self) -> Option<usize> {
+        self.payload_offset.or_else(|| self.find_payload_offset())
+    }
+
+    /// Walk the Information Elements to find the offset of the payload.
+    fn find_payload_offset(&self) -> Option<usize> {
+        let mut offset = self.information_elements_offset()?;
+
+        if let Some(ie) = self.information_elements() {
+            offset += ie.len();
+        }
+
+        Some(offset)
+    }
+}
+
+impl<T: AsRef<[u8]>> DataFrame<T> {
+    /// Return the inputs of the CCM* transformation of a secured frame. The
+    /// buffer should end with the MIC, without the FCS. The extended address
+    /// of the originator is given, as the frame might only carry its short
+    /// address.
+    ///
+    /// Returns `None` when the frame is not secured, when the frame counter
+    /// is suppressed, or when the frame is too short.
+    pub fn ccm_parts(&self, src_extended_address: &[u8; 8]) -> Option<CcmParts> {
+        let fc = self.frame_control();
+        let aux = self.auxiliary_security_header()?;
+        if !aux.check_len() {
+            return None;
+        }
+        let level = aux.security_control().security_level();
+        let frame_counter = aux.frame_counter()?;
+
+        let mut open_len = 2 + !fc.sequence_number_suppression() as usize;
+        open_len += self.addressing().map(|af| af.len()).unwrap_or(0);
+        open_len += aux.len();
+
+        let mic_len = level.mic_length();
+        let private_end = self.buffer.as_ref().len().checked_sub(mic_len)?;
+        if private_end < open_len {
+            return None;
         }
 
+        // The Header IEs are not encrypted, and are never followed by the MIC
         if fc.information_elements_present() {
-            if let Some(ie) = self.information_elements() {
-                offset += ie.len();
-            }
+            let mut headers = HeaderInformationElementsIterator {
+                data: &self.buffer.as_ref()[open_len..private_end],
+                offset: 0,
+                terminated: open_len == private_end,
+            };
+            while headers.next().is_some() {}
+            open_len += headers.offset();
+        }
+
+        // The Command ID of a MAC command frame is not encrypted either, when
+        // no Payload IEs precede it
+        if fc.frame_type() == FrameType::MacCommand && self.payload_offset() == Some(open_len) {
+            open_len += 1;
+        }
+        if private_end < open_len {
+            return None;
+        }
+
+        Some(CcmParts {
+            nonce: ccm_nonce(
+                src_extended_address,
+                frame_counter,
+                level.security_attributes(),
+            ),
+            open_len,
+            mic_len,
+            encrypted: level.data_confidentiality(),
+        })
+    }
+
+    /// Return the command identifier of a MAC command frame, the first octet
+    /// of its payload.
+    ///
+    /// Returns `None` when the frame is not a MAC command frame, or when it
+    /// has no payload.
+    pub fn command_id(&self) -> Option<CommandId> {
+        if self.frame_control().frame_type() != FrameType::MacCommand {
+            return None;
         }
 
+        let offset = self.payload_offset()?;
+        self.buffer
+            .as_ref()
+            .get(offset)
+            .map(|id| CommandId::from(*id))
+    }
+}
+
+impl<'f, T: AsRef<[u8]> + ?Sized> DataFrame<&'f T> {
+    /// Return the payload of the frame.
+    pub fn payload(&self) -> Option<&'f [u8]> {
+        let offset = self.payload_offset()?;
+
         if self.buffer.as_ref().len() <= offset {
             return None;
         }
 
         Some(&self.buffer.as_ref()[offset..])
     }
+
+    /// Parse the frame into a [`FrameRepr`], e.g. to change some fields and
+    /// emit it again.
+    ///
+    /// [`FrameRepr`]: crate::FrameRepr
+    pub fn to_repr(&self) -> Result<crate::FrameRepr<'f>> {
+        crate::FrameRepr::parse(&DataFrame::new_unchecked(self.buffer.as_ref()))
+    }
 }
 
 impl<T: AsRef<[u8]> + AsMut<[u8]>> DataFrame<T> {
     /// Set the Frame Control field values in the buffer, based on the given
     /// [`FrameControlRepr`].
     pub fn set_frame_control(&mut self, fc: &FrameControlRepr) {
+        self.payload_offset = None;
         let mut w = FrameControl::new_unchecked(&mut self.buffer.as_mut()[..2]);
         w.set_frame_type(fc.frame_type);
         w.set_security_enabled(fc.security_enabled);
@@ -190,11 +423,35 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> DataFrame<T> {
 
     /// Get a mutable reference to the Frame Control fields
     pub fn frame_control_mut(&mut self) -> FrameControl<&'_ mut [u8]> {
+        self.payload_offset = None;
         FrameControl::new_unchecked(&mut self.buffer.as_mut()[..2])
     }
 
+    /// Return an [`InformationElements`] reader/writer.
+    pub fn information_elements_mut(&mut self) -> Option<InformationElements<&'_ mut [u8]>> {
+        self.payload_offset = None;
+        if self.frame_control().information_elements_present() {
+            let offset = self.information_elements_offset()?;
+            InformationElements::new(&mut self.buffer.as_mut()[offset..]).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Return a mutable reference to the payload of the frame.
+    pub fn payload_mut(&mut self) -> Option<&'_ mut [u8]> {
+        let offset = self.payload_offset()?;
+
+        if self.buffer.as_ref().len() <= offset {
+            return None;
+        }
+
+        Some(&mut self.buffer.as_mut()[offset..])
+    }
+
     /// Set the Sequence Number field value in the buffer.
     pub fn set_sequence_number(&mut self, sequence_number: u8) {
+        self.payload_offset = None;
         // Set the sequence number suppression bit to false.
         let mut w = FrameControl::new_unchecked(&mut self.buffer.as_mut()[..2]);
         w.set_sequence_number_suppression(false);
@@ -205,6 +462,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> DataFrame<T> {
     /// Set the Addressing field values in the buffer, based on the given
     /// [`AddressingFieldsRepr`].
     pub fn set_addressing_fields(&mut self, addressing_fields: &AddressingFieldsRepr) {
+        self.payload_offset = None;
         let start = 2 + (!self.frame_control().sequence_number_suppression() as usize);
 
         let (fc, addressing) = self.buffer.as_mut().split_at_mut(start);
@@ -213,9 +471,17 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> DataFrame<T> {
     }
 
     /// Set the Auxiliary Security Header field values in the buffer, based on
-    /// the given _.
-    pub fn set_aux_sec_header(&mut self) {
-        todo!();
+    /// the given [`AuxiliarySecurityHeaderRepr`].
+    pub fn set_aux_sec_header(&mut self, aux: &AuxiliarySecurityHeaderRepr) {
+        self.payload_offset = None;
+        let mut offset = 2;
+        offset += !self.frame_control().sequence_number_suppression() as usize;
+
+        if let Some(af) = self.addressing() {
+            offset += af.len();
+        }
+
+        aux.emit(&mut self.buffer.as_mut()[offset..]);
     }
 
     /// Set the Information Elements field values in the buffer, based on the
@@ -225,6 +491,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> DataFrame<T> {
         ie: &InformationElementsRepr,
         contains_payload: bool,
     ) {
+        self.payload_offset = None;
         let mut offset = 2;
         offset += !self.frame_control().sequence_number_suppression() as usize;
 
@@ -232,6 +499,10 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> DataFrame<T> {
             offset += af.len();
         }
 
+        if self.frame_control().security_enabled() {
+            offset += self.auxiliary_security_header().unwrap().len();
+        }
+
         ie.emit(&mut self.buffer.as_mut()[offset..], contains_payload);
     }
 
@@ -259,3 +530,10 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> DataFrame<T> {
         self.buffer.as_mut()[offset..].copy_from_slice(payload);
     }
 }
+
+#[cfg(feature = "display")]
+impl<T: AsRef<[u8]>> core::fmt::Display for DataFrame<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        super::fmt_frame(self.buffer.as_ref(), f)
+    }
+}
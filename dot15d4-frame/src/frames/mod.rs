@@ -6,10 +6,12 @@ use crate::{AddressingFields, AuxiliarySecurityHeader, FrameControl, FrameType,
 
 pub(crate) mod ack;
 pub(crate) mod beacon;
+pub(crate) mod command;
 pub(crate) mod data;
 
 pub use ack::*;
 pub use beacon::*;
+pub use command::*;
 pub use data::*;
 
 /// A high-level representation of an IEEE 802.15.4 frame with a Frame Check Sequence (FCS).
@@ -90,17 +92,65 @@ impl<T: AsRef<[u8]>> FrameWithFcs<T> {
     }
 }
 
+/// An [`Iterator`] over the frames in a buffer of length-prefixed PPDUs, such
+/// as a dump of a sniffer.
+///
+/// Every PPDU starts with the PHY header, of which the lower 7 bits are the
+/// length of the PSDU, followed by the PSDU: the frame and its FCS. The FCS is
+/// verified and is not part of the returned [`Frame`]. A PPDU of which the FCS
+/// or the frame is invalid is returned as an error, after which the iteration
+/// continues with the next PPDU. A truncated last PPDU is returned as an error
+/// as well, and ends the iteration.
+#[derive(Debug)]
+pub struct PpduIterator<'f> {
+    data: &'f [u8],
+    offset: usize,
+}
+
+impl<'f> PpduIterator<'f> {
+    /// Create a new [`PpduIterator`] over the PPDUs in `data`.
+    pub fn new(data: &'f [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    /// Returns the offset of the next PPDU.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl<'f> Iterator for PpduIterator<'f> {
+    type Item = Result<Frame<&'f [u8]>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let phr = *self.data.get(self.offset)?;
+        let start = self.offset + 1;
+        let end = start + (phr & 0x7f) as usize;
+
+        let Some(psdu) = self.data.get(start..end) else {
+            self.offset = self.data.len();
+            return Some(Err(Error));
+        };
+        self.offset = end;
+
+        Some(FrameWithFcs::new(psdu).and_then(|_| Frame::new(&psdu[..psdu.len() - 2])))
+    }
+}
+
 /// A high-level representation of an IEEE 802.15.4 frame.
 pub enum Frame<T: AsRef<[u8]>> {
     /// An acknowledgment frame.
+    #[cfg(feature = "imm-ack")]
     Ack(Ack<T>),
     /// An enhanced acknowledgment frame.
     EnhancedAck(EnhancedAck<T>),
     /// A beacon frame.
+    #[cfg(feature = "legacy-beacons")]
     Beacon(Beacon<T>),
     /// An enhanced beacon frame.
     EnhancedBeacon(EnhancedBeacon<T>),
-    /// A data frame.
+    /// A data frame, or a MAC command frame of which the command is returned
+    /// by [`DataFrame::command_id`].
     Data(DataFrame<T>),
 }
 
@@ -115,22 +165,24 @@ impl<T: AsRef<[u8]>> Frame<T> {
 
         match frame_control.frame_type() {
             FrameType::Ack => match frame_control.frame_version() {
+                #[cfg(feature = "imm-ack")]
                 FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => {
                     Ok(Frame::Ack(Ack::new(buffer)?))
                 }
                 FrameVersion::Ieee802154_2020 => Ok(Frame::EnhancedAck(EnhancedAck::new(buffer)?)),
-                FrameVersion::Unknown => Err(Error),
+                _ => Err(Error),
             },
             FrameType::Beacon => match frame_control.frame_version() {
+                #[cfg(feature = "legacy-beacons")]
                 FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => {
                     Ok(Frame::Beacon(Beacon::new(buffer)?))
                 }
                 FrameVersion::Ieee802154_2020 => {
                     Ok(Frame::EnhancedBeacon(EnhancedBeacon::new(buffer)?))
                 }
-                FrameVersion::Unknown => Err(Error),
+                _ => Err(Error),
             },
-            FrameType::Data => Ok(Frame::Data(DataFrame::new(buffer)?)),
+            FrameType::Data | FrameType::MacCommand => Ok(Frame::Data(DataFrame::new(buffer)?)),
             _ => Err(Error),
         }
     }
@@ -139,6 +191,7 @@ impl<T: AsRef<[u8]>> Frame<T> {
     ///
     /// # Panics
     /// Panics if the frame is not an ack.
+    #[cfg(feature = "imm-ack")]
     pub fn into_ack(self) -> Ack<T> {
         match self {
             Frame::Ack(frame) => frame,
@@ -161,6 +214,7 @@ impl<T: AsRef<[u8]>> Frame<T> {
     ///
     /// # Panics
     /// Panics if the frame is not a beacon frame.
+    #[cfg(feature = "legacy-beacons")]
     pub fn into_beacon(self) -> Beacon<T> {
         match self {
             Frame::Beacon(frame) => frame,
@@ -193,8 +247,10 @@ impl<T: AsRef<[u8]>> Frame<T> {
     /// Return the frame control field of the frame.
     pub fn frame_control(&self) -> FrameControl<&'_ [u8]> {
         match self {
+            #[cfg(feature = "imm-ack")]
             Frame::Ack(frame) => frame.frame_control(),
             Frame::EnhancedAck(frame) => frame.frame_control(),
+            #[cfg(feature = "legacy-beacons")]
             Frame::Beacon(frame) => frame.frame_control(),
             Frame::EnhancedBeacon(frame) => frame.frame_control(),
             Frame::Data(frame) => frame.frame_control(),
@@ -204,8 +260,10 @@ impl<T: AsRef<[u8]>> Frame<T> {
     /// Return the sequence number of the frame.
     pub fn sequence_number(&self) -> Option<u8> {
         match self {
+            #[cfg(feature = "imm-ack")]
             Frame::Ack(frame) => Some(frame.sequence_number()),
             Frame::EnhancedAck(frame) => frame.sequence_number(),
+            #[cfg(feature = "legacy-beacons")]
             Frame::Beacon(frame) => Some(frame.sequence_number()),
             Frame::EnhancedBeacon(frame) => frame.sequence_number(),
             Frame::Data(frame) => frame.sequence_number(),
@@ -215,8 +273,10 @@ impl<T: AsRef<[u8]>> Frame<T> {
     /// Return the addressing field of the frame.
     pub fn addressing(&self) -> Option<AddressingFields<&'_ [u8], &'_ [u8]>> {
         match self {
+            #[cfg(feature = "imm-ack")]
             Frame::Ack(frame) => None,
             Frame::EnhancedAck(frame) => frame.addressing(),
+            #[cfg(feature = "legacy-beacons")]
             Frame::Beacon(frame) => Some(frame.addressing()),
             Frame::EnhancedBeacon(frame) => frame.addressing(),
             Frame::Data(frame) => frame.addressing(),
@@ -226,8 +286,10 @@ impl<T: AsRef<[u8]>> Frame<T> {
     /// Return the auxiliary security header of the frame.
     pub fn auxiliary_security_header(&self) -> Option<AuxiliarySecurityHeader<&'_ [u8]>> {
         match self {
+            #[cfg(feature = "imm-ack")]
             Frame::Ack(_) => None,
             Frame::EnhancedAck(frame) => frame.auxiliary_security_header(),
+            #[cfg(feature = "legacy-beacons")]
             Frame::Beacon(frame) => frame.auxiliary_security_header(),
             Frame::EnhancedBeacon(frame) => frame.auxiliary_security_header(),
             Frame::Data(frame) => frame.auxiliary_security_header(),
@@ -237,8 +299,10 @@ impl<T: AsRef<[u8]>> Frame<T> {
     /// Return the information elements of the frame.
     pub fn information_elements(&self) -> Option<crate::InformationElements<&'_ [u8]>> {
         match self {
+            #[cfg(feature = "imm-ack")]
             Frame::Ack(_) => None,
             Frame::EnhancedAck(frame) => frame.information_elements(),
+            #[cfg(feature = "legacy-beacons")]
             Frame::Beacon(frame) => None,
             Frame::EnhancedBeacon(frame) => frame.information_elements(),
             Frame::Data(frame) => frame.information_elements(),
@@ -250,8 +314,10 @@ impl<T: AsRef<[u8]> + ?Sized> Frame<&'_ T> {
     /// Return the payload of the frame.
     pub fn payload(&self) -> Option<&[u8]> {
         match self {
+            #[cfg(feature = "imm-ack")]
             Frame::Ack(frame) => None,
             Frame::EnhancedAck(frame) => frame.payload(),
+            #[cfg(feature = "legacy-beacons")]
             Frame::Beacon(frame) => frame.payload(),
             Frame::EnhancedBeacon(frame) => frame.payload(),
             Frame::Data(frame) => frame.payload(),
@@ -259,6 +325,71 @@ impl<T: AsRef<[u8]> + ?Sized> Frame<&'_ T> {
     }
 }
 
+impl<T: AsRef<[u8]> + AsMut<[u8]>> Frame<T> {
+    /// Return a mutable reference to the payload of the frame.
+    pub fn payload_mut(&mut self) -> Option<&mut [u8]> {
+        match self {
+            #[cfg(feature = "imm-ack")]
+            Frame::Ack(_) => None,
+            Frame::EnhancedAck(frame) => frame.payload_mut(),
+            #[cfg(feature = "legacy-beacons")]
+            Frame::Beacon(frame) => frame.payload_mut(),
+            Frame::EnhancedBeacon(frame) => frame.payload_mut(),
+            Frame::Data(frame) => frame.payload_mut(),
+        }
+    }
+
+    /// Return an [`InformationElements`] reader/writer for the information
+    /// elements of the frame.
+    pub fn information_elements_mut(&mut self) -> Option<crate::InformationElements<&mut [u8]>> {
+        match self {
+            #[cfg(feature = "imm-ack")]
+            Frame::Ack(_) => None,
+            Frame::EnhancedAck(frame) => frame.information_elements_mut(),
+            #[cfg(feature = "legacy-beacons")]
+            Frame::Beacon(_) => None,
+            Frame::EnhancedBeacon(frame) => frame.information_elements_mut(),
+            Frame::Data(frame) => frame.information_elements_mut(),
+        }
+    }
+}
+
+#[cfg(feature = "display")]
+impl<T: AsRef<[u8]>> core::fmt::Display for Frame<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            #[cfg(feature = "imm-ack")]
+            Frame::Ack(frame) => frame.fmt(f),
+            Frame::EnhancedAck(frame) => frame.fmt(f),
+            #[cfg(feature = "legacy-beacons")]
+            Frame::Beacon(frame) => frame.fmt(f),
+            Frame::EnhancedBeacon(frame) => frame.fmt(f),
+            Frame::Data(frame) => frame.fmt(f),
+        }
+    }
+}
+
+/// Format the frame in `buffer` like its [`FrameRepr`], or as raw bytes when
+/// it can not be parsed.
+///
+/// [`FrameRepr`]: crate::FrameRepr
+#[cfg(feature = "display")]
+fn fmt_frame(buffer: &[u8], f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let repr = if buffer.len() >= 2 {
+        crate::FrameRepr::parse(&DataFrame::new_unchecked(buffer)).ok()
+    } else {
+        None
+    };
+
+    match repr {
+        Some(repr) => core::fmt::Display::fmt(&repr, f),
+        None => {
+            let indent = f.width().unwrap_or(0);
+            writeln!(f, "{:indent$}{:x?}", "", buffer)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -268,7 +399,7 @@ mod tests {
         (
             $data:expr, $expected:pat, $into:ident
         ) => {{
-            let data = hex::decode($data).unwrap();
+            let data = crate::hex::decode_to_vec($data).unwrap();
             let frame = Frame::new(data).unwrap();
             assert!(matches!(frame, $expected));
             frame.$into()
@@ -277,6 +408,7 @@ mod tests {
 
     #[test]
     fn high_level_parsing() {
+        #[cfg(feature = "imm-ack")]
         test!("021001", Frame::Ack(_), into_ack);
         test!(
             "022e37cdab02000200020002000200020fe18f",
@@ -288,11 +420,43 @@ mod tests {
             Frame::EnhancedBeacon(_),
             into_enhanced_beacon
         );
+        #[cfg(feature = "legacy-frames")]
         test!(
             "41d801cdabffffc7d9b514004b12002b000000",
             Frame::Data(_),
             into_data
         );
+        let command = test!(
+            "032a03ffffffff003f0488021e053200f807",
+            Frame::Data(_),
+            into_data
+        );
+        assert_eq!(command.command_id(), Some(CommandId::BeaconRequest));
+    }
+
+    #[test]
+    fn payload_offset() {
+        let mut data = crate::hex::decode_to_vec("032a03ffffffff003f0488021e053200f807").unwrap();
+
+        let frame = DataFrame::new(&data[..]).unwrap();
+        assert_eq!(frame.payload(), Some(&[0x07][..]));
+        assert_eq!(frame.payload(), Some(&[0x07][..]));
+
+        // Changing the layout of the frame invalidates the cached offset.
+        let mut frame = DataFrame::new(&mut data[..]).unwrap();
+        assert_eq!(frame.payload_mut().unwrap(), &[0x07]);
+        frame
+            .frame_control_mut()
+            .set_information_elements_present(false);
+        assert_eq!(frame.payload_mut().unwrap().len(), 11);
+    }
+
+    #[test]
+    #[cfg(not(feature = "legacy-frames"))]
+    fn legacy_frames_rejected() {
+        for data in ["021001", "41d801cdabffffc7d9b514004b12002b000000"] {
+            assert!(Frame::new(crate::hex::decode_to_vec(data).unwrap()).is_err());
+        }
     }
 
     #[test]
@@ -323,4 +487,38 @@ mod tests {
         ];
         let frame = FrameWithFcs::new(&frame_with_fcs).unwrap();
     }
+
+    #[test]
+    fn ppdus() {
+        let beacon = crate::hex::decode_to_vec("40ebcdabffff0100010001000100003f3288061a110000000000191c01080780004808fc032003e80398089001c0006009a010102701c8000a1b010011000100000000071216").unwrap();
+        let ack = crate::hex::decode_to_vec("022e8dcdab0200020002000200020f00007dd4").unwrap();
+
+        let mut dump = vec![beacon.len() as u8];
+        dump.extend_from_slice(&beacon);
+        dump.push(ack.len() as u8);
+        dump.extend_from_slice(&ack);
+        // A corrupted FCS
+        dump.push(ack.len() as u8);
+        dump.extend_from_slice(&ack[..ack.len() - 1]);
+        dump.push(0x00);
+        // A truncated PPDU
+        dump.push(ack.len() as u8);
+        dump.extend_from_slice(&ack[..4]);
+
+        let mut ppdus = PpduIterator::new(&dump);
+        let frame = ppdus.next().unwrap().unwrap();
+        assert!(matches!(frame, Frame::EnhancedBeacon(_)));
+        assert_eq!(frame.sequence_number(), None);
+        assert_eq!(ppdus.offset(), beacon.len() + 1);
+
+        let Frame::EnhancedAck(frame) = ppdus.next().unwrap().unwrap() else {
+            panic!("expected an enhanced ack");
+        };
+        assert_eq!(frame.sequence_number(), Some(0x8d));
+
+        assert!(ppdus.next().unwrap().is_err());
+        assert!(ppdus.next().unwrap().is_err());
+        assert!(ppdus.next().is_none());
+        assert_eq!(ppdus.offset(), dump.len());
+    }
 }
@@ -0,0 +1,104 @@
+//! Hexadecimal strings of frames, without allocation.
+//!
+//! Frames are often written down as hexadecimal strings, e.g. in logs, in
+//! tests, or as the input of `dot15d4-cat`. [`decode`] writes such a string
+//! into a buffer of the caller, and [`Hex`] displays bytes as one:
+//!
+//! ```
+//! # use dot15d4_frame::{hex::Hex, Frame};
+//! let mut buffer = [0; 127];
+//! let frame = Frame::from_hex("022e37cdab0200020002000200020fe18f", &mut buffer).unwrap();
+//! assert!(matches!(frame, Frame::EnhancedAck(_)));
+//!
+//! let mut decoded = [0; 3];
+//! dot15d4_frame::hex::decode("02ab10", &mut decoded).unwrap();
+//! assert_eq!(format!("{}", Hex(&decoded)), "02ab10");
+//! ```
+
+use crate::{Error, Frame, Result};
+
+/// Decode the hexadecimal string `input` into `buffer`, and return the number
+/// of bytes written. Both lowercase and uppercase digits are accepted.
+///
+/// # Errors
+///
+/// Returns an error when `input` has an odd number of digits, contains other
+/// characters than digits, or does not fit in `buffer`.
+pub fn decode(input: &str, buffer: &mut [u8]) -> Result<usize> {
+    let input = input.as_bytes();
+    if !input.len().is_multiple_of(2) {
+        return Err(Error);
+    }
+
+    let len = input.len() / 2;
+    let Some(buffer) = buffer.get_mut(..len) else {
+        return Err(Error);
+    };
+
+    for (byte, digits) in buffer.iter_mut().zip(input.chunks_exact(2)) {
+        *byte = (digit(digits[0])? << 4) | digit(digits[1])?;
+    }
+
+    Ok(len)
+}
+
+/// Decode the hexadecimal string `input` into a new vector, see [`decode`].
+#[cfg(any(feature = "std", test))]
+pub fn decode_to_vec(input: &str) -> Result<std::vec::Vec<u8>> {
+    let mut buffer = std::vec![0; input.len() / 2];
+    decode(input, &mut buffer)?;
+    Ok(buffer)
+}
+
+fn digit(c: u8) -> Result<u8> {
+    match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        _ => Err(Error),
+    }
+}
+
+/// Displays bytes as a lowercase hexadecimal string, without separators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hex<'a>(pub &'a [u8]);
+
+impl core::fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for byte in self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Frame<&'a [u8]> {
+    /// Decode the hexadecimal string `input` into `buffer`, and read the
+    /// frame, see [`decode`] and [`Frame::new`].
+    pub fn from_hex(input: &str, buffer: &'a mut [u8]) -> Result<Self> {
+        let len = decode(input, buffer)?;
+        Frame::new(&buffer[..len])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_and_display() {
+        let mut buffer = [0; 4];
+        assert_eq!(decode("0aFf10", &mut buffer).unwrap(), 3);
+        assert_eq!(buffer, [0x0a, 0xff, 0x10, 0x00]);
+        assert_eq!(format!("{}", Hex(&buffer[..3])), "0aff10");
+        assert_eq!(decode("", &mut buffer).unwrap(), 0);
+
+        // Odd lengths, other characters, and too long strings are rejected
+        assert!(decode("0aF", &mut buffer).is_err());
+        assert!(decode("0g", &mut buffer).is_err());
+        assert!(decode("0a 1", &mut buffer).is_err());
+        assert!(decode("0102030405", &mut buffer).is_err());
+
+        assert!(Frame::from_hex("0102030405", &mut buffer).is_err());
+    }
+}
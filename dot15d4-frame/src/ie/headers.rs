@@ -1,7 +1,7 @@
 //! IEEE 802.15.4 Header Information Element reader and writers.
 
-use crate::frames::beacon::SuperframeSpecification;
-use crate::time::Duration;
+use crate::frames::beacon::{SuperframeSpecification, SuperframeSpecificationRepr};
+use crate::time::{Duration, Instant};
 use crate::{Error, Result};
 use dot15d4_macros::frame;
 
@@ -17,7 +17,8 @@ impl<T: AsRef<[u8]>> HeaderInformationElement<T> {
     ///
     /// # Errors
     ///
-    /// Returns an error if the length field is less than 2.
+    /// Returns an error if the buffer is too short to contain the Header
+    /// Information Element and its content.
     pub fn new(data: T) -> Result<Self> {
         let ie = Self::new_unchecked(data);
 
@@ -31,7 +32,8 @@ impl<T: AsRef<[u8]>> HeaderInformationElement<T> {
     /// Returns `false` if the buffer is too short to contain the Header
     /// Information Element.
     fn check_len(&self) -> bool {
-        self.data.as_ref().len() >= 2
+        let len = self.data.as_ref().len();
+        len >= 2 && len >= 2 + self.len()
     }
 
     /// Create a new [`HeaderInformationElement`] reader/writer from a given
@@ -49,7 +51,7 @@ impl<T: AsRef<[u8]>> HeaderInformationElement<T> {
     /// Return the length field value.
     pub fn len(&self) -> usize {
         let b = &self.data.as_ref()[0..2];
-        u16::from_le_bytes([b[0], b[1]]) as usize & 0b1111_1110
+        u16::from_le_bytes([b[0], b[1]]) as usize & 0b0111_1111
     }
 
     /// Return the [`HeaderElementId`].
@@ -73,7 +75,7 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> HeaderInformationElement<T> {
 
     /// Set the length field.
     pub fn set_length(&mut self, len: u16) {
-        const MASK: u16 = 0b1111_1110;
+        const MASK: u16 = 0b0111_1111;
 
         let b = &mut self.data.as_mut()[0..2];
         let value = u16::from_le_bytes([b[0], b[1]]) & !MASK;
@@ -243,7 +245,7 @@ impl<'f> Iterator for HeaderInformationElementsIterator<'f> {
 }
 
 /// Vendor Specific Header Information Element.
-#[frame]
+#[frame(setters)]
 #[derive(Debug)]
 pub struct VendorSpecific {
     #[bytes(3)]
@@ -256,7 +258,7 @@ pub struct VendorSpecific {
 }
 
 /// CSL Header Information Element.
-#[frame]
+#[frame(repr)]
 #[derive(Debug)]
 pub struct Csl {
     /// Return the CSL phase field value.
@@ -268,8 +270,48 @@ pub struct Csl {
     rendezvous_time: u16,
 }
 
+impl CslRepr {
+    /// The duration of one unit of the CSL phase, CSL period and rendezvous
+    /// time fields, which is 10 symbol periods of the 2.4 GHz O-QPSK PHY.
+    pub const UNIT: Duration = Duration::from_us(160);
+
+    /// Compute the CSL phase at `now` for a receiver that samples the channel
+    /// at `anchor` and every `csl_period` units from then on.
+    ///
+    /// The phase is the time until the next channel sample of the receiver,
+    /// in units of [`CslRepr::UNIT`], rounded down. The anchor can be any past
+    /// or future channel sample. A CSL period of 0 results in a phase of 0.
+    pub fn phase_at(anchor: Instant, now: Instant, csl_period: u16) -> u16 {
+        if csl_period == 0 {
+            return 0;
+        }
+
+        let period = csl_period as i64 * Self::UNIT.as_us();
+        let until_sample = (anchor - now).as_us().rem_euclid(period);
+
+        (until_sample / Self::UNIT.as_us()) as u16
+    }
+
+    /// Create a CSL representation for a receiver that samples the channel at
+    /// `anchor` and every `csl_period` units from then on, where the CSL phase
+    /// is computed at `now`.
+    pub fn new(anchor: Instant, now: Instant, csl_period: u16) -> Self {
+        Self {
+            csl_phase: Self::phase_at(anchor, now, csl_period),
+            csl_period,
+            rendezvous_time: None,
+        }
+    }
+
+    /// Return the time of the next channel sample of the receiver, where
+    /// `reference` is the time at which the CSL phase was computed.
+    pub fn next_sample(&self, reference: Instant) -> Instant {
+        reference + Self::UNIT * self.csl_phase as usize
+    }
+}
+
 /// RIT Header Information Element.
-#[frame]
+#[frame(repr)]
 #[derive(Debug)]
 pub struct Rit {
     /// Return the time to first listen field value.
@@ -281,7 +323,7 @@ pub struct Rit {
 }
 
 /// DSME Superframe Specification Header Information Element.
-#[frame]
+#[frame(repr)]
 pub struct DsmeSuperframeSpecification {
     #[bits(4)]
     /// Return the multi superframe order field value.
@@ -300,7 +342,7 @@ pub struct DsmeSuperframeSpecification {
 }
 
 /// Time Synchronization Specification Header Information Element.
-#[frame]
+#[frame(setters)]
 pub struct TimeSynchronizationSpecification {
     #[bytes(8)]
     // TODO: use a Duration type
@@ -311,7 +353,7 @@ pub struct TimeSynchronizationSpecification {
 }
 
 /// Channel Hopping Specification Header Information Element.
-#[frame]
+#[frame(setters)]
 pub struct ChannelHoppingSpecification {
     /// Return the hopping sequence ID field value.
     hopping_sequence_id: u8,
@@ -327,7 +369,7 @@ pub struct ChannelHoppingSpecification {
 }
 
 /// Renzdevous Time Header Information Element.
-#[frame]
+#[frame(repr)]
 pub struct RendezvousTime {
     /// Return the rendezvous time field value.
     rendezvous_time: u16,
@@ -420,7 +462,7 @@ impl<T: AsRef<[u8]>> core::fmt::Display for TimeCorrection<T> {
     }
 }
 
-#[frame]
+#[frame(setters)]
 #[derive(Debug)]
 /// A reader/writer for the IEEE 802.15.4 Simplified Superframe Specification
 /// Header Information Element.
@@ -435,7 +477,7 @@ pub struct SimplifiedSuperframeSpecification {
     cfp_specification: CfpSpecification,
 }
 
-#[frame]
+#[frame(repr)]
 #[derive(Debug)]
 /// A reader/writer for the IEEE 802.15.4 CFP Specification Header Information
 /// Element.
@@ -454,6 +496,102 @@ pub struct CfpSpecification {
     gts_permit: bool,
 }
 
+impl core::fmt::Display for CfpSpecificationRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+
+        writeln!(f, "{:indent$}gts count: {}", "", self.gts_count)?;
+        writeln!(f, "{:indent$}first cfp slot: {}", "", self.first_cfp_slot)?;
+        writeln!(f, "{:indent$}last cfp slot: {}", "", self.last_cfp_slot)?;
+        writeln!(f, "{:indent$}gts permit: {}", "", self.gts_permit as usize)
+    }
+}
+
+/// A high-level representation of a [`SimplifiedSuperframeSpecification`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SimplifiedSuperframeSpecificationRepr {
+    /// The timestamp field.
+    pub timestamp: u16,
+    /// The superframe specification field.
+    pub superframe_specification: SuperframeSpecificationRepr,
+    /// The CFP specification field.
+    pub cfp_specification: CfpSpecificationRepr,
+}
+
+impl SimplifiedSuperframeSpecificationRepr {
+    /// Parse a [`SimplifiedSuperframeSpecification`].
+    pub fn parse<T: AsRef<[u8]>>(reader: &SimplifiedSuperframeSpecification<T>) -> Result<Self> {
+        Ok(Self {
+            timestamp: reader.timestamp(),
+            superframe_specification: SuperframeSpecificationRepr::parse(
+                &reader.superframe_specification()?,
+            )?,
+            cfp_specification: CfpSpecificationRepr::parse(&reader.cfp_specification()?)?,
+        })
+    }
+
+    /// Return the length of the [`SimplifiedSuperframeSpecification`] when
+    /// emitted into a buffer.
+    pub fn buffer_len(&self) -> usize {
+        SimplifiedSuperframeSpecification::<&[u8]>::size()
+    }
+
+    /// Emit the [`SimplifiedSuperframeSpecification`] into a buffer.
+    pub fn emit<T: AsRef<[u8]> + AsMut<[u8]>>(
+        &self,
+        writer: &mut SimplifiedSuperframeSpecification<T>,
+    ) {
+        writer.set_timestamp(self.timestamp);
+
+        let buffer = writer.buffer.as_mut();
+        self.superframe_specification
+            .emit(&mut SuperframeSpecification::new_unchecked(
+                &mut buffer[2..4],
+            ));
+        self.cfp_specification
+            .emit(&mut CfpSpecification::new_unchecked(&mut buffer[4..6]));
+    }
+}
+
+#[cfg(feature = "fuzz")]
+impl arbitrary::Arbitrary<'_> for SimplifiedSuperframeSpecificationRepr {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
+        use crate::frames::beacon::{BeaconOrder, SuperframeOrder};
+
+        Ok(Self {
+            timestamp: u.arbitrary()?,
+            superframe_specification: SuperframeSpecificationRepr {
+                beacon_order: BeaconOrder::from(u.int_in_range(0..=15)?),
+                superframe_order: SuperframeOrder::from(u.int_in_range(0..=15)?),
+                final_cap_slot: u.int_in_range(0..=15)?,
+                battery_life_extension: u.arbitrary()?,
+                pan_coordinator: u.arbitrary()?,
+                association_permit: u.arbitrary()?,
+            },
+            cfp_specification: CfpSpecificationRepr {
+                gts_count: u.int_in_range(0..=7)?,
+                first_cfp_slot: u.int_in_range(0..=31)?,
+                last_cfp_slot: u.int_in_range(0..=15)?,
+                gts_permit: u.arbitrary()?,
+            },
+        })
+    }
+}
+
+impl core::fmt::Display for SimplifiedSuperframeSpecificationRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        let inner = indent + 2;
+
+        writeln!(f, "{:indent$}timestamp: {}", "", self.timestamp)?;
+        writeln!(f, "{:indent$}superframe specification", "")?;
+        write!(f, "{:inner$}", self.superframe_specification)?;
+        writeln!(f, "{:indent$}cfp specification", "")?;
+        write!(f, "{:inner$}", self.cfp_specification)
+    }
+}
+
 bitflags::bitflags! {
     /// Supported Frequency Bands values.
     pub struct SupportedFrequencyBands: u16 {
@@ -614,4 +752,183 @@ mod tests {
         );
         assert_eq!(HeaderElementId::from(0x80), HeaderElementId::Unkown);
     }
+
+    #[test]
+    fn csl() {
+        assert_eq!(Csl::<&[u8]>::size(), 4);
+        assert!(Csl::new(&[0x01, 0x00, 0x02][..]).is_err());
+
+        let csl = Csl::new(&[0x01, 0x00, 0x02, 0x00][..]).unwrap();
+        assert_eq!(csl.csl_phase(), 1);
+        assert_eq!(csl.csl_period(), 2);
+        assert_eq!(csl.rendezvous_time(), None);
+
+        let csl = Csl::new(&[0x01, 0x00, 0x02, 0x00, 0x03, 0x00][..]).unwrap();
+        assert_eq!(csl.rendezvous_time(), Some(3));
+    }
+
+    #[test]
+    fn csl_repr() {
+        for rendezvous_time in [None, Some(3)] {
+            let repr = CslRepr {
+                csl_phase: 1,
+                csl_period: 2,
+                rendezvous_time,
+            };
+
+            let mut buffer = std::vec![0; repr.buffer_len()];
+            repr.emit(&mut Csl::new_unchecked(&mut buffer[..]));
+
+            let csl = Csl::new(&buffer[..]).unwrap();
+            assert_eq!(CslRepr::parse(&csl).unwrap(), repr);
+        }
+    }
+
+    #[test]
+    fn csl_phase() {
+        let anchor = Instant::from_us(1_000_000);
+
+        // Sampling right now.
+        assert_eq!(CslRepr::phase_at(anchor, anchor, 100), 0);
+
+        // The next sample is one unit away.
+        let now = anchor - CslRepr::UNIT;
+        assert_eq!(CslRepr::phase_at(anchor, now, 100), 1);
+
+        // The anchor lies more than a period in the future.
+        let now = anchor - CslRepr::UNIT * 250;
+        assert_eq!(CslRepr::phase_at(anchor, now, 100), 50);
+
+        // The anchor lies in the past, just after a sample.
+        let now = anchor + CslRepr::UNIT * 201;
+        assert_eq!(CslRepr::phase_at(anchor, now, 100), 99);
+
+        // Just before the end of the period, rounded down.
+        let now = anchor + CslRepr::UNIT * 100 - Duration::from_us(1);
+        assert_eq!(CslRepr::phase_at(anchor, now, 100), 0);
+        let now = anchor + Duration::from_us(1);
+        assert_eq!(CslRepr::phase_at(anchor, now, 100), 99);
+
+        // The largest period does not overflow.
+        let now = anchor + CslRepr::UNIT;
+        assert_eq!(CslRepr::phase_at(anchor, now, u16::MAX), u16::MAX - 1);
+
+        // CSL disabled.
+        assert_eq!(CslRepr::phase_at(anchor, now, 0), 0);
+
+        let now = anchor + CslRepr::UNIT * 30;
+        let repr = CslRepr::new(anchor, now, 100);
+        assert_eq!(repr.csl_phase, 70);
+        assert_eq!(repr.csl_period, 100);
+        assert_eq!(repr.rendezvous_time, None);
+        assert_eq!(repr.next_sample(now), anchor + CslRepr::UNIT * 100);
+    }
+
+    #[test]
+    fn simplified_superframe_specification_repr() {
+        use crate::frames::beacon::{BeaconOrder, SuperframeOrder};
+
+        let data = [0x34, 0x12, 0xff, 0xcf, 0x49, 0x1f];
+        let sss = SimplifiedSuperframeSpecification::new(&data[..]).unwrap();
+        let repr = SimplifiedSuperframeSpecificationRepr::parse(&sss).unwrap();
+        assert_eq!(
+            repr,
+            SimplifiedSuperframeSpecificationRepr {
+                timestamp: 0x1234,
+                superframe_specification: SuperframeSpecificationRepr {
+                    beacon_order: BeaconOrder::OnDemand,
+                    superframe_order: SuperframeOrder::Inactive,
+                    final_cap_slot: 15,
+                    battery_life_extension: false,
+                    pan_coordinator: true,
+                    association_permit: true,
+                },
+                cfp_specification: CfpSpecificationRepr {
+                    gts_count: 1,
+                    first_cfp_slot: 9,
+                    last_cfp_slot: 15,
+                    gts_permit: true,
+                },
+            }
+        );
+        assert_eq!(repr.buffer_len(), 6);
+
+        let mut buffer = [0; 6];
+        repr.emit(&mut SimplifiedSuperframeSpecification::new_unchecked(
+            &mut buffer[..],
+        ));
+        assert_eq!(buffer, data);
+
+        let ie = crate::HeaderInformationElementRepr::SimplifiedSuperframeSpecification(repr);
+        let mut buffer = [0; 8];
+        ie.emit(&mut buffer);
+        assert_eq!(buffer[..2], [0x86, 0x11]);
+        assert_eq!(buffer[2..], data);
+
+        let header = HeaderInformationElement::new(&buffer[..]).unwrap();
+        assert_eq!(header.len(), 6);
+        assert_eq!(
+            header.element_id(),
+            HeaderElementId::SimplifiedSuperframeSpecification
+        );
+    }
+
+    #[test]
+    fn dsme_superframe_specification_repr() {
+        let repr = DsmeSuperframeSpecificationRepr {
+            multi_superframe_order: 0xa,
+            channel_diversity_mode: true,
+            cap_reduction: false,
+            deferred_beacon: true,
+        };
+        assert_eq!(repr.buffer_len(), 1);
+
+        let mut buffer = [0u8; 1];
+        repr.emit(&mut DsmeSuperframeSpecification::new_unchecked(
+            &mut buffer[..],
+        ));
+        assert_eq!(buffer, [0b1001_1010]);
+
+        let dsme = DsmeSuperframeSpecification::new(&buffer[..]).unwrap();
+        assert_eq!(DsmeSuperframeSpecificationRepr::parse(&dsme).unwrap(), repr);
+    }
+
+    #[test]
+    fn minimum_size() {
+        // Trailing variable-length fields are not included.
+        assert_eq!(VendorSpecific::<&[u8]>::size(), 3);
+        assert_eq!(ChannelHoppingSpecification::<&[u8]>::size(), 5);
+        // A partially used byte is included.
+        assert_eq!(CfpSpecification::<&[u8]>::size(), 2);
+        assert_eq!(SimplifiedSuperframeSpecification::<&[u8]>::size(), 6);
+    }
+
+    #[test]
+    fn cfp_specification_setters() {
+        let mut buffer = [0u8; 2];
+        let mut cfp = CfpSpecification::new(&mut buffer[..]).unwrap();
+        cfp.set_gts_count(5);
+        cfp.set_first_cfp_slot(0x1f);
+        cfp.set_last_cfp_slot(9);
+        cfp.set_gts_permit(true);
+
+        // Overwriting a bitfield leaves the other fields in the byte untouched.
+        cfp.set_first_cfp_slot(2);
+
+        assert_eq!(cfp.gts_count(), 5);
+        assert_eq!(cfp.first_cfp_slot(), 2);
+        assert_eq!(cfp.last_cfp_slot(), 9);
+        assert!(cfp.gts_permit());
+        assert_eq!(buffer, [0b0001_0101, 0b0001_1001]);
+    }
+
+    #[test]
+    fn vendor_specific_setters() {
+        let mut buffer = [0u8; 5];
+        let mut vendor = VendorSpecific::new(&mut buffer[..]).unwrap();
+        vendor.set_vendor_specific_payload(&[0xca, 0xfe]);
+
+        assert_eq!(vendor.vendor_specific_payload(), &[0xca, 0xfe]);
+        assert_eq!(buffer, [0, 0, 0, 0xca, 0xfe]);
+    }
 }
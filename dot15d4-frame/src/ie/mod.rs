@@ -9,12 +9,19 @@ pub use payloads::*;
 mod nested;
 pub use nested::*;
 
+use core::ops::Range;
+
 use super::{Error, Result};
 
 /// IEEE 802.15.4 Information Element reader.
+///
+/// The extents of the header and payload Information Element lists are
+/// computed once when the reader is created.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct InformationElements<T: AsRef<[u8]>> {
     data: T,
+    header_len: usize,
+    payload_len: usize,
 }
 
 impl<T: AsRef<[u8]>> InformationElements<T> {
@@ -37,43 +44,50 @@ impl<T: AsRef<[u8]>> InformationElements<T> {
     /// Returns `false` if the buffer is too short to contain the information
     /// elements.
     fn check_len(&self) -> bool {
-        let mut len = 0;
-
-        let mut iter = self.header_information_elements();
-        while iter.next().is_some() {}
-        len += iter.offset();
-
-        if len > self.data.as_ref().len() {
-            return false;
-        }
-
-        let mut iter = self.payload_information_elements();
-        while iter.next().is_some() {}
-        len += iter.offset();
-
-        self.data.as_ref().len() >= len
+        self.data.as_ref().len() >= self.len()
     }
 
     /// Create a new [`InformationElements`] reader from a given buffer without
     /// length checking.
     pub fn new_unchecked(data: T) -> Self {
-        Self { data }
+        let buffer = data.as_ref();
+
+        let mut iter = HeaderInformationElementsIterator {
+            data: buffer,
+            offset: 0,
+            terminated: buffer.is_empty(),
+        };
+        // Payload Information Elements only follow a Header Termination 1.
+        // After a Header Termination 2, the payload follows.
+        let mut payload_ies = false;
+        for ie in iter.by_ref() {
+            payload_ies = ie.element_id() == HeaderElementId::HeaderTermination1;
+        }
+        let header_len = iter.offset();
+
+        let payload_len = if payload_ies && header_len < buffer.len() {
+            let mut iter = PayloadInformationElementsIterator {
+                data: &buffer[header_len..],
+                offset: 0,
+                terminated: false,
+            };
+            while iter.next().is_some() {}
+            iter.offset()
+        } else {
+            0
+        };
+
+        Self {
+            data,
+            header_len,
+            payload_len,
+        }
     }
 
     /// Returns the length of the information elements.
     #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
-        let mut len = 0;
-
-        let mut iter = self.header_information_elements();
-        while iter.next().is_some() {}
-        len += iter.offset();
-
-        let mut iter = self.payload_information_elements();
-        while iter.next().is_some() {}
-        len += iter.offset();
-
-        len
+        self.header_len + self.payload_len
     }
 
     /// Returns an [`Iterator`] over [`HeaderInformationElement`].
@@ -87,17 +101,236 @@ impl<T: AsRef<[u8]>> InformationElements<T> {
 
     /// Returns an [`Iterator`] over [`PayloadInformationElement`].
     pub fn payload_information_elements(&self) -> PayloadInformationElementsIterator {
-        let start = self
-            .header_information_elements()
-            .map(|ie| ie.len() + 2)
-            .sum::<usize>();
-
-        let terminated = start >= self.data.as_ref().len();
+        let data = self.data.as_ref();
+        let start = self.header_len.min(data.len());
 
         PayloadInformationElementsIterator {
-            data: &self.data.as_ref()[start..],
+            data: &data[start..],
             offset: 0,
-            terminated,
+            terminated: self.payload_len == 0,
+        }
+    }
+
+    /// Return the first Nested Information Element with the given
+    /// [`NestedSubId`], looking into all MLME Payload Information Elements.
+    pub fn nested_information_element(
+        &self,
+        sub_id: NestedSubId,
+    ) -> Option<NestedInformationElement<&[u8]>> {
+        self.payload_information_elements()
+            .filter(|payload| payload.group_id() == PayloadGroupId::Mlme)
+            .flat_map(|payload| NestedInformationElementsIterator::new(payload.into_content()))
+            .find(|nested| nested.sub_id() == sub_id)
+    }
+
+    /// Return the TSCH Synchronization Nested Information Element.
+    ///
+    /// Returns `None` when it is not present or malformed.
+    pub fn tsch_synchronization(&self) -> Option<TschSynchronization<&[u8]>> {
+        let nested = self.nested_information_element(NestedSubId::Short(
+            NestedSubIdShort::TschSynchronization,
+        ))?;
+        TschSynchronization::new(nested.into_content()).ok()
+    }
+
+    /// Return the TSCH Timeslot Nested Information Element.
+    ///
+    /// Returns `None` when it is not present or malformed.
+    pub fn tsch_timeslot(&self) -> Option<TschTimeslot<&[u8]>> {
+        let nested =
+            self.nested_information_element(NestedSubId::Short(NestedSubIdShort::TschTimeslot))?;
+        TschTimeslot::new(nested.into_content()).ok()
+    }
+
+    /// Return the TSCH Slotframe and Link Nested Information Element.
+    ///
+    /// Returns `None` when it is not present or malformed.
+    pub fn tsch_slotframe_and_link(&self) -> Option<TschSlotframeAndLink<&[u8]>> {
+        let nested = self.nested_information_element(NestedSubId::Short(
+            NestedSubIdShort::TschSlotframeAndLink,
+        ))?;
+        TschSlotframeAndLink::new(nested.into_content()).ok()
+    }
+
+    /// Return the Channel Hopping Nested Information Element.
+    ///
+    /// Returns `None` when it is not present or malformed.
+    pub fn channel_hopping(&self) -> Option<ChannelHopping<&[u8]>> {
+        let nested =
+            self.nested_information_element(NestedSubId::Long(NestedSubIdLong::ChannelHopping))?;
+        ChannelHopping::new(nested.into_content()).ok()
+    }
+
+    /// Return the first Enhanced Beacon Filter Nested Information Element,
+    /// as carried by an Enhanced Beacon Request.
+    pub fn enhanced_beacon_filter(&self) -> Option<EnhancedBeaconFilter<&[u8]>> {
+        let nested = self.nested_information_element(NestedSubId::Short(
+            NestedSubIdShort::EnhancedBeaconFilter,
+        ))?;
+        EnhancedBeaconFilter::new(nested.into_content()).ok()
+    }
+
+    /// Return the range in the buffer of the first Nested Information Element
+    /// with the given [`NestedSubId`], looking into all MLME Payload
+    /// Information Elements.
+    fn nested_information_element_range(&self, sub_id: NestedSubId) -> Option<Range<usize>> {
+        let mut payloads = self.payload_information_elements();
+
+        loop {
+            // The content of the Payload Information Element follows its
+            // 2-byte header.
+            let start = self.header_len + payloads.offset() + 2;
+            let payload = payloads.next()?;
+            if payload.group_id() != PayloadGroupId::Mlme {
+                continue;
+            }
+
+            let mut nested = NestedInformationElementsIterator::new(payload.into_content());
+            loop {
+                let offset = start + nested.offset();
+                let Some(ie) = nested.next() else {
+                    break;
+                };
+
+                if ie.sub_id() == sub_id {
+                    return Some(offset..offset + ie.length() + 2);
+                }
+            }
         }
     }
 }
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> InformationElements<T> {
+    /// Return the first Header Information Element with the given
+    /// [`HeaderElementId`] as a reader/writer.
+    pub fn header_information_element_mut(
+        &mut self,
+        id: HeaderElementId,
+    ) -> Option<HeaderInformationElement<&mut [u8]>> {
+        let mut headers = self.header_information_elements();
+        let range = loop {
+            let offset = headers.offset();
+            let header = headers.next()?;
+
+            if header.element_id() == id {
+                break offset..offset + header.len() + 2;
+            }
+        };
+
+        Some(HeaderInformationElement::new_unchecked(
+            &mut self.data.as_mut()[range],
+        ))
+    }
+
+    /// Return the first Nested Information Element with the given
+    /// [`NestedSubId`] as a reader/writer, looking into all MLME Payload
+    /// Information Elements.
+    pub fn nested_information_element_mut(
+        &mut self,
+        sub_id: NestedSubId,
+    ) -> Option<NestedInformationElement<&mut [u8]>> {
+        let range = self.nested_information_element_range(sub_id)?;
+        Some(NestedInformationElement::new_unchecked(
+            &mut self.data.as_mut()[range],
+        ))
+    }
+
+    /// Return the content of the first Nested Information Element with the
+    /// given [`NestedSubId`].
+    fn nested_content_mut(&mut self, sub_id: NestedSubId) -> Option<&mut [u8]> {
+        let range = self.nested_information_element_range(sub_id)?;
+        Some(&mut self.data.as_mut()[range.start + 2..range.end])
+    }
+
+    /// Return the TSCH Synchronization Nested Information Element as a
+    /// reader/writer.
+    ///
+    /// Returns `None` when it is not present or malformed.
+    pub fn tsch_synchronization_mut(&mut self) -> Option<TschSynchronization<&mut [u8]>> {
+        let content =
+            self.nested_content_mut(NestedSubId::Short(NestedSubIdShort::TschSynchronization))?;
+        TschSynchronization::new(content).ok()
+    }
+
+    /// Return the TSCH Timeslot Nested Information Element as a
+    /// reader/writer.
+    ///
+    /// Returns `None` when it is not present or malformed.
+    pub fn tsch_timeslot_mut(&mut self) -> Option<TschTimeslot<&mut [u8]>> {
+        let content =
+            self.nested_content_mut(NestedSubId::Short(NestedSubIdShort::TschTimeslot))?;
+        TschTimeslot::new(content).ok()
+    }
+
+    /// Return the TSCH Slotframe and Link Nested Information Element as a
+    /// reader/writer.
+    ///
+    /// Returns `None` when it is not present or malformed.
+    pub fn tsch_slotframe_and_link_mut(&mut self) -> Option<TschSlotframeAndLink<&mut [u8]>> {
+        let content =
+            self.nested_content_mut(NestedSubId::Short(NestedSubIdShort::TschSlotframeAndLink))?;
+        TschSlotframeAndLink::new(content).ok()
+    }
+
+    /// Return the Channel Hopping Nested Information Element as a
+    /// reader/writer.
+    ///
+    /// Returns `None` when it is not present or malformed.
+    pub fn channel_hopping_mut(&mut self) -> Option<ChannelHopping<&mut [u8]>> {
+        let content =
+            self.nested_content_mut(NestedSubId::Long(NestedSubIdLong::ChannelHopping))?;
+        ChannelHopping::new(content).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extents() {
+        // Header Termination 1, Payload Termination and 2 bytes of payload.
+        let data = [0x00, 0x3f, 0x00, 0xf8, 0xaa, 0xbb];
+        let ie = InformationElements::new(&data[..]).unwrap();
+
+        assert_eq!(ie.len(), 4);
+        assert_eq!(ie.header_information_elements().count(), 1);
+
+        let mut payloads = ie.payload_information_elements();
+        assert_eq!(
+            payloads.next().unwrap().group_id(),
+            PayloadGroupId::PayloadTermination
+        );
+        assert_eq!(payloads.next(), None);
+
+        // Only header information elements.
+        let ie = InformationElements::new(&data[..2]).unwrap();
+        assert_eq!(ie.len(), 2);
+        assert_eq!(ie.payload_information_elements().next(), None);
+    }
+
+    #[test]
+    fn typed_nested() {
+        // The Information Elements of an Enhanced Beacon
+        let data = [
+            0x00, 0x3f, 0x11, 0x88, 0x06, 0x1a, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x1c,
+            0x00, 0x01, 0xc8, 0x00, 0x01, 0x1b, 0x00,
+        ];
+        let ie = InformationElements::new(&data[..]).unwrap();
+
+        let sync = ie.tsch_synchronization().unwrap();
+        assert_eq!(sync.absolute_slot_number().value(), 14);
+        assert_eq!(sync.join_metric(), 0);
+        assert_eq!(ie.tsch_timeslot().unwrap().id(), 0);
+        assert_eq!(
+            ie.tsch_slotframe_and_link().unwrap().number_of_slotframes(),
+            0
+        );
+        assert_eq!(ie.channel_hopping().unwrap().hopping_sequence_id(), 0);
+
+        // Only header information elements
+        let ie = InformationElements::new(&data[..2]).unwrap();
+        assert!(ie.tsch_synchronization().is_none());
+        assert!(ie.channel_hopping().is_none());
+    }
+}
@@ -1,5 +1,6 @@
 use super::{Error, Result};
 use crate::time::Duration;
+use crate::AbsoluteSlotNumber;
 use bitflags::bitflags;
 
 /// A reader/writer for the IEEE 802.15.4 Nested Information Elements.
@@ -96,6 +97,14 @@ impl<T: AsRef<[u8]>> NestedInformationElement<T> {
     }
 }
 
+impl<'f> NestedInformationElement<&'f [u8]> {
+    /// Return the content of this Nested Information Element, borrowing from
+    /// the underlying buffer instead of from the reader.
+    pub fn into_content(self) -> &'f [u8] {
+        &self.data[2..][..self.length()]
+    }
+}
+
 impl<T: AsRef<[u8]> + AsMut<[u8]>> NestedInformationElement<T> {
     /// Clear the content of this Nested Information Element.
     pub fn clear(&mut self) {
@@ -400,14 +409,10 @@ impl<T: AsRef<[u8]>> TschSynchronization<T> {
     }
 
     /// Return the absolute slot number field.
-    pub fn absolute_slot_number(&self) -> u64 {
-        let data = self.data.as_ref();
-        let mut asn = data[0] as u64;
-        asn += (data[1] as u64) << 8;
-        asn += (data[2] as u64) << 16;
-        asn += (data[3] as u64) << 24;
-        asn += (data[4] as u64) << 32;
-        asn
+    pub fn absolute_slot_number(&self) -> AbsoluteSlotNumber {
+        let mut bytes = [0; 5];
+        bytes.copy_from_slice(&self.data.as_ref()[..5]);
+        AbsoluteSlotNumber::from_bytes(bytes)
     }
 
     /// Return the join metric field.
@@ -418,13 +423,8 @@ impl<T: AsRef<[u8]>> TschSynchronization<T> {
 
 impl<T: AsRef<[u8]> + AsMut<[u8]>> TschSynchronization<T> {
     /// Set the absolute slot number field.
-    pub fn set_absolute_slot_number(&mut self, asn: u64) {
-        let data = self.data.as_mut();
-        data[0] = (asn & 0xff) as u8;
-        data[1] = ((asn >> 8) & 0xff) as u8;
-        data[2] = ((asn >> 16) & 0xff) as u8;
-        data[3] = ((asn >> 24) & 0xff) as u8;
-        data[4] = ((asn >> 32) & 0xff) as u8;
+    pub fn set_absolute_slot_number(&mut self, asn: AbsoluteSlotNumber) {
+        self.data.as_mut()[..5].copy_from_slice(&asn.to_bytes());
     }
 
     /// Set the join metric field.
@@ -484,7 +484,8 @@ impl<T: AsRef<[u8]>> TschTimeslot<T> {
             return len >= 1;
         }
 
-        len >= 25
+        // The last two timing values are either 2 or 3 bytes long
+        len == 25 || len >= 27
     }
 
     /// Create a new [`TschTimeslot`] reader/writer from a given buffer without
@@ -624,7 +625,8 @@ impl<T: AsRef<[u8]>> core::fmt::Display for TschTimeslot<T> {
 /// | ID | CCA offset | CCA | TX offset | RX offset | RX ACK delay | TX ACK delay | RX wait | ACK wait | RX/TX | Max ACK | Max TX | Timeslot length |
 /// +----+------------+-----+-----------+-----------+--------------+--------------+---------+----------+-------+---------+--------+------------------+
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TschTimeslotTimings {
     id: u8,
     /// Offset from the start of the timeslot to the start of the CCA in
@@ -1024,6 +1026,23 @@ impl<T: AsRef<[u8]> + AsMut<[u8]>> SlotframeDescriptor<T> {
     pub fn set_number_of_links(&mut self, number_of_links: u8) {
         self.data.as_mut()[3] = number_of_links;
     }
+
+    /// Return a writer for the link information at `index`.
+    ///
+    /// Returns `None` when `index` is not smaller than the links field, or
+    /// when the buffer is too short.
+    pub fn link_information_mut(&mut self, index: usize) -> Option<LinkInformation<&mut [u8]>> {
+        if index >= self.links() as usize {
+            return None;
+        }
+
+        let len = LinkInformation::<&[u8]>::len();
+        self.data
+            .as_mut()
+            .get_mut(4 + index * len..)?
+            .get_mut(..len)
+            .map(LinkInformation::new_unchecked)
+    }
 }
 
 impl<T: AsRef<[u8]> + AsMut<[u8]> + ?Sized> SlotframeDescriptor<&mut T> {
@@ -1180,7 +1199,7 @@ impl<'f> LinkInformationIterator<'f> {
         Self {
             data,
             offset: 0,
-            terminated: false,
+            terminated: data.is_empty(),
         }
     }
 }
@@ -1193,9 +1212,14 @@ impl<'f> Iterator for LinkInformationIterator<'f> {
             return None;
         }
 
-        let information = LinkInformation::new(&self.data[self.offset..]);
+        let len = LinkInformation::<&[u8]>::len();
+        let Some(data) = self.data.get(self.offset..self.offset + len) else {
+            self.terminated = true;
+            return None;
+        };
+        let information = LinkInformation::new(data);
 
-        self.offset += LinkInformation::<&[u8]>::len();
+        self.offset += len;
         self.terminated = self.offset >= self.data.as_ref().len();
 
         Some(information)
@@ -1209,7 +1233,7 @@ bitflags! {
     /// | Tx | Rx | Shared | Time keeping | Priority | Reserved |
     /// +----+----+--------+--------------+----------+----------+
     /// ```
-    #[derive(Copy, Clone)]
+    #[derive(Copy, Clone, PartialEq, Eq)]
     pub struct TschLinkOption: u8 {
         /// Transmit.
         const Tx = 0b0000_0001;
@@ -1224,6 +1248,14 @@ bitflags! {
     }
 }
 
+impl TschLinkOption {
+    /// Returns `true` when the link is used for transmitting, but not for
+    /// receiving.
+    pub fn is_tx_only(&self) -> bool {
+        self.contains(Self::Tx) && !self.contains(Self::Rx)
+    }
+}
+
 impl core::fmt::Debug for TschLinkOption {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         bitflags::parser::to_writer(self, f)
@@ -1294,6 +1326,183 @@ impl<T: AsRef<[u8]>> core::fmt::Display for ChannelHopping<T> {
     }
 }
 
+/// A reader/writer for the Link Margin IE.
+/// ```notrust
+/// +-------------+
+/// | Link margin |
+/// +-------------+
+/// 0             1
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkMargin<T: AsRef<[u8]>> {
+    data: T,
+}
+
+impl<T: AsRef<[u8]>> LinkMargin<T> {
+    /// Create a new [`LinkMargin`] reader/writer from a given buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too small.
+    pub fn new(data: T) -> Result<Self> {
+        let lm = Self::new_unchecked(data);
+
+        if !lm.check_len() {
+            return Err(Error);
+        }
+
+        Ok(lm)
+    }
+
+    /// Return `false` if the buffer is too small.
+    fn check_len(&self) -> bool {
+        !self.data.as_ref().is_empty()
+    }
+
+    /// Create a new [`LinkMargin`] reader/writer from a given buffer without
+    /// checking the length.
+    pub fn new_unchecked(data: T) -> Self {
+        Self { data }
+    }
+
+    /// Return the link margin field, in dB.
+    pub fn link_margin(&self) -> i8 {
+        self.data.as_ref()[0] as i8
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> LinkMargin<T> {
+    /// Set the link margin field, in dB.
+    pub fn set_link_margin(&mut self, link_margin: i8) {
+        self.data.as_mut()[0] = link_margin as u8;
+    }
+}
+
+impl<T: AsRef<[u8]>> core::fmt::Display for LinkMargin<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "link margin: {} dB", self.link_margin())
+    }
+}
+
+/// A reader/writer for the Enhanced Beacon Filter IE, carried by an Enhanced
+/// Beacon Request.
+/// ```notrust
+/// +--------+--------------+----------------+
+/// | Filter | Link quality | Percent filter |
+/// +--------+--------------+----------------+
+/// 0        1              1/2
+/// ```
+/// The link quality and percent filter fields are only present when the
+/// respective bits of the filter field are set. PIB attribute filters are not
+/// supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnhancedBeaconFilter<T: AsRef<[u8]>> {
+    data: T,
+}
+
+impl<T: AsRef<[u8]>> EnhancedBeaconFilter<T> {
+    /// Create a new [`EnhancedBeaconFilter`] reader/writer from a given
+    /// buffer.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer is too small.
+    pub fn new(data: T) -> Result<Self> {
+        let filter = Self::new_unchecked(data);
+
+        if !filter.check_len() {
+            return Err(Error);
+        }
+
+        Ok(filter)
+    }
+
+    /// Return `false` if the buffer is too small.
+    fn check_len(&self) -> bool {
+        !self.data.as_ref().is_empty() && self.data.as_ref().len() >= self.len()
+    }
+
+    /// Create a new [`EnhancedBeaconFilter`] reader/writer from a given
+    /// buffer without checking the length.
+    pub fn new_unchecked(data: T) -> Self {
+        Self { data }
+    }
+
+    /// Return the length of the Enhanced Beacon Filter IE in bytes.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        1 + self.has_link_quality() as usize + self.has_percent_filter() as usize
+    }
+
+    /// Returns `true` when only coordinators that permit joining should
+    /// respond.
+    pub fn permit_joining_on(&self) -> bool {
+        self.data.as_ref()[0] & 0b001 != 0
+    }
+
+    /// Returns `true` when the link quality field is present.
+    pub fn has_link_quality(&self) -> bool {
+        self.data.as_ref()[0] & 0b010 != 0
+    }
+
+    /// Returns `true` when the percent filter field is present.
+    pub fn has_percent_filter(&self) -> bool {
+        self.data.as_ref()[0] & 0b100 != 0
+    }
+
+    /// Return the minimum link quality of the Enhanced Beacon Request at
+    /// which a coordinator should respond.
+    pub fn link_quality(&self) -> Option<u8> {
+        self.has_link_quality().then(|| self.data.as_ref()[1])
+    }
+
+    /// Return the probability, in percent, with which a coordinator should
+    /// respond.
+    pub fn percent_filter(&self) -> Option<u8> {
+        self.has_percent_filter()
+            .then(|| self.data.as_ref()[1 + self.has_link_quality() as usize])
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> EnhancedBeaconFilter<T> {
+    /// Set the filter field and the fields that follow it.
+    pub fn set_filter(
+        &mut self,
+        permit_joining_on: bool,
+        link_quality: Option<u8>,
+        percent_filter: Option<u8>,
+    ) {
+        let data = self.data.as_mut();
+        data[0] = permit_joining_on as u8
+            | (link_quality.is_some() as u8) << 1
+            | (percent_filter.is_some() as u8) << 2;
+
+        for (octet, field) in data[1..]
+            .iter_mut()
+            .zip([link_quality, percent_filter].into_iter().flatten())
+        {
+            *octet = field;
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>> core::fmt::Display for EnhancedBeaconFilter<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(
+            f,
+            "permit joining on: {}",
+            self.permit_joining_on() as usize
+        )?;
+        if let Some(link_quality) = self.link_quality() {
+            write!(f, ", link quality: {link_quality}")?;
+        }
+        if let Some(percent_filter) = self.percent_filter() {
+            write!(f, ", percent filter: {percent_filter}%")?;
+        }
+        Ok(())
+    }
+}
+
 /// An [`Iterator`] over [`NestedInformationElement`].
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub struct NestedInformationElementsIterator<'f> {
@@ -1311,6 +1520,11 @@ impl<'f> NestedInformationElementsIterator<'f> {
             terminated: false,
         }
     }
+
+    /// Returns the offset of the next Nested Information Element.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
 }
 
 impl<'f> Iterator for NestedInformationElementsIterator<'f> {
@@ -14,7 +14,7 @@ impl<T: AsRef<[u8]>> PayloadInformationElement<T> {
     /// # Errors
     ///
     /// Returns an error if the buffer is too short to contain a payload
-    /// information element.
+    /// information element and its content.
     pub fn new(data: T) -> Result<Self> {
         let ie = Self::new_unchecked(data);
 
@@ -28,7 +28,8 @@ impl<T: AsRef<[u8]>> PayloadInformationElement<T> {
     /// Returns `false` if the buffer is too short to contain a payload
     /// information element.
     fn check_len(&self) -> bool {
-        self.data.as_ref().len() >= 2
+        let len = self.data.as_ref().len();
+        len >= 2 && len >= 2 + self.length()
     }
 
     /// Create a new [`PayloadInformationElement`] reader/writer from a given
@@ -40,13 +41,13 @@ impl<T: AsRef<[u8]>> PayloadInformationElement<T> {
     /// Return the length field value (which is the lenght of the content field).
     pub fn length(&self) -> usize {
         let b = &self.data.as_ref()[0..2];
-        u16::from_le_bytes([b[0], b[1]]) as usize & 0b1111111111
+        u16::from_le_bytes([b[0], b[1]]) as usize & 0b111_1111_1111
     }
 
     /// Return the [`PayloadGroupId`].
     pub fn group_id(&self) -> PayloadGroupId {
         let b = &self.data.as_ref()[0..2];
-        let id = (u16::from_le_bytes([b[0], b[1]]) >> 11) & 0b111;
+        let id = (u16::from_le_bytes([b[0], b[1]]) >> 11) & 0b1111;
         PayloadGroupId::from(id as u8)
     }
 
@@ -68,6 +69,14 @@ impl<T: AsRef<[u8]>> PayloadInformationElement<T> {
     }
 }
 
+impl<'f> PayloadInformationElement<&'f [u8]> {
+    /// Return the content of this Payload Information Element, borrowing from
+    /// the underlying buffer instead of from the reader.
+    pub fn into_content(self) -> &'f [u8] {
+        &self.data[2..][..self.length()]
+    }
+}
+
 impl<T: AsRef<[u8]> + AsMut<[u8]>> PayloadInformationElement<T> {
     /// Clear the content of this Header Information Element.
     pub fn clear(&mut self) {
@@ -181,3 +190,20 @@ impl<'f> Iterator for PayloadInformationElementsIterator<'f> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_and_group_id() {
+        // The length is 11 bits and the group ID 4 bits wide.
+        let ie = PayloadInformationElement::new_unchecked([0x01, 0x8c]);
+        assert_eq!(ie.length(), 1025);
+        assert_eq!(ie.group_id(), PayloadGroupId::Mlme);
+
+        let ie = PayloadInformationElement::new_unchecked([0x00, 0xf8]);
+        assert_eq!(ie.length(), 0);
+        assert_eq!(ie.group_id(), PayloadGroupId::PayloadTermination);
+    }
+}
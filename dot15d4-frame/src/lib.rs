@@ -44,6 +44,10 @@
 //!
 //! let Some(ie) = frame.information_elements() else { return; };
 //!
+//! let timeslot = ie.tsch_timeslot().unwrap();
+//! assert_eq!(timeslot.id(), 0);
+//!
+//! // Which is the same as:
 //! for payload in ie.payload_information_elements() {
 //!      if matches!(payload.group_id(), PayloadGroupId::Mlme) {
 //!         for nested in payload.nested_information_elements() {
@@ -108,7 +112,9 @@
 //! - [ ] `Esdu`
 //! - [x] `Mlme`: The MLME group contains a set of nested IEs. Call
 //!   [`nested_information_elements`]
-//!   to get an iterator over the nested IEs.
+//!   to get an iterator over the nested IEs. The [`InformationElements`]
+//!   reader also returns the TSCH and Channel Hopping nested IEs directly,
+//!   e.g. with [`tsch_synchronization`].
 //! - [ ] `VendorSpecific`
 //! - [ ] `PayloadTermination`
 //!
@@ -125,7 +131,7 @@
 //! - [x] [`TschSlotframeAndLink`]
 //! - [x] [`TschTimeslot`]
 //! - [ ] `HoppingTiming`
-//! - [ ] `EnhancedBeaconFilter`
+//! - [x] [`EnhancedBeaconFilter`]
 //! - [ ] `MacMetrics`
 //! - [ ] `AllMacMetrics`
 //! - [ ] `CoexistenceSpecification`
@@ -149,7 +155,7 @@
 //! - [ ] `TimestampDifference`
 //! - [ ] `TmctpSpecification`
 //! - [ ] `RccPhyOperatingMode`
-//! - [ ] `LinkMargin`
+//! - [x] [`LinkMargin`]
 //! - [ ] `RsGfskDeviceCapabilities`
 //! - [ ] `MultiPhy`
 //! - [ ] `VendorSpecific`
@@ -174,6 +180,7 @@
 //! [`header_information_elements`]: InformationElements::header_information_elements
 //! [`payload_information_elements`]: InformationElements::payload_information_elements
 //! [`nested_information_elements`]: PayloadInformationElement::nested_information_elements
+//! [`tsch_synchronization`]: InformationElements::tsch_synchronization
 #![no_std]
 #![deny(missing_docs)]
 #![deny(unsafe_code)]
@@ -187,14 +194,20 @@ extern crate std;
 mod tests;
 
 pub mod frames;
+#[cfg(feature = "legacy-beacons")]
 pub use frames::Beacon;
+pub use frames::CommandId;
 pub use frames::DataFrame;
 pub use frames::EnhancedBeacon;
 pub use frames::Frame;
 pub use frames::FrameWithFcs;
+pub use frames::PpduIterator;
 
 mod time;
 
+mod asn;
+pub use asn::*;
+
 mod frame_control;
 pub use frame_control::*;
 
@@ -210,6 +223,12 @@ pub use ie::*;
 mod repr;
 pub use repr::*;
 
+mod validate;
+pub use validate::*;
+
+#[cfg(any(feature = "hex", test))]
+pub mod hex;
+
 /// An error that can occur when reading or writing an IEEE 802.15.4 frame.
 #[derive(Debug, Clone, Copy)]
 pub struct Error;
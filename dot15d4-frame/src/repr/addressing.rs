@@ -1,10 +1,31 @@
 use super::FrameControlRepr;
 
-use crate::{Address, AddressingFields, AddressingMode, Error, FrameType, Result};
+use crate::{Address, AddressingFields, AddressingMode, Error, FrameType, FrameVersion, Result};
+
+/// The Frame Control bits that depend on the Addressing Fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressingControlRepr {
+    /// Destination addressing mode.
+    pub dst_addressing_mode: AddressingMode,
+    /// Source addressing mode.
+    pub src_addressing_mode: AddressingMode,
+    /// PAN ID compression.
+    pub pan_id_compression: bool,
+}
+
+impl AddressingControlRepr {
+    /// Set the addressing related bits in the given [`FrameControlRepr`].
+    pub fn apply(&self, fc: &mut FrameControlRepr) {
+        fc.dst_addressing_mode = self.dst_addressing_mode;
+        fc.src_addressing_mode = self.src_addressing_mode;
+        fc.pan_id_compression = self.pan_id_compression;
+    }
+}
 
 /// A high-level representation of the IEEE 802.15.4 Addressing Fields.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct AddressingFieldsRepr {
     /// Destination PAN identifier.
     pub dst_pan_id: Option<u16>,
@@ -17,6 +38,126 @@ pub struct AddressingFieldsRepr {
 }
 
 impl AddressingFieldsRepr {
+    /// Create the Addressing Fields for the given addresses and PAN IDs.
+    ///
+    /// The PAN IDs that should be elided are removed, and the addressing modes
+    /// and PAN ID compression bit that need to be set in the Frame Control
+    /// field are returned alongside.
+    ///
+    /// An absent address can be given as `None` or as [`Address::Absent`].
+    /// When both addresses are present and only one PAN ID is given, both
+    /// devices are assumed to be in the same PAN.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the combination of addresses and PAN IDs cannot
+    /// be represented in a frame of the given version.
+    pub fn from_addresses(
+        frame_version: FrameVersion,
+        dst_pan_id: Option<u16>,
+        dst_address: Option<Address>,
+        src_pan_id: Option<u16>,
+        src_address: Option<Address>,
+    ) -> Result<(Self, AddressingControlRepr)> {
+        use Address::*;
+
+        let dst_address = dst_address.filter(|addr| !addr.is_absent());
+        let src_address = src_address.filter(|addr| !addr.is_absent());
+
+        let (dst_pan_id, src_pan_id, pan_id_compression) = match frame_version {
+            #[cfg(feature = "legacy-frames")]
+            FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => {
+                match (dst_address, src_address, dst_pan_id, src_pan_id) {
+                    (Some(_), Some(_), Some(dst), Some(src)) if dst != src => {
+                        (Some(dst), Some(src), false)
+                    }
+                    (Some(_), Some(_), Some(pan_id), _)
+                    | (Some(_), Some(_), None, Some(pan_id)) => (Some(pan_id), None, true),
+                    (Some(_), None, Some(dst), _) => (Some(dst), None, false),
+                    (None, Some(_), _, Some(src)) => (None, Some(src), false),
+                    _ => return Err(Error),
+                }
+            }
+            FrameVersion::Ieee802154_2020 => {
+                match (dst_address, src_address, dst_pan_id, src_pan_id) {
+                    (None, None, None, None) => (None, None, false),
+                    (None, None, Some(dst), None) => (Some(dst), None, true),
+                    (Some(_), None, Some(dst), None) => (Some(dst), None, false),
+                    (Some(_), None, None, None) => (None, None, true),
+                    (None, Some(_), None, Some(src)) => (None, Some(src), false),
+                    (None, Some(_), None, None) => (None, None, true),
+                    (Some(Extended(_)), Some(Extended(_)), None, None) => (None, None, true),
+                    (Some(Extended(_)), Some(Extended(_)), Some(dst), Some(src)) if dst != src => {
+                        return Err(Error)
+                    }
+                    (Some(Extended(_)), Some(Extended(_)), Some(pan_id), _)
+                    | (Some(Extended(_)), Some(Extended(_)), None, Some(pan_id)) => {
+                        (Some(pan_id), None, false)
+                    }
+                    (Some(_), Some(_), Some(dst), Some(src)) if dst != src => {
+                        (Some(dst), Some(src), false)
+                    }
+                    (Some(_), Some(_), Some(pan_id), _)
+                    | (Some(_), Some(_), None, Some(pan_id)) => (Some(pan_id), None, true),
+                    _ => return Err(Error),
+                }
+            }
+            _ => return Err(Error),
+        };
+
+        let control = AddressingControlRepr {
+            dst_addressing_mode: dst_address
+                .map(AddressingMode::from)
+                .unwrap_or(AddressingMode::Absent),
+            src_addressing_mode: src_address
+                .map(AddressingMode::from)
+                .unwrap_or(AddressingMode::Absent),
+            pan_id_compression,
+        };
+
+        Ok((
+            Self {
+                dst_pan_id,
+                dst_address,
+                src_pan_id,
+                src_address,
+            },
+            control,
+        ))
+    }
+
+    /// Create the Addressing Fields of an Enhanced Acknowledgment of a frame
+    /// with these Addressing Fields, together with the Frame Control bits.
+    ///
+    /// The acknowledgment is addressed to the source address of the frame, in
+    /// the PAN of the originator: the source PAN ID of the frame, or its
+    /// destination PAN ID when both are in the same PAN. With
+    /// `include_source`, the destination address of the frame is used as
+    /// source address, unless it is a broadcast address. Without a source
+    /// address in the frame, the acknowledgment has no addressing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the addressing of the acknowledgment cannot be
+    /// represented in an IEEE 802.15.4-2020 frame.
+    pub fn acknowledgment(&self, include_source: bool) -> Result<(Self, AddressingControlRepr)> {
+        let dst_address = self.src_address.filter(|addr| !addr.is_absent());
+        if dst_address.is_none() {
+            return Self::from_addresses(FrameVersion::Ieee802154_2020, None, None, None, None);
+        }
+
+        let src_address = self
+            .dst_address
+            .filter(|addr| include_source && !addr.is_absent() && !addr.is_broadcast());
+        Self::from_addresses(
+            FrameVersion::Ieee802154_2020,
+            self.src_pan_id.or(self.dst_pan_id),
+            dst_address,
+            None,
+            src_address,
+        )
+    }
+
     /// Parse the Addressing Fields from the given buffer.
     pub fn parse(addressing: AddressingFields<&'_ [u8], &'_ [u8]>) -> Self {
         Self {
@@ -66,8 +207,290 @@ impl AddressingFieldsRepr {
         }
     }
 
+    /// Return the length of the Addressing Fields in octets, when emitted in a
+    /// frame of the given version. PAN IDs that are elided in that version
+    /// are not counted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the combination of addresses and PAN IDs cannot
+    /// be represented in a frame of the given version.
+    pub fn buffer_len_for(&self, frame_version: FrameVersion) -> Result<usize> {
+        let (fields, _) = Self::from_addresses(
+            frame_version,
+            self.dst_pan_id,
+            self.dst_address,
+            self.src_pan_id,
+            self.src_address,
+        )?;
+        let pan_id_len = |pan_id: Option<u16>| if pan_id.is_some() { 2 } else { 0 };
+        let address_len = |address: Option<Address>| address.map_or(0, |address| address.len());
+
+        Ok(pan_id_len(fields.dst_pan_id)
+            + address_len(fields.dst_address)
+            + pan_id_len(fields.src_pan_id)
+            + address_len(fields.src_address))
+    }
+
     /// Emit the Addressing Fields into the given buffer.
     pub fn emit(&self, _buffer: &AddressingFields<&'_ mut [u8], &'_ [u8]>) {
         todo!();
     }
 }
+
+impl core::fmt::Display for AddressingFieldsRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        let broadcast = |addr: &Address| {
+            if addr.is_broadcast() {
+                " (broadcast)"
+            } else {
+                ""
+            }
+        };
+
+        if let Some(dst_pan_id) = self.dst_pan_id {
+            writeln!(f, "{:indent$}dst pan id: {:x}", "", dst_pan_id)?;
+        }
+
+        if let Some(dst_addr) = &self.dst_address {
+            writeln!(
+                f,
+                "{:indent$}dst addr: {}{}",
+                "",
+                dst_addr,
+                broadcast(dst_addr)
+            )?;
+        }
+
+        if let Some(src_pan_id) = self.src_pan_id {
+            writeln!(f, "{:indent$}src pan id: {:x}", "", src_pan_id)?;
+        }
+
+        if let Some(src_addr) = &self.src_address {
+            writeln!(
+                f,
+                "{:indent$}src addr: {}{}",
+                "",
+                src_addr,
+                broadcast(src_addr)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHORT: Address = Address::Short([0x12, 0x34]);
+    const EXTENDED: Address = Address::Extended([0x01; 8]);
+
+    /// Check that the derived Frame Control bits describe exactly the PAN IDs
+    /// that are kept.
+    fn check(version: FrameVersion, repr: &AddressingFieldsRepr, control: &AddressingControlRepr) {
+        let mut fc = [0u8; 2];
+        let mut w = crate::FrameControl::new_unchecked(&mut fc[..]);
+        w.set_frame_version(version);
+        w.set_dst_addressing_mode(control.dst_addressing_mode);
+        w.set_src_addressing_mode(control.src_addressing_mode);
+        w.set_pan_id_compression(control.pan_id_compression);
+
+        let buffer = [0u8; 20];
+        let fields =
+            AddressingFields::new(&buffer[..], crate::FrameControl::new_unchecked(&fc[..]))
+                .unwrap();
+        assert_eq!(fields.dst_pan_id().is_some(), repr.dst_pan_id.is_some());
+        assert_eq!(fields.src_pan_id().is_some(), repr.src_pan_id.is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "legacy-frames")]
+    fn from_addresses_2006() {
+        use FrameVersion::Ieee802154_2006 as V;
+
+        let (repr, control) =
+            AddressingFieldsRepr::from_addresses(V, Some(1), Some(SHORT), Some(1), Some(EXTENDED))
+                .unwrap();
+        assert!(control.pan_id_compression);
+        assert_eq!(repr.dst_pan_id, Some(1));
+        assert_eq!(repr.src_pan_id, None);
+        check(V, &repr, &control);
+
+        let (repr, control) =
+            AddressingFieldsRepr::from_addresses(V, Some(1), Some(SHORT), Some(2), Some(SHORT))
+                .unwrap();
+        assert!(!control.pan_id_compression);
+        assert_eq!(repr.src_pan_id, Some(2));
+        check(V, &repr, &control);
+
+        let (repr, control) =
+            AddressingFieldsRepr::from_addresses(V, None, None, Some(2), Some(EXTENDED)).unwrap();
+        assert!(!control.pan_id_compression);
+        assert_eq!(control.dst_addressing_mode, AddressingMode::Absent);
+        assert_eq!(control.src_addressing_mode, AddressingMode::Extended);
+        check(V, &repr, &control);
+
+        assert!(AddressingFieldsRepr::from_addresses(V, None, None, None, None).is_err());
+        assert!(
+            AddressingFieldsRepr::from_addresses(V, None, Some(SHORT), None, Some(SHORT)).is_err()
+        );
+        assert!(AddressingFieldsRepr::from_addresses(V, None, Some(SHORT), None, None).is_err());
+    }
+
+    #[test]
+    fn from_addresses_2020() {
+        use FrameVersion::Ieee802154_2020 as V;
+
+        let (repr, control) = AddressingFieldsRepr::from_addresses(
+            V,
+            Some(1),
+            Some(EXTENDED),
+            Some(1),
+            Some(EXTENDED),
+        )
+        .unwrap();
+        assert!(!control.pan_id_compression);
+        assert_eq!(repr.dst_pan_id, Some(1));
+        assert_eq!(repr.src_pan_id, None);
+        check(V, &repr, &control);
+
+        let (repr, control) =
+            AddressingFieldsRepr::from_addresses(V, None, Some(EXTENDED), None, Some(EXTENDED))
+                .unwrap();
+        assert!(control.pan_id_compression);
+        check(V, &repr, &control);
+
+        let (repr, control) =
+            AddressingFieldsRepr::from_addresses(V, Some(1), Some(SHORT), None, Some(EXTENDED))
+                .unwrap();
+        assert!(control.pan_id_compression);
+        check(V, &repr, &control);
+
+        let (repr, control) =
+            AddressingFieldsRepr::from_addresses(V, Some(1), Some(SHORT), Some(2), Some(EXTENDED))
+                .unwrap();
+        assert!(!control.pan_id_compression);
+        check(V, &repr, &control);
+
+        let (repr, control) =
+            AddressingFieldsRepr::from_addresses(V, None, Some(Address::BROADCAST), None, None)
+                .unwrap();
+        assert!(control.pan_id_compression);
+        check(V, &repr, &control);
+
+        let (repr, control) =
+            AddressingFieldsRepr::from_addresses(V, Some(1), None, None, None).unwrap();
+        assert!(control.pan_id_compression);
+        check(V, &repr, &control);
+
+        assert!(AddressingFieldsRepr::from_addresses(
+            V,
+            Some(1),
+            Some(EXTENDED),
+            Some(2),
+            Some(EXTENDED)
+        )
+        .is_err());
+        assert!(
+            AddressingFieldsRepr::from_addresses(V, None, Some(SHORT), None, Some(SHORT)).is_err()
+        );
+        assert!(AddressingFieldsRepr::from_addresses(V, None, None, Some(1), None).is_err());
+    }
+
+    #[test]
+    fn acknowledgment() {
+        let received = AddressingFieldsRepr {
+            dst_pan_id: Some(1),
+            dst_address: Some(SHORT),
+            src_pan_id: None,
+            src_address: Some(EXTENDED),
+        };
+
+        let (repr, control) = received.acknowledgment(false).unwrap();
+        assert_eq!(
+            repr,
+            AddressingFieldsRepr {
+                dst_pan_id: Some(1),
+                dst_address: Some(EXTENDED),
+                src_pan_id: None,
+                src_address: None,
+            }
+        );
+        assert_eq!(control.dst_addressing_mode, AddressingMode::Extended);
+        assert_eq!(control.src_addressing_mode, AddressingMode::Absent);
+        assert!(!control.pan_id_compression);
+        check(FrameVersion::Ieee802154_2020, &repr, &control);
+
+        // The source and destination are swapped, in the PAN of the originator
+        let (repr, control) = AddressingFieldsRepr {
+            src_pan_id: Some(2),
+            ..received
+        }
+        .acknowledgment(true)
+        .unwrap();
+        assert_eq!(
+            repr,
+            AddressingFieldsRepr {
+                dst_pan_id: Some(2),
+                dst_address: Some(EXTENDED),
+                src_pan_id: None,
+                src_address: Some(SHORT),
+            }
+        );
+        assert!(control.pan_id_compression);
+        check(FrameVersion::Ieee802154_2020, &repr, &control);
+
+        // A broadcast address is never used as source address
+        let (repr, _) = AddressingFieldsRepr {
+            dst_address: Some(Address::BROADCAST),
+            ..received
+        }
+        .acknowledgment(true)
+        .unwrap();
+        assert_eq!(repr.src_address, None);
+
+        // Without a source address, there is nobody to address
+        let (repr, control) = AddressingFieldsRepr {
+            src_address: None,
+            ..received
+        }
+        .acknowledgment(true)
+        .unwrap();
+        assert_eq!(repr, AddressingFieldsRepr::default());
+        assert_eq!(control.dst_addressing_mode, AddressingMode::Absent);
+    }
+
+    #[test]
+    #[cfg(feature = "legacy-frames")]
+    fn buffer_len_for() {
+        let repr = AddressingFieldsRepr {
+            dst_pan_id: Some(1),
+            dst_address: Some(SHORT),
+            src_pan_id: Some(1),
+            src_address: Some(EXTENDED),
+        };
+        assert_eq!(
+            repr.buffer_len_for(FrameVersion::Ieee802154_2006).unwrap(),
+            12
+        );
+        assert_eq!(
+            repr.buffer_len_for(FrameVersion::Ieee802154_2020).unwrap(),
+            12
+        );
+
+        let repr = AddressingFieldsRepr {
+            dst_pan_id: None,
+            dst_address: Some(EXTENDED),
+            src_pan_id: None,
+            src_address: Some(EXTENDED),
+        };
+        assert!(repr.buffer_len_for(FrameVersion::Ieee802154_2006).is_err());
+        assert_eq!(
+            repr.buffer_len_for(FrameVersion::Ieee802154_2020).unwrap(),
+            16
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{AuxiliarySecurityHeader, KeyIdentifierMode, SecurityAttributes};
+use crate::{Error, Result};
+
+/// A high-level representation of an IEEE 802.15.4 Auxiliary Security Header.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AuxiliarySecurityHeaderRepr {
+    /// The security level.
+    pub security_level: SecurityAttributes,
+    /// The frame counter, `None` when suppressed.
+    pub frame_counter: Option<u32>,
+    /// Whether the ASN is used in the nonce instead of the frame counter.
+    pub asn_in_nonce: bool,
+    /// The key identifier.
+    pub key_identifier: KeyIdentifierRepr,
+}
+
+/// A high-level representation of the Key Identifier field, for each of the
+/// four key identifier modes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum KeyIdentifierRepr {
+    /// The key is determined implicitly from the originator and recipient.
+    Implicit,
+    /// The key is determined from the key index.
+    Index(u8),
+    /// The key is determined from a 4-octet key source and the key index.
+    ShortSource([u8; 4], u8),
+    /// The key is determined from an 8-octet key source and the key index.
+    LongSource([u8; 8], u8),
+}
+
+impl KeyIdentifierRepr {
+    /// Return the Key Identifier Mode.
+    pub fn mode(&self) -> KeyIdentifierMode {
+        match self {
+            Self::Implicit => KeyIdentifierMode::Implicit,
+            Self::Index(_) => KeyIdentifierMode::Explicit,
+            Self::ShortSource(..) => KeyIdentifierMode::ExplicitWithShortSource,
+            Self::LongSource(..) => KeyIdentifierMode::ExplicitWithLongSource,
+        }
+    }
+
+    /// Return the key index, if present.
+    pub fn index(&self) -> Option<u8> {
+        match self {
+            Self::Implicit => None,
+            Self::Index(index) | Self::ShortSource(_, index) | Self::LongSource(_, index) => {
+                Some(*index)
+            }
+        }
+    }
+
+    /// Return the key source, which is empty when not present.
+    pub fn source(&self) -> &[u8] {
+        match self {
+            Self::Implicit | Self::Index(_) => &[],
+            Self::ShortSource(source, _) => &source[..],
+            Self::LongSource(source, _) => &source[..],
+        }
+    }
+
+    /// Return the length of the Key Identifier field.
+    pub fn buffer_len(&self) -> usize {
+        self.source().len() + self.index().is_some() as usize
+    }
+}
+
+impl AuxiliarySecurityHeaderRepr {
+    /// Parse an Auxiliary Security Header.
+    pub fn parse<T: AsRef<[u8]>>(header: &AuxiliarySecurityHeader<T>) -> Result<Self> {
+        if !header.check_len() {
+            return Err(Error);
+        }
+
+        let control = header.security_control();
+        let source = header.key_source();
+
+        let key_identifier = match control.key_identifier_mode().key_identifier_mode() {
+            KeyIdentifierMode::Implicit => KeyIdentifierRepr::Implicit,
+            KeyIdentifierMode::Explicit => {
+                KeyIdentifierRepr::Index(header.key_index().ok_or(Error)?)
+            }
+            KeyIdentifierMode::ExplicitWithShortSource => KeyIdentifierRepr::ShortSource(
+                source.try_into().map_err(|_| Error)?,
+                header.key_index().ok_or(Error)?,
+            ),
+            KeyIdentifierMode::ExplicitWithLongSource => KeyIdentifierRepr::LongSource(
+                source.try_into().map_err(|_| Error)?,
+                header.key_index().ok_or(Error)?,
+            ),
+            KeyIdentifierMode::Unknown => return Err(Error),
+        };
+
+        let security_level = control.security_level().security_attributes();
+        if security_level == SecurityAttributes::Unknown {
+            return Err(Error);
+        }
+
+        Ok(Self {
+            security_level,
+            frame_counter: header.frame_counter(),
+            asn_in_nonce: control.asn_in_nonce(),
+            key_identifier,
+        })
+    }
+
+    /// Return the length of the Auxiliary Security Header when emitted into a
+    /// buffer.
+    pub fn buffer_len(&self) -> usize {
+        1 + if self.frame_counter.is_some() { 4 } else { 0 } + self.key_identifier.buffer_len()
+    }
+
+    /// Emit the Auxiliary Security Header into a buffer.
+    pub fn emit(&self, buffer: &mut [u8]) {
+        let mut control = self.security_level as u8;
+        control |= (self.key_identifier.mode() as u8) << 3;
+        control |= (self.frame_counter.is_none() as u8) << 5;
+        control |= (self.asn_in_nonce as u8) << 6;
+        buffer[0] = control;
+
+        let mut offset = 1;
+        if let Some(frame_counter) = self.frame_counter {
+            buffer[offset..offset + 4].copy_from_slice(&frame_counter.to_le_bytes());
+            offset += 4;
+        }
+
+        let source = self.key_identifier.source();
+        buffer[offset..offset + source.len()].copy_from_slice(source);
+        offset += source.len();
+
+        if let Some(index) = self.key_identifier.index() {
+            buffer[offset] = index;
+        }
+    }
+}
+
+impl core::fmt::Display for AuxiliarySecurityHeaderRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+
+        writeln!(f, "{:indent$}security level: {:?}", "", self.security_level)?;
+        if let Some(frame_counter) = self.frame_counter {
+            writeln!(f, "{:indent$}frame counter: {}", "", frame_counter)?;
+        }
+        writeln!(
+            f,
+            "{:indent$}asn in nonce: {}",
+            "", self.asn_in_nonce as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}key identifier mode: {:?}",
+            "",
+            self.key_identifier.mode()
+        )?;
+        if !self.key_identifier.source().is_empty() {
+            writeln!(
+                f,
+                "{:indent$}key source: {:x?}",
+                "",
+                self.key_identifier.source()
+            )?;
+        }
+        if let Some(index) = self.key_identifier.index() {
+            writeln!(f, "{:indent$}key index: {}", "", index)?;
+        }
+
+        Ok(())
+    }
+}
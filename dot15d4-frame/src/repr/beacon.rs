@@ -0,0 +1,58 @@
+use heapless::Vec;
+
+use super::{SlotframeDescriptorRepr, TschSlotframeAndLinkRepr, MAX_SLOTFRAME_DESCRIPTORS};
+use crate::{AbsoluteSlotNumber, EnhancedBeacon, TschTimeslotTimings};
+
+use super::super::{Error, Result};
+
+/// The information a device needs to join a TSCH network, digested from an
+/// Enhanced Beacon.
+#[derive(Debug)]
+pub struct EnhancedBeaconSummary {
+    /// The Absolute Slot Number at the timeslot in which the beacon was sent.
+    pub absolute_slot_number: AbsoluteSlotNumber,
+    /// The join metric of the sender.
+    pub join_metric: u8,
+    /// The timeslot timings. The default timings are used when the TSCH
+    /// Timeslot IE is absent.
+    pub timeslot_timings: TschTimeslotTimings,
+    /// The advertised slotframes and links.
+    pub slotframe_descriptors: Vec<SlotframeDescriptorRepr, MAX_SLOTFRAME_DESCRIPTORS>,
+    /// The hopping sequence ID, when the Channel Hopping IE is present.
+    pub hopping_sequence_id: Option<u8>,
+}
+
+impl EnhancedBeaconSummary {
+    /// Parse the TSCH Information Elements of an Enhanced Beacon.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when the TSCH Synchronization IE is absent, when the
+    /// timeslot template is only referred to by its ID, or when the TSCH
+    /// Slotframe and Link IE holds too many slotframes or links.
+    pub fn parse(frame: &EnhancedBeacon<&[u8]>) -> Result<Self> {
+        let ie = frame.information_elements().ok_or(Error)?;
+        let sync = ie.tsch_synchronization().ok_or(Error)?;
+
+        let timeslot_timings = match ie.tsch_timeslot() {
+            Some(timeslot) if timeslot.has_timeslot_timings() => timeslot.timeslot_timings(),
+            Some(timeslot) if timeslot.id() != 0 => return Err(Error),
+            _ => TschTimeslotTimings::default(),
+        };
+
+        let slotframe_descriptors = match ie.tsch_slotframe_and_link() {
+            Some(slotframes) => TschSlotframeAndLinkRepr::parse(&slotframes)?.slotframe_descriptors,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            absolute_slot_number: sync.absolute_slot_number(),
+            join_metric: sync.join_metric(),
+            timeslot_timings,
+            slotframe_descriptors,
+            hopping_sequence_id: ie
+                .channel_hopping()
+                .map(|hopping| hopping.hopping_sequence_id()),
+        })
+    }
+}
@@ -1,20 +1,45 @@
 use super::*;
-use crate::{Address, AddressingMode, FrameType, FrameVersion};
+use crate::{AbsoluteSlotNumber, Address, AddressingMode, CommandId, FrameType, FrameVersion};
 use crate::{Error, Result};
 
+#[cfg(feature = "legacy-beacons")]
 pub struct Beacon;
 pub struct EnhancedBeacon;
 pub struct Ack;
 pub struct Data;
+pub struct MacCommand;
+
+/// The frame version of data and MAC command frames, until a feature of
+/// IEEE 802.15.4-2020 is used.
+#[cfg(feature = "legacy-frames")]
+const DEFAULT_FRAME_VERSION: FrameVersion = FrameVersion::Ieee802154_2006;
+#[cfg(not(feature = "legacy-frames"))]
+const DEFAULT_FRAME_VERSION: FrameVersion = FrameVersion::Ieee802154_2020;
+
+/// What [`FrameBuilder::finalize`] does with Information Elements in a frame
+/// of a version older than IEEE 802.15.4-2020, which does not define them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VersionPolicy {
+    /// Upgrade the frame to IEEE 802.15.4-2020.
+    #[default]
+    Upgrade,
+    /// Keep the frame version, and fail to finalize the frame.
+    Strict,
+}
 
 /// A helper for building IEEE 802.15.4 frames.
 pub struct FrameBuilder<'p, T> {
     frame: FrameRepr<'p>,
     r#type: core::marker::PhantomData<T>,
+    version_policy: VersionPolicy,
+    /// Set when an Information Element did not fit in the representation,
+    /// such that [`finalize`](Self::finalize) fails.
+    overflow: bool,
 }
 
 impl FrameBuilder<'_, Ack> {
     /// Create a new builder for an immediate acknowledgment frame.
+    #[cfg(feature = "imm-ack")]
     pub fn new_imm_ack(sequence_number: u8) -> Self {
         Self {
             frame: FrameRepr {
@@ -32,10 +57,13 @@ impl FrameBuilder<'_, Ack> {
                 },
                 sequence_number: Some(sequence_number),
                 addressing_fields: None,
+                auxiliary_security_header: None,
                 information_elements: None,
                 payload: None,
             },
             r#type: Default::default(),
+            version_policy: VersionPolicy::Upgrade,
+            overflow: false,
         }
     }
 
@@ -57,14 +85,18 @@ impl FrameBuilder<'_, Ack> {
                 },
                 sequence_number: None,
                 addressing_fields: None,
+                auxiliary_security_header: None,
                 information_elements: None,
                 payload: None,
             },
             r#type: Default::default(),
+            version_policy: VersionPolicy::Upgrade,
+            overflow: false,
         }
     }
 }
 
+#[cfg(feature = "legacy-beacons")]
 impl FrameBuilder<'_, Beacon> {
     /// Create a new builder for a beacon frame.
     pub fn new_beacon() -> Self {
@@ -84,16 +116,26 @@ impl FrameBuilder<'_, Beacon> {
                 },
                 sequence_number: None,
                 addressing_fields: None,
+                auxiliary_security_header: None,
                 information_elements: None,
                 payload: None,
             },
             r#type: core::marker::PhantomData,
+            version_policy: VersionPolicy::Upgrade,
+            overflow: false,
         }
     }
 }
 
 impl FrameBuilder<'_, EnhancedBeacon> {
     /// Create a new builder for an enhanced beacon frame.
+    ///
+    /// A minimal 6TiSCH enhanced beacon is built by chaining
+    /// [`with_tsch_synchronization`](Self::with_tsch_synchronization),
+    /// [`with_default_timeslot_template`](Self::with_default_timeslot_template),
+    /// [`with_channel_hopping`](Self::with_channel_hopping) and
+    /// [`with_slotframe`](Self::with_slotframe). The Nested Information
+    /// Elements are emitted in the order they were added.
     pub fn new_enhanced_beacon() -> Self {
         Self {
             frame: FrameRepr {
@@ -111,14 +153,83 @@ impl FrameBuilder<'_, EnhancedBeacon> {
                 },
                 sequence_number: None,
                 addressing_fields: None,
+                auxiliary_security_header: None,
                 information_elements: None,
                 payload: None,
             },
             r#type: core::marker::PhantomData,
+            version_policy: VersionPolicy::Upgrade,
+            overflow: false,
         }
     }
 }
 
+impl FrameBuilder<'_, EnhancedBeacon> {
+    /// Add a TSCH Synchronization Nested Information Element.
+    pub fn with_tsch_synchronization(
+        mut self,
+        absolute_slot_number: AbsoluteSlotNumber,
+        join_metric: u8,
+    ) -> Self {
+        self.push_mlme(NestedInformationElementRepr::TschSynchronization(
+            TschSynchronizationRepr {
+                absolute_slot_number,
+                join_metric,
+            },
+        ));
+        self
+    }
+
+    /// Add a TSCH Timeslot Nested Information Element, referring to the
+    /// default timeslot template (ID 0).
+    pub fn with_default_timeslot_template(mut self) -> Self {
+        self.push_mlme(NestedInformationElementRepr::TschTimeslot(
+            TschTimeslotRepr::Default(0),
+        ));
+        self
+    }
+
+    /// Add a Channel Hopping Nested Information Element with the given
+    /// hopping sequence ID.
+    pub fn with_channel_hopping(mut self, hopping_sequence_id: u8) -> Self {
+        self.push_mlme(NestedInformationElementRepr::ChannelHopping(
+            ChannelHoppingRepr {
+                hopping_sequence_id,
+            },
+        ));
+        self
+    }
+
+    /// Add a slotframe descriptor to the TSCH Slotframe and Link Nested
+    /// Information Element.
+    ///
+    /// # Note
+    /// The TSCH Slotframe and Link Nested Information Element is added when
+    /// it is not yet present.
+    pub fn with_slotframe(mut self, slotframe: SlotframeDescriptorRepr) -> Self {
+        let pushed = self.mlme_mut().is_some_and(|mlme| {
+            let slotframes = mlme.iter_mut().find_map(|ie| match ie {
+                NestedInformationElementRepr::TschSlotframeAndLink(slotframes) => Some(slotframes),
+                _ => None,
+            });
+
+            match slotframes {
+                Some(slotframes) => slotframes.slotframe_descriptors.push(slotframe).is_ok(),
+                None => mlme
+                    .push(NestedInformationElementRepr::TschSlotframeAndLink(
+                        TschSlotframeAndLinkRepr {
+                            slotframe_descriptors: heapless::Vec::from_iter([slotframe]),
+                        },
+                    ))
+                    .is_ok(),
+            }
+        });
+        self.overflow |= !pushed;
+
+        self
+    }
+}
+
 impl<'p> FrameBuilder<'p, Data> {
     /// Create a new builder for a data frame.
     pub fn new_data(payload: &'p [u8]) -> Self {
@@ -134,14 +245,140 @@ impl<'p> FrameBuilder<'p, Data> {
                     information_elements_present: false,
                     dst_addressing_mode: AddressingMode::Absent,
                     src_addressing_mode: AddressingMode::Absent,
-                    frame_version: FrameVersion::Ieee802154_2006,
+                    frame_version: DEFAULT_FRAME_VERSION,
                 },
                 sequence_number: None,
                 addressing_fields: None,
+                auxiliary_security_header: None,
                 information_elements: None,
                 payload: Some(payload),
             },
             r#type: core::marker::PhantomData,
+            version_policy: VersionPolicy::Upgrade,
+            overflow: false,
+        }
+    }
+}
+
+impl FrameBuilder<'_, MacCommand> {
+    /// Create a new builder for a Data Request command frame.
+    ///
+    /// # Note
+    /// The Acknowledgment Request bit is set, as the acknowledgment indicates
+    /// whether data is pending.
+    pub fn new_data_request() -> Self {
+        Self {
+            frame: FrameRepr {
+                frame_control: FrameControlRepr {
+                    frame_type: FrameType::MacCommand,
+                    security_enabled: false,
+                    frame_pending: false,
+                    ack_request: true,
+                    pan_id_compression: false,
+                    sequence_number_suppression: true,
+                    information_elements_present: false,
+                    dst_addressing_mode: AddressingMode::Absent,
+                    src_addressing_mode: AddressingMode::Absent,
+                    frame_version: DEFAULT_FRAME_VERSION,
+                },
+                sequence_number: None,
+                addressing_fields: None,
+                auxiliary_security_header: None,
+                information_elements: None,
+                payload: Some(&[CommandId::DataRequest as u8]),
+            },
+            r#type: core::marker::PhantomData,
+            version_policy: VersionPolicy::Upgrade,
+            overflow: false,
+        }
+    }
+
+    /// Create a new builder for a Beacon Request command frame, as broadcast
+    /// by an active scan. The destination PAN ID and address are set to the
+    /// broadcast values, and the source address is absent.
+    pub fn new_beacon_request() -> Self {
+        Self {
+            frame: FrameRepr {
+                frame_control: FrameControlRepr {
+                    frame_type: FrameType::MacCommand,
+                    security_enabled: false,
+                    frame_pending: false,
+                    ack_request: false,
+                    pan_id_compression: false,
+                    sequence_number_suppression: true,
+                    information_elements_present: false,
+                    dst_addressing_mode: AddressingMode::Absent,
+                    src_addressing_mode: AddressingMode::Absent,
+                    frame_version: DEFAULT_FRAME_VERSION,
+                },
+                sequence_number: None,
+                addressing_fields: None,
+                auxiliary_security_header: None,
+                information_elements: None,
+                payload: Some(&[CommandId::BeaconRequest as u8]),
+            },
+            r#type: core::marker::PhantomData,
+            version_policy: VersionPolicy::Upgrade,
+            overflow: false,
+        }
+        .set_dst_pan_id(0xffff)
+        .set_dst_address(Address::BROADCAST)
+    }
+
+    /// Create a new builder for an Enhanced Beacon Request command frame,
+    /// soliciting enhanced beacons from the coordinators that pass the given
+    /// filter.
+    ///
+    /// # Note
+    /// This is a Beacon Request command frame of version IEEE 802.15.4-2020,
+    /// carrying an Enhanced Beacon Filter Nested Information Element.
+    pub fn new_enhanced_beacon_request(filter: EnhancedBeaconFilterRepr) -> Self {
+        let mut builder = Self::new_beacon_request();
+        builder.push_mlme(NestedInformationElementRepr::EnhancedBeaconFilter(filter));
+        builder
+    }
+}
+
+impl<T> FrameBuilder<'_, T> {
+    /// Add a Nested Information Element to the MLME Payload Information
+    /// Element.
+    fn push_mlme(&mut self, ie: NestedInformationElementRepr) {
+        let pushed = self.mlme_mut().is_some_and(|mlme| mlme.push(ie).is_ok());
+        self.overflow |= !pushed;
+    }
+
+    /// Return the Nested Information Elements of the MLME Payload Information
+    /// Element, adding the MLME Payload Information Element when it is not yet
+    /// present.
+    ///
+    /// Returns `None` when there is no room for the MLME Payload Information
+    /// Element.
+    fn mlme_mut(&mut self) -> Option<&mut heapless::Vec<NestedInformationElementRepr, 16>> {
+        self.frame.frame_control.information_elements_present = true;
+        self.frame.frame_control.frame_version = FrameVersion::Ieee802154_2020;
+
+        let payloads = &mut self
+            .frame
+            .information_elements
+            .get_or_insert_with(InformationElementsRepr::default)
+            .payload_information_elements;
+
+        let index = match payloads
+            .iter()
+            .position(|ie| matches!(ie, PayloadInformationElementRepr::Mlme(_)))
+        {
+            Some(index) => index,
+            None => {
+                payloads
+                    .push(PayloadInformationElementRepr::Mlme(heapless::Vec::new()))
+                    .ok()?;
+                payloads.len() - 1
+            }
+        };
+
+        match &mut payloads[index] {
+            PayloadInformationElementRepr::Mlme(nested) => Some(nested),
+            _ => unreachable!(),
         }
     }
 }
@@ -157,6 +394,19 @@ impl<'p, T> FrameBuilder<'p, T> {
         self
     }
 
+    /// Suppress the frame sequence number.
+    ///
+    /// # Note
+    /// This method enables sequence number suppression, which is only
+    /// defined for IEEE 802.15.4-2020 frames. The frame version will be set to
+    /// IEEE 802.15.4-2020.
+    pub fn suppress_sequence_number(mut self) -> Self {
+        self.frame.sequence_number = None;
+        self.frame.frame_control.sequence_number_suppression = true;
+        self.frame.frame_control.frame_version = FrameVersion::Ieee802154_2020;
+        self
+    }
+
     /// Set the destination PAN ID.
     pub fn set_dst_pan_id(mut self, pan_id: u16) -> Self {
         self.frame
@@ -202,21 +452,41 @@ impl<'p, T> FrameBuilder<'p, T> {
         self
     }
 
+    /// Set the Auxiliary Security Header, which selects the key of the frame.
+    ///
+    /// # Note
+    /// This method will enable the Security Enabled bit in the frame control.
+    /// The frame version will be set to IEEE 802.15.4-2006 when it was
+    /// IEEE 802.15.4-2003, or to IEEE 802.15.4-2020 when the frame counter is
+    /// suppressed.
+    pub fn set_auxiliary_security_header(mut self, aux: AuxiliarySecurityHeaderRepr) -> Self {
+        self.frame.frame_control.security_enabled = true;
+        self.frame.auxiliary_security_header = Some(aux);
+
+        if aux.frame_counter.is_none() || aux.asn_in_nonce {
+            self.frame.frame_control.frame_version = FrameVersion::Ieee802154_2020;
+        } else if self.frame.frame_control.frame_version == FrameVersion::Ieee802154_2003 {
+            self.frame.frame_control.frame_version = FrameVersion::Ieee802154_2006;
+        }
+
+        self
+    }
+
     /// Add a header Information Element.
     ///
     /// # Note
     /// This method will enable the Information Elements Present bit in the
-    /// frame control. The frame version will be set to IEEE 802.15.4-2020.
+    /// frame control. The frame version is handled by
+    /// [`finalize`](Self::finalize), see [`VersionPolicy`].
     pub fn add_header_information_element(mut self, ie: HeaderInformationElementRepr) -> Self {
         self.frame.frame_control.information_elements_present = true;
-        self.frame
+        self.overflow |= self
+            .frame
             .information_elements
             .get_or_insert_with(InformationElementsRepr::default)
             .header_information_elements
             .push(ie)
-            .unwrap();
-
-        self.frame.frame_control.frame_version = FrameVersion::Ieee802154_2020;
+            .is_err();
 
         self
     }
@@ -225,126 +495,113 @@ impl<'p, T> FrameBuilder<'p, T> {
     ///
     /// # Note
     /// This method will enable the Information Elements Present bit in the
-    /// frame control. The frame version will be set to IEEE 802.15.4-2020.
+    /// frame control. The frame version is handled by
+    /// [`finalize`](Self::finalize), see [`VersionPolicy`].
     pub fn add_payload_information_element(mut self, ie: PayloadInformationElementRepr) -> Self {
         self.frame.frame_control.information_elements_present = true;
-        self.frame
+        self.overflow |= self
+            .frame
             .information_elements
             .get_or_insert_with(InformationElementsRepr::default)
             .payload_information_elements
             .push(ie)
-            .unwrap();
-
-        self.frame.frame_control.frame_version = FrameVersion::Ieee802154_2020;
+            .is_err();
 
         self
     }
 
     /// Set the frame payload.
+    ///
+    /// # Note
+    /// An empty payload is left out of the frame, such that a frame only
+    /// carrying Information Elements can be built.
     pub fn set_payload(mut self, payload: &'p [u8]) -> Self {
         self.frame.payload = Some(payload);
         self
     }
 
+    /// Set what [`finalize`](Self::finalize) does with Information Elements
+    /// in a frame of a version older than IEEE 802.15.4-2020 (default =
+    /// [`VersionPolicy::Upgrade`]).
+    pub fn set_version_policy(mut self, policy: VersionPolicy) -> Self {
+        self.version_policy = policy;
+        self
+    }
+
     /// Finalize the frame builder, returning the frame representation.
     ///
     /// # Note
     /// This method will check and set if PAN ID compression is possible,
-    /// depending on the frame version.
+    /// depending on the frame version. IEEE 802.15.4-2020 frames may be built
+    /// without any address, e.g. an Enhanced Acknowledgment only carrying
+    /// Information Elements. A frame of an older version carrying
+    /// Information Elements is upgraded to IEEE 802.15.4-2020, unless the
+    /// [`VersionPolicy`] is [`VersionPolicy::Strict`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when more Information Elements were added than fit in
+    /// the representation, when the addressing fields cannot be represented,
+    /// when the Information Elements are not correctly ordered or contain
+    /// duplicates (see [`InformationElementsRepr::validate`]), or when the
+    /// frame carries Information Elements while its version does not define
+    /// them and the [`VersionPolicy`] is [`VersionPolicy::Strict`].
     pub fn finalize(mut self) -> Result<FrameRepr<'p>> {
-        // Check if PAN ID compression is possible, depending on the frame version.
-        if self.frame.frame_control.frame_version == FrameVersion::Ieee802154_2020 {
-            let Some(addr) = self.frame.addressing_fields.as_mut() else {
-                return Err(Error);
-            };
-
-            self.frame.frame_control.pan_id_compression = match (
-                addr.dst_address,
-                addr.src_address,
-                addr.dst_pan_id,
-                addr.src_pan_id,
-            ) {
-                (None, None, None, None) => false,
-                (None, None, Some(_), None) => true,
-                (Some(_), None, Some(_), None) => false,
-                (None, Some(_), None, Some(_)) => false,
-                (None, Some(_), None, None) => true,
-                (Some(Address::Extended(_)), Some(Address::Extended(_)), Some(_), None) => false,
-                (Some(Address::Extended(_)), Some(Address::Extended(_)), None, None) => true,
-                (Some(Address::Short(_)), Some(Address::Short(_)), Some(dst), Some(src)) => {
-                    if dst == src {
-                        addr.src_pan_id = None;
-                    }
-
-                    dst == src
-                }
-                (Some(Address::Short(_)), Some(Address::Extended(_)), Some(dst), Some(src)) => {
-                    if dst == src {
-                        addr.src_pan_id = None;
-                    }
-
-                    dst == src
-                }
-                (Some(Address::Extended(_)), Some(Address::Short(_)), Some(dst), Some(src)) => {
-                    if dst == src {
-                        addr.src_pan_id = None;
-                    }
+        if self.overflow {
+            return Err(Error);
+        }
 
-                    dst == src
-                }
-                (Some(Address::Short(_)), Some(Address::Extended(_)), Some(_), None) => true,
-                (Some(Address::Extended(_)), Some(Address::Short(_)), Some(_), None) => true,
-                (Some(Address::Short(_)), Some(Address::Short(_)), Some(_), None) => true,
-                _ => return Err(Error),
-            };
-        } else {
-            if matches!(self.frame.frame_control.frame_type, FrameType::Ack) {
-                // The sequence number is required for immediate acknowledgment frames.
-                if self.frame.sequence_number.is_none() {
-                    return Err(Error);
+        if self.frame.frame_control.information_elements_present
+            && self.frame.frame_control.frame_version != FrameVersion::Ieee802154_2020
+        {
+            match self.version_policy {
+                VersionPolicy::Upgrade => {
+                    self.frame.frame_control.frame_version = FrameVersion::Ieee802154_2020;
                 }
-
-                // The addressing fields are not present in acknowledgment frames.
-                self.frame.addressing_fields = None;
-
-                return Ok(self.frame);
+                VersionPolicy::Strict => return Err(Error),
             }
+        }
 
-            // - If both destination and source addresses are present, and the PAN IDs are
-            //   equal, then PAN ID compression is possible. In this case, the source PAN ID
-            //   field is omitted and the PAN ID compression bit is set to 1. If PAN IDs are
-            //   different, the PAN ID compression bit is set to 0.
-            // - If only either the destination or source address is present, the PAN ID
-            //   compression bit is set to 0. The PAN ID field of the single address shall
-            //   be included in the frame.
-            let Some(addr) = self.frame.addressing_fields.as_mut() else {
+        if self.frame.frame_control.frame_version != FrameVersion::Ieee802154_2020
+            && matches!(self.frame.frame_control.frame_type, FrameType::Ack)
+        {
+            // The sequence number is required for immediate acknowledgment frames.
+            if self.frame.sequence_number.is_none() {
                 return Err(Error);
-            };
-
-            match (
-                addr.dst_address,
-                addr.src_address,
-                addr.dst_pan_id,
-                addr.src_pan_id,
-            ) {
-                (Some(_), Some(_), Some(dst_pan_id), Some(src_pan_id)) => {
-                    if dst_pan_id == src_pan_id {
-                        self.frame.frame_control.pan_id_compression = true;
-                        addr.src_pan_id = None;
-                    }
-                }
-                (Some(_), None, Some(_), _) => {
-                    self.frame.frame_control.pan_id_compression = false;
-                    addr.src_pan_id = None;
-                }
-                (None, Some(_), _, Some(_)) => {
-                    self.frame.frame_control.pan_id_compression = false;
-                    addr.dst_pan_id = None;
-                }
-                _ => return Err(Error),
             }
+
+            // The addressing fields are not present in acknowledgment frames.
+            self.frame.addressing_fields = None;
+
+            return Ok(self.frame);
+        }
+
+        if let Some(ie) = &self.frame.information_elements {
+            ie.validate()?;
         }
 
+        self.frame.payload = self.frame.payload.filter(|payload| !payload.is_empty());
+
+        let addr = match self.frame.addressing_fields.take() {
+            Some(addr) => addr,
+            None if self.frame.frame_control.frame_version == FrameVersion::Ieee802154_2020 => {
+                AddressingFieldsRepr::default()
+            }
+            None => return Err(Error),
+        };
+
+        // Derive the PAN ID compression and the PAN IDs that need to be elided,
+        // depending on the frame version.
+        let (addr, control) = AddressingFieldsRepr::from_addresses(
+            self.frame.frame_control.frame_version,
+            addr.dst_pan_id,
+            addr.dst_address,
+            addr.src_pan_id,
+            addr.src_address,
+        )?;
+        control.apply(&mut self.frame.frame_control);
+        self.frame.addressing_fields = Some(addr);
+
         Ok(self.frame)
     }
 }
@@ -4,6 +4,7 @@ use super::super::{AddressingMode, FrameControl, FrameType, FrameVersion};
 /// A high-level representation of the IEEE 802.15.4 Frame Control field.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FrameControlRepr {
     /// Frame type.
     pub frame_type: FrameType,
@@ -63,3 +64,64 @@ impl FrameControlRepr {
         fc.set_frame_version(self.frame_version);
     }
 }
+
+impl core::fmt::Display for FrameControlRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        let enhanced = self.frame_version == FrameVersion::Ieee802154_2020
+            && matches!(self.frame_type, FrameType::Beacon | FrameType::Ack);
+
+        writeln!(
+            f,
+            "{:indent$}frame type: {}{:?}",
+            "",
+            if enhanced { "Enhanced " } else { "" },
+            self.frame_type
+        )?;
+        writeln!(
+            f,
+            "{:indent$}security: {}",
+            "", self.security_enabled as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}frame pending: {}",
+            "", self.frame_pending as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}ack request: {}",
+            "", self.ack_request as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}pan id compression: {}",
+            "", self.pan_id_compression as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}sequence number suppression: {}",
+            "", self.sequence_number_suppression as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}information elements present: {}",
+            "", self.information_elements_present as usize
+        )?;
+        writeln!(
+            f,
+            "{:indent$}dst addressing mode: {:?}",
+            "", self.dst_addressing_mode
+        )?;
+        writeln!(
+            f,
+            "{:indent$}src addressing mode: {:?}",
+            "", self.src_addressing_mode
+        )?;
+        writeln!(
+            f,
+            "{:indent$}frame version: {} ({:?})",
+            "", self.frame_version as usize, self.frame_version
+        )
+    }
+}
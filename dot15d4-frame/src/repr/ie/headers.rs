@@ -1,14 +1,20 @@
 use super::super::super::{Error, Result};
-use super::super::super::{HeaderElementId, HeaderInformationElement, TimeCorrection};
+use super::super::super::{
+    HeaderElementId, HeaderInformationElement, SimplifiedSuperframeSpecification,
+    SimplifiedSuperframeSpecificationRepr, TimeCorrection,
+};
 
 use crate::time::Duration;
 
 /// A high-level representation of a Header Information Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum HeaderInformationElementRepr {
     /// Time Correction Header Information Element.
     TimeCorrection(TimeCorrectionRepr),
+    /// Simplified Superframe Specification Header Information Element.
+    SimplifiedSuperframeSpecification(SimplifiedSuperframeSpecificationRepr),
     /// Header Termination 1.
     HeaderTermination1,
     /// Header Termination 2.
@@ -22,6 +28,13 @@ impl HeaderInformationElementRepr {
             HeaderElementId::TimeCorrection => Self::TimeCorrection(TimeCorrectionRepr::parse(
                 &TimeCorrection::new(ie.content())?,
             )?),
+            HeaderElementId::SimplifiedSuperframeSpecification => {
+                Self::SimplifiedSuperframeSpecification(
+                    SimplifiedSuperframeSpecificationRepr::parse(
+                        &SimplifiedSuperframeSpecification::new(ie.content())?,
+                    )?,
+                )
+            }
             HeaderElementId::HeaderTermination1 => Self::HeaderTermination1,
             HeaderElementId::HeaderTermination2 => Self::HeaderTermination2,
             _id => {
@@ -45,6 +58,7 @@ impl HeaderInformationElementRepr {
     fn inner_len(&self) -> usize {
         match self {
             Self::TimeCorrection(tc) => tc.buffer_len(),
+            Self::SimplifiedSuperframeSpecification(sss) => sss.buffer_len(),
             Self::HeaderTermination1 => 0,
             Self::HeaderTermination2 => 0,
         }
@@ -61,17 +75,38 @@ impl HeaderInformationElementRepr {
             Self::TimeCorrection(repr) => {
                 repr.emit(&mut TimeCorrection::new_unchecked(w.content_mut()));
             }
+            Self::SimplifiedSuperframeSpecification(repr) => {
+                repr.emit(&mut SimplifiedSuperframeSpecification::new_unchecked(
+                    w.content_mut(),
+                ));
+            }
             Self::HeaderTermination1 => {}
             Self::HeaderTermination2 => {}
         }
     }
 }
 
+impl core::fmt::Display for HeaderInformationElementRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+
+        writeln!(f, "{:indent$}{:?}", "", HeaderElementId::from(self))?;
+        match self {
+            Self::TimeCorrection(tc) => write!(f, "{:1$}", tc, indent + 2),
+            Self::SimplifiedSuperframeSpecification(sss) => write!(f, "{:1$}", sss, indent + 2),
+            Self::HeaderTermination1 | Self::HeaderTermination2 => Ok(()),
+        }
+    }
+}
+
 impl From<&HeaderInformationElementRepr> for HeaderElementId {
     fn from(val: &HeaderInformationElementRepr) -> Self {
         use HeaderInformationElementRepr::*;
         match val {
             TimeCorrection(_) => HeaderElementId::TimeCorrection,
+            SimplifiedSuperframeSpecification(_) => {
+                HeaderElementId::SimplifiedSuperframeSpecification
+            }
             HeaderTermination1 => HeaderElementId::HeaderTermination1,
             HeaderTermination2 => HeaderElementId::HeaderTermination2,
         }
@@ -79,8 +114,9 @@ impl From<&HeaderInformationElementRepr> for HeaderElementId {
 }
 
 /// A high-level representation of a Time Correction Header Information Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TimeCorrectionRepr {
     /// The time correction value in microseconds.
     pub time_correction: Duration,
@@ -109,3 +145,14 @@ impl TimeCorrectionRepr {
         buffer.set_nack(self.nack);
     }
 }
+
+impl core::fmt::Display for TimeCorrectionRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(
+            f,
+            "{:indent$}{}, nack: {}",
+            "", self.time_correction, self.nack as usize
+        )
+    }
+}
@@ -7,13 +7,14 @@ pub use nested::*;
 mod payloads;
 pub use payloads::*;
 
-use super::super::{InformationElements, PayloadInformationElement};
-use super::Result;
+use super::super::{HeaderElementId, InformationElements, NestedSubId, PayloadInformationElement};
+use super::{Error, Result};
 
 use heapless::Vec;
 
 /// A high-level representation of Information Elements.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct InformationElementsRepr {
     /// The header information elements.
     pub header_information_elements: Vec<HeaderInformationElementRepr, 16>,
@@ -46,6 +47,43 @@ impl arbitrary::Arbitrary<'_> for InformationElementsRepr {
     }
 }
 
+/// Why a list of Information Elements is not standards-conformant, see
+/// [`InformationElementsRepr::validate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InformationElementsError {
+    /// A header termination is not the last Header Information Element.
+    HeaderTerminationNotLast,
+    /// The payload termination is not the last Payload Information Element.
+    PayloadTerminationNotLast,
+    /// A Header Information Element is present more than once.
+    DuplicateHeader(HeaderElementId),
+    /// A Nested Information Element is present more than once.
+    DuplicateNested(NestedSubId),
+    /// A TSCH Slotframe and Link Nested Information Element holds invalid
+    /// links.
+    InvalidLinks,
+}
+
+impl core::fmt::Display for InformationElementsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::HeaderTerminationNotLast => write!(f, "header termination is not the last IE"),
+            Self::PayloadTerminationNotLast => {
+                write!(f, "payload termination is not the last IE")
+            }
+            Self::DuplicateHeader(id) => write!(f, "duplicate {id} IE"),
+            Self::DuplicateNested(id) => write!(f, "duplicate {id:?} nested IE"),
+            Self::InvalidLinks => write!(f, "invalid TSCH links"),
+        }
+    }
+}
+
+impl From<InformationElementsError> for Error {
+    fn from(_: InformationElementsError) -> Self {
+        Error
+    }
+}
+
 impl InformationElementsRepr {
     /// Parse Information Elements.
     pub fn parse(ie: InformationElements<&[u8]>) -> Result<Self> {
@@ -76,13 +114,139 @@ impl InformationElementsRepr {
         })
     }
 
+    /// Validate the ordering and uniqueness of the Information Elements.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when:
+    /// - a header termination is not the last Header Information Element, or
+    ///   more than one header termination is present;
+    /// - the payload termination is not the last Payload Information Element;
+    /// - a Header Information Element is present more than once, as all
+    ///   Header Information Elements that can be represented may only be
+    ///   present once;
+    /// - a Nested Information Element is present more than once, taking all
+    ///   MLME Payload Information Elements into account;
+    /// - a TSCH Slotframe and Link Nested Information Element holds invalid
+    ///   links (see [`SlotframeDescriptorRepr::validate`]).
+    pub fn validate(&self) -> core::result::Result<(), InformationElementsError> {
+        let headers = &self.header_information_elements;
+        for (i, ie) in headers.iter().enumerate() {
+            let id = HeaderElementId::from(ie);
+            let is_last = i + 1 == headers.len();
+
+            if matches!(
+                ie,
+                HeaderInformationElementRepr::HeaderTermination1
+                    | HeaderInformationElementRepr::HeaderTermination2
+            ) && !is_last
+            {
+                return Err(InformationElementsError::HeaderTerminationNotLast);
+            }
+
+            if headers[..i]
+                .iter()
+                .any(|other| HeaderElementId::from(other) == id)
+            {
+                return Err(InformationElementsError::DuplicateHeader(id));
+            }
+        }
+
+        let payloads = &self.payload_information_elements;
+        for (i, ie) in payloads.iter().enumerate() {
+            if matches!(ie, PayloadInformationElementRepr::PayloadTermination)
+                && i + 1 != payloads.len()
+            {
+                return Err(InformationElementsError::PayloadTerminationNotLast);
+            }
+        }
+
+        let mut nested = payloads.iter().flat_map(|ie| match ie {
+            PayloadInformationElementRepr::Mlme(nested) => &nested[..],
+            PayloadInformationElementRepr::PayloadTermination => &[],
+        });
+        let mut seen: Vec<NestedSubId, 16> = Vec::new();
+        nested.try_for_each(|ie| {
+            let id = NestedSubId::from(ie);
+            if seen.contains(&id) {
+                return Err(InformationElementsError::DuplicateNested(id));
+            }
+            if let NestedInformationElementRepr::TschSlotframeAndLink(slotframes) = ie {
+                slotframes
+                    .validate()
+                    .map_err(|_| InformationElementsError::InvalidLinks)?;
+            }
+            // More than 16 different nested IEs cannot be present, as there are
+            // fewer nested IE representations.
+            let _ = seen.push(id);
+            Ok(())
+        })
+    }
+
+    /// Returns `true` when the lists hold no Information Elements other than
+    /// terminations, such that nothing is emitted.
+    pub fn is_empty(&self) -> bool {
+        self.headers().next().is_none() && self.payloads().next().is_none()
+    }
+
+    /// Check that the terminations in the lists are the ones that are
+    /// emitted, see [`InformationElementsRepr::emit`]. Terminations that are
+    /// left out are derived when emitting, and are thus consistent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a header termination in the list is not the one
+    /// that is emitted, or when the payload termination is in the list without
+    /// Payload Information Elements.
+    pub fn check_terminations(&self, contains_payload: bool) -> Result<()> {
+        let (ht1, ht2, _) = self.header_terminations(contains_payload);
+        for ie in &self.header_information_elements {
+            match ie {
+                HeaderInformationElementRepr::HeaderTermination1 if !ht1 => return Err(Error),
+                HeaderInformationElementRepr::HeaderTermination2 if !ht2 => return Err(Error),
+                _ => {}
+            }
+        }
+
+        // The payload termination is optional when no payload follows.
+        if self
+            .payload_information_elements
+            .iter()
+            .any(|ie| matches!(ie, PayloadInformationElementRepr::PayloadTermination))
+            && self.payloads().next().is_none()
+        {
+            return Err(Error);
+        }
+
+        Ok(())
+    }
+
+    /// The Header Information Elements, without terminations.
+    fn headers(&self) -> impl Iterator<Item = &HeaderInformationElementRepr> {
+        self.header_information_elements.iter().filter(|ie| {
+            !matches!(
+                ie,
+                HeaderInformationElementRepr::HeaderTermination1
+                    | HeaderInformationElementRepr::HeaderTermination2
+            )
+        })
+    }
+
+    /// The Payload Information Elements, without the termination.
+    fn payloads(&self) -> impl Iterator<Item = &PayloadInformationElementRepr> {
+        self.payload_information_elements
+            .iter()
+            .filter(|ie| !matches!(ie, PayloadInformationElementRepr::PayloadTermination))
+    }
+
     /// The header terminations required to emit the Information Elements.
     /// The first bool is the HT1, the second is the HT2, and the third is the
-    /// PT.
+    /// PT. Terminations in the lists, e.g. of parsed Information Elements, are
+    /// not emitted themselves.
     fn header_terminations(&self, contains_payload: bool) -> (bool, bool, bool) {
         match (
-            !self.header_information_elements.is_empty(),
-            !self.payload_information_elements.is_empty(),
+            self.headers().next().is_some(),
+            self.payloads().next().is_some(),
             contains_payload,
         ) {
             // No IE lists, so no terminations.
@@ -114,7 +278,7 @@ impl InformationElementsRepr {
 
         let (ht1, ht2, pt) = self.header_terminations(contains_payload);
 
-        for ie in self.header_information_elements.iter() {
+        for ie in self.headers() {
             len += ie.buffer_len();
         }
 
@@ -126,7 +290,7 @@ impl InformationElementsRepr {
             len += HeaderInformationElementRepr::HeaderTermination1.buffer_len();
         }
 
-        for ie in self.payload_information_elements.iter() {
+        for ie in self.payloads() {
             len += ie.buffer_len();
         }
 
@@ -143,7 +307,7 @@ impl InformationElementsRepr {
 
         let (ht1, ht2, pt) = self.header_terminations(contains_payload);
 
-        for ie in self.header_information_elements.iter() {
+        for ie in self.headers() {
             ie.emit(&mut buffer[offset..][..ie.buffer_len()]);
             offset += ie.buffer_len();
         }
@@ -158,7 +322,7 @@ impl InformationElementsRepr {
             offset += 2;
         }
 
-        for ie in self.payload_information_elements.iter() {
+        for ie in self.payloads() {
             ie.emit(&mut PayloadInformationElement::new_unchecked(
                 &mut buffer[offset..][..ie.buffer_len()],
             ));
@@ -172,3 +336,239 @@ impl InformationElementsRepr {
         }
     }
 }
+
+impl core::fmt::Display for InformationElementsRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        let inner = indent + 2;
+
+        if !self.header_information_elements.is_empty() {
+            writeln!(f, "{:indent$}Header Information Elements", "")?;
+            for ie in self.header_information_elements.iter() {
+                write!(f, "{:inner$}", ie)?;
+            }
+        }
+
+        if !self.payload_information_elements.is_empty() {
+            writeln!(f, "{:indent$}Payload Information Elements", "")?;
+            for ie in self.payload_information_elements.iter() {
+                write!(f, "{:inner$}", ie)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::Duration;
+    use crate::{
+        NestedSubIdShort, SimplifiedSuperframeSpecification, SimplifiedSuperframeSpecificationRepr,
+        SlotframeDescriptor, TschLinkOption,
+    };
+
+    fn time_correction() -> HeaderInformationElementRepr {
+        HeaderInformationElementRepr::TimeCorrection(TimeCorrectionRepr {
+            time_correction: Duration::from_us(-31),
+            nack: true,
+        })
+    }
+
+    fn simplified_superframe_specification() -> HeaderInformationElementRepr {
+        let data = [0x34, 0x12, 0xff, 0xcf, 0x49, 0x1f];
+        HeaderInformationElementRepr::SimplifiedSuperframeSpecification(
+            SimplifiedSuperframeSpecificationRepr::parse(
+                &SimplifiedSuperframeSpecification::new(&data[..]).unwrap(),
+            )
+            .unwrap(),
+        )
+    }
+
+    fn synchronization() -> NestedInformationElementRepr {
+        NestedInformationElementRepr::TschSynchronization(TschSynchronizationRepr {
+            absolute_slot_number: crate::AbsoluteSlotNumber::new(14).unwrap(),
+            join_metric: 0,
+        })
+    }
+
+    fn mlme<const N: usize>(
+        nested: [NestedInformationElementRepr; N],
+    ) -> PayloadInformationElementRepr {
+        PayloadInformationElementRepr::Mlme(nested.into_iter().collect())
+    }
+
+    #[test]
+    fn validate_ordering() {
+        let mut ie = InformationElementsRepr::default();
+        ie.header_information_elements
+            .push(time_correction())
+            .unwrap();
+        ie.header_information_elements
+            .push(HeaderInformationElementRepr::HeaderTermination1)
+            .unwrap();
+        ie.payload_information_elements
+            .push(mlme([synchronization()]))
+            .unwrap();
+        ie.payload_information_elements
+            .push(PayloadInformationElementRepr::PayloadTermination)
+            .unwrap();
+        assert!(ie.validate().is_ok());
+
+        let mut ie = InformationElementsRepr::default();
+        ie.header_information_elements
+            .push(HeaderInformationElementRepr::HeaderTermination1)
+            .unwrap();
+        ie.header_information_elements
+            .push(time_correction())
+            .unwrap();
+        assert_eq!(
+            ie.validate(),
+            Err(InformationElementsError::HeaderTerminationNotLast)
+        );
+
+        let mut ie = InformationElementsRepr::default();
+        ie.payload_information_elements
+            .push(PayloadInformationElementRepr::PayloadTermination)
+            .unwrap();
+        ie.payload_information_elements
+            .push(mlme([synchronization()]))
+            .unwrap();
+        assert_eq!(
+            ie.validate(),
+            Err(InformationElementsError::PayloadTerminationNotLast)
+        );
+    }
+
+    #[test]
+    fn validate_duplicates() {
+        let mut ie = InformationElementsRepr::default();
+        ie.header_information_elements
+            .push(time_correction())
+            .unwrap();
+        ie.header_information_elements
+            .push(time_correction())
+            .unwrap();
+        assert_eq!(
+            ie.validate(),
+            Err(InformationElementsError::DuplicateHeader(
+                HeaderElementId::TimeCorrection
+            ))
+        );
+
+        // Every Header IE that can be represented may only be present once.
+        let mut ie = InformationElementsRepr::default();
+        ie.header_information_elements
+            .push(simplified_superframe_specification())
+            .unwrap();
+        ie.header_information_elements
+            .push(time_correction())
+            .unwrap();
+        assert!(ie.validate().is_ok());
+        ie.header_information_elements
+            .push(simplified_superframe_specification())
+            .unwrap();
+        assert_eq!(
+            ie.validate(),
+            Err(InformationElementsError::DuplicateHeader(
+                HeaderElementId::SimplifiedSuperframeSpecification
+            ))
+        );
+
+        let mut ie = InformationElementsRepr::default();
+        ie.payload_information_elements
+            .push(mlme([synchronization(), synchronization()]))
+            .unwrap();
+        assert_eq!(
+            ie.validate(),
+            Err(InformationElementsError::DuplicateNested(
+                NestedSubId::Short(NestedSubIdShort::TschSynchronization)
+            ))
+        );
+
+        let mut ie = InformationElementsRepr::default();
+        ie.payload_information_elements
+            .push(mlme([synchronization()]))
+            .unwrap();
+        ie.payload_information_elements
+            .push(mlme([synchronization()]))
+            .unwrap();
+        assert!(matches!(
+            ie.validate(),
+            Err(InformationElementsError::DuplicateNested(_))
+        ));
+    }
+
+    fn link(timeslot: u16, channel_offset: u16, options: TschLinkOption) -> LinkInformationRepr {
+        LinkInformationRepr {
+            timeslot,
+            channel_offset,
+            link_options: TschLinkOptionRepr(options),
+        }
+    }
+
+    fn slotframe<const N: usize>(links: [LinkInformationRepr; N]) -> InformationElementsRepr {
+        let mut ie = InformationElementsRepr::default();
+        ie.payload_information_elements
+            .push(mlme([NestedInformationElementRepr::TschSlotframeAndLink(
+                TschSlotframeAndLinkRepr {
+                    slotframe_descriptors: Vec::from_iter([SlotframeDescriptorRepr {
+                        handle: 0,
+                        size: 101,
+                        links: links.into_iter().collect(),
+                    }]),
+                },
+            )]))
+            .unwrap();
+        ie
+    }
+
+    #[test]
+    fn validate_links() {
+        let tx = TschLinkOption::Tx;
+        let rx = TschLinkOption::Rx;
+
+        assert!(slotframe([link(0, 0, tx | rx | TschLinkOption::Shared)])
+            .validate()
+            .is_ok());
+        assert!(slotframe([link(0, 0, tx), link(0, 1, rx), link(1, 0, rx)])
+            .validate()
+            .is_ok());
+
+        // A link without direction
+        assert_eq!(
+            slotframe([link(0, 0, TschLinkOption::TimeKeeping)]).validate(),
+            Err(InformationElementsError::InvalidLinks)
+        );
+        // The same cell as Tx-only and as Rx link
+        assert!(slotframe([link(3, 2, tx), link(3, 2, rx)])
+            .validate()
+            .is_err());
+        assert!(slotframe([link(3, 2, rx | tx), link(3, 2, tx)])
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn write_link_information() {
+        let mut buffer = [0u8; 14];
+        let mut descriptor = SlotframeDescriptor::new_unchecked(&mut buffer[..]);
+        descriptor.set_number_of_links(2);
+
+        let mut link = descriptor.link_information_mut(1).unwrap();
+        link.set_timeslot(7);
+        link.set_channel_offset(3);
+        link.set_link_options(TschLinkOption::Rx | TschLinkOption::TimeKeeping);
+        assert!(descriptor.link_information_mut(2).is_none());
+
+        let descriptor = SlotframeDescriptor::new(&buffer[..]).unwrap();
+        let link = descriptor.link_informations().nth(1).unwrap();
+        assert_eq!(link.timeslot(), 7);
+        assert_eq!(link.channel_offset(), 3);
+        assert_eq!(
+            link.link_options(),
+            TschLinkOption::Rx | TschLinkOption::TimeKeeping
+        );
+    }
+}
@@ -1,17 +1,19 @@
 use crate::time::Duration;
+use crate::AbsoluteSlotNumber;
 
 use super::super::super::{
-    ChannelHopping, LinkInformation, NestedInformationElement, NestedSubId, NestedSubIdLong,
-    NestedSubIdShort, SlotframeDescriptor, TschLinkOption, TschSlotframeAndLink,
-    TschSynchronization, TschTimeslot, TschTimeslotTimings,
+    ChannelHopping, EnhancedBeaconFilter, LinkInformation, LinkMargin, NestedInformationElement,
+    NestedSubId, NestedSubIdLong, NestedSubIdShort, SlotframeDescriptor, TschLinkOption,
+    TschSlotframeAndLink, TschSynchronization, TschTimeslot, TschTimeslotTimings,
 };
 use super::super::super::{Error, Result};
 
 use heapless::Vec;
 
 /// A high-level representation of a MLME Payload Information Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum NestedInformationElementRepr {
     /// TSCH Synchronization Information Element.
     TschSynchronization(TschSynchronizationRepr),
@@ -21,6 +23,10 @@ pub enum NestedInformationElementRepr {
     TschSlotframeAndLink(TschSlotframeAndLinkRepr),
     /// Channel Hopping Information Element.
     ChannelHopping(ChannelHoppingRepr),
+    /// Link Margin Information Element.
+    LinkMargin(LinkMarginRepr),
+    /// Enhanced Beacon Filter Information Element.
+    EnhancedBeaconFilter(EnhancedBeaconFilterRepr),
 }
 
 impl NestedInformationElementRepr {
@@ -38,6 +44,14 @@ impl NestedInformationElementRepr {
                     &TschSlotframeAndLink::new(ie.content())?,
                 )?)
             }
+            NestedSubId::Short(NestedSubIdShort::LinkMargin) => {
+                Self::LinkMargin(LinkMarginRepr::parse(&LinkMargin::new(ie.content())?)?)
+            }
+            NestedSubId::Short(NestedSubIdShort::EnhancedBeaconFilter) => {
+                Self::EnhancedBeaconFilter(EnhancedBeaconFilterRepr::parse(
+                    &EnhancedBeaconFilter::new(ie.content())?,
+                )?)
+            }
             NestedSubId::Long(NestedSubIdLong::ChannelHopping) => Self::ChannelHopping(
                 ChannelHoppingRepr::parse(&ChannelHopping::new(ie.content())?)?,
             ),
@@ -65,6 +79,8 @@ impl NestedInformationElementRepr {
             Self::TschTimeslot(repr) => repr.buffer_len(),
             Self::TschSlotframeAndLink(repr) => repr.buffer_len(),
             Self::ChannelHopping(repr) => repr.buffer_len(),
+            Self::LinkMargin(repr) => repr.buffer_len(),
+            Self::EnhancedBeaconFilter(repr) => repr.buffer_len(),
         }
     }
 
@@ -89,6 +105,31 @@ impl NestedInformationElementRepr {
             Self::ChannelHopping(repr) => {
                 repr.emit(&mut ChannelHopping::new_unchecked(w.content_mut()))
             }
+            Self::LinkMargin(repr) => repr.emit(&mut LinkMargin::new_unchecked(w.content_mut())),
+            Self::EnhancedBeaconFilter(repr) => {
+                repr.emit(&mut EnhancedBeaconFilter::new_unchecked(w.content_mut()))
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for NestedInformationElementRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        let inner = indent + 2;
+
+        match NestedSubId::from(self) {
+            NestedSubId::Short(id) => writeln!(f, "{:indent$}{:?}", "", id)?,
+            NestedSubId::Long(id) => writeln!(f, "{:indent$}{:?}", "", id)?,
+        }
+
+        match self {
+            Self::TschSynchronization(repr) => write!(f, "{:inner$}", repr),
+            Self::TschTimeslot(repr) => write!(f, "{:inner$}", repr),
+            Self::TschSlotframeAndLink(repr) => write!(f, "{:inner$}", repr),
+            Self::ChannelHopping(repr) => write!(f, "{:inner$}", repr),
+            Self::LinkMargin(repr) => write!(f, "{:inner$}", repr),
+            Self::EnhancedBeaconFilter(repr) => write!(f, "{:inner$}", repr),
         }
     }
 }
@@ -108,17 +149,24 @@ impl From<&NestedInformationElementRepr> for NestedSubId {
             NestedInformationElementRepr::ChannelHopping(_) => {
                 NestedSubId::Long(NestedSubIdLong::ChannelHopping)
             }
+            NestedInformationElementRepr::LinkMargin(_) => {
+                NestedSubId::Short(NestedSubIdShort::LinkMargin)
+            }
+            NestedInformationElementRepr::EnhancedBeaconFilter(_) => {
+                NestedSubId::Short(NestedSubIdShort::EnhancedBeaconFilter)
+            }
         }
     }
 }
 
 /// A high-level representation of a TSCH Synchronization Nested Information
 /// Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TschSynchronizationRepr {
     /// The absolute slot number (ASN).
-    pub absolute_slot_number: u64,
+    pub absolute_slot_number: AbsoluteSlotNumber,
     /// The join metric.
     pub join_metric: u8,
 }
@@ -145,13 +193,31 @@ impl TschSynchronizationRepr {
     }
 }
 
+impl core::fmt::Display for TschSynchronizationRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(
+            f,
+            "{:indent$}ASN: {}, join metric: {}",
+            "", self.absolute_slot_number, self.join_metric
+        )
+    }
+}
+
+/// The maximum number of Slotframe Descriptors of a
+/// [`TschSlotframeAndLinkRepr`].
+pub const MAX_SLOTFRAME_DESCRIPTORS: usize = 3;
+
+/// The maximum number of links of a [`SlotframeDescriptorRepr`].
+pub const MAX_SLOTFRAME_LINKS: usize = 4;
+
 /// A high-level representation of a TSCH Slotframe and Link Nested Information
 /// Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TschSlotframeAndLinkRepr {
-    // TODO: provide configurable capacity for Vec.
     /// The slotframe descriptors.
-    pub slotframe_descriptors: Vec<SlotframeDescriptorRepr, 3>,
+    pub slotframe_descriptors: Vec<SlotframeDescriptorRepr, MAX_SLOTFRAME_DESCRIPTORS>,
 }
 
 impl TschSlotframeAndLinkRepr {
@@ -177,6 +243,14 @@ impl TschSlotframeAndLinkRepr {
         })
     }
 
+    /// Validate the links of all slotframes, see
+    /// [`SlotframeDescriptorRepr::validate`].
+    pub fn validate(&self) -> Result<()> {
+        self.slotframe_descriptors
+            .iter()
+            .try_for_each(SlotframeDescriptorRepr::validate)
+    }
+
     /// The buffer length required to emit the TSCH Slotframe and Link
     /// Information Element.
     pub fn buffer_len(&self) -> usize {
@@ -204,6 +278,24 @@ impl TschSlotframeAndLinkRepr {
     }
 }
 
+impl core::fmt::Display for TschSlotframeAndLinkRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+
+        writeln!(
+            f,
+            "{:indent$}#slotframes: {}",
+            "",
+            self.slotframe_descriptors.len()
+        )?;
+        for descriptor in self.slotframe_descriptors.iter() {
+            write!(f, "{:indent$}", descriptor)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(feature = "fuzz")]
 impl arbitrary::Arbitrary<'_> for TschSlotframeAndLinkRepr {
     fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
@@ -223,16 +315,16 @@ impl arbitrary::Arbitrary<'_> for TschSlotframeAndLinkRepr {
 
 /// A high-level representation of a Slotframe Descriptor present inside of a
 /// TSCH Synchronization Nested Information Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct SlotframeDescriptorRepr {
     /// The Slotframe Handle.
     pub handle: u8,
     /// The size of the slotframe in number of timeslots.
     pub size: u16,
-    // TODO: provide configurable capacity for Vec.
     /// Number of links that belong to the slotframe identified by the
     /// Slotframe Handle.
-    pub links: Vec<LinkInformationRepr, 4>,
+    pub links: Vec<LinkInformationRepr, MAX_SLOTFRAME_LINKS>,
 }
 
 impl SlotframeDescriptorRepr {
@@ -261,6 +353,35 @@ impl SlotframeDescriptorRepr {
         })
     }
 
+    /// Validate the links of the slotframe.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when a link is neither used for transmitting nor for
+    /// receiving, or when a cell (timeslot and channel offset) is advertised
+    /// both as a Tx-only link and as a link used for receiving.
+    pub fn validate(&self) -> Result<()> {
+        for (i, link) in self.links.iter().enumerate() {
+            let options = link.link_options.0;
+            if !options.intersects(TschLinkOption::Tx | TschLinkOption::Rx) {
+                return Err(Error);
+            }
+
+            let conflicting = self.links[..i].iter().any(|other| {
+                let other_options = other.link_options.0;
+                other.timeslot == link.timeslot
+                    && other.channel_offset == link.channel_offset
+                    && (options.is_tx_only() && other_options.contains(TschLinkOption::Rx)
+                        || other_options.is_tx_only() && options.contains(TschLinkOption::Rx))
+            });
+            if conflicting {
+                return Err(Error);
+            }
+        }
+
+        Ok(())
+    }
+
     /// The buffer length required to emit the TSCH Slotframe and Link
     /// Information Element.
     pub fn buffer_len(&self) -> usize {
@@ -282,13 +403,31 @@ impl SlotframeDescriptorRepr {
     }
 }
 
+impl core::fmt::Display for SlotframeDescriptorRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+
+        writeln!(
+            f,
+            "{:indent$}Slotframe Handle: {}, #links: {}",
+            "",
+            self.handle,
+            self.links.len()
+        )?;
+        for link in self.links.iter() {
+            write!(f, "{:1$}", link, indent + 2)?;
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(feature = "fuzz")]
 impl arbitrary::Arbitrary<'_> for SlotframeDescriptorRepr {
     fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
         let mut links = Vec::new();
 
-        // Generate maximum 4 links
-        for _ in 0..u.int_in_range(0..=4)? {
+        for _ in 0..u.int_in_range(0..=MAX_SLOTFRAME_LINKS)? {
             links
                 .push(LinkInformationRepr::arbitrary(u)?)
                 .map_err(|_| arbitrary::Error::IncorrectFormat)?;
@@ -304,8 +443,9 @@ impl arbitrary::Arbitrary<'_> for SlotframeDescriptorRepr {
 
 /// A high-level representation of a Link Information present inside of a
 /// TSCH Synchronization Nested Information Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LinkInformationRepr {
     /// The timeslot
     pub timeslot: u16,
@@ -338,9 +478,20 @@ impl LinkInformationRepr {
     }
 }
 
+impl core::fmt::Display for LinkInformationRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(
+            f,
+            "{:indent$}Timeslot: {}, Channel Offset: {}, Link Options: {}",
+            "", self.timeslot, self.channel_offset, self.link_options.0
+        )
+    }
+}
+
 /// A high-level representation of a Link Option found in TSCH Timeslot Nested
 /// Information Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct TschLinkOptionRepr(pub TschLinkOption);
 
 #[cfg(feature = "fuzz")]
@@ -354,8 +505,19 @@ impl arbitrary::Arbitrary<'_> for TschLinkOptionRepr {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for TschLinkOptionRepr {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> core::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.0.bits())
+    }
+}
+
 /// A high-level representation of a TSCH Timeslot Nested Information Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum TschTimeslotRepr {
     /// Default Timeslot Template with given ID. ID shall be 0.
     Default(u8),
@@ -403,6 +565,20 @@ impl TschTimeslotRepr {
     }
 }
 
+impl core::fmt::Display for TschTimeslotRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+
+        match self {
+            Self::Default(id) => writeln!(f, "{:indent$}slot ID: {}", "", id),
+            Self::Custom(timings) => {
+                writeln!(f, "{:indent$}slot ID: {}", "", timings.id())?;
+                write!(f, "{:indent$}{:indent$}", "", timings)
+            }
+        }
+    }
+}
+
 #[cfg(feature = "fuzz")]
 impl arbitrary::Arbitrary<'_> for TschTimeslotRepr {
     fn arbitrary(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Self> {
@@ -434,8 +610,9 @@ impl arbitrary::Arbitrary<'_> for TschTimeslotRepr {
 }
 
 /// A high-level representation of a Channel Hopping Nested Information Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ChannelHoppingRepr {
     /// The hopping sequence ID.
     pub hopping_sequence_id: u8,
@@ -460,3 +637,104 @@ impl ChannelHoppingRepr {
         ie.set_hopping_sequence_id(self.hopping_sequence_id);
     }
 }
+
+impl core::fmt::Display for ChannelHoppingRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(f, "{:indent$}sequence ID: {}", "", self.hopping_sequence_id)
+    }
+}
+
+/// A high-level representation of a Link Margin Nested Information Element.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LinkMarginRepr {
+    /// The link margin, in dB.
+    pub link_margin: i8,
+}
+
+impl LinkMarginRepr {
+    /// Parse a Link Margin Information Element.
+    pub fn parse(ie: &LinkMargin<&[u8]>) -> Result<Self> {
+        Ok(Self {
+            link_margin: ie.link_margin(),
+        })
+    }
+
+    /// The buffer length required to emit the Link Margin Information
+    /// Element.
+    pub fn buffer_len(&self) -> usize {
+        1
+    }
+
+    /// Emit the Link Margin Information Element into a buffer.
+    pub fn emit(&self, ie: &mut LinkMargin<&mut [u8]>) {
+        ie.set_link_margin(self.link_margin);
+    }
+}
+
+impl core::fmt::Display for LinkMarginRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(f, "{:indent$}link margin: {} dB", "", self.link_margin)
+    }
+}
+
+/// A high-level representation of an Enhanced Beacon Filter Nested
+/// Information Element.
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct EnhancedBeaconFilterRepr {
+    /// Only coordinators that permit joining should respond.
+    pub permit_joining_on: bool,
+    /// The minimum link quality at which a coordinator should respond.
+    pub link_quality: Option<u8>,
+    /// The probability, in percent, with which a coordinator should respond.
+    pub percent_filter: Option<u8>,
+}
+
+impl EnhancedBeaconFilterRepr {
+    /// Parse an Enhanced Beacon Filter Information Element.
+    pub fn parse(ie: &EnhancedBeaconFilter<&[u8]>) -> Result<Self> {
+        Ok(Self {
+            permit_joining_on: ie.permit_joining_on(),
+            link_quality: ie.link_quality(),
+            percent_filter: ie.percent_filter(),
+        })
+    }
+
+    /// The buffer length required to emit the Enhanced Beacon Filter
+    /// Information Element.
+    pub fn buffer_len(&self) -> usize {
+        1 + self.link_quality.is_some() as usize + self.percent_filter.is_some() as usize
+    }
+
+    /// Emit the Enhanced Beacon Filter Information Element into a buffer.
+    pub fn emit(&self, ie: &mut EnhancedBeaconFilter<&mut [u8]>) {
+        ie.set_filter(
+            self.permit_joining_on,
+            self.link_quality,
+            self.percent_filter,
+        );
+    }
+}
+
+impl core::fmt::Display for EnhancedBeaconFilterRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        writeln!(
+            f,
+            "{:indent$}permit joining on: {}",
+            "", self.permit_joining_on as usize
+        )?;
+        if let Some(link_quality) = self.link_quality {
+            writeln!(f, "{:indent$}link quality: {}", "", link_quality)?;
+        }
+        if let Some(percent_filter) = self.percent_filter {
+            writeln!(f, "{:indent$}percent filter: {}%", "", percent_filter)?;
+        }
+        Ok(())
+    }
+}
@@ -6,7 +6,8 @@ use super::NestedInformationElementRepr;
 use heapless::Vec;
 
 /// A high-level representation of a Payload Information Element.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 #[allow(clippy::large_enum_variant)]
 pub enum PayloadInformationElementRepr {
     /// MLME Payload Information Element.
@@ -53,6 +54,7 @@ impl PayloadInformationElementRepr {
 
                 Ok(Self::Mlme(nested_information_elements))
             }
+            PayloadGroupId::PayloadTermination => Ok(Self::PayloadTermination),
             _ => Err(Error),
         }
     }
@@ -96,7 +98,24 @@ impl PayloadInformationElementRepr {
                     offset += ie.buffer_len();
                 }
             }
-            Self::PayloadTermination => todo!(),
+            Self::PayloadTermination => {}
+        }
+    }
+}
+
+impl core::fmt::Display for PayloadInformationElementRepr {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+
+        match self {
+            Self::Mlme(nested) => {
+                writeln!(f, "{:indent$}MLME", "")?;
+                for ie in nested.iter() {
+                    write!(f, "{:1$}", ie, indent + 2)?;
+                }
+                Ok(())
+            }
+            Self::PayloadTermination => writeln!(f, "{:indent$}PayloadTermination", ""),
         }
     }
 }
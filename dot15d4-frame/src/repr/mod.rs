@@ -1,9 +1,12 @@
-use crate::FrameType;
+use crate::{Address, AddressingMode, FrameField, FrameType, FrameVersion};
 
 use super::{DataFrame, Error, Result};
 
 mod addressing;
-pub use addressing::AddressingFieldsRepr;
+pub use addressing::{AddressingControlRepr, AddressingFieldsRepr};
+
+mod aux_sec_header;
+pub use aux_sec_header::{AuxiliarySecurityHeaderRepr, KeyIdentifierRepr};
 
 mod frame_control;
 pub use frame_control::FrameControlRepr;
@@ -12,11 +15,15 @@ mod ie;
 pub use ie::*;
 
 mod builder;
-pub use builder::FrameBuilder;
+pub use builder::{FrameBuilder, VersionPolicy};
+
+mod beacon;
+pub use beacon::EnhancedBeaconSummary;
 
 /// A high-level representation of an IEEE 802.15.4 frame.
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct FrameRepr<'p> {
     /// The frame control field.
     pub frame_control: FrameControlRepr,
@@ -24,6 +31,8 @@ pub struct FrameRepr<'p> {
     pub sequence_number: Option<u8>,
     /// The addressing fields.
     pub addressing_fields: Option<AddressingFieldsRepr>,
+    /// The auxiliary security header.
+    pub auxiliary_security_header: Option<AuxiliarySecurityHeaderRepr>,
     /// The information elements.
     pub information_elements: Option<InformationElementsRepr>,
     /// The payload.
@@ -37,6 +46,10 @@ impl<'f> FrameRepr<'f> {
         let addressing_fields = reader
             .addressing()
             .map(|af| AddressingFieldsRepr::parse(af));
+        let auxiliary_security_header = reader
+            .auxiliary_security_header()
+            .map(|aux| AuxiliarySecurityHeaderRepr::parse(&aux))
+            .transpose()?;
         let information_elements = reader
             .information_elements()
             .map(InformationElementsRepr::parse)
@@ -46,6 +59,7 @@ impl<'f> FrameRepr<'f> {
             frame_control,
             sequence_number: reader.sequence_number(),
             addressing_fields,
+            auxiliary_security_header,
             information_elements,
             payload: reader.payload(),
         })
@@ -53,22 +67,56 @@ impl<'f> FrameRepr<'f> {
 
     /// Validate the frame.
     pub fn validate(&self) -> Result<()> {
-        // If the frame type is data, then the addressing fields must be present.
+        // If the frame type is data, then the addressing fields must be present,
+        // and the frame must carry a payload or Information Elements.
         if self.frame_control.frame_type == FrameType::Data {
             if self.addressing_fields.is_none() {
                 return Err(Error);
             }
 
-            if self.payload.is_none() {
+            if self.payload.is_none() && self.information_elements.is_none() {
                 return Err(Error);
             }
         }
 
+        // The auxiliary security header is present if and only if security is
+        // enabled.
+        if self.frame_control.security_enabled != self.auxiliary_security_header.is_some() {
+            return Err(Error);
+        }
+
+        // The addressing modes of the frame control field must match the
+        // addresses, as the other fields are emitted after as many octets as
+        // the modes announce.
+        let (dst_addressing_mode, src_addressing_mode) = self
+            .addressing_fields
+            .as_ref()
+            .map(|af| (af.dst_address, af.src_address))
+            .unwrap_or_default();
+        let mode = |address: Option<Address>| address.map_or(AddressingMode::Absent, Into::into);
+        if self.frame_control.dst_addressing_mode != mode(dst_addressing_mode)
+            || self.frame_control.src_addressing_mode != mode(src_addressing_mode)
+        {
+            return Err(Error);
+        }
+
         // If the addressing fields are present, they must be valid.
         if let Some(af) = &self.addressing_fields {
             af.validate(&self.frame_control)?;
         }
 
+        // Information Elements are only allowed in IEEE 802.15.4-2020 frames,
+        // and must be well-ordered.
+        if let Some(ie) = &self.information_elements {
+            if self.frame_control.frame_version != FrameVersion::Ieee802154_2020
+                || !self.frame_control.information_elements_present
+            {
+                return Err(Error);
+            }
+
+            ie.validate()?;
+        }
+
         // If the payload is present, it must not be empty.
         if let Some(payload) = self.payload {
             if payload.is_empty() {
@@ -79,7 +127,18 @@ impl<'f> FrameRepr<'f> {
         Ok(())
     }
 
-    /// Return the length of the frame when emitted into a buffer.
+    /// Returns `true` when the frame carries Information Elements other than
+    /// terminations. This is the Information Elements Present flag that is
+    /// emitted, see [`FrameRepr::emit`].
+    pub fn information_elements_present(&self) -> bool {
+        self.information_elements
+            .as_ref()
+            .is_some_and(|ie| !ie.is_empty())
+    }
+
+    /// Return the length of the frame when emitted into a buffer, including
+    /// the terminations of the Information Elements that are derived when
+    /// emitting.
     pub fn buffer_len(&self) -> usize {
         let mut len = 2; // Frame control
 
@@ -91,6 +150,10 @@ impl<'f> FrameRepr<'f> {
             len += af.buffer_len(&self.frame_control);
         }
 
+        if let Some(aux) = &self.auxiliary_security_header {
+            len += aux.buffer_len();
+        }
+
         if let Some(ie) = &self.information_elements {
             len += ie.buffer_len(self.payload.is_some());
         }
@@ -103,8 +166,17 @@ impl<'f> FrameRepr<'f> {
     }
 
     /// Emit the frame into a buffer.
+    ///
+    /// The Information Elements Present flag and the terminations of the
+    /// Information Elements are derived from the Information Elements and the
+    /// payload. The flag and the terminations of the representation are
+    /// ignored, see [`FrameRepr::emit_strict`] to reject inconsistent ones
+    /// instead.
     pub fn emit(&self, frame: &mut DataFrame<&'_ mut [u8]>) {
-        frame.set_frame_control(&self.frame_control);
+        frame.set_frame_control(&FrameControlRepr {
+            information_elements_present: self.information_elements_present(),
+            ..self.frame_control
+        });
 
         if let Some(sequence_number) = self.sequence_number {
             frame.set_sequence_number(sequence_number);
@@ -114,6 +186,10 @@ impl<'f> FrameRepr<'f> {
             frame.set_addressing_fields(af);
         }
 
+        if let Some(aux) = &self.auxiliary_security_header {
+            frame.set_aux_sec_header(aux);
+        }
+
         if let Some(ie) = &self.information_elements {
             frame.set_information_elements(ie, self.payload.is_some());
         }
@@ -122,4 +198,213 @@ impl<'f> FrameRepr<'f> {
             frame.set_payload(payload);
         }
     }
+
+    /// Emit the frame into a buffer, like [`FrameRepr::emit`], after checking
+    /// that the fields that are derived when emitting are consistent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without writing into the buffer, when the
+    /// Information Elements Present flag does not match the Information
+    /// Elements (see [`FrameRepr::information_elements_present`]), or when a
+    /// termination in the lists is not the one that is emitted (see
+    /// [`InformationElementsRepr::check_terminations`]).
+    pub fn emit_strict(&self, frame: &mut DataFrame<&'_ mut [u8]>) -> Result<()> {
+        if self.frame_control.information_elements_present != self.information_elements_present() {
+            return Err(Error);
+        }
+
+        if let Some(ie) = &self.information_elements {
+            ie.check_terminations(self.payload.is_some())?;
+        }
+
+        self.emit(frame);
+        Ok(())
+    }
+
+    /// Returns `true` when both frames have the same meaning, see
+    /// [`FrameRepr::canonical_diff`].
+    pub fn canonical_eq(&self, other: &FrameRepr<'_>) -> bool {
+        self.canonical_diff(other).is_none()
+    }
+
+    /// Return the first field in which the frames differ.
+    ///
+    /// Differences that do not change the meaning of a frame are ignored:
+    /// - the sequence number when it is suppressed;
+    /// - a PAN ID that is elided, when it equals the other PAN ID, and thus
+    ///   the PAN ID compression bit;
+    /// - header and payload terminations, which are added when emitting;
+    /// - absent versus empty Information Elements and payload.
+    pub fn canonical_diff(&self, other: &FrameRepr<'_>) -> Option<FrameField> {
+        let fc = |frame: &FrameRepr| FrameControlRepr {
+            pan_id_compression: false,
+            information_elements_present: false,
+            ..frame.frame_control
+        };
+        if fc(self) != fc(other) {
+            return Some(FrameField::FrameControl);
+        }
+
+        let sequence_number = |frame: &FrameRepr| {
+            frame
+                .sequence_number
+                .filter(|_| !frame.frame_control.sequence_number_suppression)
+        };
+        if sequence_number(self) != sequence_number(other) {
+            return Some(FrameField::SequenceNumber);
+        }
+
+        if canonical_addressing(self.addressing_fields.as_ref())
+            != canonical_addressing(other.addressing_fields.as_ref())
+        {
+            return Some(FrameField::Addressing);
+        }
+
+        if self.auxiliary_security_header != other.auxiliary_security_header {
+            return Some(FrameField::AuxiliarySecurityHeader);
+        }
+
+        if !header_elements(self.information_elements.as_ref())
+            .eq(header_elements(other.information_elements.as_ref()))
+        {
+            return Some(FrameField::HeaderInformationElement);
+        }
+
+        if !payload_elements(self.information_elements.as_ref())
+            .eq(payload_elements(other.information_elements.as_ref()))
+        {
+            return Some(FrameField::PayloadInformationElement);
+        }
+
+        if self.payload.unwrap_or_default() != other.payload.unwrap_or_default() {
+            return Some(FrameField::Payload);
+        }
+
+        None
+    }
+}
+
+/// Assert that two [`FrameRepr`]s are equal, ignoring differences that do
+/// not change the meaning of a frame (see [`FrameRepr::canonical_diff`]).
+///
+/// On failure, the field in which the frames differ is printed, together with
+/// both frames.
+///
+/// ```
+/// # use dot15d4_frame::{assert_frame_eq, Address, FrameBuilder};
+/// let frame = FrameBuilder::new_data(&[1, 2, 3])
+///     .set_dst_pan_id(0xabcd)
+///     .set_dst_address(Address::BROADCAST)
+///     .set_src_address(Address::Short([0x01, 0x00]))
+///     .finalize()
+///     .unwrap();
+/// let expected = FrameBuilder::new_data(&[1, 2, 3])
+///     .set_dst_pan_id(0xabcd)
+///     .set_dst_address(Address::BROADCAST)
+///     .set_src_pan_id(0xabcd)
+///     .set_src_address(Address::Short([0x01, 0x00]))
+///     .finalize()
+///     .unwrap();
+/// assert_frame_eq!(frame, expected);
+/// ```
+#[macro_export]
+macro_rules! assert_frame_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::assert_frame_eq!($left, $right, "")
+    };
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        match (&$left, &$right) {
+            (left, right) => {
+                if let Some(field) = $crate::FrameRepr::canonical_diff(left, right) {
+                    panic!(
+                        "frames differ in the {}: {}\n  left: {:#?}\n right: {:#?}",
+                        field,
+                        format_args!($($arg)+),
+                        left,
+                        right
+                    );
+                }
+            }
+        }
+    };
+}
+
+/// Return the addresses together with the PAN ID they belong to, with an
+/// elided PAN ID replaced by the other PAN ID.
+fn canonical_addressing(af: Option<&AddressingFieldsRepr>) -> [Option<(Address, u16)>; 2] {
+    let Some(af) = af else {
+        return [None, None];
+    };
+    let pan_id = |pan_id: Option<u16>, other: Option<u16>| pan_id.or(other).unwrap_or(0xffff);
+
+    [
+        af.dst_address
+            .filter(|addr| !addr.is_absent())
+            .map(|addr| (addr, pan_id(af.dst_pan_id, af.src_pan_id))),
+        af.src_address
+            .filter(|addr| !addr.is_absent())
+            .map(|addr| (addr, pan_id(af.src_pan_id, af.dst_pan_id))),
+    ]
+}
+
+/// Return the Header Information Elements, without header terminations.
+fn header_elements(
+    ie: Option<&InformationElementsRepr>,
+) -> impl Iterator<Item = &HeaderInformationElementRepr> {
+    ie.into_iter()
+        .flat_map(|ie| ie.header_information_elements.iter())
+        .filter(|ie| {
+            !matches!(
+                ie,
+                HeaderInformationElementRepr::HeaderTermination1
+                    | HeaderInformationElementRepr::HeaderTermination2
+            )
+        })
+}
+
+/// Return the Payload Information Elements, without payload termination.
+fn payload_elements(
+    ie: Option<&InformationElementsRepr>,
+) -> impl Iterator<Item = &PayloadInformationElementRepr> {
+    ie.into_iter()
+        .flat_map(|ie| ie.payload_information_elements.iter())
+        .filter(|ie| !matches!(ie, PayloadInformationElementRepr::PayloadTermination))
+}
+
+impl core::fmt::Display for FrameRepr<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let indent = f.width().unwrap_or(0);
+        let inner = indent + 2;
+
+        writeln!(f, "{:indent$}Frame Control", "")?;
+        write!(f, "{:inner$}", self.frame_control)?;
+
+        if let Some(sequence_number) = self.sequence_number {
+            writeln!(f, "{:indent$}Sequence Number", "")?;
+            writeln!(f, "{:inner$}sequence number: {}", "", sequence_number)?;
+        }
+
+        if let Some(af) = &self.addressing_fields {
+            writeln!(f, "{:indent$}Addressing", "")?;
+            write!(f, "{:inner$}", af)?;
+        }
+
+        if let Some(aux) = &self.auxiliary_security_header {
+            writeln!(f, "{:indent$}Auxiliary Security Header", "")?;
+            write!(f, "{:inner$}", aux)?;
+        }
+
+        if let Some(ie) = &self.information_elements {
+            writeln!(f, "{:indent$}Information Elements", "")?;
+            write!(f, "{:inner$}", ie)?;
+        }
+
+        if let Some(payload) = self.payload {
+            writeln!(f, "{:indent$}Payload", "")?;
+            writeln!(f, "{:inner$}{:x?}", "", payload)?;
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,100 @@
+//! Crash regression tests: every file of the corpus in `tests/corpus` is a
+//! raw input that once made the frame readers panic, e.g. found by the
+//! `frame` fuzz target. Every input is read again, and must not panic.
+//!
+//! Run with `DOT15D4_IMPORT_CRASHES=1` to copy the crashes of the fuzz
+//! targets in `fuzz/artifacts` to the corpus first, e.g. after fixing them.
+//! The copies keep the name given by the fuzzer.
+
+use std::path::{Path, PathBuf};
+use std::string::String;
+use std::vec::Vec;
+
+use crate::{DataFrame, Frame, FrameRepr};
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+const ARTIFACTS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../fuzz/artifacts");
+
+/// The fuzz targets of which the crashes are imported.
+const TARGETS: &[&str] = &["frame"];
+
+/// Copy the crashing inputs of the fuzz targets to the corpus, and return
+/// the number of new entries.
+fn import_crashes() -> usize {
+    let mut imported = 0;
+
+    for target in TARGETS {
+        let Ok(entries) = std::fs::read_dir(Path::new(ARTIFACTS).join(target)) else {
+            continue;
+        };
+
+        for path in entries.map(|entry| entry.unwrap().path()) {
+            let name = path.file_name().unwrap().to_string_lossy();
+            if !["crash-", "oom-", "timeout-"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                continue;
+            }
+
+            let destination = Path::new(CORPUS).join(&*name);
+            if !destination.exists() {
+                std::fs::copy(&path, destination).unwrap();
+                imported += 1;
+            }
+        }
+    }
+
+    imported
+}
+
+/// Read `data` the way a received frame is read.
+fn read(data: &[u8]) {
+    if data.len() > 127 {
+        return;
+    }
+
+    if let Ok(frame) = DataFrame::new(data) {
+        // A valid representation is emitted again, like a relayed frame
+        if let Ok(repr) = FrameRepr::parse(&frame).and_then(|repr| repr.validate().map(|_| repr)) {
+            let mut buffer = std::vec![0; repr.buffer_len()];
+            repr.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+        }
+    }
+
+    // The readers of `Frame` are only checked for their own frame type, while
+    // its representation is parsed as if the frame is a data frame.
+    if let Ok(frame) = Frame::new(data) {
+        let _ = frame.sequence_number();
+        let _ = frame.auxiliary_security_header();
+        let _ = frame.information_elements();
+        let _ = FrameRepr::parse(&DataFrame::new_unchecked(data));
+        #[cfg(feature = "display")]
+        let _ = std::format!("{frame}");
+    }
+}
+
+#[test]
+fn corpus() {
+    if std::env::var_os("DOT15D4_IMPORT_CRASHES").is_some() {
+        std::println!("imported {} crashes", import_crashes());
+    }
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(CORPUS)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    let panicked: Vec<String> = paths
+        .iter()
+        .filter(|path| {
+            let data = std::fs::read(path).unwrap();
+            std::panic::catch_unwind(|| read(&data)).is_err()
+        })
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+
+    assert!(panicked.is_empty(), "panicked on {panicked:?}");
+}
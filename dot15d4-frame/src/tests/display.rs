@@ -0,0 +1,241 @@
+use crate::*;
+
+/// Parse a frame and return its [`FrameRepr`] formatted with [`Display`].
+///
+/// [`Display`]: core::fmt::Display
+fn display(frame: &[u8]) -> std::string::String {
+    let frame = DataFrame::new(frame).unwrap();
+    format!("{}", FrameRepr::parse(&frame).unwrap())
+}
+
+#[test]
+fn display_enhanced_beacon() {
+    let frame = [
+        0x40, 0xeb, 0xcd, 0xab, 0xff, 0xff, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00,
+        0x3f, 0x11, 0x88, 0x06, 0x1a, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x1c, 0x00, 0x01,
+        0xc8, 0x00, 0x01, 0x1b, 0x00,
+    ];
+
+    assert_eq!(
+        display(&frame),
+        "Frame Control
+  frame type: Enhanced Beacon
+  security: 0
+  frame pending: 0
+  ack request: 0
+  pan id compression: 1
+  sequence number suppression: 1
+  information elements present: 1
+  dst addressing mode: Short
+  src addressing mode: Extended
+  frame version: 2 (Ieee802154_2020)
+Addressing
+  dst pan id: abcd
+  dst addr: ff:ff (broadcast)
+  src addr: 00:01:00:01:00:01:00:01
+Information Elements
+  Header Information Elements
+    HeaderTermination1
+  Payload Information Elements
+    MLME
+      TschSynchronization
+        ASN: 14, join metric: 0
+      TschTimeslot
+        slot ID: 0
+      ChannelHopping
+        sequence ID: 0
+      TschSlotframeAndLink
+        #slotframes: 0
+"
+    );
+}
+
+#[test]
+fn display_enhanced_beacon_with_slotframes() {
+    let frame = [
+        0x40, 0xeb, 0xcd, 0xab, 0xff, 0xff, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00,
+        0x3f, 0x37, 0x88, 0x06, 0x1a, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x19, 0x1c, 0x01, 0x08,
+        0x07, 0x80, 0x00, 0x48, 0x08, 0xfc, 0x03, 0x20, 0x03, 0xe8, 0x03, 0x98, 0x08, 0x90, 0x01,
+        0xc0, 0x00, 0x60, 0x09, 0xa0, 0x10, 0x10, 0x27, 0x01, 0xc8, 0x00, 0x0f, 0x1b, 0x01, 0x00,
+        0x11, 0x00, 0x02, 0x00, 0x00, 0x01, 0x00, 0x06, 0x01, 0x00, 0x02, 0x00, 0x07,
+    ];
+
+    assert_eq!(
+        display(&frame),
+        "Frame Control
+  frame type: Enhanced Beacon
+  security: 0
+  frame pending: 0
+  ack request: 0
+  pan id compression: 1
+  sequence number suppression: 1
+  information elements present: 1
+  dst addressing mode: Short
+  src addressing mode: Extended
+  frame version: 2 (Ieee802154_2020)
+Addressing
+  dst pan id: abcd
+  dst addr: ff:ff (broadcast)
+  src addr: 00:01:00:01:00:01:00:01
+Information Elements
+  Header Information Elements
+    HeaderTermination1
+  Payload Information Elements
+    MLME
+      TschSynchronization
+        ASN: 17, join metric: 0
+      TschTimeslot
+        slot ID: 1
+        cca_offset: 1.80ms
+        cca: 0.13ms
+        tx offset: 2.12ms
+        rx offset: 1.02ms
+        tx ack delay: 1.00ms
+        rx ack delay: 0.80ms
+        rx wait: 2.20ms
+        ack wait: 0.40ms
+        rx/tx: 0.19ms
+        max ack: 2.40ms
+        max tx: 4.26ms
+        timeslot length: 10.00ms
+      ChannelHopping
+        sequence ID: 0
+      TschSlotframeAndLink
+        #slotframes: 1
+        Slotframe Handle: 0, #links: 2
+          Timeslot: 0, Channel Offset: 1, Link Options: Rx | Shared
+          Timeslot: 1, Channel Offset: 2, Link Options: Tx | Rx | Shared
+"
+    );
+}
+
+#[test]
+fn display_enhanced_ack() {
+    let frame = [
+        0x02, 0x2e, 0x37, 0xcd, 0xab, 0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x02, 0x0f,
+        0xe1, 0x8f,
+    ];
+
+    assert_eq!(
+        display(&frame),
+        "Frame Control
+  frame type: Enhanced Ack
+  security: 0
+  frame pending: 0
+  ack request: 0
+  pan id compression: 0
+  sequence number suppression: 0
+  information elements present: 1
+  dst addressing mode: Extended
+  src addressing mode: Absent
+  frame version: 2 (Ieee802154_2020)
+Sequence Number
+  sequence number: 55
+Addressing
+  dst pan id: abcd
+  dst addr: 00:02:00:02:00:02:00:02
+  src addr: absent
+Information Elements
+  Header Information Elements
+    TimeCorrection
+      -0.03ms, nack: 1
+"
+    );
+}
+
+#[test]
+#[cfg(feature = "legacy-frames")]
+fn display_data_frame() {
+    let frame = [
+        0x41, 0xd8, 0x01, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12, 0x00,
+        0x2b, 0x00, 0x00, 0x00,
+    ];
+
+    assert_eq!(
+        display(&frame),
+        "Frame Control
+  frame type: Data
+  security: 0
+  frame pending: 0
+  ack request: 0
+  pan id compression: 1
+  sequence number suppression: 0
+  information elements present: 0
+  dst addressing mode: Short
+  src addressing mode: Extended
+  frame version: 1 (Ieee802154_2006)
+Sequence Number
+  sequence number: 1
+Addressing
+  dst pan id: abcd
+  dst addr: ff:ff (broadcast)
+  src addr: 00:12:4b:00:14:b5:d9:c7
+Payload
+  [2b, 0, 0, 0]
+"
+    );
+}
+
+#[test]
+#[cfg(feature = "legacy-frames")]
+fn display_secured_data_frame() {
+    let frame = [
+        0x49, 0xd8, 0x01, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12, 0x00,
+        0x0d, 0x04, 0x03, 0x02, 0x01, 0x01, 0x2b, 0x00, 0x00, 0x00,
+    ];
+
+    assert_eq!(
+        display(&frame),
+        "Frame Control
+  frame type: Data
+  security: 1
+  frame pending: 0
+  ack request: 0
+  pan id compression: 1
+  sequence number suppression: 0
+  information elements present: 0
+  dst addressing mode: Short
+  src addressing mode: Extended
+  frame version: 1 (Ieee802154_2006)
+Sequence Number
+  sequence number: 1
+Addressing
+  dst pan id: abcd
+  dst addr: ff:ff (broadcast)
+  src addr: 00:12:4b:00:14:b5:d9:c7
+Auxiliary Security Header
+  security level: EncMic32
+  frame counter: 16909060
+  asn in nonce: 0
+  key identifier mode: Explicit
+  key index: 1
+Payload
+  [2b, 0, 0, 0]
+"
+    );
+}
+
+#[test]
+#[cfg(feature = "display")]
+fn display_frame_readers() {
+    let frame = [
+        0x40, 0xeb, 0xcd, 0xab, 0xff, 0xff, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00,
+        0x3f, 0x11, 0x88, 0x06, 0x1a, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x1c, 0x00, 0x01,
+        0xc8, 0x00, 0x01, 0x1b, 0x00,
+    ];
+    let reader = Frame::new(&frame[..]).unwrap();
+    assert_eq!(format!("{reader}"), display(&frame));
+    assert_eq!(
+        format!("{}", EnhancedBeacon::new(&frame[..]).unwrap()),
+        display(&frame)
+    );
+
+    // The width indents every line
+    assert!(format!("{reader:2}")
+        .lines()
+        .all(|line| line.starts_with("  ")));
+
+    // Frames that can not be parsed are formatted as raw bytes
+    let reader = DataFrame::new_unchecked(&[0x41][..]);
+    assert_eq!(format!("{reader}"), "[41]\n");
+}
@@ -0,0 +1,72 @@
+//! Golden tests: every frame of the corpus in `tests/golden` is parsed,
+//! compared with its expected [`FrameRepr`] as JSON, and emitted again.
+//!
+//! Run with `DOT15D4_BLESS=1` to write the parsed representations to the
+//! corpus instead, e.g. after adding a frame. Review the result before
+//! committing it.
+
+use std::path::Path;
+use std::string::{String, ToString};
+use std::vec::Vec;
+
+use serde_json::Value;
+
+use crate::{DataFrame, Frame, FrameRepr};
+
+const CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden");
+
+fn check(path: &Path, bless: bool) {
+    let mut entry: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    let name = path.file_name().unwrap().to_string_lossy();
+
+    if entry["legacy"].as_bool().unwrap_or(false) && !cfg!(feature = "legacy-frames") {
+        assert!(Frame::new(
+            &crate::hex::decode_to_vec(entry["frame"].as_str().unwrap()).unwrap()[..]
+        )
+        .is_err());
+        return;
+    }
+
+    let buffer = crate::hex::decode_to_vec(entry["frame"].as_str().unwrap()).unwrap();
+    Frame::new(&buffer[..]).unwrap_or_else(|_| panic!("{name}: invalid frame"));
+    let repr = FrameRepr::parse(&DataFrame::new_unchecked(&buffer[..]))
+        .unwrap_or_else(|_| panic!("{name}: unable to parse the frame"));
+    let parsed = serde_json::to_value(&repr).unwrap();
+
+    if bless {
+        entry["repr"] = parsed;
+        let json = serde_json::to_string_pretty(&entry).unwrap();
+        std::fs::write(path, json + "\n").unwrap();
+        return;
+    }
+
+    assert_eq!(parsed, entry["repr"], "{name}: unexpected representation");
+
+    let mut emitted = vec![0; repr.buffer_len()];
+    repr.emit(&mut DataFrame::new_unchecked(&mut emitted[..]));
+    assert_eq!(
+        crate::hex::Hex(&emitted).to_string(),
+        entry["frame"].as_str().unwrap(),
+        "{name}: the emitted frame differs"
+    );
+}
+
+#[test]
+fn golden() {
+    let bless = std::env::var_os("DOT15D4_BLESS").is_some();
+
+    let mut paths: Vec<_> = std::fs::read_dir(CORPUS)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "json")
+        })
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        check(&path, bless);
+    }
+}
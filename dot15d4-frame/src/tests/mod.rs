@@ -2,6 +2,12 @@ use crate::time::Duration;
 
 use super::*;
 
+// The `panic` feature panics on purpose on the unknown types of the corpus
+#[cfg(not(feature = "panic"))]
+mod corpus;
+mod display;
+#[cfg(feature = "serde")]
+mod golden;
 mod parsing;
 
 #[macro_export]
@@ -46,6 +52,7 @@ macro_rules! test_sub_element {
 }
 
 #[test]
+#[cfg(feature = "imm-ack")]
 fn emit_imm_ack() {
     let imm_ack = FrameBuilder::new_imm_ack(1).finalize().unwrap();
 
@@ -85,6 +92,7 @@ fn emit_ack_frame() {
 }
 
 #[test]
+#[cfg(feature = "legacy-frames")]
 fn emit_data_frame() {
     let frame = FrameBuilder::new_data(&[0x2b, 0x00, 0x00, 0x00])
         .set_sequence_number(1)
@@ -110,6 +118,33 @@ fn emit_data_frame() {
     );
 }
 
+#[test]
+#[cfg(not(feature = "legacy-frames"))]
+fn emit_data_frame_2020() {
+    let frame = FrameBuilder::new_data(&[0x2b, 0x00, 0x00, 0x00])
+        .set_sequence_number(1)
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::BROADCAST)
+        .set_src_pan_id(0xabcd)
+        .set_src_address(Address::Extended([
+            0x00, 0x12, 0x4b, 0x00, 0x14, 0xb5, 0xd9, 0xc7,
+        ]))
+        .finalize()
+        .unwrap();
+
+    let mut buffer = vec![0; frame.buffer_len()];
+
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    assert_eq!(
+        buffer,
+        [
+            0x41, 0xe8, 0x01, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12,
+            0x00, 0x2b, 0x00, 0x00, 0x00,
+        ]
+    );
+}
+
 #[test]
 fn emit_enhanced_beacon() {
     let frame = FrameRepr {
@@ -134,12 +169,13 @@ fn emit_enhanced_beacon() {
                 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01,
             ])),
         }),
+        auxiliary_security_header: None,
         information_elements: Some(InformationElementsRepr {
             header_information_elements: heapless::Vec::new(),
             payload_information_elements: heapless::Vec::from_iter([
                 PayloadInformationElementRepr::Mlme(heapless::Vec::from_iter([
                     NestedInformationElementRepr::TschSynchronization(TschSynchronizationRepr {
-                        absolute_slot_number: 17,
+                        absolute_slot_number: AbsoluteSlotNumber::new(17).unwrap(),
                         join_metric: 0,
                     }),
                     NestedInformationElementRepr::TschTimeslot(TschTimeslotRepr::Custom(
@@ -196,6 +232,733 @@ fn emit_enhanced_beacon() {
     );
 }
 
+#[test]
+#[cfg(feature = "legacy-frames")]
+fn emit_data_request() {
+    let frame = FrameBuilder::new_data_request()
+        .set_sequence_number(7)
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::Short([0x00, 0x01]))
+        .set_src_address(Address::Extended([
+            0x00, 0x12, 0x4b, 0x00, 0x14, 0xb5, 0xd9, 0xc7,
+        ]))
+        .finalize()
+        .unwrap();
+
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    assert_eq!(
+        buffer,
+        [
+            0x63, 0xd8, 0x07, 0xcd, 0xab, 0x01, 0x00, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12,
+            0x00, 0x04,
+        ]
+    );
+
+    let frame = DataFrame::new(&buffer[..]).unwrap();
+    assert_eq!(frame.frame_control().frame_type(), FrameType::MacCommand);
+    assert_eq!(
+        frame.payload().map(|payload| CommandId::from(payload[0])),
+        Some(CommandId::DataRequest)
+    );
+}
+
+#[test]
+fn emit_beacon_requests() {
+    let frame = FrameBuilder::new_beacon_request()
+        .set_sequence_number(3)
+        .finalize()
+        .unwrap();
+
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    let frame = DataFrame::new(&buffer[..]).unwrap();
+    assert_eq!(frame.command_id(), Some(CommandId::BeaconRequest));
+    let addressing = frame.addressing().unwrap();
+    assert_eq!(addressing.dst_pan_id(), Some(0xffff));
+    assert_eq!(addressing.dst_address(), Some(Address::BROADCAST));
+    assert_eq!(addressing.src_address(), Some(Address::Absent));
+    assert!(frame.information_elements().is_none());
+
+    let repr = FrameBuilder::new_enhanced_beacon_request(EnhancedBeaconFilterRepr {
+        permit_joining_on: true,
+        link_quality: None,
+        percent_filter: Some(50),
+    })
+    .set_sequence_number(3)
+    .finalize()
+    .unwrap();
+
+    let mut buffer = vec![0; repr.buffer_len()];
+    repr.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    assert_eq!(
+        buffer,
+        [
+            0x03, 0x2a, 0x03, 0xff, 0xff, 0xff, 0xff, 0x00, 0x3f, 0x04, 0x88, 0x02, 0x1e, 0x05,
+            0x32, 0x00, 0xf8, 0x07,
+        ]
+    );
+
+    let frame = DataFrame::new(&buffer[..]).unwrap();
+    assert_eq!(frame.command_id(), Some(CommandId::BeaconRequest));
+    let ie = frame.information_elements().unwrap();
+    let filter = ie.enhanced_beacon_filter().unwrap();
+    assert!(filter.permit_joining_on());
+    assert_eq!(filter.link_quality(), None);
+    assert_eq!(filter.percent_filter(), Some(50));
+
+    let parsed = frame.to_repr().unwrap();
+    let mut emitted = vec![0; parsed.buffer_len()];
+    parsed.emit(&mut DataFrame::new_unchecked(&mut emitted[..]));
+    assert_eq!(emitted, buffer);
+}
+
+#[test]
+fn emit_secured_data_frame() {
+    for key_identifier in [
+        KeyIdentifierRepr::Implicit,
+        KeyIdentifierRepr::Index(1),
+        KeyIdentifierRepr::ShortSource([1, 2, 3, 4], 2),
+        KeyIdentifierRepr::LongSource([1, 2, 3, 4, 5, 6, 7, 8], 3),
+    ] {
+        let aux = AuxiliarySecurityHeaderRepr {
+            security_level: SecurityAttributes::EncMic32,
+            frame_counter: Some(0x01020304),
+            asn_in_nonce: false,
+            key_identifier,
+        };
+        let frame = FrameBuilder::new_data(&[0x2b, 0x00, 0x00, 0x00])
+            .set_sequence_number(1)
+            .set_dst_pan_id(0xabcd)
+            .set_dst_address(Address::BROADCAST)
+            .set_src_address(Address::Extended([
+                0x00, 0x12, 0x4b, 0x00, 0x14, 0xb5, 0xd9, 0xc7,
+            ]))
+            .set_auxiliary_security_header(aux)
+            .finalize()
+            .unwrap();
+
+        let mut buffer = vec![0; frame.buffer_len()];
+        frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+        let reader = DataFrame::new(&buffer[..]).unwrap();
+        assert!(reader.frame_control().security_enabled());
+        assert_eq!(
+            reader.auxiliary_security_header().unwrap().len(),
+            aux.buffer_len()
+        );
+
+        let repr = FrameRepr::parse(&reader).unwrap();
+        assert_eq!(repr.auxiliary_security_header, Some(aux));
+        assert_eq!(repr.payload, Some(&[0x2b, 0x00, 0x00, 0x00][..]));
+        assert!(repr.validate().is_ok());
+    }
+}
+
+#[test]
+fn ccm_parts() {
+    let src = [0x00, 0x12, 0x4b, 0x00, 0x14, 0xb5, 0xd9, 0xc7];
+    for (security_level, mic_len, encrypted) in [
+        (SecurityAttributes::EncMic32, 4, true),
+        (SecurityAttributes::Mic64, 8, false),
+    ] {
+        let aux = AuxiliarySecurityHeaderRepr {
+            security_level,
+            frame_counter: Some(0x01020304),
+            asn_in_nonce: false,
+            key_identifier: KeyIdentifierRepr::Index(1),
+        };
+        let frame = FrameBuilder::new_data(&[0x2b, 0x00, 0x00, 0x00])
+            .set_sequence_number(1)
+            .set_dst_pan_id(0xabcd)
+            .set_dst_address(Address::BROADCAST)
+            .set_src_address(Address::Extended(src))
+            .set_auxiliary_security_header(aux)
+            .finalize()
+            .unwrap();
+
+        let mut buffer = vec![0xee; frame.buffer_len() + mic_len];
+        frame.emit(&mut DataFrame::new_unchecked(
+            &mut buffer[..frame.buffer_len()],
+        ));
+
+        let reader = DataFrame::new(&buffer[..]).unwrap();
+        let parts = reader.ccm_parts(&src).unwrap();
+        assert_eq!(parts.open_len, frame.buffer_len() - 4);
+        assert_eq!(parts.mic_len, mic_len);
+        assert_eq!(parts.encrypted, encrypted);
+        assert_eq!(parts.mic(&buffer), &[0xee; 8][..mic_len]);
+        assert_eq!(
+            parts.nonce,
+            [
+                0x00,
+                0x12,
+                0x4b,
+                0x00,
+                0x14,
+                0xb5,
+                0xd9,
+                0xc7,
+                0x01,
+                0x02,
+                0x03,
+                0x04,
+                security_level as u8
+            ]
+        );
+
+        if encrypted {
+            assert_eq!(parts.a_data(&buffer), &buffer[..parts.open_len]);
+            assert_eq!(parts.m_data(&buffer), &[0x2b, 0x00, 0x00, 0x00]);
+        } else {
+            assert_eq!(parts.a_data(&buffer), &buffer[..frame.buffer_len()]);
+            assert!(parts.m_data(&buffer).is_empty());
+        }
+
+        // The same parts, to transform the frame in place
+        let copy = buffer.clone();
+        let (a_data, m_data, mic) = parts.split_mut(&mut buffer);
+        assert_eq!(a_data, parts.a_data(&copy));
+        assert_eq!(m_data, parts.m_data(&copy));
+        assert_eq!(mic, parts.mic(&copy));
+    }
+
+    // The Command ID of a MAC command frame is open, as in the secured
+    // Association Request example of IEEE 802.15.4-2006 Annex C
+    #[cfg(feature = "legacy-frames")]
+    {
+        let command = [
+            0x2b, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0xff,
+            0xff, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x06, 0x05, 0x00, 0x00, 0x00,
+            0x01, 0xd8, 0x4f, 0xde, 0x52, 0x90, 0x61, 0xf9, 0xc6, 0xf1,
+        ];
+        let parts = DataFrame::new(&command[..])
+            .unwrap()
+            .ccm_parts(&[0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01])
+            .unwrap();
+        assert_eq!(parts.a_data(&command), &command[..29]);
+        assert_eq!(parts.m_data(&command), &[0xd8]);
+    }
+
+    // Frames without security have no CCM* inputs
+    let frame = FrameBuilder::new_data(&[0x2b])
+        .set_sequence_number(1)
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::BROADCAST)
+        .set_src_address(Address::Extended(src))
+        .finalize()
+        .unwrap();
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+    assert!(DataFrame::new(&buffer[..])
+        .unwrap()
+        .ccm_parts(&src)
+        .is_none());
+}
+
+#[test]
+fn emit_secured_enhanced_ack() {
+    let aux = AuxiliarySecurityHeaderRepr {
+        security_level: SecurityAttributes::Mic64,
+        frame_counter: Some(7),
+        asn_in_nonce: false,
+        key_identifier: KeyIdentifierRepr::Index(1),
+    };
+    let frame = FrameBuilder::new_ack()
+        .set_sequence_number(55)
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::Extended([
+            0x00, 0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x02,
+        ]))
+        .set_auxiliary_security_header(aux)
+        .add_header_information_element(HeaderInformationElementRepr::TimeCorrection(
+            TimeCorrectionRepr {
+                time_correction: crate::time::Duration::from_us(-31),
+                nack: true,
+            },
+        ))
+        .finalize()
+        .unwrap();
+
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    let ack = crate::frames::ack::EnhancedAck::new(&buffer[..]).unwrap();
+    assert!(ack.frame_control().security_enabled());
+    assert_eq!(
+        ack.frame_control().frame_version(),
+        FrameVersion::Ieee802154_2020
+    );
+    assert_eq!(
+        AuxiliarySecurityHeaderRepr::parse(&ack.auxiliary_security_header().unwrap()).unwrap(),
+        aux
+    );
+
+    let ie = ack.information_elements().unwrap();
+    let mut headers = ie.header_information_elements();
+    assert_eq!(
+        headers.next().unwrap().element_id(),
+        HeaderElementId::TimeCorrection
+    );
+}
+
+#[test]
+#[cfg(feature = "legacy-frames")]
+fn version_policy() {
+    let builder = || {
+        FrameBuilder::new_data(&[0x2b])
+            .set_sequence_number(1)
+            .set_dst_pan_id(0xabcd)
+            .set_dst_address(Address::Short([0x00, 0x01]))
+            .set_src_address(Address::Short([0x00, 0x02]))
+    };
+    let link_margin = || {
+        PayloadInformationElementRepr::Mlme(heapless::Vec::from_iter([
+            NestedInformationElementRepr::LinkMargin(LinkMarginRepr { link_margin: -5 }),
+        ]))
+    };
+
+    // Frames are upgraded to IEEE 802.15.4-2020 for their Information
+    // Elements by default
+    let frame = builder()
+        .add_payload_information_element(link_margin())
+        .finalize()
+        .unwrap();
+    assert_eq!(
+        frame.frame_control.frame_version,
+        FrameVersion::Ieee802154_2020
+    );
+
+    // A strict builder refuses to emit Information Elements in older frames
+    assert!(builder()
+        .set_version_policy(VersionPolicy::Strict)
+        .add_payload_information_element(link_margin())
+        .finalize()
+        .is_err());
+    let frame = builder()
+        .set_version_policy(VersionPolicy::Strict)
+        .finalize()
+        .unwrap();
+    assert_eq!(
+        frame.frame_control.frame_version,
+        FrameVersion::Ieee802154_2006
+    );
+}
+
+#[test]
+fn emit_ie_only_frames() {
+    let time_correction = || {
+        HeaderInformationElementRepr::TimeCorrection(TimeCorrectionRepr {
+            time_correction: crate::time::Duration::from_us(-31),
+            nack: true,
+        })
+    };
+
+    // An Enhanced Acknowledgment without sequence number and addresses
+    let frame = FrameBuilder::new_ack()
+        .set_sequence_number(55)
+        .suppress_sequence_number()
+        .add_header_information_element(time_correction())
+        .finalize()
+        .unwrap();
+    assert!(frame.validate().is_ok());
+
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+    assert_eq!(buffer, [0x02, 0x23, 0x02, 0x0f, 0xe1, 0x8f]);
+
+    let ack = crate::frames::ack::EnhancedAck::new(&buffer[..]).unwrap();
+    assert_eq!(ack.sequence_number(), None);
+    assert!(ack.payload().unwrap_or_default().is_empty());
+
+    // A data frame with Information Elements and an empty payload
+    let frame = FrameBuilder::new_data(&[])
+        .suppress_sequence_number()
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::Short([0x00, 0x02]))
+        .add_header_information_element(time_correction())
+        .finalize()
+        .unwrap();
+    assert_eq!(frame.payload, None);
+    assert!(frame.validate().is_ok());
+
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+    let parsed = DataFrame::new(&buffer[..]).unwrap();
+    assert_eq!(parsed.sequence_number(), None);
+    let parsed = FrameRepr::parse(&parsed).unwrap();
+    assert!(parsed.validate().is_ok());
+    assert!(parsed.canonical_eq(&frame));
+
+    // Without Information Elements, a data frame needs a payload
+    let frame = FrameBuilder::new_data(&[])
+        .set_sequence_number(1)
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::Short([0x00, 0x02]))
+        .finalize()
+        .unwrap();
+    assert!(frame.validate().is_err());
+}
+
+#[test]
+fn truncated_addressing() {
+    // A secured Enhanced Acknowledgment announcing extended addresses, of
+    // which the Addressing Fields are cut short
+    let data = [
+        0x6a, 0xef, 0x3f, 0x23, 0x89, 0xcd, 0xab, 0x43, 0x01, 0x00, 0xcd, 0xab, 0x02, 0x0f, 0x5a,
+        0xc4, 0x01,
+    ];
+    assert!(DataFrame::new(&data[..]).is_err());
+
+    // The addressing modes of a representation must match its addresses
+    let mut frame = FrameBuilder::new_ack()
+        .set_sequence_number(1)
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::Short([0x00, 0x02]))
+        .finalize()
+        .unwrap();
+    assert!(frame.validate().is_ok());
+    frame.frame_control.dst_addressing_mode = AddressingMode::Extended;
+    assert!(frame.validate().is_err());
+    frame.addressing_fields = None;
+    assert!(frame.validate().is_err());
+}
+
+#[test]
+fn emit_derived_fields() {
+    let frame = |ie: Option<InformationElementsRepr>, present: bool| FrameRepr {
+        frame_control: FrameControlRepr {
+            frame_type: FrameType::Data,
+            security_enabled: false,
+            frame_pending: false,
+            ack_request: false,
+            pan_id_compression: false,
+            sequence_number_suppression: true,
+            information_elements_present: present,
+            dst_addressing_mode: AddressingMode::Short,
+            src_addressing_mode: AddressingMode::Absent,
+            frame_version: FrameVersion::Ieee802154_2020,
+        },
+        sequence_number: None,
+        addressing_fields: Some(AddressingFieldsRepr {
+            dst_pan_id: Some(0xabcd),
+            src_pan_id: None,
+            dst_address: Some(Address::Short([0x00, 0x02])),
+            src_address: None,
+        }),
+        auxiliary_security_header: None,
+        information_elements: ie,
+        payload: Some(&[0x2b]),
+    };
+    let emit = |frame: &FrameRepr| {
+        let mut buffer = vec![0; frame.buffer_len()];
+        frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+        buffer
+    };
+    let emit_strict = |frame: &FrameRepr| {
+        let mut buffer = vec![0; frame.buffer_len()];
+        frame
+            .emit_strict(&mut DataFrame::new_unchecked(&mut buffer[..]))
+            .map(|_| buffer)
+    };
+    fn headers<const N: usize>(
+        headers: [HeaderInformationElementRepr; N],
+    ) -> InformationElementsRepr {
+        let mut ie = InformationElementsRepr::default();
+        ie.header_information_elements.extend(headers);
+        ie
+    }
+    let time_correction = || {
+        HeaderInformationElementRepr::TimeCorrection(TimeCorrectionRepr {
+            time_correction: Duration::from_us(-31),
+            nack: true,
+        })
+    };
+
+    // The flag and the HT2 in front of the payload are derived
+    let expected = emit(&frame(Some(headers([time_correction()])), true));
+    assert_eq!(
+        expected,
+        [0x01, 0x2b, 0xcd, 0xab, 0x02, 0x00, 0x02, 0x0f, 0xe1, 0x8f, 0x80, 0x3f, 0x2b]
+    );
+    assert_eq!(
+        emit(&frame(Some(headers([time_correction()])), false)),
+        expected
+    );
+    assert_eq!(
+        emit(&frame(
+            Some(headers([
+                time_correction(),
+                HeaderInformationElementRepr::HeaderTermination1
+            ])),
+            true
+        )),
+        expected
+    );
+
+    // Without Information Elements, the flag is cleared
+    let expected = emit(&frame(None, false));
+    assert_eq!(expected, [0x01, 0x29, 0xcd, 0xab, 0x02, 0x00, 0x2b]);
+    assert_eq!(emit(&frame(None, true)), expected);
+    assert_eq!(
+        emit(&frame(
+            Some(headers([HeaderInformationElementRepr::HeaderTermination2])),
+            true
+        )),
+        expected
+    );
+
+    // The strict mode rejects what is derived otherwise
+    assert!(emit_strict(&frame(Some(headers([time_correction()])), true)).is_ok());
+    assert!(emit_strict(&frame(
+        Some(headers([
+            time_correction(),
+            HeaderInformationElementRepr::HeaderTermination2
+        ])),
+        true
+    ))
+    .is_ok());
+    assert!(emit_strict(&frame(Some(headers([time_correction()])), false)).is_err());
+    assert!(emit_strict(&frame(None, true)).is_err());
+    assert!(emit_strict(&frame(
+        Some(headers([
+            time_correction(),
+            HeaderInformationElementRepr::HeaderTermination1
+        ])),
+        true
+    ))
+    .is_err());
+}
+
+#[test]
+fn emit_link_margin() {
+    let frame = FrameBuilder::new_data(&[0x2b])
+        .set_sequence_number(1)
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::Short([0x00, 0x00]))
+        .set_src_address(Address::Extended([
+            0x00, 0x12, 0x4b, 0x00, 0x14, 0xb5, 0xd9, 0xc7,
+        ]))
+        .add_payload_information_element(PayloadInformationElementRepr::Mlme(
+            heapless::Vec::from_iter([NestedInformationElementRepr::LinkMargin(LinkMarginRepr {
+                link_margin: -5,
+            })]),
+        ))
+        .finalize()
+        .unwrap();
+
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    assert_eq!(
+        buffer,
+        [
+            0x41, 0xea, 0x01, 0xcd, 0xab, 0x00, 0x00, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12,
+            0x00, 0x00, 0x3f, 0x03, 0x88, 0x01, 0x37, 0xfb, 0x00, 0xf8, 0x2b,
+        ]
+    );
+
+    let frame = DataFrame::new(&buffer[..]).unwrap();
+    let ie = frame.information_elements().unwrap();
+    let payload = ie.payload_information_elements().next().unwrap();
+    let nested = payload.nested_information_elements().next().unwrap();
+    assert_eq!(
+        nested.sub_id(),
+        NestedSubId::Short(NestedSubIdShort::LinkMargin)
+    );
+    assert_eq!(LinkMargin::new(nested.content()).unwrap().link_margin(), -5);
+
+    match NestedInformationElementRepr::parse(&nested).unwrap() {
+        NestedInformationElementRepr::LinkMargin(repr) => assert_eq!(repr.link_margin, -5),
+        _ => panic!("expected a Link Margin IE"),
+    }
+}
+
+#[test]
+fn emit_tsch_enhanced_beacon() {
+    let frame = FrameBuilder::new_enhanced_beacon()
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::BROADCAST)
+        .set_src_address(Address::Extended([
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01,
+        ]))
+        .with_tsch_synchronization(AbsoluteSlotNumber::new(17).unwrap(), 0)
+        .with_default_timeslot_template()
+        .with_channel_hopping(0)
+        .with_slotframe(SlotframeDescriptorRepr {
+            handle: 0,
+            size: 17,
+            links: heapless::Vec::from_iter([
+                LinkInformationRepr {
+                    timeslot: 0,
+                    channel_offset: 1,
+                    link_options: TschLinkOptionRepr(TschLinkOption::Rx | TschLinkOption::Shared),
+                },
+                LinkInformationRepr {
+                    timeslot: 1,
+                    channel_offset: 2,
+                    link_options: TschLinkOptionRepr(
+                        TschLinkOption::Tx | TschLinkOption::Rx | TschLinkOption::Shared,
+                    ),
+                },
+            ]),
+        })
+        .finalize()
+        .unwrap();
+
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    assert_eq!(
+        buffer,
+        [
+            0x40, 0xeb, 0xcd, 0xab, 0xff, 0xff, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00,
+            0x00, 0x3f, 0x1f, 0x88, 0x06, 0x1a, 0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x1c,
+            0x00, 0x01, 0xc8, 0x00, 0x0f, 0x1b, 0x01, 0x00, 0x11, 0x00, 0x02, 0x00, 0x00, 0x01,
+            0x00, 0x06, 0x01, 0x00, 0x02, 0x00, 0x07
+        ]
+    );
+
+    let summary = EnhancedBeaconSummary::parse(&EnhancedBeacon::new(&buffer[..]).unwrap()).unwrap();
+    assert_eq!(summary.absolute_slot_number.value(), 17);
+    assert_eq!(summary.join_metric, 0);
+    assert_eq!(summary.timeslot_timings.id(), 0);
+    assert_eq!(summary.hopping_sequence_id, Some(0));
+    assert_eq!(summary.slotframe_descriptors.len(), 1);
+    assert_eq!(summary.slotframe_descriptors[0].size, 17);
+    assert_eq!(summary.slotframe_descriptors[0].links[1].timeslot, 1);
+
+    // Without the TSCH Synchronization IE, the beacon is not a TSCH beacon
+    let frame = FrameBuilder::new_enhanced_beacon()
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::BROADCAST)
+        .set_src_address(Address::Extended([0x01; 8]))
+        .with_channel_hopping(0)
+        .finalize()
+        .unwrap();
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+    assert!(EnhancedBeaconSummary::parse(&EnhancedBeacon::new(&buffer[..]).unwrap()).is_err());
+}
+
+#[test]
+fn builder_overflow() {
+    let slotframe = |handle| SlotframeDescriptorRepr {
+        handle,
+        size: 17,
+        links: heapless::Vec::new(),
+    };
+
+    let mut builder = FrameBuilder::new_enhanced_beacon()
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::BROADCAST)
+        .set_src_address(Address::Extended([0x01; 8]))
+        .with_tsch_synchronization(AbsoluteSlotNumber::new(17).unwrap(), 0);
+    for handle in 0..MAX_SLOTFRAME_DESCRIPTORS as u8 {
+        builder = builder.with_slotframe(slotframe(handle));
+    }
+    let frame = builder.finalize().unwrap();
+    assert!(frame.validate().is_ok());
+
+    // One slotframe too many fails to finalize, instead of panicking.
+    let mut builder = FrameBuilder::new_enhanced_beacon()
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::BROADCAST)
+        .set_src_address(Address::Extended([0x01; 8]));
+    for handle in 0..=MAX_SLOTFRAME_DESCRIPTORS as u8 {
+        builder = builder.with_slotframe(slotframe(handle));
+    }
+    assert!(builder.finalize().is_err());
+
+    let mut builder = FrameBuilder::new_data(&[0x2b]);
+    for _ in 0..=16 {
+        builder = builder.add_payload_information_element(PayloadInformationElementRepr::Mlme(
+            heapless::Vec::new(),
+        ));
+    }
+    assert!(builder.finalize().is_err());
+
+    let mut builder = FrameBuilder::new_data(&[0x2b]);
+    for _ in 0..=16 {
+        builder = builder
+            .add_header_information_element(HeaderInformationElementRepr::HeaderTermination1);
+    }
+    assert!(builder.finalize().is_err());
+}
+
+#[test]
+fn edit_mutable_frames() {
+    // Stamp the ASN in an emitted TSCH Enhanced Beacon
+    let frame = FrameBuilder::new_enhanced_beacon()
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::BROADCAST)
+        .set_src_address(Address::Extended([0x01; 8]))
+        .with_tsch_synchronization(AbsoluteSlotNumber::ZERO, 0)
+        .with_default_timeslot_template()
+        .with_channel_hopping(0)
+        .finalize()
+        .unwrap();
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    let mut beacon = Frame::new(&mut buffer[..]).unwrap();
+    let mut ie = beacon.information_elements_mut().unwrap();
+    let mut sync = ie.tsch_synchronization_mut().unwrap();
+    sync.set_absolute_slot_number(AbsoluteSlotNumber::new(0x01_0000_0002).unwrap());
+    sync.set_join_metric(3);
+    assert!(ie.channel_hopping_mut().is_some());
+    assert!(ie.tsch_slotframe_and_link_mut().is_none());
+    assert!(beacon.payload_mut().is_none());
+
+    let summary = EnhancedBeaconSummary::parse(&EnhancedBeacon::new(&buffer[..]).unwrap()).unwrap();
+    assert_eq!(summary.absolute_slot_number.value(), 0x01_0000_0002);
+    assert_eq!(summary.join_metric, 3);
+    assert_eq!(summary.hopping_sequence_id, Some(0));
+
+    // Edit the Header IEs and the payload of a data frame
+    let frame = FrameBuilder::new_data(&[0x2b, 0x00, 0x00, 0x00])
+        .set_sequence_number(1)
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::Short([0x00, 0x02]))
+        .add_header_information_element(HeaderInformationElementRepr::TimeCorrection(
+            TimeCorrectionRepr {
+                time_correction: Duration::from_us(-31),
+                nack: false,
+            },
+        ))
+        .finalize()
+        .unwrap();
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    let mut data = Frame::new(&mut buffer[..]).unwrap();
+    data.payload_mut().unwrap().copy_from_slice(&[1, 2, 3, 4]);
+    let mut ie = data.information_elements_mut().unwrap();
+    assert!(ie
+        .header_information_element_mut(HeaderElementId::Csl)
+        .is_none());
+    let mut header = ie
+        .header_information_element_mut(HeaderElementId::TimeCorrection)
+        .unwrap();
+    TimeCorrection::new(header.content_mut())
+        .unwrap()
+        .set_time_correction(Duration::from_us(12));
+
+    let data = DataFrame::new(&buffer[..]).unwrap();
+    assert_eq!(data.payload(), Some(&[1, 2, 3, 4][..]));
+    let ie = data.information_elements().unwrap();
+    let header = ie.header_information_elements().next().unwrap();
+    assert_eq!(
+        TimeCorrection::new(header.content())
+            .unwrap()
+            .time_correction(),
+        Duration::from_us(12)
+    );
+}
+
 /// https://github.com/thvdveld/dot15d4/issues/29
 /// Setting `dst_pan_id` to a different value than `src_pan_id` made the `emit` function panic.
 #[test]
@@ -218,3 +981,112 @@ fn issue29() {
     println!("{:?}", frame);
     println!("packet = {:#04X?}", buffer);
 }
+
+#[test]
+fn canonical_eq() {
+    let frame = |payload: &'static [u8]| {
+        FrameBuilder::new_data(payload)
+            .set_sequence_number(1)
+            .set_dst_pan_id(0xabcd)
+            .set_dst_address(Address::Short([0x02, 0x04]))
+            .set_src_address(Address::Extended([
+                0x00, 0x12, 0x4b, 0x00, 0x14, 0xb5, 0xd9, 0xc7,
+            ]))
+            .finalize()
+            .unwrap()
+    };
+
+    // A frame equals itself after a round trip
+    let expected = frame(&[0x2b, 0x00]);
+    let mut buffer = vec![0; expected.buffer_len()];
+    expected.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+    let parsed = FrameRepr::parse(&DataFrame::new(&buffer[..]).unwrap()).unwrap();
+    assert!(parsed.canonical_eq(&expected));
+    assert_frame_eq!(parsed, expected);
+
+    // The elided source PAN ID is the destination PAN ID
+    let mut explicit = frame(&[0x2b, 0x00]);
+    explicit.addressing_fields.as_mut().unwrap().src_pan_id = Some(0xabcd);
+    explicit.frame_control.pan_id_compression = false;
+    assert!(explicit.canonical_eq(&expected));
+    explicit.addressing_fields.as_mut().unwrap().src_pan_id = Some(0xabce);
+    assert_eq!(
+        explicit.canonical_diff(&expected),
+        Some(FrameField::Addressing)
+    );
+
+    // A suppressed sequence number is not compared
+    let mut suppressed = frame(&[0x2b, 0x00]);
+    let mut other = frame(&[0x2b, 0x00]);
+    suppressed.frame_control.sequence_number_suppression = true;
+    other.frame_control.sequence_number_suppression = true;
+    other.sequence_number = Some(2);
+    assert!(suppressed.canonical_eq(&other));
+    assert_eq!(
+        frame(&[0x2b, 0x00]).canonical_diff(&other),
+        Some(FrameField::FrameControl)
+    );
+
+    assert_eq!(
+        frame(&[0x2b, 0x01]).canonical_diff(&expected),
+        Some(FrameField::Payload)
+    );
+}
+
+#[test]
+fn to_repr() {
+    // Receive a data frame, change its sequence number and emit it again
+    let frame = FrameBuilder::new_data(&[0x2b, 0x00])
+        .set_sequence_number(1)
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::Short([0x02, 0x04]))
+        .set_src_address(Address::Short([0x01, 0x00]))
+        .finalize()
+        .unwrap();
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    let mut repr = DataFrame::new(&buffer[..]).unwrap().to_repr().unwrap();
+    assert_frame_eq!(repr, frame);
+    repr.sequence_number = Some(2);
+    let mut emitted = vec![0; repr.buffer_len()];
+    repr.emit(&mut DataFrame::new_unchecked(&mut emitted[..]));
+    let emitted = DataFrame::new(&emitted[..]).unwrap();
+    assert_eq!(emitted.sequence_number(), Some(2));
+    assert_eq!(emitted.payload(), Some(&[0x2b, 0x00][..]));
+
+    // The Information Elements of an Enhanced Beacon are kept
+    let frame = FrameBuilder::new_enhanced_beacon()
+        .set_dst_pan_id(0xabcd)
+        .set_dst_address(Address::BROADCAST)
+        .set_src_address(Address::Short([0x01, 0x00]))
+        .with_tsch_synchronization(AbsoluteSlotNumber::new(17).unwrap(), 0)
+        .finalize()
+        .unwrap();
+    let mut buffer = vec![0; frame.buffer_len()];
+    frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+
+    let mut repr = EnhancedBeacon::new(&buffer[..]).unwrap().to_repr().unwrap();
+    assert_frame_eq!(repr, frame);
+    repr.addressing_fields.as_mut().unwrap().dst_pan_id = Some(0x1234);
+    let mut emitted = vec![0; repr.buffer_len()];
+    repr.emit(&mut DataFrame::new_unchecked(&mut emitted[..]));
+    let emitted = EnhancedBeacon::new(&emitted[..]).unwrap();
+    assert_eq!(emitted.addressing().unwrap().dst_pan_id(), Some(0x1234));
+    let summary = EnhancedBeaconSummary::parse(&emitted).unwrap();
+    assert_eq!(summary.absolute_slot_number.value(), 17);
+}
+
+#[test]
+#[should_panic(expected = "frames differ in the payload")]
+fn assert_frame_eq_reports_field() {
+    let frame = |payload: &'static [u8]| {
+        FrameBuilder::new_data(payload)
+            .set_dst_pan_id(0xabcd)
+            .set_dst_address(Address::BROADCAST)
+            .set_src_address(Address::Short([0x01, 0x00]))
+            .finalize()
+            .unwrap()
+    };
+    assert_frame_eq!(frame(&[1]), frame(&[2]));
+}
@@ -3,6 +3,7 @@ use crate::time::Duration;
 use crate::*;
 
 #[test]
+#[cfg(feature = "imm-ack")]
 fn parse_imm_ack() {
     let frame = [0x02, 0x10, 0x01];
     let frame = Ack::new(&frame).unwrap();
@@ -70,3 +71,38 @@ fn parse_enhanced_ack() {
         }
     );
 }
+
+#[test]
+fn parse_secured_enhanced_ack() {
+    let frame = [
+        0x0a, 0x2c, 0x37, 0xcd, 0xab, 0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x0d, 0x04,
+        0x03, 0x02, 0x01, 0x01, 0xaa, 0xaa, 0xaa, 0xaa,
+    ];
+
+    let ack = EnhancedAck::new(&frame).unwrap();
+
+    test!(
+        ack.frame_control().frame_type() => FrameType::Ack,
+        ack.frame_control().security_enabled() => true,
+        ack.frame_control().information_elements_present() => false,
+        ack.frame_control().frame_version() => FrameVersion::Ieee802154_2020,
+        ack.sequence_number() => Some(55),
+        ack.addressing().unwrap().dst_address() => Some(Address::Extended([0x00, 0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x02])),
+        ack.information_elements().is_none() => true,
+        ack.payload() => Some(&[0xaa, 0xaa, 0xaa, 0xaa][..]),
+    );
+
+    let aux = AuxiliarySecurityHeaderRepr::parse(&ack.auxiliary_security_header().unwrap());
+    assert_eq!(
+        aux.unwrap(),
+        AuxiliarySecurityHeaderRepr {
+            security_level: SecurityAttributes::EncMic32,
+            frame_counter: Some(0x01020304),
+            asn_in_nonce: false,
+            key_identifier: KeyIdentifierRepr::Index(1),
+        }
+    );
+
+    // The Auxiliary Security Header must be complete.
+    assert!(EnhancedAck::new(&frame[..16]).is_err());
+}
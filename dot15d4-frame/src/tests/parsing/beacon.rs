@@ -18,6 +18,7 @@ fn superframe_specification() {
 }
 
 #[test]
+#[cfg(feature = "legacy-beacons")]
 fn gts_specification() {
     use crate::Address;
 
@@ -66,6 +67,7 @@ fn gts_specification() {
 }
 
 #[test]
+#[cfg(feature = "legacy-beacons")]
 fn gts_slot() {
     use crate::Address;
     let data = [0xab, 0xcd, 0b0101_1010];
@@ -79,6 +81,32 @@ fn gts_slot() {
     );
 }
 
+#[test]
+#[cfg(feature = "legacy-beacons")]
+fn parse_beacon() {
+    let frame: [u8; 11] = [
+        0x00, 0x90, 0x01, 0xcd, 0xab, 0x01, 0x00, 0xff, 0xcf, 0x00, 0x00,
+    ];
+
+    let beacon = Beacon::new(&frame[..]).unwrap();
+
+    test!(
+        beacon.frame_control().frame_type() => FrameType::Beacon,
+        beacon.frame_control().frame_version() => FrameVersion::Ieee802154_2006,
+        beacon.sequence_number() => 1,
+        beacon.addressing().dst_address() => Some(Address::Absent),
+        beacon.addressing().src_pan_id() => Some(0xabcd),
+        beacon.addressing().src_address() => Some(Address::Short([0x00, 0x01])),
+        beacon.superframe_specification().pan_coordinator() => true,
+        beacon.superframe_specification().association_permit() => true,
+        beacon.gts_info().length() => 1,
+        beacon.pending_address().length() => 1,
+    );
+
+    assert!(matches!(Frame::new(&frame[..]), Ok(Frame::Beacon(_))));
+    assert!(Beacon::new(&frame[..8]).is_err());
+}
+
 #[test]
 fn parse_enhanced_beacon() {
     let frame: [u8; 35] = [
@@ -134,7 +162,7 @@ fn parse_enhanced_beacon() {
         },
         |tsch_sync| {
             test!(
-                tsch_sync.absolute_slot_number() => 14,
+                tsch_sync.absolute_slot_number().value() => 14,
                 tsch_sync.join_metric() => 0,
             );
         }
@@ -1,6 +1,7 @@
 use crate::*;
 
 #[test]
+#[cfg(feature = "legacy-frames")]
 fn parse_data_frame() {
     let frame = [
         0x41, 0xd8, 0x01, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12, 0x00,
@@ -29,3 +30,37 @@ fn parse_data_frame() {
         frame.payload() => Some(&[0x2b, 0x00, 0x00, 0x00][..]),
     );
 }
+
+#[test]
+#[cfg(feature = "legacy-frames")]
+fn parse_secured_data_frame() {
+    let frame = [
+        0x49, 0xd8, 0x01, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12, 0x00,
+        0x0d, 0x01, 0x02, 0x03, 0x04, 0x05, 0x2b, 0x00, 0x00, 0x00,
+    ];
+
+    let frame = DataFrame::new(&frame[..]).unwrap();
+    let aux = frame.auxiliary_security_header().unwrap();
+
+    test!(
+        frame.frame_control().security_enabled() => true,
+        aux.len() => 6,
+        aux.security_control().key_identifier_mode().key_identifier_mode() => KeyIdentifierMode::Explicit,
+        aux.frame_counter() => Some(0x04030201),
+        aux.key_source() => &[0u8; 0][..],
+        aux.key_index() => Some(0x05),
+        frame.payload() => Some(&[0x2b, 0x00, 0x00, 0x00][..]),
+        FrameRepr::parse(&frame).unwrap().auxiliary_security_header => Some(AuxiliarySecurityHeaderRepr {
+            security_level: SecurityAttributes::EncMic32,
+            frame_counter: Some(0x04030201),
+            asn_in_nonce: false,
+            key_identifier: KeyIdentifierRepr::Index(0x05),
+        }),
+    );
+
+    // The Auxiliary Security Header does not fit in the frame.
+    let frame = [
+        0x49, 0x88, 0x01, 0xcd, 0xab, 0xff, 0xff, 0x01, 0x00, 0x0d, 0x01,
+    ];
+    assert!(DataFrame::new(&frame[..]).is_err());
+}
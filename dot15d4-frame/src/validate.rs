@@ -0,0 +1,249 @@
+//! Validation of complete frames, reporting where a frame is malformed.
+//!
+//! [`DataFrame::new`] checks all fields of a frame, and reports the first
+//! field that is malformed as a [`ParseError`], together with its byte
+//! offset, such that tools can show which part of a frame is broken.
+//! [`field_spans`] additionally reports the byte range of every field, e.g.
+//! for an annotated hexdump or to find the exact extent of the header.
+
+use core::ops::Range;
+
+use crate::{DataFrame, Error};
+
+/// A field of a frame, in the order in which the fields appear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum FrameField {
+    /// The Frame Control field.
+    FrameControl,
+    /// The Sequence Number field.
+    SequenceNumber,
+    /// The Addressing fields.
+    Addressing,
+    /// The Auxiliary Security Header.
+    AuxiliarySecurityHeader,
+    /// A Header Information Element.
+    HeaderInformationElement,
+    /// A Payload Information Element.
+    PayloadInformationElement,
+    /// The payload of the frame.
+    Payload,
+}
+
+impl core::fmt::Display for FrameField {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FrameField::FrameControl => write!(f, "frame control"),
+            FrameField::SequenceNumber => write!(f, "sequence number"),
+            FrameField::Addressing => write!(f, "addressing fields"),
+            FrameField::AuxiliarySecurityHeader => write!(f, "auxiliary security header"),
+            FrameField::HeaderInformationElement => write!(f, "header information element"),
+            FrameField::PayloadInformationElement => write!(f, "payload information element"),
+            FrameField::Payload => write!(f, "payload"),
+        }
+    }
+}
+
+/// Why a field could not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The frame ends before the end of the field.
+    Truncated,
+    /// The frame is longer than 127 octets.
+    TooLong,
+    /// The field holds a reserved value.
+    Reserved,
+    /// The frame type is not supported by the readers of this crate.
+    Unsupported,
+    /// The addressing modes and the PAN ID compression field form an invalid
+    /// combination.
+    InvalidAddressing,
+}
+
+impl core::fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseErrorKind::Truncated => write!(f, "truncated"),
+            ParseErrorKind::TooLong => write!(f, "too long"),
+            ParseErrorKind::Reserved => write!(f, "reserved value in"),
+            ParseErrorKind::Unsupported => write!(f, "unsupported"),
+            ParseErrorKind::InvalidAddressing => write!(f, "invalid"),
+        }
+    }
+}
+
+/// The first malformed field of a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// The field that could not be read.
+    pub field: FrameField,
+    /// Why the field could not be read.
+    pub kind: ParseErrorKind,
+    /// The byte offset of the start of the field.
+    pub offset: usize,
+}
+
+impl core::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} {} at byte {}", self.kind, self.field, self.offset)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(_: ParseError) -> Self {
+        Error
+    }
+}
+
+/// Check that all fields of a frame, without FCS, can be read, as
+/// [`DataFrame::new`] does.
+///
+/// # Errors
+///
+/// Returns the first field that is malformed.
+pub fn validate(buffer: &[u8]) -> Result<(), ParseError> {
+    DataFrame::new(buffer).map(|_| ())
+}
+
+/// Walk over the fields of a frame, without FCS, calling `on_field` with the
+/// byte range of every field that is present, in order, see
+/// [`DataFrame::check_fields`].
+///
+/// # Errors
+///
+/// Returns the first field that is malformed. The fields in front of it are
+/// reported before returning.
+pub fn field_spans(
+    buffer: &[u8],
+    on_field: impl FnMut(FrameField, Range<usize>),
+) -> Result<(), ParseError> {
+    DataFrame::new_unchecked(buffer)
+        .check_fields(on_field)
+        .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(frame: &str) -> Result<(), ParseError> {
+        validate(&crate::hex::decode_to_vec(frame).unwrap())
+    }
+
+    #[test]
+    #[cfg(feature = "legacy-frames")]
+    fn valid_frames() {
+        // Enhanced Beacon
+        assert_eq!(
+            check("40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b00"),
+            Ok(())
+        );
+        // Imm-Ack
+        assert_eq!(check("020005"), Ok(()));
+        // Data frame with a payload
+        assert_eq!(check("41d801cdabffffc7d9b514004b12002b000000"), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "legacy-frames")]
+    fn spans() {
+        let frame = crate::hex::decode_to_vec(
+            "40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b00",
+        )
+        .unwrap();
+        let mut spans = std::vec::Vec::new();
+        assert_eq!(
+            field_spans(&frame, |field, range| spans.push((field, range))),
+            Ok(())
+        );
+        assert_eq!(
+            spans,
+            [
+                (FrameField::FrameControl, 0..2),
+                (FrameField::Addressing, 2..14),
+                (FrameField::HeaderInformationElement, 14..16),
+                (FrameField::PayloadInformationElement, 16..35),
+            ]
+        );
+
+        // A data frame with a payload
+        let frame = crate::hex::decode_to_vec("41d801cdabffffc7d9b514004b12002b000000").unwrap();
+        let mut spans = std::vec::Vec::new();
+        field_spans(&frame, |field, range| spans.push((field, range))).unwrap();
+        assert_eq!(
+            spans,
+            [
+                (FrameField::FrameControl, 0..2),
+                (FrameField::SequenceNumber, 2..3),
+                (FrameField::Addressing, 3..15),
+                (FrameField::Payload, 15..19),
+            ]
+        );
+
+        // The fields in front of a malformed field are reported
+        let mut spans = std::vec::Vec::new();
+        assert!(field_spans(&frame[..10], |field, range| spans.push((field, range))).is_err());
+        assert_eq!(
+            spans,
+            [
+                (FrameField::FrameControl, 0..2),
+                (FrameField::SequenceNumber, 2..3)
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "legacy-frames")]
+    fn malformed_frames() {
+        assert_eq!(
+            check("40"),
+            Err(ParseError {
+                field: FrameField::FrameControl,
+                kind: ParseErrorKind::Truncated,
+                offset: 0,
+            })
+        );
+        assert_eq!(check("0200").unwrap_err().field, FrameField::SequenceNumber);
+
+        // The source address is cut short
+        assert_eq!(
+            check("41d801cdabffffc7d9b5"),
+            Err(ParseError {
+                field: FrameField::Addressing,
+                kind: ParseErrorKind::Truncated,
+                offset: 3,
+            })
+        );
+
+        // The last Payload IE claims more content than there is
+        let err = check("40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b")
+            .unwrap_err();
+        assert_eq!(err.field, FrameField::PayloadInformationElement);
+        assert_eq!(err.kind, ParseErrorKind::Truncated);
+        assert_eq!(err.offset, 16);
+        assert_eq!(
+            format!("{err}"),
+            "truncated payload information element at byte 16"
+        );
+        // The reader rejects the frame with the same error
+        let frame = crate::hex::decode_to_vec(
+            "40ebcdabffff0100010001000100003f1188061a0e0000000000011c0001c800011b",
+        )
+        .unwrap();
+        assert_eq!(DataFrame::new(&frame[..]).unwrap_err(), err);
+
+        // Reserved frame type
+        assert_eq!(check("0400").unwrap_err().kind, ParseErrorKind::Reserved);
+        // Multipurpose frames are not supported
+        assert_eq!(check("0500").unwrap_err().kind, ParseErrorKind::Unsupported);
+        // Only a destination address, but with PAN ID compression, in an IEEE
+        // 802.15.4-2006 frame
+        assert_eq!(
+            check("411800").unwrap_err().kind,
+            ParseErrorKind::InvalidAddressing
+        );
+        assert_eq!(
+            check(&"41".repeat(128)).unwrap_err().kind,
+            ParseErrorKind::TooLong
+        );
+    }
+}
@@ -9,6 +9,9 @@ pub fn frame(attr: TokenStream, item: TokenStream) -> TokenStream {
         .unwrap();
 
     let skip_constructor = args.iter().any(|arg| arg.is_ident("no_constructor"));
+    let generate_repr = args.iter().any(|arg| arg.is_ident("repr"));
+    // A repr is emitted using the setters.
+    let generate_setters = generate_repr || args.iter().any(|arg| arg.is_ident("setters"));
 
     // Get the name of the frame element.
     let input = parse_macro_input!(item as ItemStruct);
@@ -37,6 +40,11 @@ pub fn frame(attr: TokenStream, item: TokenStream) -> TokenStream {
     };
 
     let mut impls = vec![];
+    let mut setters = vec![];
+    let mut repr_fields = vec![];
+    let mut repr_parse = vec![];
+    let mut repr_emit = vec![];
+    let mut repr_len = vec![];
 
     if !skip_constructor {
         impls.push(quote! {
@@ -65,6 +73,9 @@ pub fn frame(attr: TokenStream, item: TokenStream) -> TokenStream {
 
     let mut offset = 0;
     let mut bits_offset = 0;
+    // The size of the structure without its conditional fields and without
+    // the content of variable-length fields.
+    let mut min_size = 0;
 
     for field in input.fields {
         let fnname = field.ident.unwrap();
@@ -237,6 +248,122 @@ pub fn frame(attr: TokenStream, item: TokenStream) -> TokenStream {
                 getter
             };
 
+            // Setters are the counterpart of the getters. Bitfields are written
+            // with a read-modify-write, such that the other fields sharing the
+            // same byte are left untouched. Nested structures have no setter.
+            let ty_str = ty.to_token_stream().to_string();
+            let value = match &into {
+                Some(_) if ty_str == "& [u8]" => None,
+                Some(_) => Some(quote! { #ty::from(value) }),
+                None => Some(quote! { value }),
+            };
+            let setter = match (ty_str.as_str(), value) {
+                ("bool", Some(value)) | ("u8", Some(value))
+                    if bits.is_some() || ty_str == "bool" =>
+                {
+                    let bits = bits.unwrap_or(1);
+                    Some(quote! {
+                        let mask = (((1u16 << #bits) - 1) << #bits_offset) as u8;
+                        let b = &mut self.buffer.as_mut()[#offset..];
+                        b[0] = (b[0] & !mask) | (((#value as u8) << #bits_offset) & mask);
+                    })
+                }
+                ("u8", Some(value)) => Some(quote! {
+                    self.buffer.as_mut()[#offset] = #value;
+                }),
+                ("u16", Some(value)) | ("i16", Some(value)) | ("u64", Some(value)) => {
+                    Some(quote! {
+                        let value = #value.to_le_bytes();
+                        self.buffer.as_mut()[#offset..][..value.len()].copy_from_slice(&value);
+                    })
+                }
+                ("u32", Some(value)) | ("i32", Some(value)) if bytes == Some(3) => Some(quote! {
+                    let value = #value.to_le_bytes();
+                    self.buffer.as_mut()[#offset..][..3].copy_from_slice(&value[1..]);
+                }),
+                ("u32", Some(value)) | ("i32", Some(value)) => Some(quote! {
+                    let value = #value.to_le_bytes();
+                    self.buffer.as_mut()[#offset..][..4].copy_from_slice(&value);
+                }),
+                ("& [u8]", Some(_)) if bytes == Some(0) => Some(quote! {
+                    self.buffer.as_mut()[#offset..][..value.len()].copy_from_slice(value);
+                }),
+                ("& [u8]", Some(_)) => Some(quote! {
+                    self.buffer.as_mut()[#offset..][..#bytes].copy_from_slice(value);
+                }),
+                _ => None,
+            };
+
+            let has_setter = setter.is_some();
+            if let (true, Some(setter)) = (generate_setters, setter) {
+                let setter_name = syn::Ident::new(&format!("set_{fnname}"), fnname.span());
+                let setter_doc = format!(
+                    "Set the {} field value.",
+                    fnname.to_string().replace('_', " ")
+                );
+                let value_ty = match &into {
+                    Some(into) => quote! { #into },
+                    None => quote! { #ty },
+                };
+                setters.push(quote! {
+                    #[doc = #setter_doc]
+                    pub fn #setter_name(&mut self, value: #value_ty) {
+                        #setter
+                    }
+                });
+            }
+
+            if generate_repr {
+                let repr_doc = format!("The {} field.", fnname.to_string().replace('_', " "));
+                let (repr_ty, parse) = match (ty_str.as_str(), &into) {
+                    ("& [u8]", None) if bytes.is_some_and(|bytes| bytes > 0) && condition.is_none() => {
+                        (
+                            quote! { [u8; #bytes] },
+                            quote! { reader.#fnname().try_into().map_err(|_| Error)? },
+                        )
+                    }
+                    ("& [u8]", _) => panic!(
+                        "field `{fnname}` cannot be part of a repr, only fixed-size fields are supported"
+                    ),
+                    _ if !has_setter => panic!(
+                        "field `{fnname}` cannot be part of a repr, nested structures are not supported"
+                    ),
+                    (_, Some(into)) => (quote! { #into }, quote! { reader.#fnname() }),
+                    (_, None) => (quote! { #ty }, quote! { reader.#fnname() }),
+                };
+
+                let setter_name = syn::Ident::new(&format!("set_{fnname}"), fnname.span());
+                if condition.is_some() {
+                    let bytes = bytes.unwrap_or(0);
+                    repr_fields.push(quote! {
+                        #[doc = #repr_doc]
+                        pub #fnname: Option<#repr_ty>
+                    });
+                    repr_emit.push(quote! {
+                        if let Some(value) = self.#fnname {
+                            writer.#setter_name(value);
+                        }
+                    });
+                    repr_len.push(quote! {
+                        + if self.#fnname.is_some() { #bytes } else { 0 }
+                    });
+                } else {
+                    repr_fields.push(quote! {
+                        #[doc = #repr_doc]
+                        pub #fnname: #repr_ty
+                    });
+                    let value = if ty_str == "& [u8]" {
+                        quote! { &self.#fnname[..] }
+                    } else {
+                        quote! { self.#fnname }
+                    };
+                    repr_emit.push(quote! {
+                        writer.#setter_name(#value);
+                    });
+                }
+                repr_parse.push(quote! { #fnname: #parse });
+            }
+
             let return_type = match ty.to_token_stream().to_string().as_str() {
                 "bool" | "u8" | "u16" | "u32" | "u64" | "& [u8]" if into.is_some() => {
                     let into = into.unwrap();
@@ -263,25 +390,20 @@ pub fn frame(attr: TokenStream, item: TokenStream) -> TokenStream {
             }
         }
 
-        for attr in field.attrs {
-            if attr.path().is_ident("bytes") {
-                offset += attr
-                    .parse_args::<syn::LitInt>()
-                    .unwrap()
-                    .base10_parse::<usize>()
-                    .unwrap();
-            } else if attr.path().is_ident("bits") {
-                bits_offset += attr
-                    .parse_args::<syn::LitInt>()
-                    .unwrap()
-                    .base10_parse::<usize>()
-                    .unwrap();
+        // Every field is followed by the next one, using the size of its
+        // type unless given otherwise with the `bytes` or `bits` attribute.
+        if let Some(bits) = bits {
+            bits_offset += bits;
+            offset += bits_offset / 8;
+            bits_offset %= 8;
+        } else if let Some(bytes) = bytes {
+            offset += bytes;
+        } else {
+            panic!("the size of field `{fnname}` is unknown, add a `bytes` or `bits` attribute");
+        }
 
-                if bits_offset % 8 == 0 && bits_offset > 0 {
-                    offset += 1;
-                    bits_offset = 0;
-                }
-            }
+        if condition.is_none() {
+            min_size = offset + (bits_offset > 0) as usize;
         }
     }
 
@@ -289,12 +411,57 @@ pub fn frame(attr: TokenStream, item: TokenStream) -> TokenStream {
         impl<T: AsRef<[u8]>> #name<T> {
             #(#impls)*
 
-            /// Returns the size of this structure in bytes.
+            /// Returns the minimum size of this structure in bytes, without
+            /// conditional fields and variable-length content.
             pub const fn size() -> usize {
-                #offset
+                #min_size
             }
         }
     });
 
+    if generate_repr {
+        let repr_name = syn::Ident::new(&format!("{name}Repr"), name.span());
+        let repr_doc = format!("A high-level representation of a [`{name}`].");
+        let parse_doc = format!("Parse a [`{name}`].");
+        let len_doc = format!("Return the length of the [`{name}`] when emitted into a buffer.");
+        let emit_doc = format!("Emit the [`{name}`] into a buffer.");
+
+        f.extend(quote! {
+            #[doc = #repr_doc]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            #[cfg_attr(feature = "serde", derive(serde::Serialize))]
+            pub struct #repr_name {
+                #(#repr_fields),*
+            }
+
+            impl #repr_name {
+                #[doc = #parse_doc]
+                pub fn parse<T: AsRef<[u8]>>(reader: &#name<T>) -> Result<Self> {
+                    Ok(Self {
+                        #(#repr_parse),*
+                    })
+                }
+
+                #[doc = #len_doc]
+                pub fn buffer_len(&self) -> usize {
+                    #name::<&[u8]>::size() #(#repr_len)*
+                }
+
+                #[doc = #emit_doc]
+                pub fn emit<T: AsRef<[u8]> + AsMut<[u8]>>(&self, writer: &mut #name<T>) {
+                    #(#repr_emit)*
+                }
+            }
+        });
+    }
+
+    if generate_setters {
+        f.extend(quote! {
+            impl<T: AsRef<[u8]> + AsMut<[u8]>> #name<T> {
+                #(#setters)*
+            }
+        });
+    }
+
     f.into()
 }
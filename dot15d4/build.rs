@@ -8,7 +8,7 @@ fn main() {
     let mut configs: HashMap<&str, (&str, &str)> = HashMap::from([
         ("MAC_MIN_BE", ("u16", "0")),
         ("MAC_MAX_BE", ("u16", "8")),
-        ("MAC_MAX_CSMA_BACKOFFS", ("u16", "16")),
+        ("MAC_MAX_CSMA_BACKOFFS", ("u16", "4")),
         (
             "MAC_UNIT_BACKOFF_DURATION",
             (
@@ -26,6 +26,14 @@ fn main() {
         ("MAC_LIFS_PERIOD", ("Duration", "Duration::from_us(10_000)")),
         ("MAC_PAN_ID", ("u16", "0xffff")),
         ("MAC_IMPLICIT_BROADCAST", ("bool", "false")),
+        ("MAC_NEIGHBOR_TABLE_SIZE", ("usize", "16")),
+        ("MAC_GROUP_TABLE_SIZE", ("usize", "4")),
+        ("MAC_DEVICE_TABLE_SIZE", ("usize", "8")),
+        ("MAC_KEY_TABLE_SIZE", ("usize", "4")),
+        (
+            "MAC_MAX_FRAME_TOTAL_WAIT_TIME",
+            ("Duration", "Duration::from_us(20_000)"),
+        ),
     ]);
 
     // Make sure we get rerun if needed
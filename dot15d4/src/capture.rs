@@ -0,0 +1,206 @@
+//! Writing frames to pcap capture files.
+//!
+//! A [`PcapWriter`] writes frames, e.g. the [`FrameBuffer`]s handed to a
+//! [`Driver`](crate::phy::driver::Driver), to a pcap file that can be opened
+//! in Wireshark. Retransmissions of a frame can be left out of the capture,
+//! such that a frame that was received multiple times shows up only once.
+
+use std::io::{self, Write};
+
+use dot15d4_frame::{DataFrame, FrameWithFcs};
+
+use crate::phy::driver::FrameBuffer;
+use crate::phy::radio::{Radio, RadioFrame};
+use crate::time::Instant;
+
+/// The maximum length of a captured frame, including the FCS.
+const SNAPLEN: u32 = 129;
+
+/// The link-layer header type of a capture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkType {
+    /// `LINKTYPE_IEEE802_15_4_WITHFCS` (195): every frame ends with the FCS,
+    /// which is calculated by the [`PcapWriter`].
+    WithFcs,
+    /// `LINKTYPE_IEEE802_15_4_NOFCS` (230): frames without FCS.
+    NoFcs,
+}
+
+impl From<LinkType> for u32 {
+    fn from(link_type: LinkType) -> u32 {
+        match link_type {
+            LinkType::WithFcs => 195,
+            LinkType::NoFcs => 230,
+        }
+    }
+}
+
+/// Identifies a transmission of a frame, to recognise retransmissions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Transmission {
+    src_address: Option<dot15d4_frame::Address>,
+    sequence_number: u8,
+}
+
+impl Transmission {
+    fn parse(frame: &[u8]) -> Option<Self> {
+        let frame = DataFrame::new(frame).ok()?;
+        Some(Self {
+            src_address: frame.addressing().and_then(|fields| fields.src_address()),
+            sequence_number: frame.sequence_number()?,
+        })
+    }
+}
+
+/// Writes frames to a pcap capture.
+#[derive(Debug)]
+pub struct PcapWriter<W: Write> {
+    writer: W,
+    link_type: LinkType,
+    skip_retransmissions: bool,
+    last: Option<Transmission>,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Create a new [`PcapWriter`], writing the header of the capture.
+    pub fn new(mut writer: W, link_type: LinkType) -> io::Result<Self> {
+        // Magic number, version 2.4, UTC, no timestamp accuracy
+        writer.write_all(&0xa1b2_c3d4u32.to_le_bytes())?;
+        writer.write_all(&2u16.to_le_bytes())?;
+        writer.write_all(&4u16.to_le_bytes())?;
+        writer.write_all(&0i32.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(&SNAPLEN.to_le_bytes())?;
+        writer.write_all(&u32::from(link_type).to_le_bytes())?;
+
+        Ok(Self {
+            writer,
+            link_type,
+            skip_retransmissions: false,
+            last: None,
+        })
+    }
+
+    /// Leave out frames with the same source address and sequence number as
+    /// the previously written frame (default = false).
+    pub fn set_skip_retransmissions(&mut self, skip: bool) {
+        self.skip_retransmissions = skip;
+    }
+
+    /// Write a frame without FCS, received at `timestamp`.
+    ///
+    /// Returns `false` when the frame is left out as a retransmission.
+    pub fn write_frame(&mut self, timestamp: Instant, frame: &[u8]) -> io::Result<bool> {
+        let transmission = Transmission::parse(frame);
+        if self.skip_retransmissions && transmission.is_some() && transmission == self.last {
+            return Ok(false);
+        }
+        self.last = transmission;
+
+        // The FCS is calculated over the frame, followed by room for the FCS
+        let frame = &frame[..frame.len().min(SNAPLEN as usize - 2)];
+        let mut buffer = [0; SNAPLEN as usize];
+        buffer[..frame.len()].copy_from_slice(frame);
+        let fcs = FrameWithFcs::new_unchecked(&buffer[..frame.len() + 2])
+            .calculate_fcs()
+            .to_le_bytes();
+        let fcs = match self.link_type {
+            LinkType::WithFcs => &fcs[..],
+            LinkType::NoFcs => &[],
+        };
+
+        let us = timestamp.as_us().max(0);
+        let len = (frame.len() + fcs.len()) as u32;
+        self.writer
+            .write_all(&((us / 1_000_000) as u32).to_le_bytes())?;
+        self.writer
+            .write_all(&((us % 1_000_000) as u32).to_le_bytes())?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(frame)?;
+        self.writer.write_all(fcs)?;
+
+        Ok(true)
+    }
+
+    /// Write a received [`FrameBuffer`] in the format of the radio `R`.
+    ///
+    /// Returns `false` when the frame is left out as a retransmission, or
+    /// when it is not a valid frame for the radio.
+    pub fn write_buffer<R: Radio>(
+        &mut self,
+        timestamp: Instant,
+        buffer: &FrameBuffer,
+    ) -> io::Result<bool> {
+        match R::RadioFrame::new_checked(&buffer.buffer[..]) {
+            Ok(frame) => self.write_frame(timestamp, frame.data()),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// Flush the capture and return the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::phy::radio::tests::TestRadio;
+
+    // A data frame with sequence number 1
+    const FRAME: [u8; 19] = [
+        0x41, 0xd8, 0x01, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12, 0x00,
+        0x2b, 0x00, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn write() {
+        let frame = FRAME.to_vec();
+        let mut writer = PcapWriter::new(Vec::new(), LinkType::WithFcs).unwrap();
+        writer.set_skip_retransmissions(true);
+        assert!(writer
+            .write_frame(Instant::from_us(1_500_000), &frame)
+            .unwrap());
+        // The retransmission is left out, the next frame is not
+        assert!(!writer
+            .write_frame(Instant::from_us(1_600_000), &frame)
+            .unwrap());
+        let mut next = frame.clone();
+        next[2] = 2;
+        assert!(writer
+            .write_frame(Instant::from_us(1_700_000), &next)
+            .unwrap());
+
+        let capture = writer.into_inner().unwrap();
+        assert_eq!(&capture[..4], [0xd4, 0xc3, 0xb2, 0xa1]);
+        assert_eq!(&capture[20..24], 195u32.to_le_bytes());
+        let record = &capture[24..];
+        assert_eq!(&record[..4], 1u32.to_le_bytes());
+        assert_eq!(&record[4..8], 500_000u32.to_le_bytes());
+        assert_eq!(&record[8..12], 21u32.to_le_bytes());
+        assert_eq!(&record[16..35], &frame[..]);
+        assert!(FrameWithFcs::new(&record[16..37]).is_ok());
+        assert_eq!(capture.len(), 24 + 2 * (16 + 21));
+    }
+
+    #[test]
+    fn write_buffer() {
+        let mut buffer = FrameBuffer::default();
+        buffer.buffer[..19].copy_from_slice(&FRAME);
+        let mut writer = PcapWriter::new(Vec::new(), LinkType::NoFcs).unwrap();
+        assert!(writer
+            .write_buffer::<TestRadio>(Instant::from_us(0), &buffer)
+            .unwrap());
+
+        // The test radio always hands over 127 bytes
+        let capture = writer.into_inner().unwrap();
+        assert_eq!(&capture[20..24], 230u32.to_le_bytes());
+        assert_eq!(&capture[32..36], 127u32.to_le_bytes());
+        assert_eq!(capture.len(), 24 + 16 + 127);
+    }
+}
@@ -0,0 +1,153 @@
+//! Fast construction of acknowledgment frames.
+//!
+//! An acknowledgment must be transmitted within the turnaround time after
+//! the acknowledged frame. Building it through a [`FrameRepr`] is more work
+//! than needed for an Enhanced Acknowledgment without Information Elements
+//! and security, which [`MinimalEnhancedAck`] writes directly.
+//!
+//! [`FrameRepr`]: dot15d4_frame::FrameRepr
+use dot15d4_frame::{Address, AddressingMode, FrameControl, FrameType, FrameVersion};
+
+/// The longest [`MinimalEnhancedAck`]: the Frame Control field, the sequence
+/// number, the destination PAN ID and an extended destination address.
+pub const MAX_MINIMAL_ENHANCED_ACK_LEN: usize = 2 + 1 + 2 + 8;
+
+/// An IEEE 802.15.4-2020 Enhanced Acknowledgment without Information
+/// Elements and security, for operation without TSCH.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinimalEnhancedAck {
+    /// The sequence number of the acknowledged frame. `None` suppresses the
+    /// sequence number, as for frames with a suppressed sequence number.
+    pub sequence_number: Option<u8>,
+    /// The PAN ID of the originator of the acknowledged frame.
+    pub dst_pan_id: u16,
+    /// The address of the originator of the acknowledged frame. When absent,
+    /// the destination PAN ID is left out as well.
+    pub dst_address: Address,
+    /// Whether more data is pending for the originator.
+    pub frame_pending: bool,
+}
+
+impl MinimalEnhancedAck {
+    /// Returns the length of the acknowledgment, without the FCS.
+    pub fn buffer_len(&self) -> usize {
+        let mut len = 2;
+        len += self.sequence_number.is_some() as usize;
+
+        if !self.dst_address.is_absent() {
+            len += 2 + self.dst_address.len();
+        }
+
+        len
+    }
+
+    /// Write the acknowledgment into the first [`Self::buffer_len`] bytes of
+    /// `buffer`.
+    ///
+    /// # Panics
+    /// Panics when `buffer` is shorter than [`Self::buffer_len`].
+    pub fn emit(&self, buffer: &mut [u8]) {
+        let buffer = &mut buffer[..self.buffer_len()];
+
+        buffer[..2].fill(0);
+        let mut fc = FrameControl::new_unchecked(&mut buffer[..2]);
+        fc.set_frame_type(FrameType::Ack);
+        fc.set_frame_pending(self.frame_pending);
+        fc.set_sequence_number_suppression(self.sequence_number.is_none());
+        fc.set_dst_addressing_mode(AddressingMode::from(self.dst_address));
+        fc.set_src_addressing_mode(AddressingMode::Absent);
+        fc.set_frame_version(FrameVersion::Ieee802154_2020);
+
+        let mut offset = 2;
+        if let Some(sequence_number) = self.sequence_number {
+            buffer[offset] = sequence_number;
+            offset += 1;
+        }
+
+        // With only a destination address, the destination PAN ID is present
+        // when the PAN ID Compression field is not set
+        let address = match &self.dst_address {
+            Address::Absent => return,
+            Address::Short(address) => &address[..],
+            Address::Extended(address) => &address[..],
+        };
+        buffer[offset..][..2].copy_from_slice(&self.dst_pan_id.to_le_bytes());
+        offset += 2;
+
+        let field = &mut buffer[offset..][..address.len()];
+        field.copy_from_slice(address);
+        field.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dot15d4_frame::{DataFrame, FrameBuilder};
+
+    use super::*;
+
+    fn emit(ack: &MinimalEnhancedAck) -> std::vec::Vec<u8> {
+        let mut buffer = [0xff; MAX_MINIMAL_ENHANCED_ACK_LEN];
+        ack.emit(&mut buffer);
+        buffer[..ack.buffer_len()].to_vec()
+    }
+
+    #[test]
+    fn minimal_enhanced_ack() {
+        let ack = MinimalEnhancedAck {
+            sequence_number: None,
+            dst_pan_id: 0xabcd,
+            dst_address: Address::Extended([0x00, 0x12, 0x4b, 0x00, 0x14, 0xb5, 0xd9, 0xc7]),
+            frame_pending: false,
+        };
+        assert_eq!(ack.buffer_len(), 12);
+        assert_eq!(
+            emit(&ack),
+            [0x02, 0x2d, 0xcd, 0xab, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12, 0x00]
+        );
+
+        let ack = MinimalEnhancedAck {
+            sequence_number: Some(0x37),
+            dst_address: Address::Short([0x00, 0x02]),
+            frame_pending: true,
+            ..ack
+        };
+        assert_eq!(emit(&ack), [0x12, 0x28, 0x37, 0xcd, 0xab, 0x02, 0x00]);
+
+        let ack = MinimalEnhancedAck {
+            dst_address: Address::Absent,
+            ..ack
+        };
+        assert_eq!(emit(&ack), [0x12, 0x20, 0x37]);
+    }
+
+    #[test]
+    fn same_as_frame_builder() {
+        let addresses = [
+            Address::Short([0x00, 0x02]),
+            Address::Extended([0x00, 0x12, 0x4b, 0x00, 0x14, 0xb5, 0xd9, 0xc7]),
+        ];
+        for dst_address in addresses {
+            for sequence_number in [None, Some(0x2a)] {
+                let mut builder = FrameBuilder::new_ack()
+                    .set_dst_pan_id(0xabcd)
+                    .set_dst_address(dst_address);
+                builder = match sequence_number {
+                    Some(sequence_number) => builder.set_sequence_number(sequence_number),
+                    None => builder.suppress_sequence_number(),
+                };
+                let repr = builder.finalize().unwrap();
+                let mut expected = std::vec![0; repr.buffer_len()];
+                repr.emit(&mut DataFrame::new_unchecked(&mut expected[..]));
+
+                let ack = MinimalEnhancedAck {
+                    sequence_number,
+                    dst_pan_id: 0xabcd,
+                    dst_address,
+                    frame_pending: false,
+                };
+                assert_eq!(emit(&ack), expected);
+            }
+        }
+    }
+}
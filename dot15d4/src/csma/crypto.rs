@@ -0,0 +1,309 @@
+//! Cryptographic operations used to secure frames.
+//!
+//! The MAC only needs the AES-128 block cipher, from which the CCM* mode of
+//! operation is built. Microcontrollers often have an AES peripheral, or even
+//! a CCM* accelerator, which can be used by implementing [`CryptoBackend`].
+//! The backend secures outgoing data frames and Enhanced Acknowledgments, and
+//! unsecures and verifies incoming frames. Securing an Enhanced
+//! Acknowledgment has to happen within the acknowledgment turnaround time,
+//! which software AES may not achieve.
+
+use super::devices::SecurityError;
+
+/// Length of the CCM* length field. The nonce takes the remaining 13 bytes.
+const L: usize = 2;
+
+/// A provider of the cryptographic operations of the MAC.
+///
+/// Only [`CryptoBackend::aes128_ecb`] has to be implemented. The CCM*
+/// operations have a default implementation built on top of it, which can be
+/// replaced when the hardware supports CCM* itself.
+pub trait CryptoBackend {
+    /// Encrypt a single block with AES-128, in place.
+    fn aes128_ecb(&self, key: &[u8; 16], block: &mut [u8; 16]) -> Result<(), SecurityError>;
+
+    /// Apply the CCM* forward transform. `a_data` is only authenticated,
+    /// `m_data` is authenticated and encrypted in place. The MIC is written to
+    /// `mic`, of which the length is the MIC length of the security level.
+    fn ccm_star_encrypt(
+        &self,
+        key: &[u8; 16],
+        nonce: &[u8; 13],
+        a_data: &[u8],
+        m_data: &mut [u8],
+        mic: &mut [u8],
+    ) -> Result<(), SecurityError> {
+        check_lengths(a_data, m_data, mic)?;
+
+        let mut tag = [0; 16];
+        if !mic.is_empty() {
+            tag = cbc_mac(self, key, nonce, a_data, m_data, mic.len())?;
+        }
+        let s0 = ctr(self, key, nonce, m_data)?;
+        for (mic, (tag, s0)) in mic.iter_mut().zip(tag.iter().zip(s0)) {
+            *mic = tag ^ s0;
+        }
+        Ok(())
+    }
+
+    /// Apply the CCM* inverse transform. `m_data` is decrypted in place, and
+    /// only kept decrypted when `mic` matches.
+    fn ccm_star_decrypt(
+        &self,
+        key: &[u8; 16],
+        nonce: &[u8; 13],
+        a_data: &[u8],
+        m_data: &mut [u8],
+        mic: &[u8],
+    ) -> Result<(), SecurityError> {
+        check_lengths(a_data, m_data, mic)?;
+
+        let s0 = ctr(self, key, nonce, m_data)?;
+        if mic.is_empty() {
+            return Ok(());
+        }
+
+        let tag = cbc_mac(self, key, nonce, a_data, m_data, mic.len())?;
+        let diff = mic
+            .iter()
+            .zip(tag.iter().zip(s0))
+            .fold(0, |diff, (mic, (tag, s0))| diff | (mic ^ tag ^ s0));
+        if diff != 0 {
+            ctr(self, key, nonce, m_data)?;
+            return Err(SecurityError::TransformFailed);
+        }
+        Ok(())
+    }
+}
+
+/// Encrypt `data` in place in counter mode, starting from counter 1, and
+/// return the key stream block of counter 0.
+fn ctr<B: CryptoBackend + ?Sized>(
+    backend: &B,
+    key: &[u8; 16],
+    nonce: &[u8; 13],
+    data: &mut [u8],
+) -> Result<[u8; 16], SecurityError> {
+    let block = |counter: u16| -> Result<[u8; 16], SecurityError> {
+        let mut block = [0; 16];
+        block[0] = (L - 1) as u8;
+        block[1..14].copy_from_slice(nonce);
+        block[14..].copy_from_slice(&counter.to_be_bytes());
+        backend.aes128_ecb(key, &mut block)?;
+        Ok(block)
+    };
+
+    for (i, chunk) in data.chunks_mut(16).enumerate() {
+        let stream = block(i as u16 + 1)?;
+        for (byte, stream) in chunk.iter_mut().zip(stream) {
+            *byte ^= stream;
+        }
+    }
+    block(0)
+}
+
+/// Compute the unencrypted authentication tag of the frame.
+fn cbc_mac<B: CryptoBackend + ?Sized>(
+    backend: &B,
+    key: &[u8; 16],
+    nonce: &[u8; 13],
+    a_data: &[u8],
+    m_data: &[u8],
+    mic_len: usize,
+) -> Result<[u8; 16], SecurityError> {
+    let mut x = [0; 16];
+    x[0] = (!a_data.is_empty() as u8) << 6 | ((mic_len as u8 - 2) / 2) << 3 | (L - 1) as u8;
+    x[1..14].copy_from_slice(nonce);
+    x[14..].copy_from_slice(&(m_data.len() as u16).to_be_bytes());
+    backend.aes128_ecb(key, &mut x)?;
+
+    let mut pos = 0;
+    let mut absorb = |x: &mut [u8; 16], data: &[u8], pad: bool| {
+        for byte in data {
+            x[pos] ^= byte;
+            pos += 1;
+            if pos == 16 {
+                backend.aes128_ecb(key, x)?;
+                pos = 0;
+            }
+        }
+        if pad && pos != 0 {
+            backend.aes128_ecb(key, x)?;
+            pos = 0;
+        }
+        Ok(())
+    };
+
+    if !a_data.is_empty() {
+        absorb(&mut x, &(a_data.len() as u16).to_be_bytes(), false)?;
+        absorb(&mut x, a_data, true)?;
+    }
+    absorb(&mut x, m_data, true)?;
+    Ok(x)
+}
+
+/// Check that the lengths can be encoded, and that the MIC length is one of
+/// the lengths allowed by CCM*.
+fn check_lengths(a_data: &[u8], m_data: &[u8], mic: &[u8]) -> Result<(), SecurityError> {
+    // Longer additional data needs a longer length encoding, which is never
+    // needed for IEEE 802.15.4 frames
+    if a_data.len() >= 0xff00 || m_data.len() > u16::MAX as usize {
+        return Err(SecurityError::TransformFailed);
+    }
+    match mic.len() {
+        0 | 4 | 6 | 8 | 10 | 12 | 14 | 16 => Ok(()),
+        _ => Err(SecurityError::TransformFailed),
+    }
+}
+
+/// A backend without any cryptographic operations. Secured frames can not be
+/// transmitted.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoCrypto;
+
+impl CryptoBackend for NoCrypto {
+    fn aes128_ecb(&self, _key: &[u8; 16], _block: &mut [u8; 16]) -> Result<(), SecurityError> {
+        Err(SecurityError::TransformFailed)
+    }
+}
+
+/// A backend implementing AES-128 in software.
+#[cfg(feature = "software-crypto")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SoftwareCrypto;
+
+#[cfg(feature = "software-crypto")]
+impl CryptoBackend for SoftwareCrypto {
+    fn aes128_ecb(&self, key: &[u8; 16], block: &mut [u8; 16]) -> Result<(), SecurityError> {
+        use aes::cipher::{BlockEncrypt, KeyInit};
+
+        aes::Aes128::new(key.into()).encrypt_block(block.into());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[cfg(feature = "software-crypto")]
+    #[test]
+    fn ccm_star() {
+        let key = [
+            0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd,
+            0xce, 0xcf,
+        ];
+        let nonce = [
+            0x00, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5,
+        ];
+        let a_data: [u8; 8] = core::array::from_fn(|i| i as u8);
+        let plaintext: [u8; 23] = core::array::from_fn(|i| i as u8 + 8);
+
+        // RFC 3610, packet vector #1
+        let mut m_data = plaintext;
+        let mut mic = [0; 8];
+        SoftwareCrypto
+            .ccm_star_encrypt(&key, &nonce, &a_data, &mut m_data, &mut mic)
+            .unwrap();
+        assert_eq!(
+            m_data,
+            [
+                0x58, 0x8c, 0x97, 0x9a, 0x61, 0xc6, 0x63, 0xd2, 0xf0, 0x66, 0xd0, 0xc2, 0xc0, 0xf9,
+                0x89, 0x80, 0x6d, 0x5f, 0x6b, 0x61, 0xda, 0xc3, 0x84,
+            ]
+        );
+        assert_eq!(mic, [0x17, 0xe8, 0xd1, 0x2c, 0xfd, 0xf9, 0x26, 0xe0]);
+
+        SoftwareCrypto
+            .ccm_star_decrypt(&key, &nonce, &a_data, &mut m_data, &mic)
+            .unwrap();
+        assert_eq!(m_data, plaintext);
+
+        // A modified frame is not accepted, and stays encrypted
+        let mut m_data = plaintext;
+        SoftwareCrypto
+            .ccm_star_encrypt(&key, &nonce, &a_data, &mut m_data, &mut mic)
+            .unwrap();
+        let encrypted = m_data;
+        assert_eq!(
+            SoftwareCrypto.ccm_star_decrypt(&key, &nonce, &a_data[1..], &mut m_data, &mic),
+            Err(SecurityError::TransformFailed)
+        );
+        assert_eq!(m_data, encrypted);
+
+        // Authentication only, as for the MIC-only security levels
+        let mut mic = [0; 4];
+        SoftwareCrypto
+            .ccm_star_encrypt(&key, &nonce, &plaintext, &mut [], &mut mic)
+            .unwrap();
+        assert_eq!(mic, [0x47, 0xb5, 0xfd, 0x23]);
+        assert!(SoftwareCrypto
+            .ccm_star_decrypt(&key, &nonce, &plaintext, &mut [], &mic)
+            .is_ok());
+
+        // Encryption only
+        let mut m_data = plaintext;
+        SoftwareCrypto
+            .ccm_star_encrypt(&key, &nonce, &a_data, &mut m_data, &mut [])
+            .unwrap();
+        assert_ne!(m_data, plaintext);
+        SoftwareCrypto
+            .ccm_star_decrypt(&key, &nonce, &a_data, &mut m_data, &[])
+            .unwrap();
+        assert_eq!(m_data, plaintext);
+    }
+
+    /// The secured beacon and Association Request examples of IEEE
+    /// 802.15.4-2006 Annex C, secured in place.
+    #[cfg(feature = "software-crypto")]
+    #[test]
+    fn ccm_star_frames() {
+        use dot15d4_frame::DataFrame;
+
+        let key = core::array::from_fn(|i| 0xc0 + i as u8);
+        let src = [0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01];
+        let beacon = [
+            0x08, 0xd0, 0x84, 0x21, 0x43, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x02,
+            0x05, 0x00, 0x00, 0x00, 0x55, 0xcf, 0x00, 0x00, 0x51, 0x52, 0x53, 0x54, 0x22, 0x3b,
+            0xc1, 0xec, 0x84, 0x1a, 0xb5, 0x53,
+        ];
+        let command = [
+            0x2b, 0xdc, 0x84, 0x21, 0x43, 0x02, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0xff,
+            0xff, 0x01, 0x00, 0x00, 0x00, 0x00, 0x48, 0xde, 0xac, 0x06, 0x05, 0x00, 0x00, 0x00,
+            0x01, 0xd8, 0x4f, 0xde, 0x52, 0x90, 0x61, 0xf9, 0xc6, 0xf1,
+        ];
+
+        for (secured, plaintext) in [
+            (
+                &beacon[..],
+                &[0x55, 0xcf, 0x00, 0x00, 0x51, 0x52, 0x53, 0x54][..],
+            ),
+            (&command[..], &[0x01, 0xce][..]),
+        ] {
+            let parts = DataFrame::new(secured).unwrap().ccm_parts(&src).unwrap();
+            let payload_start = secured.len() - parts.mic_len - plaintext.len();
+            let mut frame = secured.to_vec();
+            frame[payload_start..][..plaintext.len()].copy_from_slice(plaintext);
+
+            let (a_data, m_data, mic) = parts.split_mut(&mut frame);
+            SoftwareCrypto
+                .ccm_star_encrypt(&key, &parts.nonce, a_data, m_data, mic)
+                .unwrap();
+            assert_eq!(frame, secured);
+
+            let (a_data, m_data, mic) = parts.split_mut(&mut frame);
+            SoftwareCrypto
+                .ccm_star_decrypt(&key, &parts.nonce, a_data, m_data, mic)
+                .unwrap();
+            assert_eq!(&frame[payload_start..][..plaintext.len()], plaintext);
+        }
+    }
+
+    #[test]
+    fn no_crypto() {
+        assert_eq!(
+            NoCrypto.ccm_star_encrypt(&[0; 16], &[0; 13], &[1, 2], &mut [3, 4], &mut [0; 4]),
+            Err(SecurityError::TransformFailed)
+        );
+    }
+}
@@ -0,0 +1,411 @@
+//! A small, fixed-size table of devices from which secured frames are
+//! accepted.
+//!
+//! For every device, the table keeps the incoming frame counter per key, such
+//! that replayed secured frames can be rejected. The counters can be saved and
+//! restored by the application, to keep rejecting replayed frames after a
+//! reboot.
+
+use dot15d4_frame::{
+    Address, AuxiliarySecurityHeader, AuxiliarySecurityHeaderRepr, KeyIdentifierRepr,
+};
+
+/// Identifies the key a secured frame is protected with.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum KeyId {
+    /// The key is determined implicitly from the originator and recipient.
+    Implicit,
+    /// The key is determined from the key index.
+    Index(u8),
+    /// The key is determined from a 4-octet key source and the key index.
+    ShortSource([u8; 4], u8),
+    /// The key is determined from an 8-octet key source and the key index.
+    LongSource([u8; 8], u8),
+}
+
+impl KeyId {
+    /// Return the key identifier of an Auxiliary Security Header.
+    pub fn from_header<T: AsRef<[u8]>>(header: &AuxiliarySecurityHeader<T>) -> Option<Self> {
+        AuxiliarySecurityHeaderRepr::parse(header)
+            .ok()
+            .map(|header| header.key_identifier.into())
+    }
+}
+
+impl From<KeyIdentifierRepr> for KeyId {
+    fn from(value: KeyIdentifierRepr) -> Self {
+        match value {
+            KeyIdentifierRepr::Implicit => KeyId::Implicit,
+            KeyIdentifierRepr::Index(index) => KeyId::Index(index),
+            KeyIdentifierRepr::ShortSource(source, index) => KeyId::ShortSource(source, index),
+            KeyIdentifierRepr::LongSource(source, index) => KeyId::LongSource(source, index),
+        }
+    }
+}
+
+impl From<KeyId> for KeyIdentifierRepr {
+    fn from(value: KeyId) -> Self {
+        match value {
+            KeyId::Implicit => KeyIdentifierRepr::Implicit,
+            KeyId::Index(index) => KeyIdentifierRepr::Index(index),
+            KeyId::ShortSource(source, index) => KeyIdentifierRepr::ShortSource(source, index),
+            KeyId::LongSource(source, index) => KeyIdentifierRepr::LongSource(source, index),
+        }
+    }
+}
+
+/// The incoming frame counter of a device for a given key.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct IncomingFrameCounter {
+    /// The extended address of the device.
+    pub extended_address: [u8; 8],
+    /// The key the counter belongs to.
+    pub key: KeyId,
+    /// The lowest frame counter that is still accepted from the device.
+    pub frame_counter: u32,
+}
+
+/// Reasons for rejecting a secured frame.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SecurityError {
+    /// The originator of the frame is not in the device table.
+    UnavailableDevice,
+    /// The key of the frame could not be determined, is not installed, or
+    /// there is no room left for another key of the device.
+    UnavailableKey,
+    /// The frame counter is lower than expected, or exhausted. The frame is
+    /// most likely replayed.
+    CounterError,
+    /// The security transform could not be applied to the frame.
+    TransformFailed,
+}
+
+/// A device from which secured frames are accepted.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Device<const K: usize> {
+    /// The extended address of the device, in the same byte order as
+    /// [`Address::Extended`](dot15d4_frame::Address::Extended).
+    pub extended_address: [u8; 8],
+    /// The short address of the device, if assigned, in the same byte order
+    /// as [`Address::Short`](dot15d4_frame::Address::Short).
+    pub short_address: Option<[u8; 2]>,
+    frame_counters: [Option<(KeyId, u32)>; K],
+}
+
+impl<const K: usize> Device<K> {
+    /// Create a new device, of which no frames are received yet.
+    pub const fn new(extended_address: [u8; 8], short_address: Option<[u8; 2]>) -> Self {
+        Self {
+            extended_address,
+            short_address,
+            frame_counters: [None; K],
+        }
+    }
+
+    /// Return the lowest frame counter that is still accepted for the given
+    /// key.
+    pub fn frame_counter(&self, key: &KeyId) -> u32 {
+        self.counter(key).map(|(_, counter)| *counter).unwrap_or(0)
+    }
+
+    fn counter(&self, key: &KeyId) -> Option<&(KeyId, u32)> {
+        self.frame_counters.iter().flatten().find(|(k, _)| k == key)
+    }
+
+    /// Set the lowest frame counter that is still accepted for the given key.
+    fn set_frame_counter(&mut self, key: KeyId, frame_counter: u32) -> Result<(), SecurityError> {
+        let entry = match self
+            .frame_counters
+            .iter()
+            .position(|entry| entry.is_some_and(|(k, _)| k == key))
+        {
+            Some(i) => &mut self.frame_counters[i],
+            None => self
+                .frame_counters
+                .iter_mut()
+                .find(|entry| entry.is_none())
+                .ok_or(SecurityError::UnavailableKey)?,
+        };
+        *entry = Some((key, frame_counter));
+
+        Ok(())
+    }
+
+    fn matches(&self, address: &Address) -> bool {
+        match address {
+            Address::Extended(extended) => &self.extended_address == extended,
+            Address::Short(short) => self.short_address.as_ref() == Some(short),
+            Address::Absent => false,
+        }
+    }
+}
+
+/// A table holding at most `N` [`Device`]s, each with frame counters for at
+/// most `K` keys.
+#[derive(Debug)]
+pub struct DeviceTable<const N: usize, const K: usize> {
+    entries: [Option<Device<K>>; N],
+}
+
+impl<const N: usize, const K: usize> Default for DeviceTable<N, K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, const K: usize> DeviceTable<N, K> {
+    /// Create a new empty [`DeviceTable`].
+    pub const fn new() -> Self {
+        Self { entries: [None; N] }
+    }
+
+    /// Insert or update a device in the table. The frame counters of an
+    /// existing device are kept.
+    ///
+    /// Returns the device back when the table is full.
+    pub fn insert(&mut self, device: Device<K>) -> Result<(), Device<K>> {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .flatten()
+            .find(|entry| entry.extended_address == device.extended_address)
+        {
+            entry.short_address = device.short_address;
+            return Ok(());
+        }
+
+        match self.entries.iter_mut().find(|entry| entry.is_none()) {
+            Some(entry) => {
+                *entry = Some(device);
+                Ok(())
+            }
+            None => Err(device),
+        }
+    }
+
+    /// Remove the device with the given extended address from the table.
+    pub fn remove(&mut self, extended_address: &[u8; 8]) -> Option<Device<K>> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.is_some_and(|entry| &entry.extended_address == extended_address))
+            .and_then(|entry| entry.take())
+    }
+
+    /// Return the device with the given address, if known.
+    pub fn get(&self, address: &Address) -> Option<&Device<K>> {
+        self.iter().find(|entry| entry.matches(address))
+    }
+
+    /// Check the frame counter of a secured frame originating from `address`,
+    /// without updating the incoming frame counter of the device, as the
+    /// frame is not verified yet.
+    ///
+    /// Returns the extended address of the device, which is part of the
+    /// nonce of the frame.
+    pub fn check_frame_counter(
+        &self,
+        address: &Address,
+        key: &KeyId,
+        frame_counter: u32,
+    ) -> Result<[u8; 8], SecurityError> {
+        let device = self.get(address).ok_or(SecurityError::UnavailableDevice)?;
+
+        if frame_counter == u32::MAX || frame_counter < device.frame_counter(key) {
+            return Err(SecurityError::CounterError);
+        }
+        if device.counter(key).is_none() && device.frame_counters.iter().all(Option::is_some) {
+            return Err(SecurityError::UnavailableKey);
+        }
+
+        Ok(device.extended_address)
+    }
+
+    /// Check the frame counter of a verified secured frame originating from
+    /// `address`, and update the incoming frame counter of the device.
+    ///
+    /// Returns the updated counter, which the application may want to save.
+    pub fn update_frame_counter(
+        &mut self,
+        address: &Address,
+        key: KeyId,
+        frame_counter: u32,
+    ) -> Result<IncomingFrameCounter, SecurityError> {
+        self.check_frame_counter(address, &key, frame_counter)?;
+        let device = self
+            .entries
+            .iter_mut()
+            .flatten()
+            .find(|entry| entry.matches(address))
+            .ok_or(SecurityError::UnavailableDevice)?;
+
+        device.set_frame_counter(key, frame_counter + 1)?;
+
+        Ok(IncomingFrameCounter {
+            extended_address: device.extended_address,
+            key,
+            frame_counter: frame_counter + 1,
+        })
+    }
+
+    /// Return an [`Iterator`] over all incoming frame counters, e.g. for
+    /// saving them before a reboot.
+    pub fn frame_counters(&self) -> impl Iterator<Item = IncomingFrameCounter> + '_ {
+        self.iter().flat_map(|device| {
+            device
+                .frame_counters
+                .iter()
+                .flatten()
+                .map(|(key, frame_counter)| IncomingFrameCounter {
+                    extended_address: device.extended_address,
+                    key: *key,
+                    frame_counter: *frame_counter,
+                })
+        })
+    }
+
+    /// Restore a saved incoming frame counter. The device must already be in
+    /// the table. A counter lower than the current one is ignored.
+    pub fn restore_frame_counter(
+        &mut self,
+        counter: IncomingFrameCounter,
+    ) -> Result<(), SecurityError> {
+        let device = self
+            .entries
+            .iter_mut()
+            .flatten()
+            .find(|entry| entry.extended_address == counter.extended_address)
+            .ok_or(SecurityError::UnavailableDevice)?;
+
+        let frame_counter = counter
+            .frame_counter
+            .max(device.frame_counter(&counter.key));
+        device.set_frame_counter(counter.key, frame_counter)
+    }
+
+    /// Return an [`Iterator`] over the known devices.
+    pub fn iter(&self) -> impl Iterator<Item = &Device<K>> {
+        self.entries.iter().flatten()
+    }
+
+    /// Return the number of known devices.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` when no devices are known.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_protection() {
+        let mut table = DeviceTable::<2, 1>::new();
+        let address = Address::Extended([1; 8]);
+
+        assert_eq!(
+            table.update_frame_counter(&address, KeyId::Index(1), 0),
+            Err(SecurityError::UnavailableDevice)
+        );
+
+        table
+            .insert(Device::new([1; 8], Some([0x00, 0x01])))
+            .unwrap();
+
+        // Checking a frame counter does not update it
+        assert_eq!(
+            table.check_frame_counter(&address, &KeyId::Index(1), 10),
+            Ok([1; 8])
+        );
+        assert_eq!(
+            table.get(&address).unwrap().frame_counter(&KeyId::Index(1)),
+            0
+        );
+
+        let counter = table
+            .update_frame_counter(&address, KeyId::Index(1), 10)
+            .unwrap();
+        assert_eq!(counter.frame_counter, 11);
+
+        // The same frame is replayed, also when using the short address
+        assert_eq!(
+            table.update_frame_counter(&address, KeyId::Index(1), 10),
+            Err(SecurityError::CounterError)
+        );
+        assert_eq!(
+            table.update_frame_counter(&Address::Short([0x00, 0x01]), KeyId::Index(1), 9),
+            Err(SecurityError::CounterError)
+        );
+        assert!(table
+            .update_frame_counter(&Address::Short([0x00, 0x01]), KeyId::Index(1), 11)
+            .is_ok());
+        assert_eq!(
+            table.update_frame_counter(&address, KeyId::Index(1), u32::MAX),
+            Err(SecurityError::CounterError)
+        );
+
+        // There is only room for the counter of a single key
+        assert_eq!(
+            table.update_frame_counter(&address, KeyId::Index(2), 0),
+            Err(SecurityError::UnavailableKey)
+        );
+
+        // Updating the device keeps the counters
+        table.insert(Device::new([1; 8], None)).unwrap();
+        assert_eq!(
+            table.get(&address).unwrap().frame_counter(&KeyId::Index(1)),
+            12
+        );
+    }
+
+    #[test]
+    fn save_restore() {
+        let mut table = DeviceTable::<2, 2>::new();
+        table.insert(Device::new([1; 8], None)).unwrap();
+        table.insert(Device::new([2; 8], None)).unwrap();
+        table
+            .update_frame_counter(&Address::Extended([1; 8]), KeyId::Implicit, 5)
+            .unwrap();
+        table
+            .update_frame_counter(&Address::Extended([2; 8]), KeyId::Index(3), 7)
+            .unwrap();
+
+        let mut saved = [None; 4];
+        for (slot, counter) in saved.iter_mut().zip(table.frame_counters()) {
+            *slot = Some(counter);
+        }
+
+        // After a reboot
+        let mut table = DeviceTable::<2, 2>::new();
+        table.insert(Device::new([1; 8], None)).unwrap();
+        table.insert(Device::new([2; 8], None)).unwrap();
+        for counter in saved.into_iter().flatten() {
+            table.restore_frame_counter(counter).unwrap();
+        }
+
+        assert_eq!(
+            table.update_frame_counter(&Address::Extended([1; 8]), KeyId::Implicit, 5),
+            Err(SecurityError::CounterError)
+        );
+        assert_eq!(
+            table.update_frame_counter(&Address::Extended([2; 8]), KeyId::Index(3), 7),
+            Err(SecurityError::CounterError)
+        );
+        assert_eq!(
+            table.restore_frame_counter(IncomingFrameCounter {
+                extended_address: [3; 8],
+                key: KeyId::Implicit,
+                frame_counter: 0,
+            }),
+            Err(SecurityError::UnavailableDevice)
+        );
+    }
+}
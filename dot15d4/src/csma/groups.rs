@@ -0,0 +1,111 @@
+//! A small, fixed-size table of group addresses.
+//!
+//! When `macGroupRxMode` is enabled, frames with a destination short address
+//! that is part of this table are accepted, the same way broadcast frames are.
+
+/// A table holding at most `N` group short addresses.
+#[derive(Debug)]
+pub struct GroupTable<const N: usize> {
+    entries: [[u8; 2]; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for GroupTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> GroupTable<N> {
+    /// Create a new empty [`GroupTable`].
+    pub const fn new() -> Self {
+        Self {
+            entries: [[0; 2]; N],
+            len: 0,
+        }
+    }
+
+    /// Join the group with the given short address.
+    ///
+    /// Returns the address back when the table is full.
+    pub fn join(&mut self, address: [u8; 2]) -> Result<(), [u8; 2]> {
+        if self.contains(&address) {
+            return Ok(());
+        }
+
+        match self.entries.get_mut(self.len) {
+            Some(entry) => {
+                *entry = address;
+                self.len += 1;
+                Ok(())
+            }
+            None => Err(address),
+        }
+    }
+
+    /// Leave the group with the given short address. Returns `true` when the
+    /// address was part of the table.
+    pub fn leave(&mut self, address: &[u8; 2]) -> bool {
+        let position = self.iter().position(|entry| entry == address);
+        match position {
+            Some(index) => {
+                self.entries.copy_within(index + 1..self.len, index);
+                self.len -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns `true` when the given short address is a joined group.
+    pub fn contains(&self, address: &[u8; 2]) -> bool {
+        self.as_slice().contains(address)
+    }
+
+    /// Return the joined groups, as used by
+    /// [`AddressFilter`](dot15d4_frame::AddressFilter).
+    pub fn as_slice(&self) -> &[[u8; 2]] {
+        &self.entries[..self.len]
+    }
+
+    /// Return an [`Iterator`] over the joined groups.
+    pub fn iter(&self) -> impl Iterator<Item = &[u8; 2]> {
+        self.as_slice().iter()
+    }
+
+    /// Return the number of joined groups.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` when no groups are joined.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn join_leave() {
+        let mut groups = GroupTable::<2>::new();
+        assert!(groups.is_empty());
+
+        assert_eq!(groups.join([0x80, 0x01]), Ok(()));
+        assert_eq!(groups.join([0x80, 0x01]), Ok(()));
+        assert_eq!(groups.join([0x80, 0x02]), Ok(()));
+        assert_eq!(groups.join([0x80, 0x03]), Err([0x80, 0x03]));
+        assert_eq!(groups.len(), 2);
+
+        assert!(groups.contains(&[0x80, 0x01]));
+        assert!(!groups.contains(&[0x80, 0x03]));
+
+        assert!(groups.leave(&[0x80, 0x01]));
+        assert!(!groups.leave(&[0x80, 0x01]));
+        assert!(!groups.contains(&[0x80, 0x01]));
+        assert_eq!(groups.join([0x80, 0x03]), Ok(()));
+        assert_eq!(groups.as_slice(), &[[0x80, 0x02], [0x80, 0x03]]);
+    }
+}
@@ -0,0 +1,172 @@
+//! A small, fixed-size table of installed keys.
+//!
+//! Secured frames are only accepted and transmitted when the key selected by
+//! their Auxiliary Security Header is installed. Installing a new key next to
+//! the current one, before switching outgoing frames over to it, allows the
+//! network key to be rotated without downtime.
+
+use dot15d4_frame::SecurityAttributes;
+
+use super::devices::KeyId;
+
+/// A key, together with the identifier used to select it.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct Key {
+    /// The identifier selecting this key, i.e. the key source and key index.
+    pub id: KeyId,
+    /// The 128-bit key.
+    pub key: [u8; 16],
+}
+
+// The key material is never printed, only the identifier of the key
+impl core::fmt::Debug for Key {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Key")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A table holding at most `N` [`Key`]s.
+#[derive(Debug)]
+pub struct KeyTable<const N: usize> {
+    entries: [Option<Key>; N],
+}
+
+impl<const N: usize> Default for KeyTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> KeyTable<N> {
+    /// Create a new empty [`KeyTable`].
+    pub const fn new() -> Self {
+        Self { entries: [None; N] }
+    }
+
+    /// Install a key, replacing the key with the same identifier.
+    ///
+    /// Returns the key back when the table is full.
+    pub fn insert(&mut self, key: Key) -> Result<(), Key> {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .flatten()
+            .find(|entry| entry.id == key.id)
+        {
+            *entry = key;
+            return Ok(());
+        }
+
+        match self.entries.iter_mut().find(|entry| entry.is_none()) {
+            Some(entry) => {
+                *entry = Some(key);
+                Ok(())
+            }
+            None => Err(key),
+        }
+    }
+
+    /// Remove the key with the given identifier from the table.
+    pub fn remove(&mut self, id: &KeyId) -> Option<Key> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.is_some_and(|entry| &entry.id == id))
+            .and_then(|entry| entry.take())
+    }
+
+    /// Return the key with the given identifier, if installed.
+    pub fn get(&self, id: &KeyId) -> Option<&Key> {
+        self.iter().find(|entry| &entry.id == id)
+    }
+
+    /// Return an [`Iterator`] over the installed keys.
+    pub fn iter(&self) -> impl Iterator<Item = &Key> {
+        self.entries.iter().flatten()
+    }
+
+    /// Return the number of installed keys.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` when no keys are installed.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Build the CCM* nonce of a frame from the extended address of its
+/// originator, in the same byte order as
+/// [`Address::Extended`](dot15d4_frame::Address::Extended), its frame counter
+/// and its security level.
+pub fn nonce(
+    extended_address: &[u8; 8],
+    frame_counter: u32,
+    security_level: SecurityAttributes,
+) -> [u8; 13] {
+    dot15d4_frame::ccm_nonce(extended_address, frame_counter, security_level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate() {
+        let mut keys = KeyTable::<2>::new();
+        assert!(keys.is_empty());
+
+        let current = Key {
+            id: KeyId::ShortSource([0, 0, 0, 1], 1),
+            key: [1; 16],
+        };
+        let next = Key {
+            id: KeyId::ShortSource([0, 0, 0, 1], 2),
+            key: [2; 16],
+        };
+
+        assert_eq!(keys.insert(current), Ok(()));
+        assert_eq!(keys.insert(next), Ok(()));
+        assert_eq!(
+            keys.insert(Key {
+                id: KeyId::Index(3),
+                key: [3; 16],
+            })
+            .map_err(|key| key.id),
+            Err(KeyId::Index(3))
+        );
+
+        // Both keys are selectable by their source and index
+        assert_eq!(keys.get(&current.id), Some(&current));
+        assert_eq!(keys.get(&next.id), Some(&next));
+        assert_eq!(keys.get(&KeyId::ShortSource([0, 0, 0, 2], 1)), None);
+
+        // Retire the old key
+        assert_eq!(keys.remove(&current.id), Some(current));
+        assert_eq!(keys.get(&current.id), None);
+        assert_eq!(keys.len(), 1);
+    }
+
+    #[test]
+    fn debug_hides_key() {
+        let key = Key {
+            id: KeyId::Index(1),
+            key: [0x5a; 16],
+        };
+        assert_eq!(std::format!("{key:?}"), "Key { id: Index(1), .. }");
+    }
+
+    #[test]
+    fn build_nonce() {
+        assert_eq!(
+            nonce(
+                &[0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01],
+                5,
+                SecurityAttributes::EncMic32
+            ),
+            [0xac, 0xde, 0x48, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x05]
+        );
+    }
+}
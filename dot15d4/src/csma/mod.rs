@@ -1,21 +1,47 @@
+pub mod acknowledgment;
 pub mod constants;
+pub mod crypto;
+pub mod devices;
+pub mod groups;
+pub mod keys;
+pub mod neighbors;
+pub mod poll;
 pub mod transmission;
 pub mod user_configurable_constants;
 mod utils;
 
+use core::cell::Cell;
+
+use acknowledgment::MinimalEnhancedAck;
 use constants::*;
+use crypto::{CryptoBackend, NoCrypto};
+use devices::{DeviceTable, IncomingFrameCounter, KeyId, SecurityError};
 use embedded_hal_async::delay::DelayNs;
+use groups::GroupTable;
+use keys::KeyTable;
+use neighbors::{EvictionPolicy, NeighborTable};
 use rand_core::RngCore;
 use user_configurable_constants::*;
 
 use crate::{
+    mlme::{
+        beacon_notify::{BeaconNotifyIndication, PanDescriptor},
+        data::{DataConfirm, DataStatus},
+        pib::{Pib, PibAttribute, PibAttributeId, PibBuilder, PibError},
+        poll::{PollRequest, PollStatus},
+        reset::ResetRequest,
+        MacConfirm, MacRequest,
+    },
     phy::{
+        airtime::PhyParameters,
         config::{self, RxConfig, TxConfig},
         driver::{self, Driver, FrameBuffer},
+        pool::PooledFrame,
         radio::{
-            futures::{receive, transmit},
-            Radio, RadioFrame, RadioFrameMut, TxToken,
+            futures::{receive, receive_window, transmit},
+            Radio, RadioFrame, RadioFrameMut, TxStatus, TxToken,
         },
+        stats::PhyStats,
     },
     sync::{
         channel::{Channel, Receiver, Sender},
@@ -28,7 +54,11 @@ use crate::{
     time::Duration,
 };
 use dot15d4_frame::{
-    Address, AddressingFieldsRepr, DataFrame, FrameBuilder, FrameType, FrameVersion,
+    frames::{BeaconOrder, SuperframeOrder, SuperframeSpecificationRepr},
+    Address, AddressFilter, AddressingFieldsRepr, AuxiliarySecurityHeaderRepr,
+    CfpSpecificationRepr, CommandId, DataFrame, FrameBuilder, FrameControlRepr, FrameRepr,
+    FrameType, FrameVersion, HeaderInformationElementRepr, SecurityLevel,
+    SimplifiedSuperframeSpecificationRepr,
 };
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -55,12 +85,53 @@ pub struct CsmaConfig {
     pub ignore_not_for_us: bool,
     /// Even if there is no ack_request flag set, ack it anyway
     pub ack_everything: bool,
-    /// The channel on which to transmit/receive
+    /// The channel on which to transmit/receive, the initial value of
+    /// `phyCurrentChannel` in the PIB
     pub channel: config::Channel,
+    /// The channel page of [`CsmaConfig::channel`], the initial value of
+    /// `phyCurrentPage` in the PIB (default = page 0)
+    pub channel_page: config::ChannelPage,
+    /// The CCA mode used before transmitting a frame, the initial value of
+    /// `phyCcaMode` in the PIB. With [`CcaMode::None`](config::CcaMode::None),
+    /// frames are transmitted without backoffs (default = energy detection)
+    pub cca_mode: config::CcaMode,
     /// Overwrite all frames' destination PAN ID (default = false)
     pub overwrite_dst_pan_id: bool,
     /// Overwrite all frames' source PAN ID (default = true)
     pub overwrite_src_pan_id: bool,
+    /// Rewrite an extended destination address to the short address of that
+    /// neighbor, if it is known in the neighbor table (default = false)
+    pub resolve_short_addresses: bool,
+    /// Accept frames addressed to one of the joined groups, see
+    /// `macGroupRxMode` (default = false)
+    pub group_rx_mode: bool,
+    /// How many times a broadcast or groupcast frame is transmitted again
+    /// after the first transmission. When non-zero, no ACK is requested for
+    /// these frames (default = 0)
+    pub broadcast_retransmissions: u8,
+    /// Upper bound of the random delay that is added to LIFS before every
+    /// broadcast retransmission (default = 10ms)
+    pub broadcast_jitter: Duration,
+    /// The backoff exponent of the first CSMA-CA backoff, the initial value
+    /// of `macMinBE` in the PIB (default = `MAC_MIN_BE`)
+    pub min_be: u16,
+    /// The largest backoff exponent of CSMA-CA, the initial value of
+    /// `macMaxBE` in the PIB (default = `MAC_MAX_BE`)
+    pub max_be: u16,
+    /// The number of backoffs after a busy channel before a channel access
+    /// failure, the initial value of `macMaxCSMABackoffs` in the PIB
+    /// (default = `MAC_MAX_CSMA_BACKOFFS`)
+    pub max_csma_backoffs: u16,
+    /// The number of retransmissions of an unacknowledged frame, the initial
+    /// value of `macMaxFrameRetries` in the PIB (default =
+    /// `MAC_MAX_FRAME_RETIES`)
+    pub max_frame_retries: u16,
+    /// The interframe spacing periods
+    pub timings: MacTimings,
+    /// Act as a coordinator, answering the Beacon Request commands of active
+    /// scans with a beacon, and Enhanced Beacon Requests with an enhanced
+    /// beacon (default = false)
+    pub coordinator: bool,
 }
 
 impl Default for CsmaConfig {
@@ -71,20 +142,186 @@ impl Default for CsmaConfig {
             ignore_not_for_us: true,
             ack_everything: false,
             channel: config::Channel::_26,
+            channel_page: config::ChannelPage::Page0,
+            cca_mode: config::CcaMode::EnergyDetection,
             overwrite_dst_pan_id: false,
             overwrite_src_pan_id: true,
+            resolve_short_addresses: false,
+            group_rx_mode: false,
+            broadcast_retransmissions: 0,
+            broadcast_jitter: Duration::from_us(10_000),
+            min_be: MAC_MIN_BE,
+            max_be: MAC_MAX_BE,
+            max_csma_backoffs: MAC_MAX_CSMA_BACKOFFS,
+            max_frame_retries: MAC_MAX_FRAME_RETIES,
+            timings: MacTimings::default(),
+            coordinator: false,
+        }
+    }
+}
+
+impl CsmaConfig {
+    /// Build the initial PIB attributes of this configuration.
+    fn pib(&self) -> Result<Pib, PibError> {
+        PibBuilder::new()
+            .channel(self.channel)
+            .channel_page(self.channel_page)
+            .cca_mode(self.cca_mode)
+            .backoff_exponents(self.min_be, self.max_be)
+            .max_csma_backoffs(self.max_csma_backoffs)
+            .max_frame_retries(self.max_frame_retries)
+            .build()
+    }
+
+    /// Return the worst-case time between taking `frame` from the driver and
+    /// confirming it, when every CCA finds the channel busy until the last
+    /// backoff, every backoff takes the longest possible delay, and no ACK is
+    /// ever received. Frames to the broadcast address or to one of the joined
+    /// `groups`, see [`GroupTable::as_slice`], are instead transmitted
+    /// [`CsmaConfig::broadcast_retransmissions`] more times.
+    ///
+    /// The CSMA-CA parameters of this configuration are used, not the
+    /// current values in the PIB. Without CCA, no backoffs are performed.
+    pub fn worst_case_latency(
+        &self,
+        phy: &PhyParameters,
+        frame: &FrameRepr<'_>,
+        groups: &[[u8; 2]],
+    ) -> Duration {
+        let max_csma_backoffs = if self.cca_mode == config::CcaMode::None {
+            0
+        } else {
+            self.max_csma_backoffs
+        };
+
+        // Every CCA attempt, with the backoffs in between
+        let mut channel_access =
+            phy.symbols(CCA_TIME + TURNAROUND_TIME) * (max_csma_backoffs as usize + 1);
+        let mut backoff_exponent = self.min_be;
+        for _ in 0..max_csma_backoffs {
+            backoff_exponent = core::cmp::min(backoff_exponent + 1, self.max_be);
+            channel_access =
+                channel_access + MAC_UNIT_BACKOFF_DURATION * ((1usize << backoff_exponent) - 1);
+        }
+        let attempt = channel_access + phy.frame_airtime(frame);
+
+        let is_broadcast = frame
+            .addressing_fields
+            .as_ref()
+            .and_then(|fields| fields.dst_address)
+            .is_some_and(|addr| is_broadcast_or_group(&addr, groups));
+        if is_broadcast {
+            let retransmission =
+                self.timings.lifs + self.broadcast_jitter.max(Duration::from_us(0)) + attempt;
+            return attempt + retransmission * self.broadcast_retransmissions as usize;
+        }
+
+        // The same ACK timeout and spacing as used when transmitting
+        let ack_timeout = self.timings.aifs + self.timings.sifs + Duration::from_us(288);
+        let spacing = self.timings.sifs.max(phy.symbols(TURNAROUND_TIME));
+        (attempt + ack_timeout + spacing) * (self.max_frame_retries as usize + 1)
+    }
+}
+
+/// Returns `true` when the address is the broadcast address or one of the
+/// given groups, such that frames to it are not acknowledged, but might be
+/// retransmitted.
+fn is_broadcast_or_group(addr: &Address, groups: &[[u8; 2]]) -> bool {
+    addr.is_broadcast() || addr.is_group(groups)
+}
+
+/// The interframe spacing periods used by the CSMA module. The defaults are
+/// taken from the compile time configuration, see `MAC_AIFS_PERIOD`,
+/// `MAC_SIFS_PERIOD` and `MAC_LIFS_PERIOD`.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[non_exhaustive]
+pub struct MacTimings {
+    /// Time between the end of a received frame and the start of its ACK
+    pub aifs: Duration,
+    /// Short interframe spacing, between a frame and the next transmission
+    pub sifs: Duration,
+    /// Long interframe spacing, between a broadcast frame and its
+    /// retransmission
+    pub lifs: Duration,
+}
+
+impl Default for MacTimings {
+    fn default() -> Self {
+        Self {
+            aifs: MAC_AIFS_PERIOD,
+            sifs: MAC_SIFS_PERIOD,
+            lifs: MAC_LIFS_PERIOD,
+        }
+    }
+}
+
+/// The buffer in which a frame is received: a frame of the pool of the driver
+/// when one is available, such that it is handed over without copying.
+enum RxBuffer {
+    Owned(FrameBuffer),
+    Pooled(PooledFrame<'static>),
+}
+
+impl Default for RxBuffer {
+    fn default() -> Self {
+        Self::Owned(FrameBuffer::default())
+    }
+}
+
+impl RxBuffer {
+    /// Return the received buffer, which is copied out of a pooled frame.
+    fn into_buffer(self) -> FrameBuffer {
+        match self {
+            Self::Owned(buffer) => buffer,
+            Self::Pooled(frame) => FrameBuffer::clone(&frame),
+        }
+    }
+}
+
+impl core::ops::Deref for RxBuffer {
+    type Target = FrameBuffer;
+
+    fn deref(&self) -> &FrameBuffer {
+        match self {
+            Self::Owned(buffer) => buffer,
+            Self::Pooled(frame) => frame,
+        }
+    }
+}
+
+impl core::ops::DerefMut for RxBuffer {
+    fn deref_mut(&mut self) -> &mut FrameBuffer {
+        match self {
+            Self::Owned(buffer) => buffer,
+            Self::Pooled(frame) => frame
+                .get_mut()
+                .expect("A frame is not shared before it is handed over"),
         }
     }
 }
 
 /// Structure that setups the CSMA futures
-pub struct CsmaDevice<R: Radio, Rng, D: Driver, TIMER> {
+pub struct CsmaDevice<R: Radio, Rng, D: Driver, TIMER, C = NoCrypto> {
     radio: Mutex<R>,
     rng: Mutex<Rng>,
     driver: D,
     timer: TIMER,
+    crypto: C,
     hardware_address: [u8; 8],
     config: CsmaConfig,
+    neighbors: NeighborTable<MAC_NEIGHBOR_TABLE_SIZE>,
+    groups: GroupTable<MAC_GROUP_TABLE_SIZE>,
+    devices: Mutex<DeviceTable<MAC_DEVICE_TABLE_SIZE, MAC_KEY_TABLE_SIZE>>,
+    keys: KeyTable<MAC_KEY_TABLE_SIZE>,
+    pib: Cell<Pib>,
+    /// The PIB the device was created with, restored by MLME-RESET.
+    initial_pib: Pib,
+    /// `macBSN`, the sequence number of the next transmitted beacon.
+    bsn: Cell<u8>,
+    /// `macDSN`, the sequence number of the next transmitted MAC command.
+    dsn: Cell<u8>,
+    phy_stats: Cell<PhyStats>,
 }
 
 impl<R, Rng, D, TIMER> CsmaDevice<R, Rng, D, TIMER>
@@ -93,21 +330,174 @@ where
     Rng: RngCore,
     D: Driver,
 {
-    /// Creates a new CSMA object that is ready to be run
+    /// Creates a new CSMA object that is ready to be run. Without a
+    /// [`CryptoBackend`], no secured frames can be transmitted or received.
     pub fn new(radio: R, rng: Rng, driver: D, timer: TIMER, config: CsmaConfig) -> Self {
-        let hardware_address = radio.ieee802154_address();
-        CsmaDevice {
+        Self::new_with_crypto(radio, rng, driver, timer, config, NoCrypto)
+    }
+}
+
+impl<R, Rng, D, TIMER, C> CsmaDevice<R, Rng, D, TIMER, C>
+where
+    R: Radio,
+    Rng: RngCore,
+    D: Driver,
+    C: CryptoBackend,
+{
+    /// Creates a new CSMA object that is ready to be run. Outgoing data frames
+    /// and Enhanced Acknowledgments are secured, and incoming frames are
+    /// unsecured and verified, using the given [`CryptoBackend`].
+    ///
+    /// # Panics
+    /// Panics when the CSMA-CA parameters of the [`CsmaConfig`] are out of
+    /// range, see [`PibBuilder::build`].
+    pub fn new_with_crypto(
+        radio: R,
+        rng: Rng,
+        driver: D,
+        timer: TIMER,
+        config: CsmaConfig,
+        crypto: C,
+    ) -> Self {
+        let pib = config
+            .pib()
+            .expect("The CSMA-CA parameters of the configuration are out of range");
+        Self::new_with_pib(radio, rng, driver, timer, config, crypto, pib)
+            .expect("The default PIB is always valid")
+    }
+
+    /// Creates a new CSMA object that is ready to be run, with the initial
+    /// PIB attributes built by a [`PibBuilder`]. The channel and the CSMA-CA
+    /// attributes of the PIB override [`CsmaConfig::channel`] and the CSMA-CA
+    /// parameters of the configuration.
+    ///
+    /// Fails with [`PibError::InvalidParameter`] when TSCH is enabled, as this
+    /// device only operates in CSMA-CA mode.
+    pub fn new_with_pib(
+        radio: R,
+        mut rng: Rng,
+        driver: D,
+        timer: TIMER,
+        config: CsmaConfig,
+        crypto: C,
+        mut pib: Pib,
+    ) -> Result<Self, PibError> {
+        if pib.mac_tsch_enabled {
+            return Err(PibError::InvalidParameter);
+        }
+
+        let hardware_address = *pib
+            .mac_extended_address
+            .get_or_insert_with(|| radio.ieee802154_address());
+        let bsn = rng.next_u32() as u8;
+        let dsn = rng.next_u32() as u8;
+        Ok(CsmaDevice {
             radio: Mutex::new(radio),
             rng: Mutex::new(rng),
             driver,
             timer,
+            crypto,
             hardware_address,
             config,
+            neighbors: NeighborTable::with_eviction_policy(EvictionPolicy::LeastRecentlyHeard),
+            groups: GroupTable::new(),
+            devices: Mutex::new(DeviceTable::new()),
+            keys: KeyTable::new(),
+            pib: Cell::new(pib),
+            initial_pib: pib,
+            bsn: Cell::new(bsn),
+            dsn: Cell::new(dsn),
+            phy_stats: Cell::new(PhyStats::default()),
+        })
+    }
+
+    /// The radio configuration used for receiving.
+    fn rx_config(&self) -> RxConfig {
+        self.pib.get().phy().rx_config()
+    }
+
+    /// The radio configuration used for transmitting, without CCA.
+    fn tx_config(&self) -> TxConfig {
+        TxConfig {
+            cca: config::CcaMode::None,
+            ..self.pib.get().phy().tx_config()
         }
     }
+
+    /// Returns the PAN Information Base.
+    pub fn pib(&self) -> Pib {
+        self.pib.get()
+    }
+
+    /// Return a snapshot of the diagnostic counters of the radio.
+    pub fn phy_stats(&self) -> PhyStats {
+        self.phy_stats.get()
+    }
+
+    /// MLME-GET: return the current value of a PIB attribute.
+    pub fn mlme_get(&self, id: PibAttributeId) -> PibAttribute {
+        self.pib.get().get(id)
+    }
+
+    /// MLME-SET: write the value of a PIB attribute. While running, a
+    /// [`MacRequest::Set`] should be used instead.
+    pub fn mlme_set(&mut self, attribute: PibAttribute) -> Result<(), PibError> {
+        self.pib.get_mut().set(attribute)
+    }
+
+    /// Returns a reference to the table of known neighbors.
+    pub fn neighbors(&self) -> &NeighborTable<MAC_NEIGHBOR_TABLE_SIZE> {
+        &self.neighbors
+    }
+
+    /// Returns a mutable reference to the table of known neighbors, used for
+    /// resolving short addresses at transmit time. When the table is full, the
+    /// neighbor that was heard the least recently and that is not pinned is
+    /// evicted.
+    pub fn neighbors_mut(&mut self) -> &mut NeighborTable<MAC_NEIGHBOR_TABLE_SIZE> {
+        &mut self.neighbors
+    }
+
+    /// Returns a reference to the table of joined groups.
+    pub fn groups(&self) -> &GroupTable<MAC_GROUP_TABLE_SIZE> {
+        &self.groups
+    }
+
+    /// Returns a mutable reference to the table of joined groups. Frames
+    /// addressed to these groups are only accepted when
+    /// [`CsmaConfig::group_rx_mode`] is set.
+    pub fn groups_mut(&mut self) -> &mut GroupTable<MAC_GROUP_TABLE_SIZE> {
+        &mut self.groups
+    }
+
+    /// Returns a mutable reference to the table of devices from which secured
+    /// frames are accepted. Saved incoming frame counters should be restored
+    /// here before running.
+    pub fn devices_mut(&mut self) -> &mut DeviceTable<MAC_DEVICE_TABLE_SIZE, MAC_KEY_TABLE_SIZE> {
+        self.devices.get_mut()
+    }
+
+    /// Returns a reference to the table of installed keys.
+    pub fn keys(&self) -> &KeyTable<MAC_KEY_TABLE_SIZE> {
+        &self.keys
+    }
+
+    /// Returns a mutable reference to the table of installed keys. The key of
+    /// a secured frame is selected by the key source and key index in its
+    /// Auxiliary Security Header.
+    pub fn keys_mut(&mut self) -> &mut KeyTable<MAC_KEY_TABLE_SIZE> {
+        &mut self.keys
+    }
+
+    /// Returns a mutable reference to the outgoing frame counter,
+    /// `macFrameCounter`. It is part of the PIB, and saved with
+    /// [`Pib::save`].
+    pub fn frame_counter_mut(&mut self) -> &mut u32 {
+        &mut self.pib.get_mut().mac_frame_counter
+    }
 }
 
-impl<R, Rng, D, TIMER> CsmaDevice<R, Rng, D, TIMER>
+impl<R, Rng, D, TIMER, C> CsmaDevice<R, Rng, D, TIMER, C>
 where
     R: Radio,
     for<'a> R::RadioFrame<&'a mut [u8]>: RadioFrameMut<&'a mut [u8]>,
@@ -115,27 +505,83 @@ where
     Rng: RngCore,
     D: Driver,
     TIMER: DelayNs + Clone,
+    C: CryptoBackend,
 {
     /// Run the CSMA module. This should be run in its own task and polled
     /// seperately.
-    pub async fn run(&mut self) -> ! {
-        let mut wants_to_transmit_signal = Channel::new();
-        let (sender, receiver) = wants_to_transmit_signal.split();
+    ///
+    /// Returns once [`Driver::stop`] completes and the ongoing transmission
+    /// is finished, with the radio disabled. Frames that were not taken from
+    /// the driver yet are left untouched. The CSMA module can be run again
+    /// afterwards.
+    pub async fn run(&mut self) {
         self.radio.get_mut().enable().await; // Wake up radio
-        match select::select(
-            self.transmit_package_task(sender),
-            self.receive_frame_task(receiver),
-        )
-        .await
-        {
-            Either::First(_) => panic!("Tasks should never terminate, csma transmission just did"),
-            Either::Second(_) => panic!("Tasks should never terminate, csma receiving just did"),
+        loop {
+            let mut wants_to_transmit_signal = Channel::new();
+            let (sender, receiver) = wants_to_transmit_signal.split();
+            let mut data_received_signal = Channel::new();
+            let (data_sender, data_receiver) = data_received_signal.split();
+            let mut rx_window_signal = Channel::new();
+            let (rx_window_sender, rx_window_receiver) = rx_window_signal.split();
+            let mut beacon_request_signal = Channel::new();
+            let (beacon_request_sender, beacon_request_receiver) = beacon_request_signal.split();
+            let reset = match select::select(
+                self.transmit_package_task(
+                    sender,
+                    data_receiver,
+                    rx_window_sender,
+                    beacon_request_receiver,
+                ),
+                self.receive_frame_task(
+                    receiver,
+                    data_sender,
+                    rx_window_receiver,
+                    beacon_request_sender,
+                ),
+            )
+            .await
+            {
+                Either::First(reset) => reset,
+                Either::Second(_) => {
+                    panic!("Tasks should never terminate, csma receiving just did")
+                }
+            };
+
+            // Both tasks are stopped, and restarted after a reset
+            let Some(request) = reset else {
+                break;
+            };
+            self.reset(&request).await;
+            self.driver.mlme_confirm(MacConfirm::Reset).await;
         }
+        self.shutdown().await;
+    }
+
+    /// Cancel the ongoing radio operation and disable the radio. This is done
+    /// when [`CsmaDevice::run`] returns, but should be done as well when its
+    /// future is dropped instead.
+    pub async fn shutdown(&mut self) {
+        let radio = self.radio.get_mut();
+        radio.cancel_current_opperation();
+        radio.disable().await;
+    }
+
+    /// Returns `true` when the address is the broadcast address or one of the
+    /// joined groups.
+    fn is_broadcast_or_group(&self, addr: &Address) -> bool {
+        is_broadcast_or_group(addr, self.groups.as_slice())
     }
 
-    /// Checks if the current frame is intended for us. For the hardware
-    /// address, the full 64-bit address should be provided.
-    fn is_package_for_us(hardware_address: &[u8; 8], frame: &DataFrame<&'_ [u8]>) -> bool {
+    /// Checks if the current frame is intended for us, following the third
+    /// level of filtering of IEEE 802.15.4-2020, section 6.7.2. For the
+    /// hardware address, the full 64-bit address should be provided. Frames
+    /// addressed to one of the `groups` are accepted as well.
+    fn is_package_for_us(
+        hardware_address: &[u8; 8],
+        pib: &Pib,
+        groups: &[[u8; 2]],
+        frame: &DataFrame<&'_ [u8]>,
+    ) -> bool {
         // Check if the type is known, otherwise drop
         if matches!(frame.frame_control().frame_type(), FrameType::Unknown) {
             return false;
@@ -145,33 +591,342 @@ where
             return false;
         }
 
-        let addr = match frame.addressing().and_then(|fields| fields.dst_address()) {
-            Some(addr) => addr,
-            None if MAC_IMPLICIT_BROADCAST => Address::BROADCAST,
-            _ => return false,
-        };
+        let addressing = frame.addressing();
+        let dst_address = addressing
+            .as_ref()
+            .and_then(|fields| fields.dst_address())
+            .unwrap_or(Address::Absent);
+        let dst_pan_id = addressing.as_ref().and_then(|fields| fields.dst_pan_id());
+        let src_pan_id = addressing.as_ref().and_then(|fields| fields.src_pan_id());
+
+        // The destination PAN ID, if present, is either ours or the broadcast
+        // PAN ID
+        if dst_pan_id.is_some_and(|pan_id| pan_id != pib.mac_pan_id && pan_id != BROADCAST_PAN_ID) {
+            return false;
+        }
+
+        match &dst_address {
+            // Beacons are accepted from our own PAN, or from any PAN while we
+            // are not part of one
+            Address::Absent if frame.frame_control().frame_type() == FrameType::Beacon => {
+                pib.mac_pan_id == BROADCAST_PAN_ID
+                    || src_pan_id.is_none_or(|pan_id| pan_id == pib.mac_pan_id)
+            }
+            // TODO: accept frames with only source addressing fields when we
+            // are the PAN coordinator and the source PAN ID is ours
+            Address::Absent => dst_pan_id.is_none() && pib.mac_implicit_broadcast,
+            _ => AddressFilter {
+                short_address: pib.mac_short_address.to_be_bytes(),
+                extended_address: *hardware_address,
+                groups,
+            }
+            .accepts(&dst_address),
+        }
+    }
 
-        // Check if dst_pan (in present) is provided
-        let dst_pan_id = frame
+    /// Incoming frame security processing: verify the MIC of a secured frame
+    /// and decrypt it in place with the [`CryptoBackend`], after which the
+    /// MIC is removed from the frame. Secured frames of which the frame
+    /// counter was already seen are rejected, and the incoming frame counter
+    /// is only updated once the MIC is verified.
+    ///
+    /// Returns the updated incoming frame counter, or `None` when the frame
+    /// is not secured.
+    async fn unsecure_frame(
+        &self,
+        buffer: &mut [u8],
+    ) -> Result<Option<IncomingFrameCounter>, SecurityError> {
+        let mut radio_frame =
+            R::RadioFrame::new_checked(&mut *buffer).map_err(|_| SecurityError::TransformFailed)?;
+        let data = radio_frame.data_mut();
+        let frame = DataFrame::new_unchecked(&data[..]);
+        let Some(header) = frame.auxiliary_security_header() else {
+            return Ok(None);
+        };
+        // When the frame counter is suppressed, the ASN is used in the nonce
+        // instead, which is only known in TSCH mode
+        let frame_counter = header
+            .frame_counter()
+            .ok_or(SecurityError::TransformFailed)?;
+        let key = KeyId::from_header(&header)
+            .and_then(|key| self.keys.get(&key))
+            .ok_or(SecurityError::UnavailableKey)?;
+        let address = frame
             .addressing()
-            .and_then(|fields| fields.dst_pan_id())
-            .unwrap_or(BROADCAST_PAN_ID);
-        if dst_pan_id != MAC_PAN_ID && dst_pan_id != BROADCAST_PAN_ID {
+            .and_then(|fields| fields.src_address())
+            .unwrap_or(Address::Absent);
+
+        let mut devices = self.devices.lock().await;
+        let extended_address = devices.check_frame_counter(&address, &key.id, frame_counter)?;
+        // Frames without a MIC can not be verified
+        let parts = frame
+            .ccm_parts(&extended_address)
+            .filter(|parts| parts.mic_len > 0)
+            .ok_or(SecurityError::TransformFailed)?;
+        let (a_data, m_data, mic) = parts.split_mut(data);
+        self.crypto
+            .ccm_star_decrypt(&key.key, &parts.nonce, a_data, m_data, mic)?;
+        let frame_counter = devices.update_frame_counter(&address, key.id, frame_counter)?;
+
+        let len = radio_frame.data().len() - parts.mic_len;
+        drop(radio_frame);
+        R::TxToken::from(buffer).consume(len, |_| {});
+        Ok(Some(frame_counter))
+    }
+
+    /// Outgoing frame security processing: secure a frame with the key
+    /// selected by its Auxiliary Security Header and the next
+    /// `macFrameCounter` value, using the [`CryptoBackend`]. The frame
+    /// counter is written in the Auxiliary Security Header, and the MIC is
+    /// appended to the frame.
+    ///
+    /// Frames that are not secured are left untouched. The frame counter
+    /// field of a secured frame must be present, as the ASN is not known in
+    /// CSMA-CA mode.
+    async fn secure_frame(&self, buffer: &mut [u8]) -> Result<(), SecurityError> {
+        let radio_frame =
+            R::RadioFrame::new_checked(&mut *buffer).map_err(|_| SecurityError::TransformFailed)?;
+        let len = radio_frame.data().len();
+        let frame = DataFrame::new_unchecked(radio_frame.data());
+        let Some(header) = frame.auxiliary_security_header() else {
+            return Ok(());
+        };
+        let key = KeyId::from_header(&header)
+            .and_then(|key| self.keys.get(&key))
+            .ok_or(SecurityError::UnavailableKey)?;
+        let aux = AuxiliarySecurityHeaderRepr::parse(&header)
+            .ok()
+            .filter(|aux| aux.frame_counter.is_some())
+            .ok_or(SecurityError::TransformFailed)?;
+        let mic_len = SecurityLevel::from(aux.security_level as u8).mic_length();
+        // The MIC has to fit in the frame, next to the FCS
+        if len + mic_len > MAX_PHY_PACKET_SIZE as usize - 2 {
+            return Err(SecurityError::TransformFailed);
+        }
+        drop(radio_frame);
+
+        let aux = AuxiliarySecurityHeaderRepr {
+            frame_counter: Some(self.next_frame_counter()?),
+            ..aux
+        };
+        R::TxToken::from(buffer).consume(len + mic_len, |buffer| {
+            let mut frame = DataFrame::new_unchecked(&mut buffer[..]);
+            frame.set_aux_sec_header(&aux);
+            let parts = frame
+                .ccm_parts(&self.hardware_address)
+                .ok_or(SecurityError::TransformFailed)?;
+            let (a_data, m_data, mic) = parts.split_mut(buffer);
+            self.crypto
+                .ccm_star_encrypt(&key.key, &parts.nonce, a_data, m_data, mic)
+        })
+    }
+
+    /// Return the next `macFrameCounter` value to secure a frame with.
+    fn next_frame_counter(&self) -> Result<u32, SecurityError> {
+        let mut pib = self.pib.get();
+        let current = pib.mac_frame_counter;
+        pib.mac_frame_counter = current.checked_add(1).ok_or(SecurityError::CounterError)?;
+        self.pib.set(pib);
+        Ok(current)
+    }
+
+    /// Return the next `macBSN` value to transmit a beacon with.
+    fn next_bsn(&self) -> u8 {
+        let bsn = self.bsn.get();
+        self.bsn.set(bsn.wrapping_add(1));
+        bsn
+    }
+
+    /// Return the next `macDSN` value to transmit a MAC command with.
+    fn next_dsn(&self) -> u8 {
+        let dsn = self.dsn.get();
+        self.dsn.set(dsn.wrapping_add(1));
+        dsn
+    }
+
+    /// Whether a received Beacon Request should be answered. The Enhanced
+    /// Beacon Filter IE of an Enhanced Beacon Request can restrict the answer
+    /// to coordinators that permit association, that received the request
+    /// with at least a given link quality, or to a percentage of them. The
+    /// link quality filter is not met when the radio does not report the link
+    /// quality of the request.
+    async fn accepts_beacon_request(
+        &self,
+        frame: &DataFrame<&'_ [u8]>,
+        pib: &Pib,
+        link_quality: Option<u8>,
+    ) -> bool {
+        let Some(ie) = frame.information_elements() else {
+            return true;
+        };
+        let Some(filter) = ie.enhanced_beacon_filter() else {
+            return true;
+        };
+
+        if filter.permit_joining_on() && !pib.mac_association_permit {
             return false;
         }
 
-        // TODO: Check rules if frame comes from PAN coordinator and the same MAC_PAN_ID
-        // TODO: Implement `macGroupRxMode` check here
-        match &addr {
-            _ if addr.is_broadcast() => true,
-            Address::Absent => false,
-            Address::Short(addr) => hardware_address[6..] == addr[..2],
-            Address::Extended(addr) => hardware_address == addr,
+        if let Some(min_link_quality) = filter.link_quality() {
+            if link_quality.is_none_or(|link_quality| link_quality < min_link_quality) {
+                return false;
+            }
+        }
+
+        match filter.percent_filter() {
+            Some(percent) => self.rng.lock().await.next_u32() % 100 < percent as u32,
+            None => true,
+        }
+    }
+
+    /// Transmit a beacon in answer to a Beacon Request, using CSMA-CA. An
+    /// Enhanced Beacon Request of IEEE 802.15.4-2020 is answered with an
+    /// enhanced beacon, older Beacon Requests with a beacon. Both carry
+    /// `macBSN` and a Superframe Specification advertising
+    /// `macAssociationPermit`, on which the permit joining filter of an
+    /// Enhanced Beacon Request selects. The enhanced beacon carries it in a
+    /// Simplified Superframe Specification Header IE.
+    async fn transmit_beacon(
+        &self,
+        request_version: FrameVersion,
+        wants_to_transmit_signal: &Sender<'_, ()>,
+        tx: &mut FrameBuffer,
+        timer: &mut TIMER,
+    ) {
+        let pib = self.pib.get();
+        let src_address = Address::Extended(self.hardware_address);
+
+        // Non-beacon-enabled PAN: beacon and superframe order 15, final CAP
+        // slot 15, no GTS fields and no pending addresses
+        let beacon_fields = [0xff, 0x0f | (pib.mac_association_permit as u8) << 7, 0, 0];
+        let sequence_number = self.next_bsn();
+        let repr = if request_version == FrameVersion::Ieee802154_2020 {
+            let superframe = SimplifiedSuperframeSpecificationRepr {
+                timestamp: 0,
+                superframe_specification: SuperframeSpecificationRepr {
+                    beacon_order: BeaconOrder::OnDemand,
+                    superframe_order: SuperframeOrder::Inactive,
+                    final_cap_slot: 15,
+                    battery_life_extension: false,
+                    pan_coordinator: false,
+                    association_permit: pib.mac_association_permit,
+                },
+                cfp_specification: CfpSpecificationRepr {
+                    gts_count: 0,
+                    first_cfp_slot: 0,
+                    last_cfp_slot: 0,
+                    gts_permit: false,
+                },
+            };
+            FrameBuilder::new_enhanced_beacon()
+                .set_sequence_number(sequence_number)
+                .set_src_pan_id(pib.mac_pan_id)
+                .set_src_address(src_address)
+                .add_header_information_element(
+                    HeaderInformationElementRepr::SimplifiedSuperframeSpecification(superframe),
+                )
+                .finalize()
+        } else {
+            FrameBuilder::new_beacon()
+                .set_sequence_number(sequence_number)
+                .set_src_pan_id(pib.mac_pan_id)
+                .set_src_address(src_address)
+                .set_payload(&beacon_fields)
+                .finalize()
+        };
+        let Ok(repr) = repr else {
+            return;
+        };
+        R::TxToken::from(&mut tx.buffer[..]).consume(repr.buffer_len(), |buffer| {
+            repr.emit(&mut DataFrame::new_unchecked(buffer));
+        });
+
+        let backoff_strategy = transmission::CCABackoffStrategy::new_exponential_backoff(
+            &self.rng,
+            pib.mac_min_be,
+            pib.mac_max_be,
+        );
+        let mut radio_guard = None;
+        match transmission::transmit_cca(
+            &self.radio,
+            &mut radio_guard,
+            self.tx_config(),
+            wants_to_transmit_signal,
+            tx,
+            timer,
+            backoff_strategy,
+            pib.mac_max_csma_backoffs,
+            &self.driver,
+            &self.phy_stats,
+        )
+        .await
+        {
+            Ok(_timestamp) => {}
+            Err(transmission::TransmissionError::CcaError) => {
+                self.driver.error(driver::Error::CcaFailed).await;
+            }
+            Err(transmission::TransmissionError::HwError) => {
+                self.driver.error(driver::Error::RadioError).await;
+            }
+        }
+    }
+
+    /// Emit an Enhanced Acknowledgment, secured with the same key and security
+    /// level as the acknowledged frame, and using the next outgoing frame
+    /// counter. Only frames that passed incoming security processing are
+    /// acknowledged this way.
+    async fn emit_secured_ack(
+        &self,
+        sequence_number: Option<u8>,
+        aux: AuxiliarySecurityHeaderRepr,
+        dst_pan_id: u16,
+        dst_address: Address,
+        tx_ack: &mut FrameBuffer,
+    ) -> Result<(), SecurityError> {
+        let key = self
+            .keys
+            .get(&aux.key_identifier.into())
+            .ok_or(SecurityError::UnavailableKey)?;
+
+        let frame_counter = self.next_frame_counter()?;
+        let aux = AuxiliarySecurityHeaderRepr {
+            frame_counter: Some(frame_counter),
+            asn_in_nonce: false,
+            ..aux
+        };
+
+        let mut builder = FrameBuilder::new_ack();
+        if let Some(sequence_number) = sequence_number {
+            builder = builder.set_sequence_number(sequence_number);
         }
+        let repr = builder
+            .set_dst_pan_id(dst_pan_id)
+            .set_dst_address(dst_address)
+            .set_auxiliary_security_header(aux)
+            .finalize()
+            .map_err(|_| SecurityError::TransformFailed)?;
+
+        // There are no payload IEs yet, so the whole frame is authenticated
+        let header_len = repr.buffer_len();
+        let mic_len = SecurityLevel::from(aux.security_level as u8).mic_length();
+        let nonce = keys::nonce(&self.hardware_address, frame_counter, aux.security_level);
+
+        R::TxToken::from(&mut tx_ack.buffer).consume(header_len + mic_len, |buffer| {
+            let (header, mic) = buffer.split_at_mut(header_len);
+            repr.emit(&mut DataFrame::new_unchecked(&mut header[..]));
+            self.crypto
+                .ccm_star_encrypt(&key.key, &nonce, header, &mut [], mic)
+        })
     }
 
-    async fn receive_frame_task(&self, wants_to_transmit_signal: Receiver<'_, ()>) -> ! {
-        let mut rx = FrameBuffer::default();
+    async fn receive_frame_task(
+        &self,
+        wants_to_transmit_signal: Receiver<'_, ()>,
+        data_received_signal: Sender<'_, Address>,
+        rx_window_signal: Receiver<'_, ()>,
+        beacon_request_signal: Sender<'_, FrameVersion>,
+    ) -> ! {
+        let mut rx = RxBuffer::default();
         let mut radio_guard = None;
         let mut timer = self.timer.clone();
 
@@ -181,6 +936,24 @@ where
         'outer: loop {
             yield_now().await;
 
+            // Receive in a frame of the driver, when one is free
+            if let RxBuffer::Owned(_) = rx {
+                if let Some(frame) = self.driver.alloc_rx_frame() {
+                    rx = RxBuffer::Pooled(frame);
+                }
+            }
+
+            if !self.pib.get().mac_rx_on_when_idle {
+                // The receiver is off while idle, only receive when a receive
+                // window is opened. The window is closed again as soon as the
+                // transmit task needs the radio.
+                radio_guard = None;
+                rx_window_signal.receive().await;
+                while wants_to_transmit_signal.has_item() {
+                    wants_to_transmit_signal.receive().await;
+                }
+            }
+
             // try to receive something
             let receive_result = {
                 radio_guard = match radio_guard {
@@ -191,15 +964,20 @@ where
                     receive(
                         &mut **radio_guard.as_mut().unwrap(),
                         &mut rx.buffer,
-                        RxConfig {
-                            channel: self.config.channel,
-                        },
+                        self.rx_config(),
                     ),
                     wants_to_transmit_signal.receive(),
                 )
                 .await
                 {
-                    Either::First(receive_result) => receive_result,
+                    Either::First(true) => {
+                        PhyStats::count(&self.phy_stats, |stats| &mut stats.rx_frames);
+                        true
+                    }
+                    Either::First(false) => {
+                        PhyStats::count(&self.phy_stats, |stats| &mut stats.rx_errors);
+                        false
+                    }
                     Either::Second(_) => false,
                 }
             };
@@ -210,20 +988,39 @@ where
                 radio_guard = None;
                 continue 'outer;
             }
+            let link_quality = radio_guard.as_ref().and_then(|radio| radio.rx_lqi());
+
+            let (
+                should_ack,
+                sequence_number,
+                ack_security,
+                enh_ack_dst,
+                pan_descriptor,
+                data_src,
+                frame_counter,
+            ) = {
+                let rx: &mut FrameBuffer = &mut rx;
 
-            let (should_ack, sequence_number) = {
                 // Check if package is valid IEEE and not an ACK
-                let Ok(frame) = R::RadioFrame::new_checked(&mut rx.buffer) else {
+                let Ok(radio_frame) = R::RadioFrame::new_checked(&mut rx.buffer) else {
+                    PhyStats::count(&self.phy_stats, |stats| &mut stats.rx_rejected);
                     rx.dirty = false;
                     continue 'outer;
                 };
-                let Ok(frame) = DataFrame::new(frame.data()) else {
+                let Ok(frame) = DataFrame::new(radio_frame.data()) else {
+                    PhyStats::count(&self.phy_stats, |stats| &mut stats.rx_rejected);
                     rx.dirty = false;
                     continue 'outer;
                 };
 
                 // Check if package is meant for us
-                if !Self::is_package_for_us(&self.hardware_address, &frame)
+                let groups = if self.config.group_rx_mode {
+                    self.groups.as_slice()
+                } else {
+                    &[]
+                };
+                let pib = self.pib.get();
+                if !Self::is_package_for_us(&self.hardware_address, &pib, groups, &frame)
                     && self.config.ignore_not_for_us
                 {
                     // Package is not for us to handle, ignore
@@ -237,6 +1034,40 @@ where
                     continue 'outer;
                 }
 
+                // As a coordinator, Beacon Requests are answered by the
+                // transmit task, and not handed to the upper layer
+                if self.config.coordinator && frame.command_id() == Some(CommandId::BeaconRequest) {
+                    if self
+                        .accepts_beacon_request(&frame, &pib, link_quality)
+                        .await
+                    {
+                        debug!("answering Beacon Request");
+                        beacon_request_signal.send(frame.frame_control().frame_version());
+                    }
+                    rx.dirty = false;
+                    continue 'outer;
+                }
+
+                // Drop forged and replayed secured frames
+                drop(radio_frame);
+                let frame_counter = match self.unsecure_frame(&mut rx.buffer).await {
+                    Ok(frame_counter) => frame_counter,
+                    Err(err) => {
+                        debug!("dropping secured frame: {:?}", err);
+                        self.driver.error(driver::Error::SecurityFailed(err)).await;
+                        rx.dirty = false;
+                        continue 'outer;
+                    }
+                };
+                let radio_frame = R::RadioFrame::new_unchecked(&mut rx.buffer);
+                let frame = DataFrame::new_unchecked(radio_frame.data());
+
+                // Only frames that passed security processing refresh the
+                // neighbor table
+                if let Some(src) = frame.addressing().and_then(|fields| fields.src_address()) {
+                    self.neighbors.heard(&src);
+                }
+
                 let should_ack = match frame.addressing().and_then(|addr| addr.dst_address()) {
                     // Overwrite in config
                     _ if self.config.ack_everything => true,
@@ -245,7 +1076,7 @@ where
                     _ if !frame.frame_control().ack_request() => false,
 
                     // We want ACK on broadcast -> check config
-                    Some(addr) if addr.is_broadcast() => self.config.ack_broadcast,
+                    Some(addr) if self.is_broadcast_or_group(&addr) => self.config.ack_broadcast,
 
                     // We want ACK on unicast -> check config
                     Some(_) => self.config.ack_unicast,
@@ -253,52 +1084,156 @@ where
                     // All other scenarios -> don't ack
                     None => false,
                 };
-                (should_ack, frame.sequence_number())
+
+                let pan_descriptor = if frame.frame_control().frame_type() == FrameType::Beacon {
+                    PanDescriptor::parse(
+                        radio_frame.data(),
+                        self.pib.get().phy_current_channel,
+                        self.pib.get().phy_current_page,
+                        link_quality,
+                    )
+                } else {
+                    None
+                };
+
+                // IEEE 802.15.4-2020 frames can be acknowledged with an
+                // Enh-ACK towards the originator, in the PAN of the
+                // originator, which is secured for verified secured frames
+                let enh_ack_dst = (frame.frame_control().frame_version()
+                    == FrameVersion::Ieee802154_2020)
+                    .then(|| frame.addressing())
+                    .flatten()
+                    .and_then(|fields| {
+                        let (ack, _) = AddressingFieldsRepr::parse(fields)
+                            .acknowledgment(false)
+                            .ok()?;
+                        Some((ack.dst_pan_id.unwrap_or(pib.mac_pan_id), ack.dst_address?))
+                    });
+                let ack_security = frame_counter
+                    .and_then(|_| frame.auxiliary_security_header())
+                    .and_then(|header| AuxiliarySecurityHeaderRepr::parse(&header).ok())
+                    .zip(enh_ack_dst);
+
+                let data_src = (frame.frame_control().frame_type() == FrameType::Data)
+                    .then(|| frame.addressing().and_then(|fields| fields.src_address()))
+                    .flatten();
+
+                (
+                    should_ack,
+                    frame.sequence_number(),
+                    ack_security,
+                    enh_ack_dst,
+                    pan_descriptor,
+                    data_src,
+                    frame_counter,
+                )
             };
 
+            // Let a pending poll know that data has arrived, and from whom
+            if let Some(src) = data_src {
+                data_received_signal.send(src);
+            }
+
             // Concurrently send the received message to the upper layers, and if we need to
             // ACK, we ACK
             rx.dirty = true;
             join::join(
                 async {
                     if should_ack {
-                        // Set correct sequence number and send an ACK only if valid sequence number
-                        if let Some(sequence_number) = sequence_number {
-                            let ieee_repr = FrameBuilder::new_imm_ack(sequence_number)
-                                .finalize()
-                                .expect("A simple imm-ACK should always be possible to build");
-                            let ack_token = R::TxToken::from(&mut tx_ack.buffer);
-                            ack_token.consume(ieee_repr.buffer_len(), |buffer| {
-                                let mut frame = DataFrame::new_unchecked(buffer);
-                                ieee_repr.emit(&mut frame);
-                            });
-
-                            // Wait before sending the ACK (AIFS), but we reduce
-                            // this time by half as the timer we use is not
-                            // guaranteed to be exact. This is due to how Rust futures
-                            // work and the timer becomes an 'at least this waiting time'
-                            // The goal is to transmit an ACK between 1ms and 2ms.
-                            let delay = MAC_AIFS_PERIOD / 2;
-                            timer.delay_us(delay.as_us() as u32).await;
-
-                            // We already have the lock on the radio, so start transmitting and do not
-                            // have to check anymore
-                            transmit(
-                                &mut **radio_guard.as_mut().unwrap(),
-                                &mut tx_ack.buffer,
-                                TxConfig {
-                                    channel: self.config.channel,
-                                    ..Default::default()
-                                },
-                            )
-                            .await;
+                        let ack = match (ack_security, sequence_number) {
+                            (Some((aux, (dst_pan_id, dst_address))), _) => Some(
+                                self.emit_secured_ack(
+                                    sequence_number,
+                                    aux,
+                                    dst_pan_id,
+                                    dst_address,
+                                    &mut tx_ack,
+                                )
+                                .await,
+                            ),
+                            // Set correct sequence number and send an ACK only if valid sequence number
+                            (None, Some(sequence_number)) => {
+                                let ieee_repr = FrameBuilder::new_imm_ack(sequence_number)
+                                    .finalize()
+                                    .expect("A simple imm-ACK should always be possible to build");
+                                let ack_token = R::TxToken::from(&mut tx_ack.buffer);
+                                ack_token.consume(ieee_repr.buffer_len(), |buffer| {
+                                    let mut frame = DataFrame::new_unchecked(buffer);
+                                    ieee_repr.emit(&mut frame);
+                                });
+                                Some(Ok(()))
+                            }
+                            // Without a sequence number, only an Enh-ACK can
+                            // acknowledge the frame
+                            (None, None) => enh_ack_dst.map(|(dst_pan_id, dst_address)| {
+                                let ack = MinimalEnhancedAck {
+                                    sequence_number: None,
+                                    dst_pan_id,
+                                    dst_address,
+                                    frame_pending: false,
+                                };
+                                let ack_token = R::TxToken::from(&mut tx_ack.buffer);
+                                ack_token.consume(ack.buffer_len(), |buffer| ack.emit(buffer));
+                                Ok(())
+                            }),
+                        };
+
+                        match ack {
+                            Some(Ok(())) => {
+                                // Wait before sending the ACK (AIFS), but we reduce
+                                // this time by half as the timer we use is not
+                                // guaranteed to be exact. This is due to how Rust futures
+                                // work and the timer becomes an 'at least this waiting time'
+                                // The goal is to transmit an ACK between 1ms and 2ms.
+                                let delay = self.config.timings.aifs / 2;
+                                timer.delay_us(delay.as_us() as u32).await;
+
+                                // We already have the lock on the radio, so start transmitting and do not
+                                // have to check anymore
+                                PhyStats::count(&self.phy_stats, |stats| &mut stats.tx_attempts);
+                                let status = transmit(
+                                    &mut **radio_guard.as_mut().unwrap(),
+                                    &mut tx_ack.buffer,
+                                    self.tx_config(),
+                                )
+                                .await
+                                .status;
+                                if status != TxStatus::Sent {
+                                    PhyStats::count(&self.phy_stats, |stats| &mut stats.tx_errors);
+                                }
+                                if status == TxStatus::HwError {
+                                    radio_guard = None;
+                                    self.driver.error(driver::Error::RadioError).await;
+                                }
+                            }
+                            Some(Err(err)) => {
+                                // The ACK could not be secured, do not send it
+                                radio_guard = None;
+                                self.driver.error(driver::Error::SecurityFailed(err)).await;
+                            }
+                            None => {}
                         }
                     } else {
                         // Immediatly drop gruard if we do not longer need it to ACK
                         radio_guard = None;
                     }
                 },
-                self.driver.received(core::mem::take(&mut rx)),
+                async {
+                    if let Some(frame_counter) = frame_counter {
+                        self.driver.frame_counter_updated(frame_counter).await;
+                    }
+
+                    match (pan_descriptor, core::mem::take(&mut rx)) {
+                        (Some(pan_descriptor), rx) => {
+                            debug!("MLME-BEACON-NOTIFY.indication");
+                            let indication =
+                                BeaconNotifyIndication::new(pan_descriptor, rx.into_buffer());
+                            self.driver.beacon_notify(indication).await
+                        }
+                        (None, RxBuffer::Owned(rx)) => self.driver.received(rx).await,
+                        (None, RxBuffer::Pooled(rx)) => self.driver.received_pooled(rx).await,
+                    }
+                },
             )
             .await;
             rx.dirty = false; // Reset for the following iteration
@@ -308,10 +1243,12 @@ where
     /// If the frame is malformed/invalid -> parsing error will be returned.
     /// If the frame is no ack'able -> the sequence number will be None.
     /// Second argument in the option is the frame length -> useful to find out
-    /// how long we should wait for an ACK
+    /// how long we should wait for an ACK.
+    /// Unicast frames only request an ACK when `ack_unicast` is set.
     fn set_ack_request_if_possible<'a, RadioFrame>(
         &self,
         buffer: &'a mut [u8],
+        ack_unicast: bool,
     ) -> Result<Option<(u8, u8)>, TransmissionTaskError<RadioFrame::Error>>
     where
         RadioFrame: RadioFrameMut<&'a mut [u8]>,
@@ -326,11 +1263,19 @@ where
         let frame_type = frame.frame_control().frame_type();
         if frame_type == FrameType::Data || frame_type == FrameType::MacCommand {
             match frame.addressing().and_then(|addr| addr.dst_address()) {
-                Some(addr) if addr.is_unicast() && self.config.ack_unicast => {
+                // Repeated broadcasts are never acknowledged
+                Some(addr)
+                    if self.is_broadcast_or_group(&addr)
+                        && self.config.broadcast_retransmissions > 0 =>
+                {
+                    frame.frame_control_mut().set_ack_request(false);
+                    Ok(None)
+                }
+                Some(addr) if self.is_broadcast_or_group(&addr) && self.config.ack_broadcast => {
                     frame.frame_control_mut().set_ack_request(true);
                     Ok(frame.sequence_number().map(|seq| (seq, frame_len)))
                 }
-                Some(addr) if addr.is_broadcast() && self.config.ack_broadcast => {
+                Some(addr) if addr.is_unicast() && ack_unicast => {
                     frame.frame_control_mut().set_ack_request(true);
                     Ok(frame.sequence_number().map(|seq| (seq, frame_len)))
                 }
@@ -367,23 +1312,24 @@ where
             return Ok(());
         };
 
+        let mac_pan_id = self.pib.get().mac_pan_id;
         let mut changed = false;
         if self.config.overwrite_src_pan_id
             && addr
                 .src_pan_id
-                .map(|pan_id| pan_id != MAC_PAN_ID)
+                .map(|pan_id| pan_id != mac_pan_id)
                 .unwrap_or(false)
         {
-            addr.src_pan_id = Some(MAC_PAN_ID);
+            addr.src_pan_id = Some(mac_pan_id);
             changed = true;
         }
         if self.config.overwrite_dst_pan_id
             && addr
                 .dst_pan_id
-                .map(|pan_id| pan_id != MAC_PAN_ID)
+                .map(|pan_id| pan_id != mac_pan_id)
                 .unwrap_or(false)
         {
-            addr.dst_pan_id = Some(MAC_PAN_ID);
+            addr.dst_pan_id = Some(mac_pan_id);
             changed = true;
         }
 
@@ -394,59 +1340,409 @@ where
         Ok(())
     }
 
-    async fn wait_for_valid_ack(
-        radio: &mut R,
-        channel: config::Channel,
-        sequence_number: u8,
-        ack_rx: &mut [u8; 128],
-    ) {
-        loop {
-            let result = receive(radio, ack_rx, RxConfig { channel }).await;
-            if !result {
-                // No succesful receive, try again
-                continue;
-            }
-
-            // Check if we received a valid ACK
-            let Ok(frame) = R::RadioFrame::new_checked(ack_rx) else {
-                continue;
-            };
-            let Ok(frame) = DataFrame::new(frame.data()) else {
-                continue;
-            };
-
-            if frame.frame_control().frame_type() == FrameType::Ack
-                && frame.sequence_number() == Some(sequence_number)
-            {
-                return;
-            }
-        }
-    }
-
-    async fn transmit_package_task(&self, wants_to_transmit_signal: Sender<'_, ()>) -> !
+    /// Rewrite an extended destination address into the short address of
+    /// that neighbor, if known. On success, the new length of the frame is
+    /// returned, such that the radio frame can be resized.
+    fn resolve_short_address<'a, RadioFrame>(
+        &self,
+        buffer: &'a mut [u8],
+    ) -> Result<Option<usize>, TransmissionTaskError<RadioFrame::Error>>
     where
-        R: Radio,
-        for<'a> R::RadioFrame<&'a mut [u8]>: RadioFrameMut<&'a mut [u8]>,
-        Rng: RngCore,
-        D: Driver,
+        RadioFrame: RadioFrameMut<&'a mut [u8]>,
     {
-        let mut ack_rx = FrameBuffer::default();
-        let mut timer = self.timer.clone();
+        let mut radio_frame =
+            RadioFrame::new_checked(buffer).map_err(TransmissionTaskError::InvalidDeviceFrame)?;
+        let frame_len = radio_frame.data().len();
+        let mut frame = DataFrame::new(radio_frame.data_mut())
+            .map_err(|_err| TransmissionTaskError::InvalidIEEEFrame)?;
 
-        'outer: loop {
-            // Wait until we have a frame to send
-            let mut tx = self.driver.transmit().await;
+        // Changing the addressing fields of a secured frame would invalidate
+        // its MIC
+        if frame.frame_control().security_enabled() {
+            return Ok(None);
+        }
 
-            yield_now().await;
+        let Some(fields) = frame.addressing() else {
+            return Ok(None);
+        };
+        let old_len = fields.len();
+        let addr = AddressingFieldsRepr::parse(fields);
 
-            // Enable ACK in frame coming from higher layers
-            let mut sequence_number = None;
-            match self.set_ack_request_if_possible::<R::RadioFrame<_>>(&mut tx.buffer) {
+        let Some(Address::Extended(extended)) = addr.dst_address else {
+            return Ok(None);
+        };
+        let Some(short) = self.neighbors.short_address(&extended) else {
+            return Ok(None);
+        };
+
+        // Changing the addressing mode might change which PAN IDs are present
+        let Ok(mut fc) = FrameControlRepr::parse(frame.frame_control()) else {
+            return Ok(None);
+        };
+        let Ok((addr, control)) = AddressingFieldsRepr::from_addresses(
+            fc.frame_version,
+            addr.dst_pan_id,
+            Some(Address::Short(short)),
+            addr.src_pan_id.or(addr.dst_pan_id),
+            addr.src_address,
+        ) else {
+            return Ok(None);
+        };
+        control.apply(&mut fc);
+        let new_len = addr.buffer_len(&fc);
+        let Some(shrunk_by) = old_len.checked_sub(new_len) else {
+            return Ok(None);
+        };
+        let start = 2 + (!fc.sequence_number_suppression as usize);
+
+        let mut w = frame.frame_control_mut();
+        w.set_dst_addressing_mode(control.dst_addressing_mode);
+        w.set_pan_id_compression(control.pan_id_compression);
+
+        // Move everything after the addressing fields, and write the new
+        // addressing fields in front of it
+        let data = radio_frame.data_mut();
+        data.copy_within(start + old_len..frame_len, start + new_len);
+        data[frame_len - shrunk_by..frame_len].fill(0);
+        DataFrame::new_unchecked(&mut *data).set_addressing_fields(&addr);
+
+        Ok(Some(frame_len - shrunk_by))
+    }
+
+    /// Check whether a reception while waiting for an ACK is the ACK of the
+    /// frame with `sequence_number`.
+    ///
+    /// Returns whether the ACK indicates pending data, or `None` when the
+    /// reception is not the ACK.
+    fn valid_ack(
+        frame: Option<&FrameBuffer>,
+        sequence_number: u8,
+        stats: &Cell<PhyStats>,
+    ) -> Option<bool> {
+        let Some(frame) = frame else {
+            // No succesful receive, try again
+            PhyStats::count(stats, |stats| &mut stats.rx_errors);
+            return None;
+        };
+        PhyStats::count(stats, |stats| &mut stats.rx_frames);
+
+        // Check if we received a valid ACK
+        let Ok(frame) = <R::RadioFrame<&[u8]>>::new_checked(&frame.buffer[..]) else {
+            PhyStats::count(stats, |stats| &mut stats.rx_rejected);
+            return None;
+        };
+        let Ok(frame) = DataFrame::new(frame.data()) else {
+            PhyStats::count(stats, |stats| &mut stats.rx_rejected);
+            return None;
+        };
+
+        (frame.frame_control().frame_type() == FrameType::Ack
+            && frame.sequence_number() == Some(sequence_number))
+        .then(|| frame.frame_control().frame_pending())
+    }
+
+    /// MLME-POLL: request pending data from the coordinator by transmitting a
+    /// Data Request command. When the acknowledgment indicates that data is
+    /// pending, wait at most `macMaxFrameTotalWaitTime` for a data frame from
+    /// the polled coordinator.
+    #[allow(clippy::too_many_arguments)]
+    async fn poll(
+        &self,
+        request: &PollRequest,
+        wants_to_transmit_signal: &Sender<'_, ()>,
+        data_received_signal: &Receiver<'_, Address>,
+        rx_window_signal: &Sender<'_, ()>,
+        tx: &mut FrameBuffer,
+        ack_rx: &mut FrameBuffer,
+        timer: &mut TIMER,
+    ) -> PollStatus {
+        if request.coord_address == Address::Absent {
+            return PollStatus::InvalidParameter;
+        }
+
+        let sequence_number = self.next_dsn();
+        let Ok(repr) = FrameBuilder::new_data_request()
+            .set_sequence_number(sequence_number)
+            .set_dst_pan_id(request.coord_pan_id)
+            .set_dst_address(request.coord_address)
+            .set_src_pan_id(request.coord_pan_id)
+            .set_src_address(Address::Extended(self.hardware_address))
+            .finalize()
+        else {
+            return PollStatus::InvalidParameter;
+        };
+        R::TxToken::from(&mut tx.buffer[..]).consume(repr.buffer_len(), |buffer| {
+            repr.emit(&mut DataFrame::new_unchecked(buffer));
+        });
+
+        // Forget about data that arrived before this poll
+        while data_received_signal.has_item() {
+            data_received_signal.receive().await;
+        }
+
+        let tx_config = TxConfig {
+            cca: self.pib.get().phy_cca_mode,
+            power: self
+                .neighbors
+                .tx_power(&request.coord_address)
+                .unwrap_or(self.pib.get().phy_tx_power),
+            ..self.tx_config()
+        };
+
+        let pib = self.pib.get();
+        let mut radio_guard = None;
+        for _ in 0..=pib.mac_max_frame_retries {
+            let backoff_strategy = transmission::CCABackoffStrategy::new_exponential_backoff(
+                &self.rng,
+                pib.mac_min_be,
+                pib.mac_max_be,
+            );
+            match transmission::transmit_cca(
+                &self.radio,
+                &mut radio_guard,
+                tx_config.clone(),
+                wants_to_transmit_signal,
+                tx,
+                timer,
+                backoff_strategy,
+                pib.mac_max_csma_backoffs,
+                &self.driver,
+                &self.phy_stats,
+            )
+            .await
+            {
+                Ok(_timestamp) => {}
+                Err(transmission::TransmissionError::CcaError) => {
+                    return PollStatus::ChannelAccessFailure;
+                }
+                Err(transmission::TransmissionError::HwError) => {
+                    self.driver.error(driver::Error::RadioError).await;
+                    return PollStatus::RadioFailure;
+                }
+            }
+
+            utils::acquire_lock(&self.radio, wants_to_transmit_signal, &mut radio_guard).await;
+
+            // Same timeout as for acknowledged data frames
+            let delay =
+                self.config.timings.aifs + self.config.timings.sifs + Duration::from_us(288);
+            let ack = receive_window(
+                &mut *radio_guard.unwrap(),
+                timer,
+                ack_rx,
+                self.rx_config(),
+                Duration::from_us(0),
+                delay,
+                |frame| Self::valid_ack(frame, sequence_number, &self.phy_stats),
+            )
+            .await;
+            radio_guard = None;
+
+            match ack {
+                Some(false) => return PollStatus::NoData,
+                Some(true) => {
+                    // The receive task hands the data frame to the upper layer
+                    if !self.pib.get().mac_rx_on_when_idle {
+                        rx_window_signal.send(());
+                    }
+                    return match select::select(
+                        async {
+                            while data_received_signal.receive().await != request.coord_address {}
+                        },
+                        timer.delay_us(MAC_MAX_FRAME_TOTAL_WAIT_TIME.as_us() as u32),
+                    )
+                    .await
+                    {
+                        Either::First(()) => PollStatus::Success,
+                        Either::Second(()) => PollStatus::NoData,
+                    };
+                }
+                None => {
+                    let delay = self.config.timings.sifs.max(Duration::from_us(
+                        (TURNAROUND_TIME * SYMBOL_RATE_INV_US) as i64,
+                    ));
+                    timer.delay_us(delay.as_us() as u32).await;
+                }
+            }
+        }
+
+        PollStatus::NoAck
+    }
+
+    /// Wake the radio up or put it to sleep, after taking the radio from the
+    /// receive task.
+    async fn set_radio_enabled(&self, enabled: bool, wants_to_transmit_signal: &Sender<'_, ()>) {
+        let mut radio_guard = None;
+        utils::acquire_lock(&self.radio, wants_to_transmit_signal, &mut radio_guard).await;
+        let radio = radio_guard.as_mut().unwrap();
+        if enabled {
+            radio.enable().await;
+        } else {
+            radio.disable().await;
+        }
+    }
+
+    /// MLME-SET while running. The radio is taken from the receive task
+    /// first, such that it restarts receiving with the new configuration.
+    async fn set_pib_attribute(
+        &self,
+        attribute: PibAttribute,
+        wants_to_transmit_signal: &Sender<'_, ()>,
+        rx_window_signal: &Sender<'_, ()>,
+    ) -> Result<(), PibError> {
+        let mut radio_guard = None;
+        utils::acquire_lock(&self.radio, wants_to_transmit_signal, &mut radio_guard).await;
+
+        let mut pib = self.pib.get();
+        let was_rx_on_when_idle = pib.mac_rx_on_when_idle;
+        pib.set(attribute)?;
+        self.pib.set(pib);
+
+        if !was_rx_on_when_idle && pib.mac_rx_on_when_idle {
+            // The receive task is waiting for a receive window
+            rx_window_signal.send(());
+        }
+
+        Ok(())
+    }
+
+    /// MLME-RESET, while the tasks of [`CsmaDevice::run`] are stopped. The
+    /// radio is restarted, and the neighbor and group tables are cleared.
+    ///
+    /// With `set_default_pib`, the PIB is set back to the one the device was
+    /// created with, and the device and key tables, holding the incoming
+    /// frame counters, are cleared. The outgoing frame counter is kept, such
+    /// that CCM* nonces are not reused when the keys are installed again.
+    async fn reset(&mut self, request: &ResetRequest) {
+        let radio = self.radio.get_mut();
+        radio.cancel_current_opperation();
+        radio.disable().await;
+        radio.enable().await;
+
+        self.neighbors = NeighborTable::with_eviction_policy(self.neighbors.eviction_policy());
+        self.groups = GroupTable::new();
+
+        if request.set_default_pib {
+            self.pib.set(Pib {
+                mac_frame_counter: self.pib.get().mac_frame_counter,
+                ..self.initial_pib
+            });
+            *self.devices.get_mut() = DeviceTable::new();
+            self.keys = KeyTable::new();
+        }
+    }
+
+    async fn transmit_package_task(
+        &self,
+        wants_to_transmit_signal: Sender<'_, ()>,
+        data_received_signal: Receiver<'_, Address>,
+        rx_window_signal: Sender<'_, ()>,
+        beacon_request_signal: Receiver<'_, FrameVersion>,
+    ) -> Option<ResetRequest>
+    where
+        R: Radio,
+        for<'a> R::RadioFrame<&'a mut [u8]>: RadioFrameMut<&'a mut [u8]>,
+        Rng: RngCore,
+        D: Driver,
+    {
+        let mut ack_rx = FrameBuffer::default();
+        let mut timer = self.timer.clone();
+
+        'outer: loop {
+            if !self.pib.get().mac_rx_on_when_idle {
+                // Nothing left to do, the radio can go to sleep
+                self.set_radio_enabled(false, &wants_to_transmit_signal)
+                    .await;
+            }
+
+            // Wait until we have a frame to send, a management request, a
+            // beacon to answer a Beacon Request with, or until we should stop
+            let request = match select::select(
+                select::select(self.driver.transmit(), self.driver.mlme_request()),
+                select::select(beacon_request_signal.receive(), self.driver.stop()),
+            )
+            .await
+            {
+                Either::First(request) => Either::First(request),
+                Either::Second(Either::First(request_version)) => Either::Second(request_version),
+                Either::Second(Either::Second(())) => {
+                    // Take the radio from the receive task, such that it is
+                    // not stopped while acknowledging a frame
+                    let mut radio_guard = None;
+                    utils::acquire_lock(&self.radio, &wants_to_transmit_signal, &mut radio_guard)
+                        .await;
+                    return None;
+                }
+            };
+
+            if !self.pib.get().mac_rx_on_when_idle {
+                self.set_radio_enabled(true, &wants_to_transmit_signal)
+                    .await;
+            }
+
+            let mut tx = match request {
+                Either::First(Either::First(tx)) => tx,
+                Either::Second(request_version) => {
+                    debug!("transmitting beacon");
+                    let mut tx = FrameBuffer::default();
+                    self.transmit_beacon(
+                        request_version,
+                        &wants_to_transmit_signal,
+                        &mut tx,
+                        &mut timer,
+                    )
+                    .await;
+                    continue 'outer;
+                }
+                Either::First(Either::Second(MacRequest::Poll(request))) => {
+                    debug!("MLME-POLL.request");
+                    let mut tx = FrameBuffer::default();
+                    let status = self
+                        .poll(
+                            &request,
+                            &wants_to_transmit_signal,
+                            &data_received_signal,
+                            &rx_window_signal,
+                            &mut tx,
+                            &mut ack_rx,
+                            &mut timer,
+                        )
+                        .await;
+                    debug!("MLME-POLL.confirm: {:?}", status);
+                    self.driver.mlme_confirm(MacConfirm::Poll(status)).await;
+                    continue 'outer;
+                }
+                Either::First(Either::Second(MacRequest::Set(attribute))) => {
+                    debug!("MLME-SET.request: {:?}", attribute);
+                    let status = self
+                        .set_pib_attribute(attribute, &wants_to_transmit_signal, &rx_window_signal)
+                        .await;
+                    self.driver.mlme_confirm(MacConfirm::Set(status)).await;
+                    continue 'outer;
+                }
+                Either::First(Either::Second(MacRequest::Reset(request))) => {
+                    // The reset is done by `run`, once both tasks are stopped.
+                    // The radio is taken from the receive task, such that it
+                    // is not reset while acknowledging a frame.
+                    debug!("MLME-RESET.request: {:?}", request);
+                    let mut radio_guard = None;
+                    utils::acquire_lock(&self.radio, &wants_to_transmit_signal, &mut radio_guard)
+                        .await;
+                    return Some(request);
+                }
+            };
+
+            yield_now().await;
+
+            // Enable ACK in frame coming from higher layers
+            let mut sequence_number = None;
+            let ack_unicast = self.config.ack_unicast && !tx.no_ack;
+            match self.set_ack_request_if_possible::<R::RadioFrame<_>>(&mut tx.buffer, ack_unicast)
+            {
                 Ok(seq_number) => sequence_number = seq_number,
                 Err(TransmissionTaskError::InvalidIEEEFrame) => {
                     // Invalid IEEE frame encountered
-                    #[cfg(feature = "defmt")]
-                    defmt::trace!("INVALID frame TX incoming buffer IEEE");
+                    trace!("INVALID frame TX incoming buffer IEEE");
                     self.driver.error(driver::Error::InvalidIEEEStructure).await;
                 }
                 #[allow(unused_variables)]
@@ -461,8 +1757,7 @@ where
                 Ok(()) => (),
                 Err(TransmissionTaskError::InvalidIEEEFrame) => {
                     // Invalid IEEE frame encountered
-                    #[cfg(feature = "defmt")]
-                    defmt::trace!("INVALID frame TX incoming buffer IEEE");
+                    trace!("INVALID frame TX incoming buffer IEEE");
                     self.driver.error(driver::Error::InvalidIEEEStructure).await;
                 }
                 #[allow(unused_variables)]
@@ -474,28 +1769,111 @@ where
                 }
             }
 
+            if self.config.resolve_short_addresses {
+                let new_len = match self.resolve_short_address::<R::RadioFrame<_>>(&mut tx.buffer) {
+                    Ok(new_len) => new_len,
+                    Err(TransmissionTaskError::InvalidIEEEFrame) => {
+                        self.driver.error(driver::Error::InvalidIEEEStructure).await;
+                        None
+                    }
+                    Err(TransmissionTaskError::InvalidDeviceFrame(_)) => {
+                        self.driver
+                            .error(driver::Error::InvalidDeviceStructure)
+                            .await;
+                        None
+                    }
+                };
+                if let Some(new_len) = new_len {
+                    // Let the radio know that the frame got shorter
+                    R::TxToken::from(&mut tx.buffer[..]).consume(new_len, |_| {});
+                }
+            }
+
+            let frame_sequence_number = R::RadioFrame::new_checked(&mut tx.buffer[..])
+                .ok()
+                .and_then(|frame| DataFrame::new(frame.data()).ok()?.sequence_number());
+
+            // The key of a secured frame is selected by the key source and key
+            // index in its Auxiliary Security Header
+            if let Err(err) = self.secure_frame(&mut tx.buffer).await {
+                self.driver.error(driver::Error::SecurityFailed(err)).await;
+                self.driver
+                    .mlme_confirm(MacConfirm::Data(DataConfirm {
+                        handle: tx.handle,
+                        sequence_number: frame_sequence_number,
+                        status: err.into(),
+                    }))
+                    .await;
+                continue 'outer;
+            }
+
+            let dst_address = R::RadioFrame::new_checked(&mut tx.buffer[..])
+                .ok()
+                .and_then(|frame| {
+                    let frame = DataFrame::new(frame.data()).ok()?;
+                    frame.addressing().and_then(|fields| fields.dst_address())
+                });
+
+            // Use the transmit power of the neighbor, if one is configured,
+            // and the channel of the frame, if one is given. The receive task
+            // returns to the channel of the PIB afterwards.
+            let channel = tx.channel.unwrap_or(self.pib.get().phy_current_channel);
+            let tx_config = TxConfig {
+                cca: self.pib.get().phy_cca_mode,
+                power: dst_address
+                    .and_then(|addr| self.neighbors.tx_power(&addr))
+                    .unwrap_or(self.pib.get().phy_tx_power),
+                channel,
+                ..self.tx_config()
+            };
+            let ack_rx_config = RxConfig {
+                channel,
+                ..self.rx_config()
+            };
+
+            // Broadcast and groupcast frames might be transmitted multiple times
+            let is_broadcast = dst_address.is_some_and(|addr| self.is_broadcast_or_group(&addr));
+            let retransmissions = if is_broadcast {
+                self.config.broadcast_retransmissions
+            } else {
+                0
+            };
+
+            let pib = self.pib.get();
             let mut radio_guard = None;
-            'ack: for i_ack in 1..MAC_MAX_FRAME_RETIES + 1 {
+            let mut status = DataStatus::NoAck;
+            'ack: for i_ack in 0..=pib.mac_max_frame_retries {
                 // Set vars for CCA
-                let backoff_strategy =
-                    transmission::CCABackoffStrategy::new_exponential_backoff(&self.rng);
+                let backoff_strategy = transmission::CCABackoffStrategy::new_exponential_backoff(
+                    &self.rng,
+                    pib.mac_min_be,
+                    pib.mac_max_be,
+                );
                 // Perform CCA
                 match transmission::transmit_cca(
                     &self.radio,
                     &mut radio_guard,
-                    self.config.channel,
+                    tx_config.clone(),
                     &wants_to_transmit_signal,
                     &mut tx,
                     &mut timer,
                     backoff_strategy,
+                    pib.mac_max_csma_backoffs,
                     &self.driver,
+                    &self.phy_stats,
                 )
                 .await
                 {
-                    Ok(()) => {}
-                    Err(_err) => {
+                    Ok(_timestamp) => {}
+                    Err(transmission::TransmissionError::CcaError) => {
                         // Transmission failed
                         self.driver.error(driver::Error::CcaFailed).await;
+                        status = DataStatus::ChannelAccessFailure;
+                        break 'ack;
+                    }
+                    Err(transmission::TransmissionError::HwError) => {
+                        self.driver.error(driver::Error::RadioError).await;
+                        status = DataStatus::RadioFailure;
                         break 'ack;
                     }
                 }
@@ -508,33 +1886,78 @@ where
                     // We expect an ACK to come back AIFS + time for an ACK to travel + SIFS (guard)
                     // An ACK is 3 bytes + 6 bytes (PHY header) long
                     // and should take around 288us at 250kbps to get back
-                    let delay = MAC_AIFS_PERIOD + MAC_SIFS_PERIOD + Duration::from_us(288);
+                    let delay = self.config.timings.aifs
+                        + self.config.timings.sifs
+                        + Duration::from_us(288);
 
-                    match select::select(
-                        Self::wait_for_valid_ack(
-                            &mut *radio_guard.unwrap(),
-                            self.config.channel,
-                            sequence_number,
-                            &mut ack_rx.buffer,
-                        ),
-                        // Timeout for waiting on an ACK
-                        timer.delay_us(delay.as_us() as u32),
+                    match receive_window(
+                        radio_guard.as_deref_mut().unwrap(),
+                        &mut timer,
+                        &mut ack_rx,
+                        ack_rx_config.clone(),
+                        Duration::from_us(0),
+                        delay,
+                        |frame| Self::valid_ack(frame, sequence_number, &self.phy_stats),
                     )
                     .await
                     {
-                        Either::First(()) => {
+                        Some(_) => {
                             // ACK succesful, transmission succesful
-                            // This releases the radio_gaurd too
-                            continue 'outer;
+                            status = DataStatus::Success;
+                            break 'ack;
                         }
-                        Either::Second(()) => {
+                        None => {
                             // Timout, retry logic if following part of the code
                         }
                     }
                 } else {
                     // We do not have a sequence number, so do not wait for an ACK
                     // Transmission is considered a success
-                    continue 'outer;
+                    for _ in 0..retransmissions {
+                        radio_guard = None;
+
+                        // Wait for LIFS and a random jitter, such that neighbors repeating the
+                        // same broadcast do not collide
+                        let jitter = self.rng.lock().await.next_u32() as i64
+                            % (self.config.broadcast_jitter.as_us().max(0) + 1);
+                        let delay = self.config.timings.lifs + Duration::from_us(jitter);
+                        timer.delay_us(delay.as_us() as u32).await;
+
+                        let backoff_strategy =
+                            transmission::CCABackoffStrategy::new_exponential_backoff(
+                                &self.rng,
+                                pib.mac_min_be,
+                                pib.mac_max_be,
+                            );
+                        if let Err(err) = transmission::transmit_cca(
+                            &self.radio,
+                            &mut radio_guard,
+                            tx_config.clone(),
+                            &wants_to_transmit_signal,
+                            &mut tx,
+                            &mut timer,
+                            backoff_strategy,
+                            pib.mac_max_csma_backoffs,
+                            &self.driver,
+                            &self.phy_stats,
+                        )
+                        .await
+                        {
+                            self.driver
+                                .error(match err {
+                                    transmission::TransmissionError::CcaError => {
+                                        driver::Error::CcaFailed
+                                    }
+                                    transmission::TransmissionError::HwError => {
+                                        driver::Error::RadioError
+                                    }
+                                })
+                                .await;
+                            break;
+                        }
+                    }
+                    status = DataStatus::Success;
+                    break 'ack;
                 }
 
                 // Whether we succeeded or not, we no longer need sole access to the radio
@@ -542,547 +1965,3095 @@ where
                 radio_guard = None;
 
                 // Wait for SIFS here
-                let delay = MAC_SIFS_PERIOD.max(Duration::from_us(
+                let delay = self.config.timings.sifs.max(Duration::from_us(
                     (TURNAROUND_TIME * SYMBOL_RATE_INV_US) as i64,
                 ));
                 timer.delay_us(delay.as_us() as u32).await;
 
                 // Was this the last attempt?
-                if i_ack == MAC_MAX_FRAME_RETIES {
+                if i_ack == pib.mac_max_frame_retries {
+                    debug!("no ACK after {} retransmissions", i_ack);
                     // Fail transmission
                     self.driver.error(driver::Error::AckFailed).await;
                     break 'ack;
                 } else {
-                    self.driver.error(driver::Error::AckRetry(i_ack)).await;
+                    trace!("no ACK, retransmission {}", i_ack + 1);
+                    self.driver.error(driver::Error::AckRetry(i_ack + 1)).await;
                 }
             }
+
+            // Release the radio before handing the confirmation to the upper
+            // layer
+            drop(radio_guard);
+            trace!("MCPS-DATA.confirm: {:?}", status);
+            self.driver
+                .mlme_confirm(MacConfirm::Data(DataConfirm {
+                    handle: tx.handle,
+                    sequence_number: frame_sequence_number,
+                    status,
+                }))
+                .await;
         }
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    #[cfg(feature = "software-crypto")]
+    use self::crypto::SoftwareCrypto;
     use self::driver::tests::*;
-    use crate::{phy::radio::tests::*, phy::radio::*, sync::tests::*, sync::*};
+    use crate::phy::driver::FrameHandle;
+    use crate::{
+        phy::pool::FramePool, phy::radio::tests::*, phy::radio::*, sync::tests::*, sync::*,
+    };
+    use dot15d4_frame::{
+        assert_frame_eq, AuxiliarySecurityHeaderRepr, EnhancedBeaconFilterRepr, FrameRepr,
+        KeyIdentifierRepr, SecurityAttributes,
+    };
 
     use super::*;
 
-    #[pollster::test]
-    pub async fn test_happy_path_transmit_no_ack() {
-        let radio = TestRadio::default();
-        let mut channel = TestDriverChannel::new();
-        let (driver, monitor) = channel.split();
-        let mut csma = CsmaDevice::new(
-            radio.clone(),
-            rand::thread_rng(),
-            driver,
-            Delay::default(),
-            CsmaConfig::default(),
-        );
+    /// Emit `frame`, secured with `key` by the device with extended address
+    /// `src`. The Auxiliary Security Header of the frame holds its frame
+    /// counter.
+    #[cfg(feature = "software-crypto")]
+    fn secured_frame_buffer(frame: &FrameRepr<'_>, key: &[u8; 16], src: &[u8; 8]) -> FrameBuffer {
+        let aux = frame.auxiliary_security_header.unwrap();
+        let mic_len = SecurityLevel::from(aux.security_level as u8).mic_length();
+        let len = frame.buffer_len();
 
-        // Select here, such that everything ends when the test is over
-        select::select(csma.run(), async {
-            let frame = FrameBuffer::default();
-            radio.inner(|inner| {
-                inner.assert_nxt.append(
-                    &mut [
-                        TestRadioEvent::PrepareReceive,
-                        // By default we receive
-                        TestRadioEvent::Receive,
-                        TestRadioEvent::CancelCurrentOperation,
-                        TestRadioEvent::PrepareTransmit,
-                        // Then we get the request to transmit
-                        TestRadioEvent::Transmit,
-                        // After which we go back to receiving normal traffic
-                        TestRadioEvent::PrepareReceive,
-                        TestRadioEvent::Receive,
-                    ]
-                    .into(),
-                );
-            });
-            monitor.tx.send_async(frame.clone()).await;
-            radio.wait_until_asserts_are_consumed().await;
+        let mut f = FrameBuffer::default();
+        TestTxToken::from(&mut f.buffer[..]).consume(len + mic_len, |buffer| {
+            frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..len]));
+            let parts = DataFrame::new(&buffer[..]).unwrap().ccm_parts(src).unwrap();
+            let (a_data, m_data, mic) = parts.split_mut(buffer);
+            SoftwareCrypto
+                .ccm_star_encrypt(key, &parts.nonce, a_data, m_data, mic)
+                .unwrap();
+        });
+        f
+    }
+
+    /// Emit `frame` without securing it.
+    #[cfg(feature = "software-crypto")]
+    fn frame_bytes(frame: &FrameRepr<'_>) -> std::vec::Vec<u8> {
+        let mut buffer = std::vec![0; frame.buffer_len()];
+        frame.emit(&mut DataFrame::new_unchecked(&mut buffer[..]));
+        buffer
+    }
+
+    /// Return the frame in a buffer, without the bytes after its end.
+    #[cfg(feature = "software-crypto")]
+    fn frame_data(buffer: &[u8]) -> &[u8] {
+        &buffer[..test_frame_len(buffer)]
+    }
+
+    #[test]
+    pub fn test_happy_path_transmit_no_ack() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            // Select here, such that everything ends when the test is over
+            select::select(csma.run(), async {
+                let frame = FrameBuffer::default();
+                radio.inner(|inner| {
+                    inner.assert_nxt.append(
+                        &mut [
+                            TestRadioEvent::PrepareReceive,
+                            // By default we receive
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                            TestRadioEvent::PrepareTransmit,
+                            // Then we get the request to transmit
+                            TestRadioEvent::Transmit,
+                            // After which we go back to receiving normal traffic
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                        ]
+                        .into(),
+                    );
+                });
+                monitor.tx.send_async(frame.clone()).await;
+                radio.wait_until_asserts_are_consumed().await;
+            })
+            .await;
         })
-        .await;
     }
 
-    #[pollster::test]
-    pub async fn test_happy_path_transmit_with_ack() {
-        let radio = TestRadio::default();
-        let mut channel = TestDriverChannel::new();
-        let (driver, monitor) = channel.split();
-        let mut csma = CsmaDevice::new(
-            radio.clone(),
-            rand::thread_rng(),
-            driver,
-            Delay::default(),
-            CsmaConfig::default(),
-        );
+    #[test]
+    pub fn test_happy_path_transmit_with_ack() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
 
-        select::select(csma.run(), async {
-            let sequence_number = 123;
-            let mut f = FrameBuffer::default();
-            let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
-                .set_sequence_number(sequence_number)
-                .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
-                .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
-                .set_dst_pan_id(0xfff)
-                .set_src_pan_id(0xfff)
-                .finalize()
-                .unwrap();
-            // Set ACK to false, such that we can test if it acks
-            frame_repr.frame_control.ack_request = false;
+            select::select(csma.run(), async {
+                let sequence_number = 123;
+                let mut f = FrameBuffer::default();
+                let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(sequence_number)
+                    .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                // Set ACK to false, such that we can test if it acks
+                frame_repr.frame_control.ack_request = false;
 
-            let token = TestTxToken::from(&mut f.buffer[..]);
-            token.consume(frame_repr.buffer_len(), |buf| {
-                let mut frame = DataFrame::new_unchecked(buf);
-                frame_repr.emit(&mut frame);
-            });
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+
+                // Check if frame is correct
+                let frame = TestRadioFrame::new_checked(&f.buffer).unwrap();
+                let _frame = DataFrame::new(frame.data()).unwrap();
 
-            // Check if frame is correct
-            let frame = TestRadioFrame::new_checked(&f.buffer).unwrap();
-            let _frame = DataFrame::new(frame.data()).unwrap();
+                monitor.tx.send_async(f.clone()).await;
+                radio.inner(|inner| {
+                    inner.assert_nxt.clear();
+                    inner.assert_nxt.append(
+                        &mut [
+                            TestRadioEvent::PrepareReceive,
+                            // By default we listen
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                            TestRadioEvent::PrepareTransmit,
+                            // Then we get the request to transmit
+                            TestRadioEvent::Transmit,
+                            TestRadioEvent::PrepareReceive,
+                            // After which we wait for an ACK
+                            TestRadioEvent::Receive,
+                        ]
+                        .into(),
+                    );
+                    inner.total_event_count = 0;
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                frame_repr.frame_control.ack_request = true;
+                radio.inner(|inner| {
+                    // Assert that we have the correct transmitted frame
+                    // The test radio does not keep the length of a frame
+                    let transmitted = inner.last_transmitted.unwrap();
+                    let transmitted = TestRadioFrame::new_checked(&transmitted[..]).unwrap();
+                    let transmitted = &transmitted.data()[..frame_repr.buffer_len()];
+                    assert_frame_eq!(
+                        FrameRepr::parse(&DataFrame::new(transmitted).unwrap()).unwrap(),
+                        frame_repr,
+                        "The transmitted frame should have the ack_request set by default"
+                    );
+
+                    let mut ack_frame = FrameBuffer::default();
+                    let token = TestTxToken::from(&mut ack_frame.buffer[..]);
+                    let ack_repr = FrameBuilder::new_imm_ack(sequence_number)
+                        .finalize()
+                        .unwrap();
+                    token.consume(ack_repr.buffer_len(), |buf| {
+                        let mut frame = DataFrame::new_unchecked(buf);
+                        ack_repr.emit(&mut frame);
+                    });
+                    inner.should_receive = Some(ack_frame.buffer);
+
+                    inner.assert_nxt.append(
+                        &mut [
+                            // At the end, we receive again
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                        ]
+                        .into(),
+                    )
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_happy_path_receive() {
+        block_on(async {
+            let radio = TestRadio::default();
 
-            monitor.tx.send_async(f.clone()).await;
             radio.inner(|inner| {
-                inner.assert_nxt.clear();
                 inner.assert_nxt.append(
                     &mut [
+                        TestRadioEvent::Enable,
                         TestRadioEvent::PrepareReceive,
-                        // By default we listen
-                        TestRadioEvent::Receive,
-                        TestRadioEvent::CancelCurrentOperation,
-                        TestRadioEvent::PrepareTransmit,
-                        // Then we get the request to transmit
-                        TestRadioEvent::Transmit,
-                        TestRadioEvent::PrepareReceive,
-                        // After which we wait for an ACK
                         TestRadioEvent::Receive,
                     ]
                     .into(),
-                );
-                inner.total_event_count = 0;
+                )
             });
-            radio.wait_until_asserts_are_consumed().await;
-            radio.inner(|inner| {
-                // Assert that we have the correct transmitted frame
-                let mut frame = DataFrame::new_unchecked(&mut f.buffer);
-                frame.frame_control_mut().set_ack_request(true);
-                assert_eq!(
-                    inner.last_transmitted,
-                    Some(f.buffer),
-                    "The transmitted frame should have the ack_request set by default"
-                );
 
-                let mut ack_frame = FrameBuffer::default();
-                let token = TestTxToken::from(&mut ack_frame.buffer[..]);
-                let ack_repr = FrameBuilder::new_imm_ack(sequence_number)
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            select::select(csma.run(), async {
+                let sequence_number = 123;
+                let mut f = FrameBuffer::default();
+                let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(sequence_number)
+                    .set_dst_address(Address::Extended(radio.ieee802154_address()))
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_src_pan_id(MAC_PAN_ID)
                     .finalize()
                     .unwrap();
-                token.consume(ack_repr.buffer_len(), |buf| {
+                frame_repr.frame_control.ack_request = true;
+
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
                     let mut frame = DataFrame::new_unchecked(buf);
-                    ack_repr.emit(&mut frame);
+                    frame_repr.emit(&mut frame);
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    inner.should_receive = Some(f.buffer);
+                    inner.assert_nxt.append(
+                        &mut [TestRadioEvent::PrepareTransmit, TestRadioEvent::Transmit].into(),
+                    )
+                });
+                assert_eq!(monitor.rx.receive().await.buffer, f.buffer);
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    assert_eq!(
+                        inner.last_transmitted.map(|frame| {
+                            let frame = TestRadioFrame::new_checked(frame)
+                                .expect("The frame should be a valid TestTxFrame");
+                            let frame =
+                                DataFrame::new(frame.data()).expect("Should be a valid IEEE frame");
+
+                            frame.frame_control().frame_type()
+                        }),
+                        Some(FrameType::Ack),
+                        "An ACK request should return an ACK"
+                    );
+                })
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_receive_pooled() {
+        block_on(async {
+            let radio = TestRadio::default();
+            static POOL: FramePool<2> = FramePool::new();
+            let mut channel = TestDriverChannel::new();
+            channel.rx_pool = Some(&POOL);
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_address(Address::Extended(radio.ieee802154_address()))
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_src_pan_id(MAC_PAN_ID)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
                 });
-                inner.should_receive = Some(ack_frame.buffer);
 
+                radio.inner(|inner| inner.should_receive = Some(f.buffer));
+                let frame = monitor.rx_pooled.receive().await;
+                assert_eq!(frame.buffer, f.buffer);
+                assert!(!monitor.rx.has_item());
+
+                // The next frame is received in the other buffer of the pool
+                yield_now().await;
+                assert_eq!(POOL.available(), 0);
+                drop(frame);
+                assert_eq!(POOL.available(), 1);
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_receive_beacon_notify() {
+        block_on(async {
+            let radio = TestRadio::default();
+
+            radio.inner(|inner| {
                 inner.assert_nxt.append(
                     &mut [
-                        // At the end, we receive again
+                        TestRadioEvent::Enable,
                         TestRadioEvent::PrepareReceive,
                         TestRadioEvent::Receive,
                     ]
                     .into(),
                 )
             });
-            radio.wait_until_asserts_are_consumed().await;
-            assert!(!monitor.errors.has_item(), "No errors should have occurred");
-        })
-        .await;
-    }
 
-    #[pollster::test]
-    pub async fn test_happy_path_receive() {
-        let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
 
-        radio.inner(|inner| {
-            inner.assert_nxt.append(
-                &mut [
-                    TestRadioEvent::Enable,
-                    TestRadioEvent::PrepareReceive,
-                    TestRadioEvent::Receive,
-                ]
-                .into(),
-            )
-        });
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_enhanced_beacon()
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_dst_address(Address::BROADCAST)
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .with_tsch_synchronization(
+                        dot15d4_frame::AbsoluteSlotNumber::new(42).unwrap(),
+                        1,
+                    )
+                    .finalize()
+                    .unwrap();
 
-        let mut channel = TestDriverChannel::new();
-        let (driver, monitor) = channel.split();
-        let mut csma = CsmaDevice::new(
-            radio.clone(),
-            rand::thread_rng(),
-            driver,
-            Delay::default(),
-            CsmaConfig::default(),
-        );
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    inner.should_receive = Some(f.buffer);
+                    inner.rx_lqi = Some(180);
+                });
 
-        select::select(csma.run(), async {
-            let sequence_number = 123;
-            let mut f = FrameBuffer::default();
-            let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
-                .set_sequence_number(sequence_number)
-                .set_dst_address(Address::Extended(radio.ieee802154_address()))
-                .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
-                .set_dst_pan_id(MAC_PAN_ID)
-                .set_src_pan_id(MAC_PAN_ID)
-                .finalize()
+                let indication = monitor.beacons.receive().await;
+                assert_eq!(indication.buffer().buffer, f.buffer);
+
+                let pan_descriptor = indication.pan_descriptor();
+                assert_eq!(
+                    pan_descriptor.coord_address,
+                    Address::Extended([1, 2, 3, 4, 9, 8, 7, 6])
+                );
+                assert_eq!(pan_descriptor.coord_pan_id, MAC_PAN_ID);
+                assert_eq!(pan_descriptor.channel, CsmaConfig::default().channel);
+                assert_eq!(pan_descriptor.link_quality, Some(180));
+                assert_eq!(
+                    pan_descriptor
+                        .tsch
+                        .map(|tsch| tsch.absolute_slot_number.value()),
+                    Some(42)
+                );
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_answer_beacon_requests() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig {
+                    coordinator: true,
+                    ..Default::default()
+                },
+            );
+            let hardware_address = radio.clone().ieee802154_address();
+
+            let receive = |repr: FrameRepr<'_>| {
+                let radio = radio.clone();
+                let mut f = FrameBuffer::default();
+                TestTxToken::from(&mut f.buffer[..]).consume(repr.buffer_len(), |buf| {
+                    repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                async move {
+                    radio.inner(|inner| inner.should_receive = Some(f.buffer));
+                    while radio.inner(|inner| inner.should_receive.is_some()) {
+                        yield_now().await;
+                    }
+                }
+            };
+            let transmitted = || async {
+                match select::select(
+                    async {
+                        loop {
+                            if let Some(transmitted) =
+                                radio.inner(|inner| inner.last_transmitted.take())
+                            {
+                                break transmitted;
+                            }
+                            yield_now().await;
+                        }
+                    },
+                    VirtualDelay::default().delay_ms(100),
+                )
+                .await
+                {
+                    Either::First(transmitted) => {
+                        let frame = TestRadioFrame::new_checked(&transmitted).unwrap();
+                        let sequence_number =
+                            DataFrame::new(frame.data()).unwrap().sequence_number();
+                        let descriptor = PanDescriptor::parse(
+                            frame.data(),
+                            config::Channel::_26,
+                            config::ChannelPage::Page0,
+                            None,
+                        )?;
+                        Some((sequence_number.unwrap(), descriptor))
+                    }
+                    Either::Second(()) => None,
+                }
+            };
+            let enhanced_beacon_request = |link_quality| {
+                FrameBuilder::new_enhanced_beacon_request(EnhancedBeaconFilterRepr {
+                    permit_joining_on: true,
+                    link_quality,
+                    percent_filter: None,
+                })
+                .set_sequence_number(2)
+                .finalize()
+                .unwrap()
+            };
+
+            select::select(csma.run(), async {
+                // A Beacon Request of an active scan is answered with a beacon
+                let request = FrameBuilder::new_beacon_request()
+                    .set_sequence_number(1)
+                    .finalize()
+                    .unwrap();
+                receive(request).await;
+                let (bsn, descriptor) =
+                    transmitted().await.expect("A beacon should be transmitted");
+                assert_eq!(
+                    descriptor.coord_address,
+                    Address::Extended(hardware_address)
+                );
+                assert_eq!(descriptor.coord_pan_id, MAC_PAN_ID);
+                assert!(!descriptor.superframe_spec.unwrap().association_permit);
+
+                // Only coordinators that permit association answer this EBR
+                receive(enhanced_beacon_request(None)).await;
+                assert_eq!(transmitted().await, None);
+
+                monitor
+                    .requests
+                    .send(MacRequest::Set(PibAttribute::MacAssociationPermit(true)));
+                assert_eq!(monitor.confirms.receive().await, MacConfirm::Set(Ok(())));
+                receive(enhanced_beacon_request(None)).await;
+                let (ebsn, descriptor) = transmitted()
+                    .await
+                    .expect("An enhanced beacon should be transmitted");
+                assert_eq!(ebsn, bsn.wrapping_add(1));
+                assert_eq!(
+                    descriptor.coord_address,
+                    Address::Extended(hardware_address)
+                );
+                assert!(descriptor.superframe_spec.unwrap().association_permit);
+
+                // Only coordinators that receive the EBR with at least the
+                // requested link quality answer it
+                receive(enhanced_beacon_request(Some(200))).await;
+                assert_eq!(transmitted().await, None);
+                radio.inner(|inner| inner.rx_lqi = Some(180));
+                receive(enhanced_beacon_request(Some(200))).await;
+                assert_eq!(transmitted().await, None);
+                radio.inner(|inner| inner.rx_lqi = Some(220));
+                receive(enhanced_beacon_request(Some(200))).await;
+                let (ebsn, _) = transmitted()
+                    .await
+                    .expect("An enhanced beacon should be transmitted");
+                assert_eq!(ebsn, bsn.wrapping_add(2));
+
+                // Beacon Requests are not handed to the upper layer
+                assert!(!monitor.rx.has_item());
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_receive_no_ack() {
+        block_on(async {
+            let radio = TestRadio::default();
+
+            radio.inner(|inner| {
+                inner.assert_nxt.append(
+                    &mut [
+                        TestRadioEvent::Enable,
+                        TestRadioEvent::PrepareReceive,
+                        TestRadioEvent::Receive,
+                    ]
+                    .into(),
+                )
+            });
+
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            select::select(csma.run(), async {
+                let sequence_number = 123;
+                let mut f = FrameBuffer::default();
+                let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(sequence_number)
+                    .set_dst_address(Address::Extended(radio.ieee802154_address()))
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_src_pan_id(MAC_PAN_ID)
+                    .finalize()
+                    .unwrap();
+                frame_repr.frame_control.ack_request = false;
+
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    inner.should_receive = Some(f.buffer);
+                    inner.assert_nxt.append(
+                        &mut [TestRadioEvent::PrepareReceive, TestRadioEvent::Receive].into(),
+                    )
+                });
+                assert_eq!(monitor.rx.receive().await.buffer, f.buffer);
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    assert!(
+                        inner.last_transmitted.is_none(),
+                        "If there is not an ACK request, we should not ACK (by default)"
+                    );
+                })
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_wait_for_ack_but_receive_garbage_and_cca_issues() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            // Always back off for the longest period, such that the radio goes
+            // back to receiving during every backoff
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::rngs::mock::StepRng::new(u64::MAX, 0),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            select::select(csma.run(), async {
+                let sequence_number = 123;
+                let mut f = FrameBuffer::default();
+                let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(sequence_number)
+                    .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_src_pan_id(MAC_PAN_ID)
+                    .finalize()
+                    .unwrap();
+                // Set ACK to false, such that we can test if it acks
+                frame_repr.frame_control.ack_request = false;
+
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+
+                // Check if frame is correct
+                let frame = TestRadioFrame::new_checked(&f.buffer).unwrap();
+                let _frame = DataFrame::new(frame.data()).unwrap();
+
+                monitor.tx.send_async(f.clone()).await;
+                radio.inner(|inner| {
+                    inner.assert_nxt.clear();
+                    inner.assert_nxt.append(
+                        &mut [
+                            TestRadioEvent::PrepareReceive,
+                            // By default we receive
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                            TestRadioEvent::PrepareTransmit,
+                            // Then we get a request to transmit
+                            TestRadioEvent::Transmit,
+                            // After which we wait for an ACK
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                        ]
+                        .into(),
+                    );
+                    inner.total_event_count = 0;
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    // Assert that we have the correct transmitted frame
+                    let mut frame = DataFrame::new_unchecked(&mut f.buffer);
+                    frame.frame_control_mut().set_ack_request(true);
+                    assert_eq!(
+                        inner.last_transmitted,
+                        Some(f.buffer),
+                        "The transmitted frame should have the ack_request set by default"
+                    );
+
+                    let mut ack_frame = FrameBuffer::default();
+                    ack_frame.buffer[0] = 42;
+                    ack_frame.buffer[1] = 42;
+                    ack_frame.buffer[2] = 42;
+                    ack_frame.buffer[3] = 42;
+                    inner.should_receive = Some(ack_frame.buffer);
+
+                    inner.cca_fail = true;
+                    inner.assert_nxt.append(
+                        &mut [
+                            TestRadioEvent::PrepareReceive,
+                            // We receive garbage, timer is not yet done
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                        ]
+                        .repeat(3) // magic number corresponds to delay
+                        .into(),
+                    );
+                    inner.assert_nxt.append(
+                        &mut [
+                            // CCA should have failed here
+                            TestRadioEvent::PrepareTransmit,
+                            TestRadioEvent::Transmit,
+                            // We go back to receive to process other messages, until delay
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                            // We go back to receive to process other messages, until delay
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                        ]
+                        .repeat(MAC_MAX_CSMA_BACKOFFS as usize)
+                        .into(),
+                    );
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                assert!(
+                    matches!(
+                        monitor.errors.receive().await,
+                        driver::Error::CcaFailed | driver::Error::CcaBackoff(_), // CCA has failed, so we propagate an error up
+                    ),
+                    "Packet transmission should fail due to CCA"
+                );
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_cca_mode() {
+        block_on(async {
+            // Only an IEEE 802.15.4 signal is present, below the ED threshold
+            let transmit_with = |cca_mode| async move {
+                let radio = TestRadio::default();
+                radio.inner(|inner| inner.carrier_sensed = true);
+                let mut channel = TestDriverChannel::new();
+                let (driver, monitor) = channel.split();
+                let mut csma = CsmaDevice::new(
+                    radio.clone(),
+                    rand::thread_rng(),
+                    driver,
+                    VirtualDelay::default(),
+                    CsmaConfig {
+                        ack_unicast: false,
+                        cca_mode,
+                        ..Default::default()
+                    },
+                );
+
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(123)
+                    .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+
+                match select::select(csma.run(), async {
+                    monitor.tx.send_async(f).await;
+                    select::select(
+                        monitor.errors.receive(),
+                        VirtualDelay::default().delay_ms(100),
+                    )
+                    .await
+                })
+                .await
+                {
+                    Either::First(_) => unreachable!(),
+                    Either::Second(Either::First(error)) => Some(error),
+                    Either::Second(Either::Second(())) => {
+                        assert_eq!(
+                            radio.inner(|inner| inner.requested_cca),
+                            cca_mode,
+                            "The configured CCA mode should be passed to the radio"
+                        );
+                        None
+                    }
+                }
+            };
+
+            assert_eq!(transmit_with(config::CcaMode::None).await, None);
+            assert_eq!(transmit_with(config::CcaMode::EnergyDetection).await, None);
+            assert_eq!(
+                transmit_with(config::CcaMode::EnergyDetectionAndCarrierSense).await,
+                None
+            );
+            assert_eq!(
+                transmit_with(config::CcaMode::CarrierSense).await,
+                Some(driver::Error::CcaBackoff(1))
+            );
+        })
+    }
+
+    #[test]
+    pub fn test_cca_mode_none_without_backoffs() {
+        block_on(async {
+            // The radio reports a busy channel, even though no CCA is done
+            let radio = TestRadio::default();
+            radio.inner(|inner| inner.tx_busy = true);
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig {
+                    ack_unicast: false,
+                    cca_mode: config::CcaMode::None,
+                    ..Default::default()
+                },
+            );
+
+            let mut f = FrameBuffer::default();
+            let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                .set_sequence_number(123)
+                .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
+                .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                .set_dst_pan_id(0xfff)
+                .set_src_pan_id(0xfff)
+                .finalize()
+                .unwrap();
+            TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+            });
+
+            select::select(csma.run(), async {
+                // The transmission fails at once, without a backoff
+                monitor.tx.send_async(f).await;
+                assert_eq!(monitor.errors.receive().await, driver::Error::CcaFailed);
+                radio.inner(|inner| {
+                    let transmissions = inner
+                        .events
+                        .iter()
+                        .filter(|event| **event == TestRadioEvent::Transmit)
+                        .count();
+                    assert_eq!(transmissions, 1);
+                });
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_set_cca_mode() {
+        block_on(async {
+            // Only an IEEE 802.15.4 signal is present, below the ED threshold
+            let radio = TestRadio::default();
+            radio.inner(|inner| inner.carrier_sensed = true);
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig {
+                    ack_unicast: false,
+                    ..Default::default()
+                },
+            );
+            assert_eq!(
+                csma.mlme_get(PibAttributeId::PhyCcaMode),
+                PibAttribute::PhyCcaMode(config::CcaMode::EnergyDetection)
+            );
+
+            let mut f = FrameBuffer::default();
+            let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                .set_sequence_number(123)
+                .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
+                .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                .set_dst_pan_id(0xfff)
+                .set_src_pan_id(0xfff)
+                .finalize()
+                .unwrap();
+            TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+            });
+
+            select::select(csma.run(), async {
+                monitor
+                    .requests
+                    .send(MacRequest::Set(PibAttribute::PhyCcaMode(
+                        config::CcaMode::CarrierSense,
+                    )));
+                assert_eq!(monitor.confirms.receive().await, MacConfirm::Set(Ok(())));
+
+                // The new CCA mode is used for the next transmission
+                monitor.tx.send_async(f).await;
+                assert_eq!(monitor.errors.receive().await, driver::Error::CcaBackoff(1));
+                assert_eq!(
+                    radio.inner(|inner| inner.requested_cca),
+                    config::CcaMode::CarrierSense
+                );
+            })
+            .await;
+
+            assert_eq!(csma.pib().phy().cca_mode, config::CcaMode::CarrierSense);
+        })
+    }
+
+    #[test]
+    pub fn test_transmit_no_ack_received() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            select::select(csma.run(), async {
+                let sequence_number = 123;
+                let mut f = FrameBuffer::default();
+                let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(sequence_number)
+                    .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                // Set ACK to false, such that we can test if it acks
+                frame_repr.frame_control.ack_request = false;
+
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+
+                // Check if frame is correct
+                let frame = TestRadioFrame::new_checked(&f.buffer).unwrap();
+                let _frame = DataFrame::new(frame.data()).unwrap();
+
+                monitor.tx.send_async(f.clone()).await;
+                radio.inner(|inner| {
+                    inner.assert_nxt.clear();
+                    inner.assert_nxt.append(
+                        &mut [
+                            TestRadioEvent::PrepareReceive,
+                            // By default we receive
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                            TestRadioEvent::PrepareTransmit,
+                            // Then we get a request to transmit
+                            TestRadioEvent::Transmit,
+                        ]
+                        .into(),
+                    );
+                    inner.assert_nxt.append(
+                        &mut [
+                            // After which we wait for an ACK, which does not come
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                        ]
+                        .repeat(3)
+                        .into(),
+                    );
+                    inner.total_event_count = 0;
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                assert!(
+                    matches!(
+                        monitor.errors.receive().await,
+                        driver::Error::AckFailed | driver::Error::AckRetry(_), // ACK has failed, so we propagate an error up
+                    ),
+                    "Packet transmission should fail due to ACK not received after to many times"
+                );
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_do_not_ack_by_default_on_broadcast() {
+        block_on(async {
+            let radio = TestRadio::default();
+
+            radio.inner(|inner| {
+                inner.assert_nxt.append(
+                    &mut [
+                        TestRadioEvent::Enable,
+                        TestRadioEvent::PrepareReceive,
+                        TestRadioEvent::Receive,
+                    ]
+                    .into(),
+                )
+            });
+
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            select::select(csma.run(), async {
+                let sequence_number = 123;
+                let mut f = FrameBuffer::default();
+                let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(sequence_number)
+                    .set_dst_address(Address::BROADCAST)
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_src_pan_id(MAC_PAN_ID)
+                    .finalize()
+                    .unwrap();
+                frame_repr.frame_control.ack_request = true; // This should be ignored
+
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    inner.should_receive = Some(f.buffer);
+                    inner.assert_nxt.append(
+                        &mut [TestRadioEvent::PrepareReceive, TestRadioEvent::Receive].into(),
+                    )
+                });
+                assert_eq!(monitor.rx.receive().await.buffer, f.buffer);
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    assert_eq!(
+                        inner.last_transmitted, None,
+                        "No ACK should have been transmitted on a broadcast"
+                    );
+                })
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_resolve_short_address() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let config = CsmaConfig {
+                ack_unicast: false,
+                resolve_short_addresses: true,
+                ..Default::default()
+            };
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                config,
+            );
+            csma.neighbors_mut()
+                .insert(neighbors::Neighbor {
+                    extended_address: [1, 2, 3, 4, 5, 6, 7, 8],
+                    short_address: [0x12, 0x34],
+                    tx_power: None,
+                })
+                .unwrap();
+
+            let emit = |dst_address| {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(123)
+                    .set_dst_address(dst_address)
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+                f
+            };
+
+            select::select(csma.run(), async {
+                radio.inner(|inner| {
+                    inner.assert_nxt.append(
+                        &mut [
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                            TestRadioEvent::PrepareTransmit,
+                            TestRadioEvent::Transmit,
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                        ]
+                        .into(),
+                    );
+                });
+                monitor
+                    .tx
+                    .send_async(emit(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8])))
+                    .await;
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    assert_eq!(
+                        inner.last_transmitted,
+                        Some(emit(Address::Short([0x12, 0x34])).buffer),
+                        "The destination address should have been resolved to the short address"
+                    );
+                });
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_neighbor_tx_power() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let config = CsmaConfig {
+                ack_unicast: false,
+                ..Default::default()
+            };
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                config,
+            );
+            csma.mlme_set(PibAttribute::PhyTxPower(3)).unwrap();
+            csma.neighbors_mut()
+                .insert(neighbors::Neighbor {
+                    extended_address: [1, 2, 3, 4, 5, 6, 7, 8],
+                    short_address: [0x12, 0x34],
+                    tx_power: Some(-12),
+                })
+                .unwrap();
+
+            let emit = |dst_address| {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(123)
+                    .set_dst_address(dst_address)
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+                f
+            };
+
+            let expect_transmission = || {
+                radio.inner(|inner| {
+                    inner.assert_nxt.append(
+                        &mut [
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                            TestRadioEvent::CancelCurrentOperation,
+                            TestRadioEvent::PrepareTransmit,
+                            TestRadioEvent::Transmit,
+                            TestRadioEvent::PrepareReceive,
+                            TestRadioEvent::Receive,
+                        ]
+                        .into(),
+                    );
+                });
+            };
+
+            select::select(csma.run(), async {
+                expect_transmission();
+                monitor
+                    .tx
+                    .send_async(emit(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8])))
+                    .await;
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    assert_eq!(
+                        inner.last_tx_power,
+                        Some(-12),
+                        "The transmit power of the neighbor should be used"
+                    );
+                });
+
+                expect_transmission();
+                monitor
+                    .tx
+                    .send_async(emit(Address::Extended([8, 7, 6, 5, 4, 3, 2, 1])))
+                    .await;
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    assert_eq!(
+                        inner.last_tx_power,
+                        Some(3),
+                        "The transmit power of the PIB should be used"
+                    );
+                });
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_neighbor_heard() {
+        block_on(async {
+            let radio = TestRadio::default();
+            radio.inner(|inner| {
+                inner.assert_nxt.append(
+                    &mut [
+                        TestRadioEvent::Enable,
+                        TestRadioEvent::PrepareReceive,
+                        TestRadioEvent::Receive,
+                    ]
+                    .into(),
+                )
+            });
+
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            let neighbor = |n: u8| neighbors::Neighbor {
+                extended_address: [1, 2, 3, 4, 9, 8, 7, n],
+                short_address: [0x00, n],
+                tx_power: None,
+            };
+            for n in 0..MAC_NEIGHBOR_TABLE_SIZE as u8 {
+                assert_eq!(csma.neighbors_mut().insert(neighbor(n)), Ok(None));
+            }
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(123)
+                    .set_dst_address(Address::BROADCAST)
+                    .set_src_address(Address::Short([0x00, 0x00]))
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_src_pan_id(MAC_PAN_ID)
+                    .finalize()
+                    .unwrap();
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    inner.should_receive = Some(f.buffer);
+                    inner.assert_nxt.append(
+                        &mut [TestRadioEvent::PrepareReceive, TestRadioEvent::Receive].into(),
+                    )
+                });
+                assert_eq!(monitor.rx.receive().await.buffer, f.buffer);
+            })
+            .await;
+
+            // The first neighbor was heard, so the second one is evicted
+            assert_eq!(
+                csma.neighbors_mut().insert(neighbor(0xff)),
+                Ok(Some(neighbor(1)))
+            );
+            assert_eq!(
+                csma.neighbors()
+                    .short_address(&neighbor(0).extended_address),
+                Some([0x00, 0x00])
+            );
+        })
+    }
+
+    #[test]
+    pub fn test_channel_override() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer {
+                    channel: Some(config::Channel::_11),
+                    ..FrameBuffer::default()
+                };
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_address(Address::Short([0x00, 0x02]))
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                radio.on_transmit(|frame| {
+                    TestRadioResponse::ack(frame).delayed(Duration::from_us(100))
+                });
+
+                // The frame is transmitted on its own channel, and its ACK
+                // received on it
+                monitor.tx.send_async(f).await;
+                assert_eq!(
+                    monitor.confirms.receive().await,
+                    MacConfirm::Data(DataConfirm {
+                        handle: None,
+                        sequence_number: Some(1),
+                        status: DataStatus::Success,
+                    })
+                );
+                radio.inner(|inner| assert_eq!(inner.last_tx_channel, Some(config::Channel::_11)));
+
+                // Afterwards, frames are received on the channel of the PIB
+                while radio.inner(|inner| inner.last_rx_channel) != Some(config::Channel::_26) {
+                    yield_now().await;
+                }
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_no_ack_option() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer {
+                    no_ack: true,
+                    ..FrameBuffer::default()
+                };
+                let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_address(Address::Short([0x00, 0x02]))
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                frame_repr.frame_control.ack_request = true; // This should be ignored
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                radio.on_transmit(|_| TestRadioResponse::Drop);
+
+                // The unicast frame is confirmed without waiting for an ACK
+                monitor.tx.send_async(f).await;
+                assert_eq!(
+                    monitor.confirms.receive().await,
+                    MacConfirm::Data(DataConfirm {
+                        handle: None,
+                        sequence_number: Some(1),
+                        status: DataStatus::Success,
+                    })
+                );
+                radio.inner(|inner| {
+                    let transmitted = inner.last_transmitted.unwrap();
+                    let frame = DataFrame::new(&transmitted[..127]).unwrap();
+                    assert!(!frame.frame_control().ack_request());
+                    assert!(inner.scripts.is_empty());
+                });
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_repeat_broadcast() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let config = CsmaConfig {
+                ack_broadcast: true,
+                broadcast_retransmissions: 2,
+                ..Default::default()
+            };
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                config,
+            );
+            csma.groups_mut().join([0x80, 0x01]).unwrap();
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(123)
+                    .set_dst_address(Address::Short([0x80, 0x01]))
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_src_pan_id(MAC_PAN_ID)
+                    .finalize()
+                    .unwrap();
+                let token = TestTxToken::from(&mut f.buffer[..]);
+                token.consume(frame_repr.buffer_len(), |buf| {
+                    let mut frame = DataFrame::new_unchecked(buf);
+                    frame_repr.emit(&mut frame);
+                });
+
+                monitor.tx.send_async(f.clone()).await;
+
+                // The frame is transmitted once, and repeated twice
+                let transmissions = || {
+                    radio.inner(|inner| {
+                        inner
+                            .events
+                            .iter()
+                            .filter(|event| **event == TestRadioEvent::Transmit)
+                            .count()
+                    })
+                };
+                match select::select(
+                    async {
+                        while transmissions() < 3 {
+                            yield_now().await;
+                        }
+                    },
+                    VirtualDelay::default().delay_ms(5000),
+                )
+                .await
+                {
+                    Either::First(()) => {}
+                    Either::Second(()) => panic!("The broadcast was not repeated"),
+                }
+                VirtualDelay::default().delay_ms(10).await;
+                assert_eq!(transmissions(), 3);
+
+                radio.inner(|inner| {
+                    let transmitted = inner.last_transmitted.unwrap();
+                    let frame = TestRadioFrame::new_checked(&transmitted).unwrap();
+                    let frame = DataFrame::new(frame.data()).unwrap();
+                    assert!(
+                        !frame.frame_control().ack_request(),
+                        "Repeated groupcast frames should not request an ACK"
+                    );
+                });
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_poll() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+            let hardware_address = radio.clone().ieee802154_address();
+
+            let request = MacRequest::Poll(PollRequest {
+                coord_address: Address::Short([0x00, 0x01]),
+                coord_pan_id: MAC_PAN_ID,
+            });
+
+            // Acknowledge the Data Request command, once it is transmitted
+            let acknowledge = |frame_pending| {
+                let radio = radio.clone();
+                async move {
+                    let sequence_number = loop {
+                        let transmitted = radio.inner(|inner| inner.last_transmitted.take());
+                        if let Some(transmitted) = transmitted {
+                            let frame = TestRadioFrame::new_checked(&transmitted).unwrap();
+                            let frame = DataFrame::new(frame.data()).unwrap();
+                            assert_eq!(frame.frame_control().frame_type(), FrameType::MacCommand);
+                            assert!(frame.frame_control().ack_request());
+                            assert_eq!(
+                                frame.addressing().and_then(|fields| fields.src_address()),
+                                Some(Address::Extended(hardware_address))
+                            );
+                            break frame.sequence_number().unwrap();
+                        }
+                        yield_now().await;
+                    };
+
+                    let mut ack_frame = FrameBuffer::default();
+                    let ack_repr = FrameBuilder::new_imm_ack(sequence_number)
+                        .finalize()
+                        .unwrap();
+                    TestTxToken::from(&mut ack_frame.buffer[..]).consume(
+                        ack_repr.buffer_len(),
+                        |buf| {
+                            let mut frame = DataFrame::new_unchecked(buf);
+                            ack_repr.emit(&mut frame);
+                            frame.frame_control_mut().set_frame_pending(frame_pending);
+                        },
+                    );
+                    radio.inner(|inner| inner.should_receive = Some(ack_frame.buffer));
+                    while radio.inner(|inner| inner.should_receive.is_some()) {
+                        yield_now().await;
+                    }
+                    sequence_number
+                }
+            };
+            let data_frame = |src_address| {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(42)
+                    .set_dst_address(Address::Extended(hardware_address))
+                    .set_src_address(src_address)
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_src_pan_id(MAC_PAN_ID)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                f.buffer
+            };
+
+            let confirm = || async {
+                match select::select(
+                    monitor.confirms.receive(),
+                    VirtualDelay::default().delay_ms(100),
+                )
+                .await
+                {
+                    Either::First(confirm) => confirm,
+                    Either::Second(()) => panic!("The poll request was not confirmed"),
+                }
+            };
+
+            select::select(csma.run(), async {
+                // The coordinator has no data pending
+                monitor.requests.send(request);
+                let dsn = acknowledge(false).await;
+                assert_eq!(confirm().await, MacConfirm::Poll(PollStatus::NoData));
+
+                // The coordinator has data pending, which is sent after the ACK
+                monitor.requests.send(request);
+                assert_eq!(acknowledge(true).await, dsn.wrapping_add(1));
+
+                // Data from another device does not end the poll
+                let other = data_frame(Address::Short([0x00, 0x02]));
+                radio.inner(|inner| inner.should_receive = Some(other));
+                assert_eq!(monitor.rx.receive().await.buffer, other);
+                assert!(!monitor.confirms.has_item());
+
+                let data = data_frame(Address::Short([0x00, 0x01]));
+                radio.inner(|inner| inner.should_receive = Some(data));
+
+                assert_eq!(confirm().await, MacConfirm::Poll(PollStatus::Success));
+                assert_eq!(monitor.rx.receive().await.buffer, data);
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_rx_off_when_idle() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let config = CsmaConfig {
+                ack_unicast: false,
+                ..Default::default()
+            };
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                config,
+            );
+            csma.mlme_set(PibAttribute::MacRxOnWhenIdle(false)).unwrap();
+
+            radio.inner(|inner| {
+                inner.assert_nxt.append(
+                    &mut [
+                        TestRadioEvent::Enable,
+                        // The radio sleeps while idle, instead of receiving
+                        TestRadioEvent::Disable,
+                        // Only wake up to transmit
+                        TestRadioEvent::Enable,
+                        TestRadioEvent::PrepareTransmit,
+                        TestRadioEvent::Transmit,
+                        TestRadioEvent::Disable,
+                    ]
+                    .into(),
+                );
+            });
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(123)
+                    .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
+                    .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+
+                VirtualDelay::default().delay_ms(10).await;
+                monitor.tx.send_async(f).await;
+                radio.wait_until_asserts_are_consumed().await;
+
+                VirtualDelay::default().delay_ms(10).await;
+                radio.inner(|inner| {
+                    assert!(
+                        !inner.events.contains(&TestRadioEvent::Receive),
+                        "The receiver should never have been enabled"
+                    );
+                    assert_eq!(inner.events.last(), Some(&TestRadioEvent::Disable));
+                });
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_set_while_running() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            let rx_channel = |channel| {
+                let radio = radio.clone();
+                async move {
+                    match select::select(
+                        async {
+                            while radio.inner(|inner| inner.last_rx_channel) != Some(channel) {
+                                yield_now().await;
+                            }
+                        },
+                        VirtualDelay::default().delay_ms(1000),
+                    )
+                    .await
+                    {
+                        Either::First(()) => {}
+                        Either::Second(()) => panic!("The radio did not receive on {channel:?}"),
+                    }
+                }
+            };
+
+            select::select(csma.run(), async {
+                rx_channel(config::Channel::_26).await;
+
+                // The ongoing receive is restarted on the new channel
+                monitor
+                    .requests
+                    .send(MacRequest::Set(PibAttribute::PhyCurrentChannel(
+                        config::Channel::_11,
+                    )));
+                assert_eq!(monitor.confirms.receive().await, MacConfirm::Set(Ok(())));
+                rx_channel(config::Channel::_11).await;
+
+                // Frames for the new PAN ID are accepted
+                monitor
+                    .requests
+                    .send(MacRequest::Set(PibAttribute::MacPanId(0x1234)));
+                assert_eq!(monitor.confirms.receive().await, MacConfirm::Set(Ok(())));
+
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_address(Address::BROADCAST)
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0x1234)
+                    .set_src_pan_id(0x1234)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                radio.inner(|inner| inner.should_receive = Some(f.buffer));
+                assert_eq!(monitor.rx.receive().await.buffer, f.buffer);
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_reset() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let initial_pib = PibBuilder::new()
+                .pan_id(0x4321)
+                .channel(config::Channel::_15)
+                .build()
                 .unwrap();
-            frame_repr.frame_control.ack_request = true;
+            let mut csma = CsmaDevice::new_with_pib(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+                NoCrypto,
+                initial_pib,
+            )
+            .unwrap();
+            csma.mlme_set(PibAttribute::MacPanId(0x1234)).unwrap();
+            csma.mlme_set(PibAttribute::PhyCurrentChannel(config::Channel::_11))
+                .unwrap();
+            *csma.frame_counter_mut() = 7;
+
+            let fill_tables = |csma: &mut CsmaDevice<_, _, _, _>| {
+                csma.neighbors_mut()
+                    .insert(neighbors::Neighbor {
+                        extended_address: [1; 8],
+                        short_address: [0x00, 0x01],
+                        tx_power: None,
+                    })
+                    .unwrap();
+                csma.groups_mut().join([0x80, 0x01]).unwrap();
+                csma.devices_mut()
+                    .insert(devices::Device::new([1; 8], None))
+                    .unwrap();
+                csma.keys_mut()
+                    .insert(keys::Key {
+                        id: KeyId::Index(1),
+                        key: [0; 16],
+                    })
+                    .unwrap();
+            };
+            fill_tables(&mut csma);
+
+            let restarts = || {
+                radio.inner(|inner| {
+                    inner
+                        .events
+                        .iter()
+                        .filter(|event| **event == TestRadioEvent::Disable)
+                        .count()
+                })
+            };
+
+            select::select(csma.run(), async {
+                // The radio is restarted, while the PIB is kept
+                monitor.requests.send(MacRequest::Reset(ResetRequest {
+                    set_default_pib: false,
+                }));
+                assert_eq!(monitor.confirms.receive().await, MacConfirm::Reset);
+                assert_eq!(restarts(), 1);
+                yield_now().await;
+                assert_eq!(
+                    radio.inner(|inner| inner.last_rx_channel),
+                    Some(config::Channel::_11)
+                );
+            })
+            .await;
+
+            // The neighbor and group tables are cleared, the security tables
+            // are part of the PIB
+            assert!(csma.neighbors().is_empty());
+            assert!(csma.groups().is_empty());
+            assert!(!csma.devices_mut().is_empty());
+            assert!(!csma.keys().is_empty());
+            fill_tables(&mut csma);
+
+            select::select(csma.run(), async {
+                monitor.requests.send(MacRequest::Reset(ResetRequest {
+                    set_default_pib: true,
+                }));
+                assert_eq!(monitor.confirms.receive().await, MacConfirm::Reset);
+                assert_eq!(restarts(), 2);
+            })
+            .await;
+
+            // The PIB the device was created with is restored, except for the
+            // outgoing frame counter, and all tables are cleared
+            let pib = csma.pib();
+            assert_eq!(pib.mac_pan_id, 0x4321);
+            assert_eq!(pib.phy_current_channel, config::Channel::_15);
+            assert_eq!(pib.mac_extended_address, Some(radio.ieee802154_address()));
+            assert_eq!(pib.mac_frame_counter, 7);
+            assert!(csma.neighbors().is_empty());
+            assert!(csma.groups().is_empty());
+            assert!(csma.devices_mut().is_empty());
+            assert!(csma.keys().is_empty());
+        })
+    }
+
+    #[test]
+    pub fn test_data_confirm() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            let frame = |sequence_number, dst_address| {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(sequence_number)
+                    .set_dst_address(dst_address)
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                f
+            };
+
+            select::select(csma.run(), async {
+                // Broadcast frames are not acknowledged
+                monitor.tx.send_async(frame(1, Address::BROADCAST)).await;
+                assert_eq!(
+                    monitor.confirms.receive().await,
+                    MacConfirm::Data(DataConfirm {
+                        handle: None,
+                        sequence_number: Some(1),
+                        status: DataStatus::Success,
+                    })
+                );
+
+                // Unicast frames are, but nobody answers. The handle of the
+                // frame is returned in its confirmation.
+                monitor
+                    .tx
+                    .send_async(FrameBuffer {
+                        handle: Some(FrameHandle(7)),
+                        ..frame(2, Address::Short([0x00, 0x02]))
+                    })
+                    .await;
+                assert_eq!(
+                    monitor.confirms.receive().await,
+                    MacConfirm::Data(DataConfirm {
+                        handle: Some(FrameHandle(7)),
+                        sequence_number: Some(2),
+                        status: DataStatus::NoAck,
+                    })
+                );
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_scripted_retransmissions() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            // Every retransmission is the same frame, of which the ACK is
+            // lost or corrupted, until the last one is acknowledged
+            let retransmission = |frame: &[u8]| {
+                let frame = DataFrame::new(frame).unwrap();
+                assert_eq!(frame.sequence_number(), Some(7));
+                assert!(frame.frame_control().ack_request());
+            };
+            radio.on_transmit(move |frame| {
+                retransmission(frame);
+                TestRadioResponse::Drop
+            });
+            radio.on_transmit(move |frame| {
+                retransmission(frame);
+                TestRadioResponse::corrupted_ack(frame)
+            });
+            radio.on_transmit(move |frame| {
+                retransmission(frame);
+                TestRadioResponse::ack(frame).delayed(Duration::from_us(100))
+            });
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(7)
+                    .set_dst_address(Address::Short([0x00, 0x02]))
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+
+                monitor.tx.send_async(f).await;
+                assert_eq!(
+                    monitor.confirms.receive().await,
+                    MacConfirm::Data(DataConfirm {
+                        handle: None,
+                        sequence_number: Some(7),
+                        status: DataStatus::Success,
+                    })
+                );
+                radio.inner(|inner| assert!(inner.scripts.is_empty()));
+                // The ACK was timestamped on reception
+                assert!(radio.rx_timestamp().is_some());
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_radio_failure_is_not_retried() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+            radio.inner(|inner| inner.hw_error = true);
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(7)
+                    .set_dst_address(Address::Short([0x00, 0x02]))
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+
+                monitor.tx.send_async(f).await;
+                // The failure is reported without any CCA backoff
+                assert_eq!(monitor.errors.receive().await, driver::Error::RadioError);
+                assert_eq!(
+                    monitor.confirms.receive().await,
+                    MacConfirm::Data(DataConfirm {
+                        handle: None,
+                        sequence_number: Some(7),
+                        status: DataStatus::RadioFailure,
+                    })
+                );
+                radio.inner(|inner| {
+                    let transmissions = inner
+                        .events
+                        .iter()
+                        .filter(|event| **event == TestRadioEvent::Transmit)
+                        .count();
+                    assert_eq!(transmissions, 1);
+                });
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_mac_timings() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut config = CsmaConfig::default();
+            config.timings.sifs = Duration::from_us(50_000);
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                config,
+            );
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_address(Address::Short([0x00, 0x02]))
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+
+                // SIFS is waited for after every unacknowledged transmission
+                let start = virtual_now();
+                monitor.tx.send_async(f).await;
+                monitor.confirms.receive().await;
+                assert!(
+                    virtual_now() - start
+                        >= crate::time::Instant::from_us(50_000 * MAC_MAX_FRAME_RETIES as i64)
+                );
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_csma_attributes() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig {
+                    max_frame_retries: 5,
+                    ..Default::default()
+                },
+            );
+            assert_eq!(csma.pib().mac_max_frame_retries, 5);
+            assert_eq!(
+                csma.mlme_set(PibAttribute::MacMaxFrameRetries(8)),
+                Err(PibError::InvalidParameter)
+            );
+            assert_eq!(
+                csma.mlme_set(PibAttribute::MacMaxBe(2)),
+                Err(PibError::InvalidParameter)
+            );
+            csma.mlme_set(PibAttribute::MacMaxFrameRetries(0)).unwrap();
+            csma.mlme_set(PibAttribute::MacMaxCsmaBackoffs(0)).unwrap();
+            csma.mlme_set(PibAttribute::MacMaxBe(3)).unwrap();
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_address(Address::Short([0x00, 0x02]))
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+
+                // The frame is not retransmitted when no ACK is received
+                monitor.tx.send_async(f).await;
+                assert_eq!(monitor.errors.receive().await, driver::Error::AckFailed);
+                assert_eq!(
+                    monitor.confirms.receive().await,
+                    MacConfirm::Data(DataConfirm {
+                        handle: None,
+                        sequence_number: Some(1),
+                        status: DataStatus::NoAck,
+                    })
+                );
+                radio.inner(|inner| {
+                    let transmissions = inner
+                        .events
+                        .iter()
+                        .filter(|event| **event == TestRadioEvent::Transmit)
+                        .count();
+                    assert_eq!(transmissions, 1);
+                });
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_phy_stats() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            select::select(csma.run(), async {
+                // Not a valid IEEE 802.15.4 frame
+                radio.inner(|inner| inner.should_receive = Some([0xff; 128]));
+                while radio.inner(|inner| inner.should_receive.is_some()) {
+                    yield_now().await;
+                }
+
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_address(Address::BROADCAST)
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                monitor.tx.send_async(f).await;
+                monitor.confirms.receive().await;
+            })
+            .await;
+
+            assert_eq!(
+                csma.phy_stats(),
+                PhyStats {
+                    rx_frames: 1,
+                    rx_rejected: 1,
+                    rx_errors: 0,
+                    tx_attempts: 1,
+                    tx_errors: 0,
+                }
+            );
+        })
+    }
+
+    #[test]
+    pub fn test_stop_and_restart() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+
+            // The device stops running and disables the radio
+            monitor.stop.send(());
+            match select::select(csma.run(), VirtualDelay::default().delay_ms(1000)).await {
+                Either::First(()) => {}
+                Either::Second(()) => panic!("The CSMA device did not stop"),
+            }
+            radio.inner(|inner| {
+                assert_eq!(inner.events.last(), Some(&TestRadioEvent::Disable));
+            });
+
+            // And can be run again afterwards
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_address(Address::BROADCAST)
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xfff)
+                    .set_src_pan_id(0xfff)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                monitor.tx.send_async(f.clone()).await;
+
+                match select::select(
+                    async {
+                        while radio.inner(|inner| inner.last_transmitted.is_none()) {
+                            yield_now().await;
+                        }
+                    },
+                    VirtualDelay::default().delay_ms(1000),
+                )
+                .await
+                {
+                    Either::First(()) => {}
+                    Either::Second(()) => panic!("The frame was not transmitted after restarting"),
+                }
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[cfg(feature = "software-crypto")]
+    #[test]
+    pub fn test_receive_replayed_frame() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new_with_crypto(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+                SoftwareCrypto,
+            );
+            csma.devices_mut()
+                .insert(devices::Device::new([1; 8], None))
+                .unwrap();
+            csma.keys_mut()
+                .insert(keys::Key {
+                    id: KeyId::Index(1),
+                    key: [0; 16],
+                })
+                .unwrap();
+
+            // A data frame from [1; 8], with key index 1
+            let dst_address = Address::Extended(radio.ieee802154_address());
+            let frame_repr = |frame_counter| {
+                FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_dst_address(dst_address)
+                    .set_src_address(Address::Extended([1; 8]))
+                    .set_auxiliary_security_header(AuxiliarySecurityHeaderRepr {
+                        security_level: SecurityAttributes::EncMic32,
+                        frame_counter: Some(frame_counter),
+                        asn_in_nonce: false,
+                        key_identifier: KeyIdentifierRepr::Index(1),
+                    })
+                    .finalize()
+                    .unwrap()
+            };
+            let secured_frame = || secured_frame_buffer(&frame_repr(5), &[0; 16], &[1; 8]);
+
+            // A forged frame, with the highest frame counter, secured with
+            // another key
+            let forged_frame = secured_frame_buffer(&frame_repr(u32::MAX - 1), &[1; 16], &[1; 8]);
+
+            let monitor = &monitor;
+            let expect_error = move |error| async move {
+                match select::select(
+                    monitor.errors.receive(),
+                    VirtualDelay::default().delay_ms(100),
+                )
+                .await
+                {
+                    Either::First(err) => assert_eq!(err, driver::Error::SecurityFailed(error)),
+                    Either::Second(()) => panic!("The frame should have been rejected"),
+                }
+                assert!(!monitor.rx.has_item(), "The frame should be dropped");
+            };
 
-            let token = TestTxToken::from(&mut f.buffer[..]);
-            token.consume(frame_repr.buffer_len(), |buf| {
-                let mut frame = DataFrame::new_unchecked(buf);
-                frame_repr.emit(&mut frame);
-            });
-            radio.wait_until_asserts_are_consumed().await;
-            radio.inner(|inner| {
-                inner.should_receive = Some(f.buffer);
-                inner
-                    .assert_nxt
-                    .append(&mut [TestRadioEvent::PrepareTransmit, TestRadioEvent::Transmit].into())
-            });
-            assert_eq!(monitor.rx.receive().await.buffer, f.buffer);
-            radio.wait_until_asserts_are_consumed().await;
-            radio.inner(|inner| {
+            select::select(csma.run(), async {
+                // The forged frame does not update the incoming frame counter
+                radio.inner(|inner| inner.should_receive = Some(forged_frame.buffer));
+                expect_error(SecurityError::TransformFailed).await;
+                assert!(!monitor.frame_counters.has_item());
+
+                // The frame is received decrypted, without its MIC
+                radio.inner(|inner| inner.should_receive = Some(secured_frame().buffer));
+                assert_eq!(
+                    frame_data(&monitor.rx.receive().await.buffer),
+                    frame_bytes(&frame_repr(5))
+                );
                 assert_eq!(
-                    inner.last_transmitted.map(|frame| {
-                        let frame = TestRadioFrame::new_checked(frame)
-                            .expect("The frame should be a valid TestTxFrame");
-                        let frame =
-                            DataFrame::new(frame.data()).expect("Should be a valid IEEE frame");
-
-                        frame.frame_control().frame_type()
-                    }),
-                    Some(FrameType::Ack),
-                    "An ACK request should return an ACK"
+                    monitor.frame_counters.receive().await,
+                    devices::IncomingFrameCounter {
+                        extended_address: [1; 8],
+                        key: KeyId::Index(1),
+                        frame_counter: 6,
+                    }
                 );
+
+                // The same frame is replayed
+                radio.inner(|inner| inner.should_receive = Some(secured_frame().buffer));
+                expect_error(SecurityError::CounterError).await;
             })
+            .await;
         })
-        .await;
     }
 
-    #[pollster::test]
-    pub async fn test_receive_no_ack() {
-        let radio = TestRadio::default();
-
-        radio.inner(|inner| {
-            inner.assert_nxt.append(
-                &mut [
-                    TestRadioEvent::Enable,
-                    TestRadioEvent::PrepareReceive,
-                    TestRadioEvent::Receive,
-                ]
-                .into(),
-            )
-        });
+    #[cfg(feature = "software-crypto")]
+    #[test]
+    pub fn test_forged_frame_not_heard() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new_with_crypto(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+                SoftwareCrypto,
+            );
+            csma.devices_mut()
+                .insert(devices::Device::new([1; 8], None))
+                .unwrap();
+            csma.keys_mut()
+                .insert(keys::Key {
+                    id: KeyId::Index(1),
+                    key: [0; 16],
+                })
+                .unwrap();
 
-        let mut channel = TestDriverChannel::new();
-        let (driver, monitor) = channel.split();
-        let mut csma = CsmaDevice::new(
-            radio.clone(),
-            rand::thread_rng(),
-            driver,
-            Delay::default(),
-            CsmaConfig::default(),
-        );
+            let neighbor = |n: u8| neighbors::Neighbor {
+                extended_address: [1, 1, 1, 1, 1, 1, 1, n],
+                short_address: [0x00, n],
+                tx_power: None,
+            };
+            for n in 1..=MAC_NEIGHBOR_TABLE_SIZE as u8 {
+                assert_eq!(csma.neighbors_mut().insert(neighbor(n)), Ok(None));
+            }
 
-        select::select(csma.run(), async {
-            let sequence_number = 123;
-            let mut f = FrameBuffer::default();
-            let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
-                .set_sequence_number(sequence_number)
-                .set_dst_address(Address::Extended(radio.ieee802154_address()))
-                .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+            // A data frame from the first neighbor, secured with another key
+            let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                .set_sequence_number(1)
                 .set_dst_pan_id(MAC_PAN_ID)
-                .set_src_pan_id(MAC_PAN_ID)
+                .set_dst_address(Address::Extended(radio.ieee802154_address()))
+                .set_src_address(Address::Extended([1; 8]))
+                .set_auxiliary_security_header(AuxiliarySecurityHeaderRepr {
+                    security_level: SecurityAttributes::EncMic32,
+                    frame_counter: Some(5),
+                    asn_in_nonce: false,
+                    key_identifier: KeyIdentifierRepr::Index(1),
+                })
                 .finalize()
                 .unwrap();
-            frame_repr.frame_control.ack_request = false;
+            let forged_frame = secured_frame_buffer(&frame_repr, &[1; 16], &[1; 8]);
 
-            let token = TestTxToken::from(&mut f.buffer[..]);
-            token.consume(frame_repr.buffer_len(), |buf| {
-                let mut frame = DataFrame::new_unchecked(buf);
-                frame_repr.emit(&mut frame);
-            });
-            radio.wait_until_asserts_are_consumed().await;
-            radio.inner(|inner| {
-                inner.should_receive = Some(f.buffer);
-                inner
-                    .assert_nxt
-                    .append(&mut [TestRadioEvent::PrepareReceive, TestRadioEvent::Receive].into())
-            });
-            assert_eq!(monitor.rx.receive().await.buffer, f.buffer);
-            radio.wait_until_asserts_are_consumed().await;
-            radio.inner(|inner| {
-                assert!(
-                    inner.last_transmitted.is_none(),
-                    "If there is not an ACK request, we should not ACK (by default)"
+            select::select(csma.run(), async {
+                radio.inner(|inner| inner.should_receive = Some(forged_frame.buffer));
+                assert_eq!(
+                    monitor.errors.receive().await,
+                    driver::Error::SecurityFailed(SecurityError::TransformFailed)
                 );
             })
+            .await;
+
+            // The forged frame did not refresh the first neighbor, so it is
+            // evicted
+            assert_eq!(
+                csma.neighbors_mut().insert(neighbor(0xff)),
+                Ok(Some(neighbor(1)))
+            );
         })
-        .await;
     }
 
-    #[pollster::test]
-    pub async fn test_wait_for_ack_but_receive_garbage_and_cca_issues() {
-        let radio = TestRadio::default();
-        let mut channel = TestDriverChannel::new();
-        let (driver, monitor) = channel.split();
-        let mut csma = CsmaDevice::new(
-            radio.clone(),
-            rand::thread_rng(),
-            driver,
-            Delay::default(),
-            CsmaConfig::default(),
-        );
+    /// An unsecured frame from the upper layer, selecting the key with index
+    /// `key_index` in its Auxiliary Security Header.
+    fn unsecured_frame_repr(key_index: u8, frame_counter: u32) -> FrameRepr<'static> {
+        FrameBuilder::new_data(&[1, 2, 3, 4])
+            .set_sequence_number(123)
+            .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
+            .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
+            .set_dst_pan_id(0xfff)
+            .set_src_pan_id(0xfff)
+            .set_auxiliary_security_header(AuxiliarySecurityHeaderRepr {
+                security_level: SecurityAttributes::EncMic32,
+                frame_counter: Some(frame_counter),
+                asn_in_nonce: false,
+                key_identifier: KeyIdentifierRepr::ShortSource([0, 0, 0, 1], key_index),
+            })
+            .finalize()
+            .unwrap()
+    }
 
-        select::select(csma.run(), async {
-            let sequence_number = 123;
-            let mut f = FrameBuffer::default();
-            let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
-                .set_sequence_number(sequence_number)
-                .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
-                .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
-                .set_dst_pan_id(MAC_PAN_ID)
-                .set_src_pan_id(MAC_PAN_ID)
-                .finalize()
+    #[cfg(feature = "software-crypto")]
+    #[test]
+    pub fn test_transmit_key_selection() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let config = CsmaConfig {
+                ack_unicast: false,
+                ..Default::default()
+            };
+            let mut csma = CsmaDevice::new_with_crypto(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                config,
+                SoftwareCrypto,
+            );
+            // The next key is installed before the current one is retired
+            for index in [1, 2] {
+                csma.keys_mut()
+                    .insert(keys::Key {
+                        id: KeyId::ShortSource([0, 0, 0, 1], index),
+                        key: [index; 16],
+                    })
+                    .unwrap();
+            }
+            *csma.frame_counter_mut() = 10;
+            let own_address = radio.ieee802154_address();
+
+            let unsecured_frame = |key_index| {
+                let mut f = FrameBuffer::default();
+                let frame_repr = unsecured_frame_repr(key_index, 0);
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                f
+            };
+
+            select::select(csma.run(), async {
+                // There is no key with index 3
+                monitor.tx.send_async(unsecured_frame(3)).await;
+                assert_eq!(
+                    monitor.errors.receive().await,
+                    driver::Error::SecurityFailed(SecurityError::UnavailableKey)
+                );
+                assert_eq!(
+                    monitor.confirms.receive().await,
+                    MacConfirm::Data(DataConfirm {
+                        handle: None,
+                        sequence_number: Some(123),
+                        status: DataStatus::UnavailableKey,
+                    })
+                );
+                assert!(radio.inner(|inner| inner.last_transmitted.is_none()));
+
+                // The frames are secured with the selected key and the next
+                // frame counter, which was not used by the rejected frame
+                for (key_index, frame_counter) in [(1, 10), (2, 11)] {
+                    monitor.tx.send_async(unsecured_frame(key_index)).await;
+                    while radio.inner(|inner| inner.last_transmitted.is_none()) {
+                        yield_now().await;
+                    }
+                    let expected = secured_frame_buffer(
+                        &unsecured_frame_repr(key_index, frame_counter),
+                        &[key_index; 16],
+                        &own_address,
+                    );
+                    let transmitted = radio.inner(|inner| inner.last_transmitted.take()).unwrap();
+                    assert_eq!(frame_data(&transmitted), frame_data(&expected.buffer));
+                    assert_eq!(
+                        monitor.confirms.receive().await,
+                        MacConfirm::Data(DataConfirm {
+                            handle: None,
+                            sequence_number: Some(123),
+                            status: DataStatus::Success,
+                        })
+                    );
+                }
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    /// Counts the CCM* transforms of [`SoftwareCrypto`].
+    #[cfg(feature = "software-crypto")]
+    #[derive(Default, Clone)]
+    struct CountingCrypto {
+        encrypted: std::rc::Rc<Cell<usize>>,
+        decrypted: std::rc::Rc<Cell<usize>>,
+    }
+
+    #[cfg(feature = "software-crypto")]
+    impl CryptoBackend for CountingCrypto {
+        fn aes128_ecb(&self, key: &[u8; 16], block: &mut [u8; 16]) -> Result<(), SecurityError> {
+            SoftwareCrypto.aes128_ecb(key, block)
+        }
+
+        fn ccm_star_encrypt(
+            &self,
+            key: &[u8; 16],
+            nonce: &[u8; 13],
+            a_data: &[u8],
+            m_data: &mut [u8],
+            mic: &mut [u8],
+        ) -> Result<(), SecurityError> {
+            self.encrypted.set(self.encrypted.get() + 1);
+            SoftwareCrypto.ccm_star_encrypt(key, nonce, a_data, m_data, mic)
+        }
+
+        fn ccm_star_decrypt(
+            &self,
+            key: &[u8; 16],
+            nonce: &[u8; 13],
+            a_data: &[u8],
+            m_data: &mut [u8],
+            mic: &[u8],
+        ) -> Result<(), SecurityError> {
+            self.decrypted.set(self.decrypted.get() + 1);
+            SoftwareCrypto.ccm_star_decrypt(key, nonce, a_data, m_data, mic)
+        }
+    }
+
+    #[cfg(feature = "software-crypto")]
+    #[test]
+    pub fn test_crypto_backend_data_frames() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let config = CsmaConfig {
+                ack_unicast: false,
+                ..Default::default()
+            };
+            let crypto = CountingCrypto::default();
+            let mut csma = CsmaDevice::new_with_crypto(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                config,
+                crypto.clone(),
+            );
+            let own_address = radio.ieee802154_address();
+            csma.keys_mut()
+                .insert(keys::Key {
+                    id: KeyId::Index(1),
+                    key: [1; 16],
+                })
                 .unwrap();
-            // Set ACK to false, such that we can test if it acks
-            frame_repr.frame_control.ack_request = false;
+            csma.devices_mut()
+                .insert(devices::Device::new(own_address, None))
+                .unwrap();
+            *csma.frame_counter_mut() = 3;
 
-            let token = TestTxToken::from(&mut f.buffer[..]);
-            token.consume(frame_repr.buffer_len(), |buf| {
-                let mut frame = DataFrame::new_unchecked(buf);
-                frame_repr.emit(&mut frame);
-            });
+            // A frame to ourselves, such that it can be received back
+            let frame_repr = |frame_counter| {
+                FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(1)
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_dst_address(Address::Extended(own_address))
+                    .set_src_address(Address::Extended(own_address))
+                    .set_auxiliary_security_header(AuxiliarySecurityHeaderRepr {
+                        security_level: SecurityAttributes::EncMic64,
+                        frame_counter: Some(frame_counter),
+                        asn_in_nonce: false,
+                        key_identifier: KeyIdentifierRepr::Index(1),
+                    })
+                    .finalize()
+                    .unwrap()
+            };
+
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr(0).buffer_len(), |buf| {
+                    frame_repr(0).emit(&mut DataFrame::new_unchecked(buf));
+                });
+
+                // The data frame is secured by the backend
+                monitor.tx.send_async(f).await;
+                while radio.inner(|inner| inner.last_transmitted.is_none()) {
+                    yield_now().await;
+                }
+                let transmitted = radio.inner(|inner| inner.last_transmitted.take()).unwrap();
+                assert_eq!(crypto.encrypted.get(), 1);
+                assert_ne!(frame_data(&transmitted), frame_bytes(&frame_repr(3)));
+
+                // And unsecured by the backend when it is received
+                radio.inner(|inner| inner.should_receive = Some(transmitted));
+                assert_eq!(
+                    frame_data(&monitor.rx.receive().await.buffer),
+                    frame_bytes(&frame_repr(3))
+                );
+                assert_eq!(crypto.decrypted.get(), 1);
+                assert!(!monitor.errors.has_item(), "No errors should have occurred");
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_transmit_secured_without_crypto() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
+            csma.keys_mut()
+                .insert(keys::Key {
+                    id: KeyId::ShortSource([0, 0, 0, 1], 1),
+                    key: [1; 16],
+                })
+                .unwrap();
 
-            // Check if frame is correct
-            let frame = TestRadioFrame::new_checked(&f.buffer).unwrap();
-            let _frame = DataFrame::new(frame.data()).unwrap();
+            select::select(csma.run(), async {
+                let mut f = FrameBuffer::default();
+                let frame_repr = unsecured_frame_repr(1, 0);
+                TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                    frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+
+                // The frame is not sent in plaintext when it can not be secured
+                monitor.tx.send_async(f).await;
+                assert_eq!(
+                    monitor.errors.receive().await,
+                    driver::Error::SecurityFailed(SecurityError::TransformFailed)
+                );
+                assert_eq!(
+                    monitor.confirms.receive().await,
+                    MacConfirm::Data(DataConfirm {
+                        handle: None,
+                        sequence_number: Some(123),
+                        status: DataStatus::UnsupportedSecurity,
+                    })
+                );
+                assert!(radio.inner(|inner| inner.last_transmitted.is_none()));
+            })
+            .await;
+        })
+    }
 
-            monitor.tx.send_async(f.clone()).await;
+    #[cfg(feature = "software-crypto")]
+    #[test]
+    pub fn test_secured_enhanced_ack() {
+        block_on(async {
+            let radio = TestRadio::default();
             radio.inner(|inner| {
-                inner.assert_nxt.clear();
                 inner.assert_nxt.append(
                     &mut [
-                        TestRadioEvent::PrepareReceive,
-                        // By default we receive
-                        TestRadioEvent::Receive,
-                        TestRadioEvent::CancelCurrentOperation,
-                        TestRadioEvent::PrepareTransmit,
-                        // Then we get a request to transmit
-                        TestRadioEvent::Transmit,
-                        // After which we wait for an ACK
+                        TestRadioEvent::Enable,
                         TestRadioEvent::PrepareReceive,
                         TestRadioEvent::Receive,
                     ]
                     .into(),
-                );
-                inner.total_event_count = 0;
+                )
             });
-            radio.wait_until_asserts_are_consumed().await;
-            radio.inner(|inner| {
-                // Assert that we have the correct transmitted frame
-                let mut frame = DataFrame::new_unchecked(&mut f.buffer);
-                frame.frame_control_mut().set_ack_request(true);
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new_with_crypto(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+                SoftwareCrypto,
+            );
+            csma.keys_mut()
+                .insert(keys::Key {
+                    id: KeyId::Index(1),
+                    key: [0; 16],
+                })
+                .unwrap();
+            csma.devices_mut()
+                .insert(devices::Device::new([1; 8], None))
+                .unwrap();
+            *csma.frame_counter_mut() = 7;
+
+            // A secured IEEE 802.15.4-2020 frame, requesting an ACK
+            let own_address = radio.ieee802154_address();
+            let frame_repr = |key_index| {
+                let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(42)
+                    .set_dst_address(Address::Extended(own_address))
+                    .set_src_address(Address::Extended([1; 8]))
+                    .set_dst_pan_id(MAC_PAN_ID)
+                    .set_src_pan_id(MAC_PAN_ID)
+                    .set_auxiliary_security_header(AuxiliarySecurityHeaderRepr {
+                        security_level: SecurityAttributes::EncMic32,
+                        frame_counter: None,
+                        asn_in_nonce: false,
+                        key_identifier: KeyIdentifierRepr::Index(key_index),
+                    })
+                    .finalize()
+                    .unwrap();
+                // Suppressing the frame counter made it an IEEE 802.15.4-2020
+                // frame, but the frame counter is needed to unsecure it
+                if let Some(aux) = frame_repr.auxiliary_security_header.as_mut() {
+                    aux.frame_counter = Some(3);
+                }
+                frame_repr.frame_control.ack_request = true;
+                frame_repr
+            };
+
+            let (frame, unknown_key_frame) = (
+                secured_frame_buffer(&frame_repr(1), &[0; 16], &[1; 8]),
+                secured_frame_buffer(&frame_repr(2), &[0; 16], &[1; 8]),
+            );
+            // A forged frame, secured with another key
+            let forged_frame = secured_frame_buffer(&frame_repr(1), &[1; 16], &[1; 8]);
+
+            select::select(csma.run(), async {
+                radio.wait_until_asserts_are_consumed().await;
+
+                // A forged frame is not acknowledged, and does not use up a
+                // frame counter value, such that the Enh-ACK below still uses 7
+                radio.inner(|inner| inner.should_receive = Some(forged_frame.buffer));
                 assert_eq!(
-                    inner.last_transmitted,
-                    Some(f.buffer),
-                    "The transmitted frame should have the ack_request set by default"
+                    monitor.errors.receive().await,
+                    driver::Error::SecurityFailed(SecurityError::TransformFailed)
                 );
+                VirtualDelay::default().delay_ms(10).await;
+                radio.inner(|inner| assert!(inner.last_transmitted.is_none()));
 
-                let mut ack_frame = FrameBuffer::default();
-                ack_frame.buffer[0] = 42;
-                ack_frame.buffer[1] = 42;
-                ack_frame.buffer[2] = 42;
-                ack_frame.buffer[3] = 42;
-                inner.should_receive = Some(ack_frame.buffer);
+                radio.inner(|inner| {
+                    inner.should_receive = Some(frame.buffer);
+                    inner.assert_nxt.append(
+                        &mut [TestRadioEvent::PrepareTransmit, TestRadioEvent::Transmit].into(),
+                    )
+                });
+                assert_eq!(
+                    frame_data(&monitor.rx.receive().await.buffer),
+                    frame_bytes(&frame_repr(1))
+                );
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    let ack = inner
+                        .last_transmitted
+                        .expect("An Enh-ACK should be transmitted");
+                    let mut data = frame_data(&ack).to_vec();
+                    let ack = DataFrame::new(frame_data(&ack)).unwrap();
+                    assert_eq!(ack.frame_control().frame_type(), FrameType::Ack);
+                    assert_eq!(ack.sequence_number(), Some(42));
+                    assert_eq!(
+                        ack.addressing().and_then(|fields| fields.dst_address()),
+                        Some(Address::Extended([1; 8]))
+                    );
+                    assert_eq!(
+                        AuxiliarySecurityHeaderRepr::parse(
+                            &ack.auxiliary_security_header().unwrap()
+                        )
+                        .unwrap(),
+                        AuxiliarySecurityHeaderRepr {
+                            security_level: SecurityAttributes::EncMic32,
+                            frame_counter: Some(7),
+                            asn_in_nonce: false,
+                            key_identifier: KeyIdentifierRepr::Index(1),
+                        }
+                    );
+                    // The MIC is computed by the crypto backend, with the
+                    // nonce of this device
+                    let parts = ack.ccm_parts(&own_address).unwrap();
+                    let (a_data, m_data, mic) = parts.split_mut(&mut data);
+                    assert_eq!(
+                        SoftwareCrypto.ccm_star_decrypt(
+                            &[0; 16],
+                            &parts.nonce,
+                            a_data,
+                            m_data,
+                            mic
+                        ),
+                        Ok(())
+                    );
+                    inner.last_transmitted = None;
+                });
 
-                inner.cca_fail = true;
-                inner.assert_nxt.append(
-                    &mut [
-                        TestRadioEvent::PrepareReceive,
-                        // We receive garbage, timer is not yet done
-                        TestRadioEvent::Receive,
-                        TestRadioEvent::CancelCurrentOperation,
-                    ]
-                    .repeat(3) // magic number corresponds to delay
-                    .into(),
+                // No ACK can be secured with a key that is not installed
+                radio.inner(|inner| inner.should_receive = Some(unknown_key_frame.buffer));
+                assert_eq!(
+                    monitor.errors.receive().await,
+                    driver::Error::SecurityFailed(SecurityError::UnavailableKey)
                 );
+                VirtualDelay::default().delay_ms(10).await;
+                radio.inner(|inner| assert!(inner.last_transmitted.is_none()));
+            })
+            .await;
+        })
+    }
+
+    #[test]
+    pub fn test_enhanced_ack_without_sequence_number() {
+        block_on(async {
+            let radio = TestRadio::default();
+            radio.inner(|inner| {
                 inner.assert_nxt.append(
                     &mut [
-                        // CCA should have failed here
-                        TestRadioEvent::PrepareTransmit,
-                        TestRadioEvent::Transmit,
-                        // We go back to receive to process other messages, until delay
-                        TestRadioEvent::PrepareReceive,
-                        TestRadioEvent::Receive,
-                        TestRadioEvent::CancelCurrentOperation,
-                        // We go back to receive to process other messages, until delay
+                        TestRadioEvent::Enable,
                         TestRadioEvent::PrepareReceive,
                         TestRadioEvent::Receive,
-                        TestRadioEvent::CancelCurrentOperation,
                     ]
-                    .repeat(MAC_MAX_CSMA_BACKOFFS as usize - 1)
                     .into(),
-                );
+                )
             });
-            radio.wait_until_asserts_are_consumed().await;
-            assert!(
-                matches!(
-                    monitor.errors.receive().await,
-                    driver::Error::CcaFailed | driver::Error::CcaBackoff(_), // CCA has failed, so we propagate an error up
-                ),
-                "Packet transmission should fail due to CCA"
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
             );
+
+            // An IEEE 802.15.4-2020 frame without sequence number, requesting
+            // an ACK
+            let mut frame = FrameBuffer::default();
+            let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                .suppress_sequence_number()
+                .set_dst_address(Address::Extended(radio.ieee802154_address()))
+                .set_src_address(Address::Extended([1; 8]))
+                .set_dst_pan_id(MAC_PAN_ID)
+                .set_src_pan_id(MAC_PAN_ID)
+                .finalize()
+                .unwrap();
+            frame_repr.frame_control.ack_request = true;
+            TestTxToken::from(&mut frame.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+            });
+
+            select::select(csma.run(), async {
+                radio.wait_until_asserts_are_consumed().await;
+                radio.inner(|inner| {
+                    inner.should_receive = Some(frame.buffer);
+                    inner.assert_nxt.append(
+                        &mut [TestRadioEvent::PrepareTransmit, TestRadioEvent::Transmit].into(),
+                    )
+                });
+                assert_eq!(monitor.rx.receive().await.buffer, frame.buffer);
+                radio.wait_until_asserts_are_consumed().await;
+
+                let expected = MinimalEnhancedAck {
+                    sequence_number: None,
+                    dst_pan_id: MAC_PAN_ID,
+                    dst_address: Address::Extended([1; 8]),
+                    frame_pending: false,
+                };
+                let mut buffer = [0; acknowledgment::MAX_MINIMAL_ENHANCED_ACK_LEN];
+                expected.emit(&mut buffer);
+                radio.inner(|inner| {
+                    let ack = inner
+                        .last_transmitted
+                        .expect("An Enh-ACK should be transmitted");
+                    assert_eq!(
+                        ack[..expected.buffer_len()],
+                        buffer[..expected.buffer_len()]
+                    );
+                });
+            })
+            .await;
         })
-        .await;
     }
 
-    #[pollster::test]
-    pub async fn test_transmit_no_ack_received() {
-        let radio = TestRadio::default();
-        let mut channel = TestDriverChannel::new();
-        let (driver, monitor) = channel.split();
-        let mut csma = CsmaDevice::new(
-            radio.clone(),
-            rand::thread_rng(),
-            driver,
-            Delay::default(),
-            CsmaConfig::default(),
-        );
+    #[test]
+    pub fn test_enhanced_ack_addressing() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channel = TestDriverChannel::new();
+            let (driver, monitor) = channel.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig::default(),
+            );
 
-        select::select(csma.run(), async {
-            let sequence_number = 123;
-            let mut f = FrameBuffer::default();
+            // A frame from another PAN, to the broadcast PAN ID
+            let mut frame = FrameBuffer::default();
             let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
-                .set_sequence_number(sequence_number)
-                .set_dst_address(Address::Extended([1, 2, 3, 4, 5, 6, 7, 8]))
-                .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
-                .set_dst_pan_id(0xfff)
-                .set_src_pan_id(0xfff)
+                .suppress_sequence_number()
+                .set_dst_address(Address::Extended(radio.ieee802154_address()))
+                .set_src_address(Address::Short([0x00, 0x01]))
+                .set_dst_pan_id(BROADCAST_PAN_ID)
+                .set_src_pan_id(0x1234)
                 .finalize()
                 .unwrap();
-            // Set ACK to false, such that we can test if it acks
-            frame_repr.frame_control.ack_request = false;
-
-            let token = TestTxToken::from(&mut f.buffer[..]);
-            token.consume(frame_repr.buffer_len(), |buf| {
-                let mut frame = DataFrame::new_unchecked(buf);
-                frame_repr.emit(&mut frame);
+            frame_repr.frame_control.ack_request = true;
+            TestTxToken::from(&mut frame.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+                frame_repr.emit(&mut DataFrame::new_unchecked(buf));
             });
 
-            // Check if frame is correct
-            let frame = TestRadioFrame::new_checked(&f.buffer).unwrap();
-            let _frame = DataFrame::new(frame.data()).unwrap();
+            select::select(csma.run(), async {
+                radio.inner(|inner| inner.should_receive = Some(frame.buffer));
+                assert_eq!(monitor.rx.receive().await.buffer, frame.buffer);
+                while radio.inner(|inner| inner.last_transmitted.is_none()) {
+                    yield_now().await;
+                }
 
-            monitor.tx.send_async(f.clone()).await;
-            radio.inner(|inner| {
-                inner.assert_nxt.clear();
-                inner.assert_nxt.append(
-                    &mut [
-                        TestRadioEvent::PrepareReceive,
-                        // By default we receive
-                        TestRadioEvent::Receive,
-                        TestRadioEvent::CancelCurrentOperation,
-                        TestRadioEvent::PrepareTransmit,
-                        // Then we get a request to transmit
-                        TestRadioEvent::Transmit,
-                    ]
-                    .into(),
-                );
-                inner.assert_nxt.append(
-                    &mut [
-                        // After which we wait for an ACK, which does not come
-                        TestRadioEvent::PrepareReceive,
-                        TestRadioEvent::Receive,
-                        TestRadioEvent::CancelCurrentOperation,
-                    ]
-                    .repeat(3)
-                    .into(),
-                );
-                inner.total_event_count = 0;
-            });
-            radio.wait_until_asserts_are_consumed().await;
-            assert!(
-                matches!(
-                    monitor.errors.receive().await,
-                    driver::Error::AckFailed | driver::Error::AckRetry(_), // ACK has failed, so we propagate an error up
-                ),
-                "Packet transmission should fail due to ACK not received after to many times"
-            );
+                // The Enh-ACK is sent to the originator, in its own PAN
+                let expected = MinimalEnhancedAck {
+                    sequence_number: None,
+                    dst_pan_id: 0x1234,
+                    dst_address: Address::Short([0x00, 0x01]),
+                    frame_pending: false,
+                };
+                let mut buffer = [0; acknowledgment::MAX_MINIMAL_ENHANCED_ACK_LEN];
+                expected.emit(&mut buffer);
+                radio.inner(|inner| {
+                    let ack = inner.last_transmitted.unwrap();
+                    assert_eq!(
+                        ack[..expected.buffer_len()],
+                        buffer[..expected.buffer_len()]
+                    );
+                });
+            })
+            .await;
         })
-        .await;
     }
 
-    #[pollster::test]
-    pub async fn test_do_not_ack_by_default_on_broadcast() {
-        let radio = TestRadio::default();
-
-        radio.inner(|inner| {
-            inner.assert_nxt.append(
-                &mut [
-                    TestRadioEvent::Enable,
-                    TestRadioEvent::PrepareReceive,
-                    TestRadioEvent::Receive,
-                ]
-                .into(),
+    #[test]
+    pub fn test_rx_filter() {
+        type Device<'a> =
+            CsmaDevice<TestRadio, rand::rngs::ThreadRng, TestDriver<'a>, VirtualDelay>;
+        let hardware_address = [1, 2, 3, 4, 5, 6, 7, 8];
+        let mut pib = PibBuilder::new()
+            .pan_id(0xabcd)
+            .short_address(0x0708)
+            .build()
+            .unwrap();
+        let accepts = |pib: &Pib, repr: &FrameRepr<'_>| {
+            let mut buffer = [0; 127];
+            let len = repr.buffer_len();
+            repr.emit(&mut DataFrame::new_unchecked(&mut buffer[..len]));
+            Device::is_package_for_us(
+                &hardware_address,
+                pib,
+                &[],
+                &DataFrame::new(&buffer[..len]).unwrap(),
             )
-        });
+        };
+        let data = |dst_pan_id, dst_address| {
+            FrameBuilder::new_data(&[1, 2, 3])
+                .set_sequence_number(1)
+                .set_dst_pan_id(dst_pan_id)
+                .set_dst_address(dst_address)
+                .set_src_pan_id(0xabcd)
+                .set_src_address(Address::Short([0x00, 0x01]))
+                .finalize()
+                .unwrap()
+        };
 
-        let mut channel = TestDriverChannel::new();
-        let (driver, monitor) = channel.split();
-        let mut csma = CsmaDevice::new(
-            radio.clone(),
-            rand::thread_rng(),
-            driver,
-            Delay::default(),
-            CsmaConfig::default(),
-        );
+        assert!(accepts(&pib, &data(0xabcd, Address::Short([7, 8]))));
+        assert!(!accepts(&pib, &data(0xabcd, Address::Short([7, 9]))));
+        assert!(accepts(&pib, &data(0xabcd, Address::BROADCAST)));
+        // The broadcast PAN ID is accepted, other PANs are not
+        assert!(accepts(&pib, &data(0xffff, Address::BROADCAST)));
+        assert!(accepts(
+            &pib,
+            &data(0xffff, Address::Extended(hardware_address))
+        ));
+        assert!(!accepts(&pib, &data(0x1234, Address::BROADCAST)));
 
-        select::select(csma.run(), async {
-            let sequence_number = 123;
-            let mut f = FrameBuffer::default();
-            let mut frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
-                .set_sequence_number(sequence_number)
-                .set_dst_address(Address::BROADCAST)
-                .set_src_address(Address::Extended([1, 2, 3, 4, 9, 8, 7, 6]))
-                .set_dst_pan_id(MAC_PAN_ID)
-                .set_src_pan_id(MAC_PAN_ID)
+        // Frames to our addresses in another PAN are not for us
+        assert!(!accepts(&pib, &data(0x1234, Address::Short([7, 8]))));
+        assert!(!accepts(
+            &pib,
+            &data(0x1234, Address::Extended(hardware_address))
+        ));
+        let mut other_pan = pib;
+        other_pan.set(PibAttribute::MacPanId(0x1234)).unwrap();
+        assert!(accepts(&other_pan, &data(0x1234, Address::Short([7, 8]))));
+        assert!(!accepts(&other_pan, &data(0xabcd, Address::Short([7, 8]))));
+
+        // The short address is not derived from the extended address
+        let mut unassigned = pib;
+        unassigned
+            .set(PibAttribute::MacShortAddress(0xfffe))
+            .unwrap();
+        assert!(!accepts(&unassigned, &data(0xabcd, Address::Short([7, 8]))));
+        assert!(!accepts(
+            &unassigned,
+            &data(0xabcd, Address::Short([0xff, 0xfe]))
+        ));
+        assert!(accepts(
+            &unassigned,
+            &data(0xabcd, Address::Extended(hardware_address))
+        ));
+
+        // Without destination addressing, only with macImplicitBroadcast
+        let implicit = FrameBuilder::new_data(&[1, 2, 3])
+            .set_src_pan_id(0xabcd)
+            .set_src_address(Address::Short([0x00, 0x01]))
+            .finalize()
+            .unwrap();
+        assert!(!accepts(&pib, &implicit));
+        pib.set(PibAttribute::MacImplicitBroadcast(true)).unwrap();
+        assert!(accepts(&pib, &implicit));
+
+        // Beacons of other PANs are only accepted when not in a PAN
+        let beacon = FrameBuilder::new_beacon()
+            .set_sequence_number(1)
+            .set_src_pan_id(0x1234)
+            .set_src_address(Address::Short([0x00, 0x01]))
+            .finalize()
+            .unwrap();
+        assert!(!accepts(&pib, &beacon));
+        pib.set(PibAttribute::MacPanId(0xffff)).unwrap();
+        assert!(accepts(&pib, &beacon));
+    }
+
+    #[test]
+    pub fn test_worst_case_latency() {
+        let phy = PhyParameters::O_QPSK_2450;
+        let mut config = CsmaConfig::default();
+        let frame = |dst_address| {
+            FrameBuilder::new_data(&[1, 2, 3, 4])
+                .set_sequence_number(1)
+                .set_dst_pan_id(0xabcd)
+                .set_dst_address(dst_address)
+                .set_src_pan_id(0xabcd)
+                .set_src_address(Address::Short([0x00, 0x01]))
                 .finalize()
-                .unwrap();
-            frame_repr.frame_control.ack_request = true; // This should be ignored
+                .unwrap()
+        };
 
-            let token = TestTxToken::from(&mut f.buffer[..]);
-            token.consume(frame_repr.buffer_len(), |buf| {
-                let mut frame = DataFrame::new_unchecked(buf);
-                frame_repr.emit(&mut frame);
-            });
-            radio.wait_until_asserts_are_consumed().await;
-            radio.inner(|inner| {
-                inner.should_receive = Some(f.buffer);
-                inner
-                    .assert_nxt
-                    .append(&mut [TestRadioEvent::PrepareReceive, TestRadioEvent::Receive].into())
-            });
-            assert_eq!(monitor.rx.receive().await.buffer, f.buffer);
-            radio.wait_until_asserts_are_consumed().await;
-            radio.inner(|inner| {
-                assert_eq!(
-                    inner.last_transmitted, None,
-                    "No ACK should have been transmitted on a broadcast"
-                );
-            })
-        })
-        .await;
+        // 5 CCAs of 320us, 26 unit backoff periods of 320us, and a frame of
+        // 15 octets
+        let attempt = Duration::from_us(5 * 320 + 26 * 320 + 21 * 32);
+        assert_eq!(
+            config.worst_case_latency(&phy, &frame(Address::BROADCAST), &[]),
+            attempt
+        );
+        config.broadcast_retransmissions = 1;
+        assert_eq!(
+            config.worst_case_latency(&phy, &frame(Address::BROADCAST), &[]),
+            attempt + config.timings.lifs + config.broadcast_jitter + attempt
+        );
+        // Frames to a joined group are retransmitted like broadcast frames
+        assert_eq!(
+            config.worst_case_latency(&phy, &frame(Address::Short([0x80, 0x01])), &[[0x80, 0x01]]),
+            attempt + config.timings.lifs + config.broadcast_jitter + attempt
+        );
+
+        // Every attempt waits for the ACK and SIFS
+        assert_eq!(
+            config.worst_case_latency(&phy, &frame(Address::Short([0x00, 0x02])), &[]),
+            (attempt + Duration::from_us(2288 + 1000)) * 4
+        );
+
+        // 2 CCAs and 1 unit backoff period, without retransmissions
+        config.min_be = 1;
+        config.max_be = 1;
+        config.max_csma_backoffs = 1;
+        config.max_frame_retries = 0;
+        let attempt = Duration::from_us(2 * 320 + 320 + 21 * 32);
+        assert_eq!(
+            config.worst_case_latency(&phy, &frame(Address::Short([0x00, 0x02])), &[]),
+            attempt + Duration::from_us(2288 + 1000)
+        );
+
+        // Without CCA, there are no backoffs
+        config.cca_mode = config::CcaMode::None;
+        let attempt = Duration::from_us(320 + 21 * 32);
+        assert_eq!(
+            config.worst_case_latency(&phy, &frame(Address::Short([0x00, 0x02])), &[]),
+            attempt + Duration::from_us(2288 + 1000)
+        );
     }
 }
@@ -0,0 +1,361 @@
+//! A small, fixed-size table of known neighbors.
+//!
+//! The table maps the extended address of a neighbor onto the short address
+//! it was assigned, such that frames towards that neighbor can use the short
+//! addressing mode. Optionally, a transmit power can be stored per neighbor.
+//!
+//! When the table is full, a new neighbor either is rejected, or replaces the
+//! neighbor that was heard the least recently, depending on the
+//! [`EvictionPolicy`]. Neighbors that must never be evicted, like the time
+//! source or the parent of the device, are pinned with [`NeighborTable::pin`].
+
+use core::cell::Cell;
+
+use dot15d4_frame::Address;
+
+/// A neighbor of which both the extended and the short address are known.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Neighbor {
+    /// The extended address of the neighbor, in the same byte order as
+    /// [`Address::Extended`](dot15d4_frame::Address::Extended).
+    pub extended_address: [u8; 8],
+    /// The short address of the neighbor, in the same byte order as
+    /// [`Address::Short`](dot15d4_frame::Address::Short).
+    pub short_address: [u8; 2],
+    /// The transmit power used for frames towards this neighbor, in dBm. When
+    /// `None`, `phyTxPower` from the PIB is used.
+    pub tx_power: Option<i8>,
+}
+
+/// What [`NeighborTable::insert`] does when the table is full.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum EvictionPolicy {
+    /// The new neighbor is rejected.
+    #[default]
+    Never,
+    /// The neighbor that was heard the least recently, and that is not pinned,
+    /// is replaced by the new neighbor.
+    LeastRecentlyHeard,
+}
+
+#[derive(Debug)]
+struct Entry {
+    neighbor: Neighbor,
+    pinned: bool,
+    /// The value of [`NeighborTable::clock`] when the neighbor was last heard.
+    last_heard: Cell<u32>,
+}
+
+/// A table holding at most `N` [`Neighbor`]s.
+#[derive(Debug)]
+pub struct NeighborTable<const N: usize> {
+    entries: [Option<Entry>; N],
+    policy: EvictionPolicy,
+    /// Counts the neighbors being heard, ordering them by recency.
+    clock: Cell<u32>,
+}
+
+impl<const N: usize> Default for NeighborTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> NeighborTable<N> {
+    /// Create a new empty [`NeighborTable`], which rejects new neighbors
+    /// when full.
+    pub const fn new() -> Self {
+        Self::with_eviction_policy(EvictionPolicy::Never)
+    }
+
+    /// Create a new empty [`NeighborTable`] with the given [`EvictionPolicy`].
+    pub const fn with_eviction_policy(policy: EvictionPolicy) -> Self {
+        Self {
+            entries: [const { None }; N],
+            policy,
+            clock: Cell::new(0),
+        }
+    }
+
+    /// Return the [`EvictionPolicy`] of the table.
+    pub fn eviction_policy(&self) -> EvictionPolicy {
+        self.policy
+    }
+
+    /// Set the [`EvictionPolicy`] of the table.
+    pub fn set_eviction_policy(&mut self, policy: EvictionPolicy) {
+        self.policy = policy;
+    }
+
+    /// Insert or update a neighbor in the table. The neighbor counts as
+    /// heard, and an updated neighbor stays pinned.
+    ///
+    /// Returns the evicted neighbor when the table was full, see
+    /// [`EvictionPolicy`], or the neighbor back when it is full and no
+    /// neighbor can be evicted.
+    pub fn insert(&mut self, neighbor: Neighbor) -> Result<Option<Neighbor>, Neighbor> {
+        let now = self.tick();
+
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .flatten()
+            .find(|entry| entry.neighbor.extended_address == neighbor.extended_address)
+        {
+            entry.neighbor = neighbor;
+            entry.last_heard.set(now);
+            return Ok(None);
+        }
+
+        let new = Entry {
+            neighbor,
+            pinned: false,
+            last_heard: Cell::new(now),
+        };
+
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.is_none()) {
+            *entry = Some(new);
+            return Ok(None);
+        }
+
+        if self.policy == EvictionPolicy::Never {
+            return Err(neighbor);
+        }
+
+        // The age is the distance to the clock, such that wrapping around is
+        // harmless.
+        match self
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.as_ref().is_some_and(|entry| !entry.pinned))
+            .max_by_key(|entry| {
+                entry
+                    .as_ref()
+                    .map_or(0, |entry| now.wrapping_sub(entry.last_heard.get()))
+            }) {
+            Some(entry) => Ok(entry.replace(new).map(|evicted| evicted.neighbor)),
+            None => Err(neighbor),
+        }
+    }
+
+    /// Remove the neighbor with the given extended address from the table,
+    /// even when it is pinned.
+    pub fn remove(&mut self, extended_address: &[u8; 8]) -> Option<Neighbor> {
+        self.entries
+            .iter_mut()
+            .find(|entry| {
+                entry
+                    .as_ref()
+                    .is_some_and(|entry| &entry.neighbor.extended_address == extended_address)
+            })
+            .and_then(|entry| entry.take())
+            .map(|entry| entry.neighbor)
+    }
+
+    /// Pin the neighbor with the given extended address, such that it is
+    /// never evicted. Returns `false` when the neighbor is not known.
+    pub fn pin(&mut self, extended_address: &[u8; 8]) -> bool {
+        self.set_pinned(extended_address, true)
+    }
+
+    /// Unpin the neighbor with the given extended address, see
+    /// [`NeighborTable::pin`]. Returns `false` when the neighbor is not known.
+    pub fn unpin(&mut self, extended_address: &[u8; 8]) -> bool {
+        self.set_pinned(extended_address, false)
+    }
+
+    /// Returns `true` when the neighbor with the given extended address is
+    /// pinned.
+    pub fn is_pinned(&self, extended_address: &[u8; 8]) -> bool {
+        self.entry(&Address::Extended(*extended_address))
+            .is_some_and(|entry| entry.pinned)
+    }
+
+    /// Mark the neighbor with the given address as heard, e.g. when a frame
+    /// is received from it. Returns `false` when the neighbor is not known.
+    pub fn heard(&self, address: &Address) -> bool {
+        match self.entry(address) {
+            Some(entry) => {
+                entry.last_heard.set(self.tick());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return the short address of the neighbor with the given extended
+    /// address, if known.
+    pub fn short_address(&self, extended_address: &[u8; 8]) -> Option<[u8; 2]> {
+        self.iter()
+            .find(|entry| &entry.extended_address == extended_address)
+            .map(|entry| entry.short_address)
+    }
+
+    /// Return the transmit power for frames towards the given address, if
+    /// known.
+    pub fn tx_power(&self, address: &Address) -> Option<i8> {
+        self.entry(address)
+            .and_then(|entry| entry.neighbor.tx_power)
+    }
+
+    /// Return an [`Iterator`] over the known neighbors.
+    pub fn iter(&self) -> impl Iterator<Item = &Neighbor> {
+        self.entries.iter().flatten().map(|entry| &entry.neighbor)
+    }
+
+    /// Return the number of known neighbors.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` when no neighbors are known.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` when no more neighbors fit without evicting one.
+    pub fn is_full(&self) -> bool {
+        self.len() == N
+    }
+
+    fn entry(&self, address: &Address) -> Option<&Entry> {
+        self.entries.iter().flatten().find(|entry| match address {
+            Address::Short(short) => &entry.neighbor.short_address == short,
+            Address::Extended(extended) => &entry.neighbor.extended_address == extended,
+            Address::Absent => false,
+        })
+    }
+
+    fn set_pinned(&mut self, extended_address: &[u8; 8], pinned: bool) -> bool {
+        match self
+            .entries
+            .iter_mut()
+            .flatten()
+            .find(|entry| &entry.neighbor.extended_address == extended_address)
+        {
+            Some(entry) => {
+                entry.pinned = pinned;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Advance the clock, and return its new value.
+    fn tick(&self) -> u32 {
+        let now = self.clock.get().wrapping_add(1);
+        self.clock.set(now);
+        now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_lookup_remove() {
+        let mut table = NeighborTable::<2>::new();
+        assert!(table.is_empty());
+
+        let a = Neighbor {
+            extended_address: [1; 8],
+            short_address: [0x00, 0x01],
+            tx_power: None,
+        };
+        let b = Neighbor {
+            extended_address: [2; 8],
+            short_address: [0x00, 0x02],
+            tx_power: Some(-10),
+        };
+        let c = Neighbor {
+            extended_address: [3; 8],
+            short_address: [0x00, 0x03],
+            tx_power: None,
+        };
+
+        assert_eq!(table.insert(a), Ok(None));
+        assert_eq!(table.insert(b), Ok(None));
+        assert_eq!(table.insert(c), Err(c));
+        assert_eq!(table.len(), 2);
+
+        assert_eq!(table.short_address(&[1; 8]), Some([0x00, 0x01]));
+        assert_eq!(table.short_address(&[3; 8]), None);
+
+        // Updating an existing entry does not need a free slot.
+        assert_eq!(
+            table.insert(Neighbor {
+                extended_address: [1; 8],
+                short_address: [0x00, 0x11],
+                tx_power: None,
+            }),
+            Ok(None)
+        );
+        assert_eq!(table.short_address(&[1; 8]), Some([0x00, 0x11]));
+
+        assert_eq!(table.tx_power(&Address::Extended([2; 8])), Some(-10));
+        assert_eq!(table.tx_power(&Address::Short([0x00, 0x02])), Some(-10));
+        assert_eq!(table.tx_power(&Address::Short([0x00, 0x11])), None);
+
+        assert_eq!(table.remove(&[2; 8]), Some(b));
+        assert_eq!(table.remove(&[2; 8]), None);
+        assert_eq!(table.insert(c), Ok(None));
+        assert_eq!(table.short_address(&[3; 8]), Some([0x00, 0x03]));
+    }
+
+    #[test]
+    fn eviction() {
+        let neighbor = |n| Neighbor {
+            extended_address: [n; 8],
+            short_address: [0x00, n],
+            tx_power: None,
+        };
+        let mut table =
+            NeighborTable::<3>::with_eviction_policy(EvictionPolicy::LeastRecentlyHeard);
+        assert_eq!(table.eviction_policy(), EvictionPolicy::LeastRecentlyHeard);
+        for n in 1..=3 {
+            assert_eq!(table.insert(neighbor(n)), Ok(None));
+        }
+        assert!(table.is_full());
+
+        // The least recently heard neighbor is evicted
+        assert!(table.heard(&Address::Short([0x00, 0x01])));
+        assert!(!table.heard(&Address::Short([0x00, 0x09])));
+        assert_eq!(table.insert(neighbor(4)), Ok(Some(neighbor(2))));
+        assert_eq!(table.short_address(&[2; 8]), None);
+
+        // Pinned neighbors are never evicted
+        assert!(table.pin(&[3; 8]));
+        assert!(table.pin(&[1; 8]));
+        assert!(!table.pin(&[2; 8]));
+        assert!(table.is_pinned(&[3; 8]));
+        assert_eq!(table.insert(neighbor(5)), Ok(Some(neighbor(4))));
+        assert!(table.pin(&[5; 8]));
+        assert_eq!(table.insert(neighbor(6)), Err(neighbor(6)));
+
+        // Updating a neighbor keeps it pinned
+        assert_eq!(
+            table.insert(Neighbor {
+                tx_power: Some(0),
+                ..neighbor(3)
+            }),
+            Ok(None)
+        );
+        assert!(table.is_pinned(&[3; 8]));
+        assert!(table.unpin(&[3; 8]));
+        assert_eq!(
+            table.insert(neighbor(6)),
+            Ok(Some(Neighbor {
+                tx_power: Some(0),
+                ..neighbor(3)
+            }))
+        );
+
+        // Without eviction, a full table rejects new neighbors
+        table.set_eviction_policy(EvictionPolicy::Never);
+        assert!(table.unpin(&[1; 8]));
+        assert_eq!(table.insert(neighbor(7)), Err(neighbor(7)));
+    }
+}
@@ -0,0 +1,245 @@
+//! A poll-driven variant of the CSMA module, for firmware without an async
+//! executor.
+//!
+//! The future of [`CsmaDevice::run`](super::CsmaDevice::run) is polled from a
+//! main loop, in the same way as `Interface::poll` in smoltcp. The CSMA module
+//! waits using a [`PollDelay`] of a [`PollClock`], such that the current time
+//! is given by the caller of [`CsmaPoller::poll`], which in return gets the
+//! time of the next timeout.
+//!
+//! ```ignore
+//! let clock = PollClock::new();
+//! let mut csma = CsmaDevice::new(radio, rng, driver, clock.delay(), CsmaConfig::default());
+//! let run = core::pin::pin!(csma.run());
+//! let mut poller = CsmaPoller::new(&clock, run);
+//! loop {
+//!     match poller.poll(now()) {
+//!         NextWakeup::Now => {}
+//!         NextWakeup::At(instant) => wait_for_interrupt_until(instant),
+//!         NextWakeup::Event => wait_for_interrupt(),
+//!         NextWakeup::Finished => break,
+//!     }
+//! }
+//! ```
+
+use core::cell::Cell;
+use core::future::{poll_fn, Future};
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll, RawWaker, RawWakerVTable, Waker};
+
+use embedded_hal_async::delay::DelayNs;
+
+use crate::time::{Duration, Instant};
+
+/// Maximum number of times the future is polled in one call to
+/// [`CsmaPoller::poll`].
+const MAX_POLLS: usize = 32;
+
+/// Set when the future is woken. This is shared by all pollers, which only
+/// results in some unneeded polls when there are multiple.
+static WOKEN: AtomicBool = AtomicBool::new(false);
+
+static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, wake, wake, drop);
+
+fn clone(_: *const ()) -> RawWaker {
+    RawWaker::new(core::ptr::null(), &VTABLE)
+}
+
+fn wake(_: *const ()) {
+    WOKEN.store(true, Ordering::Release);
+}
+
+fn drop(_: *const ()) {}
+
+/// When [`CsmaPoller::poll`] should be called again.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NextWakeup {
+    /// Immediately, there is more work to do.
+    Now,
+    /// At the latest at the given time, or earlier when an interrupt of the
+    /// radio occurred or the driver has something to hand to the MAC.
+    At(Instant),
+    /// Only when an interrupt of the radio occurred or the driver has
+    /// something to hand to the MAC.
+    Event,
+    /// Never, the CSMA module stopped running.
+    Finished,
+}
+
+/// The time source of a poll-driven CSMA module.
+#[derive(Debug)]
+pub struct PollClock {
+    now: Cell<Instant>,
+    next_wakeup: Cell<Option<Instant>>,
+}
+
+impl Default for PollClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PollClock {
+    /// Create a new [`PollClock`].
+    pub const fn new() -> Self {
+        Self {
+            now: Cell::new(Instant::from_us(0)),
+            next_wakeup: Cell::new(None),
+        }
+    }
+
+    /// Return a timer for the CSMA module that uses this clock.
+    pub fn delay(&self) -> PollDelay<'_> {
+        PollDelay { clock: self }
+    }
+
+    fn schedule(&self, at: Instant) {
+        let next_wakeup = match self.next_wakeup.get() {
+            Some(next_wakeup) => next_wakeup.min(at),
+            None => at,
+        };
+        self.next_wakeup.set(Some(next_wakeup));
+    }
+}
+
+/// A timer that completes once the time of its [`PollClock`] passed the
+/// deadline.
+#[derive(Debug, Clone)]
+pub struct PollDelay<'c> {
+    clock: &'c PollClock,
+}
+
+impl DelayNs for PollDelay<'_> {
+    async fn delay_ns(&mut self, ns: u32) {
+        let deadline = self.clock.now.get() + Duration::from_us(ns.div_ceil(1000) as i64);
+        poll_fn(|_| {
+            if self.clock.now.get() >= deadline {
+                Poll::Ready(())
+            } else {
+                self.clock.schedule(deadline);
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// Drives the future of [`CsmaDevice::run`](super::CsmaDevice::run) without
+/// an executor.
+pub struct CsmaPoller<'c, 'f, F> {
+    clock: &'c PollClock,
+    run: Pin<&'f mut F>,
+    finished: bool,
+}
+
+impl<'c, 'f, F: Future<Output = ()>> CsmaPoller<'c, 'f, F> {
+    /// Create a new [`CsmaPoller`]. The CSMA module should use a timer of
+    /// `clock`.
+    pub fn new(clock: &'c PollClock, run: Pin<&'f mut F>) -> Self {
+        Self {
+            clock,
+            run,
+            finished: false,
+        }
+    }
+
+    /// Let the CSMA module make progress, with `now` as the current time.
+    pub fn poll(&mut self, now: Instant) -> NextWakeup {
+        if self.finished {
+            return NextWakeup::Finished;
+        }
+
+        self.clock.now.set(now);
+        self.clock.next_wakeup.set(None);
+
+        // Safety: the vtable does not use the data pointer
+        let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+        let mut cx = Context::from_waker(&waker);
+
+        for _ in 0..MAX_POLLS {
+            WOKEN.store(false, Ordering::Release);
+            if self.run.as_mut().poll(&mut cx).is_ready() {
+                self.finished = true;
+                return NextWakeup::Finished;
+            }
+
+            if !WOKEN.load(Ordering::Acquire) {
+                return match self.clock.next_wakeup.get() {
+                    Some(at) => NextWakeup::At(at),
+                    None => NextWakeup::Event,
+                };
+            }
+        }
+
+        NextWakeup::Now
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::csma::{CsmaConfig, CsmaDevice};
+    use crate::mlme::data::{DataConfirm, DataStatus};
+    use crate::mlme::MacConfirm;
+    use crate::phy::driver::{tests::*, FrameBuffer};
+    use crate::phy::radio::{tests::*, TxToken};
+    use dot15d4_frame::{Address, DataFrame, FrameBuilder};
+
+    #[test]
+    fn poll_without_executor() {
+        let radio = TestRadio::default();
+        let mut channel = TestDriverChannel::new();
+        let (driver, monitor) = channel.split();
+        let clock = PollClock::new();
+        let mut csma = CsmaDevice::new(
+            radio.clone(),
+            rand::thread_rng(),
+            driver,
+            clock.delay(),
+            CsmaConfig::default(),
+        );
+        let run = core::pin::pin!(csma.run());
+        let mut poller = CsmaPoller::new(&clock, run);
+
+        // The test radio always wakes itself while receiving
+        let mut now = Instant::from_us(0);
+        assert_eq!(poller.poll(now), NextWakeup::Now);
+
+        // Nobody acknowledges the frame, so we wait for every ACK timeout
+        let mut f = FrameBuffer::default();
+        let frame_repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+            .set_sequence_number(1)
+            .set_dst_address(Address::Short([0x00, 0x02]))
+            .set_src_address(Address::Short([0x00, 0x01]))
+            .set_dst_pan_id(0xfff)
+            .set_src_pan_id(0xfff)
+            .finalize()
+            .unwrap();
+        TestTxToken::from(&mut f.buffer[..]).consume(frame_repr.buffer_len(), |buf| {
+            frame_repr.emit(&mut DataFrame::new_unchecked(buf));
+        });
+        monitor.tx.send(f);
+
+        while !monitor.confirms.has_item() {
+            match poller.poll(now) {
+                NextWakeup::Now => now = now + Duration::from_us(100),
+                NextWakeup::At(at) => {
+                    assert!(at > now);
+                    now = at;
+                }
+                other => panic!("Unexpected wakeup {other:?}"),
+            }
+        }
+        assert!(now > Instant::from_us(0));
+        assert_eq!(
+            pollster::block_on(monitor.confirms.receive()),
+            MacConfirm::Data(DataConfirm {
+                handle: None,
+                sequence_number: Some(1),
+                status: DataStatus::NoAck,
+            })
+        );
+    }
+}
@@ -1,60 +1,84 @@
+use core::cell::Cell;
+
 use embedded_hal_async::delay::DelayNs;
 use rand_core::RngCore;
 
 use super::user_configurable_constants::*;
 use super::utils;
 
-use crate::phy::config;
+use crate::phy::config::CcaMode;
 use crate::phy::config::TxConfig;
 use crate::phy::driver;
 use crate::phy::driver::Driver;
 use crate::phy::driver::FrameBuffer;
 use crate::phy::radio::futures::transmit;
 use crate::phy::radio::Radio;
+use crate::phy::radio::TxStatus;
+use crate::phy::stats::PhyStats;
 use crate::sync::channel::Sender;
 use crate::sync::join::join;
 use crate::sync::mutex::Mutex;
 use crate::sync::mutex::MutexGuard;
+use crate::time::Instant;
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum TransmissionError {
     CcaError,
+    HwError,
 }
 
 #[allow(clippy::too_many_arguments)]
 pub async fn transmit_cca<'m, R, TIMER, Rng, D>(
     radio: &'m Mutex<R>,
     radio_guard: &mut Option<MutexGuard<'m, R>>,
-    channel: config::Channel,
+    tx_config: TxConfig,
     wants_to_transmit_signal: &Sender<'_, ()>,
     tx_frame: &mut FrameBuffer,
     timer: &mut TIMER,
     mut backoff_strategy: CCABackoffStrategy<'_, Rng>,
+    max_backoffs: u16,
     driver: &D,
-) -> Result<(), TransmissionError>
+    stats: &Cell<PhyStats>,
+) -> Result<Option<Instant>, TransmissionError>
 where
     R: Radio,
     TIMER: DelayNs,
     Rng: RngCore,
     D: Driver,
 {
-    'cca: for number_of_backoffs in 1..MAC_MAX_CSMA_BACKOFFS + 1 {
+    // Without CCA, a busy channel is not detected, so backing off is of no use
+    let max_backoffs = if tx_config.cca == CcaMode::None {
+        0
+    } else {
+        max_backoffs
+    };
+
+    for number_of_backoffs in 0..=max_backoffs {
         // try to transmit
         let transmission_result = {
             utils::acquire_lock(radio, wants_to_transmit_signal, radio_guard).await;
+            PhyStats::count(stats, |stats| &mut stats.tx_attempts);
             transmit(
                 &mut **radio_guard.as_mut().unwrap(),
                 &mut tx_frame.buffer,
-                TxConfig {
-                    channel,
-                    ..TxConfig::default_with_cca()
-                },
+                tx_config.clone(),
             )
             .await
         };
-        if transmission_result {
-            break 'cca; // Send succesfully, now wait for ack
+        match transmission_result.status {
+            TxStatus::Sent => {
+                // Send succesfully, now wait for ack
+                return Ok(transmission_result.timestamp);
+            }
+            TxStatus::ChannelBusy => PhyStats::count(stats, |stats| &mut stats.tx_errors),
+            TxStatus::HwError => {
+                // A backoff does not help against a failing radio
+                PhyStats::count(stats, |stats| &mut stats.tx_errors);
+                *radio_guard = None;
+                debug!("radio failed to transmit");
+                return Err(TransmissionError::HwError);
+            }
         }
 
         // As we are now going to wait a number of periods, release the
@@ -63,25 +87,28 @@ where
 
         // CCA did not go succesfully
         // Was this the last attempt?
-        if number_of_backoffs == MAC_MAX_CSMA_BACKOFFS {
+        if number_of_backoffs == max_backoffs {
+            debug!("channel busy after {} backoffs", number_of_backoffs);
             return Err(TransmissionError::CcaError); // Fail transmission
         } else {
+            trace!("channel busy, backoff {}", number_of_backoffs + 1);
             // Perform backoff and report current status to driver
             join(
                 backoff_strategy.perform_backoff(timer),
-                driver.error(driver::Error::CcaBackoff(number_of_backoffs)),
+                driver.error(driver::Error::CcaBackoff(number_of_backoffs + 1)),
             )
             .await;
         }
     }
 
-    Ok(())
+    Err(TransmissionError::CcaError)
 }
 
 pub enum CCABackoffStrategy<'r, Rng: RngCore> {
     None,
     ExponentialBackoff {
         backoff_exponent: u16,
+        max_backoff_exponent: u16,
         rng: &'r Mutex<Rng>,
     },
 }
@@ -91,9 +118,10 @@ impl<'r, Rng: RngCore> CCABackoffStrategy<'r, Rng> {
         Self::None
     }
 
-    pub fn new_exponential_backoff(rng: &'r Mutex<Rng>) -> Self {
+    pub fn new_exponential_backoff(rng: &'r Mutex<Rng>, min_be: u16, max_be: u16) -> Self {
         Self::ExponentialBackoff {
-            backoff_exponent: MAC_MIN_BE,
+            backoff_exponent: min_be,
+            max_backoff_exponent: max_be,
             rng,
         }
     }
@@ -103,10 +131,11 @@ impl<'r, Rng: RngCore> CCABackoffStrategy<'r, Rng> {
             Self::None => {}
             Self::ExponentialBackoff {
                 backoff_exponent,
+                max_backoff_exponent,
                 rng,
             } => {
                 // Wait now for a random number of periods, before retrying
-                *backoff_exponent = core::cmp::min(*backoff_exponent + 1, MAC_MAX_BE);
+                *backoff_exponent = core::cmp::min(*backoff_exponent + 1, *max_backoff_exponent);
 
                 // delay periods = random(2^{BE} - 1) periods
                 // Page 63 IEEE 802.15.4 2015 edition
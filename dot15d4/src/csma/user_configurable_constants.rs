@@ -11,7 +11,7 @@ mod constants {
     // they be?
     pub const MAC_MIN_BE: u16 = 0;
     pub const MAC_MAX_BE: u16 = 8;
-    pub const MAC_MAX_CSMA_BACKOFFS: u16 = 16;
+    pub const MAC_MAX_CSMA_BACKOFFS: u16 = 4;
     pub const MAC_UNIT_BACKOFF_DURATION: Duration =
         Duration::from_us((UNIT_BACKOFF_PERIOD * SYMBOL_RATE_INV_US) as i64);
     pub const MAC_MAX_FRAME_RETIES: u16 = 3; // 0-7
@@ -23,6 +23,11 @@ mod constants {
                                                                      // PAN Id
     pub const MAC_PAN_ID: u16 = 0xffff;
     pub const MAC_IMPLICIT_BROADCAST: bool = false;
+    pub const MAC_NEIGHBOR_TABLE_SIZE: usize = 16;
+    pub const MAC_GROUP_TABLE_SIZE: usize = 4;
+    pub const MAC_DEVICE_TABLE_SIZE: usize = 8;
+    pub const MAC_KEY_TABLE_SIZE: usize = 4;
+    pub const MAC_MAX_FRAME_TOTAL_WAIT_TIME: Duration = Duration::from_us(20_000);
 }
 
 #[cfg(not(test))]
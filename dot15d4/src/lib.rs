@@ -9,7 +9,11 @@ pub(crate) mod utils;
 
 pub use dot15d4_frame as frame;
 
+#[cfg(feature = "capture")]
+pub mod capture;
 pub mod csma;
+pub mod mlme;
 pub mod phy;
 pub mod sync;
 pub mod time;
+pub mod tsch;
@@ -0,0 +1,491 @@
+//! Association, on the side of the coordinator.
+//!
+//! A device joins a PAN by transmitting an Association Request command to a
+//! coordinator, which replies with an Association Response command. Whether
+//! a device is accepted, and which short address it gets, is decided by a
+//! [`CoordinatorPolicy`]. Accepted devices are stored as children of the
+//! [`Coordinator`] in a [`NeighborTable`], such that frames towards them can
+//! use their short address. The children can be persisted between reboots
+//! with [`Coordinator::emit_address_map`] and
+//! [`Coordinator::restore_address_map`].
+
+use dot15d4_frame::CommandId;
+use rand_core::RngCore;
+
+use super::short_address::ShortAddressAllocator;
+use crate::csma::neighbors::{Neighbor, NeighborTable};
+
+/// The short address telling an associated device to use its extended
+/// address, in the same byte order as
+/// [`Address::Short`](dot15d4_frame::Address::Short).
+pub const SHORT_ADDRESS_USE_EXTENDED: [u8; 2] = [0xfe, 0xff];
+
+/// The short address of a device that is not associated, in the same byte
+/// order as [`Address::Short`](dot15d4_frame::Address::Short).
+pub const SHORT_ADDRESS_NONE: [u8; 2] = [0xff, 0xff];
+
+/// The Capability Information field of an Association Request command.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CapabilityInformation {
+    /// The device is a full-function device.
+    pub full_function_device: bool,
+    /// The device is mains powered.
+    pub mains_powered: bool,
+    /// The receiver of the device is enabled while it is idle.
+    pub rx_on_when_idle: bool,
+    /// The device requests fast association.
+    pub fast_association: bool,
+    /// The device can secure frames.
+    pub security_capable: bool,
+    /// The device wants the coordinator to allocate a short address.
+    pub allocate_address: bool,
+}
+
+impl From<u8> for CapabilityInformation {
+    fn from(value: u8) -> Self {
+        Self {
+            full_function_device: value & 0b0000_0010 != 0,
+            mains_powered: value & 0b0000_0100 != 0,
+            rx_on_when_idle: value & 0b0000_1000 != 0,
+            fast_association: value & 0b0001_0000 != 0,
+            security_capable: value & 0b0100_0000 != 0,
+            allocate_address: value & 0b1000_0000 != 0,
+        }
+    }
+}
+
+impl From<CapabilityInformation> for u8 {
+    fn from(value: CapabilityInformation) -> Self {
+        (value.full_function_device as u8) << 1
+            | (value.mains_powered as u8) << 2
+            | (value.rx_on_when_idle as u8) << 3
+            | (value.fast_association as u8) << 4
+            | (value.security_capable as u8) << 6
+            | (value.allocate_address as u8) << 7
+    }
+}
+
+/// The status of an Association Response command.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssociationStatus {
+    /// The device is associated.
+    Successful,
+    /// The coordinator has no room for another device.
+    PanAtCapacity,
+    /// The coordinator does not allow the device to join.
+    PanAccessDenied,
+    /// The device is associated using fast association.
+    FastAssociationSuccessful,
+}
+
+impl From<AssociationStatus> for u8 {
+    fn from(value: AssociationStatus) -> Self {
+        match value {
+            AssociationStatus::Successful => 0x00,
+            AssociationStatus::PanAtCapacity => 0x01,
+            AssociationStatus::PanAccessDenied => 0x02,
+            AssociationStatus::FastAssociationSuccessful => 0x80,
+        }
+    }
+}
+
+impl TryFrom<u8> for AssociationStatus {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(AssociationStatus::Successful),
+            0x01 => Ok(AssociationStatus::PanAtCapacity),
+            0x02 => Ok(AssociationStatus::PanAccessDenied),
+            0x80 => Ok(AssociationStatus::FastAssociationSuccessful),
+            _ => Err(()),
+        }
+    }
+}
+
+/// An incoming Association Request command.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssociationRequest {
+    /// The extended address of the device that wants to associate.
+    pub device_address: [u8; 8],
+    /// The capabilities of the device.
+    pub capability_information: CapabilityInformation,
+}
+
+impl AssociationRequest {
+    /// Parse the payload of an Association Request command, starting with
+    /// the command identifier, transmitted by `device_address`.
+    pub fn parse(device_address: [u8; 8], payload: &[u8]) -> Option<Self> {
+        match payload {
+            [id, capability, ..] if CommandId::from(*id) == CommandId::AssociationRequest => {
+                Some(Self {
+                    device_address,
+                    capability_information: CapabilityInformation::from(*capability),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The Association Response command towards a device.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AssociationResponse {
+    /// The extended address of the device that requested association.
+    pub device_address: [u8; 8],
+    /// The short address allocated to the device, [`SHORT_ADDRESS_NONE`] when
+    /// the association failed.
+    pub short_address: [u8; 2],
+    /// The outcome of the association.
+    pub status: AssociationStatus,
+}
+
+impl AssociationResponse {
+    /// The length of the command payload, including the command identifier.
+    pub const fn buffer_len(&self) -> usize {
+        4
+    }
+
+    /// Emit the command payload, including the command identifier, into
+    /// `buffer`.
+    ///
+    /// ## Panics
+    /// This function panics if the buffer is too small.
+    pub fn emit(&self, buffer: &mut [u8]) {
+        buffer[0] = CommandId::AssociationResponse as u8;
+        buffer[1..3].copy_from_slice(&self.short_address);
+        buffer[3] = self.status.into();
+    }
+}
+
+/// The decision of a [`CoordinatorPolicy`] on an Association Request.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssociationDecision {
+    /// Accept the device, with a short address from the
+    /// [`ShortAddressAllocator`].
+    Accept,
+    /// Accept the device, allocating it the given short address.
+    AcceptWith([u8; 2]),
+    /// Reject the device with the given status.
+    Reject(AssociationStatus),
+}
+
+/// The policy of a coordinator for incoming Association Requests.
+pub trait CoordinatorPolicy {
+    /// Whether association is allowed at all, `macAssociationPermit`
+    /// (default = true).
+    fn association_permit(&self) -> bool {
+        true
+    }
+
+    /// The maximum number of associated children (default = no limit other
+    /// than the size of the table of children).
+    fn max_children(&self) -> usize {
+        usize::MAX
+    }
+
+    /// Decide whether to accept a device, and which short address it gets.
+    /// Only called when association is permitted, the device is not a child
+    /// yet and there is room for another child.
+    fn associate(&mut self, request: &AssociationRequest) -> AssociationDecision;
+}
+
+/// A policy accepting every device, as long as there is room for it.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PermitAll;
+
+impl CoordinatorPolicy for PermitAll {
+    fn associate(&mut self, _request: &AssociationRequest) -> AssociationDecision {
+        AssociationDecision::Accept
+    }
+}
+
+/// The length of a child in the persisted address map.
+const CHILD_LEN: usize = 10;
+
+/// The coordinator side of association, holding at most `N` children.
+#[derive(Debug)]
+pub struct Coordinator<P, const N: usize> {
+    policy: P,
+    allocator: ShortAddressAllocator,
+    children: NeighborTable<N>,
+}
+
+impl<P: CoordinatorPolicy, const N: usize> Coordinator<P, N> {
+    /// Create a new [`Coordinator`] without children.
+    pub fn new(policy: P, allocator: ShortAddressAllocator) -> Self {
+        Self {
+            policy,
+            allocator,
+            children: NeighborTable::new(),
+        }
+    }
+
+    /// Return a reference to the policy.
+    pub fn policy(&self) -> &P {
+        &self.policy
+    }
+
+    /// Return a mutable reference to the policy.
+    pub fn policy_mut(&mut self) -> &mut P {
+        &mut self.policy
+    }
+
+    /// Return the table of associated children.
+    pub fn children(&self) -> &NeighborTable<N> {
+        &self.children
+    }
+
+    /// Forget a child, e.g. after it disassociated. Its short address can be
+    /// allocated again.
+    pub fn remove_child(&mut self, extended_address: &[u8; 8]) -> Option<Neighbor> {
+        self.children.remove(extended_address)
+    }
+
+    /// Handle an incoming Association Request, and return the response to
+    /// transmit.
+    ///
+    /// A device that is already a child keeps its short address. An accepted
+    /// device is added to the children. When it did not ask for a short
+    /// address, it is told to use its extended address.
+    pub fn handle_association_request(
+        &mut self,
+        request: &AssociationRequest,
+        rng: &mut impl RngCore,
+    ) -> AssociationResponse {
+        let response = |short_address, status| AssociationResponse {
+            device_address: request.device_address,
+            short_address,
+            status,
+        };
+
+        if !self.policy.association_permit() {
+            return response(SHORT_ADDRESS_NONE, AssociationStatus::PanAccessDenied);
+        }
+
+        if let Some(short_address) = self.children.short_address(&request.device_address) {
+            return response(short_address, AssociationStatus::Successful);
+        }
+
+        if self.children.len() >= self.policy.max_children().min(N) {
+            return response(SHORT_ADDRESS_NONE, AssociationStatus::PanAtCapacity);
+        }
+
+        let short_address = match self.policy.associate(request) {
+            AssociationDecision::Reject(status) => return response(SHORT_ADDRESS_NONE, status),
+            _ if !request.capability_information.allocate_address => SHORT_ADDRESS_USE_EXTENDED,
+            AssociationDecision::Accept => match self.allocator.allocate(&self.children, rng) {
+                Some(short_address) => short_address,
+                None => return response(SHORT_ADDRESS_NONE, AssociationStatus::PanAtCapacity),
+            },
+            AssociationDecision::AcceptWith(short_address) => short_address,
+        };
+        let status = if request.capability_information.fast_association {
+            AssociationStatus::FastAssociationSuccessful
+        } else {
+            AssociationStatus::Successful
+        };
+
+        match self.children.insert(Neighbor {
+            extended_address: request.device_address,
+            short_address,
+            tx_power: None,
+        }) {
+            Ok(_) => response(short_address, status),
+            Err(_) => response(SHORT_ADDRESS_NONE, AssociationStatus::PanAtCapacity),
+        }
+    }
+
+    /// The length of the persisted address map.
+    pub fn address_map_len(&self) -> usize {
+        3 + CHILD_LEN * self.children.len()
+    }
+
+    /// Emit the children and the state of the allocator into `buffer`, such
+    /// that they can be persisted, e.g. in flash. The transmit powers of the
+    /// children are not persisted. Returns the number of bytes written, or
+    /// `None` when the buffer is too small.
+    pub fn emit_address_map(&self, buffer: &mut [u8]) -> Option<usize> {
+        let len = self.address_map_len();
+        let buffer = buffer.get_mut(..len)?;
+
+        buffer[..2].copy_from_slice(&self.allocator.next().to_le_bytes());
+        buffer[2] = self.children.len() as u8;
+        for (child, chunk) in self
+            .children
+            .iter()
+            .zip(buffer[3..].chunks_exact_mut(CHILD_LEN))
+        {
+            chunk[..8].copy_from_slice(&child.extended_address);
+            chunk[8..].copy_from_slice(&child.short_address);
+        }
+        Some(len)
+    }
+
+    /// Restore the children and the state of the allocator from an address
+    /// map emitted by [`Coordinator::emit_address_map`]. The current children
+    /// are replaced.
+    ///
+    /// Returns `None` when the address map is malformed or does not fit in
+    /// the table of children, in which case nothing is changed.
+    pub fn restore_address_map(&mut self, buffer: &[u8]) -> Option<()> {
+        let [next_0, next_1, count, children @ ..] = buffer else {
+            return None;
+        };
+        let children = children.get(..*count as usize * CHILD_LEN)?;
+        if *count as usize > N {
+            return None;
+        }
+
+        self.children = NeighborTable::new();
+        for chunk in children.chunks_exact(CHILD_LEN) {
+            let mut child = Neighbor {
+                extended_address: [0; 8],
+                short_address: [0; 2],
+                tx_power: None,
+            };
+            child.extended_address.copy_from_slice(&chunk[..8]);
+            child.short_address.copy_from_slice(&chunk[8..]);
+            // Duplicates only overwrite each other, so there is always room
+            let _ = self.children.insert(child);
+        }
+        self.allocator
+            .set_next(u16::from_le_bytes([*next_0, *next_1]));
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::mock::StepRng;
+
+    use super::*;
+    use crate::mlme::short_address::AllocationMode;
+
+    /// Reject one device, and choose the short address of another.
+    struct TestPolicy {
+        permit: bool,
+    }
+
+    impl CoordinatorPolicy for TestPolicy {
+        fn association_permit(&self) -> bool {
+            self.permit
+        }
+
+        fn max_children(&self) -> usize {
+            2
+        }
+
+        fn associate(&mut self, request: &AssociationRequest) -> AssociationDecision {
+            if request.device_address == [0xba; 8] {
+                return AssociationDecision::Reject(AssociationStatus::PanAccessDenied);
+            }
+            if request.device_address == [0xcc; 8] {
+                return AssociationDecision::AcceptWith([0xcc, 0xcc]);
+            }
+            AssociationDecision::Accept
+        }
+    }
+
+    fn request(address: u8) -> AssociationRequest {
+        AssociationRequest::parse([address; 8], &[0x01, 0b1000_1000]).unwrap()
+    }
+
+    #[test]
+    fn associate() {
+        let mut rng = StepRng::new(0, 1);
+        let mut coordinator = Coordinator::<_, 4>::new(
+            TestPolicy { permit: false },
+            ShortAddressAllocator::new(AllocationMode::Sequential),
+        );
+
+        let response = coordinator.handle_association_request(&request(1), &mut rng);
+        assert_eq!(response.status, AssociationStatus::PanAccessDenied);
+        assert_eq!(response.short_address, SHORT_ADDRESS_NONE);
+
+        coordinator.policy_mut().permit = true;
+        let response = coordinator.handle_association_request(&request(1), &mut rng);
+        assert_eq!(response.status, AssociationStatus::Successful);
+        assert_eq!(response.short_address, [1, 0]);
+        assert_eq!(coordinator.children().short_address(&[1; 8]), Some([1, 0]));
+
+        // Associating again keeps the short address
+        let response = coordinator.handle_association_request(&request(1), &mut rng);
+        assert_eq!(response.short_address, [1, 0]);
+
+        let response = coordinator.handle_association_request(&request(0xba), &mut rng);
+        assert_eq!(response.status, AssociationStatus::PanAccessDenied);
+
+        // Without asking for a short address
+        let mut request_2 = request(2);
+        request_2.capability_information.allocate_address = false;
+        let response = coordinator.handle_association_request(&request_2, &mut rng);
+        assert_eq!(response.short_address, SHORT_ADDRESS_USE_EXTENDED);
+
+        // The policy allows 2 children
+        let response = coordinator.handle_association_request(&request(0xcc), &mut rng);
+        assert_eq!(response.status, AssociationStatus::PanAtCapacity);
+        assert_eq!(coordinator.children().len(), 2);
+
+        coordinator.remove_child(&[2; 8]);
+        let response = coordinator.handle_association_request(&request(0xcc), &mut rng);
+        assert_eq!(response.short_address, [0xcc, 0xcc]);
+
+        let mut buffer = [0; 4];
+        let response = AssociationResponse {
+            device_address: [1; 8],
+            short_address: [0x34, 0x12],
+            status: AssociationStatus::Successful,
+        };
+        response.emit(&mut buffer[..response.buffer_len()]);
+        assert_eq!(buffer, [0x02, 0x34, 0x12, 0x00]);
+    }
+
+    #[test]
+    fn persist_address_map() {
+        let mut rng = StepRng::new(0, 1);
+        let mut coordinator = Coordinator::<_, 4>::new(PermitAll, Default::default());
+        coordinator.handle_association_request(&request(1), &mut rng);
+        coordinator.handle_association_request(&request(2), &mut rng);
+
+        let mut buffer = [0; 64];
+        let len = coordinator.emit_address_map(&mut buffer).unwrap();
+        assert_eq!(len, coordinator.address_map_len());
+        assert_eq!(&buffer[..3], &[3, 0, 2]);
+        assert!(coordinator
+            .emit_address_map(&mut buffer[..len - 1])
+            .is_none());
+
+        // After a reboot, the allocation continues where it stopped
+        let mut restored = Coordinator::<_, 4>::new(PermitAll, Default::default());
+        assert_eq!(restored.restore_address_map(&buffer[..len]), Some(()));
+        assert_eq!(restored.children().short_address(&[2; 8]), Some([2, 0]));
+        let response = restored.handle_association_request(&request(3), &mut rng);
+        assert_eq!(response.short_address, [3, 0]);
+
+        assert!(restored.restore_address_map(&buffer[..len - 1]).is_none());
+        assert_eq!(restored.children().len(), 3);
+        let mut small = Coordinator::<_, 1>::new(PermitAll, Default::default());
+        assert!(small.restore_address_map(&buffer[..len]).is_none());
+    }
+
+    #[test]
+    fn capability_information() {
+        let capability = CapabilityInformation::from(0b1100_1110);
+        assert!(capability.full_function_device);
+        assert!(capability.mains_powered);
+        assert!(capability.rx_on_when_idle);
+        assert!(!capability.fast_association);
+        assert!(capability.security_capable);
+        assert!(capability.allocate_address);
+        assert_eq!(u8::from(capability), 0b1100_1110);
+
+        assert!(AssociationRequest::parse([0; 8], &[0x04, 0x00]).is_none());
+        assert!(AssociationRequest::parse([0; 8], &[0x01]).is_none());
+    }
+}
@@ -0,0 +1,246 @@
+//! MLME-BEACON-NOTIFY.indication.
+//!
+//! Received beacon frames are parsed into a [`PanDescriptor`], such that the
+//! upper layers do not need to parse the frame again.
+
+use dot15d4_frame::{
+    frames::{BeaconOrder, SuperframeOrder},
+    AbsoluteSlotNumber, Address, Beacon, DataFrame, FrameType, FrameVersion, HeaderElementId,
+    SimplifiedSuperframeSpecification,
+};
+
+use crate::phy::{
+    config::{Channel, ChannelPage},
+    driver::FrameBuffer,
+};
+
+/// The fields of the Superframe Specification of a beacon frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SuperframeSpec {
+    /// The beacon order.
+    pub beacon_order: BeaconOrder,
+    /// The superframe order.
+    pub superframe_order: SuperframeOrder,
+    /// The final CAP slot.
+    pub final_cap_slot: u8,
+    /// Whether battery life extension is enabled.
+    pub battery_life_extension: bool,
+    /// Whether the beacon was transmitted by the PAN coordinator.
+    pub pan_coordinator: bool,
+    /// Whether the coordinator is accepting association requests.
+    pub association_permit: bool,
+}
+
+/// The TSCH information carried by an enhanced beacon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TschInfo {
+    /// The Absolute Slot Number at the time the beacon was transmitted.
+    pub absolute_slot_number: AbsoluteSlotNumber,
+    /// The join metric of the coordinator.
+    pub join_metric: u8,
+    /// The channel hopping sequence ID, if advertised.
+    pub hopping_sequence_id: Option<u8>,
+}
+
+/// The description of a PAN, as learned from a received beacon frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanDescriptor {
+    /// The address of the coordinator that transmitted the beacon.
+    pub coord_address: Address,
+    /// The PAN ID of the coordinator.
+    pub coord_pan_id: u16,
+    /// The channel on which the beacon was received.
+    pub channel: Channel,
+    /// The channel page of [`PanDescriptor::channel`].
+    pub channel_page: ChannelPage,
+    /// The Superframe Specification, present in beacons that are not enhanced
+    /// beacons, and in enhanced beacons with a Simplified Superframe
+    /// Specification Header IE.
+    pub superframe_spec: Option<SuperframeSpec>,
+    /// The link quality of the received beacon, if reported by the radio.
+    pub link_quality: Option<u8>,
+    /// The TSCH information, if present in the enhanced beacon.
+    pub tsch: Option<TschInfo>,
+}
+
+impl PanDescriptor {
+    /// Parse a [`PanDescriptor`] from the IEEE 802.15.4 frame in `data`, with
+    /// the `link_quality` the radio reported for it, see [`Radio::rx_lqi`].
+    ///
+    /// Returns `None` when the frame is not a valid beacon frame, or when it
+    /// does not carry a coordinator address.
+    ///
+    /// [`Radio::rx_lqi`]: crate::phy::radio::Radio::rx_lqi
+    pub fn parse(
+        data: &[u8],
+        channel: Channel,
+        channel_page: ChannelPage,
+        link_quality: Option<u8>,
+    ) -> Option<Self> {
+        let frame = DataFrame::new(data).ok()?;
+        let fc = frame.frame_control();
+
+        if fc.frame_type() != FrameType::Beacon {
+            return None;
+        }
+
+        let addressing = frame.addressing()?;
+        let coord_address = match addressing.src_address()? {
+            Address::Absent => return None,
+            address => address,
+        };
+        let coord_pan_id = addressing.src_pan_id().or(addressing.dst_pan_id())?;
+
+        let superframe_spec = match fc.frame_version() {
+            FrameVersion::Ieee802154_2003 | FrameVersion::Ieee802154_2006 => {
+                let beacon = Beacon::new(data).ok()?;
+                let spec = beacon.superframe_specification();
+                Some(SuperframeSpec {
+                    beacon_order: spec.beacon_order(),
+                    superframe_order: spec.superframe_order(),
+                    final_cap_slot: spec.final_cap_slot(),
+                    battery_life_extension: spec.battery_life_extension(),
+                    pan_coordinator: spec.pan_coordinator(),
+                    association_permit: spec.association_permit(),
+                })
+            }
+            _ => frame.information_elements().and_then(|ie| {
+                let header = ie.header_information_elements().find(|header| {
+                    header.element_id() == HeaderElementId::SimplifiedSuperframeSpecification
+                })?;
+                let superframe = SimplifiedSuperframeSpecification::new(header.content()).ok()?;
+                let spec = superframe.superframe_specification().ok()?;
+                Some(SuperframeSpec {
+                    beacon_order: spec.beacon_order(),
+                    superframe_order: spec.superframe_order(),
+                    final_cap_slot: spec.final_cap_slot(),
+                    battery_life_extension: spec.battery_life_extension(),
+                    pan_coordinator: spec.pan_coordinator(),
+                    association_permit: spec.association_permit(),
+                })
+            }),
+        };
+
+        let ie = frame.information_elements();
+        let tsch = ie
+            .as_ref()
+            .and_then(|ie| ie.tsch_synchronization())
+            .map(|sync| (sync.absolute_slot_number(), sync.join_metric()));
+        let hopping_sequence_id = ie
+            .as_ref()
+            .and_then(|ie| ie.channel_hopping())
+            .map(|hopping| hopping.hopping_sequence_id());
+
+        Some(Self {
+            coord_address,
+            coord_pan_id,
+            channel,
+            channel_page,
+            superframe_spec,
+            link_quality,
+            tsch: tsch.map(|(absolute_slot_number, join_metric)| TschInfo {
+                absolute_slot_number,
+                join_metric,
+                hopping_sequence_id,
+            }),
+        })
+    }
+}
+
+/// MLME-BEACON-NOTIFY.indication, handed to the upper layer for every
+/// received beacon frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BeaconNotifyIndication {
+    pan_descriptor: PanDescriptor,
+    buffer: FrameBuffer,
+}
+
+impl BeaconNotifyIndication {
+    /// Create a new [`BeaconNotifyIndication`] from a parsed [`PanDescriptor`]
+    /// and the buffer holding the received beacon.
+    pub fn new(pan_descriptor: PanDescriptor, buffer: FrameBuffer) -> Self {
+        Self {
+            pan_descriptor,
+            buffer,
+        }
+    }
+
+    /// Return the [`PanDescriptor`] of the received beacon.
+    pub fn pan_descriptor(&self) -> &PanDescriptor {
+        &self.pan_descriptor
+    }
+
+    /// Return the buffer holding the received beacon, as received from the
+    /// radio.
+    pub fn buffer(&self) -> &FrameBuffer {
+        &self.buffer
+    }
+
+    /// Consume the indication, returning the buffer holding the received
+    /// beacon.
+    pub fn into_buffer(self) -> FrameBuffer {
+        self.buffer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_enhanced_beacon() {
+        let frame = [
+            0x40, 0xeb, 0xcd, 0xab, 0xff, 0xff, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00,
+            0x00, 0x3f, 0x11, 0x88, 0x06, 0x1a, 0x0e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x1c,
+            0x00, 0x01, 0xc8, 0x00, 0x01, 0x1b, 0x00,
+        ];
+
+        let descriptor =
+            PanDescriptor::parse(&frame, Channel::_15, ChannelPage::Page0, Some(212)).unwrap();
+        assert_eq!(
+            descriptor,
+            PanDescriptor {
+                coord_address: Address::Extended([0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01]),
+                coord_pan_id: 0xabcd,
+                channel: Channel::_15,
+                channel_page: ChannelPage::Page0,
+                superframe_spec: None,
+                link_quality: Some(212),
+                tsch: Some(TschInfo {
+                    absolute_slot_number: AbsoluteSlotNumber::new(14).unwrap(),
+                    join_metric: 0,
+                    hopping_sequence_id: Some(0),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn parse_beacon() {
+        let frame = [
+            0x00, 0x90, 0x01, 0xcd, 0xab, 0x01, 0x00, 0xff, 0xcf, 0x00, 0x00,
+        ];
+
+        let descriptor =
+            PanDescriptor::parse(&frame, Channel::_26, ChannelPage::Page0, None).unwrap();
+        assert_eq!(descriptor.coord_address, Address::Short([0x00, 0x01]));
+        assert_eq!(descriptor.coord_pan_id, 0xabcd);
+        assert_eq!(descriptor.tsch, None);
+        assert_eq!(descriptor.link_quality, None);
+
+        let spec = descriptor.superframe_spec.unwrap();
+        assert_eq!(spec.beacon_order, BeaconOrder::OnDemand);
+        assert!(spec.pan_coordinator);
+        assert!(spec.association_permit);
+
+        // Data frames are not beacons.
+        let data = [
+            0x41, 0xd8, 0x01, 0xcd, 0xab, 0xff, 0xff, 0xc7, 0xd9, 0xb5, 0x14, 0x00, 0x4b, 0x12,
+            0x00, 0x2b, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(
+            PanDescriptor::parse(&data, Channel::_26, ChannelPage::Page0, None),
+            None
+        );
+    }
+}
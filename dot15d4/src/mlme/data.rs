@@ -0,0 +1,57 @@
+//! MCPS-DATA.confirm.
+//!
+//! Every frame taken from [`Driver::transmit`](crate::phy::driver::Driver)
+//! is confirmed to the upper layer, once it is either acknowledged, or the
+//! transmission is given up on.
+
+use crate::csma::devices::SecurityError;
+use crate::phy::driver::FrameHandle;
+
+/// Status of MCPS-DATA.confirm.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataStatus {
+    /// The frame was transmitted, and acknowledged when an acknowledgment was
+    /// requested.
+    Success,
+    /// The frame could not be transmitted, because the channel was busy.
+    ChannelAccessFailure,
+    /// The frame could not be transmitted, because the radio failed.
+    RadioFailure,
+    /// The frame was not acknowledged, after all retransmissions.
+    NoAck,
+    /// The key to secure the frame with is not in the key table.
+    UnavailableKey,
+    /// The frame could not be secured, because `macFrameCounter` is
+    /// exhausted.
+    CounterError,
+    /// The frame could not be secured, e.g. because its frame counter is
+    /// suppressed or the MIC does not fit in the frame.
+    UnsupportedSecurity,
+}
+
+impl From<SecurityError> for DataStatus {
+    fn from(value: SecurityError) -> Self {
+        match value {
+            SecurityError::UnavailableKey => DataStatus::UnavailableKey,
+            SecurityError::CounterError => DataStatus::CounterError,
+            SecurityError::UnavailableDevice | SecurityError::TransformFailed => {
+                DataStatus::UnsupportedSecurity
+            }
+        }
+    }
+}
+
+/// MCPS-DATA.confirm.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataConfirm {
+    /// The handle of the confirmed frame, see [`FrameBuffer::handle`].
+    ///
+    /// [`FrameBuffer::handle`]: crate::phy::driver::FrameBuffer::handle
+    pub handle: Option<FrameHandle>,
+    /// The sequence number of the confirmed frame, `None` when suppressed.
+    pub sequence_number: Option<u8>,
+    /// The result of the transmission.
+    pub status: DataStatus,
+}
@@ -0,0 +1,250 @@
+//! A request/response API on top of the [`Driver`] of the MAC.
+//!
+//! Instead of implementing [`Driver`] and matching every [`MacConfirm`] to
+//! the [`MacRequest`] it confirms, an application can split [`MacChannels`]
+//! into a [`MacDriver`] for the MAC, and a [`MacHandle`] of which every
+//! request returns its own confirmation:
+//!
+//! ```ignore
+//! let mut channels = MacChannels::new();
+//! let (mut handle, driver) = channels.split();
+//! let mut csma = CsmaDevice::new(radio, rng, driver, timer, CsmaConfig::default());
+//!
+//! select(csma.run(), async {
+//!     handle.set(PibAttribute::MacPanId(0xabcd)).await.unwrap();
+//!     let confirm = handle.data_request(frame).await;
+//!     let received = handle.receive().await;
+//! })
+//! .await;
+//! ```
+//!
+//! Like the other synchronization primitives of this crate, the channels only
+//! connect futures that are polled by the same task. The requests are not
+//! cancel safe: when the future of a request is dropped, the request may
+//! still be executed, and its confirmation is discarded by the next request.
+
+use super::{
+    data::DataConfirm,
+    pib::{PibAttribute, PibError},
+    poll::{PollRequest, PollStatus},
+    reset::ResetRequest,
+    MacConfirm, MacRequest,
+};
+use crate::phy::driver::{Driver, Error, FrameBuffer, FrameHandle};
+use crate::sync::channel::{Channel, Receiver, Sender};
+
+/// The channels between a [`MacHandle`] and a [`MacDriver`].
+#[derive(Default)]
+pub struct MacChannels {
+    tx: Channel<FrameBuffer>,
+    rx: Channel<FrameBuffer>,
+    errors: Channel<Error>,
+    requests: Channel<MacRequest>,
+    confirms: Channel<MacConfirm>,
+}
+
+impl MacChannels {
+    /// Create new, empty channels.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Split the channels into the [`MacHandle`] for the application and the
+    /// [`MacDriver`] for the MAC.
+    pub fn split(&mut self) -> (MacHandle<'_>, MacDriver<'_>) {
+        let (tx_send, tx_recv) = self.tx.split();
+        let (rx_send, rx_recv) = self.rx.split();
+        let (errors_send, errors_recv) = self.errors.split();
+        let (requests_send, requests_recv) = self.requests.split();
+        let (confirms_send, confirms_recv) = self.confirms.split();
+        (
+            MacHandle {
+                tx: tx_send,
+                rx: rx_recv,
+                errors: errors_recv,
+                requests: requests_send,
+                confirms: confirms_recv,
+                next_handle: FrameHandle::default(),
+            },
+            MacDriver {
+                tx: tx_recv,
+                rx: rx_send,
+                errors: errors_send,
+                requests: requests_recv,
+                confirms: confirms_send,
+            },
+        )
+    }
+}
+
+/// The application side of [`MacChannels`].
+pub struct MacHandle<'a> {
+    tx: Sender<'a, FrameBuffer>,
+    rx: Receiver<'a, FrameBuffer>,
+    errors: Receiver<'a, Error>,
+    requests: Sender<'a, MacRequest>,
+    confirms: Receiver<'a, MacConfirm>,
+    next_handle: FrameHandle,
+}
+
+impl MacHandle<'_> {
+    /// MCPS-DATA: transmit a frame and wait for its confirmation. A frame
+    /// without [`FrameBuffer::handle`] gets the next handle of this
+    /// [`MacHandle`].
+    pub async fn data_request(&mut self, mut frame: FrameBuffer) -> DataConfirm {
+        let handle = *frame.handle.get_or_insert_with(|| {
+            let handle = self.next_handle;
+            self.next_handle = handle.next();
+            handle
+        });
+
+        self.tx.send_async(frame).await;
+        loop {
+            if let MacConfirm::Data(confirm) = self.confirms.receive().await {
+                if confirm.handle == Some(handle) {
+                    return confirm;
+                }
+            }
+        }
+    }
+
+    /// MLME-SET: write the value of a PIB attribute.
+    pub async fn set(&mut self, attribute: PibAttribute) -> Result<(), PibError> {
+        self.requests.send_async(MacRequest::Set(attribute)).await;
+        loop {
+            if let MacConfirm::Set(status) = self.confirms.receive().await {
+                return status;
+            }
+        }
+    }
+
+    /// MLME-POLL: poll the coordinator for pending data. Received data is
+    /// returned by [`MacHandle::receive`].
+    pub async fn poll(&mut self, request: PollRequest) -> PollStatus {
+        self.requests.send_async(MacRequest::Poll(request)).await;
+        loop {
+            if let MacConfirm::Poll(status) = self.confirms.receive().await {
+                return status;
+            }
+        }
+    }
+
+    /// MLME-RESET: reset the MAC.
+    pub async fn reset(&mut self, request: ResetRequest) {
+        self.requests.send_async(MacRequest::Reset(request)).await;
+        loop {
+            if let MacConfirm::Reset = self.confirms.receive().await {
+                return;
+            }
+        }
+    }
+
+    /// Wait for the next received frame, including beacons.
+    pub async fn receive(&self) -> FrameBuffer {
+        self.rx.receive().await
+    }
+
+    /// Wait for the next error reported by the MAC. Only the last error is
+    /// kept while nobody is waiting.
+    pub async fn error(&self) -> Error {
+        self.errors.receive().await
+    }
+}
+
+/// The [`Driver`] side of [`MacChannels`], to be handed to the MAC.
+pub struct MacDriver<'a> {
+    tx: Receiver<'a, FrameBuffer>,
+    rx: Sender<'a, FrameBuffer>,
+    errors: Sender<'a, Error>,
+    requests: Receiver<'a, MacRequest>,
+    confirms: Sender<'a, MacConfirm>,
+}
+
+impl Driver for MacDriver<'_> {
+    async fn transmit(&self) -> FrameBuffer {
+        self.tx.receive().await
+    }
+
+    async fn received(&self, buffer: FrameBuffer) {
+        self.rx.send_async(buffer).await;
+    }
+
+    async fn error(&self, error: Error) {
+        self.errors.send(error);
+    }
+
+    async fn mlme_request(&self) -> MacRequest {
+        self.requests.receive().await
+    }
+
+    async fn mlme_confirm(&self, confirm: MacConfirm) {
+        self.confirms.send(confirm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dot15d4_frame::{Address, DataFrame, FrameBuilder};
+
+    use super::*;
+    use crate::csma::{CsmaConfig, CsmaDevice};
+    use crate::mlme::data::DataStatus;
+    use crate::phy::radio::tests::{TestRadio, TestTxToken};
+    use crate::phy::radio::TxToken;
+    use crate::sync::{select, tests::*};
+
+    #[test]
+    fn requests() {
+        block_on(async {
+            let radio = TestRadio::default();
+            let mut channels = MacChannels::new();
+            let (mut handle, driver) = channels.split();
+            let mut csma = CsmaDevice::new(
+                radio.clone(),
+                rand::thread_rng(),
+                driver,
+                VirtualDelay::default(),
+                CsmaConfig {
+                    ack_unicast: false,
+                    ..Default::default()
+                },
+            );
+
+            select::select(csma.run(), async {
+                assert_eq!(handle.set(PibAttribute::MacPanId(0xabcd)).await, Ok(()));
+                assert_eq!(
+                    handle.set(PibAttribute::MacTschEnabled(true)).await,
+                    Err(PibError::ReadOnly)
+                );
+
+                let mut frame = FrameBuffer::default();
+                let repr = FrameBuilder::new_data(&[1, 2, 3, 4])
+                    .set_sequence_number(5)
+                    .set_dst_address(Address::Short([0x00, 0x02]))
+                    .set_src_address(Address::Short([0x00, 0x01]))
+                    .set_dst_pan_id(0xabcd)
+                    .set_src_pan_id(0xabcd)
+                    .finalize()
+                    .unwrap();
+                TestTxToken::from(&mut frame.buffer[..]).consume(repr.buffer_len(), |buf| {
+                    repr.emit(&mut DataFrame::new_unchecked(buf));
+                });
+                assert_eq!(
+                    handle.data_request(frame).await,
+                    DataConfirm {
+                        handle: Some(FrameHandle(0)),
+                        sequence_number: Some(5),
+                        status: DataStatus::Success,
+                    }
+                );
+
+                handle
+                    .reset(ResetRequest {
+                        set_default_pib: true,
+                    })
+                    .await;
+            })
+            .await;
+        })
+    }
+}
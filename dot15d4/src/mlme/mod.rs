@@ -0,0 +1,44 @@
+//! MAC sublayer management entity (MLME) primitives.
+
+pub mod associate;
+pub mod beacon_notify;
+pub mod data;
+pub mod handle;
+pub mod msdu;
+pub mod pan_descriptors;
+pub mod pib;
+pub mod poll;
+pub mod reset;
+pub mod short_address;
+
+use data::DataConfirm;
+use pib::{PibAttribute, PibError};
+use poll::{PollRequest, PollStatus};
+use reset::ResetRequest;
+
+/// A request from the upper layer to the MLME.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MacRequest {
+    /// MLME-POLL.request
+    Poll(PollRequest),
+    /// MLME-SET.request
+    Set(PibAttribute),
+    /// MLME-RESET.request
+    Reset(ResetRequest),
+}
+
+/// The confirmation of a [`MacRequest`], handed back to the upper layer.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MacConfirm {
+    /// MCPS-DATA.confirm
+    Data(DataConfirm),
+    /// MLME-POLL.confirm
+    Poll(PollStatus),
+    /// MLME-SET.confirm
+    Set(Result<(), PibError>),
+    /// MLME-RESET.confirm
+    Reset,
+}
@@ -0,0 +1,431 @@
+//! MCPS-DATA with the construction of the MAC header offloaded to the MAC.
+//!
+//! A [`Driver`] hands complete IEEE 802.15.4 frames to the MAC. Upper layers
+//! that do not want to deal with framing implement [`MsduLayer`] instead, and
+//! wrap it in an [`MsduDriver`]. The [`MsduDriver`] builds a data frame around
+//! every [`DataRequest`], and parses received data frames into a
+//! [`DataIndication`]. Received MAC commands are handed to the
+//! [`MsduLayer`] as an [`MlmeCommandIndication`], such that commands the MAC
+//! does not handle itself can be implemented above the MAC. Other received
+//! frames, such as beacons, are dropped.
+
+use core::{cell::Cell, future::Future, marker::PhantomData};
+
+use dot15d4_frame::{Address, AddressingMode, DataFrame, FrameBuilder, FrameType};
+
+use super::{data::DataConfirm, MacConfirm};
+use crate::phy::{
+    driver::{Driver, Error, FrameBuffer, FrameHandle, Priority},
+    radio::{Radio, RadioFrame, TxToken},
+};
+
+/// The maximum length of an MSDU, a frame without MAC header and FCS.
+pub const MAX_MSDU_LEN: usize = 125;
+
+/// The length of the FCS, appended by the radio.
+const FCS_LEN: usize = 2;
+
+/// The payload of a data frame.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Msdu {
+    buffer: [u8; MAX_MSDU_LEN],
+    len: usize,
+}
+
+impl Msdu {
+    /// Copy `data` into a new [`Msdu`].
+    ///
+    /// Returns `None` when `data` is longer than [`MAX_MSDU_LEN`].
+    pub fn new(data: &[u8]) -> Option<Self> {
+        let mut buffer = [0; MAX_MSDU_LEN];
+        buffer.get_mut(..data.len())?.copy_from_slice(data);
+        Some(Self {
+            buffer,
+            len: data.len(),
+        })
+    }
+
+    /// Return the payload.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+}
+
+impl core::fmt::Debug for Msdu {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Msdu").field(&self.as_slice()).finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Msdu {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "Msdu({=[u8]})", self.as_slice())
+    }
+}
+
+/// MCPS-DATA.request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataRequest {
+    /// The addressing mode of the source address. With
+    /// [`AddressingMode::Short`], the short address set with
+    /// [`MsduDriver::set_short_address`] is used, falling back to the extended
+    /// address when there is none.
+    pub src_addressing_mode: AddressingMode,
+    /// The PAN ID of the destination.
+    pub dst_pan_id: u16,
+    /// The destination address.
+    pub dst_address: Address,
+    /// The payload to transmit.
+    pub msdu: Msdu,
+    /// The priority of the frame in the transmit queue.
+    pub priority: Priority,
+    /// The handle of the request, `msduHandle`, which is returned in its
+    /// [`DataConfirm`].
+    pub handle: Option<FrameHandle>,
+}
+
+/// MCPS-DATA.indication.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataIndication {
+    /// The PAN ID of the source, `None` when elided.
+    pub src_pan_id: Option<u16>,
+    /// The source address.
+    pub src_address: Address,
+    /// The PAN ID of the destination, `None` when elided.
+    pub dst_pan_id: Option<u16>,
+    /// The destination address.
+    pub dst_address: Address,
+    /// The sequence number of the frame, `None` when suppressed.
+    pub sequence_number: Option<u8>,
+    /// The received payload.
+    pub msdu: Msdu,
+}
+
+/// A received MAC command, which is not handled by the MAC itself, e.g. an
+/// experimental or vendor specific command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MlmeCommandIndication {
+    /// The command identifier, see [`CommandId`] for the identifiers of the
+    /// standard.
+    ///
+    /// [`CommandId`]: dot15d4_frame::CommandId
+    pub command_id: u8,
+    /// The PAN ID of the source, `None` when elided.
+    pub src_pan_id: Option<u16>,
+    /// The source address.
+    pub source: Address,
+    /// The payload of the command, without the command identifier.
+    pub payload: Msdu,
+}
+
+/// An upper layer that exchanges payloads with the MAC, without handling the
+/// IEEE 802.15.4 framing. Use it through an [`MsduDriver`].
+pub trait MsduLayer {
+    /// Waits until there is a payload to be transmitted.
+    fn data_request(&self) -> impl Future<Output = DataRequest>;
+    /// Hold until the received payload is handled.
+    fn data_indication(&self, indication: DataIndication) -> impl Future<Output = ()>;
+    /// Hold until the received MAC command is handled. By default, the
+    /// command is dropped.
+    fn command_indication(&self, _indication: MlmeCommandIndication) -> impl Future<Output = ()> {
+        core::future::ready(())
+    }
+    /// Hold until the confirmation of a [`DataRequest`] is handled. By
+    /// default, nothing is done.
+    fn data_confirm(&self, _confirm: DataConfirm) -> impl Future<Output = ()> {
+        core::future::ready(())
+    }
+    /// Hold until the error is handled. By default, nothing is done.
+    fn error(&self, _error: Error) -> impl Future<Output = ()> {
+        core::future::ready(())
+    }
+}
+
+/// A [`Driver`] that builds and parses the MAC header for an [`MsduLayer`].
+/// The frames are formatted for the radio `R`.
+pub struct MsduDriver<L, R> {
+    layer: L,
+    pan_id: u16,
+    extended_address: [u8; 8],
+    short_address: Cell<Option<[u8; 2]>>,
+    sequence_number: Cell<u8>,
+    radio: PhantomData<R>,
+}
+
+impl<L: MsduLayer, R: Radio> MsduDriver<L, R> {
+    /// Create a new [`MsduDriver`], transmitting frames from `pan_id` with
+    /// `extended_address` as source address.
+    pub fn new(layer: L, pan_id: u16, extended_address: [u8; 8]) -> Self {
+        Self {
+            layer,
+            pan_id,
+            extended_address,
+            short_address: Cell::new(None),
+            sequence_number: Cell::new(0),
+            radio: PhantomData,
+        }
+    }
+
+    /// Set the short address used as source address, e.g. after associating.
+    pub fn set_short_address(&self, short_address: Option<[u8; 2]>) {
+        self.short_address.set(short_address);
+    }
+
+    /// Return the wrapped [`MsduLayer`].
+    pub fn layer(&self) -> &L {
+        &self.layer
+    }
+
+    /// Build a data frame for `request` into `buffer`.
+    fn build(&self, request: &DataRequest, buffer: &mut [u8]) -> Option<()>
+    where
+        for<'a> R::TxToken<'a>: From<&'a mut [u8]>,
+    {
+        let src_address = match (request.src_addressing_mode, self.short_address.get()) {
+            (AddressingMode::Short, Some(short)) => Address::Short(short),
+            _ => Address::Extended(self.extended_address),
+        };
+        let sequence_number = self.sequence_number.get();
+
+        let repr = FrameBuilder::new_data(request.msdu.as_slice())
+            .set_sequence_number(sequence_number)
+            .set_dst_pan_id(request.dst_pan_id)
+            .set_dst_address(request.dst_address)
+            .set_src_pan_id(self.pan_id)
+            .set_src_address(src_address)
+            .finalize()
+            .ok()?;
+        if repr.buffer_len() + FCS_LEN > 127 {
+            return None;
+        }
+
+        self.sequence_number.set(sequence_number.wrapping_add(1));
+        R::TxToken::from(buffer).consume(repr.buffer_len(), |buffer| {
+            repr.emit(&mut DataFrame::new_unchecked(buffer));
+        });
+        Some(())
+    }
+
+    /// Parse a received data frame into a [`DataIndication`].
+    fn parse_data(frame: &DataFrame<&[u8]>) -> Option<DataIndication> {
+        let addressing = frame.addressing()?;
+        Some(DataIndication {
+            src_pan_id: addressing.src_pan_id(),
+            src_address: addressing.src_address()?,
+            dst_pan_id: addressing.dst_pan_id(),
+            dst_address: addressing.dst_address()?,
+            sequence_number: frame.sequence_number(),
+            msdu: Msdu::new(frame.payload().unwrap_or(&[]))?,
+        })
+    }
+
+    /// Parse a received MAC command frame into an [`MlmeCommandIndication`].
+    fn parse_command(frame: &DataFrame<&[u8]>) -> Option<MlmeCommandIndication> {
+        let addressing = frame.addressing()?;
+        let (command_id, payload) = frame.payload()?.split_first()?;
+        Some(MlmeCommandIndication {
+            command_id: *command_id,
+            src_pan_id: addressing.src_pan_id(),
+            source: addressing.src_address()?,
+            payload: Msdu::new(payload)?,
+        })
+    }
+}
+
+impl<L: MsduLayer, R: Radio> Driver for MsduDriver<L, R>
+where
+    for<'a> R::TxToken<'a>: From<&'a mut [u8]>,
+{
+    async fn transmit(&self) -> FrameBuffer {
+        loop {
+            let request = self.layer.data_request().await;
+            let mut buffer = FrameBuffer {
+                priority: request.priority,
+                handle: request.handle,
+                ..FrameBuffer::default()
+            };
+            if self.build(&request, &mut buffer.buffer).is_some() {
+                return buffer;
+            }
+
+            // The payload does not fit in a frame with these addresses
+            self.layer.error(Error::InvalidIEEEStructure).await;
+        }
+    }
+
+    async fn received(&self, buffer: FrameBuffer) {
+        let Ok(frame) = R::RadioFrame::new_checked(&buffer.buffer[..]) else {
+            return;
+        };
+        let Ok(frame) = DataFrame::new(frame.data()) else {
+            return;
+        };
+
+        match frame.frame_control().frame_type() {
+            FrameType::Data => {
+                if let Some(indication) = Self::parse_data(&frame) {
+                    self.layer.data_indication(indication).await;
+                }
+            }
+            FrameType::MacCommand => {
+                if let Some(indication) = Self::parse_command(&frame) {
+                    self.layer.command_indication(indication).await;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    async fn error(&self, error: Error) {
+        self.layer.error(error).await;
+    }
+
+    async fn mlme_confirm(&self, confirm: MacConfirm) {
+        match confirm {
+            MacConfirm::Data(confirm) => self.layer.data_confirm(confirm).await,
+            // The MAC was reset, start the sequence numbers over
+            MacConfirm::Reset => self.sequence_number.set(0),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::VecDeque, vec::Vec};
+
+    use super::*;
+    use crate::mlme::data::DataStatus;
+    use crate::phy::radio::tests::TestRadio;
+
+    #[derive(Default)]
+    struct TestLayer {
+        requests: RefCell<VecDeque<DataRequest>>,
+        indications: RefCell<Vec<DataIndication>>,
+        commands: RefCell<Vec<MlmeCommandIndication>>,
+        confirms: RefCell<Vec<DataConfirm>>,
+        errors: RefCell<Vec<Error>>,
+    }
+
+    impl MsduLayer for &TestLayer {
+        async fn data_request(&self) -> DataRequest {
+            self.requests.borrow_mut().pop_front().unwrap()
+        }
+
+        async fn data_indication(&self, indication: DataIndication) {
+            self.indications.borrow_mut().push(indication);
+        }
+
+        async fn command_indication(&self, indication: MlmeCommandIndication) {
+            self.commands.borrow_mut().push(indication);
+        }
+
+        async fn data_confirm(&self, confirm: DataConfirm) {
+            self.confirms.borrow_mut().push(confirm);
+        }
+
+        async fn error(&self, error: Error) {
+            self.errors.borrow_mut().push(error);
+        }
+    }
+
+    #[test]
+    fn transmit_and_receive() {
+        let layer = TestLayer::default();
+        let driver = MsduDriver::<_, TestRadio>::new(&layer, 0xabcd, [1; 8]);
+        driver.set_short_address(Some([0x01, 0x00]));
+
+        let request = DataRequest {
+            src_addressing_mode: AddressingMode::Short,
+            dst_pan_id: 0xabcd,
+            dst_address: Address::Short([0x02, 0x00]),
+            msdu: Msdu::new(&[1, 2, 3, 4]).unwrap(),
+            priority: Priority::High,
+            handle: Some(FrameHandle(3)),
+        };
+        // Too large for a frame, reported and skipped
+        let too_large = DataRequest {
+            msdu: Msdu::new(&[0; MAX_MSDU_LEN]).unwrap(),
+            ..request
+        };
+        layer
+            .requests
+            .borrow_mut()
+            .extend([too_large, request, request]);
+
+        let first = pollster::block_on(driver.transmit());
+        assert_eq!(layer.errors.take(), [Error::InvalidIEEEStructure]);
+        assert_eq!(first.priority, Priority::High);
+        assert_eq!(first.handle, Some(FrameHandle(3)));
+        let second = pollster::block_on(driver.transmit());
+
+        let frame = DataFrame::new(&first.buffer[..127]).unwrap();
+        assert_eq!(frame.sequence_number(), Some(0));
+        assert!(frame.frame_control().pan_id_compression());
+        let frame = DataFrame::new(&second.buffer[..127]).unwrap();
+        assert_eq!(frame.sequence_number(), Some(1));
+
+        // The transmitted frame is received as the same MSDU, with its padding
+        pollster::block_on(driver.received(first));
+        let indication = layer.indications.take().pop().unwrap();
+        assert_eq!(indication.src_address, Address::Short([0x01, 0x00]));
+        assert_eq!(indication.dst_address, Address::Short([0x02, 0x00]));
+        assert_eq!(indication.dst_pan_id, Some(0xabcd));
+        assert_eq!(indication.src_pan_id, None);
+        assert_eq!(indication.sequence_number, Some(0));
+        assert_eq!(&indication.msdu.as_slice()[..4], [1, 2, 3, 4]);
+
+        // Only data frames are indicated
+        let mut ack = FrameBuffer::default();
+        let repr = FrameBuilder::new_imm_ack(7).finalize().unwrap();
+        repr.emit(&mut DataFrame::new_unchecked(&mut ack.buffer[..3]));
+        pollster::block_on(driver.received(ack));
+        assert!(layer.indications.borrow().is_empty());
+        assert!(layer.commands.borrow().is_empty());
+
+        let confirm = DataConfirm {
+            handle: Some(FrameHandle(3)),
+            sequence_number: Some(0),
+            status: DataStatus::Success,
+        };
+        pollster::block_on(driver.mlme_confirm(MacConfirm::Data(confirm)));
+        assert_eq!(layer.confirms.take(), [confirm]);
+
+        // The sequence numbers start over after a reset of the MAC
+        pollster::block_on(driver.mlme_confirm(MacConfirm::Reset));
+        layer.requests.borrow_mut().push_back(request);
+        let third = pollster::block_on(driver.transmit());
+        let frame = DataFrame::new(&third.buffer[..127]).unwrap();
+        assert_eq!(frame.sequence_number(), Some(0));
+    }
+
+    #[test]
+    fn command_indication() {
+        let layer = TestLayer::default();
+        let driver = MsduDriver::<_, TestRadio>::new(&layer, 0xabcd, [1; 8]);
+
+        // A vendor specific command, unknown to the MAC
+        let mut command = FrameBuffer::default();
+        let repr = FrameBuilder::new_data_request()
+            .set_payload(&[0x42, 1, 2, 3])
+            .set_sequence_number(9)
+            .set_dst_pan_id(0xabcd)
+            .set_dst_address(Address::Short([0x01, 0x00]))
+            .set_src_pan_id(0xabcd)
+            .set_src_address(Address::Extended([2; 8]))
+            .finalize()
+            .unwrap();
+        repr.emit(&mut DataFrame::new_unchecked(
+            &mut command.buffer[..repr.buffer_len()],
+        ));
+        pollster::block_on(driver.received(command));
+
+        let indication = layer.commands.take().pop().unwrap();
+        assert_eq!(indication.command_id, 0x42);
+        assert_eq!(indication.source, Address::Extended([2; 8]));
+        assert_eq!(indication.src_pan_id, None);
+        assert_eq!(&indication.payload.as_slice()[..3], [1, 2, 3]);
+        assert!(layer.indications.borrow().is_empty());
+    }
+}
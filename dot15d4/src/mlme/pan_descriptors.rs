@@ -0,0 +1,173 @@
+//! A small, fixed-size table of PANs learned from received beacons.
+//!
+//! The [`PanDescriptor`]s of scanned or received beacons are collected in a
+//! [`PanDescriptorTable`], after which the best coordinator to join is picked
+//! with [`PanDescriptorTable::best`]. This is the same for associating with a
+//! coordinator, and for joining a TSCH network through one of its enhanced
+//! beacons.
+
+use core::cmp::Ordering;
+
+use super::beacon_notify::PanDescriptor;
+
+/// Compare two coordinators, where the greater one is the better one to join:
+/// the one with the lowest join metric, and then the one of which the beacon
+/// was received with the highest link quality. A missing join metric ranks
+/// the same as any other, a missing link quality as the lowest.
+pub fn compare(a: &PanDescriptor, b: &PanDescriptor) -> Ordering {
+    let join_metric = |descriptor: &PanDescriptor| descriptor.tsch.map(|tsch| tsch.join_metric);
+    let join_metric = match (join_metric(a), join_metric(b)) {
+        (Some(a), Some(b)) => b.cmp(&a),
+        _ => Ordering::Equal,
+    };
+
+    join_metric.then(a.link_quality.cmp(&b.link_quality))
+}
+
+/// A table holding the [`PanDescriptor`]s of at most `N` coordinators.
+#[derive(Debug)]
+pub struct PanDescriptorTable<const N: usize> {
+    entries: [Option<PanDescriptor>; N],
+}
+
+impl<const N: usize> Default for PanDescriptorTable<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> PanDescriptorTable<N> {
+    /// Create a new empty [`PanDescriptorTable`].
+    pub const fn new() -> Self {
+        Self { entries: [None; N] }
+    }
+
+    /// Insert a descriptor, or update the descriptor of the same coordinator
+    /// in the same PAN. When the table is full, the worst coordinator is
+    /// replaced if the new one is better, see [`compare`].
+    ///
+    /// Returns the descriptor that did not fit in the table, if any.
+    pub fn insert(&mut self, descriptor: PanDescriptor) -> Option<PanDescriptor> {
+        if let Some(entry) = self.entries.iter_mut().flatten().find(|entry| {
+            entry.coord_address == descriptor.coord_address
+                && entry.coord_pan_id == descriptor.coord_pan_id
+        }) {
+            *entry = descriptor;
+            return None;
+        }
+
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.is_none()) {
+            *entry = Some(descriptor);
+            return None;
+        }
+
+        let worst = self
+            .entries
+            .iter_mut()
+            .flatten()
+            .min_by(|a, b| compare(a, b))?;
+        if compare(&descriptor, worst) == Ordering::Greater {
+            Some(core::mem::replace(worst, descriptor))
+        } else {
+            Some(descriptor)
+        }
+    }
+
+    /// Return the best coordinator to join, see [`compare`]. Only PANs of
+    /// which the ID is in `allowed_pan_ids` are considered, or all PANs when
+    /// it is empty. Coordinators that do not permit association are skipped.
+    pub fn best(&self, allowed_pan_ids: &[u16]) -> Option<&PanDescriptor> {
+        self.iter()
+            .filter(|entry| {
+                allowed_pan_ids.is_empty() || allowed_pan_ids.contains(&entry.coord_pan_id)
+            })
+            .filter(|entry| {
+                entry
+                    .superframe_spec
+                    .is_none_or(|spec| spec.association_permit)
+            })
+            .max_by(|a, b| compare(a, b))
+    }
+
+    /// Remove all descriptors, e.g. before a new scan.
+    pub fn clear(&mut self) {
+        self.entries = [None; N];
+    }
+
+    /// Return an [`Iterator`] over the known descriptors.
+    pub fn iter(&self) -> impl Iterator<Item = &PanDescriptor> {
+        self.entries.iter().flatten()
+    }
+
+    /// Return the number of known descriptors.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` when no descriptors are known.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dot15d4_frame::{AbsoluteSlotNumber, Address};
+
+    use super::*;
+    use crate::mlme::beacon_notify::TschInfo;
+    use crate::phy::config::{Channel, ChannelPage};
+
+    fn descriptor(short: u8, join_metric: u8, link_quality: u8) -> PanDescriptor {
+        PanDescriptor {
+            coord_address: Address::Short([short, 0x00]),
+            coord_pan_id: 0xabcd,
+            channel: Channel::_26,
+            channel_page: ChannelPage::Page0,
+            superframe_spec: None,
+            link_quality: Some(link_quality),
+            tsch: Some(TschInfo {
+                absolute_slot_number: AbsoluteSlotNumber::ZERO,
+                join_metric,
+                hopping_sequence_id: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn best_coordinator() {
+        let mut table = PanDescriptorTable::<2>::new();
+        assert!(table.best(&[]).is_none());
+
+        // The lowest join metric wins, then the highest link quality
+        assert_eq!(table.insert(descriptor(1, 1, 200)), None);
+        assert_eq!(table.insert(descriptor(2, 0, 100)), None);
+        assert_eq!(table.best(&[]), Some(&descriptor(2, 0, 100)));
+        assert_eq!(table.insert(descriptor(1, 0, 200)), None);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.best(&[]), Some(&descriptor(1, 0, 200)));
+
+        // A full table only takes better coordinators
+        assert_eq!(
+            table.insert(descriptor(3, 2, 255)),
+            Some(descriptor(3, 2, 255))
+        );
+        assert_eq!(
+            table.insert(descriptor(3, 0, 150)),
+            Some(descriptor(2, 0, 100))
+        );
+
+        // Only allowed PANs are considered
+        let other_pan = PanDescriptor {
+            coord_pan_id: 0x1234,
+            ..descriptor(4, 0, 255)
+        };
+        assert_eq!(table.insert(other_pan), Some(descriptor(3, 0, 150)));
+        assert_eq!(table.best(&[]), Some(&other_pan));
+        assert_eq!(table.best(&[0xabcd]), Some(&descriptor(1, 0, 200)));
+        assert_eq!(table.best(&[0xffff]), None);
+
+        table.clear();
+        assert!(table.is_empty());
+    }
+}
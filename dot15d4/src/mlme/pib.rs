@@ -0,0 +1,919 @@
+//! PAN Information Base (PIB).
+//!
+//! The PIB holds the attributes that manage the MAC and PHY sublayers. The
+//! attributes are read with MLME-GET and written with MLME-SET. The initial
+//! values are set with a [`PibBuilder`], such that the basic configuration
+//! does not need a sequence of MLME-SET requests.
+//!
+//! The PHY attributes, prefixed with `phy`, are stored in the same [`Pib`] as
+//! the MAC attributes, and are updated with the same MLME-SET requests. The
+//! view of the PHY on them is returned by [`Pib::phy`].
+
+use crate::csma::user_configurable_constants::{
+    MAC_IMPLICIT_BROADCAST, MAC_MAX_BE, MAC_MAX_CSMA_BACKOFFS, MAC_MAX_FRAME_RETIES, MAC_MIN_BE,
+    MAC_PAN_ID,
+};
+use crate::phy::config::{CcaMode, Channel, ChannelPage};
+use crate::phy::pib::PhyPib;
+
+/// Version of the encoding of [`Pib::save`], the first byte of the encoding.
+const SAVE_VERSION: u8 = 1;
+
+const FLAG_RX_ON_WHEN_IDLE: u8 = 0b0000_0001;
+const FLAG_IMPLICIT_BROADCAST: u8 = 0b0000_0010;
+const FLAG_SECURITY_ENABLED: u8 = 0b0000_0100;
+const FLAG_TSCH_ENABLED: u8 = 0b0000_1000;
+const FLAG_EXTENDED_ADDRESS: u8 = 0b0001_0000;
+const FLAG_ASSOCIATION_PERMIT: u8 = 0b0010_0000;
+
+/// Identifier of a PIB attribute, used with MLME-GET.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PibAttributeId {
+    /// `phyTxPower`
+    PhyTxPower,
+    /// `macRxOnWhenIdle`
+    MacRxOnWhenIdle,
+    /// `phyCurrentChannel`
+    PhyCurrentChannel,
+    /// `macPanId`
+    MacPanId,
+    /// `macShortAddress`
+    MacShortAddress,
+    /// `macImplicitBroadcast`
+    MacImplicitBroadcast,
+    /// `macSecurityEnabled`
+    MacSecurityEnabled,
+    /// `macTschEnabled`
+    MacTschEnabled,
+    /// `macMinBE`
+    MacMinBe,
+    /// `macMaxBE`
+    MacMaxBe,
+    /// `macMaxCSMABackoffs`
+    MacMaxCsmaBackoffs,
+    /// `macMaxFrameRetries`
+    MacMaxFrameRetries,
+    /// `macAssociationPermit`
+    MacAssociationPermit,
+    /// `phyCcaMode`
+    PhyCcaMode,
+    /// `phyCurrentPage`
+    PhyCurrentPage,
+    /// `macFrameCounter`
+    MacFrameCounter,
+}
+
+impl PibAttributeId {
+    /// Whether the attribute is a PHY attribute, part of [`PhyPib`], rather
+    /// than a MAC attribute.
+    pub fn is_phy(&self) -> bool {
+        matches!(
+            self,
+            PibAttributeId::PhyTxPower
+                | PibAttributeId::PhyCurrentChannel
+                | PibAttributeId::PhyCcaMode
+                | PibAttributeId::PhyCurrentPage
+        )
+    }
+}
+
+/// A PIB attribute together with its value.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PibAttribute {
+    /// `phyTxPower`: the transmit power of the radio, in dBm.
+    PhyTxPower(i8),
+    /// `macRxOnWhenIdle`: whether the receiver is enabled while the device is
+    /// idle.
+    MacRxOnWhenIdle(bool),
+    /// `phyCurrentChannel`: the channel on which is transmitted and received,
+    /// which must be part of `phyCurrentPage`.
+    PhyCurrentChannel(Channel),
+    /// `macPanId`: the identifier of the PAN on which the device is operating.
+    MacPanId(u16),
+    /// `macShortAddress`: the short address of the device, `0xffff` when it
+    /// has none, `0xfffe` when it only uses its extended address.
+    MacShortAddress(u16),
+    /// `macImplicitBroadcast`: whether frames without destination address and
+    /// destination PAN ID are accepted as broadcast frames.
+    MacImplicitBroadcast(bool),
+    /// `macSecurityEnabled`: whether the MAC secures and unsecures frames.
+    MacSecurityEnabled(bool),
+    /// `macTschEnabled`: whether the MAC operates in TSCH mode. This
+    /// attribute is read-only, the mode is selected when creating the device.
+    MacTschEnabled(bool),
+    /// `macMinBE`: the backoff exponent of the first CSMA-CA backoff, at most
+    /// `macMaxBE`.
+    MacMinBe(u16),
+    /// `macMaxBE`: the largest backoff exponent of CSMA-CA, at least
+    /// `macMinBE` and in the range 3..=8.
+    MacMaxBe(u16),
+    /// `macMaxCSMABackoffs`: the number of backoffs after a busy channel
+    /// before a channel access failure is reported, in the range 0..=5.
+    MacMaxCsmaBackoffs(u16),
+    /// `macMaxFrameRetries`: the number of retransmissions of a frame of
+    /// which no acknowledgment is received, in the range 0..=7.
+    MacMaxFrameRetries(u16),
+    /// `macAssociationPermit`: whether the coordinator accepts association
+    /// requests.
+    MacAssociationPermit(bool),
+    /// `phyCcaMode`: the CCA mode used before transmitting a frame. With
+    /// [`CcaMode::None`], frames are transmitted without backoffs.
+    PhyCcaMode(CcaMode),
+    /// `phyCurrentPage`: the channel page of `phyCurrentChannel`, which must
+    /// include that channel.
+    PhyCurrentPage(ChannelPage),
+    /// `macFrameCounter`: the frame counter of the next secured frame that is
+    /// transmitted.
+    MacFrameCounter(u32),
+}
+
+impl PibAttribute {
+    /// Return the identifier of the attribute.
+    pub fn id(&self) -> PibAttributeId {
+        match self {
+            PibAttribute::PhyTxPower(_) => PibAttributeId::PhyTxPower,
+            PibAttribute::MacRxOnWhenIdle(_) => PibAttributeId::MacRxOnWhenIdle,
+            PibAttribute::PhyCurrentChannel(_) => PibAttributeId::PhyCurrentChannel,
+            PibAttribute::MacPanId(_) => PibAttributeId::MacPanId,
+            PibAttribute::MacShortAddress(_) => PibAttributeId::MacShortAddress,
+            PibAttribute::MacImplicitBroadcast(_) => PibAttributeId::MacImplicitBroadcast,
+            PibAttribute::MacSecurityEnabled(_) => PibAttributeId::MacSecurityEnabled,
+            PibAttribute::MacTschEnabled(_) => PibAttributeId::MacTschEnabled,
+            PibAttribute::MacMinBe(_) => PibAttributeId::MacMinBe,
+            PibAttribute::MacMaxBe(_) => PibAttributeId::MacMaxBe,
+            PibAttribute::MacMaxCsmaBackoffs(_) => PibAttributeId::MacMaxCsmaBackoffs,
+            PibAttribute::MacMaxFrameRetries(_) => PibAttributeId::MacMaxFrameRetries,
+            PibAttribute::MacAssociationPermit(_) => PibAttributeId::MacAssociationPermit,
+            PibAttribute::PhyCcaMode(_) => PibAttributeId::PhyCcaMode,
+            PibAttribute::PhyCurrentPage(_) => PibAttributeId::PhyCurrentPage,
+            PibAttribute::MacFrameCounter(_) => PibAttributeId::MacFrameCounter,
+        }
+    }
+}
+
+/// Status of a failed MLME-SET request.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PibError {
+    /// The value is out of the valid range of the attribute.
+    InvalidParameter,
+    /// The attribute is read-only.
+    ReadOnly,
+    /// The attribute is not supported.
+    UnsupportedAttribute,
+}
+
+/// The PAN Information Base.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Pib {
+    /// The transmit power of the radio, in dBm (default = 0 dBm).
+    pub phy_tx_power: i8,
+    /// Whether the receiver is enabled while the device is idle (default =
+    /// true). When false, the radio is only enabled while transmitting, while
+    /// waiting for an acknowledgment and while polling the coordinator.
+    pub mac_rx_on_when_idle: bool,
+    /// The channel on which is transmitted and received (default = 26).
+    pub phy_current_channel: Channel,
+    /// The identifier of the PAN on which the device is operating (default =
+    /// `MAC_PAN_ID`).
+    pub mac_pan_id: u16,
+    /// The short address of the device (default = `0xffff`, no short
+    /// address). Only frames to the extended address of the device are
+    /// accepted while it has no short address.
+    pub mac_short_address: u16,
+    /// Whether frames without destination address and destination PAN ID
+    /// are accepted as broadcast frames (default = `MAC_IMPLICIT_BROADCAST`).
+    pub mac_implicit_broadcast: bool,
+    /// The extended address of the device (default = `None`, the address of
+    /// the radio is used).
+    pub mac_extended_address: Option<[u8; 8]>,
+    /// Whether the MAC secures and unsecures frames (default = false).
+    pub mac_security_enabled: bool,
+    /// Whether the MAC operates in TSCH mode (default = false).
+    pub mac_tsch_enabled: bool,
+    /// The backoff exponent of the first CSMA-CA backoff (default =
+    /// `MAC_MIN_BE`).
+    pub mac_min_be: u16,
+    /// The largest backoff exponent of CSMA-CA (default = `MAC_MAX_BE`).
+    pub mac_max_be: u16,
+    /// The number of backoffs after a busy channel before a channel access
+    /// failure is reported (default = `MAC_MAX_CSMA_BACKOFFS`).
+    pub mac_max_csma_backoffs: u16,
+    /// The number of retransmissions of a frame of which no acknowledgment
+    /// is received (default = `MAC_MAX_FRAME_RETIES`).
+    pub mac_max_frame_retries: u16,
+    /// Whether the coordinator accepts association requests (default =
+    /// false). Advertised in the beacons of the coordinator.
+    pub mac_association_permit: bool,
+    /// The CCA mode used before transmitting a frame (default = energy
+    /// detection).
+    pub phy_cca_mode: CcaMode,
+    /// The channel page of `phy_current_channel` (default = page 0).
+    pub phy_current_page: ChannelPage,
+    /// The frame counter of the next secured frame that is transmitted
+    /// (default = 0). It is part of the nonce of the CCM* transform, so it
+    /// must never go back while the keys remain in use, see [`Pib::save`].
+    pub mac_frame_counter: u32,
+}
+
+impl Default for Pib {
+    fn default() -> Self {
+        Self {
+            phy_tx_power: 0,
+            mac_rx_on_when_idle: true,
+            phy_current_channel: Channel::_26,
+            mac_pan_id: MAC_PAN_ID,
+            mac_short_address: 0xffff,
+            mac_implicit_broadcast: MAC_IMPLICIT_BROADCAST,
+            mac_extended_address: None,
+            mac_security_enabled: false,
+            mac_tsch_enabled: false,
+            mac_min_be: MAC_MIN_BE,
+            mac_max_be: MAC_MAX_BE,
+            mac_max_csma_backoffs: MAC_MAX_CSMA_BACKOFFS,
+            mac_max_frame_retries: MAC_MAX_FRAME_RETIES,
+            mac_association_permit: false,
+            phy_cca_mode: CcaMode::EnergyDetection,
+            phy_current_page: ChannelPage::Page0,
+            mac_frame_counter: 0,
+        }
+    }
+}
+
+impl Pib {
+    /// MLME-GET: return the current value of an attribute.
+    pub fn get(&self, id: PibAttributeId) -> PibAttribute {
+        match id {
+            PibAttributeId::PhyTxPower => PibAttribute::PhyTxPower(self.phy_tx_power),
+            PibAttributeId::MacRxOnWhenIdle => {
+                PibAttribute::MacRxOnWhenIdle(self.mac_rx_on_when_idle)
+            }
+            PibAttributeId::PhyCurrentChannel => {
+                PibAttribute::PhyCurrentChannel(self.phy_current_channel)
+            }
+            PibAttributeId::MacPanId => PibAttribute::MacPanId(self.mac_pan_id),
+            PibAttributeId::MacShortAddress => {
+                PibAttribute::MacShortAddress(self.mac_short_address)
+            }
+            PibAttributeId::MacImplicitBroadcast => {
+                PibAttribute::MacImplicitBroadcast(self.mac_implicit_broadcast)
+            }
+            PibAttributeId::MacSecurityEnabled => {
+                PibAttribute::MacSecurityEnabled(self.mac_security_enabled)
+            }
+            PibAttributeId::MacTschEnabled => PibAttribute::MacTschEnabled(self.mac_tsch_enabled),
+            PibAttributeId::MacMinBe => PibAttribute::MacMinBe(self.mac_min_be),
+            PibAttributeId::MacMaxBe => PibAttribute::MacMaxBe(self.mac_max_be),
+            PibAttributeId::MacMaxCsmaBackoffs => {
+                PibAttribute::MacMaxCsmaBackoffs(self.mac_max_csma_backoffs)
+            }
+            PibAttributeId::MacMaxFrameRetries => {
+                PibAttribute::MacMaxFrameRetries(self.mac_max_frame_retries)
+            }
+            PibAttributeId::MacAssociationPermit => {
+                PibAttribute::MacAssociationPermit(self.mac_association_permit)
+            }
+            PibAttributeId::PhyCcaMode => PibAttribute::PhyCcaMode(self.phy_cca_mode),
+            PibAttributeId::PhyCurrentPage => PibAttribute::PhyCurrentPage(self.phy_current_page),
+            PibAttributeId::MacFrameCounter => {
+                PibAttribute::MacFrameCounter(self.mac_frame_counter)
+            }
+        }
+    }
+
+    /// Return the PHY attributes, from which the configuration of the radio
+    /// is derived.
+    pub fn phy(&self) -> PhyPib {
+        PhyPib {
+            current_channel: self.phy_current_channel,
+            current_page: self.phy_current_page,
+            cca_mode: self.phy_cca_mode,
+            tx_power: self.phy_tx_power,
+        }
+    }
+
+    /// MLME-SET: write the value of an attribute.
+    pub fn set(&mut self, attribute: PibAttribute) -> Result<(), PibError> {
+        let mut pib = *self;
+        match attribute {
+            PibAttribute::PhyTxPower(power) => pib.phy_tx_power = power,
+            PibAttribute::MacRxOnWhenIdle(rx_on) => pib.mac_rx_on_when_idle = rx_on,
+            PibAttribute::PhyCurrentChannel(channel) => pib.phy_current_channel = channel,
+            PibAttribute::MacPanId(pan_id) => pib.mac_pan_id = pan_id,
+            PibAttribute::MacShortAddress(address) => pib.mac_short_address = address,
+            PibAttribute::MacImplicitBroadcast(enabled) => pib.mac_implicit_broadcast = enabled,
+            PibAttribute::MacSecurityEnabled(enabled) => pib.mac_security_enabled = enabled,
+            PibAttribute::MacTschEnabled(_) => return Err(PibError::ReadOnly),
+            PibAttribute::MacMinBe(be) => pib.mac_min_be = be,
+            PibAttribute::MacMaxBe(be) => pib.mac_max_be = be,
+            PibAttribute::MacMaxCsmaBackoffs(backoffs) => pib.mac_max_csma_backoffs = backoffs,
+            PibAttribute::MacMaxFrameRetries(retries) => pib.mac_max_frame_retries = retries,
+            PibAttribute::MacAssociationPermit(permit) => pib.mac_association_permit = permit,
+            PibAttribute::PhyCcaMode(mode) => pib.phy_cca_mode = mode,
+            PibAttribute::PhyCurrentPage(page) => pib.phy_current_page = page,
+            PibAttribute::MacFrameCounter(counter) => pib.mac_frame_counter = counter,
+        }
+
+        if !pib.has_valid_csma_attributes() || !pib.has_valid_channel() {
+            return Err(PibError::InvalidParameter);
+        }
+        *self = pib;
+        Ok(())
+    }
+
+    /// The length of the encoding of [`Pib::save`].
+    pub const SAVED_LEN: usize = 30;
+
+    /// Write the attributes to `buffer`, e.g. to persist them in flash across
+    /// a reboot or deep sleep, and return the number of bytes written, which
+    /// is [`Pib::SAVED_LEN`].
+    ///
+    /// The encoding is versioned and stable, multi-byte values are little
+    /// endian:
+    ///
+    /// | Offset | Length | Value                                         |
+    /// |--------|--------|-----------------------------------------------|
+    /// | 0      | 1      | version, 1                                    |
+    /// | 1      | 1      | flags: rx on when idle (bit 0), implicit      |
+    /// |        |        | broadcast (1), security (2), TSCH (3),        |
+    /// |        |        | extended address present (4), association     |
+    /// |        |        | permit (5)                                    |
+    /// | 2      | 1      | `phyTxPower`                                  |
+    /// | 3      | 1      | `phyCurrentChannel`                           |
+    /// | 4      | 2      | `macPanId`                                    |
+    /// | 6      | 8      | `macExtendedAddress`, zeros when not present  |
+    /// | 14     | 2      | `macMinBE`                                    |
+    /// | 16     | 2      | `macMaxBE`                                    |
+    /// | 18     | 2      | `macMaxCSMABackoffs`                          |
+    /// | 20     | 2      | `macMaxFrameRetries`                          |
+    /// | 22     | 2      | `macShortAddress`                             |
+    /// | 24     | 1      | `phyCcaMode`, 0 when no CCA is performed      |
+    /// | 25     | 1      | `phyCurrentPage`                              |
+    /// | 26     | 4      | `macFrameCounter`                             |
+    ///
+    /// `macFrameCounter` is saved as well, such that a restored device does
+    /// not reuse the CCM* nonces of the frames it secured before. As it
+    /// advances with every secured frame, the PIB should be saved again right
+    /// before going to sleep.
+    ///
+    /// # Panics
+    /// Panics when `buffer` is shorter than [`Pib::SAVED_LEN`].
+    pub fn save(&self, buffer: &mut [u8]) -> usize {
+        let buffer = &mut buffer[..Self::SAVED_LEN];
+
+        let mut flags = 0;
+        for (enabled, flag) in [
+            (self.mac_rx_on_when_idle, FLAG_RX_ON_WHEN_IDLE),
+            (self.mac_implicit_broadcast, FLAG_IMPLICIT_BROADCAST),
+            (self.mac_security_enabled, FLAG_SECURITY_ENABLED),
+            (self.mac_tsch_enabled, FLAG_TSCH_ENABLED),
+            (self.mac_extended_address.is_some(), FLAG_EXTENDED_ADDRESS),
+            (self.mac_association_permit, FLAG_ASSOCIATION_PERMIT),
+        ] {
+            if enabled {
+                flags |= flag;
+            }
+        }
+
+        buffer[0] = SAVE_VERSION;
+        buffer[1] = flags;
+        buffer[2] = self.phy_tx_power as u8;
+        buffer[3] = self.phy_current_channel.into();
+        buffer[4..6].copy_from_slice(&self.mac_pan_id.to_le_bytes());
+        buffer[6..14].copy_from_slice(&self.mac_extended_address.unwrap_or_default());
+        buffer[14..16].copy_from_slice(&self.mac_min_be.to_le_bytes());
+        buffer[16..18].copy_from_slice(&self.mac_max_be.to_le_bytes());
+        buffer[18..20].copy_from_slice(&self.mac_max_csma_backoffs.to_le_bytes());
+        buffer[20..22].copy_from_slice(&self.mac_max_frame_retries.to_le_bytes());
+        buffer[22..24].copy_from_slice(&self.mac_short_address.to_le_bytes());
+        buffer[24] = self.phy_cca_mode.into();
+        buffer[25] = self.phy_current_page.into();
+        buffer[26..30].copy_from_slice(&self.mac_frame_counter.to_le_bytes());
+
+        Self::SAVED_LEN
+    }
+
+    /// Read the attributes written by [`Pib::save`].
+    ///
+    /// Fails with [`PibError::InvalidParameter`] when the buffer is too short,
+    /// was written by an unknown version, or holds attributes that
+    /// [`PibBuilder::build`] rejects.
+    pub fn restore(buffer: &[u8]) -> Result<Pib, PibError> {
+        if buffer.first() != Some(&SAVE_VERSION) {
+            return Err(PibError::InvalidParameter);
+        }
+        let Some(buffer) = buffer.get(..Self::SAVED_LEN) else {
+            return Err(PibError::InvalidParameter);
+        };
+
+        let flags = buffer[1];
+        let u16_at = |offset: usize| u16::from_le_bytes([buffer[offset], buffer[offset + 1]]);
+        let pib = Pib {
+            phy_tx_power: buffer[2] as i8,
+            mac_rx_on_when_idle: flags & FLAG_RX_ON_WHEN_IDLE != 0,
+            phy_current_channel: Channel::try_from(buffer[3] as i32)
+                .map_err(|_| PibError::InvalidParameter)?,
+            mac_pan_id: u16_at(4),
+            mac_short_address: u16_at(22),
+            mac_implicit_broadcast: flags & FLAG_IMPLICIT_BROADCAST != 0,
+            mac_extended_address: (flags & FLAG_EXTENDED_ADDRESS != 0)
+                .then(|| buffer[6..14].try_into().unwrap()),
+            mac_security_enabled: flags & FLAG_SECURITY_ENABLED != 0,
+            mac_tsch_enabled: flags & FLAG_TSCH_ENABLED != 0,
+            mac_min_be: u16_at(14),
+            mac_max_be: u16_at(16),
+            mac_max_csma_backoffs: u16_at(18),
+            mac_max_frame_retries: u16_at(20),
+            mac_association_permit: flags & FLAG_ASSOCIATION_PERMIT != 0,
+            phy_cca_mode: CcaMode::try_from(buffer[24]).map_err(|_| PibError::InvalidParameter)?,
+            phy_current_page: ChannelPage::try_from(buffer[25])
+                .map_err(|_| PibError::InvalidParameter)?,
+            mac_frame_counter: u32::from_le_bytes(buffer[26..30].try_into().unwrap()),
+        };
+
+        PibBuilder { pib }.build()
+    }
+
+    /// Whether the CSMA-CA attributes are in the ranges of IEEE 802.15.4:
+    /// `macMinBE` is at most `macMaxBE`, which is in 3..=8,
+    /// `macMaxCSMABackoffs` is at most 5 and `macMaxFrameRetries` at most 7.
+    fn has_valid_csma_attributes(&self) -> bool {
+        self.mac_min_be <= self.mac_max_be
+            && (3..=8).contains(&self.mac_max_be)
+            && self.mac_max_csma_backoffs <= 5
+            && self.mac_max_frame_retries <= 7
+    }
+
+    /// Whether the current channel is part of the current channel page.
+    fn has_valid_channel(&self) -> bool {
+        self.phy_current_page.supports(self.phy_current_channel)
+    }
+}
+
+/// Builder for the initial values of the [`Pib`].
+///
+/// Attributes that are not set keep the default value of the standard, see
+/// the fields of [`Pib`]:
+///
+/// ```
+/// # use dot15d4::mlme::pib::PibBuilder;
+/// # use dot15d4::phy::config::Channel;
+/// let pib = PibBuilder::new()
+///     .pan_id(0xabcd)
+///     .channel(Channel::_15)
+///     .security_enabled(true)
+///     .build()
+///     .unwrap();
+/// assert_eq!(pib.mac_pan_id, 0xabcd);
+/// assert!(!pib.mac_tsch_enabled);
+/// ```
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PibBuilder {
+    pib: Pib,
+}
+
+impl PibBuilder {
+    /// Create a new [`PibBuilder`] with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the extended address of the device, instead of using the address
+    /// of the radio.
+    pub fn extended_address(mut self, address: [u8; 8]) -> Self {
+        self.pib.mac_extended_address = Some(address);
+        self
+    }
+
+    /// Set the identifier of the PAN.
+    pub fn pan_id(mut self, pan_id: u16) -> Self {
+        self.pib.mac_pan_id = pan_id;
+        self
+    }
+
+    /// Set the short address of the device.
+    pub fn short_address(mut self, address: u16) -> Self {
+        self.pib.mac_short_address = address;
+        self
+    }
+
+    /// Set whether frames without destination address and destination PAN ID
+    /// are accepted as broadcast frames.
+    pub fn implicit_broadcast(mut self, enabled: bool) -> Self {
+        self.pib.mac_implicit_broadcast = enabled;
+        self
+    }
+
+    /// Set the channel on which is transmitted and received.
+    pub fn channel(mut self, channel: Channel) -> Self {
+        self.pib.phy_current_channel = channel;
+        self
+    }
+
+    /// Set the channel page of the channel.
+    pub fn channel_page(mut self, page: ChannelPage) -> Self {
+        self.pib.phy_current_page = page;
+        self
+    }
+
+    /// Set the transmit power of the radio, in dBm.
+    pub fn tx_power(mut self, power: i8) -> Self {
+        self.pib.phy_tx_power = power;
+        self
+    }
+
+    /// Set the CCA mode used before transmitting a frame.
+    pub fn cca_mode(mut self, mode: CcaMode) -> Self {
+        self.pib.phy_cca_mode = mode;
+        self
+    }
+
+    /// Set whether the receiver is enabled while the device is idle.
+    pub fn rx_on_when_idle(mut self, rx_on: bool) -> Self {
+        self.pib.mac_rx_on_when_idle = rx_on;
+        self
+    }
+
+    /// Enable or disable the security of frames.
+    pub fn security_enabled(mut self, enabled: bool) -> Self {
+        self.pib.mac_security_enabled = enabled;
+        self
+    }
+
+    /// Enable or disable the TSCH mode.
+    pub fn tsch_enabled(mut self, enabled: bool) -> Self {
+        self.pib.mac_tsch_enabled = enabled;
+        self
+    }
+
+    /// Set the smallest and largest backoff exponent of CSMA-CA.
+    pub fn backoff_exponents(mut self, min_be: u16, max_be: u16) -> Self {
+        self.pib.mac_min_be = min_be;
+        self.pib.mac_max_be = max_be;
+        self
+    }
+
+    /// Set the number of backoffs after a busy channel before a channel
+    /// access failure is reported.
+    pub fn max_csma_backoffs(mut self, backoffs: u16) -> Self {
+        self.pib.mac_max_csma_backoffs = backoffs;
+        self
+    }
+
+    /// Set the number of retransmissions of a frame of which no
+    /// acknowledgment is received.
+    pub fn max_frame_retries(mut self, retries: u16) -> Self {
+        self.pib.mac_max_frame_retries = retries;
+        self
+    }
+
+    /// Set whether the coordinator accepts association requests.
+    pub fn association_permit(mut self, permit: bool) -> Self {
+        self.pib.mac_association_permit = permit;
+        self
+    }
+
+    /// Validate the attributes and return the [`Pib`].
+    ///
+    /// Fails with [`PibError::InvalidParameter`] when the extended address is
+    /// all zeros or the broadcast address, when the channel is not part of
+    /// the channel page, when TSCH is enabled on a sub-GHz channel, as the
+    /// default hopping sequence only covers the 2450 MHz band, or when a
+    /// CSMA-CA attribute is out of range.
+    pub fn build(self) -> Result<Pib, PibError> {
+        let pib = self.pib;
+
+        if matches!(pib.mac_extended_address, Some(address) if address == [0x00; 8] || address == [0xff; 8])
+        {
+            return Err(PibError::InvalidParameter);
+        }
+        if !pib.has_valid_channel() {
+            return Err(PibError::InvalidParameter);
+        }
+        if pib.mac_tsch_enabled && pib.phy_current_channel.is_sub_ghz() {
+            return Err(PibError::InvalidParameter);
+        }
+        if !pib.has_valid_csma_attributes() {
+            return Err(PibError::InvalidParameter);
+        }
+
+        Ok(pib)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_set() {
+        let mut pib = Pib::default();
+        assert_eq!(
+            pib.get(PibAttributeId::PhyTxPower),
+            PibAttribute::PhyTxPower(0)
+        );
+
+        assert_eq!(pib.set(PibAttribute::PhyTxPower(-8)), Ok(()));
+        assert_eq!(
+            pib.get(PibAttributeId::PhyTxPower),
+            PibAttribute::PhyTxPower(-8)
+        );
+        assert_eq!(pib.phy_tx_power, -8);
+
+        assert_eq!(
+            pib.get(PibAttributeId::MacRxOnWhenIdle),
+            PibAttribute::MacRxOnWhenIdle(true)
+        );
+        assert_eq!(pib.set(PibAttribute::MacRxOnWhenIdle(false)), Ok(()));
+        assert!(!pib.mac_rx_on_when_idle);
+
+        assert_eq!(
+            pib.set(PibAttribute::PhyCurrentChannel(Channel::_11)),
+            Ok(())
+        );
+        assert_eq!(
+            pib.get(PibAttributeId::PhyCurrentChannel),
+            PibAttribute::PhyCurrentChannel(Channel::_11)
+        );
+
+        assert_eq!(
+            pib.get(PibAttributeId::MacPanId),
+            PibAttribute::MacPanId(MAC_PAN_ID)
+        );
+        assert_eq!(pib.set(PibAttribute::MacPanId(0x1234)), Ok(()));
+        assert_eq!(pib.mac_pan_id, 0x1234);
+
+        assert_eq!(
+            pib.get(PibAttributeId::MacShortAddress),
+            PibAttribute::MacShortAddress(0xffff)
+        );
+        assert_eq!(pib.set(PibAttribute::MacShortAddress(0x0001)), Ok(()));
+        assert_eq!(pib.mac_short_address, 0x0001);
+
+        assert_eq!(pib.set(PibAttribute::MacImplicitBroadcast(true)), Ok(()));
+        assert_eq!(
+            pib.get(PibAttributeId::MacImplicitBroadcast),
+            PibAttribute::MacImplicitBroadcast(true)
+        );
+
+        assert_eq!(pib.set(PibAttribute::MacSecurityEnabled(true)), Ok(()));
+        assert!(pib.mac_security_enabled);
+        assert_eq!(
+            pib.set(PibAttribute::MacTschEnabled(true)),
+            Err(PibError::ReadOnly)
+        );
+        assert_eq!(
+            pib.get(PibAttributeId::MacTschEnabled),
+            PibAttribute::MacTschEnabled(false)
+        );
+
+        assert_eq!(pib.set(PibAttribute::MacAssociationPermit(true)), Ok(()));
+        assert_eq!(
+            pib.get(PibAttributeId::MacAssociationPermit),
+            PibAttribute::MacAssociationPermit(true)
+        );
+
+        assert_eq!(
+            pib.get(PibAttributeId::PhyCcaMode),
+            PibAttribute::PhyCcaMode(CcaMode::EnergyDetection)
+        );
+        assert_eq!(pib.set(PibAttribute::PhyCcaMode(CcaMode::None)), Ok(()));
+        assert_eq!(pib.phy_cca_mode, CcaMode::None);
+
+        assert_eq!(
+            pib.get(PibAttributeId::PhyCurrentPage),
+            PibAttribute::PhyCurrentPage(ChannelPage::Page0)
+        );
+    }
+
+    #[test]
+    fn channel_page() {
+        let mut pib = Pib::default();
+
+        // Page 1 has no 2450 MHz channels, so the channel has to change first
+        let valid = pib;
+        assert_eq!(
+            pib.set(PibAttribute::PhyCurrentPage(ChannelPage::Page1)),
+            Err(PibError::InvalidParameter)
+        );
+        assert_eq!(pib, valid);
+
+        assert_eq!(
+            pib.set(PibAttribute::PhyCurrentChannel(Channel::_1)),
+            Ok(())
+        );
+        assert_eq!(
+            pib.set(PibAttribute::PhyCurrentPage(ChannelPage::Page1)),
+            Ok(())
+        );
+        assert_eq!(
+            pib.get(PibAttributeId::PhyCurrentPage),
+            PibAttribute::PhyCurrentPage(ChannelPage::Page1)
+        );
+        assert_eq!(pib.phy().current_page, ChannelPage::Page1);
+
+        let valid = pib;
+        assert_eq!(
+            pib.set(PibAttribute::PhyCurrentChannel(Channel::_26)),
+            Err(PibError::InvalidParameter)
+        );
+        assert_eq!(pib, valid);
+
+        assert_eq!(
+            PibBuilder::new()
+                .channel(Channel::_26)
+                .channel_page(ChannelPage::Page2)
+                .build(),
+            Err(PibError::InvalidParameter)
+        );
+        assert!(PibBuilder::new()
+            .channel(Channel::_0)
+            .channel_page(ChannelPage::Page2)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn phy_attributes() {
+        let mut pib = Pib::default();
+        pib.set(PibAttribute::PhyCurrentChannel(Channel::_15))
+            .unwrap();
+        pib.set(PibAttribute::PhyCcaMode(CcaMode::CarrierSense))
+            .unwrap();
+        pib.set(PibAttribute::PhyTxPower(-4)).unwrap();
+        assert_eq!(
+            pib.phy(),
+            PhyPib {
+                current_channel: Channel::_15,
+                current_page: ChannelPage::Page0,
+                cca_mode: CcaMode::CarrierSense,
+                tx_power: -4,
+            }
+        );
+
+        for (attribute, is_phy) in [
+            (PibAttribute::PhyTxPower(0), true),
+            (PibAttribute::PhyCurrentChannel(Channel::_26), true),
+            (PibAttribute::PhyCcaMode(CcaMode::None), true),
+            (PibAttribute::PhyCurrentPage(ChannelPage::Page0), true),
+            (PibAttribute::MacPanId(0x1234), false),
+            (PibAttribute::MacRxOnWhenIdle(true), false),
+        ] {
+            assert_eq!(attribute.id().is_phy(), is_phy);
+        }
+    }
+
+    #[test]
+    fn csma_attributes() {
+        let mut pib = Pib::default();
+        assert_eq!(
+            pib.get(PibAttributeId::MacMaxFrameRetries),
+            PibAttribute::MacMaxFrameRetries(MAC_MAX_FRAME_RETIES)
+        );
+        assert_eq!(pib.set(PibAttribute::MacMaxFrameRetries(5)), Ok(()));
+        assert_eq!(pib.mac_max_frame_retries, 5);
+        assert_eq!(pib.set(PibAttribute::MacMaxCsmaBackoffs(4)), Ok(()));
+        assert_eq!(
+            pib.get(PibAttributeId::MacMaxCsmaBackoffs),
+            PibAttribute::MacMaxCsmaBackoffs(4)
+        );
+
+        assert_eq!(pib.set(PibAttribute::MacMaxBe(5)), Ok(()));
+        assert_eq!(pib.set(PibAttribute::MacMinBe(3)), Ok(()));
+        assert_eq!(pib.get(PibAttributeId::MacMinBe), PibAttribute::MacMinBe(3));
+        assert_eq!(pib.get(PibAttributeId::MacMaxBe), PibAttribute::MacMaxBe(5));
+
+        // Invalid values leave the PIB untouched
+        let valid = pib;
+        for attribute in [
+            PibAttribute::MacMinBe(6),
+            PibAttribute::MacMaxBe(2),
+            PibAttribute::MacMaxBe(9),
+            PibAttribute::MacMaxCsmaBackoffs(6),
+            PibAttribute::MacMaxFrameRetries(8),
+        ] {
+            assert_eq!(pib.set(attribute), Err(PibError::InvalidParameter));
+            assert_eq!(pib, valid);
+        }
+
+        // The bounds of the ranges are valid
+        for attribute in [
+            PibAttribute::MacMaxCsmaBackoffs(0),
+            PibAttribute::MacMaxCsmaBackoffs(5),
+            PibAttribute::MacMaxFrameRetries(0),
+            PibAttribute::MacMaxFrameRetries(7),
+            PibAttribute::MacMaxBe(8),
+            PibAttribute::MacMinBe(0),
+            PibAttribute::MacMaxBe(3),
+        ] {
+            assert_eq!(pib.set(attribute), Ok(()));
+            assert_eq!(pib.get(attribute.id()), attribute);
+        }
+    }
+
+    #[test]
+    fn builder() {
+        assert_eq!(PibBuilder::new().build(), Ok(Pib::default()));
+
+        let pib = PibBuilder::new()
+            .extended_address([1, 2, 3, 4, 5, 6, 7, 8])
+            .pan_id(0xabcd)
+            .short_address(0x0001)
+            .channel(Channel::_20)
+            .tx_power(4)
+            .rx_on_when_idle(false)
+            .security_enabled(true)
+            .tsch_enabled(true)
+            .backoff_exponents(3, 5)
+            .max_csma_backoffs(4)
+            .max_frame_retries(7)
+            .association_permit(true)
+            .cca_mode(CcaMode::None)
+            .build()
+            .unwrap();
+        assert_eq!(pib.mac_extended_address, Some([1, 2, 3, 4, 5, 6, 7, 8]));
+        assert_eq!(pib.mac_pan_id, 0xabcd);
+        assert_eq!(pib.mac_short_address, 0x0001);
+        assert_eq!(pib.phy_current_channel, Channel::_20);
+        assert_eq!(pib.phy_tx_power, 4);
+        assert!(!pib.mac_rx_on_when_idle);
+        assert!(pib.mac_security_enabled);
+        assert!(pib.mac_tsch_enabled);
+        assert_eq!((pib.mac_min_be, pib.mac_max_be), (3, 5));
+        assert_eq!(pib.mac_max_csma_backoffs, 4);
+        assert_eq!(pib.mac_max_frame_retries, 7);
+        assert!(pib.mac_association_permit);
+        assert_eq!(pib.phy_cca_mode, CcaMode::None);
+
+        assert_eq!(
+            PibBuilder::new().extended_address([0xff; 8]).build(),
+            Err(PibError::InvalidParameter)
+        );
+        assert_eq!(
+            PibBuilder::new()
+                .channel(Channel::_1)
+                .tsch_enabled(true)
+                .build(),
+            Err(PibError::InvalidParameter)
+        );
+        assert_eq!(
+            PibBuilder::new().backoff_exponents(5, 3).build(),
+            Err(PibError::InvalidParameter)
+        );
+    }
+
+    #[test]
+    fn save_restore() {
+        let pib = PibBuilder::new()
+            .extended_address([1, 2, 3, 4, 5, 6, 7, 8])
+            .pan_id(0xabcd)
+            .short_address(0x1234)
+            .channel(Channel::_20)
+            .tx_power(-4)
+            .rx_on_when_idle(false)
+            .tsch_enabled(true)
+            .backoff_exponents(3, 5)
+            .max_csma_backoffs(4)
+            .max_frame_retries(3)
+            .association_permit(true)
+            .cca_mode(CcaMode::CarrierSense)
+            .build()
+            .unwrap();
+        let pib = Pib {
+            mac_frame_counter: 0x0102_0304,
+            ..pib
+        };
+
+        let mut buffer = [0; 32];
+        assert_eq!(pib.save(&mut buffer), Pib::SAVED_LEN);
+        assert_eq!(
+            buffer[..Pib::SAVED_LEN],
+            [
+                0x01, 0x38, 0xfc, 0x14, 0xcd, 0xab, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+                0x03, 0x00, 0x05, 0x00, 0x04, 0x00, 0x03, 0x00, 0x34, 0x12, 0x02, 0x00, 0x04, 0x03,
+                0x02, 0x01
+            ]
+        );
+        assert_eq!(Pib::restore(&buffer), Ok(pib));
+
+        assert_eq!(Pib::default().save(&mut buffer), Pib::SAVED_LEN);
+        assert_eq!(Pib::restore(&buffer), Ok(Pib::default()));
+
+        // Truncated, unknown versions and invalid attributes are rejected
+        assert_eq!(
+            Pib::restore(&buffer[..Pib::SAVED_LEN - 1]),
+            Err(PibError::InvalidParameter)
+        );
+        for (offset, value) in [(0, 2), (3, 27), (16, 40), (24, 4), (25, 1), (25, 3)] {
+            let mut invalid = buffer;
+            invalid[offset] = value;
+            assert_eq!(Pib::restore(&invalid), Err(PibError::InvalidParameter));
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! MLME-POLL.
+//!
+//! A device that does not keep its receiver enabled polls its coordinator
+//! for pending data by transmitting a Data Request command. The coordinator
+//! indicates in the acknowledgment whether data is pending, after which the
+//! data frame is expected within `macMaxFrameTotalWaitTime`.
+
+use dot15d4_frame::Address;
+
+/// MLME-POLL.request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollRequest {
+    /// The address of the coordinator to poll.
+    pub coord_address: Address,
+    /// The PAN ID of the coordinator to poll.
+    pub coord_pan_id: u16,
+}
+
+/// Status of MLME-POLL.confirm.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollStatus {
+    /// Data was pending and has been received. The data is handed to the
+    /// upper layer as any other received frame.
+    Success,
+    /// The coordinator has no data pending, or the pending data was not
+    /// received in time.
+    NoData,
+    /// The Data Request command was not acknowledged.
+    NoAck,
+    /// The Data Request command could not be transmitted, because the channel
+    /// was busy.
+    ChannelAccessFailure,
+    /// The Data Request command could not be transmitted, because the radio
+    /// failed.
+    RadioFailure,
+    /// The request contains an invalid coordinator address.
+    InvalidParameter,
+}
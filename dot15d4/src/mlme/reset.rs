@@ -0,0 +1,19 @@
+//! MLME-RESET.
+//!
+//! Resets the MAC sublayer while it is running, such that the upper layer can
+//! recover from fatal errors without rebooting the device. The radio is
+//! disabled and enabled again, the neighbor and group tables are cleared, and
+//! the PIB is optionally set back to its initial values. The confirmation is
+//! [`MacConfirm::Reset`].
+//!
+//! [`MacConfirm::Reset`]: super::MacConfirm::Reset
+
+/// MLME-RESET.request.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResetRequest {
+    /// Whether the PIB attributes are set back to the values the device was
+    /// created with, clearing the device and key tables. The extended address
+    /// and the outgoing frame counter of the device are kept.
+    pub set_default_pib: bool,
+}
@@ -0,0 +1,156 @@
+//! Allocation of short addresses to associating devices.
+//!
+//! Short addresses are allocated either sequentially or at random. An address
+//! is never allocated twice: addresses already in use by a neighbor are
+//! skipped, as are the reserved addresses `0xfffe` and `0xffff`.
+
+use rand_core::RngCore;
+
+use crate::csma::neighbors::NeighborTable;
+
+/// The highest short address that can be allocated.
+const MAX_SHORT_ADDRESS: u16 = 0xfffd;
+
+/// The number of random addresses tried before falling back to a sequential
+/// search.
+const RANDOM_ATTEMPTS: usize = 8;
+
+/// How short addresses are chosen.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AllocationMode {
+    /// Allocate increasing addresses, wrapping around after `0xfffd`.
+    #[default]
+    Sequential,
+    /// Allocate random addresses, which makes collisions between the address
+    /// spaces of multiple coordinators less likely.
+    Random,
+}
+
+/// Allocates unused short addresses.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShortAddressAllocator {
+    mode: AllocationMode,
+    /// The next address to try, in sequential mode.
+    next: u16,
+    /// An address that is never allocated, e.g. the address of the
+    /// coordinator itself.
+    reserved: Option<u16>,
+}
+
+impl Default for ShortAddressAllocator {
+    fn default() -> Self {
+        Self::new(AllocationMode::default())
+    }
+}
+
+impl ShortAddressAllocator {
+    /// Create a new [`ShortAddressAllocator`], starting at address `0x0001`.
+    pub const fn new(mode: AllocationMode) -> Self {
+        Self {
+            mode,
+            next: 1,
+            reserved: None,
+        }
+    }
+
+    /// Never allocate `address`, in the same byte order as
+    /// [`Address::Short`](dot15d4_frame::Address::Short).
+    pub fn reserve(&mut self, address: [u8; 2]) {
+        self.reserved = Some(u16::from_le_bytes(address));
+    }
+
+    /// Return the next address that is tried in sequential mode.
+    pub fn next(&self) -> u16 {
+        self.next
+    }
+
+    /// Set the next address that is tried in sequential mode, e.g. when
+    /// restoring a persisted state.
+    pub fn set_next(&mut self, next: u16) {
+        self.next = next;
+    }
+
+    /// Allocate an address that is not used by any of the `neighbors`, in the
+    /// same byte order as [`Address::Short`](dot15d4_frame::Address::Short).
+    ///
+    /// Returns `None` when no address is left.
+    pub fn allocate<const N: usize>(
+        &mut self,
+        neighbors: &NeighborTable<N>,
+        rng: &mut impl RngCore,
+    ) -> Option<[u8; 2]> {
+        let is_free = |address: u16| {
+            address <= MAX_SHORT_ADDRESS
+                && Some(address) != self.reserved
+                && neighbors
+                    .iter()
+                    .all(|neighbor| neighbor.short_address != address.to_le_bytes())
+        };
+
+        if self.mode == AllocationMode::Random {
+            if let Some(address) = (0..RANDOM_ATTEMPTS)
+                .map(|_| (rng.next_u32() % (MAX_SHORT_ADDRESS as u32 + 1)) as u16)
+                .find(|address| is_free(*address))
+            {
+                return Some(address.to_le_bytes());
+            }
+        }
+
+        // At most N + 1 addresses are in use, so this ends quickly
+        let address = (0..=MAX_SHORT_ADDRESS)
+            .map(|offset| {
+                ((self.next as u32 + offset as u32) % (MAX_SHORT_ADDRESS as u32 + 1)) as u16
+            })
+            .find(|address| is_free(*address))?;
+        if self.mode == AllocationMode::Sequential {
+            self.next = address.wrapping_add(1) % (MAX_SHORT_ADDRESS + 1);
+        }
+        Some(address.to_le_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::mock::StepRng;
+
+    use super::*;
+    use crate::csma::neighbors::Neighbor;
+
+    #[test]
+    fn allocate() {
+        let mut rng = StepRng::new(0x1234, 1);
+        let mut neighbors = NeighborTable::<4>::new();
+        neighbors
+            .insert(Neighbor {
+                extended_address: [1; 8],
+                short_address: [0x02, 0x00],
+                tx_power: None,
+            })
+            .unwrap();
+
+        let mut allocator = ShortAddressAllocator::new(AllocationMode::Sequential);
+        allocator.reserve([0x01, 0x00]);
+        assert_eq!(allocator.allocate(&neighbors, &mut rng), Some([0x03, 0x00]));
+        assert_eq!(allocator.allocate(&neighbors, &mut rng), Some([0x04, 0x00]));
+
+        // Wraps around, skipping the reserved addresses
+        allocator.set_next(0xfffd);
+        assert_eq!(allocator.allocate(&neighbors, &mut rng), Some([0xfd, 0xff]));
+        assert_eq!(allocator.allocate(&neighbors, &mut rng), Some([0x00, 0x00]));
+        assert_eq!(allocator.allocate(&neighbors, &mut rng), Some([0x03, 0x00]));
+
+        // Taken random addresses are skipped
+        let mut allocator = ShortAddressAllocator::new(AllocationMode::Random);
+        neighbors
+            .insert(Neighbor {
+                extended_address: [2; 8],
+                short_address: [0x34, 0x12],
+                tx_power: None,
+            })
+            .unwrap();
+        assert_eq!(allocator.allocate(&neighbors, &mut rng), Some([0x35, 0x12]));
+        assert_eq!(allocator.next(), 1);
+    }
+}
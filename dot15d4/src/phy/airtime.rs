@@ -0,0 +1,119 @@
+//! Estimation of the time a frame is on the air.
+//!
+//! A PPDU consists of the synchronization header (SHR), the PHY header (PHR)
+//! and the PSDU, the frame including its FCS. [`PhyParameters`] describe how
+//! long each part takes for a PHY, such that the airtime of a frame can be
+//! checked against e.g. the `max_tx` of a TSCH timeslot, or used for
+//! admission control.
+
+use dot15d4_frame::{FrameRepr, TschTimeslotTimings};
+
+use crate::time::Duration;
+
+/// The length of the FCS, in octets.
+const FCS_LEN: usize = 2;
+
+/// The timing parameters of a PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhyParameters {
+    /// The duration of a symbol, in microseconds.
+    pub symbol_duration_us: u32,
+    /// The number of symbols per octet.
+    pub symbols_per_octet: u32,
+    /// The length of the SHR (preamble and SFD), in symbols.
+    pub shr_symbols: u32,
+    /// The length of the PHR, in symbols.
+    pub phr_symbols: u32,
+}
+
+impl PhyParameters {
+    /// 2450 MHz O-QPSK, 250 kb/s.
+    pub const O_QPSK_2450: Self = Self {
+        symbol_duration_us: 16,
+        symbols_per_octet: 2,
+        shr_symbols: 10,
+        phr_symbols: 2,
+    };
+
+    /// 868 MHz BPSK, 20 kb/s.
+    pub const BPSK_868: Self = Self {
+        symbol_duration_us: 50,
+        symbols_per_octet: 8,
+        shr_symbols: 40,
+        phr_symbols: 8,
+    };
+
+    /// 915 MHz BPSK, 40 kb/s.
+    pub const BPSK_915: Self = Self {
+        symbol_duration_us: 25,
+        symbols_per_octet: 8,
+        shr_symbols: 40,
+        phr_symbols: 8,
+    };
+
+    /// Return the duration of `symbols` symbols.
+    pub const fn symbols(&self, symbols: u32) -> Duration {
+        Duration::from_us(symbols as i64 * self.symbol_duration_us as i64)
+    }
+
+    /// Return the airtime of a PSDU of `psdu_len` octets, including the SHR
+    /// and the PHR.
+    pub const fn airtime(&self, psdu_len: usize) -> Duration {
+        self.symbols(self.shr_symbols + self.phr_symbols + psdu_len as u32 * self.symbols_per_octet)
+    }
+
+    /// Return the airtime of `frame`, including its FCS.
+    pub fn frame_airtime(&self, frame: &FrameRepr<'_>) -> Duration {
+        self.airtime(frame.buffer_len() + FCS_LEN)
+    }
+
+    /// Returns `true` when `frame` can be transmitted within the `max_tx` of
+    /// a TSCH timeslot.
+    pub fn fits_in_timeslot(&self, frame: &FrameRepr<'_>, timings: &TschTimeslotTimings) -> bool {
+        self.frame_airtime(frame).as_us() <= timings.max_tx().as_us()
+    }
+}
+
+impl Default for PhyParameters {
+    fn default() -> Self {
+        Self::O_QPSK_2450
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dot15d4_frame::{Address, FrameBuilder};
+
+    use super::*;
+
+    #[test]
+    fn airtime() {
+        let phy = PhyParameters::O_QPSK_2450;
+        // 32us per octet, 6 octets of SHR and PHR
+        assert_eq!(phy.airtime(127), Duration::from_us(4256));
+        assert_eq!(PhyParameters::BPSK_868.airtime(0), Duration::from_us(2400));
+        assert_eq!(PhyParameters::BPSK_915.airtime(1), Duration::from_us(1400));
+
+        let frame = FrameBuilder::new_data(&[0; 10])
+            .set_sequence_number(1)
+            .set_dst_pan_id(0xabcd)
+            .set_dst_address(Address::Short([0x00, 0x02]))
+            .set_src_pan_id(0xabcd)
+            .set_src_address(Address::Short([0x00, 0x01]))
+            .finalize()
+            .unwrap();
+        // 9 octets of header, 10 of payload and the FCS
+        assert_eq!(frame.buffer_len(), 19);
+        assert_eq!(phy.frame_airtime(&frame), phy.airtime(21));
+        assert!(phy.fits_in_timeslot(&frame, &TschTimeslotTimings::default()));
+
+        let frame = FrameBuilder::new_data(&[0; 10])
+            .set_sequence_number(1)
+            .set_dst_pan_id(0xabcd)
+            .set_dst_address(Address::Short([0x00, 0x02]))
+            .finalize()
+            .unwrap();
+        assert!(!PhyParameters::BPSK_868.fits_in_timeslot(&frame, &TschTimeslotTimings::default()));
+    }
+}
@@ -1,7 +1,77 @@
+use crate::time::Instant;
+
+/// IEEE 802.15.4 channel pages.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelPage {
+    /// 868 MHz and 915 MHz BPSK, and 2450 MHz O-QPSK
+    #[default]
+    Page0,
+    /// 868 MHz and 915 MHz ASK
+    Page1,
+    /// 868 MHz and 915 MHz O-QPSK
+    Page2,
+}
+
+impl ChannelPage {
+    /// Returns `true` when the channel is part of this channel page.
+    pub fn supports(&self, channel: Channel) -> bool {
+        match self {
+            ChannelPage::Page0 => true,
+            ChannelPage::Page1 | ChannelPage::Page2 => channel.is_sub_ghz(),
+        }
+    }
+}
+
+impl TryFrom<u8> for ChannelPage {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(ChannelPage::Page0),
+            1 => Ok(ChannelPage::Page1),
+            2 => Ok(ChannelPage::Page2),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<ChannelPage> for u8 {
+    fn from(page: ChannelPage) -> u8 {
+        match page {
+            ChannelPage::Page0 => 0,
+            ChannelPage::Page1 => 1,
+            ChannelPage::Page2 => 2,
+        }
+    }
+}
+
 /// IEEE 802.15.4 channels
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Channel {
+    /// 868.3 MHz
+    _0,
+    /// 906 MHz
+    _1,
+    /// 908 MHz
+    _2,
+    /// 910 MHz
+    _3,
+    /// 912 MHz
+    _4,
+    /// 914 MHz
+    _5,
+    /// 916 MHz
+    _6,
+    /// 918 MHz
+    _7,
+    /// 920 MHz
+    _8,
+    /// 922 MHz
+    _9,
+    /// 924 MHz
+    _10,
     /// 2_405 MHz
     _11,
     /// 2_410 MHz
@@ -41,6 +111,17 @@ impl TryFrom<i32> for Channel {
 
     fn try_from(value: i32) -> Result<Self, Self::Error> {
         match value {
+            0 => Ok(Channel::_0),
+            1 => Ok(Channel::_1),
+            2 => Ok(Channel::_2),
+            3 => Ok(Channel::_3),
+            4 => Ok(Channel::_4),
+            5 => Ok(Channel::_5),
+            6 => Ok(Channel::_6),
+            7 => Ok(Channel::_7),
+            8 => Ok(Channel::_8),
+            9 => Ok(Channel::_9),
+            10 => Ok(Channel::_10),
             11 => Ok(Channel::_11),
             12 => Ok(Channel::_12),
             13 => Ok(Channel::_13),
@@ -65,6 +146,17 @@ impl TryFrom<i32> for Channel {
 impl From<Channel> for u8 {
     fn from(ch: Channel) -> u8 {
         match ch {
+            Channel::_0 => 0,
+            Channel::_1 => 1,
+            Channel::_2 => 2,
+            Channel::_3 => 3,
+            Channel::_4 => 4,
+            Channel::_5 => 5,
+            Channel::_6 => 6,
+            Channel::_7 => 7,
+            Channel::_8 => 8,
+            Channel::_9 => 9,
+            Channel::_10 => 10,
             Channel::_11 => 11,
             Channel::_12 => 12,
             Channel::_13 => 13,
@@ -91,24 +183,252 @@ impl Default for Channel {
     }
 }
 
+impl Channel {
+    /// Returns `true` for the 868 MHz and 915 MHz channels (0 to 10).
+    pub fn is_sub_ghz(&self) -> bool {
+        u8::from(*self) <= 10
+    }
+
+    /// Return the center frequency of the channel in kHz.
+    pub fn center_frequency_khz(&self) -> u32 {
+        match u8::from(*self) {
+            0 => 868_300,
+            ch @ 1..=10 => 906_000 + 2_000 * (ch as u32 - 1),
+            ch => 2_405_000 + 5_000 * (ch as u32 - 11),
+        }
+    }
+}
+
+/// A set of channels of one channel page, e.g. the channels to scan.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ChannelMask {
+    page: ChannelPage,
+    /// Bit `n` is set when channel `n` is part of the mask.
+    channels: u32,
+}
+
+impl ChannelMask {
+    /// Create an empty [`ChannelMask`] for channels of `page`.
+    pub const fn new(page: ChannelPage) -> Self {
+        Self { page, channels: 0 }
+    }
+
+    /// Create a [`ChannelMask`] holding all channels of `page`.
+    pub fn all(page: ChannelPage) -> Self {
+        let mut mask = Self::new(page);
+        for channel in 0..=26 {
+            if let Ok(channel) = Channel::try_from(channel) {
+                mask.insert(channel);
+            }
+        }
+        mask
+    }
+
+    /// Create a [`ChannelMask`] from a bitmap, with bit `n` set for channel
+    /// `n`, as in the channel lists of MLME-SCAN.request. Bits of channels
+    /// that are not part of `page` are ignored.
+    pub fn from_bits(page: ChannelPage, bits: u32) -> Self {
+        let all = Self::all(page);
+        Self {
+            page,
+            channels: bits & all.channels,
+        }
+    }
+
+    /// Return the mask as a bitmap, with bit `n` set for channel `n`.
+    pub fn bits(&self) -> u32 {
+        self.channels
+    }
+
+    /// Return the channel page of the channels.
+    pub fn page(&self) -> ChannelPage {
+        self.page
+    }
+
+    /// Add `channel` to the mask.
+    ///
+    /// Returns `false` when the channel is not part of the channel page.
+    pub fn insert(&mut self, channel: Channel) -> bool {
+        if !self.page.supports(channel) {
+            return false;
+        }
+        self.channels |= 1 << u8::from(channel);
+        true
+    }
+
+    /// Remove `channel` from the mask.
+    pub fn remove(&mut self, channel: Channel) {
+        self.channels &= !(1 << u8::from(channel));
+    }
+
+    /// Returns `true` when `channel` is part of the mask.
+    pub fn contains(&self, channel: Channel) -> bool {
+        self.channels & (1 << u8::from(channel)) != 0
+    }
+
+    /// Returns `true` when the mask holds no channels.
+    pub fn is_empty(&self) -> bool {
+        self.channels == 0
+    }
+
+    /// Return the number of channels in the mask.
+    pub fn len(&self) -> usize {
+        self.channels.count_ones() as usize
+    }
+
+    /// Return the channel with the lowest number in the mask.
+    pub fn first(&self) -> Option<Channel> {
+        self.iter().next()
+    }
+
+    /// Iterate over the channels in the mask, in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = Channel> {
+        let channels = self.channels;
+        (0..=26)
+            .filter(move |channel| channels & (1 << channel) != 0)
+            .filter_map(|channel| Channel::try_from(channel).ok())
+    }
+}
+
+impl FromIterator<Channel> for ChannelMask {
+    /// Collect channels into a [`ChannelMask`] of channel page 0.
+    fn from_iter<I: IntoIterator<Item = Channel>>(iter: I) -> Self {
+        let mut mask = Self::new(ChannelPage::Page0);
+        for channel in iter {
+            mask.insert(channel);
+        }
+        mask
+    }
+}
+
+/// Clear Channel Assessment (CCA) modes.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CcaMode {
+    /// Transmit without performing CCA.
+    #[default]
+    None,
+    /// CCA mode 1: the medium is busy when the detected energy is above the
+    /// ED threshold.
+    EnergyDetection,
+    /// CCA mode 2: the medium is busy when a signal with the modulation and
+    /// spreading characteristics of IEEE 802.15.4 is detected.
+    CarrierSense,
+    /// CCA mode 3: the medium is busy when a signal with the modulation and
+    /// spreading characteristics of IEEE 802.15.4 is detected, with an
+    /// energy above the ED threshold.
+    EnergyDetectionAndCarrierSense,
+}
+
+impl CcaMode {
+    /// Returns `true` when a CCA should be performed before transmitting.
+    pub fn is_enabled(&self) -> bool {
+        !matches!(self, CcaMode::None)
+    }
+}
+
+impl TryFrom<u8> for CcaMode {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(CcaMode::None),
+            1 => Ok(CcaMode::EnergyDetection),
+            2 => Ok(CcaMode::CarrierSense),
+            3 => Ok(CcaMode::EnergyDetectionAndCarrierSense),
+            _ => Err(()),
+        }
+    }
+}
+
+impl From<CcaMode> for u8 {
+    /// The number of the CCA mode, 0 when no CCA is performed.
+    fn from(mode: CcaMode) -> u8 {
+        match mode {
+            CcaMode::None => 0,
+            CcaMode::EnergyDetection => 1,
+            CcaMode::CarrierSense => 2,
+            CcaMode::EnergyDetectionAndCarrierSense => 3,
+        }
+    }
+}
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct RxConfig {
     pub channel: Channel,
+    pub page: ChannelPage,
 }
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, Default, Clone, PartialEq)]
 pub struct TxConfig {
     pub channel: Channel,
-    pub cca: bool,
+    pub page: ChannelPage,
+    pub cca: CcaMode,
+    /// Transmit power in dBm
+    pub power: i8,
+    /// Start the transmission at this time, in the time base of the
+    /// timestamps of the radio. Only radios that support it schedule the
+    /// transmission, see [`transmit_at`].
+    ///
+    /// [`transmit_at`]: super::radio::futures::transmit_at
+    pub at: Option<Instant>,
 }
 
 impl TxConfig {
     pub fn default_with_cca() -> Self {
         Self {
-            cca: true,
+            cca: CcaMode::EnergyDetection,
             ..Default::default()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn center_frequency() {
+        assert_eq!(Channel::_0.center_frequency_khz(), 868_300);
+        assert_eq!(Channel::_1.center_frequency_khz(), 906_000);
+        assert_eq!(Channel::_10.center_frequency_khz(), 924_000);
+        assert_eq!(Channel::_11.center_frequency_khz(), 2_405_000);
+        assert_eq!(Channel::_26.center_frequency_khz(), 2_480_000);
+    }
+
+    #[test]
+    fn channel_page() {
+        assert!(ChannelPage::Page0.supports(Channel::_26));
+        assert!(ChannelPage::Page2.supports(Channel::_5));
+        assert!(!ChannelPage::Page2.supports(Channel::_11));
+        assert_eq!(ChannelPage::try_from(2), Ok(ChannelPage::Page2));
+        assert_eq!(ChannelPage::try_from(3), Err(()));
+    }
+
+    #[test]
+    fn channel_mask() {
+        assert_eq!(ChannelMask::all(ChannelPage::Page0).len(), 27);
+        assert_eq!(ChannelMask::all(ChannelPage::Page1).len(), 11);
+
+        let mut mask = ChannelMask::new(ChannelPage::Page2);
+        assert!(mask.is_empty());
+        assert!(mask.insert(Channel::_3));
+        assert!(!mask.insert(Channel::_11));
+        assert!(mask.contains(Channel::_3));
+        assert_eq!(mask.bits(), 1 << 3);
+
+        let mut mask: ChannelMask = [Channel::_26, Channel::_11, Channel::_15]
+            .into_iter()
+            .collect();
+        assert_eq!(mask.first(), Some(Channel::_11));
+        mask.remove(Channel::_11);
+        assert!(mask.iter().eq([Channel::_15, Channel::_26].into_iter()));
+
+        // The 2450 MHz channels are not part of channel page 1
+        let mask = ChannelMask::from_bits(ChannelPage::Page1, 0x07ff_f800 | 0b11);
+        assert!(mask.iter().eq([Channel::_0, Channel::_1].into_iter()));
+    }
+}
@@ -1,5 +1,10 @@
 use core::future::Future;
 
+use crate::csma::devices::{IncomingFrameCounter, SecurityError};
+use crate::mlme::{beacon_notify::BeaconNotifyIndication, MacConfirm, MacRequest};
+use crate::phy::config::Channel;
+use crate::phy::pool::PooledFrame;
+
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Error {
@@ -17,17 +22,75 @@ pub enum Error {
     InvalidIEEEStructure,
     /// Something went wrong in the radio
     RadioError,
+    /// A secured frame was rejected
+    SecurityFailed(SecurityError),
 }
 
 /// Should be given as an argument to the task that will run the network
 /// protocol. This trait allows to abstract over channels in async executors.
 pub trait Driver {
-    /// Waits until there is something to be transmitted
+    /// Waits until there is something to be transmitted. The
+    /// [`FrameBuffer::priority`] of the frame orders it in the transmit queue.
     fn transmit(&self) -> impl Future<Output = FrameBuffer>;
     /// Hold until the buffer is received successfully
     fn received(&self, buffer: FrameBuffer) -> impl Future<Output = ()>;
+    /// Return an empty frame of a [`FramePool`] in which the next frame is
+    /// received. By default, frames are received in a buffer of the MAC and
+    /// copied to [`Driver::received`].
+    ///
+    /// [`FramePool`]: crate::phy::pool::FramePool
+    fn alloc_rx_frame(&self) -> Option<PooledFrame<'static>> {
+        None
+    }
+    /// Hold until the frame received in a frame of [`Driver::alloc_rx_frame`]
+    /// is received successfully. By default, the buffer is copied to
+    /// [`Driver::received`].
+    fn received_pooled(&self, frame: PooledFrame<'static>) -> impl Future<Output = ()> {
+        self.received(FrameBuffer::clone(&frame))
+    }
     /// Hold until the buffer is received successfully
     fn error(&self, error: Error) -> impl Future<Output = ()>;
+    /// Hold until the beacon notification is received successfully. By
+    /// default, only the raw buffer is handed to [`Driver::received`].
+    fn beacon_notify(&self, indication: BeaconNotifyIndication) -> impl Future<Output = ()> {
+        self.received(indication.into_buffer())
+    }
+    /// Hold until the updated incoming frame counter of a device is handled.
+    /// Applications can save the counter, such that replayed frames are still
+    /// rejected after a reboot. By default, nothing is done.
+    fn frame_counter_updated(&self, _counter: IncomingFrameCounter) -> impl Future<Output = ()> {
+        core::future::ready(())
+    }
+    /// Waits until there is a management request. By default, there are no
+    /// management requests.
+    fn mlme_request(&self) -> impl Future<Output = MacRequest> {
+        core::future::pending()
+    }
+    /// Hold until the confirmation of a management request is received
+    /// successfully
+    fn mlme_confirm(&self, _confirm: MacConfirm) -> impl Future<Output = ()> {
+        core::future::ready(())
+    }
+    /// Waits until the MAC should stop running, after which it can be run
+    /// again. By default, the MAC runs forever.
+    fn stop(&self) -> impl Future<Output = ()> {
+        core::future::pending()
+    }
+}
+
+/// The priority of a frame, set by the upper layer. Frames of a higher
+/// priority are transmitted before queued frames of a lower priority.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Regular data.
+    #[default]
+    Normal,
+    /// Data that should not wait behind regular data. In TSCH, only frames of
+    /// this or a higher priority use priority links.
+    High,
+    /// Control frames of the network, e.g. routing or join messages.
+    Control,
 }
 
 /// A buffer that is used to store 1 frame.
@@ -47,6 +110,24 @@ pub struct FrameBuffer {
     pub buffer: [u8; 128],
     /// Whether or not the buffer is ready to be read from
     pub dirty: bool,
+    /// The priority of the frame. Only used for frames to transmit.
+    pub priority: Priority,
+    /// The channel on which the frame is transmitted, and its ACK is
+    /// received, instead of the current channel of the PIB (default =
+    /// `None`). Only used for frames to transmit, e.g. for scan or
+    /// commissioning frames on another channel.
+    pub channel: Option<Channel>,
+    /// The handle of the frame, `msduHandle`, which is returned in the
+    /// [`DataConfirm`] of the frame (default = `None`). Only used for frames
+    /// to transmit.
+    ///
+    /// [`DataConfirm`]: crate::mlme::data::DataConfirm
+    pub handle: Option<FrameHandle>,
+    /// Transmit the frame without requesting an ACK, and thus without
+    /// retransmissions, even when it is unicast and the MAC acknowledges
+    /// unicast frames (default = `false`). Only used for frames to transmit,
+    /// e.g. for best-effort telemetry.
+    pub no_ack: bool,
 }
 
 impl Default for FrameBuffer {
@@ -54,12 +135,34 @@ impl Default for FrameBuffer {
         Self {
             buffer: [0u8; 128],
             dirty: false,
+            priority: Priority::Normal,
+            channel: None,
+            handle: None,
+            no_ack: false,
         }
     }
 }
 
+/// Identifies a frame to transmit from its request up to its confirmation,
+/// e.g. to correlate a [`DataConfirm`] with its request, or to purge the
+/// frame from a transmit queue.
+///
+/// [`DataConfirm`]: crate::mlme::data::DataConfirm
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameHandle(pub u8);
+
+impl FrameHandle {
+    /// Return the handle that follows this one, e.g. to generate a handle for
+    /// every request.
+    pub const fn next(self) -> Self {
+        Self(self.0.wrapping_add(1))
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
+    use crate::phy::pool::FramePool;
     use crate::sync::channel::{Channel, Receiver, Sender};
 
     use super::*;
@@ -74,7 +177,15 @@ pub mod tests {
     pub struct TestDriverChannel {
         pub tx: Channel<FrameBuffer>,
         pub rx: Channel<FrameBuffer>,
+        pub rx_pooled: Channel<PooledFrame<'static>>,
+        /// When set, frames are received in this pool
+        pub rx_pool: Option<&'static FramePool<2>>,
         pub errors: Channel<Error>,
+        pub beacons: Channel<BeaconNotifyIndication>,
+        pub requests: Channel<MacRequest>,
+        pub confirms: Channel<MacConfirm>,
+        pub frame_counters: Channel<IncomingFrameCounter>,
+        pub stop: Channel<()>,
     }
 
     impl TestDriverChannel {
@@ -82,24 +193,50 @@ pub mod tests {
             Self {
                 tx: Channel::new(),
                 rx: Channel::new(),
+                rx_pooled: Channel::new(),
+                rx_pool: None,
                 errors: Channel::new(),
+                beacons: Channel::new(),
+                requests: Channel::new(),
+                confirms: Channel::new(),
+                frame_counters: Channel::new(),
+                stop: Channel::new(),
             }
         }
 
         pub fn split(&mut self) -> (TestDriver<'_>, TestDriverMonitor<'_>) {
             let (tx_send, tx_recv) = self.tx.split();
             let (rx_send, rx_recv) = self.rx.split();
+            let (rx_pooled_send, rx_pooled_recv) = self.rx_pooled.split();
             let (errors_send, errors_recv) = self.errors.split();
+            let (beacons_send, beacons_recv) = self.beacons.split();
+            let (requests_send, requests_recv) = self.requests.split();
+            let (confirms_send, confirms_recv) = self.confirms.split();
+            let (frame_counters_send, frame_counters_recv) = self.frame_counters.split();
+            let (stop_send, stop_recv) = self.stop.split();
             (
                 TestDriver {
                     tx: tx_recv,
                     rx: rx_send,
+                    rx_pooled: rx_pooled_send,
+                    rx_pool: self.rx_pool,
                     errors: errors_send,
+                    beacons: beacons_send,
+                    requests: requests_recv,
+                    confirms: confirms_send,
+                    frame_counters: frame_counters_send,
+                    stop: stop_recv,
                 },
                 TestDriverMonitor {
                     tx: tx_send,
                     rx: rx_recv,
+                    rx_pooled: rx_pooled_recv,
                     errors: errors_recv,
+                    beacons: beacons_recv,
+                    requests: requests_send,
+                    confirms: confirms_recv,
+                    frame_counters: frame_counters_recv,
+                    stop: stop_send,
                 },
             )
         }
@@ -108,13 +245,26 @@ pub mod tests {
     pub struct TestDriverMonitor<'a> {
         pub tx: Sender<'a, FrameBuffer>,
         pub rx: Receiver<'a, FrameBuffer>,
+        pub rx_pooled: Receiver<'a, PooledFrame<'static>>,
         pub errors: Receiver<'a, Error>,
+        pub beacons: Receiver<'a, BeaconNotifyIndication>,
+        pub requests: Sender<'a, MacRequest>,
+        pub confirms: Receiver<'a, MacConfirm>,
+        pub frame_counters: Receiver<'a, IncomingFrameCounter>,
+        pub stop: Sender<'a, ()>,
     }
 
     pub struct TestDriver<'a> {
         tx: Receiver<'a, FrameBuffer>,
         rx: Sender<'a, FrameBuffer>,
+        rx_pooled: Sender<'a, PooledFrame<'static>>,
+        rx_pool: Option<&'static FramePool<2>>,
         errors: Sender<'a, Error>,
+        beacons: Sender<'a, BeaconNotifyIndication>,
+        requests: Receiver<'a, MacRequest>,
+        confirms: Sender<'a, MacConfirm>,
+        frame_counters: Sender<'a, IncomingFrameCounter>,
+        stop: Receiver<'a, ()>,
     }
 
     impl Driver for TestDriver<'_> {
@@ -126,8 +276,36 @@ pub mod tests {
             self.rx.send(buffer);
         }
 
+        fn alloc_rx_frame(&self) -> Option<PooledFrame<'static>> {
+            self.rx_pool?.alloc()
+        }
+
+        async fn received_pooled(&self, frame: PooledFrame<'static>) {
+            self.rx_pooled.send(frame);
+        }
+
         async fn error(&self, error: Error) {
             self.errors.send(error);
         }
+
+        async fn beacon_notify(&self, indication: BeaconNotifyIndication) {
+            self.beacons.send(indication);
+        }
+
+        async fn frame_counter_updated(&self, counter: IncomingFrameCounter) {
+            self.frame_counters.send(counter);
+        }
+
+        async fn mlme_request(&self) -> MacRequest {
+            self.requests.receive().await
+        }
+
+        async fn mlme_confirm(&self, confirm: MacConfirm) {
+            self.confirms.send(confirm);
+        }
+
+        async fn stop(&self) {
+            self.stop.receive().await
+        }
     }
 }
@@ -3,6 +3,11 @@
 //! This module provides access to IEEE 802.15.4 devices. It provides a trait
 //! for transmitting and recieving frames, [Device].
 
+pub mod airtime;
 pub mod config;
 pub mod driver;
+pub mod pib;
+pub mod pool;
 pub mod radio;
+pub mod scan;
+pub mod stats;
@@ -0,0 +1,83 @@
+//! The PHY attributes of the PAN Information Base.
+//!
+//! The standard splits the PIB in the attributes of the PHY, prefixed with
+//! `phy`, and those of the MAC, prefixed with `mac`. Both are stored in the
+//! single [`Pib`] of the device, such that an MLME-SET of a PHY attribute is
+//! validated and applied like any other attribute, and the PHY attributes
+//! cannot drift from the MAC attributes. [`PhyPib`] is the view of the PHY on
+//! the [`Pib`], from which the configuration of the radio is derived.
+//!
+//! [`Pib`]: crate::mlme::pib::Pib
+
+use super::config::{CcaMode, Channel, ChannelPage, RxConfig, TxConfig};
+
+/// The PHY attributes of the PIB, see [`Pib::phy`].
+///
+/// [`Pib::phy`]: crate::mlme::pib::Pib::phy
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhyPib {
+    /// `phyCurrentChannel`: the channel on which is transmitted and received.
+    pub current_channel: Channel,
+    /// `phyCurrentPage`: the channel page of [`PhyPib::current_channel`].
+    pub current_page: ChannelPage,
+    /// `phyCcaMode`: the CCA mode used before transmitting a frame.
+    pub cca_mode: CcaMode,
+    /// `phyTxPower`: the transmit power of the radio, in dBm.
+    pub tx_power: i8,
+}
+
+impl PhyPib {
+    /// The radio configuration used for receiving.
+    pub fn rx_config(&self) -> RxConfig {
+        RxConfig {
+            channel: self.current_channel,
+            page: self.current_page,
+        }
+    }
+
+    /// The radio configuration used for transmitting, with a CCA of
+    /// [`PhyPib::cca_mode`].
+    pub fn tx_config(&self) -> TxConfig {
+        TxConfig {
+            channel: self.current_channel,
+            page: self.current_page,
+            cca: self.cca_mode,
+            power: self.tx_power,
+            at: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radio_config() {
+        let pib = PhyPib {
+            current_channel: Channel::_15,
+            current_page: ChannelPage::Page0,
+            cca_mode: CcaMode::CarrierSense,
+            tx_power: -4,
+        };
+
+        assert_eq!(
+            pib.rx_config(),
+            RxConfig {
+                channel: Channel::_15,
+                page: ChannelPage::Page0,
+            }
+        );
+        assert_eq!(
+            pib.tx_config(),
+            TxConfig {
+                channel: Channel::_15,
+                page: ChannelPage::Page0,
+                cca: CcaMode::CarrierSense,
+                power: -4,
+                at: None,
+            }
+        );
+    }
+}
@@ -0,0 +1,179 @@
+//! A fixed-size pool of reference-counted frame buffers.
+//!
+//! Frames received in a [`PooledFrame`] are handed to the upper layer without
+//! copying the [`FrameBuffer`], see [`Driver::alloc_rx_frame`]. The upper
+//! layer can hold on to as many received frames as the pool has buffers, while
+//! the MAC keeps receiving into the remaining ones.
+//!
+//! [`Driver::alloc_rx_frame`]: super::driver::Driver::alloc_rx_frame
+
+use core::cell::{Cell, UnsafeCell};
+use core::ops::Deref;
+
+use critical_section::Mutex;
+
+use super::driver::{FrameBuffer, Priority};
+
+struct Slot {
+    frame: UnsafeCell<FrameBuffer>,
+    references: Mutex<Cell<usize>>,
+}
+
+// Safety: the frame is only mutated through the single handle to it, see
+// `PooledFrame::get_mut`, and the references are counted in a critical section
+unsafe impl Sync for Slot {}
+
+impl Slot {
+    const fn new() -> Self {
+        Self {
+            frame: UnsafeCell::new(FrameBuffer {
+                buffer: [0u8; 128],
+                dirty: false,
+                priority: Priority::Normal,
+                channel: None,
+                handle: None,
+                no_ack: false,
+            }),
+            references: Mutex::new(Cell::new(0)),
+        }
+    }
+}
+
+/// A pool of `N` frame buffers, which can be placed in a `static`.
+pub struct FramePool<const N: usize> {
+    slots: [Slot; N],
+}
+
+impl<const N: usize> Default for FramePool<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FramePool<N> {
+    /// Create a new pool of which all buffers are free.
+    pub const fn new() -> Self {
+        Self {
+            slots: [const { Slot::new() }; N],
+        }
+    }
+
+    /// Take an empty frame buffer from the pool, or `None` when all buffers
+    /// are in use.
+    pub fn alloc(&self) -> Option<PooledFrame<'_>> {
+        let slot = critical_section::with(|cs| {
+            let slot = self
+                .slots
+                .iter()
+                .find(|slot| slot.references.borrow(cs).get() == 0)?;
+            slot.references.borrow(cs).set(1);
+            Some(slot)
+        })?;
+
+        let mut frame = PooledFrame { slot };
+        *frame.get_mut().expect("A new frame is not shared") = FrameBuffer::default();
+        Some(frame)
+    }
+
+    /// Return the number of free buffers.
+    pub fn available(&self) -> usize {
+        critical_section::with(|cs| {
+            self.slots
+                .iter()
+                .filter(|slot| slot.references.borrow(cs).get() == 0)
+                .count()
+        })
+    }
+}
+
+/// A reference-counted handle to a frame buffer of a [`FramePool`]. Cloning
+/// the handle does not copy the buffer, which returns to the pool when the
+/// last handle is dropped.
+pub struct PooledFrame<'p> {
+    slot: &'p Slot,
+}
+
+impl PooledFrame<'_> {
+    /// Return a mutable reference to the buffer, or `None` when the buffer is
+    /// shared with another handle.
+    pub fn get_mut(&mut self) -> Option<&mut FrameBuffer> {
+        // Safety: this is the only handle, which is borrowed mutably
+        self.is_unique()
+            .then(|| unsafe { &mut *self.slot.frame.get() })
+    }
+
+    /// Returns `true` when no other handle refers to the buffer.
+    pub fn is_unique(&self) -> bool {
+        critical_section::with(|cs| self.slot.references.borrow(cs).get() == 1)
+    }
+}
+
+impl Deref for PooledFrame<'_> {
+    type Target = FrameBuffer;
+
+    fn deref(&self) -> &FrameBuffer {
+        // Safety: while shared, the buffer is never mutated
+        unsafe { &*self.slot.frame.get() }
+    }
+}
+
+impl Clone for PooledFrame<'_> {
+    fn clone(&self) -> Self {
+        critical_section::with(|cs| {
+            let references = self.slot.references.borrow(cs);
+            references.set(references.get() + 1);
+        });
+        Self { slot: self.slot }
+    }
+}
+
+impl Drop for PooledFrame<'_> {
+    fn drop(&mut self) {
+        critical_section::with(|cs| {
+            let references = self.slot.references.borrow(cs);
+            references.set(references.get() - 1);
+        });
+    }
+}
+
+impl core::fmt::Debug for PooledFrame<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("PooledFrame").field(&**self).finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for PooledFrame<'_> {
+    fn format(&self, fmt: defmt::Formatter) {
+        defmt::write!(fmt, "PooledFrame({})", &**self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alloc_and_release() {
+        let pool = FramePool::<2>::new();
+        assert_eq!(pool.available(), 2);
+
+        let mut first = pool.alloc().unwrap();
+        first.get_mut().unwrap().buffer[0] = 42;
+        let _second = pool.alloc().unwrap();
+        assert!(pool.alloc().is_none());
+
+        // A shared buffer can not be changed
+        let mut shared = first.clone();
+        assert_eq!(shared.buffer[0], 42);
+        assert!(shared.get_mut().is_none());
+        drop(first);
+        assert!(shared.is_unique());
+        assert_eq!(pool.available(), 0);
+
+        // The buffer is empty again when it is reused
+        drop(shared);
+        assert_eq!(pool.available(), 1);
+        assert_eq!(*pool.alloc().unwrap(), FrameBuffer::default());
+    }
+}
@@ -0,0 +1,574 @@
+//! Adapter for radio drivers that report events through interrupt callbacks.
+//!
+//! Some radio HALs do not expose futures, but start an operation and report
+//! its outcome from an interrupt handler. [`CallbackRadio`] turns such a
+//! driver, implementing [`CallbackDriver`], into a [`Radio`]. The interrupt
+//! handler reports the outcome of the operation to the shared
+//! [`RadioEvents`], which wakes the future waiting on it:
+//!
+//! ```ignore
+//! static EVENTS: RadioEvents = RadioEvents::new();
+//!
+//! fn radio_irq() {
+//!     match hal::radio_event() {
+//!         hal::Event::CcaBusy => EVENTS.cca_result(false),
+//!         hal::Event::TxEnd(time) => EVENTS.tx_done(Some(time)),
+//!         hal::Event::RxEnd(psdu, time) => EVENTS.rx_done(psdu, Some(time)),
+//!         _ => {}
+//!     }
+//! }
+//!
+//! let radio = CallbackRadio::new(hal::Radio::new(), &EVENTS);
+//! ```
+//!
+//! The frame buffers used by the adapter start with the PHY header, the length
+//! of the PSDU, followed by the PSDU: the MAC frame and its FCS.
+use core::cell::RefCell;
+use core::future::poll_fn;
+use core::ptr::NonNull;
+use core::task::{Context, Poll, Waker};
+
+use critical_section::Mutex;
+
+use super::{Radio, RadioFrame, RadioFrameMut, RxToken, TxResult, TxStatus, TxToken};
+use crate::phy::config::{RxConfig, TxConfig};
+use crate::time::Instant;
+
+/// The length of the PHY header in the frame buffers, in octets.
+const PHR_LEN: usize = 1;
+/// The length of the FCS, in octets.
+const FCS_LEN: usize = 2;
+/// The maximum length of a PSDU, in octets.
+const MAX_PSDU_LEN: usize = 127;
+
+/// A radio driver that starts operations, and reports their outcome from an
+/// interrupt handler to [`RadioEvents`].
+pub trait CallbackDriver {
+    /// Put the radio in a low-power sleep mode.
+    fn disable(&mut self);
+    /// Wake the radio from sleep.
+    fn enable(&mut self);
+
+    /// Start receiving on the channel in `cfg`. A received frame is reported
+    /// with [`RadioEvents::rx_done`].
+    fn start_receive(&mut self, cfg: &RxConfig);
+
+    /// Start transmitting `frame`, a PHY header followed by the PSDU, after
+    /// the CCA requested in `cfg`. The outcome is reported with
+    /// [`RadioEvents::cca_result`], [`RadioEvents::tx_done`] or
+    /// [`RadioEvents::tx_failed`].
+    ///
+    /// # Safety
+    /// The frame remains valid until the outcome of the transmission is
+    /// reported, or until [`CallbackDriver::cancel`] is called.
+    unsafe fn start_transmit(&mut self, cfg: &TxConfig, frame: &[u8]);
+
+    /// Stop the ongoing operation. No event may be reported for it
+    /// afterwards.
+    fn cancel(&mut self);
+
+    /// Returns the IEEE802.15.4 8-octet MAC address of the radio device.
+    fn ieee802154_address(&self) -> [u8; 8];
+}
+
+struct EventState {
+    /// The outcome of the ongoing transmission.
+    tx: Option<TxResult>,
+    /// Whether a frame was received in `rx_buffer`.
+    rx: bool,
+    rx_timestamp: Option<Instant>,
+    rx_buffer: [u8; PHR_LEN + MAX_PSDU_LEN],
+    waker: Option<Waker>,
+}
+
+impl EventState {
+    fn wake(&mut self) {
+        if let Some(waker) = self.waker.take() {
+            waker.wake()
+        }
+    }
+
+    fn register(&mut self, cx: &Context<'_>) {
+        match &mut self.waker {
+            Some(waker) => waker.clone_from(cx.waker()),
+            waker @ None => *waker = Some(cx.waker().clone()),
+        }
+    }
+}
+
+/// The events of a [`CallbackDriver`], shared between its interrupt handler
+/// and the [`CallbackRadio`] waiting on them.
+pub struct RadioEvents {
+    state: Mutex<RefCell<EventState>>,
+}
+
+impl RadioEvents {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(RefCell::new(EventState {
+                tx: None,
+                rx: false,
+                rx_timestamp: None,
+                rx_buffer: [0; PHR_LEN + MAX_PSDU_LEN],
+                waker: None,
+            })),
+        }
+    }
+
+    /// Report that the frame was transmitted, starting at `timestamp` if the
+    /// radio timestamps transmissions.
+    pub fn tx_done(&self, timestamp: Option<Instant>) {
+        self.finish_tx(TxResult {
+            status: TxStatus::Sent,
+            timestamp,
+        })
+    }
+
+    /// Report that the radio failed to transmit the frame.
+    pub fn tx_failed(&self) {
+        self.finish_tx(TxResult {
+            status: TxStatus::HwError,
+            timestamp: None,
+        })
+    }
+
+    /// Report the result of the CCA before a transmission. When the channel
+    /// is busy, the frame is not transmitted. When it is idle, the
+    /// transmission continues and is reported with [`RadioEvents::tx_done`].
+    pub fn cca_result(&self, idle: bool) {
+        if !idle {
+            self.finish_tx(TxResult {
+                status: TxStatus::ChannelBusy,
+                timestamp: None,
+            })
+        }
+    }
+
+    /// Report that `psdu`, the MAC frame including its FCS, was received,
+    /// starting at `timestamp` if the radio timestamps receptions. Frames
+    /// longer than 127 octets are ignored.
+    pub fn rx_done(&self, psdu: &[u8], timestamp: Option<Instant>) {
+        if psdu.len() > MAX_PSDU_LEN {
+            return;
+        }
+
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            state.rx_buffer[0] = psdu.len() as u8;
+            state.rx_buffer[PHR_LEN..][..psdu.len()].copy_from_slice(psdu);
+            state.rx = true;
+            state.rx_timestamp = timestamp;
+            state.wake();
+        })
+    }
+
+    fn finish_tx(&self, result: TxResult) {
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            state.tx = Some(result);
+            state.wake();
+        })
+    }
+
+    /// Forget the events of previous operations.
+    fn reset(&self) {
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            state.tx = None;
+            state.rx = false;
+        })
+    }
+
+    fn poll_tx(&self, cx: &Context<'_>) -> Poll<TxResult> {
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            match state.tx.take() {
+                Some(result) => Poll::Ready(result),
+                None => {
+                    state.register(cx);
+                    Poll::Pending
+                }
+            }
+        })
+    }
+
+    fn poll_rx(&self, cx: &Context<'_>, buffer: &mut [u8; 128]) -> Poll<Option<Instant>> {
+        critical_section::with(|cs| {
+            let mut state = self.state.borrow_ref_mut(cs);
+            if state.rx {
+                state.rx = false;
+                buffer.copy_from_slice(&state.rx_buffer);
+                Poll::Ready(state.rx_timestamp)
+            } else {
+                state.register(cx);
+                Poll::Pending
+            }
+        })
+    }
+}
+
+impl Default for RadioEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`Radio`] on top of a [`CallbackDriver`], completing its futures when
+/// the interrupt handler of the driver reports to `events`.
+pub struct CallbackRadio<'e, D: CallbackDriver> {
+    driver: D,
+    events: &'e RadioEvents,
+    rx_buffer: Option<NonNull<[u8; 128]>>,
+    tx_frame: Option<(TxConfig, NonNull<[u8]>)>,
+    rx_timestamp: Option<Instant>,
+}
+
+impl<'e, D: CallbackDriver> CallbackRadio<'e, D> {
+    pub fn new(driver: D, events: &'e RadioEvents) -> Self {
+        Self {
+            driver,
+            events,
+            rx_buffer: None,
+            tx_frame: None,
+            rx_timestamp: None,
+        }
+    }
+
+    /// Returns a reference to the driver.
+    pub fn driver(&self) -> &D {
+        &self.driver
+    }
+
+    /// Returns a mutable reference to the driver.
+    pub fn driver_mut(&mut self) -> &mut D {
+        &mut self.driver
+    }
+}
+
+impl<D: CallbackDriver> Radio for CallbackRadio<'_, D> {
+    type RadioFrame<T>
+        = CallbackRadioFrame<T>
+    where
+        T: AsRef<[u8]>;
+    type RxToken<'a> = CallbackRxToken<'a>;
+    type TxToken<'b> = CallbackTxToken<'b>;
+
+    async fn disable(&mut self) {
+        self.driver.disable();
+    }
+
+    async fn enable(&mut self) {
+        self.driver.enable();
+    }
+
+    async unsafe fn prepare_receive(&mut self, cfg: &RxConfig, bytes: &mut [u8; 128]) {
+        self.events.reset();
+        self.tx_frame = None;
+        self.rx_buffer = Some(NonNull::from(bytes));
+        self.driver.start_receive(cfg);
+    }
+
+    async fn receive(&mut self) -> bool {
+        let Some(mut buffer) = self.rx_buffer else {
+            return false;
+        };
+
+        // Safety: the caller of `prepare_receive` keeps the buffer writable
+        // until the reception succeeds, or the radio state changes.
+        let buffer = unsafe { buffer.as_mut() };
+        let timestamp = poll_fn(|cx| self.events.poll_rx(cx, buffer)).await;
+
+        self.rx_buffer = None;
+        self.rx_timestamp = timestamp;
+        true
+    }
+
+    async unsafe fn prepare_transmit(&mut self, cfg: &TxConfig, bytes: &mut [u8]) {
+        self.events.reset();
+        self.rx_buffer = None;
+        let len = match bytes.first() {
+            Some(&psdu_len) => (PHR_LEN + psdu_len as usize).min(bytes.len()),
+            None => 0,
+        };
+        self.tx_frame = Some((cfg.clone(), NonNull::from(&bytes[..len])));
+    }
+
+    fn cancel_current_opperation(&mut self) {
+        self.driver.cancel();
+        self.events.reset();
+        self.rx_buffer = None;
+        self.tx_frame = None;
+    }
+
+    async fn transmit(&mut self) -> TxResult {
+        let Some((cfg, frame)) = self.tx_frame.take() else {
+            return TxResult {
+                status: TxStatus::HwError,
+                timestamp: None,
+            };
+        };
+
+        self.events.reset();
+        // Safety: the caller of `prepare_transmit` keeps the frame valid until
+        // the transmission finished, or the radio state changes, in which
+        // case the driver is canceled.
+        unsafe { self.driver.start_transmit(&cfg, frame.as_ref()) };
+        poll_fn(|cx| self.events.poll_tx(cx)).await
+    }
+
+    fn rx_timestamp(&self) -> Option<Instant> {
+        self.rx_timestamp
+    }
+
+    fn ieee802154_address(&self) -> [u8; 8] {
+        self.driver.ieee802154_address()
+    }
+}
+
+/// A frame buffer of a [`CallbackRadio`]: the PHY header followed by the
+/// PSDU.
+#[derive(Debug, Clone)]
+pub struct CallbackRadioFrame<T: AsRef<[u8]>> {
+    buffer: T,
+}
+
+impl<T: AsRef<[u8]>> CallbackRadioFrame<T> {
+    fn mpdu_len(&self) -> usize {
+        (self.buffer.as_ref()[0] as usize).saturating_sub(FCS_LEN)
+    }
+}
+
+impl<T: AsRef<[u8]>> RadioFrame<T> for CallbackRadioFrame<T> {
+    type Error = ();
+
+    fn new_unchecked(buffer: T) -> Self {
+        Self { buffer }
+    }
+
+    fn new_checked(buffer: T) -> Result<Self, Self::Error> {
+        let bytes = buffer.as_ref();
+        match bytes.first() {
+            Some(&len)
+                if (FCS_LEN..=MAX_PSDU_LEN).contains(&(len as usize))
+                    && PHR_LEN + len as usize <= bytes.len() =>
+            {
+                Ok(Self { buffer })
+            }
+            _ => Err(()),
+        }
+    }
+
+    /// Returns the MAC frame, without the PHY header and FCS.
+    fn data(&self) -> &[u8] {
+        &self.buffer.as_ref()[PHR_LEN..][..self.mpdu_len()]
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> RadioFrameMut<T> for CallbackRadioFrame<T> {
+    fn data_mut(&mut self) -> &mut [u8] {
+        let len = self.mpdu_len();
+        &mut self.buffer.as_mut()[PHR_LEN..][..len]
+    }
+}
+
+pub struct CallbackRxToken<'a> {
+    buffer: &'a mut [u8],
+}
+
+impl RxToken for CallbackRxToken<'_> {
+    fn consume<F, R>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let len = (self.buffer[0] as usize).saturating_sub(FCS_LEN);
+        f(&mut self.buffer[PHR_LEN..][..len])
+    }
+}
+
+impl<'a> From<&'a mut [u8]> for CallbackRxToken<'a> {
+    fn from(value: &'a mut [u8]) -> Self {
+        Self { buffer: value }
+    }
+}
+
+pub struct CallbackTxToken<'a> {
+    buffer: &'a mut [u8],
+}
+
+impl TxToken for CallbackTxToken<'_> {
+    /// Writes the PHY header for a MAC frame of `len` octets, and lets `f`
+    /// write the MAC frame. The FCS is left to the radio.
+    fn consume<F, R>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        self.buffer[0] = (len + FCS_LEN) as u8;
+        f(&mut self.buffer[PHR_LEN..][..len])
+    }
+}
+
+impl<'a> From<&'a mut [u8]> for CallbackTxToken<'a> {
+    fn from(value: &'a mut [u8]) -> Self {
+        Self { buffer: value }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc, vec::Vec};
+
+    use super::*;
+    use crate::phy::config::CcaMode;
+    use crate::sync::{join::join, tests::block_on, yield_now::yield_now};
+
+    #[derive(Debug, PartialEq)]
+    enum Call {
+        StartReceive,
+        StartTransmit(CcaMode, Vec<u8>),
+        Cancel,
+    }
+
+    #[derive(Default, Clone)]
+    struct MockDriver {
+        calls: Rc<RefCell<Vec<Call>>>,
+    }
+
+    impl CallbackDriver for MockDriver {
+        fn disable(&mut self) {}
+
+        fn enable(&mut self) {}
+
+        fn start_receive(&mut self, _cfg: &RxConfig) {
+            self.calls.borrow_mut().push(Call::StartReceive);
+        }
+
+        unsafe fn start_transmit(&mut self, cfg: &TxConfig, frame: &[u8]) {
+            self.calls
+                .borrow_mut()
+                .push(Call::StartTransmit(cfg.cca, frame.to_vec()));
+        }
+
+        fn cancel(&mut self) {
+            self.calls.borrow_mut().push(Call::Cancel);
+        }
+
+        fn ieee802154_address(&self) -> [u8; 8] {
+            [0xca; 8]
+        }
+    }
+
+    #[test]
+    fn transmit() {
+        let events = RadioEvents::new();
+        let driver = MockDriver::default();
+        let mut radio = CallbackRadio::new(driver.clone(), &events);
+        assert_eq!(radio.ieee802154_address(), [0xca; 8]);
+
+        let mut buffer = [0u8; 128];
+        CallbackTxToken::from(&mut buffer[..]).consume(3, |frame| {
+            frame.copy_from_slice(&[0x02, 0x10, 0x2a]);
+        });
+        let cfg = TxConfig::default_with_cca();
+
+        // The interrupt handler reports an idle channel, then the transmission
+        let irq = async {
+            yield_now().await;
+            events.cca_result(true);
+            yield_now().await;
+            events.tx_done(Some(Instant::from_us(42)));
+        };
+        let (result, _) = block_on(join(
+            async {
+                unsafe { radio.prepare_transmit(&cfg, &mut buffer).await };
+                radio.transmit().await
+            },
+            irq,
+        ));
+        assert_eq!(
+            result,
+            TxResult {
+                status: TxStatus::Sent,
+                timestamp: Some(Instant::from_us(42)),
+            }
+        );
+        // The radio fills in the FCS
+        assert_eq!(
+            driver.calls.borrow()[..],
+            [Call::StartTransmit(
+                CcaMode::EnergyDetection,
+                vec![5, 0x02, 0x10, 0x2a, 0, 0]
+            )]
+        );
+
+        // A busy channel ends the transmission
+        let irq = async {
+            yield_now().await;
+            events.cca_result(false);
+        };
+        let (result, _) = block_on(join(
+            async {
+                unsafe { radio.prepare_transmit(&cfg, &mut buffer).await };
+                radio.transmit().await
+            },
+            irq,
+        ));
+        assert_eq!(result.status, TxStatus::ChannelBusy);
+
+        // Events of earlier operations are forgotten
+        events.tx_failed();
+        let irq = async {
+            yield_now().await;
+            events.tx_done(None);
+        };
+        let (result, _) = block_on(join(
+            async {
+                unsafe { radio.prepare_transmit(&cfg, &mut buffer).await };
+                radio.transmit().await
+            },
+            irq,
+        ));
+        assert_eq!(result.status, TxStatus::Sent);
+    }
+
+    #[test]
+    fn receive() {
+        let events = RadioEvents::new();
+        let driver = MockDriver::default();
+        let mut radio = CallbackRadio::new(driver.clone(), &events);
+
+        let mut buffer = [0u8; 128];
+        assert!(!block_on(radio.receive()));
+
+        let irq = async {
+            yield_now().await;
+            events.rx_done(&[0x02, 0x10, 0x2a, 0xaa, 0xbb], Some(Instant::from_us(7)));
+        };
+        let (received, _) = block_on(join(
+            async {
+                unsafe {
+                    radio
+                        .prepare_receive(&RxConfig::default(), &mut buffer)
+                        .await
+                };
+                radio.receive().await
+            },
+            irq,
+        ));
+        assert!(received);
+        assert_eq!(radio.rx_timestamp(), Some(Instant::from_us(7)));
+        assert_eq!(driver.calls.borrow()[..], [Call::StartReceive]);
+
+        let frame = CallbackRadioFrame::new_checked(&buffer[..]).unwrap();
+        assert_eq!(frame.data(), [0x02, 0x10, 0x2a]);
+        CallbackRxToken::from(&mut buffer[..]).consume(|frame| {
+            assert_eq!(frame, [0x02, 0x10, 0x2a]);
+        });
+
+        // Frames that do not fit a PSDU are ignored
+        events.rx_done(&[0; 128], None);
+        radio.cancel_current_opperation();
+        assert_eq!(driver.calls.borrow().last(), Some(&Call::Cancel));
+        assert!(!block_on(radio.receive()));
+        assert!(CallbackRadioFrame::new_checked(&[200u8; 128][..]).is_err());
+    }
+}
@@ -1,8 +1,13 @@
 use core::cell::RefCell;
 use core::mem::MaybeUninit;
 
-use super::Radio;
+use embedded_hal_async::delay::DelayNs;
+
+use super::{Radio, TxResult};
 use crate::phy::config::{RxConfig, TxConfig};
+use crate::phy::driver::FrameBuffer;
+use crate::sync::{select, Either};
+use crate::time::{Duration, Instant};
 
 /// Helper structure to have cleanup logic when dropping a future
 struct OnDrop<F: FnOnce()> {
@@ -44,7 +49,7 @@ pub async fn transmit<'task, T: AsMut<[u8]>, R: Radio>(
     radio: &'task mut R,
     data: &'task mut T,
     config: TxConfig,
-) -> bool {
+) -> TxResult {
     let radio = RefCell::new(radio);
     // Should just work as a drop is handled at the end, after the other radio uses
     let on_drop = OnDrop::new(|| radio.borrow_mut().cancel_current_opperation());
@@ -59,6 +64,29 @@ pub async fn transmit<'task, T: AsMut<[u8]>, R: Radio>(
     result
 }
 
+/// Transmit at the time of [`TxConfig::at`], e.g. at the TX offset in a
+/// TSCH timeslot. Radios that do not support scheduled transmissions, see
+/// [`Radio::supports_transmit_at`], transmit after waiting with `timer` for
+/// the time left from `now`, which is in the time base of the radio. The
+/// frame is transmitted right away when the time has passed.
+pub async fn transmit_at<T: AsMut<[u8]>, R: Radio, TIMER: DelayNs>(
+    radio: &mut R,
+    timer: &mut TIMER,
+    data: &mut T,
+    mut config: TxConfig,
+    now: Instant,
+) -> TxResult {
+    if let Some(at) = config.at.filter(|_| !radio.supports_transmit_at()) {
+        let wait = at - now;
+        if wait.as_us() > 0 {
+            timer.delay_us(wait.as_us() as u32).await;
+        }
+        config.at = None;
+    }
+
+    transmit(radio, data, config).await
+}
+
 /// Convenience Future around receiving through the radio. This future first
 /// prepares the radio, then receives before succeeding. This future, upon
 /// cancelling, stops the radio from receiving and puts the radio in an IDLE
@@ -82,3 +110,127 @@ pub async fn receive<'task, R: Radio>(
     on_drop.defuse(); // Prevent the cancel operation from happening
     result
 }
+
+/// Listen during a receive window, which opens `start` from now and closes
+/// `duration` later, e.g. an RX slot of TSCH or the wait for an ACK. Every
+/// reception into `rx` is handed to `on_frame`, with `None` when it failed,
+/// until `on_frame` returns `Some`. The radio is idle again once the window
+/// closed.
+///
+/// Returns the value returned by `on_frame`, or `None` when the window closed
+/// first.
+pub async fn receive_window<R: Radio, TIMER: DelayNs, T>(
+    radio: &mut R,
+    timer: &mut TIMER,
+    rx: &mut FrameBuffer,
+    config: RxConfig,
+    start: Duration,
+    duration: Duration,
+    mut on_frame: impl FnMut(Option<&FrameBuffer>) -> Option<T>,
+) -> Option<T> {
+    if start.as_us() > 0 {
+        timer.delay_us(start.as_us() as u32).await;
+    }
+
+    let listen = async {
+        loop {
+            let received = receive(radio, &mut rx.buffer, config.clone()).await;
+            if let Some(value) = on_frame(received.then_some(&*rx)) {
+                return value;
+            }
+        }
+    };
+    match select::select(listen, timer.delay_us(duration.as_us().max(0) as u32)).await {
+        Either::First(value) => Some(value),
+        Either::Second(()) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phy::config::Channel;
+    use crate::phy::radio::tests::TestRadio;
+    use crate::phy::radio::TxStatus;
+    use crate::sync::tests::{block_on, virtual_now, VirtualDelay};
+
+    #[test]
+    fn window() {
+        block_on(async {
+            let mut radio = TestRadio::default();
+            let mut timer = VirtualDelay::default();
+            let mut rx = FrameBuffer::default();
+            let config = RxConfig {
+                channel: Channel::_26,
+                ..Default::default()
+            };
+
+            // Frames are handed over until one is accepted
+            radio.inner(|inner| inner.should_receive = Some([42; 128]));
+            let mut frames = 0;
+            let accepted = receive_window(
+                &mut radio,
+                &mut timer,
+                &mut rx,
+                config.clone(),
+                Duration::from_us(100),
+                Duration::from_us(1000),
+                |frame| {
+                    frames += 1;
+                    frame.map(|frame| frame.buffer[0])
+                },
+            )
+            .await;
+            assert_eq!(accepted, Some(42));
+            assert_eq!(frames, 1);
+            assert_eq!(
+                radio.inner(|inner| inner.last_rx_channel),
+                Some(Channel::_26)
+            );
+
+            // The window closes when nothing is accepted
+            let accepted = receive_window(
+                &mut radio,
+                &mut timer,
+                &mut rx,
+                config,
+                Duration::from_us(0),
+                Duration::from_us(1000),
+                |_| None::<()>,
+            )
+            .await;
+            assert_eq!(accepted, None);
+        })
+    }
+    #[test]
+    fn scheduled_transmission() {
+        block_on(async {
+            let mut radio = TestRadio::default();
+            let mut timer = VirtualDelay::default();
+            let mut data = [0u8; 128];
+
+            // Without support, the transmission is delayed
+            let now = virtual_now();
+            let config = TxConfig {
+                at: Some(now + Duration::from_us(2120)),
+                ..Default::default()
+            };
+            let result = transmit_at(&mut radio, &mut timer, &mut data, config.clone(), now).await;
+            assert_eq!(result.status, TxStatus::Sent);
+            assert!(result.timestamp >= config.at);
+            assert_eq!(radio.inner(|inner| inner.last_tx_at), None);
+
+            // The radio schedules the transmission itself
+            radio.inner(|inner| inner.supports_transmit_at = true);
+            let now = virtual_now();
+            let config = TxConfig {
+                at: Some(now + Duration::from_us(2120)),
+                ..Default::default()
+            };
+            let result = transmit_at(&mut radio, &mut timer, &mut data, config.clone(), now).await;
+            assert_eq!(result.timestamp, config.at);
+            assert_eq!(virtual_now(), now);
+            assert_eq!(radio.inner(|inner| inner.last_tx_at), config.at);
+        })
+    }
+}
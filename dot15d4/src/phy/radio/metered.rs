@@ -0,0 +1,190 @@
+//! Accounting of the time the radio is on.
+//!
+//! [`MeteredRadio`] wraps a [`Radio`], and reports every change of its state
+//! to a [`DutyCycleMeter`], with the time read from a [`Clock`]. The meter is
+//! shared, such that the duty cycle can be read while the MAC owns the radio:
+//!
+//! ```ignore
+//! static METER: DutyCycleMeter = DutyCycleMeter::new();
+//!
+//! let radio = MeteredRadio::new(hal::Radio::new(), hal::now, &METER);
+//! let mut csma = CsmaDevice::new(radio, rng, driver, timer, CsmaConfig::default());
+//!
+//! let time = METER.radio_time(hal::now());
+//! info!("on: {} ppm, tx: {} ppm", time.duty_cycle_ppm(), time.tx_duty_cycle_ppm());
+//! ```
+
+use super::{Radio, TxResult};
+use crate::phy::config::{RxConfig, TxConfig};
+use crate::phy::stats::{DutyCycleMeter, RadioState};
+use crate::time::{Clock, Instant};
+
+/// A [`Radio`] of which the time spent in every [`RadioState`] is accounted
+/// in a [`DutyCycleMeter`].
+pub struct MeteredRadio<'m, R, CLK> {
+    radio: R,
+    clock: CLK,
+    meter: &'m DutyCycleMeter,
+}
+
+impl<'m, R: Radio, CLK: Clock> MeteredRadio<'m, R, CLK> {
+    pub fn new(radio: R, clock: CLK, meter: &'m DutyCycleMeter) -> Self {
+        Self {
+            radio,
+            clock,
+            meter,
+        }
+    }
+
+    /// Returns a reference to the wrapped radio.
+    pub fn radio(&self) -> &R {
+        &self.radio
+    }
+
+    /// Returns a mutable reference to the wrapped radio.
+    pub fn radio_mut(&mut self) -> &mut R {
+        &mut self.radio
+    }
+
+    fn enter(&self, state: RadioState) {
+        self.meter.enter(state, self.clock.now());
+    }
+}
+
+impl<R: Radio, CLK: Clock> Radio for MeteredRadio<'_, R, CLK> {
+    type RadioFrame<T>
+        = R::RadioFrame<T>
+    where
+        T: AsRef<[u8]>;
+    type RxToken<'a> = R::RxToken<'a>;
+    type TxToken<'b> = R::TxToken<'b>;
+
+    async fn disable(&mut self) {
+        self.radio.disable().await;
+        self.enter(RadioState::Off);
+    }
+
+    async fn enable(&mut self) {
+        self.radio.enable().await;
+        self.enter(RadioState::Idle);
+    }
+
+    async unsafe fn prepare_receive(&mut self, cfg: &RxConfig, bytes: &mut [u8; 128]) {
+        self.enter(RadioState::Rx);
+        // Safety: the caller upholds the contract of `prepare_receive`.
+        unsafe { self.radio.prepare_receive(cfg, bytes).await }
+    }
+
+    async fn receive(&mut self) -> bool {
+        let received = self.radio.receive().await;
+        self.enter(RadioState::Idle);
+        received
+    }
+
+    async unsafe fn prepare_transmit(&mut self, cfg: &TxConfig, bytes: &mut [u8]) {
+        self.enter(RadioState::Idle);
+        // Safety: the caller upholds the contract of `prepare_transmit`.
+        unsafe { self.radio.prepare_transmit(cfg, bytes).await }
+    }
+
+    fn cancel_current_opperation(&mut self) {
+        self.radio.cancel_current_opperation();
+        if self.meter.state() != RadioState::Off {
+            self.enter(RadioState::Idle);
+        }
+    }
+
+    async fn transmit(&mut self) -> TxResult {
+        self.enter(RadioState::Tx);
+        let result = self.radio.transmit().await;
+        self.enter(RadioState::Idle);
+        result
+    }
+
+    fn supports_transmit_at(&self) -> bool {
+        self.radio.supports_transmit_at()
+    }
+
+    fn rx_timestamp(&self) -> Option<Instant> {
+        self.radio.rx_timestamp()
+    }
+
+    fn rx_lqi(&self) -> Option<u8> {
+        self.radio.rx_lqi()
+    }
+
+    fn ieee802154_address(&self) -> [u8; 8] {
+        self.radio.ieee802154_address()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal_async::delay::DelayNs;
+
+    use super::*;
+    use crate::phy::driver::FrameBuffer;
+    use crate::phy::radio::futures::{receive_window, transmit};
+    use crate::phy::radio::tests::TestRadio;
+    use crate::phy::stats::RadioTime;
+    use crate::sync::tests::{block_on, virtual_now, VirtualDelay};
+    use crate::time::Duration;
+
+    #[test]
+    fn duty_cycle() {
+        block_on(async {
+            let meter = DutyCycleMeter::new();
+            let mut radio = MeteredRadio::new(TestRadio::default(), virtual_now, &meter);
+            let mut timer = VirtualDelay::default();
+            let start = virtual_now();
+
+            // Nothing is accounted before the first state change
+            timer.delay_us(500).await;
+            radio.enable().await;
+            timer.delay_us(100).await;
+
+            // Listening during a window, which is canceled when it closes
+            let mut rx = FrameBuffer::default();
+            let received = receive_window(
+                &mut radio,
+                &mut timer,
+                &mut rx,
+                RxConfig::default(),
+                Duration::from_us(0),
+                Duration::from_us(1000),
+                |_| None::<()>,
+            )
+            .await;
+            assert_eq!(received, None);
+            assert_eq!(meter.state(), RadioState::Idle);
+
+            let mut data = [0u8; 128];
+            transmit(&mut radio, &mut data, TxConfig::default()).await;
+            radio.disable().await;
+            timer.delay_us(8900).await;
+
+            let time = meter.radio_time(virtual_now());
+            assert_eq!(
+                time,
+                RadioTime {
+                    off: Duration::from_us(8900),
+                    idle: Duration::from_us(100),
+                    rx: Duration::from_us(1000),
+                    tx: Duration::from_us(0),
+                }
+            );
+            assert_eq!(time.total().as_us(), (virtual_now() - start).as_us() - 500);
+            assert_eq!(time.duty_cycle_ppm(), 110_000);
+
+            // Transmissions are accounted for the regulatory duty cycle
+            meter.reset(virtual_now());
+            meter.enter(RadioState::Tx, virtual_now());
+            timer.delay_us(36).await;
+            meter.enter(RadioState::Off, virtual_now());
+            timer.delay_us(35_964).await;
+            let time = meter.radio_time(virtual_now());
+            assert_eq!(time.tx, Duration::from_us(36));
+            assert_eq!(time.tx_duty_cycle_ppm(), 1_000);
+        })
+    }
+}
@@ -1,8 +1,36 @@
+pub mod callback;
 pub mod futures;
+pub mod metered;
 
 use core::future::Future;
 
 use super::config::{RxConfig, TxConfig};
+use crate::time::Instant;
+
+/// Whether [`Radio::transmit`] transmitted the frame.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxStatus {
+    /// The frame was transmitted.
+    Sent,
+    /// The CCA found the channel busy, the frame was not transmitted. The
+    /// transmission can be retried after a backoff.
+    ChannelBusy,
+    /// The radio failed to transmit the frame, retrying is not expected to
+    /// help.
+    HwError,
+}
+
+/// The result of [`Radio::transmit`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxResult {
+    /// Whether the frame was transmitted.
+    pub status: TxStatus,
+    /// When the start of the frame was transmitted, if the radio timestamps
+    /// transmissions.
+    pub timestamp: Option<Instant>,
+}
 
 pub trait Radio {
     type RadioFrame<T>: RadioFrame<T>
@@ -55,8 +83,28 @@ pub trait Radio {
 
     /// Request the radio to transmit the queued frame.
     ///
-    /// Returns whether a transmission was successful.
-    fn transmit(&mut self) -> impl Future<Output = bool>;
+    /// Returns whether the frame was transmitted, the channel was busy or the
+    /// radio failed, and when the frame was transmitted.
+    fn transmit(&mut self) -> impl Future<Output = TxResult>;
+
+    /// Returns `true` when the radio starts a transmission at the time of
+    /// [`TxConfig::at`] by itself. Other radios transmit right away, see
+    /// [`futures::transmit_at`] for the fallback.
+    fn supports_transmit_at(&self) -> bool {
+        false
+    }
+
+    /// Returns when the start of the last received frame, e.g. an ACK, was
+    /// received, if the radio timestamps receptions.
+    fn rx_timestamp(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Returns the link quality indication (LQI) of the last received frame,
+    /// if the radio reports it.
+    fn rx_lqi(&self) -> Option<u8> {
+        None
+    }
 
     /// Returns the IEEE802.15.4 8-octet MAC address of the radio device.
     fn ieee802154_address(&self) -> [u8; 8];
@@ -93,6 +141,7 @@ pub trait TxToken {
 pub mod tests {
     use core::panic;
     use std::{
+        boxed::Box,
         cell::RefCell,
         collections::VecDeque,
         future::poll_fn,
@@ -104,9 +153,16 @@ pub mod tests {
 
     use embedded_hal_async::delay::DelayNs;
 
-    use crate::sync::{select, tests::StdDelay};
+    use dot15d4_frame::{DataFrame, FrameBuilder};
+
+    use crate::phy::config::{CcaMode, Channel};
+    use crate::sync::{
+        select,
+        tests::{virtual_now, VirtualDelay},
+    };
+    use crate::time::{Duration, Instant};
 
-    use super::{Radio, RadioFrame, RadioFrameMut, RxToken, TxToken};
+    use super::{Radio, RadioFrame, RadioFrameMut, RxToken, TxResult, TxStatus, TxToken};
 
     #[derive(Debug, Clone, Copy, PartialEq)]
     pub enum TestRadioEvent {
@@ -119,16 +175,96 @@ pub mod tests {
         Enable,
     }
 
+    /// How the test radio responds to a transmitted frame, as decided by a
+    /// script registered with [`TestRadio::on_transmit`].
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub enum TestRadioResponse {
+        /// Nothing is received, as if the frame or its response got lost.
+        Drop,
+        /// The frame is received right after the transmission.
+        Receive([u8; 128]),
+        /// The frame is received after a delay, e.g. a late ACK.
+        ReceiveAfter(Duration, [u8; 128]),
+    }
+
+    impl TestRadioResponse {
+        /// Receive an Imm-Ack for the transmitted `frame`.
+        pub fn ack(frame: &[u8]) -> Self {
+            let sequence_number = DataFrame::new(frame)
+                .ok()
+                .and_then(|frame| frame.sequence_number())
+                .expect("An ACK can only be sent for a frame with a sequence number");
+            Self::Receive(Self::imm_ack(sequence_number))
+        }
+
+        /// Receive an Imm-Ack for the transmitted `frame` with the wrong
+        /// sequence number, as if it got corrupted.
+        pub fn corrupted_ack(frame: &[u8]) -> Self {
+            match Self::ack(frame) {
+                Self::Receive(mut ack) => {
+                    ack[2] = !ack[2];
+                    Self::Receive(ack)
+                }
+                response => response,
+            }
+        }
+
+        /// Receive the response after `delay`.
+        pub fn delayed(self, delay: Duration) -> Self {
+            match self {
+                Self::Receive(frame) => Self::ReceiveAfter(delay, frame),
+                Self::ReceiveAfter(after, frame) => Self::ReceiveAfter(after + delay, frame),
+                Self::Drop => Self::Drop,
+            }
+        }
+
+        fn imm_ack(sequence_number: u8) -> [u8; 128] {
+            let mut buffer = [0; 128];
+            let ack = FrameBuilder::new_imm_ack(sequence_number)
+                .finalize()
+                .unwrap();
+            ack.emit(&mut DataFrame::new_unchecked(
+                &mut buffer[..ack.buffer_len()],
+            ));
+            buffer
+        }
+    }
+
+    type TestRadioScript = Box<dyn FnOnce(&[u8]) -> TestRadioResponse>;
+
     pub struct TestRadioInner {
         pub ieee802154_address: [u8; 8],
         pub should_receive: Option<[u8; 128]>,
+        /// The frame in `should_receive` is only received from this time on
+        pub receive_at: Option<Instant>,
+        /// The scripts deciding the response to the next transmitted frames
+        pub scripts: VecDeque<TestRadioScript>,
         pub receive_buffer: Option<NonNull<[u8]>>,
         pub events: Vec<TestRadioEvent>,
+        /// The medium is busy for every CCA mode
         pub cca_fail: bool,
+        /// Energy above the ED threshold is present on the medium
+        pub energy_detected: bool,
+        /// An IEEE 802.15.4 signal is present on the medium
+        pub carrier_sensed: bool,
+        /// The radio reports a busy channel for every transmission, even when
+        /// no CCA is performed
+        pub tx_busy: bool,
+        /// The radio fails every transmission
+        pub hw_error: bool,
         pub assert_nxt: VecDeque<TestRadioEvent>,
         pub total_event_count: usize,
         pub last_transmitted: Option<[u8; 128]>,
-        pub has_requested_cca: bool,
+        pub last_tx_power: Option<i8>,
+        pub last_tx_channel: Option<Channel>,
+        pub last_tx_at: Option<Instant>,
+        /// The radio schedules transmissions at `TxConfig::at`
+        pub supports_transmit_at: bool,
+        pub last_rx_channel: Option<Channel>,
+        pub last_rx_timestamp: Option<Instant>,
+        /// The LQI reported for received frames
+        pub rx_lqi: Option<u8>,
+        pub requested_cca: CcaMode,
         assert_waker: Option<Waker>,
     }
 
@@ -143,14 +279,27 @@ pub mod tests {
                 inner: Rc::new(RefCell::new(TestRadioInner {
                     ieee802154_address,
                     should_receive: None,
+                    receive_at: None,
+                    scripts: VecDeque::new(),
                     events: vec![],
                     cca_fail: false,
+                    tx_busy: false,
+                    hw_error: false,
+                    energy_detected: false,
+                    carrier_sensed: false,
                     assert_nxt: VecDeque::new(),
                     receive_buffer: None,
                     total_event_count: 0,
                     last_transmitted: None,
+                    last_tx_power: None,
+                    last_tx_channel: None,
+                    last_tx_at: None,
+                    supports_transmit_at: false,
+                    last_rx_channel: None,
+                    last_rx_timestamp: None,
+                    rx_lqi: None,
                     assert_waker: None,
-                    has_requested_cca: false,
+                    requested_cca: CcaMode::None,
                 })),
             }
         }
@@ -163,6 +312,15 @@ pub mod tests {
             f(&mut inner)
         }
 
+        /// Register a script that inspects the next transmitted frame, and
+        /// decides what is received in response. Scripts are used in the
+        /// order in which they are registered, one per successful
+        /// transmission. Without scripts, nothing is received in response,
+        /// unless set in `should_receive`.
+        pub fn on_transmit(&self, script: impl FnOnce(&[u8]) -> TestRadioResponse + 'static) {
+            self.inner.borrow_mut().scripts.push_back(Box::new(script));
+        }
+
         pub fn new_event(&mut self, evnt: TestRadioEvent) {
             let mut inner = self.inner.borrow_mut();
 
@@ -196,7 +354,7 @@ pub mod tests {
 
         /// Async wait for all radio events to have happened.
         /// This function is ment to be only used in tests an as such will panic
-        /// if not all events have happened within 5s of starting
+        /// if not all events have happened within 5s of virtual time
         pub async fn wait_until_asserts_are_consumed(&self) {
             let wait_for_events = poll_fn(|cx| {
                 let mut inner = self.inner.borrow_mut();
@@ -218,7 +376,7 @@ pub mod tests {
                 }
             });
 
-            match select::select(wait_for_events, StdDelay::default().delay_ms(5000)).await {
+            match select::select(wait_for_events, VirtualDelay::default().delay_ms(5000)).await {
                 crate::sync::Either::First(_) => {}
                 crate::sync::Either::Second(_) => {
                     if !std::thread::panicking() {
@@ -253,10 +411,11 @@ pub mod tests {
 
         async unsafe fn prepare_receive(
             &mut self,
-            _cfg: &crate::phy::config::RxConfig,
+            cfg: &crate::phy::config::RxConfig,
             bytes: &mut [u8; 128],
         ) {
             self.new_event(TestRadioEvent::PrepareReceive);
+            self.inner.borrow_mut().last_rx_channel = Some(cfg.channel);
             // Safety: Rust references are always valid and never dangling
             // Reference is also owned by the caller which will stay alive for the entire
             // duration this part of the api is used.
@@ -275,7 +434,8 @@ pub mod tests {
                 let mut inner = self.inner.borrow_mut();
 
                 if let Some(mut receive_buffer) = inner.receive_buffer {
-                    if let Some(should_receive) = inner.should_receive {
+                    let arrived = inner.receive_at.is_none_or(|at| virtual_now() >= at);
+                    if let Some(should_receive) = inner.should_receive.filter(|_| arrived) {
                         // Safety: The user of this API should also be the one that owns the
                         // receive_buffer
                         unsafe { receive_buffer.as_mut().copy_from_slice(&should_receive) }
@@ -283,6 +443,8 @@ pub mod tests {
                         // Reset pointers
                         inner.receive_buffer = None;
                         inner.should_receive = None;
+                        inner.receive_at = None;
+                        inner.last_rx_timestamp = Some(virtual_now());
 
                         Poll::Ready(true)
                     } else {
@@ -305,17 +467,73 @@ pub mod tests {
             buffer.clone_from_slice(&bytes[..128]);
             let mut inner = self.inner.borrow_mut();
             inner.last_transmitted = Some(buffer);
-            inner.has_requested_cca = cfg.cca;
+            inner.last_tx_power = Some(cfg.power);
+            inner.last_tx_channel = Some(cfg.channel);
+            inner.last_tx_at = cfg.at;
+            inner.requested_cca = cfg.cca;
         }
 
         fn cancel_current_opperation(&mut self) {
             self.new_event(TestRadioEvent::CancelCurrentOperation);
         }
 
-        async fn transmit(&mut self) -> bool {
+        async fn transmit(&mut self) -> TxResult {
             self.new_event(TestRadioEvent::Transmit);
-            let inner = self.inner.borrow();
-            !(inner.has_requested_cca && inner.cca_fail)
+            let mut inner = self.inner.borrow_mut();
+            let busy = inner.tx_busy
+                || match inner.requested_cca {
+                    CcaMode::None => false,
+                    CcaMode::EnergyDetection => inner.cca_fail || inner.energy_detected,
+                    CcaMode::CarrierSense => inner.cca_fail || inner.carrier_sensed,
+                    CcaMode::EnergyDetectionAndCarrierSense => {
+                        inner.cca_fail || (inner.energy_detected && inner.carrier_sensed)
+                    }
+                };
+
+            if !busy && !inner.hw_error {
+                if let Some(script) = inner.scripts.pop_front() {
+                    let frame = inner.last_transmitted.unwrap_or([0; 128]);
+                    match script(&frame[..127]) {
+                        TestRadioResponse::Drop => {}
+                        TestRadioResponse::Receive(frame) => {
+                            inner.should_receive = Some(frame);
+                            inner.receive_at = None;
+                        }
+                        TestRadioResponse::ReceiveAfter(delay, frame) => {
+                            inner.should_receive = Some(frame);
+                            inner.receive_at = Some(virtual_now() + delay);
+                        }
+                    }
+                }
+            }
+
+            let status = if inner.hw_error {
+                TxStatus::HwError
+            } else if busy {
+                TxStatus::ChannelBusy
+            } else {
+                TxStatus::Sent
+            };
+            let start = match inner.last_tx_at {
+                Some(at) if inner.supports_transmit_at => at.max(virtual_now()),
+                _ => virtual_now(),
+            };
+            TxResult {
+                status,
+                timestamp: (status == TxStatus::Sent).then_some(start),
+            }
+        }
+
+        fn supports_transmit_at(&self) -> bool {
+            self.inner.borrow().supports_transmit_at
+        }
+
+        fn rx_timestamp(&self) -> Option<Instant> {
+            self.inner.borrow().last_rx_timestamp
+        }
+
+        fn rx_lqi(&self) -> Option<u8> {
+            self.inner.borrow().rx_lqi
         }
 
         fn ieee802154_address(&self) -> [u8; 8] {
@@ -323,6 +541,18 @@ pub mod tests {
         }
     }
 
+    /// The last byte of a test frame buffer holds the length of the frame,
+    /// which is written by [`TestTxToken`]. Buffers without a valid length
+    /// hold a frame of 127 bytes.
+    const TEST_FRAME_LEN: usize = 127;
+
+    pub fn test_frame_len(buffer: &[u8]) -> usize {
+        match buffer.get(TEST_FRAME_LEN) {
+            Some(&len) if len != 0 && len as usize <= TEST_FRAME_LEN => len as usize,
+            _ => TEST_FRAME_LEN,
+        }
+    }
+
     #[derive(Debug, Clone)]
     pub struct TestRadioFrame<T: AsRef<[u8]>> {
         buffer: T,
@@ -339,12 +569,14 @@ pub mod tests {
         }
 
         fn data(&self) -> &[u8] {
-            &self.buffer.as_ref()[..127]
+            let len = test_frame_len(self.buffer.as_ref());
+            &self.buffer.as_ref()[..len]
         }
     }
     impl<T: AsRef<[u8]> + AsMut<[u8]>> RadioFrameMut<T> for TestRadioFrame<T> {
         fn data_mut(&mut self) -> &mut [u8] {
-            &mut self.buffer.as_mut()[..127]
+            let len = test_frame_len(self.buffer.as_ref());
+            &mut self.buffer.as_mut()[..len]
         }
     }
 
@@ -372,6 +604,9 @@ pub mod tests {
         where
             F: FnOnce(&mut [u8]) -> R,
         {
+            if let Some(frame_len) = self.buffer.get_mut(TEST_FRAME_LEN) {
+                *frame_len = len as u8;
+            }
             f(&mut self.buffer[..len])
         }
     }
@@ -0,0 +1,145 @@
+//! Listening on the channels of a [`ChannelMask`], one after the other.
+//!
+//! [`ChannelScan`] is the building block for MLME-SCAN, for sniffers hopping
+//! over channels, and for probing the quality of channels: it listens on every
+//! channel for a fixed duration, hands every received frame to a callback, and
+//! reports a [`ChannelScanResult`] per channel.
+
+use embedded_hal_async::delay::DelayNs;
+
+use super::{
+    config::{Channel, ChannelMask, ChannelPage, RxConfig},
+    driver::FrameBuffer,
+    radio::{futures::receive_window, Radio},
+};
+use crate::time::Duration;
+
+/// The result of listening on one channel.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelScanResult {
+    /// The channel that was listened on.
+    pub channel: Channel,
+    /// The channel page of [`ChannelScanResult::channel`].
+    pub page: ChannelPage,
+    /// The number of frames received on the channel.
+    pub frames_received: usize,
+}
+
+/// Listens on the channels of a [`ChannelMask`] in increasing order.
+#[derive(Debug, Clone)]
+pub struct ChannelScan {
+    remaining: ChannelMask,
+    duration: Duration,
+}
+
+impl ChannelScan {
+    /// Create a new [`ChannelScan`], listening for `duration` on every
+    /// channel of `channels`.
+    pub fn new(channels: ChannelMask, duration: Duration) -> Self {
+        Self {
+            remaining: channels,
+            duration,
+        }
+    }
+
+    /// Return the channels that are not scanned yet.
+    pub fn remaining(&self) -> ChannelMask {
+        self.remaining
+    }
+
+    /// Listen on the next channel, calling `on_frame` for every frame that is
+    /// received into `rx`.
+    ///
+    /// Returns `None` once all channels are scanned.
+    pub async fn next<R, TIMER>(
+        &mut self,
+        radio: &mut R,
+        timer: &mut TIMER,
+        rx: &mut FrameBuffer,
+        mut on_frame: impl FnMut(Channel, &FrameBuffer),
+    ) -> Option<ChannelScanResult>
+    where
+        R: Radio,
+        TIMER: DelayNs,
+    {
+        let channel = self.remaining.first()?;
+        self.remaining.remove(channel);
+        let config = RxConfig {
+            channel,
+            page: self.remaining.page(),
+        };
+
+        let mut frames_received = 0;
+        // No frame ends the window, it closes when the time on this channel
+        // is up
+        receive_window(
+            radio,
+            timer,
+            rx,
+            config.clone(),
+            Duration::from_us(0),
+            self.duration,
+            |frame| {
+                if let Some(frame) = frame {
+                    frames_received += 1;
+                    on_frame(channel, frame);
+                }
+                None::<()>
+            },
+        )
+        .await;
+
+        Some(ChannelScanResult {
+            channel,
+            page: config.page,
+            frames_received,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::phy::radio::tests::TestRadio;
+    use crate::sync::tests::{block_on, VirtualDelay};
+
+    #[test]
+    fn scan() {
+        block_on(async {
+            let mut radio = TestRadio::default();
+            let mut timer = VirtualDelay::default();
+            let mut rx = FrameBuffer::default();
+            let channels: ChannelMask = [Channel::_26, Channel::_11, Channel::_15]
+                .into_iter()
+                .collect();
+            let mut scan = ChannelScan::new(channels, Duration::from_us(1000));
+
+            let mut visited = Vec::new();
+            let mut received = Vec::new();
+            // A frame is waiting on the first channel
+            radio.inner(|inner| inner.should_receive = Some([42; 128]));
+            while let Some(result) = scan
+                .next(&mut radio, &mut timer, &mut rx, |channel, frame| {
+                    received.push((channel, frame.buffer[0]))
+                })
+                .await
+            {
+                assert_eq!(
+                    radio.inner(|inner| inner.last_rx_channel),
+                    Some(result.channel)
+                );
+                visited.push((result.channel, result.frames_received));
+            }
+
+            assert_eq!(
+                visited,
+                [(Channel::_11, 1), (Channel::_15, 0), (Channel::_26, 0)]
+            );
+            assert_eq!(received, [(Channel::_11, 42)]);
+            assert!(scan.remaining().is_empty());
+        })
+    }
+}
@@ -0,0 +1,215 @@
+//! Diagnostic counters of the radio.
+//!
+//! The counters are kept below the MAC, such that they also include frames
+//! that are dropped before the MAC looks at them, and ACKs.
+//!
+//! The time the radio spends in every [`RadioState`] is accumulated by a
+//! [`DutyCycleMeter`], e.g. through a [`MeteredRadio`], to estimate the
+//! battery lifetime or to check the duty-cycle limits of SubGHz bands.
+//!
+//! [`MeteredRadio`]: crate::phy::radio::metered::MeteredRadio
+
+use core::cell::Cell;
+
+use critical_section::Mutex;
+
+use crate::time::{Duration, Instant};
+
+/// A snapshot of the diagnostic counters of the radio. All counters wrap
+/// around on overflow.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PhyStats {
+    /// Frames received by the radio, including rejected frames.
+    pub rx_frames: u32,
+    /// Received frames that failed the checks of the radio, e.g. the CRC, or
+    /// that are not valid IEEE 802.15.4 frames.
+    pub rx_rejected: u32,
+    /// Receive operations that failed in the radio.
+    pub rx_errors: u32,
+    /// Frames handed to the radio for transmission, including ACKs and
+    /// retransmissions.
+    pub tx_attempts: u32,
+    /// Transmissions that failed in the radio, e.g. because the channel was
+    /// busy.
+    pub tx_errors: u32,
+}
+
+impl PhyStats {
+    /// Increment one of the counters in `stats`.
+    pub(crate) fn count(stats: &Cell<Self>, counter: fn(&mut Self) -> &mut u32) {
+        let mut snapshot = stats.get();
+        let counter = counter(&mut snapshot);
+        *counter = counter.wrapping_add(1);
+        stats.set(snapshot);
+    }
+}
+
+/// The state of the radio, for the accounting of the time it is on.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RadioState {
+    /// The radio sleeps.
+    Off,
+    /// The radio is on, but neither receiving nor transmitting.
+    Idle,
+    /// The radio listens for, or receives a frame.
+    Rx,
+    /// The radio transmits a frame, including the CCA before it.
+    Tx,
+}
+
+/// The cumulative time the radio spent in every [`RadioState`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RadioTime {
+    /// Time spent in [`RadioState::Off`].
+    pub off: Duration,
+    /// Time spent in [`RadioState::Idle`].
+    pub idle: Duration,
+    /// Time spent in [`RadioState::Rx`].
+    pub rx: Duration,
+    /// Time spent in [`RadioState::Tx`].
+    pub tx: Duration,
+}
+
+impl Default for RadioTime {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl RadioTime {
+    /// No time spent in any state.
+    pub const ZERO: Self = Self {
+        off: Duration::from_us(0),
+        idle: Duration::from_us(0),
+        rx: Duration::from_us(0),
+        tx: Duration::from_us(0),
+    };
+
+    /// Returns the time the radio was on: idle, receiving or transmitting.
+    pub fn on(&self) -> Duration {
+        self.idle + self.rx + self.tx
+    }
+
+    /// Returns the total time that was accounted for.
+    pub fn total(&self) -> Duration {
+        self.off + self.on()
+    }
+
+    /// Returns the fraction of the time the radio was on, in parts per
+    /// million.
+    pub fn duty_cycle_ppm(&self) -> u32 {
+        Self::ppm(self.on(), self.total())
+    }
+
+    /// Returns the fraction of the time the radio was transmitting, in parts
+    /// per million. This is the figure limited by regulations in e.g. the
+    /// 868 MHz band.
+    pub fn tx_duty_cycle_ppm(&self) -> u32 {
+        Self::ppm(self.tx, self.total())
+    }
+
+    fn ppm(part: Duration, total: Duration) -> u32 {
+        if total.as_us() <= 0 {
+            return 0;
+        }
+        (part.as_us() as i128 * 1_000_000 / total.as_us() as i128) as u32
+    }
+
+    fn add(&mut self, state: RadioState, duration: Duration) {
+        let time = match state {
+            RadioState::Off => &mut self.off,
+            RadioState::Idle => &mut self.idle,
+            RadioState::Rx => &mut self.rx,
+            RadioState::Tx => &mut self.tx,
+        };
+        *time = *time + duration;
+    }
+}
+
+#[derive(Clone, Copy)]
+struct MeterState {
+    state: RadioState,
+    /// When `state` was entered, `None` before the first state change.
+    since: Option<Instant>,
+    time: RadioTime,
+}
+
+impl MeterState {
+    /// Account the time spent in the current state until `now`.
+    fn elapse(&mut self, now: Instant) {
+        if let Some(since) = self.since {
+            let elapsed = (now - since).as_us().max(0);
+            self.time.add(self.state, Duration::from_us(elapsed));
+        }
+        self.since = Some(now);
+    }
+}
+
+/// Accumulates the time the radio spends in every [`RadioState`]. The meter
+/// can be shared between the radio and e.g. the application that reports
+/// the duty cycle. Time is only accounted from the first state change on.
+pub struct DutyCycleMeter {
+    state: Mutex<Cell<MeterState>>,
+}
+
+impl Default for DutyCycleMeter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DutyCycleMeter {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(Cell::new(MeterState {
+                state: RadioState::Off,
+                since: None,
+                time: RadioTime::ZERO,
+            })),
+        }
+    }
+
+    /// Record that the radio entered `state` at `now`.
+    pub fn enter(&self, state: RadioState, now: Instant) {
+        self.update(|meter| {
+            meter.elapse(now);
+            meter.state = state;
+        });
+    }
+
+    /// Returns the current state of the radio.
+    pub fn state(&self) -> RadioState {
+        critical_section::with(|cs| self.state.borrow(cs).get().state)
+    }
+
+    /// Returns the time spent in every state, including the time spent in
+    /// the current state until `now`.
+    pub fn radio_time(&self, now: Instant) -> RadioTime {
+        self.update(|meter| {
+            meter.elapse(now);
+            meter.time
+        })
+    }
+
+    /// Forget the accounted time and start again from `now`, e.g. at the
+    /// start of an observation period of a duty-cycle limit.
+    pub fn reset(&self, now: Instant) {
+        self.update(|meter| {
+            meter.time = RadioTime::ZERO;
+            meter.since = Some(now);
+        });
+    }
+
+    fn update<T>(&self, f: impl FnOnce(&mut MeterState) -> T) -> T {
+        critical_section::with(|cs| {
+            let cell = self.state.borrow(cs);
+            let mut meter = cell.get();
+            let result = f(&mut meter);
+            cell.set(meter);
+            result
+        })
+    }
+}
@@ -7,16 +7,102 @@ use embedded_hal_async::delay::DelayNs;
 
 use super::yield_now;
 
-/// Implementation of a timer that can be used in tests. Delays delay for 10
-/// iterations
+/// How far the virtual time advances every time the future under test is
+/// polled after waking itself, e.g. while the test radio is receiving.
+const VIRTUAL_TICK_NS: u64 = 10_000;
+
+/// Virtual time after which a test is considered stuck.
+const VIRTUAL_TIMEOUT_NS: u64 = 60_000_000_000;
+
+#[derive(Default)]
+struct VirtualClock {
+    now_ns: u64,
+    next_deadline_ns: Option<u64>,
+}
+
+std::thread_local! {
+    static VIRTUAL_CLOCK: std::cell::RefCell<VirtualClock> = Default::default();
+}
+
+/// Returns the virtual time of the test running on this thread.
+pub fn virtual_now() -> crate::time::Instant {
+    VIRTUAL_CLOCK.with_borrow(|clock| crate::time::Instant::from_us((clock.now_ns / 1000) as i64))
+}
+
+/// Implementation of a timer that uses the virtual time of [`block_on`].
 #[derive(Default, Clone)]
-pub struct Delay {}
+pub struct VirtualDelay {}
+
+impl DelayNs for VirtualDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        let deadline = VIRTUAL_CLOCK.with_borrow(|clock| clock.now_ns + ns as u64);
+        // Like a hardware timer, even a delay of 0 lets other futures run
+        yield_now::yield_now().await;
+        core::future::poll_fn(|_| {
+            VIRTUAL_CLOCK.with_borrow_mut(|clock| {
+                if clock.now_ns >= deadline {
+                    Poll::Ready(())
+                } else {
+                    clock.next_deadline_ns = Some(match clock.next_deadline_ns {
+                        Some(next) => next.min(deadline),
+                        None => deadline,
+                    });
+                    Poll::Pending
+                }
+            })
+        })
+        .await
+    }
+}
+
+struct WokenFlag(std::sync::atomic::AtomicBool);
 
-impl DelayNs for Delay {
-    async fn delay_ns(&mut self, _ns: u32) {
-        for _ in 0..10 {
-            yield_now::yield_now().await
+impl std::task::Wake for WokenFlag {
+    fn wake(self: std::sync::Arc<Self>) {
+        self.wake_by_ref()
+    }
+
+    fn wake_by_ref(self: &std::sync::Arc<Self>) {
+        self.0.store(true, std::sync::atomic::Ordering::Release);
+    }
+}
+
+/// Run a future to completion in virtual time, starting at 0. When the future
+/// is woken, time advances by a small tick. Otherwise, time jumps to the first
+/// deadline of a [`VirtualDelay`], such that long delays take no real time.
+///
+/// Panics when the future can not make progress, or when it did not complete
+/// within a minute of virtual time.
+pub fn block_on<F: Future>(f: F) -> F::Output {
+    use std::sync::atomic::Ordering;
+
+    VIRTUAL_CLOCK.with_borrow_mut(|clock| *clock = VirtualClock::default());
+
+    let woken = std::sync::Arc::new(WokenFlag(false.into()));
+    let waker = Waker::from(woken.clone());
+    let mut cx = core::task::Context::from_waker(&waker);
+    let mut f = core::pin::pin!(f);
+
+    loop {
+        woken.0.store(false, Ordering::Release);
+        VIRTUAL_CLOCK.with_borrow_mut(|clock| clock.next_deadline_ns = None);
+
+        if let Poll::Ready(output) = f.as_mut().poll(&mut cx) {
+            return output;
         }
+
+        VIRTUAL_CLOCK.with_borrow_mut(|clock| {
+            clock.now_ns = match (woken.0.load(Ordering::Acquire), clock.next_deadline_ns) {
+                (true, Some(deadline)) => deadline.min(clock.now_ns + VIRTUAL_TICK_NS),
+                (true, None) => clock.now_ns + VIRTUAL_TICK_NS,
+                (false, Some(deadline)) => deadline,
+                (false, None) => panic!("The future is not woken and is not waiting on a timer"),
+            };
+            assert!(
+                clock.now_ns < VIRTUAL_TIMEOUT_NS,
+                "The future did not complete within the virtual timeout"
+            );
+        });
     }
 }
 
@@ -153,7 +239,30 @@ mod inner_tests {
 
     use embedded_hal_async::delay::DelayNs;
 
-    use super::StdDelay;
+    use super::{block_on, virtual_now, StdDelay, VirtualDelay};
+    use crate::sync::{select, yield_now::yield_now, Either};
+
+    #[test]
+    pub fn test_virtual_delay() {
+        let start = Instant::now();
+        block_on(async {
+            VirtualDelay::default().delay_ms(10_000).await;
+            assert_eq!(virtual_now().as_us(), 10_000_000);
+
+            // Time advances while another future keeps waking itself
+            let busy = async {
+                loop {
+                    yield_now().await
+                }
+            };
+            assert_eq!(
+                select::select(busy, VirtualDelay::default().delay_ms(5)).await,
+                Either::Second(())
+            );
+            assert_eq!(virtual_now().as_us(), 10_005_000);
+        });
+        assert!(Instant::now() - start < Duration::from_secs(1));
+    }
 
     #[pollster::test]
     pub async fn test_std_delay_future() {
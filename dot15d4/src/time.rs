@@ -2,8 +2,11 @@
 //!
 //! - [`Instant`] is used to represent a point in time.
 //! - [`Duration`] is used to represent a duration of time.
+//! - [`Clock`] is used to read the current time, e.g. [`StdClock`] on hosted
+//!   targets.
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Instant {
     us: i64,
@@ -15,6 +18,13 @@ impl Instant {
         Self { us }
     }
 
+    /// Create a new `Instant` from the ticks of a clock since the epoch,
+    /// counting `ticks_per_second`. The result is rounded down to a
+    /// microsecond.
+    pub const fn from_ticks(ticks: u64, ticks_per_second: u32) -> Self {
+        Self::from_us((ticks as u128 * 1_000_000 / ticks_per_second as u128) as i64)
+    }
+
     /// Returns the point in time as microseconds since the epoch.
     pub const fn as_us(&self) -> i64 {
         self.us
@@ -22,6 +32,7 @@ impl Instant {
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Eq, Ord)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct Duration(i64);
 
@@ -37,6 +48,91 @@ impl Duration {
     }
 }
 
+/// A source of the current time, e.g. a free-running hardware timer. Only
+/// the differences between the returned [`Instant`]s are relevant, not their
+/// epoch.
+///
+/// The clock counts ticks at a rate that is known at compile time, e.g. the
+/// 32.768 kHz of a low-power RTC, and [`Clock::now`] converts them to an
+/// [`Instant`]:
+///
+/// ```
+/// # use dot15d4::time::Clock;
+/// struct Rtc;
+///
+/// impl Clock for Rtc {
+///     const TICKS_PER_SECOND: u32 = 32_768;
+///
+///     fn now_ticks(&self) -> u64 {
+///         65_536 // e.g. read from the RTC counter
+///     }
+/// }
+///
+/// assert_eq!(Rtc.now().as_us(), 2_000_000);
+/// ```
+///
+/// A closure returning an [`Instant`] is a clock with a tick of a
+/// microsecond.
+pub trait Clock {
+    /// The number of ticks per second.
+    const TICKS_PER_SECOND: u32;
+
+    /// Returns the current time in ticks.
+    fn now_ticks(&self) -> u64;
+
+    /// Returns the current time.
+    fn now(&self) -> Instant {
+        const { assert!(Self::TICKS_PER_SECOND > 0, "A clock needs ticks") };
+        Instant::from_ticks(self.now_ticks(), Self::TICKS_PER_SECOND)
+    }
+}
+
+impl<F: Fn() -> Instant> Clock for F {
+    const TICKS_PER_SECOND: u32 = 1_000_000;
+
+    fn now_ticks(&self) -> u64 {
+        self().as_us() as u64
+    }
+
+    fn now(&self) -> Instant {
+        self()
+    }
+}
+
+/// A [`Clock`] backed by [`std::time::Instant`], counting microseconds since
+/// it was created, e.g. for tests and hosted targets.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug)]
+pub struct StdClock {
+    epoch: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl StdClock {
+    /// Create a new [`StdClock`], of which the epoch is now.
+    pub fn new() -> Self {
+        Self {
+            epoch: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    const TICKS_PER_SECOND: u32 = 1_000_000;
+
+    fn now_ticks(&self) -> u64 {
+        self.epoch.elapsed().as_micros() as u64
+    }
+}
+
 impl core::ops::Sub for Instant {
     type Output = Self;
 
@@ -113,6 +209,37 @@ mod tests {
         assert_eq!((a + Duration::from_us(50)).as_us(), 150);
     }
 
+    #[test]
+    fn clock() {
+        struct Rtc(u64);
+
+        impl Clock for Rtc {
+            const TICKS_PER_SECOND: u32 = 32_768;
+
+            fn now_ticks(&self) -> u64 {
+                self.0
+            }
+        }
+
+        assert_eq!(Rtc(0).now(), Instant::from_us(0));
+        assert_eq!(Rtc(32_768 * 3600).now(), Instant::from_us(3_600_000_000));
+        // A tick is 30.5 us, rounded down
+        assert_eq!(Rtc(1).now(), Instant::from_us(30));
+
+        let closure = || Instant::from_us(42);
+        assert_eq!(closure.now_ticks(), 42);
+        assert_eq!(closure.now(), Instant::from_us(42));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn std_clock() {
+        let clock = StdClock::new();
+        let start = clock.now();
+        std::thread::sleep(std::time::Duration::from_millis(1));
+        assert!((clock.now() - start).as_us() >= 1_000);
+    }
+
     #[test]
     fn duration() {
         let a = Duration::from_us(100);
@@ -0,0 +1,146 @@
+//! Adaptive time synchronization.
+//!
+//! The clock of a device drifts relative to the clock of its time source.
+//! Every time the device resynchronizes, either using the Time Correction IE
+//! of an Enhanced Acknowledgment or the arrival time of an Enhanced Beacon,
+//! the correction divided by the time since the previous resynchronization
+//! measures that drift. Correcting the slot boundaries proportionally keeps
+//! the device synchronized for longer, such that fewer keep-alive frames are
+//! needed.
+
+use dot15d4_frame::TimeCorrectionRepr;
+
+use crate::time::{Duration, Instant};
+
+/// Resynchronizations closer together than this are too imprecise to measure
+/// the drift.
+const MIN_INTERVAL: Duration = Duration::from_us(1_000_000);
+
+/// Measurements above this drift, in parts per billion, are discarded. Two
+/// crystals of 40 ppm drift at most 80 ppm relative to each other.
+const MAX_DRIFT_PPB: i64 = 100_000;
+
+/// Estimates the drift of the local clock relative to the time source.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DriftEstimator {
+    last_sync: Option<Instant>,
+    /// The drift in parts per billion.
+    drift_ppb: Option<i64>,
+    /// The compensation applied since the last resynchronization, in
+    /// nanoseconds.
+    compensated_ns: i64,
+    /// The part of the compensation that was not applied yet, as it is
+    /// smaller than a microsecond, in nanoseconds.
+    residual_ns: i64,
+}
+
+impl DriftEstimator {
+    /// Create a new [`DriftEstimator`] without a drift measurement.
+    pub const fn new() -> Self {
+        Self {
+            last_sync: None,
+            drift_ppb: None,
+            compensated_ns: 0,
+            residual_ns: 0,
+        }
+    }
+
+    /// Record a resynchronization with the time source at `now`, where the
+    /// local clock was corrected by `correction`.
+    pub fn resynchronized(&mut self, now: Instant, correction: Duration) {
+        if let Some(last_sync) = self.last_sync {
+            let interval = now - last_sync;
+            if interval.as_us() >= MIN_INTERVAL.as_us() {
+                // The correction is what remained after the compensation
+                let drift_ns = self.compensated_ns + correction.as_us() * 1000;
+                let sample = drift_ns * 1_000_000 / interval.as_us();
+                if sample.abs() <= MAX_DRIFT_PPB {
+                    self.drift_ppb = Some(match self.drift_ppb {
+                        Some(drift) => (3 * drift + sample) / 4,
+                        None => sample,
+                    });
+                }
+            }
+        }
+
+        self.last_sync = Some(now);
+        self.compensated_ns = 0;
+    }
+
+    /// Record a resynchronization using the Time Correction IE received at
+    /// `now` from the time source.
+    pub fn time_correction_received(&mut self, now: Instant, ie: &TimeCorrectionRepr) {
+        self.resynchronized(now, Duration::from_us(ie.time_correction.as_us()));
+    }
+
+    /// Return the correction to apply to a slot boundary that is `elapsed`
+    /// after the previous one. Corrections smaller than a microsecond are
+    /// accumulated until they add up.
+    pub fn compensation(&mut self, elapsed: Duration) -> Duration {
+        let Some(drift_ppb) = self.drift_ppb else {
+            return Duration::from_us(0);
+        };
+
+        let compensation_ns = self.residual_ns + elapsed.as_us() * drift_ppb / 1_000_000;
+        let compensation_us = compensation_ns / 1000;
+        self.residual_ns = compensation_ns % 1000;
+        self.compensated_ns += compensation_us * 1000;
+        Duration::from_us(compensation_us)
+    }
+
+    /// Return the measured drift in parts per million, if known.
+    pub fn drift_ppm(&self) -> Option<i32> {
+        self.drift_ppb
+            .map(|drift| ((drift + drift.signum() * 500) / 1000) as i32)
+    }
+
+    /// Forget the drift measurement, e.g. when changing time source.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compensate_drift() {
+        let mut estimator = DriftEstimator::new();
+        let slot = Duration::from_us(10_000);
+
+        // The local clock runs 20 ppm slow
+        estimator.resynchronized(Instant::from_us(0), Duration::from_us(0));
+        assert_eq!(estimator.drift_ppm(), None);
+        assert_eq!(estimator.compensation(slot), Duration::from_us(0));
+
+        estimator.resynchronized(Instant::from_us(10_000_000), Duration::from_us(200));
+        assert_eq!(estimator.drift_ppm(), Some(20));
+
+        // 200 ns per slot is applied once it adds up to a microsecond
+        let total = (0..1000).fold(Duration::from_us(0), |total, _| {
+            total + estimator.compensation(slot)
+        });
+        assert_eq!(total, Duration::from_us(200));
+
+        // Perfectly compensated, so the drift estimate does not change
+        estimator.resynchronized(Instant::from_us(20_000_000), Duration::from_us(0));
+        assert_eq!(estimator.drift_ppm(), Some(20));
+
+        // A new measurement of 24 ppm is averaged with the previous ones
+        for _ in 0..1000 {
+            estimator.compensation(slot);
+        }
+        estimator.resynchronized(Instant::from_us(30_000_000), Duration::from_us(40));
+        assert_eq!(estimator.drift_ppm(), Some(21));
+
+        // Too short intervals and outliers are not used
+        estimator.resynchronized(Instant::from_us(30_100_000), Duration::from_us(50));
+        estimator.resynchronized(Instant::from_us(40_100_000), Duration::from_us(5_000));
+        assert_eq!(estimator.drift_ppm(), Some(21));
+
+        estimator.reset();
+        assert_eq!(estimator.drift_ppm(), None);
+    }
+}
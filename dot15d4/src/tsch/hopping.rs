@@ -0,0 +1,268 @@
+//! Channel hopping, avoiding channels of bad quality.
+//!
+//! The channel of a link is taken from the hopping sequence, using the ASN and
+//! the channel offset of the link. Channels on which few transmissions
+//! succeed, e.g. due to Wi-Fi interference, can be blacklisted:
+//! - [`BlacklistMode::Network`] removes the channels from the hopping
+//!   sequence. All devices of the network need the same blacklist to keep
+//!   meeting each other on the same channel.
+//! - [`BlacklistMode::Local`] keeps the hopping sequence, but does not use
+//!   links that fall on a blacklisted channel.
+
+use dot15d4_frame::AbsoluteSlotNumber;
+
+/// The default hopping sequence for the 16 channels of the 2450 MHz band.
+pub const DEFAULT_HOPPING_SEQUENCE: [u8; 16] = [
+    16, 17, 23, 18, 26, 15, 25, 22, 19, 11, 12, 13, 24, 14, 20, 21,
+];
+
+/// The maximum length of a hopping sequence.
+pub const MAX_HOPPING_SEQUENCE_LEN: usize = 16;
+
+/// How the blacklist is applied.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum BlacklistMode {
+    /// Blacklisted channels are removed from the hopping sequence.
+    #[default]
+    Network,
+    /// Links on blacklisted channels are skipped.
+    Local,
+}
+
+/// A hopping sequence, together with the blacklisted channels.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelHopping {
+    sequence: [u8; MAX_HOPPING_SEQUENCE_LEN],
+    len: usize,
+    /// A bitmap of the blacklisted channels.
+    blacklist: u32,
+    mode: BlacklistMode,
+}
+
+impl Default for ChannelHopping {
+    fn default() -> Self {
+        Self::new(&DEFAULT_HOPPING_SEQUENCE).unwrap()
+    }
+}
+
+impl ChannelHopping {
+    /// Create a new [`ChannelHopping`] without blacklisted channels.
+    ///
+    /// Returns `None` when the sequence is empty, too long, or contains a
+    /// channel above 31.
+    pub fn new(sequence: &[u8]) -> Option<Self> {
+        if sequence.is_empty()
+            || sequence.len() > MAX_HOPPING_SEQUENCE_LEN
+            || sequence.iter().any(|channel| *channel >= 32)
+        {
+            return None;
+        }
+
+        let mut hopping = Self {
+            sequence: [0; MAX_HOPPING_SEQUENCE_LEN],
+            len: sequence.len(),
+            blacklist: 0,
+            mode: BlacklistMode::default(),
+        };
+        hopping.sequence[..sequence.len()].copy_from_slice(sequence);
+        Some(hopping)
+    }
+
+    /// Return the hopping sequence.
+    pub fn sequence(&self) -> &[u8] {
+        &self.sequence[..self.len]
+    }
+
+    /// Return the bitmap of blacklisted channels, where bit `n` is set when
+    /// channel `n` is blacklisted.
+    pub fn blacklist(&self) -> u32 {
+        self.blacklist
+    }
+
+    /// Set the bitmap of blacklisted channels, and how it is applied.
+    pub fn set_blacklist(&mut self, blacklist: u32, mode: BlacklistMode) {
+        self.blacklist = blacklist;
+        self.mode = mode;
+    }
+
+    /// Returns `true` when the channel is blacklisted.
+    pub fn is_blacklisted(&self, channel: u8) -> bool {
+        channel < 32 && self.blacklist & (1 << channel) != 0
+    }
+
+    /// Return the channel of a link with the given channel offset at `asn`.
+    ///
+    /// Returns `None` when the link should be skipped, as it falls on a
+    /// channel that is blacklisted locally.
+    pub fn channel(&self, asn: AbsoluteSlotNumber, channel_offset: u16) -> Option<u8> {
+        let hop = asn.value() + channel_offset as u64;
+        let channel = self.sequence[(hop % self.len as u64) as usize];
+
+        match self.mode {
+            BlacklistMode::Network => {
+                let allowed = || {
+                    self.sequence()
+                        .iter()
+                        .copied()
+                        .filter(|channel| !self.is_blacklisted(*channel))
+                };
+                // Never blacklist all channels
+                match allowed().count() as u64 {
+                    0 => Some(channel),
+                    len => allowed().nth((hop % len) as usize),
+                }
+            }
+            BlacklistMode::Local => (!self.is_blacklisted(channel)).then_some(channel),
+        }
+    }
+}
+
+/// The measured quality of a channel.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ChannelStats {
+    /// The number of transmissions that requested an acknowledgment.
+    pub tx_attempts: u32,
+    /// The number of those transmissions that were acknowledged.
+    pub tx_acked: u32,
+    /// The recent packet delivery ratio, in per mille.
+    pub pdr: Option<u16>,
+    /// The recent average RSSI of received frames, in dBm.
+    pub rssi: Option<i8>,
+}
+
+/// Per-channel statistics, for the channels 0 to 31.
+#[derive(Debug)]
+pub struct ChannelQuality {
+    channels: [ChannelStats; 32],
+}
+
+impl Default for ChannelQuality {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChannelQuality {
+    /// Create a new [`ChannelQuality`] without measurements.
+    pub const fn new() -> Self {
+        Self {
+            channels: [ChannelStats {
+                tx_attempts: 0,
+                tx_acked: 0,
+                pdr: None,
+                rssi: None,
+            }; 32],
+        }
+    }
+
+    /// Record the outcome of a transmission that requested an
+    /// acknowledgment.
+    pub fn record_tx(&mut self, channel: u8, acked: bool) {
+        let Some(stats) = self.channels.get_mut(channel as usize) else {
+            return;
+        };
+        stats.tx_attempts = stats.tx_attempts.wrapping_add(1);
+        if acked {
+            stats.tx_acked = stats.tx_acked.wrapping_add(1);
+        }
+
+        // An exponentially weighted average, such that the blacklist adapts
+        // to changing interference
+        let sample = if acked { 1000 } else { 0 };
+        stats.pdr = Some(match stats.pdr {
+            Some(pdr) => ((7 * pdr as u32 + sample) / 8) as u16,
+            None => sample as u16,
+        });
+    }
+
+    /// Record the RSSI of a frame received on a channel.
+    pub fn record_rx(&mut self, channel: u8, rssi: i8) {
+        let Some(stats) = self.channels.get_mut(channel as usize) else {
+            return;
+        };
+        stats.rssi = Some(match stats.rssi {
+            Some(average) => ((7 * average as i16 + rssi as i16) / 8) as i8,
+            None => rssi,
+        });
+    }
+
+    /// Return the statistics of a channel.
+    pub fn stats(&self, channel: u8) -> Option<&ChannelStats> {
+        self.channels.get(channel as usize)
+    }
+
+    /// Return the bitmap of channels with a packet delivery ratio below
+    /// `min_pdr` per mille, once at least `min_attempts` transmissions were
+    /// done on them.
+    pub fn blacklist(&self, min_pdr: u16, min_attempts: u32) -> u32 {
+        self.channels
+            .iter()
+            .enumerate()
+            .filter(|(_, stats)| {
+                stats.tx_attempts >= min_attempts && stats.pdr.is_some_and(|pdr| pdr < min_pdr)
+            })
+            .fold(0, |blacklist, (channel, _)| blacklist | (1 << channel))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asn(asn: u64) -> AbsoluteSlotNumber {
+        AbsoluteSlotNumber::new(asn).unwrap()
+    }
+
+    #[test]
+    fn hop_over_blacklist() {
+        let mut hopping = ChannelHopping::default();
+        assert_eq!(hopping.channel(asn(0), 0), Some(16));
+        assert_eq!(hopping.channel(asn(0), 2), Some(23));
+        assert_eq!(hopping.channel(asn(17), 0), Some(17));
+
+        // Channels 17 and 23 are left out of the sequence
+        hopping.set_blacklist(1 << 17 | 1 << 23, BlacklistMode::Network);
+        assert_eq!(hopping.channel(asn(0), 1), Some(18));
+        assert_eq!(hopping.channel(asn(0), 14), Some(16));
+        assert!((0..100).all(|n| !matches!(hopping.channel(asn(n), 0), Some(17 | 23) | None)));
+
+        // The sequence stays the same, but blacklisted channels are skipped
+        hopping.set_blacklist(1 << 17 | 1 << 23, BlacklistMode::Local);
+        assert_eq!(hopping.channel(asn(0), 0), Some(16));
+        assert_eq!(hopping.channel(asn(0), 1), None);
+        assert_eq!(hopping.channel(asn(0), 3), Some(18));
+
+        // At least one channel is kept
+        let mut hopping = ChannelHopping::new(&[15, 20]).unwrap();
+        hopping.set_blacklist(u32::MAX, BlacklistMode::Network);
+        assert_eq!(hopping.channel(asn(1), 0), Some(20));
+
+        assert!(ChannelHopping::new(&[]).is_none());
+        assert!(ChannelHopping::new(&[40]).is_none());
+    }
+
+    #[test]
+    fn blacklist_from_quality() {
+        let mut quality = ChannelQuality::new();
+        for i in 0..20 {
+            quality.record_tx(15, true);
+            quality.record_tx(20, i % 4 == 0);
+        }
+        quality.record_tx(25, false);
+        quality.record_rx(15, -60);
+        quality.record_rx(15, -68);
+
+        let stats = quality.stats(20).unwrap();
+        assert_eq!((stats.tx_attempts, stats.tx_acked), (20, 5));
+        assert!(stats.pdr.unwrap() < 500);
+        assert_eq!(quality.stats(15).unwrap().pdr, Some(1000));
+        assert_eq!(quality.stats(15).unwrap().rssi, Some(-61));
+
+        // Channel 25 has too few measurements
+        assert_eq!(quality.blacklist(500, 10), 1 << 20);
+        assert_eq!(quality.blacklist(500, 1), 1 << 20 | 1 << 25);
+    }
+}
@@ -0,0 +1,13 @@
+//! Building blocks for Time Slotted Channel Hopping (TSCH).
+
+pub use dot15d4_frame::AbsoluteSlotNumber;
+
+pub mod drift;
+pub mod hopping;
+pub mod queue;
+pub mod schedule;
+
+/// The minimum backoff exponent of the TSCH CSMA-CA algorithm (`macMinBe`).
+pub const TSCH_MIN_BE: u8 = 1;
+/// The maximum backoff exponent of the TSCH CSMA-CA algorithm (`macMaxBe`).
+pub const TSCH_MAX_BE: u8 = 7;
@@ -0,0 +1,514 @@
+//! The TSCH transmit queue.
+//!
+//! Frames are queued per neighbor, which is the destination address of the
+//! frame. When a Tx link comes up, the frame to transmit is selected based on
+//! the neighbor of the link:
+//! - a link towards a neighbor only carries frames for that neighbor,
+//! - a link towards the broadcast address carries broadcast frames, and when
+//!   the link is shared, also frames for any other neighbor.
+//!
+//! Frames of a higher [`Priority`] go first, and only frames above
+//! [`Priority::Normal`] use links with the Priority option. Frames of the
+//! same priority are transmitted in the order they were queued.
+//!
+//! After a failed transmission on a shared link, the queue of that neighbor
+//! backs off for a random number of shared links, as described by the TSCH
+//! CSMA-CA algorithm (IEEE 802.15.4-2020, 6.2.5.3). Dedicated links are not
+//! subject to the backoff.
+//!
+//! Queued frames are tracked by their [`FrameHandle`], which is generated
+//! when the upper layer did not set one, until they are transmitted, dropped
+//! or purged.
+
+use core::cmp::Reverse;
+
+use dot15d4_frame::{Address, TschLinkOption};
+use rand_core::RngCore;
+
+use super::{TSCH_MAX_BE, TSCH_MIN_BE};
+use crate::csma::user_configurable_constants::MAC_MAX_FRAME_RETIES;
+use crate::phy::driver::{FrameBuffer, FrameHandle, Priority};
+
+#[derive(Debug)]
+struct Entry {
+    neighbor: Address,
+    frame: FrameBuffer,
+    /// Used to keep the frames in FIFO order.
+    order: u32,
+    /// The number of failed transmissions of the frame.
+    retries: u16,
+}
+
+/// The backoff of the queue of a neighbor.
+#[derive(Debug, Clone, Copy)]
+struct Backoff {
+    neighbor: Address,
+    exponent: u8,
+    /// The number of shared links to skip before transmitting again.
+    window: u16,
+}
+
+/// A transmit queue holding at most `N` frames, for any number of neighbors.
+#[derive(Debug)]
+pub struct TschQueue<const N: usize> {
+    entries: [Option<Entry>; N],
+    backoffs: [Option<Backoff>; N],
+    next_order: u32,
+    next_handle: FrameHandle,
+}
+
+impl<const N: usize> Default for TschQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> TschQueue<N> {
+    /// Create a new empty [`TschQueue`].
+    pub fn new() -> Self {
+        Self {
+            entries: core::array::from_fn(|_| None),
+            backoffs: [None; N],
+            next_order: 0,
+            next_handle: FrameHandle(0),
+        }
+    }
+
+    /// Queue a frame towards `neighbor`, which is the broadcast address for
+    /// broadcast frames, and return its handle. A frame without
+    /// [`FrameBuffer::handle`] gets a handle that is not used by another
+    /// queued frame.
+    ///
+    /// Returns the frame back when the queue is full, or when its handle is
+    /// already used by another queued frame.
+    #[allow(clippy::result_large_err)]
+    pub fn enqueue(
+        &mut self,
+        neighbor: Address,
+        mut frame: FrameBuffer,
+    ) -> Result<FrameHandle, FrameBuffer> {
+        let handle = match frame.handle {
+            Some(handle) if self.position(handle).is_some() => return Err(frame),
+            Some(handle) => handle,
+            None => {
+                let Some(handle) = self.generate_handle() else {
+                    return Err(frame);
+                };
+                handle
+            }
+        };
+        let Some(entry) = self.entries.iter_mut().find(|entry| entry.is_none()) else {
+            return Err(frame);
+        };
+
+        frame.handle = Some(handle);
+        *entry = Some(Entry {
+            neighbor,
+            frame,
+            order: self.next_order,
+            retries: 0,
+        });
+        self.next_order = self.next_order.wrapping_add(1);
+        Ok(handle)
+    }
+
+    /// Select the frame to transmit on a link towards `link_neighbor`. This
+    /// should be called exactly once for every Tx link, as it also counts
+    /// down the backoff of the neighbors that could have used a shared link.
+    ///
+    /// Returns `None` when no frame can be transmitted on the link.
+    pub fn select(
+        &mut self,
+        link_neighbor: &Address,
+        options: TschLinkOption,
+    ) -> Option<FrameHandle> {
+        if !options.contains(TschLinkOption::Tx) {
+            return None;
+        }
+        let shared = options.contains(TschLinkOption::Shared);
+        let priority_link = options.contains(TschLinkOption::Priority);
+
+        let oldest = |entries: &[Option<Entry>], eligible: &dyn Fn(&Address) -> bool| {
+            entries
+                .iter()
+                .enumerate()
+                .filter_map(|(i, entry)| entry.as_ref().map(|entry| (i, entry)))
+                .filter(|(_, entry)| !priority_link || entry.frame.priority > Priority::Normal)
+                .filter(|(_, entry)| eligible(&entry.neighbor))
+                .min_by_key(|(_, entry)| {
+                    (
+                        Reverse(entry.frame.priority),
+                        entry.order.wrapping_sub(self.next_order),
+                    )
+                })
+                .and_then(|(_, entry)| entry.frame.handle)
+        };
+        let backed_off = |neighbor: &Address| {
+            shared
+                && self
+                    .backoff(neighbor)
+                    .is_some_and(|backoff| backoff.window > 0)
+        };
+
+        let selected = if link_neighbor.is_broadcast() {
+            oldest(&self.entries, &|neighbor| {
+                neighbor.is_broadcast() && !backed_off(neighbor)
+            })
+            .or_else(|| {
+                shared
+                    .then(|| oldest(&self.entries, &|neighbor| !backed_off(neighbor)))
+                    .flatten()
+            })
+        } else {
+            oldest(&self.entries, &|neighbor| {
+                neighbor == link_neighbor && !backed_off(neighbor)
+            })
+        };
+
+        if shared {
+            for backoff in self.backoffs.iter_mut().flatten() {
+                if link_neighbor.is_broadcast() || &backoff.neighbor == link_neighbor {
+                    backoff.window = backoff.window.saturating_sub(1);
+                }
+            }
+        }
+
+        selected
+    }
+
+    /// Return the frame referred to by `handle`.
+    pub fn frame(&self, handle: FrameHandle) -> Option<&FrameBuffer> {
+        let index = self.position(handle)?;
+        self.entries[index].as_ref().map(|entry| &entry.frame)
+    }
+
+    /// Return the number of failed transmissions of the frame referred to by
+    /// `handle`, or `None` when the frame is not queued.
+    pub fn retries(&self, handle: FrameHandle) -> Option<u16> {
+        let index = self.position(handle)?;
+        self.entries[index].as_ref().map(|entry| entry.retries)
+    }
+
+    /// MCPS-PURGE: remove the frame referred to by `handle` from the queue.
+    ///
+    /// Returns the frame, or `None` when it is not queued.
+    pub fn purge(&mut self, handle: FrameHandle) -> Option<FrameBuffer> {
+        let index = self.position(handle)?;
+        self.remove(index)
+    }
+
+    /// Report the outcome of transmitting the frame referred to by `handle`
+    /// on a link with the given options. Frames without acknowledgment are
+    /// always transmitted successfully.
+    ///
+    /// Returns the frame when it leaves the queue, either because it was
+    /// transmitted or because it was retried too often.
+    pub fn transmitted(
+        &mut self,
+        handle: FrameHandle,
+        success: bool,
+        options: TschLinkOption,
+        rng: &mut impl RngCore,
+    ) -> Option<FrameBuffer> {
+        let index = self.position(handle)?;
+        let entry = self.entries[index].as_mut()?;
+        let neighbor = entry.neighbor;
+        let shared = options.contains(TschLinkOption::Shared);
+
+        if success {
+            if shared {
+                self.reset_backoff(&neighbor);
+            }
+            self.remove(index)
+        } else {
+            entry.retries += 1;
+            let dropped = entry.retries > MAC_MAX_FRAME_RETIES;
+            if shared {
+                self.increase_backoff(neighbor, rng);
+            }
+            dropped.then(|| self.remove(index)).flatten()
+        }
+    }
+
+    /// Remove the entry at `index` and return its frame.
+    fn remove(&mut self, index: usize) -> Option<FrameBuffer> {
+        let entry = self.entries[index].take()?;
+
+        // The next frame towards the neighbor starts without backoff
+        if !self
+            .entries
+            .iter()
+            .flatten()
+            .any(|queued| queued.neighbor == entry.neighbor)
+        {
+            self.reset_backoff(&entry.neighbor);
+        }
+
+        Some(entry.frame)
+    }
+
+    /// Return the index of the entry of the frame referred to by `handle`.
+    fn position(&self, handle: FrameHandle) -> Option<usize> {
+        self.entries.iter().position(|entry| {
+            entry
+                .as_ref()
+                .is_some_and(|entry| entry.frame.handle == Some(handle))
+        })
+    }
+
+    /// Generate a handle that is not used by a queued frame.
+    fn generate_handle(&mut self) -> Option<FrameHandle> {
+        for _ in 0..=u8::MAX {
+            let handle = self.next_handle;
+            self.next_handle = handle.next();
+            if self.position(handle).is_none() {
+                return Some(handle);
+            }
+        }
+        None
+    }
+
+    /// Return the number of queued frames towards `neighbor`.
+    pub fn len_for(&self, neighbor: &Address) -> usize {
+        self.entries
+            .iter()
+            .flatten()
+            .filter(|entry| &entry.neighbor == neighbor)
+            .count()
+    }
+
+    /// Return the number of queued frames.
+    pub fn len(&self) -> usize {
+        self.entries.iter().flatten().count()
+    }
+
+    /// Returns `true` when no frames are queued.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn backoff(&self, neighbor: &Address) -> Option<&Backoff> {
+        self.backoffs
+            .iter()
+            .flatten()
+            .find(|backoff| &backoff.neighbor == neighbor)
+    }
+
+    fn reset_backoff(&mut self, neighbor: &Address) {
+        if let Some(backoff) = self
+            .backoffs
+            .iter_mut()
+            .find(|backoff| backoff.is_some_and(|backoff| &backoff.neighbor == neighbor))
+        {
+            *backoff = None;
+        }
+    }
+
+    fn increase_backoff(&mut self, neighbor: Address, rng: &mut impl RngCore) {
+        let exponent = match self.backoff(&neighbor) {
+            Some(backoff) => (backoff.exponent + 1).min(TSCH_MAX_BE),
+            None => (TSCH_MIN_BE + 1).min(TSCH_MAX_BE),
+        };
+        let backoff = Backoff {
+            neighbor,
+            exponent,
+            window: (rng.next_u32() % (1 << exponent)) as u16,
+        };
+
+        // There is at most one backoff per queued frame, so there is always
+        // room for the backoff of a neighbor with queued frames
+        let index = self
+            .backoffs
+            .iter()
+            .position(|entry| entry.is_some_and(|entry| entry.neighbor == neighbor))
+            .or_else(|| self.backoffs.iter().position(|entry| entry.is_none()));
+        if let Some(index) = index {
+            self.backoffs[index] = Some(backoff);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+
+    const A: Address = Address::Short([0x00, 0x01]);
+    const B: Address = Address::Short([0x00, 0x02]);
+
+    fn frame(id: u8) -> FrameBuffer {
+        let mut frame = FrameBuffer::default();
+        frame.buffer[0] = id;
+        frame
+    }
+
+    /// A frame as it leaves the queue, with the handle it got.
+    fn queued(id: u8, handle: u8) -> FrameBuffer {
+        FrameBuffer {
+            handle: Some(FrameHandle(handle)),
+            ..frame(id)
+        }
+    }
+
+    fn id(queue: &TschQueue<4>, handle: Option<FrameHandle>) -> Option<u8> {
+        handle.and_then(|handle| queue.frame(handle).map(|frame| frame.buffer[0]))
+    }
+
+    #[test]
+    fn select_per_link() {
+        let dedicated = TschLinkOption::Tx;
+        let shared = TschLinkOption::Tx | TschLinkOption::Shared;
+        let mut rng = StepRng::new(0, 0);
+
+        let mut queue = TschQueue::<4>::new();
+        queue.enqueue(A, frame(1)).unwrap();
+        queue.enqueue(B, frame(2)).unwrap();
+        queue.enqueue(Address::BROADCAST, frame(3)).unwrap();
+        queue.enqueue(A, frame(4)).unwrap();
+        assert_eq!(queue.enqueue(A, frame(5)), Err(frame(5)));
+        assert_eq!(queue.len_for(&A), 2);
+
+        // Rx links do not transmit
+        assert_eq!(queue.select(&A, TschLinkOption::Rx), None);
+
+        // A dedicated link only carries frames for its neighbor, in order
+        let handle = queue.select(&B, dedicated);
+        assert_eq!(id(&queue, handle), Some(2));
+        let handle = queue.select(&A, dedicated);
+        assert_eq!(id(&queue, handle), Some(1));
+
+        // Broadcast frames go first on broadcast links
+        let handle = queue.select(&Address::BROADCAST, dedicated);
+        assert_eq!(id(&queue, handle), Some(3));
+        assert_eq!(
+            queue.transmitted(handle.unwrap(), true, dedicated, &mut rng),
+            Some(queued(3, 2))
+        );
+
+        // Unicast frames only use shared broadcast links
+        assert_eq!(queue.select(&Address::BROADCAST, dedicated), None);
+        let handle = queue.select(&Address::BROADCAST, shared);
+        assert_eq!(id(&queue, handle), Some(1));
+        assert_eq!(
+            queue.transmitted(handle.unwrap(), true, shared, &mut rng),
+            Some(queued(1, 0))
+        );
+        let handle = queue.select(&A, dedicated);
+        assert_eq!(id(&queue, handle), Some(4));
+        assert_eq!(queue.len(), 2);
+    }
+
+    #[test]
+    fn select_by_priority() {
+        let dedicated = TschLinkOption::Tx;
+        let priority = TschLinkOption::Tx | TschLinkOption::Priority;
+        let prioritized = |id, priority| FrameBuffer {
+            priority,
+            ..frame(id)
+        };
+
+        let mut queue = TschQueue::<4>::new();
+        queue.enqueue(A, frame(1)).unwrap();
+        queue.enqueue(A, prioritized(2, Priority::High)).unwrap();
+        queue.enqueue(A, prioritized(3, Priority::Control)).unwrap();
+        queue.enqueue(A, prioritized(4, Priority::High)).unwrap();
+
+        let handle = queue.select(&A, dedicated);
+        assert_eq!(id(&queue, handle), Some(3));
+        queue.transmitted(handle.unwrap(), true, dedicated, &mut StepRng::new(0, 0));
+        let handle = queue.select(&A, priority);
+        assert_eq!(id(&queue, handle), Some(2));
+        queue.transmitted(handle.unwrap(), true, priority, &mut StepRng::new(0, 0));
+        let handle = queue.select(&A, priority);
+        assert_eq!(id(&queue, handle), Some(4));
+        queue.transmitted(handle.unwrap(), true, priority, &mut StepRng::new(0, 0));
+
+        // Normal frames do not use priority links
+        assert_eq!(queue.select(&A, priority), None);
+        let handle = queue.select(&A, dedicated);
+        assert_eq!(id(&queue, handle), Some(1));
+    }
+
+    #[test]
+    fn shared_link_backoff() {
+        let dedicated = TschLinkOption::Tx;
+        let shared = TschLinkOption::Tx | TschLinkOption::Shared;
+        // Always draws the largest window of the first backoff exponent
+        let mut rng = StepRng::new(3, 0);
+
+        let mut queue = TschQueue::<4>::new();
+        queue.enqueue(A, frame(1)).unwrap();
+        queue.enqueue(B, frame(2)).unwrap();
+
+        let handle = queue.select(&A, shared).unwrap();
+        assert_eq!(queue.transmitted(handle, false, shared, &mut rng), None);
+
+        // A skips 3 shared links, B is not affected
+        assert_eq!(queue.select(&A, shared), None);
+        for _ in 0..2 {
+            let handle = queue.select(&Address::BROADCAST, shared);
+            assert_eq!(id(&queue, handle), Some(2));
+        }
+        let handle = queue.select(&A, shared);
+        assert_eq!(id(&queue, handle), Some(1));
+
+        // Dedicated links ignore the backoff
+        assert_eq!(
+            queue.transmitted(handle.unwrap(), false, shared, &mut rng),
+            None
+        );
+        assert_eq!(queue.select(&A, shared), None);
+        let handle = queue.select(&A, dedicated);
+        assert_eq!(id(&queue, handle), Some(1));
+
+        // The frame is dropped after too many retries, which resets the
+        // backoff of the neighbor
+        let handle = handle.unwrap();
+        for _ in 2..MAC_MAX_FRAME_RETIES {
+            assert_eq!(queue.transmitted(handle, false, dedicated, &mut rng), None);
+        }
+        assert_eq!(
+            queue.transmitted(handle, false, dedicated, &mut rng),
+            Some(queued(1, 0))
+        );
+        queue.enqueue(A, frame(3)).unwrap();
+        let handle = queue.select(&A, shared);
+        assert_eq!(id(&queue, handle), Some(3));
+    }
+
+    #[test]
+    fn handles_and_purge() {
+        let dedicated = TschLinkOption::Tx;
+        let mut rng = StepRng::new(0, 0);
+
+        let mut queue = TschQueue::<4>::new();
+        assert_eq!(queue.enqueue(A, frame(1)), Ok(FrameHandle(0)));
+        let handle = FrameHandle(1);
+        let with_handle = FrameBuffer {
+            handle: Some(handle),
+            ..frame(2)
+        };
+        assert_eq!(queue.enqueue(A, with_handle.clone()), Ok(handle));
+        assert_eq!(queue.enqueue(B, with_handle.clone()), Err(with_handle));
+        // Generated handles skip the handles of queued frames
+        assert_eq!(queue.enqueue(B, frame(3)), Ok(FrameHandle(2)));
+
+        // The retries of a frame are tracked by its handle
+        assert_eq!(queue.select(&A, dedicated), Some(FrameHandle(0)));
+        assert_eq!(
+            queue.transmitted(FrameHandle(0), false, dedicated, &mut rng),
+            None
+        );
+        assert_eq!(queue.retries(FrameHandle(0)), Some(1));
+        assert_eq!(queue.retries(handle), Some(0));
+
+        // A purged frame is no longer transmitted
+        assert_eq!(queue.purge(FrameHandle(0)), Some(queued(1, 0)));
+        assert_eq!(queue.purge(FrameHandle(0)), None);
+        assert_eq!(queue.retries(FrameHandle(0)), None);
+        assert_eq!(queue.select(&A, dedicated), Some(handle));
+        assert_eq!(queue.frame(handle).map(|frame| frame.buffer[0]), Some(2));
+        assert_eq!(queue.enqueue(A, frame(4)), Ok(FrameHandle(3)));
+        assert_eq!(queue.len(), 3);
+    }
+}
@@ -0,0 +1,536 @@
+//! The TSCH schedule: slotframes and the links within them.
+//!
+//! Slotframes and links are added and removed at runtime, e.g. when the 6top
+//! Protocol (6P) negotiates cells with a neighbor, and are referred to by
+//! their handle (`macSlotframeHandle` and `macLinkHandle`).
+//!
+//! When links of multiple slotframes are scheduled at the same ASN, the link
+//! with precedence is used, as in IEEE 802.15.4-2020: Tx links go before
+//! links without Tx, and otherwise the slotframe with the lowest handle goes
+//! first. Two Tx links in the same timeslot of the same slotframe are rejected,
+//! as they would always collide. Tx links of different slotframes that meet at
+//! some ASN are allowed, and reported by [`TschSchedule::conflicts`].
+//!
+//! The capacities of a schedule are const generics. A schedule that is
+//! advertised in Enhanced Beacons, see [`TschSchedule::advertisement`], holds
+//! at most [`MAX_SLOTFRAME_DESCRIPTORS`] slotframes, which is checked at
+//! compile time.
+
+use dot15d4_frame::{
+    AbsoluteSlotNumber, Address, LinkInformationRepr, SlotframeDescriptorRepr, TschLinkOption,
+    TschLinkOptionRepr, TschSlotframeAndLinkRepr, MAX_SLOTFRAME_DESCRIPTORS,
+};
+
+/// A slotframe of a [`TschSchedule`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Slotframe {
+    /// The handle of the slotframe (`macSlotframeHandle`).
+    pub handle: u8,
+    /// The number of timeslots in the slotframe.
+    pub size: u16,
+}
+
+/// The type of a [`Link`] (`macLinkType`).
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum LinkType {
+    /// A link for any frame.
+    #[default]
+    Normal,
+    /// A link that is also used to transmit Enhanced Beacons.
+    Advertising,
+}
+
+/// A link of a [`TschSchedule`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Link {
+    /// The handle of the link (`macLinkHandle`).
+    pub handle: u16,
+    /// The handle of the slotframe the link belongs to.
+    pub slotframe_handle: u8,
+    /// The timeslot of the link within its slotframe.
+    pub timeslot: u16,
+    /// The channel offset of the link.
+    pub channel_offset: u16,
+    /// The options of the link.
+    pub options: TschLinkOption,
+    /// The type of the link.
+    pub link_type: LinkType,
+    /// The neighbor the link is used with, the broadcast address for links
+    /// with any neighbor.
+    pub neighbor: Address,
+}
+
+impl Link {
+    /// Returns `true` when the link is used for transmitting.
+    pub fn is_tx(&self) -> bool {
+        self.options.contains(TschLinkOption::Tx)
+    }
+
+    /// The key ordering links by precedence, the lowest goes first.
+    fn precedence(&self) -> (bool, u8, u16) {
+        (!self.is_tx(), self.slotframe_handle, self.handle)
+    }
+}
+
+/// Reasons for rejecting a change to a [`TschSchedule`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ScheduleError {
+    /// There is no room left for another slotframe or link.
+    Full,
+    /// The handle is already in use.
+    DuplicateHandle,
+    /// The slotframe of the link is not in the schedule.
+    UnknownSlotframe,
+    /// The slotframe is empty, or the timeslot of the link lies outside of its
+    /// slotframe.
+    InvalidParameter,
+    /// The link is a Tx link in the same timeslot as another Tx link of the
+    /// same slotframe.
+    Conflict,
+}
+
+/// A schedule holding at most `S` slotframes and `L` links.
+#[derive(Debug)]
+pub struct TschSchedule<const S: usize, const L: usize> {
+    slotframes: [Option<Slotframe>; S],
+    links: [Option<Link>; L],
+}
+
+impl<const S: usize, const L: usize> Default for TschSchedule<S, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const S: usize, const L: usize> TschSchedule<S, L> {
+    /// Create a new empty [`TschSchedule`].
+    pub const fn new() -> Self {
+        Self {
+            slotframes: [None; S],
+            links: [None; L],
+        }
+    }
+
+    /// Add a slotframe of `size` timeslots.
+    pub fn add_slotframe(&mut self, handle: u8, size: u16) -> Result<(), ScheduleError> {
+        if size == 0 {
+            return Err(ScheduleError::InvalidParameter);
+        }
+        if self.slotframe(handle).is_some() {
+            return Err(ScheduleError::DuplicateHandle);
+        }
+
+        let entry = self
+            .slotframes
+            .iter_mut()
+            .find(|entry| entry.is_none())
+            .ok_or(ScheduleError::Full)?;
+        *entry = Some(Slotframe { handle, size });
+        Ok(())
+    }
+
+    /// Remove a slotframe, together with all of its links.
+    ///
+    /// Returns the removed slotframe, or `None` when it is not in the
+    /// schedule.
+    pub fn remove_slotframe(&mut self, handle: u8) -> Option<Slotframe> {
+        let slotframe = self
+            .slotframes
+            .iter_mut()
+            .find(|entry| entry.is_some_and(|slotframe| slotframe.handle == handle))?
+            .take();
+
+        for entry in self.links.iter_mut() {
+            if entry.is_some_and(|link| link.slotframe_handle == handle) {
+                *entry = None;
+            }
+        }
+
+        slotframe
+    }
+
+    /// Return the slotframe with the given handle.
+    pub fn slotframe(&self, handle: u8) -> Option<&Slotframe> {
+        self.slotframes()
+            .find(|slotframe| slotframe.handle == handle)
+    }
+
+    /// Return an iterator over the slotframes.
+    pub fn slotframes(&self) -> impl Iterator<Item = &Slotframe> {
+        self.slotframes.iter().flatten()
+    }
+
+    /// Add a link to the schedule.
+    pub fn add_link(&mut self, link: Link) -> Result<(), ScheduleError> {
+        let slotframe = self
+            .slotframe(link.slotframe_handle)
+            .ok_or(ScheduleError::UnknownSlotframe)?;
+        if link.timeslot >= slotframe.size {
+            return Err(ScheduleError::InvalidParameter);
+        }
+        if self.link(link.handle).is_some() {
+            return Err(ScheduleError::DuplicateHandle);
+        }
+        if link.is_tx()
+            && self.links().any(|other| {
+                other.is_tx()
+                    && other.slotframe_handle == link.slotframe_handle
+                    && other.timeslot == link.timeslot
+            })
+        {
+            return Err(ScheduleError::Conflict);
+        }
+
+        let entry = self
+            .links
+            .iter_mut()
+            .find(|entry| entry.is_none())
+            .ok_or(ScheduleError::Full)?;
+        *entry = Some(link);
+        Ok(())
+    }
+
+    /// Remove a link.
+    ///
+    /// Returns the removed link, or `None` when it is not in the schedule.
+    pub fn remove_link(&mut self, handle: u16) -> Option<Link> {
+        self.links
+            .iter_mut()
+            .find(|entry| entry.is_some_and(|link| link.handle == handle))?
+            .take()
+    }
+
+    /// Return the link with the given handle.
+    pub fn link(&self, handle: u16) -> Option<&Link> {
+        self.links().find(|link| link.handle == handle)
+    }
+
+    /// Return an iterator over the links.
+    pub fn links(&self) -> impl Iterator<Item = &Link> {
+        self.links.iter().flatten()
+    }
+
+    /// Return the TSCH Slotframe and Link IE advertising the slotframes of the
+    /// schedule, with their links with any neighbor, in Enhanced Beacons.
+    ///
+    /// Fails to compile when the schedule holds more slotframes than
+    /// [`MAX_SLOTFRAME_DESCRIPTORS`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ScheduleError::Full`] when a slotframe has more links with
+    /// any neighbor than a Slotframe Descriptor holds.
+    pub fn advertisement(&self) -> Result<TschSlotframeAndLinkRepr, ScheduleError> {
+        const {
+            assert!(
+                S <= MAX_SLOTFRAME_DESCRIPTORS,
+                "the slotframes of the schedule do not fit in an Enhanced Beacon"
+            )
+        };
+
+        let mut advertisement = TschSlotframeAndLinkRepr {
+            slotframe_descriptors: Default::default(),
+        };
+
+        for slotframe in self.slotframes() {
+            let mut descriptor = SlotframeDescriptorRepr {
+                handle: slotframe.handle,
+                size: slotframe.size,
+                links: Default::default(),
+            };
+            for link in self.links().filter(|link| {
+                link.slotframe_handle == slotframe.handle && link.neighbor.is_broadcast()
+            }) {
+                descriptor
+                    .links
+                    .push(LinkInformationRepr {
+                        timeslot: link.timeslot,
+                        channel_offset: link.channel_offset,
+                        link_options: TschLinkOptionRepr(link.options),
+                    })
+                    .map_err(|_| ScheduleError::Full)?;
+            }
+
+            // Fits, as asserted above
+            let _ = advertisement.slotframe_descriptors.push(descriptor);
+        }
+
+        advertisement
+            .slotframe_descriptors
+            .sort_unstable_by_key(|descriptor| descriptor.handle);
+        Ok(advertisement)
+    }
+
+    /// Return an iterator over the Tx links of other slotframes that are
+    /// scheduled at the same ASN as the Tx link `link`, at least once.
+    pub fn conflicts<'s>(&'s self, link: &'s Link) -> impl Iterator<Item = &'s Link> + 's {
+        let size = |handle| self.slotframe(handle).map(|slotframe| slotframe.size);
+
+        self.links().filter(move |other| {
+            if !link.is_tx() || !other.is_tx() || other.slotframe_handle == link.slotframe_handle {
+                return false;
+            }
+            let (Some(size), Some(other_size)) =
+                (size(link.slotframe_handle), size(other.slotframe_handle))
+            else {
+                return false;
+            };
+
+            // Both links are scheduled at an ASN with
+            // ASN % size == timeslot and ASN % other_size == other.timeslot,
+            // which exists when the timeslots agree modulo the GCD of the sizes.
+            let gcd = gcd(size, other_size);
+            link.timeslot % gcd == other.timeslot % gcd
+        })
+    }
+
+    /// Return an iterator over the links scheduled at `asn`, in order of
+    /// precedence.
+    pub fn active_links(&self, asn: AbsoluteSlotNumber) -> ActiveLinks<'_, S, L> {
+        ActiveLinks {
+            schedule: self,
+            asn,
+            last: None,
+        }
+    }
+
+    /// Return the link with precedence at `asn`, or `None` when no link is
+    /// scheduled at `asn`.
+    pub fn active_link(&self, asn: AbsoluteSlotNumber) -> Option<&Link> {
+        self.active_links(asn).next()
+    }
+
+    /// Returns `true` when `link` is scheduled at `asn`.
+    fn is_active(&self, link: &Link, asn: AbsoluteSlotNumber) -> bool {
+        self.slotframe(link.slotframe_handle)
+            .is_some_and(|slotframe| asn.slotframe_offset(slotframe.size) == link.timeslot)
+    }
+}
+
+/// An iterator over the links scheduled at an ASN, in order of precedence,
+/// see [`TschSchedule::active_links`].
+#[derive(Debug)]
+pub struct ActiveLinks<'s, const S: usize, const L: usize> {
+    schedule: &'s TschSchedule<S, L>,
+    asn: AbsoluteSlotNumber,
+    last: Option<(bool, u8, u16)>,
+}
+
+impl<'s, const S: usize, const L: usize> Iterator for ActiveLinks<'s, S, L> {
+    type Item = &'s Link;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self
+            .schedule
+            .links()
+            .filter(|link| self.schedule.is_active(link, self.asn))
+            .filter(|link| self.last.is_none_or(|last| link.precedence() > last))
+            .min_by_key(|link| link.precedence())?;
+        self.last = Some(next.precedence());
+        Some(next)
+    }
+}
+
+fn gcd(mut a: u16, mut b: u16) -> u16 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::*;
+
+    fn link(handle: u16, slotframe_handle: u8, timeslot: u16, options: TschLinkOption) -> Link {
+        Link {
+            handle,
+            slotframe_handle,
+            timeslot,
+            channel_offset: 0,
+            options,
+            link_type: LinkType::Normal,
+            neighbor: Address::BROADCAST,
+        }
+    }
+
+    #[test]
+    fn add_and_remove() {
+        let mut schedule = TschSchedule::<2, 4>::new();
+        let shared = TschLinkOption::Tx | TschLinkOption::Rx | TschLinkOption::Shared;
+
+        assert_eq!(schedule.add_slotframe(0, 7), Ok(()));
+        assert_eq!(
+            schedule.add_slotframe(0, 11),
+            Err(ScheduleError::DuplicateHandle)
+        );
+        assert_eq!(
+            schedule.add_slotframe(1, 0),
+            Err(ScheduleError::InvalidParameter)
+        );
+        assert_eq!(schedule.add_slotframe(1, 11), Ok(()));
+        assert_eq!(schedule.add_slotframe(2, 11), Err(ScheduleError::Full));
+
+        assert_eq!(schedule.add_link(link(1, 0, 0, shared)), Ok(()));
+        assert_eq!(
+            schedule.add_link(link(1, 0, 1, TschLinkOption::Rx)),
+            Err(ScheduleError::DuplicateHandle)
+        );
+        assert_eq!(
+            schedule.add_link(link(2, 2, 0, TschLinkOption::Rx)),
+            Err(ScheduleError::UnknownSlotframe)
+        );
+        assert_eq!(
+            schedule.add_link(link(2, 0, 7, TschLinkOption::Rx)),
+            Err(ScheduleError::InvalidParameter)
+        );
+        // Two Tx links in the same timeslot of a slotframe always collide
+        assert_eq!(
+            schedule.add_link(link(2, 0, 0, TschLinkOption::Tx)),
+            Err(ScheduleError::Conflict)
+        );
+        assert_eq!(schedule.add_link(link(2, 0, 0, TschLinkOption::Rx)), Ok(()));
+        assert_eq!(schedule.add_link(link(3, 1, 3, TschLinkOption::Tx)), Ok(()));
+        assert_eq!(schedule.add_link(link(4, 1, 4, TschLinkOption::Tx)), Ok(()));
+        assert_eq!(
+            schedule.add_link(link(5, 1, 5, TschLinkOption::Tx)),
+            Err(ScheduleError::Full)
+        );
+
+        assert_eq!(schedule.remove_link(2).map(|link| link.handle), Some(2));
+        assert_eq!(schedule.remove_link(2), None);
+        assert_eq!(schedule.links().count(), 3);
+
+        // Removing a slotframe removes its links
+        assert_eq!(
+            schedule.remove_slotframe(1),
+            Some(Slotframe {
+                handle: 1,
+                size: 11
+            })
+        );
+        assert_eq!(schedule.remove_slotframe(1), None);
+        assert_eq!(
+            schedule.links().map(|link| link.handle).collect::<Vec<_>>(),
+            [1]
+        );
+        assert_eq!(schedule.add_slotframe(2, 101), Ok(()));
+    }
+
+    #[test]
+    fn active_links() {
+        let mut schedule = TschSchedule::<3, 8>::new();
+        schedule.add_slotframe(0, 4).unwrap();
+        schedule.add_slotframe(1, 6).unwrap();
+        schedule.add_slotframe(2, 5).unwrap();
+
+        schedule
+            .add_link(link(10, 1, 0, TschLinkOption::Rx))
+            .unwrap();
+        schedule
+            .add_link(link(11, 0, 0, TschLinkOption::Rx))
+            .unwrap();
+        schedule
+            .add_link(link(12, 2, 0, TschLinkOption::Tx))
+            .unwrap();
+        schedule
+            .add_link(link(13, 1, 2, TschLinkOption::Tx))
+            .unwrap();
+
+        let tx = link(14, 0, 2, TschLinkOption::Tx);
+        schedule.add_link(tx).unwrap();
+
+        let active = |asn| {
+            schedule
+                .active_links(AbsoluteSlotNumber::new(asn).unwrap())
+                .map(|link| link.handle)
+                .collect::<Vec<_>>()
+        };
+        // The Tx link goes first, then the lowest slotframe handle
+        assert_eq!(active(0), [12, 11, 10]);
+        assert_eq!(active(12), [11, 10]);
+        assert_eq!(active(2), [14, 13]);
+        assert!(active(1).is_empty());
+        assert_eq!(
+            schedule
+                .active_link(AbsoluteSlotNumber::new(20).unwrap())
+                .map(|link| link.handle),
+            Some(13)
+        );
+        assert_eq!(
+            schedule.active_link(AbsoluteSlotNumber::new(3).unwrap()),
+            None
+        );
+
+        // Slotframes of size 4 and 6 meet every 12 timeslots, at timeslots
+        // that agree modulo 2
+        let conflicts = |link: &Link| {
+            schedule
+                .conflicts(link)
+                .map(|link| link.handle)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(conflicts(&tx), [12, 13]);
+        assert_eq!(conflicts(&link(15, 0, 1, TschLinkOption::Tx)), [12]);
+        assert!(conflicts(&link(15, 0, 1, TschLinkOption::Rx)).is_empty());
+    }
+
+    #[test]
+    fn advertisement() {
+        let mut schedule = TschSchedule::<2, 8>::new();
+        let shared = TschLinkOption::Tx | TschLinkOption::Rx | TschLinkOption::Shared;
+        schedule.add_slotframe(1, 11).unwrap();
+        schedule.add_slotframe(0, 7).unwrap();
+        schedule.add_link(link(1, 0, 0, shared)).unwrap();
+        schedule
+            .add_link(Link {
+                neighbor: Address::Short([0x00, 0x02]),
+                ..link(2, 0, 1, TschLinkOption::Tx)
+            })
+            .unwrap();
+        schedule
+            .add_link(Link {
+                channel_offset: 3,
+                ..link(3, 0, 2, TschLinkOption::Rx)
+            })
+            .unwrap();
+
+        // Only the links with any neighbor are advertised, by slotframe handle
+        let advertisement = schedule.advertisement().unwrap();
+        let descriptors = &advertisement.slotframe_descriptors;
+        assert_eq!(descriptors.len(), 2);
+        assert_eq!((descriptors[0].handle, descriptors[0].size), (0, 7));
+        assert_eq!(
+            descriptors[0]
+                .links
+                .iter()
+                .map(|link| (link.timeslot, link.channel_offset, link.link_options.0))
+                .collect::<Vec<_>>(),
+            [(0, 0, shared), (2, 3, TschLinkOption::Rx)]
+        );
+        assert_eq!((descriptors[1].handle, descriptors[1].size), (1, 11));
+        assert!(descriptors[1].links.is_empty());
+        assert!(advertisement.validate().is_ok());
+
+        // A slotframe with too many links for a Slotframe Descriptor
+        for handle in 4..7 {
+            schedule
+                .add_link(link(handle, 1, handle, TschLinkOption::Rx))
+                .unwrap();
+        }
+        assert!(schedule.advertisement().is_ok());
+        schedule
+            .add_link(link(7, 1, 7, TschLinkOption::Rx))
+            .unwrap();
+        assert!(schedule.advertisement().is_ok());
+        schedule
+            .add_link(link(8, 1, 8, TschLinkOption::Rx))
+            .unwrap();
+        assert_eq!(schedule.advertisement(), Err(ScheduleError::Full));
+    }
+}
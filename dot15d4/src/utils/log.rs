@@ -1,51 +1,76 @@
 //! Logger backend agnostic logging
+//!
+//! The macros forward to `log` when the `log` feature is enabled, otherwise to
+//! `defmt` when the `defmt` feature is enabled. Without either, nothing is
+//! logged. Only use format strings and arguments supported by both: `{}` and
+//! `{:?}`, with arguments implementing both `Debug` and `defmt::Format`.
 
 #[macro_export]
 macro_rules! error {
-    ($($arg:tt)*) => {
-        #[cfg(all(not(feature="log"), feature = "defmt"))]
-        defmt::error!($($arg)*);
-        #[cfg(feature = "log")]
-        ::log::error!($($arg)*);
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(all(not(feature = "log"), feature = "defmt"))]
+            ::defmt::error!($s $(, $x)*);
+            #[cfg(feature = "log")]
+            ::log::error!($s $(, $x)*);
+            #[cfg(not(any(feature = "log", feature = "defmt")))]
+            let _ = ($(&$x),*);
+        }
     };
 }
 
 #[macro_export]
 macro_rules! warn {
-    ($($arg:tt)*) => {
-        #[cfg(all(not(feature="log"), feature = "defmt"))]
-        defmt::warn!($($arg)*);
-        #[cfg(feature = "log")]
-        ::log::warn!($($arg)*);
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(all(not(feature = "log"), feature = "defmt"))]
+            ::defmt::warn!($s $(, $x)*);
+            #[cfg(feature = "log")]
+            ::log::warn!($s $(, $x)*);
+            #[cfg(not(any(feature = "log", feature = "defmt")))]
+            let _ = ($(&$x),*);
+        }
     };
 }
 
 #[macro_export]
 macro_rules! info {
-    ($($arg:tt)*) => {
-        #[cfg(all(not(feature="log"), feature = "defmt"))]
-        ::defmt::info!($($arg)*);
-        #[cfg(feature = "log")]
-        ::log::info!($($arg)*);
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(all(not(feature = "log"), feature = "defmt"))]
+            ::defmt::info!($s $(, $x)*);
+            #[cfg(feature = "log")]
+            ::log::info!($s $(, $x)*);
+            #[cfg(not(any(feature = "log", feature = "defmt")))]
+            let _ = ($(&$x),*);
+        }
     };
 }
 
 #[macro_export]
 macro_rules! debug {
-    ($($arg:tt)*) => {
-        #[cfg(all(not(feature="log"), feature = "defmt"))]
-        ::defmt::debug!($($arg)*);
-        #[cfg(feature = "log")]
-        ::log::debug!($($arg)*);
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(all(not(feature = "log"), feature = "defmt"))]
+            ::defmt::debug!($s $(, $x)*);
+            #[cfg(feature = "log")]
+            ::log::debug!($s $(, $x)*);
+            #[cfg(not(any(feature = "log", feature = "defmt")))]
+            let _ = ($(&$x),*);
+        }
     };
 }
 
 #[macro_export]
 macro_rules! trace {
-    ($($arg:tt)*) => {
-        #[cfg(all(not(feature="log"), feature = "defmt"))]
-        ::defmt::trace!($($arg)*);
-        #[cfg(feature = "log")]
-        ::log::trace!($($arg)*);
+    ($s:literal $(, $x:expr)* $(,)?) => {
+        {
+            #[cfg(all(not(feature = "log"), feature = "defmt"))]
+            ::defmt::trace!($s $(, $x)*);
+            #[cfg(feature = "log")]
+            ::log::trace!($s $(, $x)*);
+            #[cfg(not(any(feature = "log", feature = "defmt")))]
+            let _ = ($(&$x),*);
+        }
     };
 }